@@ -5,6 +5,7 @@
 
 // src/main.rs - Optimized for high-performance UI with downloads
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::{Manager, Runtime, State, Window, Emitter};
@@ -60,19 +61,6 @@ mod mock_rustloader {
         pub priority: Option<DownloadPriority>,
     }
     
-    // Mock download item
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct DownloadItem {
-        pub id: String,
-        pub url: String,
-        pub title: Option<String>,
-        pub progress: f64,
-        pub status: DownloadStatus,
-        pub total_bytes: u64,
-        pub downloaded_bytes: u64,
-        pub speed: f64,
-    }
-    
     // Mock functions
     pub async fn add_download_to_queue(_options: DownloadOptions<'_>) -> Result<String, String> {
         Ok("mock-download-id".to_string())
@@ -98,10 +86,6 @@ mod mock_rustloader {
         Ok(())
     }
     
-    pub fn get_all_downloads() -> Vec<DownloadItem> {
-        Vec::new()
-    }
-    
     pub fn get_download_status(_id: &str) -> Option<DownloadStatus> {
         Some(DownloadStatus::Downloading)
     }
@@ -125,14 +109,6 @@ mod mock_rustloader {
         false
     }
     
-    pub fn activate_pro_license(_key: &str, _email: &str) -> Result<String, String> {
-        Ok("License activated".to_string())
-    }
-    
-    pub fn get_license_info() -> Result<String, String> {
-        Ok("Free version".to_string())
-    }
-    
     pub fn get_download_progress() -> Result<ProgressData, String> {
         Ok(ProgressData {
             progress: 0,
@@ -160,8 +136,6 @@ mod mock_rustloader {
 use mock_rustloader::{
     download_video,
     check_is_pro,
-    activate_pro_license,
-    get_license_info,
     get_download_progress,
     DownloadOptions,
     DownloadPriority,
@@ -172,7 +146,6 @@ use mock_rustloader::{
     cancel_download,
     pause_all_downloads,
     resume_all_downloads,
-    get_all_downloads,
     get_download_status,
     ProgressData, // Add this import
 };
@@ -194,6 +167,52 @@ use app_lib::{
     }
 };
 
+use rustloader::templates::{self as job_templates, JobTemplate};
+use rustloader::download_manager::{
+    self as queue_manager, shutdown_download_manager_blocking, BatchAction, DownloadStatus as QueueStatus, SpeedSample,
+    SHUTDOWN_FLUSH_TIMEOUT,
+};
+use rustloader::completion::CompletionAction;
+use rustloader::queue_changes::{self, QueueChange, QueueDelta};
+use rustloader::file_actions;
+use rustloader::recent_urls;
+use rustloader::config::load_config;
+use rustloader::smart_defaults::{self, SmartDefaults, SuggestedOptions};
+use rustloader::http_headers::RequestHeaders;
+use rustloader::media_preview::{self, MediaPreview};
+use rustloader::license::{self, LicenseState};
+use rustloader::downloader::quota_status;
+use rustloader::quota::QuotaStatus;
+use rustloader::history::{History, HistoryEntry};
+use rustloader::crash_report;
+use rustloader::dependency_validator::{self, DependencyInfo};
+use rustloader::instance_lock::{self, InstanceKind, InstanceLock};
+use rustloader::error::{AppError, ErrorReport, NetworkErrorKind};
+
+// Keeps the instance lock for as long as the app runs, once it has it
+struct InstanceLockState(Mutex<Option<InstanceLock>>);
+
+// Keeps trying to take the queue over from the CLI process running it, then
+// runs it here
+fn retry_instance_lock<R: Runtime>(app: tauri::AppHandle<R>) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(instance_lock::RETRY_INTERVAL).await;
+      match instance_lock::acquire_instance_lock(InstanceKind::Gui) {
+        Ok(Some(lock)) => {
+          *app.state::<InstanceLockState>().0.lock().unwrap() = Some(lock);
+          if let Err(e) = queue_manager::take_over_queue().await {
+            eprintln!("Could not start the download queue: {}", e);
+          }
+          return;
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Could not take the instance lock: {}", e),
+      }
+    }
+  });
+}
+
 // Simple progress state for backward compatibility
 struct ProgressState(Arc<Mutex<i32>>);
 
@@ -222,7 +241,7 @@ async fn start_optimized_download(
     output_dir: Option<String>,
     priority: Option<String>,
     download_state: State<'_, DownloadManagerState>
-) -> Result<String, String> {
+) -> Result<String, ErrorReport> {
     // Generate a unique ID for this download
     let download_id = Uuid::new_v4().to_string();
     
@@ -330,7 +349,7 @@ async fn start_optimized_download(
                         
                         if should_update {
                             // Get download details - in a real app this would come from the download manager
-                            let downloads = get_all_downloads();
+                            let downloads = queue_manager::downloads_snapshot();
                             if let Some(download) = downloads.iter().find(|d| d.id == download_id_clone) {
                                 // Calculate accurate speed
                                 let now = Instant::now();
@@ -344,7 +363,7 @@ async fn start_optimized_download(
                                 
                                 // Calculate ETA
                                 let time_remaining = if download.progress < 100.0 && speed > 0.0 {
-                                    let remaining_bytes = download.total_bytes - download.downloaded_bytes;
+                                    let remaining_bytes = download.total_bytes.saturating_sub(download.downloaded_bytes);
                                     Some((remaining_bytes as f64 / speed) as u64)
                                 } else {
                                     None
@@ -435,25 +454,25 @@ async fn start_optimized_download(
 
 // Command to list all active downloads
 #[tauri::command]
-async fn list_downloads() -> Result<Vec<DownloadProgress>, String> {
-    let all_downloads = get_all_downloads();
+async fn list_downloads() -> Result<Vec<DownloadProgress>, ErrorReport> {
+    let all_downloads = queue_manager::downloads_snapshot();
     
-    let progress_items = all_downloads.into_iter()
+    let progress_items = all_downloads.iter()
         .map(|download| {
             // Map download status
             let status_str = match download.status {
-                DownloadStatus::Completed => "complete",
-                DownloadStatus::Paused => "paused",
-                DownloadStatus::Failed => "error",
-                DownloadStatus::Canceled => "cancelled",
-                DownloadStatus::Downloading => "downloading",
+                QueueStatus::Completed => "complete",
+                QueueStatus::Paused => "paused",
+                QueueStatus::Failed => "error",
+                QueueStatus::Canceled => "cancelled",
+                QueueStatus::Downloading => "downloading",
                 _ => "queued",
             };
             
             DownloadProgress {
                 id: download.id.clone(),
                 progress: download.progress,
-                file_name: download.title.unwrap_or_else(|| "Downloading...".to_string()),
+                file_name: download.title.clone().unwrap_or_else(|| "Downloading...".to_string()),
                 file_size: download.total_bytes,
                 downloaded_size: download.downloaded_bytes,
                 speed: download.speed,
@@ -466,16 +485,145 @@ async fn list_downloads() -> Result<Vec<DownloadProgress>, String> {
     Ok(progress_items)
 }
 
+// Bumped by each subscription so only the newest one keeps emitting
+static QUEUE_SUBSCRIPTION: AtomicU64 = AtomicU64::new(0);
+
+// How often a subscription checks the queue for changes
+const QUEUE_CHANGES_INTERVAL: Duration = Duration::from_millis(250);
+
+// Error reports of the downloads that failed in `delta`. A reset delta
+// replays the whole list, so failures from before aren't reported again.
+fn failure_reports(delta: &QueueDelta) -> Vec<ErrorReport> {
+    if delta.reset {
+        return Vec::new();
+    }
+    delta
+        .changes
+        .iter()
+        .filter_map(|change| match change {
+            QueueChange::Updated { fields, .. } => fields
+                .get("error_report")
+                .and_then(|report| serde_json::from_value(report.clone()).ok()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Command to follow the download list by changes instead of polling the full
+// list. Returns the changes after `since` (the whole list if `since` is
+// missing or too old), then emits "queue-changes" with each later delta and
+// "download-error" with the error report of each download that fails.
+#[tauri::command]
+async fn subscribe_queue_changes(window: Window, since: Option<u64>) -> Result<QueueDelta, ErrorReport> {
+    let delta = queue_changes::queue_changes_since(since);
+    let subscription = QUEUE_SUBSCRIPTION.fetch_add(1, Ordering::SeqCst) + 1;
+    let mut seq = delta.seq;
+
+    tokio::spawn(async move {
+        while QUEUE_SUBSCRIPTION.load(Ordering::SeqCst) == subscription {
+            tokio::time::sleep(QUEUE_CHANGES_INTERVAL).await;
+            let delta = queue_changes::queue_changes_since(Some(seq));
+            if delta.changes.is_empty() && !delta.reset {
+                continue;
+            }
+            seq = delta.seq;
+            for report in failure_reports(&delta) {
+                if let Err(e) = window.emit("download-error", report) {
+                    eprintln!("Error emitting download-error event: {}", e);
+                }
+            }
+            if let Err(e) = window.emit("queue-changes", delta) {
+                eprintln!("Error emitting queue-changes event: {}", e);
+                break;
+            }
+        }
+    });
+
+    Ok(delta)
+}
+
+// Command to get the bytes still to download across the queue, using
+// sizes estimated at enqueue time for downloads that haven't started
+#[tauri::command]
+async fn get_queue_remaining_bytes() -> Result<u64, ErrorReport> {
+    Ok(queue_manager::get_remaining_bytes())
+}
+
+// Command to get recent speed samples of a download, oldest first,
+// for rendering a speed sparkline
+#[tauri::command]
+async fn get_speed_history(id: String) -> Result<Vec<SpeedSample>, ErrorReport> {
+    Ok(queue_manager::get_speed_history(&id))
+}
+
+// Command to apply the order of pending downloads after a drag and drop
+#[tauri::command]
+async fn reorder_queue(ids: Vec<String>) -> Result<(), ErrorReport> {
+    queue_manager::reorder_queue(ids).await.map_err(|e| ErrorReport::new(&e))
+}
+
+// Error details for the failed-download dialog
+#[derive(Serialize)]
+struct DownloadErrorDetails {
+    message: Option<String>,
+    context: Vec<String>,
+}
+
+// Command to get why a download failed, with the last lines of yt-dlp output
+#[tauri::command]
+fn get_download_error(id: String) -> Result<DownloadErrorDetails, ErrorReport> {
+    let item = queue_manager::find_saved_download(&id)
+        .map_err(|e| ErrorReport::new(&e))?
+        .ok_or_else(|| ErrorReport::new(&AppError::ValidationError(format!("No download found with ID '{}'", id))))?;
+    Ok(DownloadErrorDetails {
+        message: item.error_message,
+        context: item.error_context,
+    })
+}
+
+// Command to show a downloaded file in the platform file manager
+#[tauri::command]
+fn reveal_in_file_manager(id: String) -> Result<(), ErrorReport> {
+    file_actions::resolve_download_file(&id)
+        .and_then(|path| file_actions::reveal_in_file_manager(&path))
+        .map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to open a downloaded file with the default application
+#[tauri::command]
+fn open_file(id: String) -> Result<(), ErrorReport> {
+    file_actions::resolve_download_file(&id)
+        .and_then(|path| file_actions::open_file(&path))
+        .map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to autocomplete the address bar from previously used URLs
+#[tauri::command]
+fn suggest_urls(prefix: String) -> Result<Vec<String>, ErrorReport> {
+    recent_urls::suggest_urls(&prefix).map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to prefill the options a URL's channel is usually downloaded with
+#[tauri::command]
+async fn suggest_options(url: String) -> Result<Option<SuggestedOptions>, ErrorReport> {
+    if load_config().unwrap_or_default().smart_defaults == SmartDefaults::Off {
+        return Ok(None);
+    }
+    smart_defaults::suggest_options(&url, &RequestHeaders::default())
+        .await
+        .map_err(|e| ErrorReport::new(&e))
+}
+
 // Command to pause downloads
 #[tauri::command]
-async fn pause_download_item(id: String) -> Result<(), String> {
-    pause_download(&id).await.map_err(|e| e.to_string())
+async fn pause_download_item(id: String) -> Result<(), ErrorReport> {
+    pause_download(&id).await.map_err(|e| ErrorReport::new(&AppError::General(e)))
 }
 
 // Command to resume downloads
 #[tauri::command]
-async fn resume_download_item(id: String) -> Result<(), String> {
-    resume_download(&id).await.map_err(|e| e.to_string())
+async fn resume_download_item(id: String) -> Result<(), ErrorReport> {
+    resume_download(&id).await.map_err(|e| ErrorReport::new(&AppError::General(e)))
 }
 
 // Command to cancel downloads
@@ -483,7 +631,7 @@ async fn resume_download_item(id: String) -> Result<(), String> {
 async fn cancel_download_item(
     id: String, 
     download_state: State<'_, DownloadManagerState>
-) -> Result<(), String> {
+) -> Result<(), ErrorReport> {
     // Try to cancel via download manager state first (for active downloads)
     let dm_result = download_state.cancel_download(&id);
     
@@ -497,28 +645,119 @@ async fn cancel_download_item(
         // Combine error messages
         let mut error_msg = String::new();
         if let Err(e) = dm_result {
-            error_msg.push_str(&e.to_string());
+            error_msg.push_str(&e);
         }
         if let Err(e) = queue_result {
             if !error_msg.is_empty() {
                 error_msg.push_str(", ");
             }
-            error_msg.push_str(&e.to_string());
+            error_msg.push_str(&e);
         }
-        Err(error_msg)
+        Err(ErrorReport::new(&AppError::General(error_msg)))
     }
 }
 
 // Command to pause all downloads
 #[tauri::command]
-async fn pause_all() -> Result<(), String> {
-    pause_all_downloads().await.map_err(|e| e.to_string())
+async fn pause_all() -> Result<(), ErrorReport> {
+    pause_all_downloads().await.map_err(|e| ErrorReport::new(&AppError::General(e)))
 }
 
 // Command to resume all downloads
 #[tauri::command]
-async fn resume_all() -> Result<(), String> {
-    resume_all_downloads().await.map_err(|e| e.to_string())
+async fn resume_all() -> Result<(), ErrorReport> {
+    resume_all_downloads().await.map_err(|e| ErrorReport::new(&AppError::General(e)))
+}
+
+// Batch commands for multi-select; each is applied in one step by the queue
+// and sends a single update
+#[tauri::command]
+async fn pause_items(ids: Vec<String>) -> Result<(), ErrorReport> {
+    queue_manager::batch_downloads(BatchAction::Pause, ids).await.map_err(|e| ErrorReport::new(&e))
+}
+
+#[tauri::command]
+async fn cancel_items(ids: Vec<String>) -> Result<(), ErrorReport> {
+    queue_manager::batch_downloads(BatchAction::Cancel, ids).await.map_err(|e| ErrorReport::new(&e))
+}
+
+#[tauri::command]
+async fn set_priority_items(ids: Vec<String>, priority: queue_manager::DownloadPriority) -> Result<(), ErrorReport> {
+    queue_manager::batch_downloads(BatchAction::SetPriority(priority), ids).await.map_err(|e| ErrorReport::new(&e))
+}
+
+#[tauri::command]
+async fn retry_items(ids: Vec<String>) -> Result<(), ErrorReport> {
+    queue_manager::batch_downloads(BatchAction::Retry, ids).await.map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to undo the last clear or bulk cancel; returns a description of
+// what was undone, or nothing if there was nothing to undo
+#[tauri::command]
+async fn undo_queue() -> Result<Option<String>, ErrorReport> {
+    queue_manager::undo_queue_operation()
+        .await
+        .map(|snapshot| snapshot.map(|snapshot| snapshot.describe()))
+        .map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to set what happens once the queue finishes: "shutdown", "sleep",
+// "notify", or "run" for the when_done_command in config.json; no action
+// clears it. The webview never supplies a command to run.
+#[tauri::command]
+async fn set_when_done(action: Option<String>) -> Result<(), ErrorReport> {
+    let action = match action.as_deref() {
+        Some("run") => {
+            let command = load_config()
+                .map_err(|e| ErrorReport::new(&e))?
+                .when_done_command
+                .ok_or_else(|| ErrorReport::new(&AppError::ValidationError(
+                    "Set when_done_command in config.json to run a command when the queue finishes".to_string(),
+                )))?;
+            Some(CompletionAction::parse(&["run", &command]).map_err(|e| ErrorReport::new(&e))?)
+        }
+        Some(action) => Some(CompletionAction::parse(&[action]).map_err(|e| ErrorReport::new(&e))?),
+        None => None,
+    };
+    queue_manager::set_queue_completion_action(action).await.map_err(|e| ErrorReport::new(&e))
+}
+
+// Named job template for the GUI template picker
+#[derive(Serialize, Deserialize)]
+struct TemplateEntry {
+    name: String,
+    template: JobTemplate,
+}
+
+// Command to list saved job templates
+#[tauri::command]
+fn list_templates() -> Result<Vec<TemplateEntry>, ErrorReport> {
+    job_templates::list_templates()
+        .map(|templates| {
+            templates
+                .into_iter()
+                .map(|(name, template)| TemplateEntry { name, template })
+                .collect()
+        })
+        .map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to save a job template from the current form options
+#[tauri::command]
+fn save_template(name: String, template: JobTemplate) -> Result<(), ErrorReport> {
+    job_templates::save_template(&name, template).map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to save a job template from a previous download
+#[tauri::command]
+fn save_template_from_download(download_id: String, name: String) -> Result<JobTemplate, ErrorReport> {
+    job_templates::save_template_from_download(&download_id, &name).map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to delete a job template
+#[tauri::command]
+fn delete_template(name: String) -> Result<(), ErrorReport> {
+    job_templates::delete_template(&name).map_err(|e| ErrorReport::new(&e))
 }
 
 // Legacy commands for backward compatibility
@@ -534,7 +773,7 @@ fn start_download<R: Runtime>(
   download_subtitles: bool,
   output_dir: Option<String>,
   progress_state: State<'_, ProgressState>
-) -> Result<(), String> {
+) -> Result<(), ErrorReport> {
   let window_copy = window.clone();
   let url_copy = url.clone();
 
@@ -636,46 +875,34 @@ fn poll_download_progress<R: Runtime>(window: Window<R>) {
   });
 }
 
+// Command to get everything the preview panel shows for a URL: thumbnail,
+// formats, expected size per quality, chapters and availability warnings
 #[tauri::command]
-fn get_video_info(url: String) -> Result<VideoInfo, String> {
-    // Use a 10-second timeout to prevent hanging
-    let output = std::process::Command::new("yt-dlp")
-        .args(["--dump-json", "--no-playlist", "--socket-timeout", "10", &url])
-        .output()
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+async fn get_media_preview(url: String) -> Result<MediaPreview, ErrorReport> {
+    media_preview::get_media_preview(&url, &RequestHeaders::default())
+        .await
+        .map_err(|e| ErrorReport::new(&e))
+}
 
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr).to_string();
-        return Err(format!("yt-dlp execution failed: {}", error_msg));
+// Legacy summary of the media preview
+#[tauri::command]
+async fn get_video_info(url: String) -> Result<VideoInfo, ErrorReport> {
+    let preview = get_media_preview(url).await?;
+    if !preview.available {
+        return Err(ErrorReport::new(&AppError::NetworkError {
+            kind: NetworkErrorKind::ContentUnavailable,
+            message: format!("Video is unavailable: {:?}", preview.warnings),
+            retriable: false,
+        }));
     }
 
-    let json_str = String::from_utf8_lossy(&output.stdout).to_string();
-    let json_value: serde_json::Value = serde_json::from_str(&json_str)
-        .map_err(|e| format!("Failed to parse yt-dlp output: {}", e))?;
-
-    let title = json_value["title"].as_str().unwrap_or("Unknown Title").to_string();
-    let uploader = json_value["uploader"].as_str().unwrap_or("Unknown Uploader").to_string();
-    let duration = json_value["duration"].as_f64().map(|d| d as i32);
-    let views = json_value["view_count"].as_i64();
-    let likes = json_value["like_count"].as_i64();
-    let upload_date = json_value["upload_date"].as_str().map(|date| {
-        if date.len() == 8 {
-            let year = &date[0..4];
-            let month = &date[4..6];
-            let day = &date[6..8];
-            format!("{}-{}-{}", year, month, day)
-        } else {
-            date.to_string()
-        }
-    });
-
     Ok(VideoInfo {
-        title,
-        uploader,
-        duration,
-        views,
-        likes,
-        uploadDate: upload_date,
+        title: preview.title.unwrap_or_else(|| "Unknown Title".to_string()),
+        uploader: preview.uploader.unwrap_or_else(|| "Unknown Uploader".to_string()),
+        duration: preview.duration_secs.map(|d| d as i32),
+        views: preview.view_count.map(|v| v as i64),
+        likes: preview.like_count.map(|l| l as i64),
+        uploadDate: preview.upload_date,
     })
 }
 
@@ -699,33 +926,153 @@ fn check_license() -> String {
   }
 }
 
+// License panel commands; each returns the full state for the panel to render
+#[tauri::command]
+fn license_state() -> Result<LicenseState, ErrorReport> {
+  license::license_state().map_err(|e| ErrorReport::new(&e))
+}
+
+#[tauri::command]
+fn activate_license(license_key: String, email: String) -> Result<LicenseState, ErrorReport> {
+  match license::activate_license(&license_key, &email).map_err(|e| ErrorReport::new(&e))? {
+    license::LicenseStatus::Invalid(reason) => Err(ErrorReport::new(&AppError::LicenseError(reason))),
+    status => Ok(LicenseState::from_status(&status)),
+  }
+}
+
+#[tauri::command]
+fn deactivate_license() -> Result<LicenseState, ErrorReport> {
+  license::deactivate_license().map_err(|e| ErrorReport::new(&e))?;
+  license::license_state().map_err(|e| ErrorReport::new(&e))
+}
+
+#[tauri::command]
+fn start_trial() -> Result<LicenseState, ErrorReport> {
+  license::start_trial().map_err(|e| ErrorReport::new(&e))?;
+  license::license_state().map_err(|e| ErrorReport::new(&e))
+}
+
+// Verify the installed license again, e.g. after the clock or machine changed
 #[tauri::command]
-fn activate_license(license_key: String, email: String) -> Result<String, String> {
-  activate_pro_license(&license_key, &email)
+fn refresh_license() -> Result<LicenseState, ErrorReport> {
+  license::license_state().map_err(|e| ErrorReport::new(&e))
 }
 
+// Command to get the downloads left today and when the count resets, e.g.
+// for "3 of 5 downloads left today, resets in 6h"
 #[tauri::command]
-fn license_info() -> Result<String, String> {
-  get_license_info()
+fn get_quota_status() -> Result<QuotaStatus, ErrorReport> {
+  quota_status().map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to get finished downloads, newest first, optionally only those
+// whose title, URL or file path contains `query`
+#[tauri::command]
+async fn get_download_history(query: Option<String>, limit: usize) -> Result<Vec<HistoryEntry>, ErrorReport> {
+  tokio::task::spawn_blocking(move || {
+    let history = History::open_default()?;
+    match query.as_deref().map(str::trim).filter(|query| !query.is_empty()) {
+      Some(query) => history.search(query, limit),
+      None => history.list(limit),
+    }
+  })
+  .await
+  .map_err(|e| ErrorReport::new(&AppError::General(e.to_string())))?
+  .map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to delete the whole download history; downloaded files are kept
+#[tauri::command]
+async fn clear_download_history() -> Result<usize, ErrorReport> {
+  tokio::task::spawn_blocking(|| History::open_default()?.clear(None))
+      .await
+      .map_err(|e| ErrorReport::new(&AppError::General(e.to_string())))?
+      .map_err(|e| ErrorReport::new(&e))
+}
+
+// Command to get the last known yt-dlp and ffmpeg status for the health
+// indicator, checking them the first time
+#[tauri::command]
+async fn get_dependency_status() -> Result<Vec<DependencyInfo>, ErrorReport> {
+  tokio::task::spawn_blocking(dependency_validator::dependency_status)
+      .await
+      .map_err(|e| ErrorReport::new(&AppError::General(e.to_string())))
+}
+
+// Command to check yt-dlp and ffmpeg again, e.g. after installing one by hand
+#[tauri::command]
+async fn recheck_dependencies() -> Result<Vec<DependencyInfo>, ErrorReport> {
+  tokio::task::spawn_blocking(dependency_validator::recheck_dependencies)
+      .await
+      .map_err(|e| ErrorReport::new(&AppError::General(e.to_string())))
+}
+
+// Set while a dependency install runs, so a second click doesn't start another
+static DEPENDENCY_INSTALL_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Progress of a dependency install, emitted as "dependency-install"
+#[derive(Clone, Serialize)]
+struct DependencyInstallEvent {
+  name: String,
+  // "started", "finished" or "failed"
+  stage: &'static str,
+  error: Option<ErrorReport>,
+}
+
+// Command to install or update yt-dlp or ffmpeg in the background. Emits
+// "dependency-install" when it starts and ends, then "dependency-status"
+// with the re-checked status of all dependencies.
+#[tauri::command]
+async fn install_dependency(window: Window, name: String) -> Result<(), ErrorReport> {
+  if !dependency_validator::TRACKED_DEPENDENCIES.contains(&name.as_str()) {
+    return Err(ErrorReport::new(&AppError::General(format!("Unknown dependency: {}", name))));
+  }
+  if DEPENDENCY_INSTALL_RUNNING.swap(true, Ordering::SeqCst) {
+    return Err(ErrorReport::new(&AppError::General("A dependency install is already running".to_string())));
+  }
+
+  tokio::spawn(async move {
+    let emit = |stage: &'static str, error: Option<ErrorReport>| {
+      let event = DependencyInstallEvent { name: name.clone(), stage, error };
+      if let Err(e) = window.emit("dependency-install", event) {
+        eprintln!("Error emitting dependency-install event: {}", e);
+      }
+    };
+
+    emit("started", None);
+    let target = name.clone();
+    let result = tokio::task::spawn_blocking(move || dependency_validator::install_or_update_dependency(&target)).await;
+    match result {
+      Ok(Ok(())) => emit("finished", None),
+      Ok(Err(e)) => emit("failed", Some(ErrorReport::new(&e))),
+      Err(e) => emit("failed", Some(ErrorReport::new(&AppError::General(e.to_string())))),
+    }
+    DEPENDENCY_INSTALL_RUNNING.store(false, Ordering::SeqCst);
+
+    match tokio::task::spawn_blocking(dependency_validator::recheck_dependencies).await {
+      Ok(status) => {
+        if let Err(e) = window.emit("dependency-status", status) {
+          eprintln!("Error emitting dependency-status event: {}", e);
+        }
+      }
+      Err(e) => eprintln!("Dependency re-check failed: {}", e),
+    }
+  });
+
+  Ok(())
 }
 
 #[tauri::command]
 fn list_download_paths() -> Vec<String> {
   let mut paths = Vec::new();
   
-  if let Some(home_dir) = dirs_next::home_dir() {
-    let mut videos_dir = home_dir.clone();
-    videos_dir.push("Downloads");
-    videos_dir.push("rustloader");
-    videos_dir.push("videos");
+  if let Some(download_dir) = rustloader::paths::default_download_dir() {
+    let videos_dir = download_dir.join("videos");
     if let Some(path_str) = videos_dir.to_str() {
       paths.push(path_str.to_string());
     }
 
-    let mut audio_dir = home_dir;
-    audio_dir.push("Downloads");
-    audio_dir.push("rustloader");
-    audio_dir.push("audio");
+    let audio_dir = download_dir.join("audio");
     if let Some(path_str) = audio_dir.to_str() {
       paths.push(path_str.to_string());
     }
@@ -736,7 +1083,7 @@ fn list_download_paths() -> Vec<String> {
 
 #[tauri::command]
 fn check_pending_downloads() -> bool {
-  !get_all_downloads().is_empty()
+  !queue_manager::downloads_snapshot().is_empty()
 }
 
 // Check if this is the first run of the application
@@ -751,6 +1098,9 @@ fn is_first_run() -> bool {
 // The function is already imported in the dependencies
 
 fn main() {
+  // A panic leaves a crash report in the data directory
+  crash_report::install_panic_hook();
+
   let progress_state = Arc::new(Mutex::new(0));
 
   // Download manager state will be created in setup since we need the app handle
@@ -761,6 +1111,17 @@ fn main() {
       .plugin(tauri_plugin_log::Builder::default().build())
       .manage(ProgressState(progress_state))
       .setup(|app| {
+          // Take ownership of the download queue unless another instance has it
+          let instance_lock = instance_lock::acquire_instance_lock(InstanceKind::Gui).unwrap_or_else(|e| {
+              eprintln!("Could not take the instance lock: {}", e);
+              None
+          });
+          // A CLI run owns the queue for now; take it over when it ends
+          if instance_lock.is_none() && instance_lock::other_instance().is_some() {
+              retry_instance_lock(app.handle().clone());
+          }
+          app.manage(InstanceLockState(Mutex::new(instance_lock)));
+          
           // Create and register the download manager state
           let download_manager_state = DownloadManagerState::new(app.handle().clone());
           app.manage(download_manager_state);
@@ -781,11 +1142,32 @@ fn main() {
           // Optimized download commands
           start_optimized_download,
           list_downloads,
+          subscribe_queue_changes,
+          get_queue_remaining_bytes,
+          get_speed_history,
+          get_download_error,
+          reveal_in_file_manager,
+          open_file,
+          suggest_urls,
+          suggest_options,
           pause_download_item,
           resume_download_item,
           cancel_download_item,
           pause_all,
           resume_all,
+          reorder_queue,
+          pause_items,
+          cancel_items,
+          set_priority_items,
+          retry_items,
+          undo_queue,
+          set_when_done,
+          
+          // Job templates
+          list_templates,
+          save_template,
+          save_template_from_download,
+          delete_template,
           
           // Notification commands - comment out until notification functionality is fully implemented
           // are_notifications_supported,
@@ -799,14 +1181,35 @@ fn main() {
           start_download,
           get_progress,
           is_pro,
+          license_state,
           activate_license,
-          license_info,
+          deactivate_license,
+          refresh_license,
+          start_trial,
+          get_quota_status,
+          get_download_history,
+          clear_download_history,
           check_license,
           list_download_paths,
           check_pending_downloads,
+          
+          // Dependency health indicator
+          get_dependency_status,
+          recheck_dependencies,
+          install_dependency,
           get_video_info,
+          get_media_preview,
           poll_download_progress
       ])
-      .run(tauri::generate_context!())
-      .expect("error while running tauri application");
+      .build(tauri::generate_context!())
+      .expect("error while building tauri application")
+      .run(|_app, event| {
+          // The event loop is not on the async runtime, so the queue is
+          // stopped and saved without waiting on it
+          if let tauri::RunEvent::Exit = event {
+              if let Err(e) = shutdown_download_manager_blocking(SHUTDOWN_FLUSH_TIMEOUT) {
+                  log::warn!("Could not save the download queue on exit: {}", e);
+              }
+          }
+      });
 }
@@ -2,6 +2,7 @@ import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
 import Alert from './Alert';
+import { errorMessage } from './ErrorHandler';
 
 interface DownloadFormProps {
   isPro: boolean;
@@ -135,9 +136,7 @@ const DownloadForm: React.FC<DownloadFormProps> = ({
     } catch (err) {
       console.error('Error fetching video info:', err);
       setError(
-        `Failed to fetch video info: ${
-          err instanceof Error ? err.message : 'Unknown error'
-        }`
+        `Failed to fetch video info: ${errorMessage(err)}`
       );
     } finally {
       setIsLoading(false);
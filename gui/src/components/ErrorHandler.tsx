@@ -1,6 +1,22 @@
 import React, { useState } from 'react';
 import { AlertTriangle, XCircle, RefreshCw, ChevronDown, ChevronUp, Copy } from 'lucide-react';
 
+// What a backend command rejects with
+export interface ErrorReport {
+  code: string;
+  category: 'network' | 'download' | 'dependency' | 'input' | 'settings' | 'storage' | 'license' | 'internal';
+  message: string;
+  item_id?: string | null;
+  retriable: boolean;
+}
+
+// The message of an error thrown by invoke() or in the frontend
+export const errorMessage = (err: unknown): string => {
+  if (err instanceof Error) return err.message;
+  if (typeof err === 'object' && err !== null && 'message' in err) return String((err as ErrorReport).message);
+  return String(err);
+};
+
 interface ErrorHandlerProps {
   error: string;
   suggestion?: string;
@@ -1,7 +1,21 @@
 // src/components/LicenseInfo.tsx
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import Alert from './Alert';
+import { errorMessage } from './ErrorHandler';
+
+// Mirrors rustloader::license::LicenseState
+interface LicenseState {
+  tier: 'free' | 'pro' | 'trial' | 'invalid';
+  email: string | null;
+  key_hint: string | null;
+  activated_at: string | null;
+  expires_at: string | null;
+  trial_days_left: number | null;
+  features: string[];
+  activation_count: number;
+  reason: string | null;
+}
 
 interface LicenseInfoProps {
   isProVersion: boolean;
@@ -14,6 +28,52 @@ const LicenseInfo: React.FC<LicenseInfoProps> = ({ isProVersion, onActivationCom
   const [isActivating, setIsActivating] = useState(false);
   const [error, setError] = useState('');
   const [success, setSuccess] = useState('');
+  const [license, setLicense] = useState<LicenseState | null>(null);
+
+  useEffect(() => {
+    invoke<LicenseState>('license_state')
+      .then(setLicense)
+      .catch((err) => setError(`Could not read license: ${errorMessage(err)}`));
+  }, []);
+
+  const formatDate = (date: string | null): string => {
+    return date ? new Date(date).toLocaleDateString() : 'Never';
+  };
+
+  const handleDeactivate = async () => {
+    setError('');
+    setSuccess('');
+    try {
+      setLicense(await invoke<LicenseState>('deactivate_license'));
+      setSuccess('License removed from this machine');
+      onActivationComplete(false);
+    } catch (err) {
+      setError(`Deactivation failed: ${errorMessage(err)}`);
+    }
+  };
+
+  const handleStartTrial = async () => {
+    setError('');
+    setSuccess('');
+    try {
+      setLicense(await invoke<LicenseState>('start_trial'));
+      setSuccess('Pro trial started!');
+      onActivationComplete(true);
+    } catch (err) {
+      setError(`Could not start trial: ${errorMessage(err)}`);
+    }
+  };
+
+  const handleRefresh = async () => {
+    setError('');
+    try {
+      const state = await invoke<LicenseState>('refresh_license');
+      setLicense(state);
+      onActivationComplete(state.tier === 'pro');
+    } catch (err) {
+      setError(`Could not refresh license: ${errorMessage(err)}`);
+    }
+  };
 
   // Validation functions
   const validateEmail = (email: string): boolean => {
@@ -58,10 +118,11 @@ const LicenseInfo: React.FC<LicenseInfoProps> = ({ isProVersion, onActivationCom
     setIsActivating(true);
     
     try {
-      await invoke('activate_license', {
+      const state = await invoke<LicenseState>('activate_license', {
         licenseKey,
         email
       });
+      setLicense(state);
       
       setSuccess('License activated successfully!');
       setLicenseKey('');
@@ -70,15 +131,22 @@ const LicenseInfo: React.FC<LicenseInfoProps> = ({ isProVersion, onActivationCom
       // Notify parent component
       onActivationComplete(true);
     } catch (err) {
-      setError(`Activation failed: ${err instanceof Error ? err.message : String(err)}`);
+      setError(`Activation failed: ${errorMessage(err)}`);
       onActivationComplete(false);
     } finally {
       setIsActivating(false);
     }
   };
 
+  const trialBanner = license?.tier === 'trial' && (
+    <div className="mb-4 p-3 rounded-md bg-primary-50 dark:bg-primary-900/20 text-primary-800 dark:text-primary-200 text-sm">
+      Pro trial: {license.trial_days_left} day{license.trial_days_left === 1 ? '' : 's'} left
+      (ends {formatDate(license.expires_at)})
+    </div>
+  );
+
   // If already on Pro version, show different content
-  if (isProVersion) {
+  if (license ? license.tier === 'pro' : isProVersion) {
     return (
       <div className="bg-white dark:bg-gray-800 rounded-lg shadow p-5">
         <div className="bg-green-50 dark:bg-green-900/20 border border-green-200 dark:border-green-800 rounded-lg p-5">
@@ -98,6 +166,39 @@ const LicenseInfo: React.FC<LicenseInfoProps> = ({ isProVersion, onActivationCom
             <li>Multi-threaded downloads for maximum speed</li>
             <li>Priority updates and support</li>
           </ul>
+          {license && (
+            <dl className="grid grid-cols-2 gap-x-4 gap-y-1 text-sm text-green-700 dark:text-green-300 mb-4">
+              <dt className="font-medium">Email</dt>
+              <dd>{license.email}</dd>
+              <dt className="font-medium">License key</dt>
+              <dd className="font-mono">{license.key_hint}</dd>
+              <dt className="font-medium">Activated</dt>
+              <dd>{formatDate(license.activated_at)}</dd>
+              <dt className="font-medium">Expires</dt>
+              <dd>{formatDate(license.expires_at)}</dd>
+              <dt className="font-medium">Activations</dt>
+              <dd>{license.activation_count}</dd>
+            </dl>
+          )}
+          {error && (
+            <Alert type="error" message={error} onDismiss={() => setError('')} />
+          )}
+          <div className="flex gap-2">
+            <button
+              type="button"
+              onClick={handleRefresh}
+              className="py-1.5 px-3 text-sm border border-green-300 dark:border-green-700 text-green-800 dark:text-green-200 rounded-md"
+            >
+              Refresh
+            </button>
+            <button
+              type="button"
+              onClick={handleDeactivate}
+              className="py-1.5 px-3 text-sm border border-red-300 dark:border-red-700 text-red-700 dark:text-red-300 rounded-md"
+            >
+              Deactivate
+            </button>
+          </div>
         </div>
       </div>
     );
@@ -109,6 +210,12 @@ const LicenseInfo: React.FC<LicenseInfoProps> = ({ isProVersion, onActivationCom
         Activate Pro License
       </h2>
       
+      {trialBanner}
+      
+      {license?.tier === 'invalid' && license.reason && (
+        <Alert type="warning" message={`Installed license is not valid: ${license.reason}`} />
+      )}
+      
       {error && (
         <Alert type="error" message={error} onDismiss={() => setError('')} />
       )}
@@ -177,6 +284,16 @@ const LicenseInfo: React.FC<LicenseInfoProps> = ({ isProVersion, onActivationCom
           </ul>
         </div>
 
+        {license?.tier === 'free' && (
+          <button
+            type="button"
+            onClick={handleStartTrial}
+            className="mt-4 w-full py-2 px-4 border border-primary-600 text-primary-600 dark:text-primary-400 font-medium rounded-md"
+          >
+            Start free trial
+          </button>
+        )}
+
         <p className="mt-4 text-center text-sm text-gray-600 dark:text-gray-400">
           Don't have a license yet? <a href="https://rustloader.com/pro" target="_blank" rel="noopener noreferrer" className="text-primary-600 dark:text-primary-400 hover:underline">Purchase Pro</a>
         </p>
@@ -0,0 +1,74 @@
+// src/age_gate.rs
+//! Fallbacks for age-restricted YouTube videos that don't need an account.
+//! When yt-dlp stops at the age gate, the download is retried through the
+//! video's embed page and then with YouTube player clients that often skip
+//! the check, until one works. The strategy that worked is recorded on the
+//! download.
+
+use crate::video_id::extract_video_id;
+use serde::{Deserialize, Serialize};
+
+/// YouTube player clients tried through `--extractor-args`, in order
+pub const PLAYER_CLIENTS: [&str; 3] = ["tv_embedded", "web_embedded", "web_creator"];
+
+/// One way of getting past the age gate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgeGateStrategy {
+    /// Download from `youtube.com/embed/<id>` instead of the watch page
+    EmbedUrl,
+    /// Ask yt-dlp to use this YouTube player client
+    PlayerClient(String),
+}
+
+impl AgeGateStrategy {
+    /// URL to download `url` from with this strategy
+    pub fn url(&self, url: &str) -> String {
+        match (self, youtube_video_id(url)) {
+            (AgeGateStrategy::EmbedUrl, Some(id)) => format!("https://www.youtube.com/embed/{}", id),
+            _ => url.to_string(),
+        }
+    }
+
+    /// Value for yt-dlp's `--extractor-args`, if the strategy needs one
+    pub fn extractor_args(&self) -> Option<String> {
+        match self {
+            AgeGateStrategy::EmbedUrl => None,
+            AgeGateStrategy::PlayerClient(client) => Some(format!("youtube:player_client={}", client)),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            AgeGateStrategy::EmbedUrl => "embed URL".to_string(),
+            AgeGateStrategy::PlayerClient(client) => format!("{} player client", client),
+        }
+    }
+}
+
+/// Whether a download failed at an age gate, from its error message and
+/// the last lines yt-dlp printed
+pub fn is_age_gated(message: &str, context: &[String]) -> bool {
+    std::iter::once(message).chain(context.iter().map(String::as_str)).any(|line| {
+        let line = line.to_lowercase();
+        ["confirm your age", "age-restricted", "age restricted", "inappropriate for some users"]
+            .iter()
+            .any(|needle| line.contains(needle))
+    })
+}
+
+/// The video ID of a YouTube URL
+fn youtube_video_id(url: &str) -> Option<String> {
+    extract_video_id(url).filter(|video| video.site == "youtube").map(|video| video.id)
+}
+
+/// Strategies to try, in order, after `url` hit an age gate; none for
+/// anything but a single YouTube video
+pub fn fallback_strategies(url: &str) -> Vec<AgeGateStrategy> {
+    if youtube_video_id(url).is_none() {
+        return Vec::new();
+    }
+    std::iter::once(AgeGateStrategy::EmbedUrl)
+        .chain(PLAYER_CLIENTS.iter().map(|client| AgeGateStrategy::PlayerClient(client.to_string())))
+        .collect()
+}
@@ -0,0 +1,405 @@
+//! Time-of-day bandwidth management for Rustloader
+//!
+//! Profiles from the config file map windows of the day (e.g. work hours) to a
+//! rate limit. The bandwidth manager resolves the limit for the current time and
+//! ramps linearly between adjacent profiles so limits don't jump abruptly.
+//!
+//! Running downloads check the limit every [`LIMIT_CHECK_INTERVAL`] with a
+//! [`RateLimitWatch`], so profile boundaries and overrides reach them too:
+//! the built-in downloader throttles to the new limit, while yt-dlp, which
+//! can't change `--limit-rate` once started, is restarted and resumes.
+
+use crate::config::{load_config, save_config, AppConfig};
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::error::AppError;
+use chrono::{Local, NaiveTime, Timelike};
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default ramp length between adjacent profiles
+pub const DEFAULT_TRANSITION_MINUTES: u32 = 15;
+
+/// Rate used in place of "unlimited" while ramping into or out of an unlimited profile.
+/// Matches the cap the internal downloader uses when no limit is configured.
+const UNLIMITED_RAMP_CEILING: u64 = 15 * 1024 * 1024;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// How often running downloads look for a new limit
+pub const LIMIT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Smallest change, as a share of the applied limit, that yt-dlp is
+/// restarted for. Ramps move the limit a little every minute.
+pub const MIN_RESTART_CHANGE: f64 = 0.25;
+
+/// Number of recently completed downloads averaged for throughput estimates
+const THROUGHPUT_SAMPLE_SIZE: usize = 10;
+
+/// A bandwidth profile as stored in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BandwidthProfile {
+    /// Display name (e.g. "work-hours")
+    pub name: String,
+    /// Start of the window in local time (HH:MM)
+    pub start: String,
+    /// End of the window in local time (HH:MM), may wrap past midnight
+    pub end: String,
+    /// Rate limit such as "1M" or "500K"; `None` means unlimited
+    pub limit: Option<String>,
+}
+
+/// A validated profile with parsed times and limit
+#[derive(Debug, Clone)]
+struct ResolvedProfile {
+    name: String,
+    start: u32,
+    end: u32,
+    limit: Option<u64>,
+}
+
+impl ResolvedProfile {
+    fn contains(&self, minute: u32) -> bool {
+        match self.start.cmp(&self.end) {
+            std::cmp::Ordering::Less => minute >= self.start && minute < self.end,
+            std::cmp::Ordering::Greater => minute >= self.start || minute < self.end,
+            std::cmp::Ordering::Equal => true,
+        }
+    }
+}
+
+/// Snapshot of the bandwidth manager state for display
+#[derive(Debug, Clone)]
+pub struct BandwidthStatus {
+    /// Name of the profile active right now, if any
+    pub active_profile: Option<String>,
+    /// Effective limit in bytes per second (`None` = unlimited)
+    pub current_limit: Option<u64>,
+    /// Runtime override in effect, if any
+    pub override_limit: Option<String>,
+    /// Number of configured profiles
+    pub profile_count: usize,
+}
+
+/// Resolves the download rate limit from time-of-day profiles
+#[derive(Debug, Clone)]
+pub struct BandwidthManager {
+    profiles: Vec<ResolvedProfile>,
+    transition_minutes: u32,
+    /// `Some(limit)` when a runtime override replaces the schedule
+    override_limit: Option<Option<u64>>,
+}
+
+impl BandwidthManager {
+    /// Create a manager from configured profiles
+    pub fn new(profiles: &[BandwidthProfile], transition_minutes: u32) -> Result<Self, AppError> {
+        let mut resolved = Vec::with_capacity(profiles.len());
+
+        for profile in profiles {
+            resolved.push(ResolvedProfile {
+                name: profile.name.clone(),
+                start: parse_time_of_day(&profile.start)?,
+                end: parse_time_of_day(&profile.end)?,
+                limit: match &profile.limit {
+                    Some(limit) => parse_rate_limit(limit)?,
+                    None => None,
+                },
+            });
+        }
+
+        Ok(Self {
+            profiles: resolved,
+            transition_minutes: transition_minutes.min(MINUTES_PER_DAY / 2),
+            override_limit: None,
+        })
+    }
+
+    /// Create a manager from the user's config file, including any runtime override
+    pub fn from_config() -> Result<Self, AppError> {
+        Self::from_app_config(&load_config()?)
+    }
+
+    /// Create a manager from loaded settings, including any runtime override
+    pub fn from_app_config(config: &AppConfig) -> Result<Self, AppError> {
+        let transition = config
+            .bandwidth_transition_minutes
+            .unwrap_or(DEFAULT_TRANSITION_MINUTES);
+
+        let mut manager = Self::new(&config.bandwidth_profiles, transition)?;
+        if let Some(limit) = &config.bandwidth_override {
+            manager = manager.with_override(Some(parse_rate_limit(limit)?));
+        }
+
+        Ok(manager)
+    }
+
+    /// Replace the schedule with a fixed limit (`Some(None)` = unlimited)
+    pub fn with_override(mut self, limit: Option<Option<u64>>) -> Self {
+        self.override_limit = limit;
+        self
+    }
+
+    /// Name of the profile active at the given time
+    pub fn active_profile(&self, time: NaiveTime) -> Option<&str> {
+        let minute = minute_of_day(time);
+        self.profiles
+            .iter()
+            .find(|p| p.contains(minute))
+            .map(|p| p.name.as_str())
+    }
+
+    /// Effective limit in bytes per second at the given time (`None` = unlimited)
+    pub fn limit_at(&self, time: NaiveTime) -> Option<u64> {
+        if let Some(limit) = self.override_limit {
+            return limit;
+        }
+
+        let minute = minute_of_day(time);
+        let current = self.raw_limit(minute);
+
+        if self.transition_minutes == 0 {
+            return current;
+        }
+
+        // Find the most recent profile boundary within the transition window
+        let boundary = self
+            .profiles
+            .iter()
+            .flat_map(|p| [p.start, p.end])
+            .filter(|b| minutes_between(*b, minute) < self.transition_minutes)
+            .min_by_key(|b| minutes_between(*b, minute));
+
+        let boundary = match boundary {
+            Some(b) => b,
+            None => return current,
+        };
+
+        let previous = self.raw_limit((boundary + MINUTES_PER_DAY - 1) % MINUTES_PER_DAY);
+        if previous == current {
+            return current;
+        }
+
+        let from = previous.unwrap_or(UNLIMITED_RAMP_CEILING) as f64;
+        let to = current.unwrap_or(UNLIMITED_RAMP_CEILING) as f64;
+        let fraction = minutes_between(boundary, minute) as f64 / self.transition_minutes as f64;
+
+        Some((from + (to - from) * fraction).round() as u64)
+    }
+
+    /// Limit of the first profile covering the given minute, without ramping
+    fn raw_limit(&self, minute: u32) -> Option<u64> {
+        self.profiles
+            .iter()
+            .find(|p| p.contains(minute))
+            .and_then(|p| p.limit)
+    }
+
+    /// Throughput to expect at the given time: the measured average,
+    /// capped by the limit in effect
+    pub fn expected_throughput(&self, measured: Option<f64>, time: NaiveTime) -> Option<f64> {
+        match (measured, self.limit_at(time)) {
+            (Some(measured), Some(limit)) => Some(measured.min(limit as f64)),
+            (Some(measured), None) => Some(measured),
+            // Without measurements a limit is the best guess we have
+            (None, Some(limit)) => Some(limit as f64),
+            (None, None) => None,
+        }
+    }
+
+    /// Summarize the manager state at the given time
+    pub fn status_at(&self, time: NaiveTime) -> BandwidthStatus {
+        BandwidthStatus {
+            active_profile: self.active_profile(time).map(|s| s.to_string()),
+            current_limit: self.limit_at(time),
+            override_limit: self.override_limit.map(|limit| match limit {
+                Some(bytes) => format_rate_limit(bytes),
+                None => "unlimited".to_string(),
+            }),
+            profile_count: self.profiles.len(),
+        }
+    }
+}
+
+/// Parse a rate limit such as "500K", "2M" or "unlimited" into bytes per second
+pub fn parse_rate_limit(limit: &str) -> Result<Option<u64>, AppError> {
+    let limit = limit.trim();
+    if limit.eq_ignore_ascii_case("unlimited") || limit.eq_ignore_ascii_case("none") || limit == "0" {
+        return Ok(None);
+    }
+
+    let re = Regex::new(r"^(\d+)([KkMmGg])?$").unwrap();
+    let captures = re.captures(limit).ok_or_else(|| {
+        AppError::ValidationError(format!(
+            "Invalid bandwidth limit: {}. Use format like '500K', '2M' or 'unlimited'",
+            limit
+        ))
+    })?;
+
+    let value: u64 = captures[1]
+        .parse()
+        .map_err(|_| AppError::ValidationError(format!("Invalid bandwidth value: {}", limit)))?;
+
+    let multiplier = match captures.get(2).map(|m| m.as_str().to_ascii_uppercase()) {
+        Some(unit) if unit == "K" => 1024,
+        Some(unit) if unit == "M" => 1024 * 1024,
+        Some(unit) if unit == "G" => 1024 * 1024 * 1024,
+        _ => 1,
+    };
+
+    Ok(Some(value.saturating_mul(multiplier)))
+}
+
+/// Format a byte rate for yt-dlp's `--limit-rate`
+pub fn format_rate_limit(bytes_per_second: u64) -> String {
+    if bytes_per_second >= 1024 * 1024 && bytes_per_second % (1024 * 1024) == 0 {
+        format!("{}M", bytes_per_second / (1024 * 1024))
+    } else {
+        format!("{}K", bytes_per_second.div_ceil(1024).max(1))
+    }
+}
+
+/// Limit in bytes per second for downloads running now (`None` = unlimited)
+pub fn current_limit() -> Option<u64> {
+    match BandwidthManager::from_config() {
+        Ok(manager) => manager.limit_at(Local::now().time()),
+        Err(e) => {
+            warn!("Ignoring bandwidth profiles: {}", e);
+            None
+        }
+    }
+}
+
+/// Rate limit to pass to yt-dlp for a download starting now, if any
+pub fn current_rate_limit() -> Option<String> {
+    current_limit().map(format_rate_limit)
+}
+
+/// The limit a running download applies, and whether the profiles or the
+/// override have moved it since
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitWatch {
+    applied: Option<u64>,
+    min_change: f64,
+}
+
+impl RateLimitWatch {
+    /// Watch a download that started with `limit`, reporting every change
+    pub fn new(limit: Option<u64>) -> Self {
+        Self { applied: limit, min_change: 0.0 }
+    }
+
+    /// Only report changes of at least `share` of the applied limit.
+    /// Switching between limited and unlimited is always reported.
+    pub fn with_min_change(mut self, share: f64) -> Self {
+        self.min_change = share;
+        self
+    }
+
+    /// Limit the download runs with (`None` = unlimited)
+    pub fn applied(&self) -> Option<u64> {
+        self.applied
+    }
+
+    /// The limit in effect now, if the download should switch to it
+    pub fn changed(&mut self) -> Option<Option<u64>> {
+        self.check(current_limit())
+    }
+
+    /// `limit`, if the download should switch to it; it is then the applied limit
+    pub fn check(&mut self, limit: Option<u64>) -> Option<Option<u64>> {
+        let switch = match (self.applied, limit) {
+            (Some(applied), Some(limit)) => {
+                limit != applied && limit.abs_diff(applied) as f64 >= applied as f64 * self.min_change
+            }
+            (applied, limit) => applied != limit,
+        };
+        if switch {
+            self.applied = limit;
+        }
+        switch.then_some(limit)
+    }
+}
+
+/// Average transfer rate in bytes per second over the most recently
+/// completed downloads, falling back to the combined speed of downloads
+/// in progress when none have finished yet
+pub fn average_throughput(downloads: &[DownloadItem]) -> Option<f64> {
+    let mut completed: Vec<&DownloadItem> = downloads
+        .iter()
+        .filter(|item| item.is_completed() && item.total_bytes > 0)
+        .collect();
+    completed.sort_by_key(|item| std::cmp::Reverse(item.finished_at));
+
+    let (bytes, seconds) = completed
+        .iter()
+        .filter_map(|item| {
+            let elapsed = item.finished_at? - item.started_at?;
+            let seconds = elapsed.num_milliseconds() as f64 / 1000.0;
+            (seconds > 0.0).then_some((item.total_bytes as f64, seconds))
+        })
+        .take(THROUGHPUT_SAMPLE_SIZE)
+        .fold((0.0, 0.0), |(bytes, seconds), (b, s)| (bytes + b, seconds + s));
+
+    if seconds > 0.0 {
+        return Some(bytes / seconds);
+    }
+
+    let current: f64 = downloads
+        .iter()
+        .filter(|item| item.status == DownloadStatus::Downloading)
+        .map(|item| item.speed)
+        .sum();
+    (current > 0.0).then_some(current)
+}
+
+/// Expected throughput for queued downloads right now, from recent
+/// history and the bandwidth profiles
+pub fn expected_throughput(downloads: &[DownloadItem]) -> Option<f64> {
+    let measured = average_throughput(downloads);
+    match BandwidthManager::from_config() {
+        Ok(manager) => manager.expected_throughput(measured, Local::now().time()),
+        Err(e) => {
+            warn!("Ignoring bandwidth profiles: {}", e);
+            measured
+        }
+    }
+}
+
+/// Current bandwidth manager status for `rustloader queue bandwidth`
+pub fn get_bandwidth_status() -> Result<BandwidthStatus, AppError> {
+    let manager = BandwidthManager::from_config()?;
+    Ok(manager.status_at(Local::now().time()))
+}
+
+/// Set or clear (`None` / "auto") the runtime bandwidth override
+pub fn set_bandwidth_override(limit: Option<&str>) -> Result<(), AppError> {
+    let mut config = load_config()?;
+
+    config.bandwidth_override = match limit {
+        Some(value) if !value.eq_ignore_ascii_case("auto") => {
+            // Validate before persisting
+            parse_rate_limit(value)?;
+            Some(value.to_string())
+        }
+        _ => None,
+    };
+
+    save_config(&config)
+}
+
+fn parse_time_of_day(time: &str) -> Result<u32, AppError> {
+    let parsed = NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| {
+        AppError::TimeFormatError(format!("Profile time must be in the format HH:MM: {}", time))
+    })?;
+    Ok(minute_of_day(parsed))
+}
+
+fn minute_of_day(time: NaiveTime) -> u32 {
+    time.hour() * 60 + time.minute()
+}
+
+/// Minutes elapsed going forward from `from` to `to`, wrapping at midnight
+fn minutes_between(from: u32, to: u32) -> u32 {
+    (to + MINUTES_PER_DAY - from) % MINUTES_PER_DAY
+}
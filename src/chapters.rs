@@ -0,0 +1,152 @@
+// src/chapters.rs
+//! Chapter downloads for `--chapters "Intro,2"`. The requested chapters,
+//! by title or number, are looked up in the video's chapter list and each
+//! is handed to yt-dlp as a `--download-sections` title pattern, so every
+//! chapter is saved as its own file.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::metadata_cache::fetch_media_info;
+use serde_json::Value;
+use std::path::Path;
+
+/// A chapter of a video
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    /// Position in the video, from 1
+    pub number: usize,
+    pub title: String,
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+impl Chapter {
+    /// The `--download-sections` pattern matching only this chapter's title
+    pub fn section_pattern(&self) -> String {
+        format!("^{}$", regex::escape(&self.title))
+    }
+}
+
+/// A chapter as requested on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChapterRef {
+    /// Chapter number, from 1
+    Number(usize),
+    /// Chapter title, matched ignoring case
+    Title(String),
+}
+
+/// Parse a comma-separated list of chapter titles and numbers, e.g.
+/// "Intro,Lecture 2,5". A title containing a comma is requested by number.
+pub fn parse_chapter_selection(value: &str) -> Result<Vec<ChapterRef>, AppError> {
+    let mut selection = Vec::new();
+    for part in value.split(',').map(str::trim) {
+        if part.is_empty() {
+            continue;
+        }
+        match part.parse::<usize>() {
+            Ok(0) => {
+                return Err(AppError::ValidationError(
+                    "Chapter numbers start at 1".to_string(),
+                ))
+            }
+            Ok(number) => selection.push(ChapterRef::Number(number)),
+            Err(_) => selection.push(ChapterRef::Title(part.to_string())),
+        }
+    }
+    if selection.is_empty() {
+        return Err(AppError::ValidationError(
+            "No chapters given; list titles or numbers separated by commas".to_string(),
+        ));
+    }
+    Ok(selection)
+}
+
+/// The chapters listed in `yt-dlp --dump-json` output
+pub fn chapters_from_info(info: &Value) -> Vec<Chapter> {
+    info["chapters"]
+        .as_array()
+        .map(|chapters| {
+            chapters
+                .iter()
+                .enumerate()
+                .map(|(index, chapter)| Chapter {
+                    number: index + 1,
+                    title: chapter["title"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("Chapter {}", index + 1)),
+                    start_secs: chapter["start_time"].as_f64().unwrap_or(0.0),
+                    end_secs: chapter["end_time"].as_f64().unwrap_or(0.0),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The requested chapters in video order, each once. Fails naming the
+/// chapters that don't exist, with the list of those that do.
+pub fn select_chapters(available: &[Chapter], requested: &[ChapterRef]) -> Result<Vec<Chapter>, AppError> {
+    if available.is_empty() {
+        return Err(AppError::ValidationError("This video has no chapters".to_string()));
+    }
+
+    let mut picked = vec![false; available.len()];
+    let mut missing = Vec::new();
+    for chapter in requested {
+        let found = match chapter {
+            ChapterRef::Number(number) => available.iter().position(|c| c.number == *number),
+            ChapterRef::Title(title) => available.iter().position(|c| c.title.trim().eq_ignore_ascii_case(title)),
+        };
+        match (found, chapter) {
+            (Some(index), _) => picked[index] = true,
+            (None, ChapterRef::Number(number)) => missing.push(number.to_string()),
+            (None, ChapterRef::Title(title)) => missing.push(format!("\"{}\"", title)),
+        }
+    }
+
+    if !missing.is_empty() {
+        let listing = available
+            .iter()
+            .map(|c| format!("{}. {}", c.number, c.title))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(AppError::ValidationError(format!(
+            "No chapter {} in this video. Its chapters are: {}",
+            missing.join(", "),
+            listing
+        )));
+    }
+
+    Ok(available
+        .iter()
+        .zip(picked)
+        .filter(|(_, picked)| *picked)
+        .map(|(chapter, _)| chapter.clone())
+        .collect())
+}
+
+/// Look up the requested chapters of the video at `url`
+pub async fn resolve_chapters(
+    url: &str,
+    selection: &str,
+    request_headers: &RequestHeaders,
+) -> Result<Vec<Chapter>, AppError> {
+    let requested = parse_chapter_selection(selection)?;
+    let info = fetch_media_info(url, request_headers).await?;
+    select_chapters(&chapters_from_info(&info), &requested)
+}
+
+/// Output template saving each chapter under the video's name followed by
+/// the chapter number and title, e.g. "Title - 02 Lecture 2.mp4"
+pub fn chapter_template(output_path: &str) -> String {
+    let path = Path::new(output_path);
+    let (stem, extension) = match path.extension() {
+        Some(ext) => (
+            &output_path[..output_path.len() - ext.len() - 1],
+            ext.to_string_lossy().into_owned(),
+        ),
+        None => (output_path, "%(ext)s".to_string()),
+    };
+    format!("{} - %(section_number)02d %(section_title)s.{}", stem, extension)
+}
@@ -0,0 +1,135 @@
+// src/circuit_breaker.rs
+//! Pauses the whole queue when downloads keep failing with network errors,
+//! e.g. behind a VPN that dropped overnight, instead of letting every queued
+//! download burn its retries. While the breaker is open no download starts;
+//! connectivity is probed periodically and the queue resumes once it's back.
+
+use crate::config::load_config;
+use crate::site_stats::FailureKind;
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration as StdDuration;
+use tokio::sync::Notify;
+
+/// Connectivity is never probed more often than this
+const MIN_PROBE_INTERVAL_SECS: u64 = 5;
+
+/// When the queue is paused for network failures. Set under
+/// `circuit_breaker` in config.json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerSettings {
+    pub enabled: bool,
+    /// Consecutive network failures that pause the queue
+    pub failures: u32,
+    /// Failures older than this no longer count towards `failures`
+    pub window_minutes: u32,
+    /// How often connectivity is checked while the queue is paused
+    pub probe_interval_secs: u64,
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failures: 5,
+            window_minutes: 10,
+            probe_interval_secs: 60,
+        }
+    }
+}
+
+impl CircuitBreakerSettings {
+    /// How long to wait between connectivity probes
+    pub fn probe_interval(&self) -> StdDuration {
+        StdDuration::from_secs(self.probe_interval_secs.max(MIN_PROBE_INTERVAL_SECS))
+    }
+}
+
+/// Consecutive network failures and whether they paused the queue
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker {
+    failures: Vec<DateTime<Utc>>,
+    opened_at: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A download finished, or failed for a reason other than the network,
+    /// which ends the run of failures
+    pub fn record_success(&mut self) {
+        self.failures.clear();
+    }
+
+    /// A download failed. Returns true when this failure opens the breaker.
+    pub fn record_failure(&mut self, kind: FailureKind, settings: &CircuitBreakerSettings, now: DateTime<Utc>) -> bool {
+        if kind != FailureKind::Network {
+            self.record_success();
+            return false;
+        }
+        if !settings.enabled || self.is_open() {
+            return false;
+        }
+
+        let window = Duration::minutes(settings.window_minutes as i64);
+        self.failures.retain(|at| now.signed_duration_since(*at) <= window);
+        self.failures.push(now);
+        if self.failures.len() < settings.failures.max(1) as usize {
+            return false;
+        }
+        self.opened_at = Some(now);
+        true
+    }
+
+    /// Whether the queue is paused
+    pub fn is_open(&self) -> bool {
+        self.opened_at.is_some()
+    }
+
+    /// Connectivity is back: let downloads start again
+    pub fn close(&mut self) {
+        self.failures.clear();
+        self.opened_at = None;
+    }
+}
+
+static BREAKER: Lazy<Mutex<CircuitBreaker>> = Lazy::new(|| Mutex::new(CircuitBreaker::new()));
+static OPENED: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Feed the result of a queued download to the breaker. `None` is a success.
+pub fn record_download_result(failure: Option<FailureKind>) {
+    let mut breaker = BREAKER.lock().unwrap();
+    let Some(kind) = failure else {
+        breaker.record_success();
+        return;
+    };
+    let settings = load_config().map(|config| config.circuit_breaker).unwrap_or_default();
+    if breaker.record_failure(kind, &settings, Utc::now()) {
+        warn!(
+            "{} downloads in a row failed with network errors; pausing the queue until the connection is back",
+            settings.failures
+        );
+        OPENED.notify_one();
+    }
+}
+
+/// Whether the queue is paused for network failures
+pub fn queue_held() -> bool {
+    BREAKER.lock().unwrap().is_open()
+}
+
+/// Wait until the breaker opens
+pub async fn opened() {
+    OPENED.notified().await;
+}
+
+/// Let downloads start again
+pub fn close() {
+    BREAKER.lock().unwrap().close();
+}
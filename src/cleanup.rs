@@ -0,0 +1,153 @@
+// src/cleanup.rs
+
+use crate::config::load_config;
+use crate::download_manager::DownloadItem;
+use crate::error::AppError;
+use crate::paths::default_download_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A leftover partial download found on disk
+#[derive(Debug, Clone)]
+pub struct PartialFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// The unfinished download this file belongs to, if one could be identified
+    pub download: Option<DownloadItem>,
+}
+
+impl PartialFile {
+    /// Whether the owning download can be resumed from this file
+    pub fn is_resumable(&self) -> bool {
+        self.download.as_ref().is_some_and(|item| !item.is_completed())
+    }
+}
+
+/// Remove a file found by a cleanup: into the system trash so a mistake can
+/// be recovered, or for good when `permanent` is set
+pub fn discard_file(path: &Path, permanent: bool) -> Result<(), AppError> {
+    if permanent {
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+    trash::delete(path)
+        .map_err(|e| AppError::General(format!("Could not move {} to the trash: {}", path.display(), e)))
+}
+
+/// Default locations where downloads (and their partial files) end up
+pub fn default_download_dirs() -> Vec<PathBuf> {
+    let mut download_dirs = Vec::new();
+
+    if let Some(home_path) = default_download_dir() {
+        download_dirs.push(home_path.clone());
+
+        let mut videos_path = home_path.clone();
+        videos_path.push("videos");
+        download_dirs.push(videos_path);
+
+        let mut audio_path = home_path;
+        audio_path.push("audio");
+        download_dirs.push(audio_path);
+    }
+
+    // Interrupted staged downloads leave their partial files in the staging directory
+    if let Some(staging) = load_config().ok().and_then(|config| config.staging_path().ok().flatten()) {
+        download_dirs.push(staging);
+    }
+
+    download_dirs.push(std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    download_dirs
+}
+
+/// Whether a file name looks like a yt-dlp partial download artifact
+pub fn is_partial_file_name(name: &str) -> bool {
+    name.ends_with(".part") || name.ends_with(".ytdl") || name.contains(".part-Frag")
+}
+
+/// The media name a partial file was created for, e.g.
+/// `My Video.f137.mp4.part` -> `My Video`
+pub fn partial_base_name(name: &str) -> &str {
+    let name = match name.find(".part-Frag") {
+        Some(pos) => &name[..pos],
+        None => name.trim_end_matches(".part").trim_end_matches(".ytdl"),
+    };
+
+    // Drop the extension and any yt-dlp format marker (".f137")
+    let mut base = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    if let Some((stem, marker)) = base.rsplit_once('.') {
+        if marker.len() > 1 && marker.starts_with('f') && marker[1..].chars().all(|c| c.is_ascii_digit()) {
+            base = stem;
+        }
+    }
+    base
+}
+
+/// Compare names the way yt-dlp sanitizes titles: only letters and digits count
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Find the download a partial file belongs to, by title or video ID
+pub fn match_partial_file<'a>(file_name: &str, downloads: &'a [DownloadItem]) -> Option<&'a DownloadItem> {
+    let base = normalize_name(partial_base_name(file_name));
+    if base.is_empty() {
+        return None;
+    }
+
+    downloads.iter().find(|item| {
+        let title_matches = item
+            .title
+            .as_deref()
+            .map(normalize_name)
+            .is_some_and(|title| title == base);
+        let id_matches = crate::video_id::extract_video_id(&item.url)
+            .is_some_and(|video| file_name.contains(&video.id));
+        title_matches || id_matches
+    })
+}
+
+/// Inventory partial files across the default download directories and the
+/// output directories of known downloads
+pub fn scan_partial_files(downloads: &[DownloadItem]) -> Result<Vec<PartialFile>, AppError> {
+    let mut dirs = default_download_dirs();
+    for item in downloads {
+        if let Some(dir) = &item.output_dir {
+            dirs.push(PathBuf::from(dir));
+        }
+    }
+    dirs.sort();
+    dirs.dedup();
+
+    let mut found = Vec::new();
+    for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+        scan_dir(dir, downloads, &mut found);
+    }
+    Ok(found)
+}
+
+fn scan_dir(dir: &Path, downloads: &[DownloadItem], found: &mut Vec<PartialFile>) {
+    if crate::security::validate_path_safety(dir).is_err() {
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_file() || !is_partial_file_name(&file_name) {
+            continue;
+        }
+
+        found.push(PartialFile {
+            size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            download: match_partial_file(&file_name, downloads).cloned(),
+            path,
+        });
+    }
+}
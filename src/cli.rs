@@ -2,12 +2,21 @@
 
 use clap::{Arg, ArgAction, Command};
 
+/// Maximum video qualities `--quality` accepts
+pub const VIDEO_QUALITIES: [&str; 6] = ["360", "480", "720", "1080", "1440", "2160"];
+
+/// The qualities above 1080p, which need Rustloader Pro. The feature gate
+/// checks them against the license before anything is downloaded.
+pub const PRO_QUALITIES: [&str; 2] = ["1440", "2160"];
+
 /// Build the command-line interface for the application
 pub fn build_cli() -> Command {
     let mut app = Command::new("rustloader")
         .version("1.0.0")
         .author("Ibrahim Mohamed")
         .about("Advanced video downloader for various content sources")
+        // Subcommands such as `queue` and `suggest-urls` don't take the top-level URL
+        .subcommand_negates_reqs(true)
         .subcommand(
             Command::new("download")
                 .about("Download a video or audio")
@@ -20,9 +29,22 @@ pub fn build_cli() -> Command {
                 .arg(
                     Arg::new("quality")
                         .long("quality")
+                        .visible_alias("video-quality")
                         .short('q')
-                        .help("Specify the desired quality (480, 720, 1080)")
-                        .value_parser(["480", "720", "1080"]),
+                        .help("Specify the maximum video quality (360, 480, 720, 1080; 1440 and 2160 with Pro)")
+                        .value_parser(VIDEO_QUALITIES),
+                )
+                .arg(
+                    Arg::new("audio-quality")
+                        .long("audio-quality")
+                        .help("Audio quality to pair with the video (low, medium, high)")
+                        .value_parser(["low", "medium", "high"]),
+                )
+                .arg(
+                    Arg::new("codec")
+                        .long("codec")
+                        .help("Preferred video codec (avc1, vp9, av1)")
+                        .value_parser(["avc1", "vp9", "av1"]),
                 )
                 .arg(
                     Arg::new("format")
@@ -58,17 +80,32 @@ pub fn build_cli() -> Command {
                         .help("Download subtitles if available")
                         .action(ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("no-playlist")
+                        .long("no-playlist")
+                        .help("Download only the video, even if the template enables --playlist")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("playlist"),
+                )
+                .arg(
+                    Arg::new("no-subtitles")
+                        .long("no-subs")
+                        .help("Skip subtitles, even if the template enables --subs")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("subtitles"),
+                )
                 .arg(
                     Arg::new("output-dir")
                         .long("output-dir")
+                        .visible_alias("output")
                         .short('o')
-                        .help("Specify custom output directory")
+                        .help("Specify custom output directory, or - to stream the media to stdout (a named pipe works too)")
                         .value_name("DIRECTORY"),
                 )
                 .arg(
                     Arg::new("video-bitrate")
                         .long("bitrate")
-                        .help("Set video bitrate (e.g., 1000K)")
+                        .help("Set the bitrate (e.g., 1000K, 1.5M or best; 32K-320K for mp3)")
                         .value_name("BITRATE"),
                 )
                 .arg(
@@ -81,14 +118,306 @@ pub fn build_cli() -> Command {
                 .arg(
                     Arg::new("add-to-queue")
                         .long("queue")
-                        .help("Add to download queue instead of downloading immediately")
+                        .help("Add to download queue instead of downloading immediately; a playlist is queued one video per download")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("deadline")
+                        .long("deadline")
+                        .help("Stop the queued download if it hasn't finished within this time (e.g. 30m, 2h)")
+                        .value_name("DURATION")
+                        .requires("add-to-queue"),
+                )
+                .arg(
+                    Arg::new("on-deadline")
+                        .long("on-deadline")
+                        .help("What to do when the deadline passes")
+                        .value_parser(["fail", "pause"])
+                        .default_value("fail")
+                        .requires("deadline"),
+                )
+                .arg(
+                    Arg::new("resume-after")
+                        .long("resume-after")
+                        .help("With --on-deadline pause, resume the download automatically after this time (e.g. 8h)")
+                        .value_name("DURATION")
+                        .requires("deadline"),
+                )
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .short('t')
+                        .help("Apply a saved job template (explicit options take precedence)")
+                        .value_name("NAME"),
+                )
+                .arg(
+                    Arg::new("transcode")
+                        .long("transcode")
+                        .help("Re-encode after download (hevc, upscale-1080, upscale-2160)")
+                        .value_parser(["hevc", "upscale-1080", "upscale-2160"]),
+                )
+                .arg(
+                    Arg::new("ffmpeg-args")
+                        .long("ffmpeg-args")
+                        .help("Extra ffmpeg options for post-processing, e.g. \"-vf hflip -crf 20\"")
+                        .value_name("ARGS")
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    Arg::new("no-merge")
+                        .long("no-merge")
+                        .help("Save the video and audio streams as separate files (combine them later with `mux`)")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("transcode"),
+                )
+                .arg(
+                    Arg::new("trim-silence")
+                        .long("trim-silence")
+                        .help("Remove leading and trailing silence from extracted audio (thresholds under silence_trim in config.json)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("transliterate")
+                        .long("transliterate")
+                        .help("Save under an ASCII transliteration of the title (the original stays in the metadata tags)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("media-center-naming")
+                        .long("media-center-naming")
+                        .help("Name episodes like \"Show - S01E03 - Title.mp4\" for Jellyfin, Plex and Kodi, from series metadata, SxxEyy in the title or the playlist position")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("nfo")
+                        .long("nfo")
+                        .help("Write an .nfo sidecar next to each episode named with --media-center-naming")
+                        .requires("media-center-naming")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("split-size")
+                        .long("split-size")
+                        .help("Split outputs larger than SIZE (e.g. 2G, 500M) into numbered parts with a manifest, for FAT32 drives and upload limits; put them back together with `rustloader join`")
+                        .value_name("SIZE"),
+                )
+                .arg(
+                    Arg::new("chapters")
+                        .long("chapters")
+                        .help("Download only these chapters, by title or number (e.g. \"Intro,Lecture 2\" or 1,3), each to its own file")
+                        .value_name("CHAPTERS")
+                        .conflicts_with_all(["start-time", "end-time", "playlist", "no-merge", "media-center-naming", "split-size"]),
+                )
+                .arg(
+                    Arg::new("vertical-mode")
+                        .long("vertical-mode")
+                        .help("Fit the video to --target-aspect by padding or cropping after download (keep, pad, crop)")
+                        .value_parser(["keep", "pad", "crop"]),
+                )
+                .arg(
+                    Arg::new("target-aspect")
+                        .long("target-aspect")
+                        .help("Aspect ratio of the target screen for --vertical-mode (default 9:16)")
+                        .value_name("W:H"),
+                )
+                .arg(
+                    Arg::new("auto-update-deps")
+                        .long("auto-update-deps")
+                        .help("Update yt-dlp and retry once if the site extractor looks outdated, without asking")
                         .action(ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .help("Don't ask for confirmation before long transcodes")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("print-cmd")
+                        .long("print-cmd")
+                        .help("Print the yt-dlp command that would be run, without downloading")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("user-agent")
+                        .long("user-agent")
+                        .help("Send a custom User-Agent instead of the default browser one")
+                        .value_name("USER_AGENT"),
+                )
+                .arg(
+                    Arg::new("header")
+                        .long("header")
+                        .help("Add an HTTP header, e.g. \"Referer: https://example.com/\" (repeatable)")
+                        .value_name("NAME: VALUE")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("player-client")
+                        .long("player-client")
+                        .help("YouTube player clients to request formats with, e.g. android or tv,web (this download only)")
+                        .value_name("CLIENTS"),
+                )
+                .arg(
+                    Arg::new("geo-bypass-country")
+                        .long("geo-bypass-country")
+                        .help("Two-letter country code to present for geo-restricted content, e.g. US (this download only)")
+                        .value_name("CC"),
+                )
+                .arg(
+                    Arg::new("mirror")
+                        .long("mirror")
+                        .help("Alternate URL for the same content, tried if the main URL fails (repeatable)")
+                        .value_name("URL")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .help("Tag a queued download, e.g. to match a retention policy (repeatable)")
+                        .value_name("TAG")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("interactive")
+                        .long("interactive")
+                        .short('i')
+                        .help("List the available formats and pick one interactively")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["quality", "audio-quality", "codec", "playlist"]),
+                )
+        )
+        .subcommand(Command::new("init").about("Set up download defaults and dependencies interactively"))
+        .subcommand(
+            Command::new("dedupe")
+                .about("Show space saved by linking duplicate downloads")
+                .subcommand(
+                    Command::new("mode")
+                        .about("Choose what happens when a download is identical to an existing file")
+                        .arg(
+                            Arg::new("mode")
+                                .help("off, ask before linking, or hardlink automatically")
+                                .required(true)
+                                .value_parser(["off", "ask", "hardlink"])
+                                .index(1),
+                        ),
+                )
+                .subcommand(
+                    Command::new("naming")
+                        .about("Choose how a download is named when its title is already taken")
+                        .arg(
+                            Arg::new("naming")
+                                .help("counter (\"Title (2).mp4\"), timestamp, or uploader (\"Uploader - Title.mp4\")")
+                                .required(true)
+                                .value_parser(["counter", "timestamp", "uploader"])
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("license")
+                .about("Show license information, or start the Pro trial")
+                .subcommand(Command::new("trial").about(format!(
+                    "Try the Pro features free for {} days (once per machine)",
+                    crate::license::TRIAL_DAYS
+                ))),
+        )
+        .subcommand(
+            Command::new("plugins")
+                .about("Show installed plugins and any problems with them")
+                .subcommand(Command::new("list").about("List installed plugins (the default)")),
+        )
+        .subcommand(
+            Command::new("publish")
+                .about("Link finished downloads into extra folders, e.g. a media library")
+                .subcommand(Command::new("list").about("List publish rules"))
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a rule linking matching downloads into one or more folders")
+                        .arg(
+                            Arg::new("target")
+                                .help("Folder to link downloads into")
+                                .required(true)
+                                .num_args(1..)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .help("Only publish downloads in this format")
+                                .value_parser(["mp4", "mp3"]),
+                        )
+                        .arg(
+                            Arg::new("url-contains")
+                                .long("url-contains")
+                                .help("Only publish downloads whose URL contains this text"),
+                        )
+                        .arg(
+                            Arg::new("symlink")
+                                .long("symlink")
+                                .help("Create symlinks instead of hardlinks")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove a publish rule")
+                        .arg(
+                            Arg::new("number")
+                                .help("Rule number shown by 'publish list'")
+                                .required(true)
+                                .value_parser(clap::value_parser!(usize))
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("encrypt-state")
+                .about("Encrypt the download queue and recent URLs at rest with a key from the OS keychain")
+                .arg(
+                    Arg::new("mode")
+                        .help("Turn encryption on or off; shows the current setting when omitted")
+                        .value_parser(["on", "off"])
+                        .index(1),
+                ),
         )
         .subcommand(
             Command::new("queue")
                 .about("Manage download queue")
-                .subcommand(Command::new("list").about("List all downloads in the queue"))
+                .subcommand(
+                    Command::new("list")
+                        .about("List all downloads in the queue")
+                        .arg(
+                            Arg::new("sort")
+                                .long("sort")
+                                .help("Sort by speed, progress, size (largest first) or added (oldest first)")
+                                .value_parser(["speed", "progress", "added", "size"]),
+                        )
+                        .arg(
+                            Arg::new("status")
+                                .long("status")
+                                .help("Only list downloads with this status; repeat to include several")
+                                .value_parser(["queued", "downloading", "paused", "completed", "failed", "canceled"])
+                                .action(ArgAction::Append),
+                        ),
+                )
+                .subcommand(Command::new("stats").about("Show queue totals and the estimated finish time"))
+                .subcommand(
+                    Command::new("show")
+                        .about("Show all details of a download")
+                        .arg(
+                            Arg::new("id")
+                                .help("Download ID or unique prefix")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the download as JSON")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
                 .subcommand(Command::new("pause-all").about("Pause all active downloads"))
                 .subcommand(Command::new("resume-all").about("Resume all paused downloads"))
                 .subcommand(
@@ -138,8 +467,458 @@ pub fn build_cli() -> Command {
                                 .value_parser(["low", "normal", "high", "critical"]),
                         ),
                 )
+                .subcommand(
+                    Command::new("schedule")
+                        .about("Hold a queued download until a start time or inside a daily time window")
+                        .arg(
+                            Arg::new("id")
+                                .help("Download ID")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("at")
+                                .long("at")
+                                .help("Start no earlier than this: +2h, 23:30, \"2026-03-01 23:30\" or an RFC 3339 time")
+                                .value_name("TIME")
+                                .required_unless_present_any(["window", "clear"]),
+                        )
+                        .arg(
+                            Arg::new("window")
+                                .long("window")
+                                .help("Only start between these local times each day, e.g. 01:00-06:00")
+                                .value_name("START-END"),
+                        )
+                        .arg(
+                            Arg::new("clear")
+                                .long("clear")
+                                .help("Remove the start time and window")
+                                .action(ArgAction::SetTrue)
+                                .conflicts_with_all(["at", "window"]),
+                        ),
+                )
                 .subcommand(Command::new("clear-completed").about("Remove completed downloads from the queue"))
-                .subcommand(Command::new("clear-failed").about("Clear failed downloads from the queue")),
+                .subcommand(Command::new("clear-failed").about("Clear failed downloads from the queue"))
+                .subcommand(
+                    Command::new("undo")
+                        .about("Bring back downloads removed by the last clear-completed, clear-failed or bulk cancel")
+                        .arg(
+                            Arg::new("list")
+                                .long("list")
+                                .help("List the operations that can be undone instead")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("archive")
+                        .about("Move old finished downloads from the queue into the history archive")
+                        .arg(
+                            Arg::new("older-than")
+                                .long("older-than")
+                                .help("Archive downloads finished more than this many days ago (default: the queue_archive_after_days setting, or 30)")
+                                .value_name("DAYS")
+                                .value_parser(clap::value_parser!(u32)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("repair")
+                        .about("Reset downloads stuck in Downloading with nothing running them, e.g. after a crash"),
+                )
+                .subcommand(
+                    Command::new("run")
+                        .about("Process the queue in the foreground, e.g. as a container entrypoint")
+                        .arg(
+                            Arg::new("until-empty")
+                                .long("until-empty")
+                                .help("Exit once no downloads are queued or running")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("when-done")
+                                .long("when-done")
+                                .help("Once all active downloads finish: shutdown, sleep, notify, or run <COMMAND>")
+                                .value_names(["ACTION", "COMMAND"])
+                                .num_args(1..=2),
+                        ),
+                )
+                .subcommand(
+                    Command::new("wait")
+                        .about("Wait until a download (or the whole queue) finishes; exits 0 when completed, 3 when failed, 4 when canceled, 124 on timeout")
+                        .arg(
+                            Arg::new("id")
+                                .help("Download ID or unique prefix")
+                                .required_unless_present("all")
+                                .conflicts_with("all")
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .help("Wait for every download that isn't paused")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("timeout")
+                                .long("timeout")
+                                .help("Give up after this long (e.g., 90s, 30m, 1h)")
+                                .value_name("DURATION"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("follow")
+                        .about("Stream queue events as NDJSON until interrupted")
+                        .arg(
+                            Arg::new("interval")
+                                .long("interval")
+                                .help("Seconds between progress snapshots")
+                                .value_name("SECONDS")
+                                .default_value("5")
+                                .value_parser(clap::value_parser!(u64).range(1..)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("bandwidth")
+                        .about("Show bandwidth profiles or override the download limit")
+                        .arg(
+                            Arg::new("limit")
+                                .help("Limit to apply (e.g., 2M, 500K, unlimited) or 'auto' to follow profiles")
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("service")
+                .about("Install or remove desktop integration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("install")
+                        .about("Open rustloader:// links and .rustloader job files with Rustloader (Windows)"),
+                )
+                .subcommand(Command::new("uninstall").about("Remove the link and job file registration")),
+        )
+        .subcommand(
+            Command::new("paths")
+                .about("Print every file and directory Rustloader uses")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the paths as JSON")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("limits")
+                .about("Show how many downloads are left today and when the count resets")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the limits as JSON")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("List, search or clear the downloads Rustloader has finished")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("Show the most recently finished downloads")
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .short('n')
+                                .value_name("COUNT")
+                                .help("How many downloads to show (default: 20)")
+                                .value_parser(clap::value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the downloads as JSON")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("search")
+                        .about("Find finished downloads by title, URL or file path")
+                        .arg(
+                            Arg::new("query")
+                                .help("Text to look for")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .short('n')
+                                .value_name("COUNT")
+                                .help("How many downloads to show (default: 20)")
+                                .value_parser(clap::value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the downloads as JSON")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("clear")
+                        .about("Delete entries from the download history (downloaded files are kept)")
+                        .arg(
+                            Arg::new("older-than")
+                                .long("older-than")
+                                .value_name("DAYS")
+                                .help("Only delete downloads finished more than DAYS days ago")
+                                .value_parser(clap::value_parser!(u32)),
+                        )
+                        .arg(
+                            Arg::new("yes")
+                                .long("yes")
+                                .short('y')
+                                .help("Delete without asking")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Show statistics from the download history")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("sites")
+                        .about("Success rate, average speed and common failures per site")
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the statistics as JSON")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("providers")
+                .about("Check which sites work with the installed yt-dlp")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("status")
+                        .about("Extract a known-good video from each site without downloading it")
+                        .arg(
+                            Arg::new("site")
+                                .long("site")
+                                .value_name("SITE")
+                                .help("Only check this site, e.g. youtube.com (repeatable)")
+                                .action(ArgAction::Append),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the results as JSON")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("template")
+                .about("Manage reusable job templates")
+                .subcommand(Command::new("list").about("List saved templates"))
+                .subcommand(
+                    Command::new("show")
+                        .about("Show the options stored in a template")
+                        .arg(
+                            Arg::new("name")
+                                .help("Template name")
+                                .required(true)
+                                .index(1),
+                        ),
+                )
+                .subcommand(
+                    Command::new("save-from")
+                        .about("Save the options of a previous download as a template")
+                        .arg(
+                            Arg::new("id")
+                                .help("Download ID (or unique prefix) to copy options from")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .help("Template name")
+                                .required(true)
+                                .index(2),
+                        ),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Delete a saved template")
+                        .arg(
+                            Arg::new("name")
+                                .help("Template name")
+                                .required(true)
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("cleanup")
+                .about("Find leftover partial downloads")
+                .arg(
+                    Arg::new("scan")
+                        .long("scan")
+                        .help("Scan download directories for .part/.ytdl files, resume matching downloads and delete the rest")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .help("Resume and delete without asking")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("permanent")
+                        .long("permanent")
+                        .help("Delete files for good instead of moving them to the trash")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Compare the estimated size, codec and bits per pixel of each quality a video offers")
+                .arg(
+                    Arg::new("url")
+                        .help("The URL of the video")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the comparison as JSON")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("gc")
+                .about("Apply the retention policies: delete expired files and remove expired queue entries")
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Show what would be deleted and removed without changing anything")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("play")
+                .about("Download a video and play it in mpv or VLC while it downloads")
+                .arg(
+                    Arg::new("url")
+                        .help("The URL of the video")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("player")
+                        .long("player")
+                        .help("Player to open instead of the configured one (a name on the PATH or a path)")
+                        .value_name("PLAYER"),
+                ),
+        )
+        .subcommand(
+            Command::new("mux")
+                .about("Combine a video-only and an audio-only file (from --no-merge) without re-encoding")
+                .arg(
+                    Arg::new("video")
+                        .help("File with the video stream")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("audio")
+                        .help("File with the audio stream")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Where to save the result (default: next to the video, named after it)")
+                        .value_name("FILE"),
+                ),
+        )
+        .subcommand(
+            Command::new("join")
+                .about("Put a file split with --split-size back together, checking each part")
+                .arg(
+                    Arg::new("file")
+                        .help("The .manifest.json file or any of the numbered parts")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Where to save the joined file (default: next to the parts, under its original name)")
+                        .value_name("FILE"),
+                ),
+        )
+        .subcommand(
+            Command::new("snapshot")
+                .about("Save still frames from a video URL or file")
+                .arg(
+                    Arg::new("source")
+                        .help("Video URL or local file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("at")
+                        .long("at")
+                        .help("Time of the first still, e.g. 00:12:34 or 754.5")
+                        .value_name("TIME")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .short('n')
+                        .help("Number of stills to take")
+                        .value_parser(clap::value_parser!(u32))
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .help("Seconds between stills")
+                        .value_parser(clap::value_parser!(f64))
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::new("image-format")
+                        .long("image-format")
+                        .help("Image format for the stills")
+                        .value_parser(["png", "jpg"])
+                        .default_value("png"),
+                )
+                .arg(
+                    Arg::new("output-dir")
+                        .long("output-dir")
+                        .short('o')
+                        .help("Where to save the stills (default: Downloads/rustloader/snapshots)")
+                        .value_name("DIR"),
+                ),
+        )
+        .subcommand(
+            Command::new("suggest-urls")
+                .about("Print recently used URLs matching a prefix (for shell completion)")
+                .hide(true)
+                .arg(
+                    Arg::new("prefix")
+                        .help("Start of the URL or channel name")
+                        .index(1),
+                ),
         )
         // Support for just URL as before for backward compatibility
         .arg(
@@ -151,9 +930,22 @@ pub fn build_cli() -> Command {
         .arg(
             Arg::new("quality")
                 .long("quality")
+                .visible_alias("video-quality")
                 .short('q')
-                .help("Specify the desired quality (480, 720, 1080)")
-                .value_parser(["480", "720", "1080"]),
+                .help("Specify the maximum video quality (360, 480, 720, 1080; 1440 and 2160 with Pro)")
+                .value_parser(VIDEO_QUALITIES),
+        )
+        .arg(
+            Arg::new("audio-quality")
+                .long("audio-quality")
+                .help("Audio quality to pair with the video (low, medium, high)")
+                .value_parser(["low", "medium", "high"]),
+        )
+        .arg(
+            Arg::new("codec")
+                .long("codec")
+                .help("Preferred video codec (avc1, vp9, av1)")
+                .value_parser(["avc1", "vp9", "av1"]),
         )
         .arg(
             Arg::new("format")
@@ -189,19 +981,191 @@ pub fn build_cli() -> Command {
                 .help("Download subtitles if available")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("no-playlist")
+                .long("no-playlist")
+                .help("Download only the video, even if the template enables --playlist")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("playlist"),
+        )
+        .arg(
+            Arg::new("no-subtitles")
+                .long("no-subs")
+                .help("Skip subtitles, even if the template enables --subs")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("subtitles"),
+        )
         .arg(
             Arg::new("output-dir")
                 .long("output-dir")
+                .visible_alias("output")
                 .short('o')
-                .help("Specify custom output directory")
+                .help("Specify custom output directory, or - to stream the media to stdout (a named pipe works too)")
                 .value_name("DIRECTORY"),
         )
         .arg(
             Arg::new("video-bitrate")
                 .long("bitrate")
-                .help("Set video bitrate (e.g., 1000K)")
+                .help("Set the bitrate (e.g., 1000K, 1.5M or best; 32K-320K for mp3)")
                 .value_name("BITRATE"),
         )
+        .arg(
+            Arg::new("template")
+                .long("template")
+                .short('t')
+                .help("Apply a saved job template (explicit options take precedence)")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("transcode")
+                .long("transcode")
+                .help("Re-encode after download (hevc, upscale-1080, upscale-2160)")
+                .value_parser(["hevc", "upscale-1080", "upscale-2160"]),
+        )
+        .arg(
+            Arg::new("ffmpeg-args")
+                .long("ffmpeg-args")
+                .help("Extra ffmpeg options for post-processing, e.g. \"-vf hflip -crf 20\"")
+                .value_name("ARGS")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("no-merge")
+                .long("no-merge")
+                .help("Save the video and audio streams as separate files (combine them later with `mux`)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("transcode"),
+        )
+        .arg(
+            Arg::new("trim-silence")
+                .long("trim-silence")
+                .help("Remove leading and trailing silence from extracted audio (thresholds under silence_trim in config.json)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("transliterate")
+                .long("transliterate")
+                .help("Save under an ASCII transliteration of the title (the original stays in the metadata tags)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("media-center-naming")
+                .long("media-center-naming")
+                .help("Name episodes like \"Show - S01E03 - Title.mp4\" for Jellyfin, Plex and Kodi, from series metadata, SxxEyy in the title or the playlist position")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("nfo")
+                .long("nfo")
+                .help("Write an .nfo sidecar next to each episode named with --media-center-naming")
+                .requires("media-center-naming")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("split-size")
+                .long("split-size")
+                .help("Split outputs larger than SIZE (e.g. 2G, 500M) into numbered parts with a manifest, for FAT32 drives and upload limits; put them back together with `rustloader join`")
+                .value_name("SIZE"),
+        )
+        .arg(
+            Arg::new("chapters")
+                .long("chapters")
+                .help("Download only these chapters, by title or number (e.g. \"Intro,Lecture 2\" or 1,3), each to its own file")
+                .value_name("CHAPTERS")
+                .conflicts_with_all(["start-time", "end-time", "playlist", "no-merge", "media-center-naming", "split-size"]),
+        )
+        .arg(
+            Arg::new("vertical-mode")
+                .long("vertical-mode")
+                .help("Fit the video to --target-aspect by padding or cropping after download (keep, pad, crop)")
+                .value_parser(["keep", "pad", "crop"]),
+        )
+        .arg(
+            Arg::new("target-aspect")
+                .long("target-aspect")
+                .help("Aspect ratio of the target screen for --vertical-mode (default 9:16)")
+                .value_name("W:H"),
+        )
+        .arg(
+            Arg::new("auto-update-deps")
+                .long("auto-update-deps")
+                .help("Update yt-dlp and retry once if the site extractor looks outdated, without asking")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("yes")
+                .long("yes")
+                .short('y')
+                .help("Don't ask for confirmation before long transcodes")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("print-cmd")
+                .long("print-cmd")
+                .help("Print the yt-dlp command that would be run, without downloading")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("trace-file")
+                .long("trace-file")
+                .value_name("PATH")
+                .help("Record queue and download timings: a Chrome trace (.json) or folded stacks for flamegraphs (.folded)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("non-interactive")
+                .long("non-interactive")
+                .help("Never prompt; use safe defaults (automatic in containers and CI, or with RUSTLOADER_NON_INTERACTIVE=1)")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("user-agent")
+                .long("user-agent")
+                .help("Send a custom User-Agent instead of the default browser one")
+                .value_name("USER_AGENT"),
+        )
+        .arg(
+            Arg::new("header")
+                .long("header")
+                .help("Add an HTTP header, e.g. \"Referer: https://example.com/\" (repeatable)")
+                .value_name("NAME: VALUE")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("player-client")
+                .long("player-client")
+                .help("YouTube player clients to request formats with, e.g. android or tv,web (this download only)")
+                .value_name("CLIENTS"),
+        )
+        .arg(
+            Arg::new("geo-bypass-country")
+                .long("geo-bypass-country")
+                .help("Two-letter country code to present for geo-restricted content, e.g. US (this download only)")
+                .value_name("CC"),
+        )
+        .arg(
+            Arg::new("mirror")
+                .long("mirror")
+                .help("Alternate URL for the same content, tried if the main URL fails (repeatable)")
+                .value_name("URL")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .help("Tag a queued download, e.g. to match a retention policy (repeatable)")
+                .value_name("TAG")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("List the available formats and pick one interactively")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["quality", "audio-quality", "codec", "playlist"]),
+        )
         // Add license activation argument
         .arg(
             Arg::new("activate-license")
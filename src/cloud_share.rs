@@ -0,0 +1,179 @@
+// src/cloud_share.rs
+//! Google Drive, Dropbox and OneDrive share links. The share page isn't
+//! media yt-dlp reliably understands, so it is turned into the direct
+//! download URL of the shared file, which yt-dlp's native downloader fetches
+//! like any other file. Large Drive files sit behind a "can't scan for
+//! viruses" page whose confirm token is followed here.
+//!
+//! Links are resolved when the download starts rather than when it is
+//! queued, since Drive confirm tokens expire.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use base64::{engine::general_purpose, Engine as _};
+use log::debug;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use crate::proxy::client_builder;
+use reqwest::{header, Url};
+use std::time::Duration;
+
+const RESOLVE_TIMEOUT_SECS: u64 = 15;
+
+/// Largest Drive warning page read while looking for the confirm token
+const MAX_PAGE_BYTES: usize = 2 * 1024 * 1024;
+
+static DRIVE_FORM_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?is)<form[^>]*id="download-form"[^>]*>(.*?)</form>"#).unwrap());
+static FORM_ACTION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?is)<form[^>]*id="download-form"[^>]*action="([^"]+)""#).unwrap());
+static HIDDEN_INPUT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)<input[^>]*type="hidden"[^>]*name="([^"]+)"[^>]*value="([^"]*)""#).unwrap());
+static LEGACY_CONFIRM_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"href="(/uc\?export=download[^"]*confirm=[^"]+)""#).unwrap());
+
+/// A cloud storage service with share links
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudProvider {
+    GoogleDrive,
+    Dropbox,
+    OneDrive,
+}
+
+impl CloudProvider {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CloudProvider::GoogleDrive => "Google Drive",
+            CloudProvider::Dropbox => "Dropbox",
+            CloudProvider::OneDrive => "OneDrive",
+        }
+    }
+}
+
+/// A share link and the URL its file downloads from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudLink {
+    pub provider: CloudProvider,
+    pub url: String,
+}
+
+/// The direct download URL for a share link, without any network access.
+/// Drive links may still need [`resolve_share_link`] for the confirm token.
+pub fn direct_download_url(url: &str) -> Option<CloudLink> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    let host = host.trim_start_matches("www.");
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+        .unwrap_or_default();
+    let query = |key: &str| parsed.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.into_owned());
+
+    match host {
+        "drive.google.com" | "docs.google.com" | "drive.usercontent.google.com" => {
+            let id = match segments.as_slice() {
+                ["file", "d", id, ..] => Some(id.to_string()),
+                _ => query("id"),
+            }?;
+            if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return None;
+            }
+            Some(CloudLink {
+                provider: CloudProvider::GoogleDrive,
+                url: format!("https://drive.usercontent.google.com/download?id={}&export=download", id),
+            })
+        }
+        "dropbox.com" | "dl.dropboxusercontent.com" => {
+            if !matches!(segments.first(), Some(&"s" | &"scl" | &"sh")) {
+                return None;
+            }
+            let mut direct = parsed.clone();
+            let pairs: Vec<(String, String)> = parsed
+                .query_pairs()
+                .filter(|(key, _)| key != "dl" && key != "raw")
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            direct.query_pairs_mut().clear().extend_pairs(pairs).append_pair("dl", "1");
+            Some(CloudLink {
+                provider: CloudProvider::Dropbox,
+                url: direct.to_string(),
+            })
+        }
+        "1drv.ms" | "onedrive.live.com" => {
+            // The shares API takes the whole share link, base64url encoded
+            let encoded = general_purpose::URL_SAFE_NO_PAD.encode(url.trim());
+            Some(CloudLink {
+                provider: CloudProvider::OneDrive,
+                url: format!("https://api.onedrive.com/v1.0/shares/u!{}/root/content", encoded),
+            })
+        }
+        _ if host.ends_with(".sharepoint.com") && segments.first().is_some_and(|first| first.starts_with(':')) => {
+            let mut direct = parsed.clone();
+            let pairs: Vec<(String, String)> = parsed
+                .query_pairs()
+                .filter(|(key, _)| key != "download")
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            direct.query_pairs_mut().clear().extend_pairs(pairs).append_pair("download", "1");
+            Some(CloudLink {
+                provider: CloudProvider::OneDrive,
+                url: direct.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The confirmed download URL on Drive's "can't scan for viruses" page
+pub fn drive_confirm_url(page: &str) -> Option<String> {
+    if let (Some(form), Some(action)) = (DRIVE_FORM_RE.captures(page), FORM_ACTION_RE.captures(page)) {
+        let mut url = Url::parse(&action[1].replace("&amp;", "&")).ok()?;
+        {
+            let mut query = url.query_pairs_mut();
+            for input in HIDDEN_INPUT_RE.captures_iter(&form[1]) {
+                query.append_pair(&input[1], &input[2].replace("&amp;", "&"));
+            }
+        }
+        return Some(url.to_string());
+    }
+    let href = LEGACY_CONFIRM_RE.captures(page)?;
+    Some(format!("https://drive.google.com{}", href[1].replace("&amp;", "&")))
+}
+
+/// The direct download URL for a share link, following Drive's confirm
+/// page when the file is too large to be scanned. `None` for any other URL.
+pub async fn resolve_share_link(url: &str, request_headers: &RequestHeaders) -> Result<Option<CloudLink>, AppError> {
+    let Some(link) = direct_download_url(url) else {
+        return Ok(None);
+    };
+    if link.provider != CloudProvider::GoogleDrive {
+        return Ok(Some(link));
+    }
+
+    let client = client_builder(request_headers.proxy.as_deref())?
+        .timeout(Duration::from_secs(RESOLVE_TIMEOUT_SECS))
+        .user_agent(request_headers.user_agent())
+        .build()?;
+    let response = client.get(&link.url).send().await?.error_for_status()?;
+    let is_page = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    // Small files come straight back; the body is dropped unread
+    if !is_page {
+        return Ok(Some(link));
+    }
+
+    let body = response.bytes().await?;
+    let page = String::from_utf8_lossy(&body[..body.len().min(MAX_PAGE_BYTES)]);
+    match drive_confirm_url(&page) {
+        Some(confirmed) => {
+            debug!("Following Drive's download confirmation for {}", url);
+            Ok(Some(CloudLink { url: confirmed, ..link }))
+        }
+        None => Err(AppError::ValidationError(
+            "This Google Drive file isn't shared publicly or has hit its download limit".to_string(),
+        )),
+    }
+}
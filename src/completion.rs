@@ -0,0 +1,190 @@
+// src/completion.rs
+//! Actions run once the download queue has finished, for overnight batches:
+//! shut down, sleep, notify, or run a command (`--when-done`).
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::error::AppError;
+use crate::ffmpeg_args::split_args;
+use crate::process::CommandSpec;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// What to do when every active download has finished
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "command", rename_all = "lowercase")]
+pub enum CompletionAction {
+    /// Power off, with a minute's grace where the platform allows it
+    Shutdown,
+    /// Suspend the system
+    Sleep,
+    /// Show a desktop notification
+    Notify,
+    /// Run a command, split like a shell would but not run through one
+    Run(String),
+}
+
+impl CompletionAction {
+    /// Parse `--when-done` values: an action name, followed by the command for `run`
+    pub fn parse(values: &[&str]) -> Result<Self, AppError> {
+        let (action, rest) = values
+            .split_first()
+            .ok_or_else(|| AppError::ValidationError("--when-done needs an action".to_string()))?;
+        let action = match (action.to_lowercase().as_str(), rest) {
+            ("shutdown", []) => CompletionAction::Shutdown,
+            ("sleep", []) => CompletionAction::Sleep,
+            ("notify", []) => CompletionAction::Notify,
+            ("run", [command]) if !command.trim().is_empty() => CompletionAction::Run(command.to_string()),
+            ("run", _) => {
+                return Err(AppError::ValidationError(
+                    "--when-done run needs a command, e.g. --when-done run \"./sync.sh\"".to_string(),
+                ))
+            }
+            ("shutdown" | "sleep" | "notify", _) => {
+                return Err(AppError::ValidationError(format!("--when-done {} takes no command", action)))
+            }
+            _ => {
+                return Err(AppError::ValidationError(format!(
+                    "Invalid --when-done action '{}', expected shutdown, sleep, notify or run",
+                    action
+                )))
+            }
+        };
+        action.validate()?;
+        Ok(action)
+    }
+
+    /// Check a `run` command can be split into a program and arguments
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let CompletionAction::Run(command) = self {
+            if split_args(command)?.is_empty() {
+                return Err(AppError::ValidationError("--when-done run needs a command".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            CompletionAction::Shutdown => "shut down".to_string(),
+            CompletionAction::Sleep => "sleep".to_string(),
+            CompletionAction::Notify => "notify".to_string(),
+            CompletionAction::Run(command) => format!("run '{}'", command),
+        }
+    }
+}
+
+/// How the downloads of a finished batch ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueSummary {
+    pub completed: usize,
+    pub failed: usize,
+    pub canceled: usize,
+}
+
+impl QueueSummary {
+    pub fn from_downloads<'a>(downloads: impl IntoIterator<Item = &'a DownloadItem>) -> Self {
+        let mut summary = Self::default();
+        for item in downloads {
+            match item.status {
+                DownloadStatus::Completed => summary.completed += 1,
+                DownloadStatus::Failed => summary.failed += 1,
+                DownloadStatus::Canceled => summary.canceled += 1,
+                _ => {}
+            }
+        }
+        summary
+    }
+}
+
+/// Whether a download has work ahead that starts without anyone stepping
+/// in: queued, running, or paused until its deadline resume time or the end
+/// of a system sleep or network outage
+pub fn is_pending(item: &DownloadItem) -> bool {
+    item.is_active()
+        || (item.is_paused() && (item.resume_at.is_some() || item.paused_for_sleep || item.paused_for_outage))
+}
+
+/// Whether any download is pending
+pub fn is_queue_busy(downloads: &[DownloadItem]) -> bool {
+    downloads.iter().any(is_pending)
+}
+
+/// Whether every download of `batch` still in the queue has finished. One
+/// the user paused is still work to do, so it holds the completion action.
+pub fn is_batch_finished(downloads: &[DownloadItem], batch: &HashSet<String>) -> bool {
+    !batch.is_empty()
+        && downloads
+            .iter()
+            .filter(|item| batch.contains(&item.id))
+            .all(DownloadItem::is_finished)
+}
+
+/// Run a completion action
+pub async fn run_completion_action(action: &CompletionAction, summary: QueueSummary) -> Result<(), AppError> {
+    info!("Queue finished ({:?}), running completion action: {}", summary, action.describe());
+    match action {
+        CompletionAction::Notify => {
+            crate::notifications::notify_queue_finished(summary.completed, summary.failed);
+            Ok(())
+        }
+        CompletionAction::Shutdown => run_system_command(shutdown_command()).await,
+        CompletionAction::Sleep => run_system_command(sleep_command()).await,
+        CompletionAction::Run(command) => {
+            let args = split_args(command)?;
+            let (program, args) = args
+                .split_first()
+                .ok_or_else(|| AppError::ValidationError("--when-done run needs a command".to_string()))?;
+            let status = CommandSpec::user_command(program, args)
+                .to_async_command()?
+                .env("RUSTLOADER_COMPLETED", summary.completed.to_string())
+                .env("RUSTLOADER_FAILED", summary.failed.to_string())
+                .status()
+                .await?;
+            if !status.success() {
+                warn!("Completion command '{}' exited with {}", command, status);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn run_system_command((program, args): (&str, &[&str])) -> Result<(), AppError> {
+    let status = CommandSpec::system(program).args(args).to_async_command()?.status().await?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::General(format!("{} exited with {}", program, status)))
+    }
+}
+
+#[cfg(windows)]
+fn shutdown_command() -> (&'static str, &'static [&'static str]) {
+    ("shutdown", &["/s", "/t", "60"])
+}
+
+#[cfg(target_os = "macos")]
+fn shutdown_command() -> (&'static str, &'static [&'static str]) {
+    ("osascript", &["-e", "tell application \"System Events\" to shut down"])
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn shutdown_command() -> (&'static str, &'static [&'static str]) {
+    // Cancellable with `shutdown -c` during the minute's grace
+    ("shutdown", &["-h", "+1"])
+}
+
+#[cfg(windows)]
+fn sleep_command() -> (&'static str, &'static [&'static str]) {
+    ("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"])
+}
+
+#[cfg(target_os = "macos")]
+fn sleep_command() -> (&'static str, &'static [&'static str]) {
+    ("pmset", &["sleepnow"])
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn sleep_command() -> (&'static str, &'static [&'static str]) {
+    ("systemctl", &["suspend"])
+}
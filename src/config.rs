@@ -0,0 +1,193 @@
+// src/config.rs
+// Persistent user configuration stored alongside the license file
+
+use crate::bandwidth::BandwidthProfile;
+use crate::circuit_breaker::CircuitBreakerSettings;
+use crate::dedupe::{DuplicateHandling, DuplicateNaming};
+use crate::downloader::direct::{DEFAULT_DIRECT_CONNECTIONS, MAX_DIRECT_CONNECTIONS};
+use crate::downloader::MemoryCleanupPolicy;
+use crate::error::AppError;
+use crate::guardrails::{parse_size_limit, DEFAULT_MAX_FILESIZE};
+use crate::paths::config_dir;
+use crate::proxy::ProxyTemplate;
+use crate::publish::PublishRule;
+use crate::retention::RetentionPolicy;
+use crate::security::validate_path_safety;
+use crate::silence::SilenceSettings;
+use crate::smart_defaults::SmartDefaults;
+use crate::templates::JobTemplate;
+use crate::transcode::EncodeBenchmark;
+use crate::webhooks::Webhook;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// User configuration loaded from `config.json` in the Rustloader config directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Time-of-day bandwidth profiles applied by the bandwidth manager
+    pub bandwidth_profiles: Vec<BandwidthProfile>,
+    /// Minutes over which the limit ramps between adjacent profiles; 0 switches
+    /// at the boundary, unset uses the default ramp
+    pub bandwidth_transition_minutes: Option<u32>,
+    /// Runtime override set via `rustloader queue bandwidth` (e.g. "2M", "unlimited")
+    pub bandwidth_override: Option<String>,
+    /// Named job templates applied with `--template`
+    pub templates: BTreeMap<String, JobTemplate>,
+    /// Cached encoder benchmarks used to estimate transcode time, keyed by encoder
+    pub encode_benchmarks: BTreeMap<String, EncodeBenchmark>,
+    /// Download directory used when no `--output-dir` is given
+    pub download_dir: Option<String>,
+    /// Output format used when no `--format` is given (mp4 or mp3)
+    pub default_format: Option<String>,
+    /// Video quality used when no `--quality` is given (e.g. "1080")
+    pub default_quality: Option<String>,
+    /// Number of downloads the queue runs at once
+    pub max_concurrent_downloads: Option<usize>,
+    /// Whether to show a desktop notification when a download finishes
+    pub notifications: Option<bool>,
+    /// Encrypt the queue state and recent URLs with a key from the OS keychain
+    pub encrypt_state: bool,
+    /// What to do when a finished download is identical to a file already downloaded
+    pub duplicate_handling: DuplicateHandling,
+    /// How a new download is named when its title is already taken
+    pub duplicate_naming: DuplicateNaming,
+    /// Extra folders finished downloads are linked into
+    pub publish_rules: Vec<PublishRule>,
+    /// Whether the free version shows upgrade messages; Pro never does
+    pub promos: Option<bool>,
+    /// Language of upgrade messages (e.g. "de"), overriding LANG
+    pub promo_locale: Option<String>,
+    /// Days after which finished downloads move from the queue state to the
+    /// history archive; 0 keeps them in the queue
+    pub queue_archive_after_days: Option<u32>,
+    /// Largest file yt-dlp may download (e.g. "10G"); "none" for no limit
+    pub max_filesize: Option<String>,
+    /// Fast scratch directory where downloads are written, merged and
+    /// transcoded before the finished file moves to the download directory
+    pub staging_dir: Option<String>,
+    /// Proxy for all yt-dlp traffic (e.g. "socks5h://127.0.0.1:9050" for
+    /// Tor). `{id}` in its credentials is replaced per download.
+    pub proxy: Option<String>,
+    /// Give each download its own proxy credentials, so Tor routes it over
+    /// a separate circuit
+    pub proxy_isolation: bool,
+    /// When and how hard long downloads trim their progress buffers
+    pub memory_cleanup: MemoryCleanupPolicy,
+    /// What `--trim-silence` treats as silence
+    pub silence_trim: SilenceSettings,
+    /// URLs told about finished downloads and, optionally, their progress
+    pub webhooks: Vec<Webhook>,
+    /// Serve queue events on a local socket or named pipe for other apps
+    pub event_stream: bool,
+    /// Whether options suggested by a channel's download history are
+    /// shown as a hint, applied, or turned off
+    pub smart_defaults: SmartDefaults,
+    /// Update yt-dlp and retry once when a queued download fails with an outdated extractor
+    pub auto_update_deps: bool,
+    /// When repeated network failures pause the queue
+    pub circuit_breaker: CircuitBreakerSettings,
+    /// Player `rustloader play` opens, e.g. "mpv" or the path to VLC;
+    /// the first of mpv and VLC on the PATH when unset
+    pub player: Option<String>,
+    /// Command the GUI's "run" completion action runs when the queue
+    /// finishes; the GUI itself can only pick it, never supply one
+    pub when_done_command: Option<String>,
+    /// How long finished downloads keep their files and queue entries, per tag
+    pub retention: Vec<RetentionPolicy>,
+    /// Connections the built-in downloader splits a direct media link over;
+    /// 0 leaves direct links to yt-dlp
+    pub direct_connections: Option<usize>,
+}
+
+impl AppConfig {
+    /// Desktop notifications are on unless turned off
+    pub fn notifications_enabled(&self) -> bool {
+        self.notifications.unwrap_or(true)
+    }
+
+    /// How old finished downloads get before they are archived, or None
+    /// when archiving is off
+    pub fn archive_after(&self) -> Option<chrono::Duration> {
+        match self.queue_archive_after_days.unwrap_or(crate::download_history::DEFAULT_ARCHIVE_AFTER_DAYS) {
+            0 => None,
+            days => Some(chrono::Duration::days(days as i64)),
+        }
+    }
+
+    /// The file size limit passed to yt-dlp, or None for no limit
+    pub fn max_filesize_limit(&self) -> Result<Option<String>, AppError> {
+        let value = self.max_filesize.as_deref().unwrap_or(DEFAULT_MAX_FILESIZE).trim();
+        Ok(parse_size_limit(value)?.map(|_| value.to_string()))
+    }
+
+    /// Connections per direct download, at most [`MAX_DIRECT_CONNECTIONS`];
+    /// 0 when the built-in downloader is turned off
+    pub fn direct_connections(&self) -> usize {
+        self.direct_connections
+            .unwrap_or(DEFAULT_DIRECT_CONNECTIONS)
+            .min(MAX_DIRECT_CONNECTIONS)
+    }
+
+    /// The staging directory, if one is configured
+    pub fn staging_path(&self) -> Result<Option<PathBuf>, AppError> {
+        match self.staging_dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()) {
+            Some(dir) => {
+                let path = PathBuf::from(dir);
+                validate_path_safety(&path)?;
+                Ok(Some(path))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The configured proxy, if any
+    pub fn proxy_template(&self) -> Result<Option<ProxyTemplate>, AppError> {
+        match self.proxy.as_deref().map(str::trim).filter(|proxy| !proxy.is_empty()) {
+            Some(proxy) => Ok(Some(ProxyTemplate::parse(proxy, self.proxy_isolation)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Upgrade messages are on unless turned off
+    pub fn promos_enabled(&self) -> bool {
+        self.promos.unwrap_or(true)
+    }
+}
+
+/// Get the path to the configuration file
+pub fn get_config_path() -> Result<PathBuf, AppError> {
+    Ok(config_dir()?.join("config.json"))
+}
+
+/// Load the configuration, falling back to defaults if no file exists
+pub fn load_config() -> Result<AppConfig, AppError> {
+    let config_path = get_config_path()?;
+
+    if !config_path.exists() {
+        debug!("No config file found at {:?}, using defaults", config_path);
+        return Ok(AppConfig::default());
+    }
+
+    let contents = fs::read_to_string(&config_path)?;
+    match serde_json::from_str(&contents) {
+        Ok(config) => Ok(config),
+        Err(e) => {
+            warn!("Config file {:?} is invalid: {}", config_path, e);
+            Err(AppError::JsonError(e))
+        }
+    }
+}
+
+/// Save the configuration to disk
+pub fn save_config(config: &AppConfig) -> Result<(), AppError> {
+    let config_path = get_config_path()?;
+    let json = serde_json::to_string_pretty(config)?;
+    fs::write(&config_path, json)?;
+
+    debug!("Configuration saved to {:?}", config_path);
+    Ok(())
+}
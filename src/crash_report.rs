@@ -0,0 +1,170 @@
+// src/crash_report.rs
+//! Crash reports. The panic hook writes the panic message, where it
+//! happened, a backtrace, the last queue operations and the versions in use
+//! to a file in the data directory and prints its path, then lets the
+//! default hook report the panic as usual.
+//!
+//! Everything here runs inside panic handling, so it must not panic itself
+//! or wait on the async runtime: no runtime is started, locks are only
+//! tried, and the queue state is written on a separate thread with a
+//! timeout, in case the panicking thread holds the queue's locks.
+
+use crate::dependency_validator::last_dependency_status;
+use crate::download_manager::{flush_download_queue_blocking, SHUTDOWN_FLUSH_TIMEOUT};
+use crate::error::AppError;
+use crate::paths::data_dir;
+use crate::version::VERSION;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+// Named PanicHookInfo since 1.81; the old name keeps building on the MSRV
+#[allow(deprecated)]
+use std::panic::PanicInfo;
+use std::path::PathBuf;
+use std::sync::{Mutex, TryLockError};
+
+/// Queue operations kept for the next crash report
+pub const MAX_RECENT_OPERATIONS: usize = 50;
+
+/// Queue operations with the time they were processed, oldest first
+type RecentOperations = VecDeque<(DateTime<Utc>, String)>;
+
+static RECENT_OPERATIONS: Lazy<Mutex<RecentOperations>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_RECENT_OPERATIONS)));
+
+/// Remember a queue operation for the crash report, dropping the oldest
+/// once [`MAX_RECENT_OPERATIONS`] are kept
+pub fn record_operation(name: &str) {
+    let mut operations = RECENT_OPERATIONS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if operations.len() == MAX_RECENT_OPERATIONS {
+        operations.pop_front();
+    }
+    operations.push_back((Utc::now(), name.to_string()));
+}
+
+/// The remembered queue operations, oldest first, e.g.
+/// "2026-10-15T09:30:00Z add". Empty if another thread holds them.
+pub fn recent_operations() -> Vec<String> {
+    let operations = match RECENT_OPERATIONS.try_lock() {
+        Ok(operations) => operations,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return Vec::new(),
+    };
+    operations
+        .iter()
+        .map(|(at, name)| format!("{} {}", at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true), name))
+        .collect()
+}
+
+/// What a crash report records
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub time: DateTime<Utc>,
+    pub message: String,
+    /// File and line of the panic
+    pub location: Option<String>,
+    pub thread: String,
+    pub backtrace: String,
+    pub operations: Vec<String>,
+    /// Rustloader, OS and dependency versions
+    pub versions: Vec<(String, String)>,
+}
+
+impl CrashReport {
+    /// Describe a panic as it is being handled
+    #[allow(deprecated)]
+    pub fn capture(info: &PanicInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "(no message)".to_string());
+        Self {
+            time: Utc::now(),
+            message,
+            location: info.location().map(|location| format!("{}:{}", location.file(), location.line())),
+            thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+            backtrace: Backtrace::force_capture().to_string(),
+            operations: recent_operations(),
+            versions: versions(),
+        }
+    }
+
+    /// The report as text
+    pub fn render(&self) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "Rustloader crash report");
+        let _ = writeln!(report, "Time:     {}", self.time.to_rfc3339());
+        let _ = writeln!(report, "Panic:    {}", self.message);
+        let _ = writeln!(report, "Location: {}", self.location.as_deref().unwrap_or("unknown"));
+        let _ = writeln!(report, "Thread:   {}", self.thread);
+        let _ = writeln!(report, "\nVersions:");
+        for (name, version) in &self.versions {
+            let _ = writeln!(report, "  {}: {}", name, version);
+        }
+        let _ = writeln!(report, "\nLast queue operations (oldest first):");
+        if self.operations.is_empty() {
+            let _ = writeln!(report, "  (none)");
+        }
+        for operation in &self.operations {
+            let _ = writeln!(report, "  {}", operation);
+        }
+        let _ = writeln!(report, "\nBacktrace:\n{}", self.backtrace);
+        report
+    }
+
+    /// Save the report in the crash directory, returning its path
+    pub fn write(&self) -> Result<PathBuf, AppError> {
+        let dir = crash_dir()?;
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("crash-{}-{}.txt", self.time.format("%Y%m%d-%H%M%S"), std::process::id()));
+        fs::write(&path, self.render())?;
+        Ok(path)
+    }
+}
+
+/// Where crash reports are saved
+pub fn crash_dir() -> Result<PathBuf, AppError> {
+    Ok(data_dir()?.join("crashes"))
+}
+
+/// Rustloader's version, the platform, and the last checked dependencies
+fn versions() -> Vec<(String, String)> {
+    let mut versions = vec![
+        ("rustloader".to_string(), VERSION.to_string()),
+        (
+            "platform".to_string(),
+            format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        ),
+    ];
+    match last_dependency_status() {
+        Some(dependencies) => versions.extend(
+            dependencies
+                .into_iter()
+                .map(|dependency| (dependency.name, dependency.version)),
+        ),
+        None => versions.push(("dependencies".to_string(), "not checked in this session".to_string())),
+    }
+    versions
+}
+
+/// Replace the panic hook with one that saves a crash report and the queue
+/// state first. The previous hook still runs afterwards.
+pub fn install_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::capture(info);
+        match report.write() {
+            Ok(path) => eprintln!("Rustloader crashed. A crash report was saved to {}", path.display()),
+            Err(e) => eprintln!("Rustloader crashed, and the crash report could not be saved: {}", e),
+        }
+        if let Err(e) = flush_download_queue_blocking(SHUTDOWN_FLUSH_TIMEOUT) {
+            eprintln!("The download queue could not be saved: {}", e);
+        }
+        previous_hook(info);
+    }));
+}
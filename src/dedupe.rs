@@ -0,0 +1,244 @@
+// src/dedupe.rs
+//! Detection of downloads that are byte-identical to a file already in the
+//! library, and replacement of the copy with a hardlink to the original.
+//!
+//! Candidates are matched on size first, so only files of the same length
+//! are ever hashed.
+
+use crate::cleanup::default_download_dirs;
+use crate::config::load_config;
+use crate::download_manager::get_data_dir;
+use crate::error::AppError;
+use log::{debug, info};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const STATS_FILE: &str = "dedupe_stats.json";
+
+/// What to do when a finished download duplicates an existing file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateHandling {
+    /// Don't look for duplicates
+    #[default]
+    Off,
+    /// Report duplicates and ask before linking (queued downloads only report)
+    Ask,
+    /// Replace duplicates with hardlinks automatically
+    Hardlink,
+}
+
+impl DuplicateHandling {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicateHandling::Off => "off",
+            DuplicateHandling::Ask => "ask",
+            DuplicateHandling::Hardlink => "hardlink",
+        }
+    }
+}
+
+/// How a download is named when a file with its title already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateNaming {
+    /// "Title (1).mp4", "Title (2).mp4", ...
+    #[default]
+    Counter,
+    /// "Title (20240131-184500).mp4"
+    Timestamp,
+    /// "Uploader - Title.mp4"
+    Uploader,
+}
+
+impl DuplicateNaming {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "counter" => Ok(DuplicateNaming::Counter),
+            "timestamp" => Ok(DuplicateNaming::Timestamp),
+            "uploader" => Ok(DuplicateNaming::Uploader),
+            _ => Err(AppError::ValidationError(format!(
+                "Unknown duplicate naming '{}', expected counter, timestamp or uploader",
+                value
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicateNaming::Counter => "counter",
+            DuplicateNaming::Timestamp => "timestamp",
+            DuplicateNaming::Uploader => "uploader",
+        }
+    }
+}
+
+/// Space reclaimed by deduplication so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupeStats {
+    pub files_linked: u64,
+    pub bytes_saved: u64,
+}
+
+/// Folders searched for existing copies: the configured download directory
+/// and the default download locations
+pub fn library_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = load_config()
+        .ok()
+        .and_then(|config| config.download_dir)
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    for dir in default_download_dirs() {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// SHA-256 of a file's contents
+pub fn file_hash(path: &Path) -> Result<Vec<u8>, AppError> {
+    let mut file = File::open(path)?;
+    let mut context = digest::Context::new(&digest::SHA256);
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+    Ok(context.finish().as_ref().to_vec())
+}
+
+/// Whether two paths already refer to the same file on disk
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &fs::Metadata, _b: &fs::Metadata) -> bool {
+    false
+}
+
+/// Look in `dirs` (not recursively) for a file with the same contents as
+/// `path`. Files that are already links to `path` don't count.
+pub fn find_duplicate(path: &Path, dirs: &[PathBuf]) -> Result<Option<PathBuf>, AppError> {
+    let metadata = fs::metadata(path)?;
+    if metadata.len() == 0 {
+        return Ok(None);
+    }
+    let canonical = path.canonicalize()?;
+    let mut hash = None;
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let candidate = entry.path();
+            let Ok(candidate_metadata) = fs::metadata(&candidate) else {
+                continue;
+            };
+            if !candidate_metadata.is_file()
+                || candidate_metadata.len() != metadata.len()
+                || same_file(&metadata, &candidate_metadata)
+                || candidate.canonicalize().ok().as_ref() == Some(&canonical)
+            {
+                continue;
+            }
+
+            if hash.is_none() {
+                hash = Some(file_hash(path)?);
+            }
+            if file_hash(&candidate).ok() == hash {
+                debug!("{:?} is identical to {:?}", path, candidate);
+                return Ok(Some(candidate));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Replace `duplicate` with a hardlink to `original` and return the bytes
+/// saved. The duplicate is only removed once the link exists.
+pub fn replace_with_hardlink(duplicate: &Path, original: &Path) -> Result<u64, AppError> {
+    let size = fs::metadata(duplicate)?.len();
+    let temp_path = duplicate.with_extension("rustloader-link");
+
+    let _ = fs::remove_file(&temp_path);
+    fs::hard_link(original, &temp_path).map_err(|e| {
+        AppError::General(format!("Could not link {:?} to {:?}: {}", duplicate, original, e))
+    })?;
+    if let Err(e) = fs::rename(&temp_path, duplicate) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(AppError::IoError(e));
+    }
+
+    info!("Replaced {:?} with a hardlink to {:?}", duplicate, original);
+    Ok(size)
+}
+
+/// Where the deduplication savings are recorded
+pub fn stats_path() -> PathBuf {
+    get_data_dir().join(STATS_FILE)
+}
+
+/// Space saved by deduplication so far
+pub fn load_dedupe_stats() -> Result<DedupeStats, AppError> {
+    let path = stats_path();
+    if !path.exists() {
+        return Ok(DedupeStats::default());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Add a linked file to the stats
+pub fn record_saving(bytes: u64) -> Result<DedupeStats, AppError> {
+    let mut stats = load_dedupe_stats().unwrap_or_default();
+    stats.files_linked += 1;
+    stats.bytes_saved += bytes;
+    fs::write(stats_path(), serde_json::to_string_pretty(&stats)?)?;
+    Ok(stats)
+}
+
+/// Link `duplicate` to `original` and record the space saved
+pub fn deduplicate(duplicate: &Path, original: &Path) -> Result<u64, AppError> {
+    let saved = replace_with_hardlink(duplicate, original)?;
+    record_saving(saved)?;
+    Ok(saved)
+}
+
+/// An existing copy of a finished download
+#[derive(Debug, Clone)]
+pub struct DuplicateMatch {
+    pub original: PathBuf,
+    /// Bytes saved when the download was replaced with a hardlink
+    pub bytes_saved: Option<u64>,
+}
+
+/// Check a finished download against the library and, in hardlink mode,
+/// link it to the existing copy
+pub fn dedupe_download(path: &Path) -> Result<Option<DuplicateMatch>, AppError> {
+    let handling = load_config().map(|config| config.duplicate_handling).unwrap_or_default();
+    if handling == DuplicateHandling::Off {
+        return Ok(None);
+    }
+
+    let Some(original) = find_duplicate(path, &library_dirs())? else {
+        return Ok(None);
+    };
+    let bytes_saved = match handling {
+        DuplicateHandling::Hardlink => Some(deduplicate(path, &original)?),
+        _ => None,
+    };
+    Ok(Some(DuplicateMatch { original, bytes_saved }))
+}
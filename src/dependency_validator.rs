@@ -4,34 +4,78 @@
 //! like yt-dlp and ffmpeg, checking versions, binary integrity, and known vulnerabilities.
 
 use crate::error::AppError;
+use crate::ffmpeg_features::{unsupported_features, ALL_FEATURES};
+use crate::gatekeeper::prepare_installed_binary;
+use crate::process::{can_run, is_on_path, CommandSpec};
 use base64::{engine::general_purpose, Engine as _};
 use colored::*;
 use log::{debug, info, trace, warn};
+use once_cell::sync::Lazy;
 use ring::digest;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::{Output, Stdio};
+use std::sync::Mutex;
 
-// Minimum acceptable versions for dependencies
+// Minimum acceptable versions for dependencies. Features with newer ffmpeg
+// requirements are checked when used, see `ffmpeg_features`.
 pub const MIN_YTDLP_VERSION: &str = "2023.07.06";
 pub const MIN_FFMPEG_VERSION: &str = "4.0.0";
 
+/// Where the latest yt-dlp release assets are downloaded from
+const YTDLP_RELEASE_URL: &str = "https://github.com/yt-dlp/yt-dlp/releases/latest/download";
+
 // Known vulnerable versions to warn about
 const VULNERABLE_YTDLP_VERSIONS: [&str; 2] = ["2022.05.18", "2022.08.14"];
 const VULNERABLE_FFMPEG_VERSIONS: [&str; 2] = ["4.3.1", "4.4.2"];
 
+/// Whether the update triggered by extractor failures succeeded, once tried this run
+static EXTRACTOR_UPDATE: Lazy<Mutex<Option<bool>>> = Lazy::new(|| Mutex::new(None));
+
+/// Dependencies shown in the GUI's health indicator
+pub const TRACKED_DEPENDENCIES: [&str; 2] = ["yt-dlp", "ffmpeg"];
+
+/// The latest [`recheck_dependencies`] result
+static DEPENDENCY_STATUS: Lazy<Mutex<Option<Vec<DependencyInfo>>>> = Lazy::new(|| Mutex::new(None));
+
 #[allow(dead_code)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DependencyInfo {
     pub name: String,
     pub version: String,
     pub path: String,
     pub hash: Option<String>,
+    /// Whether a usable binary was found
+    pub installed: bool,
     pub is_min_version: bool,
     pub is_vulnerable: bool,
 }
 
+impl DependencyInfo {
+    /// A dependency found at `path` whose version couldn't be read
+    fn unverified(name: &str, path: String) -> Self {
+        let installed = !path.is_empty() && !path.starts_with("__continuing_without_");
+        Self {
+            name: name.to_string(),
+            version: "unknown".to_string(),
+            path,
+            hash: None,
+            installed,
+            is_min_version: false,
+            is_vulnerable: false,
+        }
+    }
+}
+
+/// Run a command and collect its output, or `None` if it was refused or
+/// couldn't be started
+fn run_output(spec: &CommandSpec) -> Option<Output> {
+    spec.to_command().ok()?.output().ok()
+}
+
 /// Get the installation path for a dependency
 /// 
 /// This function tries multiple strategies to locate a dependency:
@@ -41,39 +85,29 @@ pub struct DependencyInfo {
 /// 4. For ffmpeg, try platform-specific detection
 fn get_dependency_path(name: &str) -> Result<String, AppError> {
     // First try using system path tools
-    #[cfg(target_os = "windows")]
-    let search_commands = vec!["where"];
-
-    #[cfg(not(target_os = "windows"))]
-    let search_commands = vec!["which"];
-
-    for command in &search_commands {
-        if let Ok(output) = Command::new(command).arg(name).output() {
-            if output.status.success() {
-                let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if !path.is_empty() {
-                    info!("Found {} at path: {}", name, path);
-                    println!("{}: {}", format!("Found {} at", name).green(), path);
-                    
-                    // Double check that this path actually works
-                    let version_cmd = if name == "ffmpeg" { "-version" } else { "--version" };
-                    let mut version_check = Command::new(&path);
-                    version_check.arg(version_cmd);
-                    
-                    if version_check.output().is_ok() {
-                        debug!("Verified {} is executable at: {}", name, path);
-                        return Ok(path);
-                    }
-                    
-                    debug!("Path exists but command not executable: {}", path);
+    if let Some(output) = run_output(&CommandSpec::locate(name)) {
+        if output.status.success() {
+            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !path.is_empty() {
+                info!("Found {} at path: {}", name, path);
+                println!("{}: {}", format!("Found {} at", name).green(), path);
+                
+                // Double check that this path actually works
+                let version_cmd = if name == "ffmpeg" { "-version" } else { "--version" };
+                
+                if can_run(&path, version_cmd) {
+                    debug!("Verified {} is executable at: {}", name, path);
+                    return Ok(path);
                 }
+                
+                debug!("Path exists but command not executable: {}", path);
             }
         }
     }
 
     // Try calling the program directly (it might be in PATH)
     let version_arg = if name == "ffmpeg" { "-version" } else { "--version" };
-    if Command::new(name).arg(version_arg).output().is_ok() {
+    if can_run(name, version_arg) {
         info!("{} is available directly in PATH", name);
         println!("{}", format!("{} is available in PATH", name).green());
         return Ok(name.to_string());
@@ -331,7 +365,7 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
         for path in common_paths {
             if Path::new(&path).exists() {
                 debug!("Testing common path: {}", path);
-                if Command::new(&path).arg("-version").output().is_ok() {
+                if can_run(&path, "-version") {
                     info!("Found {} at common location: {}", name, path);
                     println!("{}: {}", format!("Found {} at", name).green(), path);
                     return Ok(path.to_string());
@@ -368,10 +402,6 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
                 // Container package managers
                 ("flatpak", vec!["info", "org.ffmpeg.FFmpeg"], r".*"),
                 ("snap", vec!["info", "ffmpeg"], r".*"),
-
-                // Universal package managers
-                ("which", vec!["ffmpeg"], r".*"),
-                ("type", vec!["-p", "ffmpeg"], r".*"),
             ];
             
             // Store detected distro and version for more intelligent fallbacks
@@ -399,10 +429,10 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
             let mut matching_paths = Vec::new();
             
             for (pkg_cmd, args, path_pattern) in package_manager_queries.iter() {
-                if Command::new(pkg_cmd).arg("--version").output().is_ok() {
+                if can_run(pkg_cmd, "--version") {
                     debug!("Found package manager: {}", pkg_cmd);
 
-                    if let Ok(output) = Command::new(pkg_cmd).args(args).output() {
+                    if let Some(output) = run_output(CommandSpec::package_manager(pkg_cmd).args(args)) {
                         if output.status.success() {
                             let output_str = String::from_utf8_lossy(&output.stdout);
                             let stderr_str = String::from_utf8_lossy(&output.stderr);
@@ -418,10 +448,6 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
                                                 // These list the full path
                                                 line.trim().split_whitespace().last().unwrap_or(line).trim()
                                             },
-                                            "which" | "type" => {
-                                                // These output just the path
-                                                line.trim()
-                                            },
                                             "apt-file" | "dnf" => {
                                                 // These output package: path format
                                                 if let Some(idx) = line.find(':') {
@@ -435,7 +461,7 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
                                         
                                         // Verify the path exists and is executable
                                         if Path::new(path).exists() {
-                                            if Command::new(path).arg("-version").output().is_ok() {
+                                            if can_run(path, "-version") {
                                                 info!("Found {} using package manager {}: {}", name, pkg_cmd, path);
                                                 
                                                 // If this matches our detected distro, return immediately
@@ -500,8 +526,8 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
             };
             
             for (cmd, args, purpose) in package_status_checks {
-                if Command::new(cmd).arg("--version").output().is_ok() {
-                    if let Ok(output) = Command::new(cmd).args(&args).output() {
+                if can_run(cmd, "--version") {
+                    if let Some(output) = run_output(CommandSpec::package_manager(cmd).args(&args)) {
                         let stdout = String::from_utf8_lossy(&output.stdout);
                         let stderr = String::from_utf8_lossy(&output.stderr);
                         
@@ -544,7 +570,7 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
             
             for (alt_name, version_arg) in alternatives {
                 debug!("Checking alternative: {}", alt_name);
-                if Command::new(alt_name).arg(version_arg).output().is_ok() {
+                if can_run(alt_name, version_arg) {
                     info!("Found alternative {} which appears to be working", alt_name);
                     println!("{}: {}", "Found alternative".green(), alt_name);
                     return Ok(alt_name.to_string());
@@ -559,7 +585,7 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
             ];
             
             for (app_name, command, args, _version_pattern) in embedded_locations {
-                if Command::new(command).args(args).output().is_ok() {
+                if can_run(command, args[0]) {
                     info!("Found {} which may include ffmpeg capabilities", app_name);
                     println!(
                         "{}",
@@ -584,7 +610,7 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
             // For yt-dlp, check for youtube-dl as a fallback
             println!("{}", "Checking for youtube-dl as a fallback...".yellow());
             
-            if Command::new("youtube-dl").arg("--version").output().is_ok() {
+            if can_run("youtube-dl", "--version") {
                 info!("Found youtube-dl which can be used as a fallback");
                 println!("{}", "Found youtube-dl which can be used as a fallback. Note that some features may not work correctly.".yellow());
                 
@@ -606,16 +632,16 @@ fn get_dependency_path(name: &str) -> Result<String, AppError> {
             
             for alt in alternatives {
                 let parts: Vec<&str> = alt.split_whitespace().collect();
-                if parts.len() > 1 {
-                    // For commands with arguments
-                    if Command::new(parts[0]).args(&parts[1..]).arg("--version").output().is_ok() {
+                if let [python, "-m", module] = parts[..] {
+                    // Run as a Python module
+                    if run_output(CommandSpec::python_module(python, module).flag("--version")).is_some() {
                         info!("Found alternative {} which appears to be working", alt);
                         println!("{}: {}", "Found alternative".green(), alt);
                         return Ok(alt.to_string());
                     }
                 } else {
                     // For simple commands
-                    if Command::new(alt).arg("--version").output().is_ok() {
+                    if can_run(alt, "--version") {
                         info!("Found alternative {} which appears to be working", alt);
                         println!("{}: {}", "Found alternative".green(), alt);
                         return Ok(alt.to_string());
@@ -781,7 +807,8 @@ fn parse_version(output: &str, name: &str) -> String {
         })
 }
 
-fn is_minimum_version(version: &str, min_version: &str) -> bool {
+/// Whether dotted `version` is at least `min_version`
+pub fn is_minimum_version(version: &str, min_version: &str) -> bool {
     let version_parts: Vec<u32> = version.split('.').filter_map(|s| s.parse().ok()).collect();
     let min_parts: Vec<u32> = min_version.split('.').filter_map(|s| s.parse().ok()).collect();
 
@@ -814,17 +841,13 @@ pub fn get_dependency_info(name: &str) -> Result<DependencyInfo, AppError> {
             )
             .yellow()
         );
-        return Ok(DependencyInfo {
-            name: name.to_string(),
-            version: "unknown".to_string(),
-            path,
-            hash: None,
-            is_min_version: false,
-            is_vulnerable: false,
-        });
+        return Ok(DependencyInfo::unverified(name, path));
     }
 
-    let output = match Command::new(&path).arg("--version").output() {
+    let output = match CommandSpec::version_probe(&path, "--version")
+        .to_command()
+        .and_then(|mut command| command.output().map_err(AppError::IoError))
+    {
         Ok(o) => o,
         Err(e) => {
             println!(
@@ -832,14 +855,7 @@ pub fn get_dependency_info(name: &str) -> Result<DependencyInfo, AppError> {
                 format!("Warning: Failed to get {} version", name).yellow(),
                 e
             );
-            return Ok(DependencyInfo {
-                name: name.to_string(),
-                version: "unknown".to_string(),
-                path,
-                hash: None,
-                is_min_version: false,
-                is_vulnerable: false,
-            });
+            return Ok(DependencyInfo::unverified(name, path));
         }
     };
 
@@ -848,14 +864,7 @@ pub fn get_dependency_info(name: &str) -> Result<DependencyInfo, AppError> {
             "{}",
             format!("Warning: {} version check failed, but continuing", name).yellow()
         );
-        return Ok(DependencyInfo {
-            name: name.to_string(),
-            version: "unknown".to_string(),
-            path,
-            hash: None,
-            is_min_version: false,
-            is_vulnerable: false,
-        });
+        return Ok(DependencyInfo::unverified(name, path));
     }
 
     let version_output = String::from_utf8_lossy(&output.stdout).to_string();
@@ -887,11 +896,48 @@ pub fn get_dependency_info(name: &str) -> Result<DependencyInfo, AppError> {
         version,
         path,
         hash,
+        installed: true,
         is_min_version,
         is_vulnerable,
     })
 }
 
+/// Check the tracked dependencies again, e.g. after an install, and
+/// remember the result for [`dependency_status`]
+#[allow(dead_code)]
+pub fn recheck_dependencies() -> Vec<DependencyInfo> {
+    let status: Vec<DependencyInfo> = TRACKED_DEPENDENCIES
+        .iter()
+        .map(|name| {
+            get_dependency_info(name).unwrap_or_else(|e| {
+                warn!("Could not check {}: {}", name, e);
+                DependencyInfo::unverified(name, String::new())
+            })
+        })
+        .collect();
+    *DEPENDENCY_STATUS.lock().unwrap() = Some(status.clone());
+    status
+}
+
+/// The latest dependency check, if there has been one, without waiting for
+/// a check that is running; safe to call while handling a panic
+pub fn last_dependency_status() -> Option<Vec<DependencyInfo>> {
+    match DEPENDENCY_STATUS.try_lock() {
+        Ok(status) => status.clone(),
+        Err(std::sync::TryLockError::Poisoned(status)) => status.into_inner().clone(),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
+/// The latest dependency check, checking now if there hasn't been one
+#[allow(dead_code)]
+pub fn dependency_status() -> Vec<DependencyInfo> {
+    if let Some(status) = DEPENDENCY_STATUS.lock().unwrap().clone() {
+        return status;
+    }
+    recheck_dependencies()
+}
+
 /// Checks if ffmpeg is available and usable on the system
 ///
 /// This function uses multiple strategies to check for a working ffmpeg:
@@ -902,11 +948,7 @@ pub fn get_dependency_info(name: &str) -> Result<DependencyInfo, AppError> {
 /// Returns true if a working ffmpeg is found, false otherwise
 pub fn is_ffmpeg_available() -> bool {
     // First, try the direct command approach - fastest check for when it's in PATH
-    if std::process::Command::new("ffmpeg")
-        .arg("-version")
-        .output()
-        .is_ok()
-    {
+    if can_run("ffmpeg", "-version") {
         info!("ffmpeg is available in PATH");
         println!("{}", "ffmpeg is available in PATH".green());
         return true;
@@ -918,11 +960,7 @@ pub fn is_ffmpeg_available() -> bool {
             // If the path doesn't contain this marker string, we found a valid path
             if !path.starts_with("__continuing_without_") {
                 // Double verify that this path works by running a version check
-                if std::process::Command::new(&path)
-                    .arg("-version")
-                    .output()
-                    .is_ok() 
-                {
+                if can_run(&path, "-version") {
                     info!("Found working ffmpeg at: {}", path);
                     println!("{}: {}", "Found working ffmpeg at".green(), path);
                     return true;
@@ -980,12 +1018,7 @@ pub fn is_ffmpeg_available() -> bool {
 
     // Check all the common paths we've collected
     for path in common_paths {
-        if std::path::Path::new(&path).exists() && 
-           std::process::Command::new(&path)
-               .arg("-version")
-               .output()
-               .is_ok() 
-        {
+        if std::path::Path::new(&path).exists() && can_run(&path, "-version") {
             info!("Found working ffmpeg at common path: {}", path);
             println!("{}: {}", "Found working ffmpeg at".green(), path);
             return true;
@@ -993,20 +1026,12 @@ pub fn is_ffmpeg_available() -> bool {
     }
 
     // One final attempt using system commands to locate ffmpeg
-    #[cfg(target_os = "windows")]
-    let which_cmd = "where";
-    #[cfg(not(target_os = "windows"))]
-    let which_cmd = "which";
-
-    if let Ok(output) = std::process::Command::new(which_cmd).arg("ffmpeg").output() {
+    if let Some(output) = run_output(&CommandSpec::locate("ffmpeg")) {
         if output.status.success() {
             let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
             if !path.is_empty() && 
                std::path::Path::new(&path).exists() && 
-               std::process::Command::new(&path)
-                   .arg("-version")
-                   .output()
-                   .is_ok() 
+               can_run(&path, "-version")
             {
                 info!("Found working ffmpeg using system path tool: {}", path);
                 println!("{}: {}", "Found working ffmpeg using system path tool at".green(), path);
@@ -1068,6 +1093,14 @@ pub fn validate_dependencies() -> Result<HashMap<String, DependencyInfo>, AppErr
                         info.version, 
                         MIN_FFMPEG_VERSION);
                 }
+                for feature in unsupported_features(&info.version, ALL_FEATURES) {
+                    println!(
+                        "{}: {} needs ffmpeg {} or newer",
+                        "NOTE".blue(),
+                        feature.description(),
+                        feature.min_version()
+                    );
+                }
                 if info.is_vulnerable {
                     println!(
                         "{}: Version {} has known vulnerabilities",
@@ -1115,16 +1148,41 @@ pub fn validate_dependencies() -> Result<HashMap<String, DependencyInfo>, AppErr
     Ok(results)
 }
 
+/// Upgrade yt-dlp through pip, for installs that `yt-dlp --update` refuses to touch
+fn upgrade_ytdlp_with_pip() -> bool {
+    for cmd in ["pip3", "python3", "python"] {
+        if !can_run(cmd, "--version") {
+            continue;
+        }
+        let mut spec = CommandSpec::pip(cmd);
+        spec.args(&["install", "--user", "--upgrade", "yt-dlp"]);
+        debug!("Trying to upgrade yt-dlp with: {}", spec.program());
+        match spec.to_command().and_then(|mut command| Ok(command.output()?)) {
+            Ok(output) if output.status.success() => return true,
+            Ok(output) => debug!("Upgrade failed: {}", String::from_utf8_lossy(&output.stderr)),
+            Err(e) => debug!("Error running {}: {}", cmd, e),
+        }
+    }
+    false
+}
+
 pub fn update_ytdlp() -> Result<(), AppError> {
     println!("{}", "Updating yt-dlp to latest version...".blue());
-    let output = Command::new("yt-dlp")
-        .arg("--update")
+    let output = CommandSpec::ytdlp()
+        .flag("--update")
+        .to_command()?
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
         .map_err(AppError::IoError)?;
 
-    if output.success() {
+    // pip and package-manager installs can't update themselves
+    let updated = output.success() || {
+        println!("{}", "yt-dlp can't update itself, trying pip...".yellow());
+        upgrade_ytdlp_with_pip()
+    };
+
+    if updated {
         match get_dependency_info("yt-dlp") {
             Ok(info) => {
                 println!("Updated yt-dlp version: {}", info.version);
@@ -1160,6 +1218,26 @@ pub fn update_ytdlp() -> Result<(), AppError> {
     }
 }
 
+/// Update yt-dlp after a download failed because a site extractor looks
+/// outdated. The update runs at most once per run; concurrent callers wait
+/// for it and share its result.
+pub fn update_ytdlp_once() -> Result<(), AppError> {
+    let mut attempted = EXTRACTOR_UPDATE.lock().unwrap();
+    let updated = *attempted.get_or_insert_with(|| match update_ytdlp() {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("yt-dlp update failed: {}", e);
+            false
+        }
+    });
+
+    if updated {
+        Ok(())
+    } else {
+        Err(AppError::General("yt-dlp could not be updated".to_string()))
+    }
+}
+
 #[allow(dead_code)]
 pub fn verify_dependency_integrity(name: &str) -> Result<bool, AppError> {
     println!("Verifying integrity of {}", name);
@@ -1184,8 +1262,9 @@ pub fn check_rust_updates() -> Result<(), AppError> {
         println!("{}", "Skipping Rust update check in release mode.".blue());
         return Ok(());
     }
-    if !Command::new("rustup")
-        .arg("--version")
+    if !CommandSpec::rustup()
+        .flag("--version")
+        .to_command()?
         .status()
         .map_err(AppError::IoError)?
         .success()
@@ -1196,8 +1275,9 @@ pub fn check_rust_updates() -> Result<(), AppError> {
         );
         return Ok(());
     }
-    let output = Command::new("rustup")
-        .arg("update")
+    let output = CommandSpec::rustup()
+        .flag("update")
+        .to_command()?
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
@@ -1260,6 +1340,63 @@ pub fn install_or_update_dependency(name: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// yt-dlp release asset for this platform
+fn ytdlp_release_asset() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "yt-dlp.exe"
+    } else if cfg!(target_os = "macos") {
+        "yt-dlp_macos"
+    } else {
+        "yt-dlp"
+    }
+}
+
+/// Expected SHA-256 (hex) of `asset` from a release's SHA2-256SUMS file
+pub fn parse_release_checksum(sums: &str, asset: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == asset && hash.len() == 64).then(|| hash.to_lowercase())
+    })
+}
+
+/// Download the latest yt-dlp release binary to `binary_path`, verify it
+/// against the published checksums, and make it runnable (on macOS this
+/// clears the quarantine attribute and checks the code signature)
+fn download_ytdlp_release(binary_path: &Path) -> Result<(), AppError> {
+    let asset = ytdlp_release_asset();
+    let status = CommandSpec::curl()
+        .flag("-fsSL")
+        .option("-o", binary_path.to_string_lossy())
+        .operand(format!("{}/{}", YTDLP_RELEASE_URL, asset))
+        .to_command()?
+        .status()?;
+    if !status.success() {
+        return Err(AppError::DownloadError(format!("Could not download {}", asset)));
+    }
+
+    let sums = CommandSpec::curl()
+        .flag("-fsSL")
+        .operand(format!("{}/SHA2-256SUMS", YTDLP_RELEASE_URL))
+        .to_command()?
+        .output()?;
+    let expected = parse_release_checksum(&String::from_utf8_lossy(&sums.stdout), asset);
+    let contents = std::fs::read(binary_path)?;
+    let actual: String = digest::digest(&digest::SHA256, &contents)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if expected.as_deref() != Some(actual.as_str()) {
+        let _ = std::fs::remove_file(binary_path);
+        warn!("Checksum mismatch for {}: expected {:?}, got {}", asset, expected, actual);
+        return Err(AppError::SecurityViolation);
+    }
+
+    prepare_installed_binary(binary_path)
+}
+
 fn install_ytdlp() -> Result<(), AppError> {
     println!("{}", "Installing yt-dlp...".blue());
     
@@ -1271,20 +1408,20 @@ fn install_ytdlp() -> Result<(), AppError> {
         // Primary methods (most reliable)
         ("pip3", &["install", "--user", "--upgrade", "yt-dlp"]),
         ("pip", &["install", "--user", "--upgrade", "yt-dlp"]),
-        ("python3", &["-m", "pip", "install", "--user", "--upgrade", "yt-dlp"]),
-        ("python", &["-m", "pip", "install", "--user", "--upgrade", "yt-dlp"]),
+        ("python3", &["install", "--user", "--upgrade", "yt-dlp"]),
+        ("python", &["install", "--user", "--upgrade", "yt-dlp"]),
         
         // Alternative methods (if primary fails)
-        ("python3", &["-m", "pip", "install", "--upgrade", "yt-dlp"]),
-        ("python", &["-m", "pip", "install", "--upgrade", "yt-dlp"]),
+        ("python3", &["install", "--upgrade", "yt-dlp"]),
+        ("python", &["install", "--upgrade", "yt-dlp"]),
     ];
     
     // Try each Python-based installation method
     for (cmd, args) in python_commands {
         debug!("Trying to install yt-dlp with: {} {}", cmd, args.join(" "));
-        if Command::new(cmd).arg("--version").output().is_ok() {
+        if can_run(cmd, "--version") {
             println!("Using {} to install yt-dlp...", cmd);
-            match Command::new(cmd).args(args).output() {
+            match CommandSpec::pip(cmd).args(args).to_command().and_then(|mut command| Ok(command.output()?)) {
                 Ok(output) => {
                     if output.status.success() {
                         success = true;
@@ -1326,7 +1463,7 @@ fn install_ytdlp() -> Result<(), AppError> {
             ];
             
             for (pkg_manager, args) in package_managers {
-                if Command::new(pkg_manager).arg("--version").output().is_ok() {
+                if can_run(pkg_manager, "--version") {
                     debug!("Trying to install yt-dlp with: {} {}", pkg_manager, args.join(" "));
                     
                     // Most package managers need sudo, except for some (snap, flatpak)
@@ -1334,33 +1471,22 @@ fn install_ytdlp() -> Result<(), AppError> {
                     
                     if need_sudo {
                         println!("Using sudo {} to install yt-dlp...", pkg_manager);
-                        match Command::new("sudo")
-                            .arg(pkg_manager)
-                            .args(&args)
-                            .output() {
-                                Ok(output) => {
-                                    if output.status.success() {
-                                        success = true;
-                                        println!("{}", "yt-dlp installed successfully.".green());
-                                        break;
-                                    }
-                                },
-                                Err(_) => { /* Continue to next method */ }
+                        if let Some(output) = run_output(CommandSpec::package_manager(pkg_manager).args(&args).as_root()) {
+                            if output.status.success() {
+                                success = true;
+                                println!("{}", "yt-dlp installed successfully.".green());
+                                break;
                             }
+                        }
                     } else {
                         println!("Using {} to install yt-dlp...", pkg_manager);
-                        match Command::new(pkg_manager)
-                            .args(&args)
-                            .output() {
-                                Ok(output) => {
-                                    if output.status.success() {
-                                        success = true;
-                                        println!("{}", "yt-dlp installed successfully.".green());
-                                        break;
-                                    }
-                                },
-                                Err(_) => { /* Continue to next method */ }
+                        if let Some(output) = run_output(CommandSpec::package_manager(pkg_manager).args(&args)) {
+                            if output.status.success() {
+                                success = true;
+                                println!("{}", "yt-dlp installed successfully.".green());
+                                break;
                             }
+                        }
                     }
                 }
             }
@@ -1375,39 +1501,28 @@ fn install_ytdlp() -> Result<(), AppError> {
             ];
             
             for (pkg_manager, args) in package_managers {
-                if Command::new(pkg_manager).arg("--version").output().is_ok() {
+                if can_run(pkg_manager, "--version") {
                     debug!("Trying to install yt-dlp with: {} {}", pkg_manager, args.join(" "));
                     
                     // MacPorts needs sudo, Homebrew doesn't
                     if pkg_manager == "port" {
                         println!("Using sudo {} to install yt-dlp...", pkg_manager);
-                        match Command::new("sudo")
-                            .arg(pkg_manager)
-                            .args(&args)
-                            .output() {
-                                Ok(output) => {
-                                    if output.status.success() {
-                                        success = true;
-                                        println!("{}", "yt-dlp installed successfully.".green());
-                                        break;
-                                    }
-                                },
-                                Err(_) => { /* Continue to next method */ }
+                        if let Some(output) = run_output(CommandSpec::package_manager(pkg_manager).args(&args).as_root()) {
+                            if output.status.success() {
+                                success = true;
+                                println!("{}", "yt-dlp installed successfully.".green());
+                                break;
                             }
+                        }
                     } else {
                         println!("Using {} to install yt-dlp...", pkg_manager);
-                        match Command::new(pkg_manager)
-                            .args(&args)
-                            .output() {
-                                Ok(output) => {
-                                    if output.status.success() {
-                                        success = true;
-                                        println!("{}", "yt-dlp installed successfully.".green());
-                                        break;
-                                    }
-                                },
-                                Err(_) => { /* Continue to next method */ }
+                        if let Some(output) = run_output(CommandSpec::package_manager(pkg_manager).args(&args)) {
+                            if output.status.success() {
+                                success = true;
+                                println!("{}", "yt-dlp installed successfully.".green());
+                                break;
                             }
+                        }
                     }
                 }
             }
@@ -1424,22 +1539,17 @@ fn install_ytdlp() -> Result<(), AppError> {
             ];
             
             for (pkg_manager, args) in package_managers {
-                if Command::new(pkg_manager).arg("--version").output().is_ok() {
+                if can_run(pkg_manager, "--version") {
                     debug!("Trying to install yt-dlp with: {} {}", pkg_manager, args.join(" "));
                     println!("Using {} to install yt-dlp...", pkg_manager);
                     
-                    match Command::new(pkg_manager)
-                        .args(&args)
-                        .output() {
-                            Ok(output) => {
-                                if output.status.success() {
-                                    success = true;
-                                    println!("{}", "yt-dlp installed successfully.".green());
-                                    break;
-                                }
-                            },
-                            Err(_) => { /* Continue to next method */ }
+                    if let Some(output) = run_output(CommandSpec::package_manager(pkg_manager).args(&args)) {
+                        if output.status.success() {
+                            success = true;
+                            println!("{}", "yt-dlp installed successfully.".green());
+                            break;
                         }
+                    }
                 }
             }
         }
@@ -1491,17 +1601,27 @@ fn install_ytdlp() -> Result<(), AppError> {
         // Output status message
         println!("Downloading yt-dlp to {}", binary_path);
         
-        // Recommend manual download and provide instructions
-        println!("{}", "Direct download not implemented yet.".yellow());
-        println!("{}", "Please download yt-dlp manually:".yellow());
-        println!("1. Visit: https://github.com/yt-dlp/yt-dlp/releases/latest");
-        println!("2. Download the appropriate binary for your platform");
-        println!("3. Save it to a directory in your PATH");
-        println!("4. Make it executable (chmod +x yt-dlp on Linux/macOS)");
+        match download_ytdlp_release(Path::new(&binary_path)) {
+            Ok(()) => {
+                println!("{}", "yt-dlp downloaded and verified.".green());
+                if !can_run("yt-dlp", "--version") {
+                    println!("{} {}", "Add this directory to your PATH:".yellow(), install_path);
+                }
+                success = true;
+            }
+            Err(e) => {
+                println!("{}: {}", "Direct download failed".red(), e);
+                println!("{}", "Please download yt-dlp manually:".yellow());
+                println!("1. Visit: https://github.com/yt-dlp/yt-dlp/releases/latest");
+                println!("2. Download the appropriate binary for your platform");
+                println!("3. Save it to a directory in your PATH");
+                println!("4. Make it executable (chmod +x yt-dlp on Linux/macOS)");
+            }
+        }
     }
     
     // Final check to verify installation
-    if success || Command::new("yt-dlp").arg("--version").output().is_ok() {
+    if success || can_run("yt-dlp", "--version") {
         // Success case - verify the installation
         match get_dependency_info("yt-dlp") {
             Ok(info) => {
@@ -1555,35 +1675,22 @@ fn install_ffmpeg() -> Result<(), AppError> {
     #[cfg(target_os = "macos")]
     {
         // First try Homebrew
-        if Command::new("which")
-            .arg("brew")
-            .stdout(Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false) 
-        {
+        if is_on_path("brew") {
             println!("{}", "Using Homebrew to install ffmpeg...".blue());
-            success = Command::new("brew")
-                .arg("install")
-                .arg("ffmpeg")
+            success = CommandSpec::package_manager("brew")
+                .args(&["install", "ffmpeg"])
+                .to_command()?
                 .status()
                 .map_err(AppError::IoError)?
                 .success();
         } 
         // If Homebrew isn't available or failed, try MacPorts
-        else if Command::new("which")
-            .arg("port")
-            .stdout(Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false) && !success 
-        {
+        else if is_on_path("port") && !success {
             println!("{}", "Using MacPorts to install ffmpeg...".blue());
-            let sudo_command = "sudo".to_string();
-            success = Command::new(&sudo_command)
-                .arg("port")
-                .arg("install")
-                .arg("ffmpeg")
+            success = CommandSpec::package_manager("port")
+                .args(&["install", "ffmpeg"])
+                .as_root()
+                .to_command()?
                 .status()
                 .map_err(AppError::IoError)?
                 .success();
@@ -1616,35 +1723,20 @@ fn install_ffmpeg() -> Result<(), AppError> {
         ];
         
         for (pm, args) in package_managers.iter() {
-            if Command::new("which")
-                .arg(pm)
-                .stdout(Stdio::null())
-                .status()
-                .map(|s| s.success())
-                .unwrap_or(false) && !success
-            {
+            if is_on_path(pm) && !success {
                 println!("Using {} to install ffmpeg...", pm);
                 
+                let mut spec = CommandSpec::package_manager(pm);
+                spec.args(args);
                 // We need sudo for most package managers, but not for snap or flatpak
-                let need_sudo = !["snap", "flatpak"].contains(&pm);
-                
-                if need_sudo {
-                    let sudo_command = "sudo".to_string();
-                    let pm_string = (*pm).to_string();
-                    success = Command::new(&sudo_command)
-                        .arg(&pm_string)
-                        .args(args)
-                        .status()
-                        .map_err(AppError::IoError)?
-                        .success();
-                } else {
-                    // Direct invocation for snap and flatpak
-                    success = Command::new(*pm)
-                        .args(args)
-                        .status()
-                        .map_err(AppError::IoError)?
-                        .success();
+                if !["snap", "flatpak"].contains(pm) {
+                    spec.as_root();
                 }
+                success = spec
+                    .to_command()?
+                    .status()
+                    .map_err(AppError::IoError)?
+                    .success();
                 
                 if success {
                     break;
@@ -1665,35 +1757,22 @@ fn install_ffmpeg() -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         // Try Chocolatey first
-        if Command::new("where")
-            .arg("choco")
-            .stdout(Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false) && !success
-        {
+        if is_on_path("choco") && !success {
             println!("{}", "Using Chocolatey to install ffmpeg...".blue());
-            success = Command::new("choco")
-                .arg("install")
-                .arg("ffmpeg")
-                .arg("-y")
+            success = CommandSpec::package_manager("choco")
+                .args(&["install", "ffmpeg", "-y"])
+                .to_command()?
                 .status()
                 .map_err(AppError::IoError)?
                 .success();
         }
         
         // Try Scoop if Chocolatey failed or isn't available
-        if Command::new("where")
-            .arg("scoop")
-            .stdout(Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false) && !success
-        {
+        if is_on_path("scoop") && !success {
             println!("{}", "Using Scoop to install ffmpeg...".blue());
-            success = Command::new("scoop")
-                .arg("install")
-                .arg("ffmpeg")
+            success = CommandSpec::package_manager("scoop")
+                .args(&["install", "ffmpeg"])
+                .to_command()?
                 .status()
                 .map_err(AppError::IoError)?
                 .success();
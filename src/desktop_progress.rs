@@ -0,0 +1,126 @@
+// src/desktop_progress.rs
+//! Download progress on the Linux desktop through the Unity LauncherEntry
+//! D-Bus API. Docks and task managers that implement it draw a progress
+//! bar and a count of running downloads on Rustloader's icon. These include
+//! Ubuntu Dock, Dash to Dock, KDE Plasma and Plank.
+//!
+//! The signal is sent with `gdbus`, so no D-Bus library is linked, and the
+//! dock matches it to the installed `rustloader.desktop` file. Built with the
+//! `platform-integration` feature.
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::download_map::{snapshot_items, DownloadMap};
+use crate::process::CommandSpec;
+use log::debug;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The desktop entry the dock shows progress on
+pub const APP_URI: &str = "application://rustloader.desktop";
+
+/// Object path the signal is sent from; docks only look at the app URI
+const OBJECT_PATH: &str = "/com/rustloader/LauncherEntry";
+
+/// How often the dock is updated while downloads run
+const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the dock shows: a badge with the number of running downloads and
+/// their combined progress from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LauncherProgress {
+    pub active: usize,
+    pub fraction: f64,
+}
+
+/// Combined progress of the running downloads, weighted by size when every
+/// size is known, or None when nothing is downloading
+pub fn launcher_progress(items: &[DownloadItem]) -> Option<LauncherProgress> {
+    let active: Vec<&DownloadItem> = items
+        .iter()
+        .filter(|item| item.status == DownloadStatus::Downloading)
+        .collect();
+    if active.is_empty() {
+        return None;
+    }
+
+    let total_bytes: u64 = active.iter().map(|item| item.total_bytes).sum();
+    let fraction = if total_bytes > 0 && active.iter().all(|item| item.total_bytes > 0) {
+        active.iter().map(|item| item.downloaded_bytes).sum::<u64>() as f64 / total_bytes as f64
+    } else {
+        active.iter().map(|item| item.progress).sum::<f64>() / active.len() as f64 / 100.0
+    };
+    Some(LauncherProgress {
+        active: active.len(),
+        fraction: fraction.clamp(0.0, 1.0),
+    })
+}
+
+/// The LauncherEntry properties for `progress` as GVariant text; None
+/// hides the bar and badge
+pub fn launcher_properties(progress: Option<LauncherProgress>) -> String {
+    match progress {
+        Some(progress) => format!(
+            "{{'progress': <{:.3}>, 'progress-visible': <true>, 'count': <int64 {}>, 'count-visible': <true>}}",
+            progress.fraction, progress.active
+        ),
+        None => "{'progress-visible': <false>, 'count-visible': <false>}".to_string(),
+    }
+}
+
+/// `gdbus` command emitting the LauncherEntry `Update` signal
+pub fn update_command(properties: &str) -> CommandSpec {
+    let mut spec = CommandSpec::system("gdbus");
+    spec.flag("emit")
+        .flag("--session")
+        .option("--object-path", OBJECT_PATH)
+        .option("--signal", "com.canonical.Unity.LauncherEntry.Update")
+        .operand(APP_URI)
+        .operand(properties);
+    spec
+}
+
+/// Keep the dock up to date with the queue's downloads. Does nothing off
+/// Linux; stops quietly when `gdbus` or the session bus isn't available.
+pub fn spawn_desktop_progress(downloads: Arc<RwLock<DownloadMap>>) {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(UPDATE_INTERVAL);
+        let mut shown = launcher_properties(None);
+        loop {
+            ticker.tick().await;
+            let items = snapshot_items(&downloads);
+            let properties = launcher_properties(launcher_progress(&items));
+            if properties == shown {
+                continue;
+            }
+
+            let mut command = match update_command(&properties).to_async_command() {
+                Ok(command) => command,
+                Err(e) => {
+                    debug!("Not sending desktop progress: {}", e);
+                    return;
+                }
+            };
+            let status = command
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .await;
+            match status {
+                Ok(status) if status.success() => shown = properties,
+                Ok(status) => {
+                    debug!("gdbus exited with {}, desktop progress is off", status);
+                    return;
+                }
+                Err(e) => {
+                    debug!("gdbus unavailable, desktop progress is off: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
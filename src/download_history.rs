@@ -0,0 +1,53 @@
+// src/download_history.rs
+//! Archive of finished downloads moved out of the live queue state.
+//!
+//! Completed, failed and canceled downloads older than the configured age
+//! are moved into the history database so the queue state stays small and
+//! quick to save. The archive is only read for history views (`stats sites`,
+//! `queue show`, `cleanup --scan`) and is sealed like the database's other
+//! entries when encryption at rest is on.
+
+use crate::download_manager::DownloadItem;
+use crate::download_map::DownloadMap;
+use crate::error::AppError;
+use crate::history::History;
+use chrono::{DateTime, Duration, Utc};
+use std::path::Path;
+
+/// Finished downloads are archived after this many days unless configured
+pub const DEFAULT_ARCHIVE_AFTER_DAYS: u32 = 30;
+
+/// Read the archived downloads, oldest first
+pub fn load_history(path: &Path) -> Result<Vec<DownloadItem>, AppError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    History::open(path)?.archived()
+}
+
+/// Add downloads to the archive. A download archived again (e.g. it was
+/// retried after being archived) replaces its older entry.
+pub fn append_history(path: &Path, items: &[DownloadItem]) -> Result<(), AppError> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    History::open(path)?.archive(items)
+}
+
+/// Take finished downloads that ended more than `max_age` before `now` out
+/// of `downloads`, returning them oldest first
+pub fn take_archivable(
+    downloads: &mut DownloadMap,
+    max_age: Duration,
+    now: DateTime<Utc>,
+) -> Vec<DownloadItem> {
+    let cutoff = now - max_age;
+    let ids: Vec<String> = downloads
+        .values()
+        .filter(|item| item.is_finished() && item.finished_at.unwrap_or(item.added_at) < cutoff)
+        .map(|item| item.id.clone())
+        .collect();
+    let mut taken: Vec<DownloadItem> = ids.iter().filter_map(|id| downloads.remove(id)).collect();
+    taken.sort_by_key(|item| item.finished_at.unwrap_or(item.added_at));
+    taken
+}
@@ -1,19 +1,54 @@
 // src/download_manager.rs
 // Enhanced download functionality with queue management, prioritization, persistence, and concurrency
 
-use crate::error::AppError;
-use chrono::{DateTime, Utc};
-use log::{debug, error};
+use crate::age_gate::{fallback_strategies, is_age_gated, AgeGateStrategy};
+use crate::chapters::resolve_chapters;
+use crate::circuit_breaker;
+use crate::completion::{is_batch_finished, is_pending, is_queue_busy, run_completion_action, CompletionAction, QueueSummary};
+use crate::config::load_config;
+use crate::crash_report::record_operation;
+use crate::dedupe::dedupe_download;
+use crate::dependency_validator::update_ytdlp_once;
+use crate::downloader::{check_network_connectivity, DownloadBackend, DownloadOutcome, MediaEstimate, ProgressCallback, StderrTail};
+use crate::download_map::{snapshot_items, DownloadMap};
+use crate::error::{AppError, ErrorReport};
+use crate::format_selector::{AudioQuality, FormatPreferences, VideoCodec};
+use crate::history::{history_path, History, HistoryEntry};
+use crate::http_headers::{HttpHeader, RequestHeaders};
+use crate::instance_lock::{ensure_queue_owner, other_instance};
+use crate::ipc::spawn_event_stream;
+use crate::media_naming::MediaNaming;
+use crate::notifications::{notify_queue_held, notify_queue_restored};
+use crate::paths::data_dir;
+use crate::playlist::fetch_playlist;
+use crate::power::{watch_power_events, PowerEvent};
+use crate::publish::publish_download;
+use crate::download_history::{append_history, load_history, take_archivable};
+use crate::region_overrides::RegionOverrides;
+use crate::retention::{delete_expired_files, plan_gc, validate_policies, GcAction, GcItem, RetentionPolicy};
+use crate::queue_order::sort_queue_ids;
+use crate::queue_schedule::{start_delay, ScheduleChange, TimeWindow};
+use crate::queue_repair::{plan_repairs, Repair, RepairAction, LOST_PROGRESS_MESSAGE};
+use crate::queue_undo::{get_queue_snapshots_path, load_snapshots, record_snapshot, restore_snapshot, save_snapshots, QueueSnapshot};
+use crate::transcode::TranscodeKind;
+use crate::vertical::VerticalOptions;
+use crate::webhooks::spawn_webhooks;
+use crate::security::{generate_hmac_signature, get_local_state_key, verify_hmac_signature};
+use crate::site_stats::{classify_failure, FailureKind};
+use crate::state_crypto::{read_state_file, write_atomic, write_state_file};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Local, Utc};
+use log::{debug, error, info, warn};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
-use std::time::Duration;
-use tokio::sync::{broadcast, mpsc, Semaphore};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, mpsc, Notify, OwnedSemaphorePermit, Semaphore};
 use tokio::task::JoinHandle;
-use dirs_next as dirs;
+use tracing::Instrument;
 
 /// Priority levels for downloads
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -30,6 +65,14 @@ impl Default for DownloadPriority {
     }
 }
 
+/// Downloads the queue runs at once unless configured otherwise
+pub const DEFAULT_CONCURRENT_DOWNLOADS: usize = 3;
+
+/// Number of speed samples kept per download (two minutes at one sample per second)
+pub const SPEED_HISTORY_CAPACITY: usize = 120;
+/// Minimum spacing between recorded speed samples
+const SPEED_SAMPLE_INTERVAL_MS: i64 = 1000;
+
 /// Current status of a download
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DownloadStatus {
@@ -47,6 +90,37 @@ impl Default for DownloadStatus {
     }
 }
 
+/// What to do with a download that runs past its deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadlineAction {
+    /// Stop it and mark it failed
+    #[default]
+    Fail,
+    /// Stop it and pause it, to resume later
+    Pause,
+}
+
+impl DeadlineAction {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.to_lowercase().as_str() {
+            "fail" => Ok(DeadlineAction::Fail),
+            "pause" => Ok(DeadlineAction::Pause),
+            _ => Err(AppError::ValidationError(format!(
+                "Invalid deadline action '{}', expected fail or pause",
+                value
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeadlineAction::Fail => "fail",
+            DeadlineAction::Pause => "pause",
+        }
+    }
+}
+
 /// A download item in the queue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadItem {
@@ -54,10 +128,25 @@ pub struct DownloadItem {
     pub id: String,
     /// URL to download from
     pub url: String,
+    /// Alternate URLs for the same content, tried in order if `url` fails
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Source the file was finally downloaded from, `url` or one of the mirrors
+    #[serde(default)]
+    pub downloaded_from: Option<String>,
+    /// How an age gate was passed, when the plain URL was refused
+    #[serde(default)]
+    pub age_gate_strategy: Option<AgeGateStrategy>,
     /// Title or name of the content
     pub title: Option<String>,
     /// Selected quality option
     pub quality: Option<String>,
+    /// Explicit yt-dlp format selection (overrides quality)
+    pub format_id: Option<String>,
+    /// Preferred audio quality for video downloads
+    pub audio_quality: Option<AudioQuality>,
+    /// Preferred video codec
+    pub video_codec: Option<VideoCodec>,
     /// Output format (mp3, mp4, etc.)
     pub format: String,
     /// Optional start time for clip extraction
@@ -74,10 +163,47 @@ pub struct DownloadItem {
     pub force_download: bool,
     /// Optional bitrate for audio
     pub bitrate: Option<String>,
+    /// Heavy post-processing to apply after download
+    pub transcode: Option<TranscodeKind>,
+    /// Extra ffmpeg options for post-processing, as option/value pairs
+    #[serde(default)]
+    pub ffmpeg_args: Vec<String>,
+    /// Keep the video and audio streams as separate files
+    #[serde(default)]
+    pub no_merge: bool,
+    /// Remove leading and trailing silence from extracted audio
+    #[serde(default)]
+    pub trim_silence: bool,
+    /// Padding or cropping to a target aspect ratio
+    #[serde(default)]
+    pub vertical: VerticalOptions,
+    /// Save under an ASCII transliteration of the title
+    #[serde(default)]
+    pub transliterate: bool,
+    /// Name episodes for media centers, e.g. "Show - S01E03 - Title.mp4"
+    #[serde(default)]
+    pub media_naming: Option<MediaNaming>,
+    /// Split outputs larger than this many bytes into numbered parts
+    #[serde(default)]
+    pub split_size: Option<u64>,
+    /// Only these chapters, by title or number, e.g. "Intro,2"
+    #[serde(default)]
+    pub chapters: Option<String>,
+    /// Custom user agent, instead of the default browser one
+    pub user_agent: Option<String>,
+    /// Extra HTTP headers such as Referer
+    #[serde(default)]
+    pub headers: Vec<HttpHeader>,
+    /// Player clients and geo-bypass country for region-specific availability
+    #[serde(default)]
+    pub region: RegionOverrides,
     /// Current download status
     pub status: DownloadStatus,
     /// Download priority
     pub priority: DownloadPriority,
+    /// Place among downloads of the same priority, set by a manual reorder
+    #[serde(default)]
+    pub queue_position: Option<u64>,
     /// When the download was added to queue
     pub added_at: DateTime<Utc>,
     /// When the download started
@@ -96,11 +222,104 @@ pub struct DownloadItem {
     pub retry_count: u32,
     /// Error message if failed
     pub error_message: Option<String>,
-    /// Output file path once completed
+    /// Code, category and retriability of the error, for the GUI's error toast
+    #[serde(default)]
+    pub error_report: Option<ErrorReport>,
+    /// Last lines of yt-dlp's stderr from the failed attempt
+    #[serde(default)]
+    pub error_context: Vec<String>,
+    /// Output file path once completed; the yt-dlp output template when the
+    /// file isn't known, e.g. for playlists
     pub output_path: Option<String>,
+    /// Downloader that completed the transfer
+    #[serde(default)]
+    pub backend: Option<DownloadBackend>,
+    /// Existing file in the library this download turned out to be identical to
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    /// Links to the output file created by publish rules
+    #[serde(default)]
+    pub published_to: Vec<String>,
+    /// Labels grouping downloads, e.g. for retention policies
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Wall-clock budget for each run of the download, in seconds
+    #[serde(default)]
+    pub deadline_secs: Option<u64>,
+    /// What happens when the deadline passes
+    #[serde(default)]
+    pub deadline_action: DeadlineAction,
+    /// How long a download paused at its deadline waits before resuming;
+    /// without it, it stays paused until resumed by hand
+    #[serde(default)]
+    pub resume_after_secs: Option<u64>,
+    /// When a download paused at its deadline is resumed automatically
+    #[serde(default)]
+    pub resume_at: Option<DateTime<Utc>>,
+    /// The queued download doesn't start before this time
+    #[serde(default)]
+    pub scheduled_at: Option<DateTime<Utc>>,
+    /// The queued download only starts inside this daily window
+    #[serde(default)]
+    pub start_window: Option<TimeWindow>,
+    /// Being played by `rustloader play` while it downloads
+    #[serde(default)]
+    pub streaming: bool,
     /// Unique token for cancellation and control
     #[serde(skip)]
     pub cancel_token: Option<broadcast::Sender<()>>,
+    /// Recent speed samples, kept in memory only
+    #[serde(skip)]
+    pub speed_history: SpeedHistory,
+    /// Paused because the system went to sleep, to be resumed on wake
+    #[serde(skip)]
+    pub paused_for_sleep: bool,
+    /// Paused by the circuit breaker, to be resumed when the network is back
+    #[serde(skip)]
+    pub paused_for_outage: bool,
+}
+
+/// A timestamped download speed measurement
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedSample {
+    pub timestamp: DateTime<Utc>,
+    /// Speed in bytes per second
+    pub speed: f64,
+}
+
+/// Ring buffer of recent speed samples for a download
+#[derive(Debug, Clone, Default)]
+pub struct SpeedHistory {
+    samples: VecDeque<SpeedSample>,
+}
+
+impl SpeedHistory {
+    /// Record a sample, dropping the oldest once full. Samples arriving
+    /// sooner than the sampling interval after the previous one are ignored.
+    /// Returns whether the sample was kept.
+    pub fn record(&mut self, sample: SpeedSample) -> bool {
+        if let Some(last) = self.samples.back() {
+            let elapsed = sample.timestamp.signed_duration_since(last.timestamp);
+            if elapsed.num_milliseconds() < SPEED_SAMPLE_INTERVAL_MS {
+                return false;
+            }
+        }
+
+        if self.samples.len() == SPEED_HISTORY_CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        true
+    }
+
+    /// Samples from oldest to newest
+    pub fn samples(&self) -> Vec<SpeedSample> {
+        self.samples.iter().copied().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
 }
 
 impl DownloadItem {
@@ -111,8 +330,14 @@ impl DownloadItem {
         Self {
             id,
             url: url.to_string(),
+            mirrors: Vec::new(),
+            downloaded_from: None,
+            age_gate_strategy: None,
             title: None,
             quality: None,
+            format_id: None,
+            audio_quality: None,
+            video_codec: None,
             format: format.to_string(),
             start_time: None,
             end_time: None,
@@ -121,8 +346,21 @@ impl DownloadItem {
             output_dir: None,
             force_download: false,
             bitrate: None,
+            transcode: None,
+            ffmpeg_args: Vec::new(),
+            no_merge: false,
+            trim_silence: false,
+            vertical: VerticalOptions::default(),
+            transliterate: false,
+            media_naming: None,
+            split_size: None,
+            chapters: None,
+            user_agent: None,
+            headers: Vec::new(),
+            region: RegionOverrides::default(),
             status: DownloadStatus::Queued,
             priority: DownloadPriority::Normal,
+            queue_position: None,
             added_at: Utc::now(),
             started_at: None,
             finished_at: None,
@@ -132,8 +370,24 @@ impl DownloadItem {
             speed: 0.0,
             retry_count: 0,
             error_message: None,
+            error_report: None,
+            error_context: Vec::new(),
             output_path: None,
+            backend: None,
+            duplicate_of: None,
+            published_to: Vec::new(),
+            tags: Vec::new(),
+            deadline_secs: None,
+            deadline_action: DeadlineAction::Fail,
+            resume_after_secs: None,
+            resume_at: None,
+            scheduled_at: None,
+            start_window: None,
+            streaming: false,
             cancel_token: None,
+            speed_history: SpeedHistory::default(),
+            paused_for_sleep: false,
+            paused_for_outage: false,
         }
     }
 
@@ -191,9 +445,8 @@ impl DownloadItem {
         }
     }
     
-    /// Update progress information
-    #[allow(dead_code)]
-    pub fn update_progress(&mut self, downloaded: u64, total: u64, speed: f64) {
+    /// Update progress information, returning whether a new speed sample was recorded
+    pub fn update_progress(&mut self, downloaded: u64, total: u64, speed: f64) -> bool {
         self.downloaded_bytes = downloaded;
         self.total_bytes = total;
         self.speed = speed;
@@ -201,6 +454,11 @@ impl DownloadItem {
         if total > 0 {
             self.progress = (downloaded as f64 / total as f64) * 100.0;
         }
+        
+        self.speed_history.record(SpeedSample {
+            timestamp: Utc::now(),
+            speed,
+        })
     }
     
     /// Mark download as started
@@ -224,6 +482,7 @@ impl DownloadItem {
         self.status = DownloadStatus::Failed;
         self.finished_at = Some(Utc::now());
         self.error_message = error;
+        self.error_report = None;
     }
     
     /// Mark download as paused
@@ -231,8 +490,30 @@ impl DownloadItem {
         self.status = DownloadStatus::Paused;
     }
     
+    /// Pause a download that ran past its deadline, scheduling it to
+    /// resume after `resume_after_secs` if set
+    pub fn mark_deadline_paused(&mut self, now: DateTime<Utc>) {
+        self.mark_paused();
+        self.speed = 0.0;
+        self.resume_at = self
+            .resume_after_secs
+            .map(|secs| now + chrono::Duration::seconds(secs as i64));
+    }
+    
+    /// Whether a paused download is due to resume automatically
+    pub fn is_resume_due(&self, now: DateTime<Utc>) -> bool {
+        self.is_paused() && self.resume_at.is_some_and(|at| at <= now)
+    }
+    
+    /// How long the download waits for its start time and window from
+    /// `now`, or None when it may start
+    pub fn start_delay<Tz: chrono::TimeZone>(&self, now: &DateTime<Tz>) -> Option<chrono::Duration> {
+        start_delay(self.scheduled_at, self.start_window, now)
+    }
+    
     /// Mark download as resumed
     pub fn mark_resumed(&mut self) {
+        self.resume_at = None;
         if self.status == DownloadStatus::Paused {
             self.status = DownloadStatus::Queued;
             if self.started_at.is_some() {
@@ -242,8 +523,42 @@ impl DownloadItem {
         }
     }
     
+    /// Audio and codec preferences for format selection
+    pub fn format_preferences(&self) -> FormatPreferences {
+        FormatPreferences {
+            audio_quality: self.audio_quality,
+            video_codec: self.video_codec,
+        }
+    }
+    
+    /// Custom user agent and headers to send with requests
+    pub fn request_headers(&self) -> RequestHeaders {
+        RequestHeaders {
+            user_agent: self.user_agent.clone(),
+            headers: self.headers.clone(),
+            // Rendered per run from the configured proxy
+            proxy: None,
+            extractor_args: self.age_gate_strategy.as_ref().and_then(AgeGateStrategy::extractor_args),
+            region: self.region.clone(),
+        }
+    }
+    
+    /// Reset a finished download so it can be queued again
+    pub fn mark_requeued(&mut self) {
+        self.status = DownloadStatus::Queued;
+        self.started_at = None;
+        self.finished_at = None;
+        self.error_message = None;
+        self.error_report = None;
+        self.error_context.clear();
+        self.speed = 0.0;
+        self.speed_history.clear();
+        self.backend = None;
+        self.duplicate_of = None;
+        self.published_to.clear();
+    }
+    
     /// Increment retry count
-    #[allow(dead_code)]
     pub fn increment_retry_count(&mut self) {
         self.retry_count += 1;
     }
@@ -264,7 +579,6 @@ impl DownloadItemBuilder {
     }
     
     /// Set the title
-    #[allow(dead_code)]
     pub fn title(mut self, title: Option<&str>) -> Self {
         self.item.title = title.map(|s| s.to_string());
         self
@@ -276,6 +590,19 @@ impl DownloadItemBuilder {
         self
     }
     
+    /// Set an explicit yt-dlp format selection
+    pub fn format_id(mut self, format_id: Option<&str>) -> Self {
+        self.item.format_id = format_id.map(|s| s.to_string());
+        self
+    }
+    
+    /// Set audio quality and codec preferences
+    pub fn format_preferences(mut self, preferences: FormatPreferences) -> Self {
+        self.item.audio_quality = preferences.audio_quality;
+        self.item.video_codec = preferences.video_codec;
+        self
+    }
+    
     /// Set time range
     pub fn time_range(mut self, start: Option<&str>, end: Option<&str>) -> Self {
         self.item.start_time = start.map(|s| s.to_string());
@@ -313,12 +640,100 @@ impl DownloadItemBuilder {
         self
     }
     
+    /// Set post-download transcode
+    pub fn transcode(mut self, transcode: Option<TranscodeKind>) -> Self {
+        self.item.transcode = transcode;
+        self
+    }
+    
+    /// Set custom ffmpeg post-processing options
+    pub fn ffmpeg_args(mut self, ffmpeg_args: &[String]) -> Self {
+        self.item.ffmpeg_args = ffmpeg_args.to_vec();
+        self
+    }
+    
+    /// Keep the video and audio streams as separate files
+    pub fn no_merge(mut self, no_merge: bool) -> Self {
+        self.item.no_merge = no_merge;
+        self
+    }
+    
+    /// Remove leading and trailing silence from extracted audio
+    pub fn trim_silence(mut self, trim_silence: bool) -> Self {
+        self.item.trim_silence = trim_silence;
+        self
+    }
+    
+    /// Set padding or cropping to a target aspect ratio
+    pub fn vertical(mut self, vertical: VerticalOptions) -> Self {
+        self.item.vertical = vertical;
+        self
+    }
+    
+    /// Save under an ASCII transliteration of the title
+    pub fn transliterate(mut self, transliterate: bool) -> Self {
+        self.item.transliterate = transliterate;
+        self
+    }
+    
+    /// Name episodes for media centers
+    pub fn media_naming(mut self, media_naming: Option<MediaNaming>) -> Self {
+        self.item.media_naming = media_naming;
+        self
+    }
+    
+    /// Split outputs larger than `split_size` bytes into numbered parts
+    pub fn split_size(mut self, split_size: Option<u64>) -> Self {
+        self.item.split_size = split_size;
+        self
+    }
+    
+    /// Download only these chapters, each to its own file
+    pub fn chapters(mut self, chapters: Option<&str>) -> Self {
+        self.item.chapters = chapters.map(str::to_string);
+        self
+    }
+    
+    /// Set the per-run deadline and what happens when it passes
+    pub fn deadline(mut self, deadline_secs: Option<u64>, action: DeadlineAction, resume_after_secs: Option<u64>) -> Self {
+        self.item.deadline_secs = deadline_secs;
+        self.item.deadline_action = action;
+        self.item.resume_after_secs = resume_after_secs;
+        self
+    }
+    
+    /// Set alternate URLs to fall back to
+    pub fn mirrors(mut self, mirrors: &[String]) -> Self {
+        self.item.mirrors = mirrors.to_vec();
+        self
+    }
+    
+    /// Set the tags
+    pub fn tags(mut self, tags: &[String]) -> Self {
+        self.item.tags = tags.to_vec();
+        self
+    }
+    
+    /// Set the custom user agent and headers
+    pub fn request_headers(mut self, request_headers: RequestHeaders) -> Self {
+        self.item.user_agent = request_headers.user_agent;
+        self.item.headers = request_headers.headers;
+        self.item.region = request_headers.region;
+        self
+    }
+    
     /// Set priority
     pub fn priority(mut self, priority: DownloadPriority) -> Self {
         self.item.priority = priority;
         self
     }
     
+    /// Set the expected size, if known before the download starts
+    pub fn estimated_size(mut self, total_bytes: Option<u64>) -> Self {
+        self.item.total_bytes = total_bytes.unwrap_or(0);
+        self
+    }
+    
     /// Build the download item
     pub fn build(self) -> DownloadItem {
         self.item
@@ -341,16 +756,95 @@ pub enum QueueCommand {
     MoveUp(String), // id
     #[allow(dead_code)]
     MoveDown(String), // id
+    Reorder(Vec<String>), // ids in the new order
+    /// Apply one action to several downloads under a single lock
+    Batch(BatchAction, Vec<String>),
+    /// Bring back the downloads changed by the latest snapshotted operation
+    Undo,
+    /// Move finished downloads older than this into the history archive
+    ArchiveFinished(chrono::Duration),
+    /// Reset downloads stuck in Downloading without a running task
+    Repair(Vec<Repair>),
+    /// Change when a queued download may start
+    Schedule(String, ScheduleChange),
+    /// Delete the files and remove the queue entries retention policies expired
+    CollectGarbage(Vec<GcItem>),
     SaveQueue,
     LoadQueue,
+    /// The system is suspending: stop running downloads
+    SystemSleep,
+    /// The system woke up: restart downloads stopped by the suspend
+    SystemWake,
+    /// Downloads keep failing with network errors: stop running downloads
+    NetworkOutage,
+    /// The network is back: restart downloads stopped by the outage
+    NetworkRestored,
+}
+
+impl QueueCommand {
+    /// Short name for logs and trace spans
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueueCommand::Add(_) => "add",
+            QueueCommand::Pause(_) => "pause",
+            QueueCommand::Resume(_) => "resume",
+            QueueCommand::Cancel(_) => "cancel",
+            QueueCommand::PauseAll => "pause_all",
+            QueueCommand::ResumeAll => "resume_all",
+            QueueCommand::SetPriority(..) => "set_priority",
+            QueueCommand::RemoveCompleted => "remove_completed",
+            QueueCommand::ClearFailed => "clear_failed",
+            QueueCommand::MoveUp(_) => "move_up",
+            QueueCommand::MoveDown(_) => "move_down",
+            QueueCommand::Reorder(_) => "reorder",
+            QueueCommand::Batch(..) => "batch",
+            QueueCommand::Undo => "undo",
+            QueueCommand::ArchiveFinished(_) => "archive_finished",
+            QueueCommand::CollectGarbage(_) => "collect_garbage",
+            QueueCommand::Repair(_) => "repair",
+            QueueCommand::Schedule(..) => "schedule",
+            QueueCommand::SaveQueue => "save_queue",
+            QueueCommand::LoadQueue => "load_queue",
+            QueueCommand::SystemSleep => "system_sleep",
+            QueueCommand::SystemWake => "system_wake",
+            QueueCommand::NetworkOutage => "network_outage",
+            QueueCommand::NetworkRestored => "network_restored",
+        }
+    }
+}
+
+/// An action applied to a selection of downloads at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchAction {
+    /// Pause running and queued downloads
+    #[allow(dead_code)]
+    Pause,
+    /// Cancel unfinished downloads
+    Cancel,
+    /// Change the priority, moving queued downloads accordingly
+    #[allow(dead_code)]
+    SetPriority(DownloadPriority),
+    /// Queue failed and canceled downloads again
+    #[allow(dead_code)]
+    Retry,
+}
+
+/// What a batch action changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchOutcome {
+    /// Downloads the action applied to; others were left as they were
+    pub changed: Vec<String>,
+    /// Downloads whose running task must be stopped
+    pub stopped: Vec<String>,
 }
 
 /// Manages a queue of downloads with advanced features
 #[derive(Debug)]
 pub struct DownloadQueue {
     /// Map of download IDs to download items
-    downloads: Arc<RwLock<HashMap<String, DownloadItem>>>,
-    /// Queue of pending download IDs, ordered by priority and time added
+    downloads: Arc<RwLock<DownloadMap>>,
+    /// Queue of pending download IDs, ordered by priority and time added.
+    /// Where both are needed, this is locked before `downloads`.
     queue: Arc<Mutex<Vec<String>>>,
     /// Max concurrent downloads
     max_concurrent: Arc<RwLock<usize>>,
@@ -368,6 +862,11 @@ pub struct DownloadQueue {
     is_running: Arc<RwLock<bool>>,
     /// Channel for notifying listeners of queue changes
     notify_tx: broadcast::Sender<()>,
+    /// Wakes the queue processor to start downloads, when the queue
+    /// changed or a slot was freed
+    schedule: Arc<Notify>,
+    /// The running queue processor, awaited on stop so its final save lands
+    processor: Mutex<Option<JoinHandle<()>>>,
 }
 
 /// Default implementation for DownloadQueue
@@ -377,7 +876,7 @@ impl Default for DownloadQueue {
         let (notify_tx, _) = broadcast::channel(100);
         
         Self {
-            downloads: Arc::new(RwLock::new(HashMap::new())),
+            downloads: Arc::new(RwLock::new(DownloadMap::new())),
             queue: Arc::new(Mutex::new(Vec::new())),
             max_concurrent: Arc::new(RwLock::new(3)), // Default to 3 concurrent downloads
             concurrency_control: Arc::new(Semaphore::new(3)),
@@ -387,6 +886,8 @@ impl Default for DownloadQueue {
             active_tasks: Arc::new(Mutex::new(HashMap::new())),
             is_running: Arc::new(RwLock::new(false)),
             notify_tx,
+            schedule: Arc::new(Notify::new()),
+            processor: Mutex::new(None),
         }
     }
 }
@@ -398,7 +899,7 @@ impl DownloadQueue {
         let (notify_tx, _) = broadcast::channel(100);
         
         Self {
-            downloads: Arc::new(RwLock::new(HashMap::new())),
+            downloads: Arc::new(RwLock::new(DownloadMap::new())),
             queue: Arc::new(Mutex::new(Vec::new())),
             max_concurrent: Arc::new(RwLock::new(max_concurrent_downloads)),
             concurrency_control: Arc::new(Semaphore::new(max_concurrent_downloads)),
@@ -408,6 +909,8 @@ impl DownloadQueue {
             active_tasks: Arc::new(Mutex::new(HashMap::new())),
             is_running: Arc::new(RwLock::new(false)),
             notify_tx,
+            schedule: Arc::new(Notify::new()),
+            processor: Mutex::new(None),
         }
     }
     
@@ -418,11 +921,25 @@ impl DownloadQueue {
     }
     
     /// Get a notification receiver to be notified of queue changes
-    #[allow(dead_code)]
     pub fn get_notification_receiver(&self) -> broadcast::Receiver<()> {
         self.notify_tx.subscribe()
     }
     
+    /// Send a command to the queue processor. Changes are refused while
+    /// another Rustloader process owns the queue; saving and loading become no-ops.
+    async fn send(&self, cmd: QueueCommand) -> Result<(), AppError> {
+        if let Err(e) = ensure_queue_owner() {
+            return match cmd {
+                QueueCommand::SaveQueue | QueueCommand::LoadQueue => Ok(()),
+                _ => Err(e),
+            };
+        }
+        
+        self.command_tx.send(cmd).await.map_err(|e| {
+            AppError::General(format!("Failed to send queue command: {}", e))
+        })
+    }
+    
     /// Start the queue processor in a separate task
     pub async fn start(&self) -> Result<(), AppError> {
         {
@@ -446,8 +963,57 @@ impl DownloadQueue {
         let state_path = self.state_path.clone();
         let command_rx_mutex = self.command_rx.clone();
         let notify_tx = self.notify_tx.clone();
+        let schedule = self.schedule.clone();
+        
+        // Report finished downloads, and progress where asked, to configured webhooks
+        let config = load_config().unwrap_or_default();
+        spawn_webhooks(config.webhooks, downloads.clone(), notify_tx.subscribe());
+        if config.event_stream {
+            spawn_event_stream(downloads.clone(), notify_tx.subscribe());
+        }
+        #[cfg(feature = "platform-integration")]
+        crate::desktop_progress::spawn_desktop_progress(downloads.clone());
+        
+        // Stop downloads before the system sleeps and restart them on wake
+        let power_tx = self.command_tx.clone();
+        tokio::spawn(async move {
+            let mut events = watch_power_events();
+            while let Some(event) = events.recv().await {
+                let cmd = match event {
+                    PowerEvent::Suspending => QueueCommand::SystemSleep,
+                    PowerEvent::Resumed => QueueCommand::SystemWake,
+                };
+                if power_tx.send(cmd).await.is_err() {
+                    break;
+                }
+            }
+        });
         
+        // Hold the queue while downloads keep failing with network errors,
+        // probing until the connection is back
+        let outage_tx = self.command_tx.clone();
         tokio::spawn(async move {
+            loop {
+                circuit_breaker::opened().await;
+                if outage_tx.send(QueueCommand::NetworkOutage).await.is_err() {
+                    break;
+                }
+                notify_queue_held();
+                let probe_interval = load_config().unwrap_or_default().circuit_breaker.probe_interval();
+                while circuit_breaker::queue_held() {
+                    tokio::time::sleep(probe_interval).await;
+                    if check_network_connectivity().await {
+                        circuit_breaker::close();
+                        notify_queue_restored();
+                    }
+                }
+                if outage_tx.send(QueueCommand::NetworkRestored).await.is_err() {
+                    break;
+                }
+            }
+        });
+        
+        let processor = tokio::spawn(async move {
             let command_rx = {
                 let mut guard = command_rx_mutex.lock().unwrap();
                 guard.take()
@@ -455,47 +1021,104 @@ impl DownloadQueue {
             
             if let Some(mut rx) = command_rx {
                 let mut autosave_interval = tokio::time::interval(std::time::Duration::from_secs(60));
+                let mut save_debounce = SaveDebounce::new(SAVE_DEBOUNCE);
+                // Compact the queue state a few minutes after start, then hourly
+                let mut archive_interval = tokio::time::interval_at(
+                    tokio::time::Instant::now() + Duration::from_secs(300),
+                    Duration::from_secs(3600),
+                );
+                let save_now = || save_queue_state(Arc::clone(&downloads), Arc::clone(&queue), state_path.clone());
+                // Start whatever was queued before the processor ran
+                schedule.notify_one();
                 
                 loop {
+                    let resume_in = next_resume_in(&downloads).into_iter().chain(next_start_in(&downloads)).min();
+                    let save_in = save_debounce.due_in(Instant::now());
                     tokio::select! {
-                        // Process queue commands
+                        // Process queue commands; bursts of saves are coalesced
                         Some(cmd) = rx.recv() => {
-                            let ctx = CommandContext {
-                                downloads: &downloads,
-                                queue: &queue,
-                                _max_concurrent: &max_concurrent,
-                                concurrency_control: &concurrency_control,
-                                active_tasks: &active_tasks,
-                                state_path: &state_path,
-                                notify_tx: &notify_tx,
-                            };
-                            process_command(cmd, &ctx).await;
+                            let is_save = matches!(cmd, QueueCommand::SaveQueue);
+                            if is_save && !save_debounce.request(Instant::now()) {
+                                debug!("Deferring queue save, the last one was moments ago");
+                            } else {
+                                let ctx = CommandContext {
+                                    downloads: &downloads,
+                                    queue: &queue,
+                                    _max_concurrent: &max_concurrent,
+                                    active_tasks: &active_tasks,
+                                    state_path: &state_path,
+                                    notify_tx: &notify_tx,
+                                };
+                                process_command(cmd, &ctx).await;
+                                if is_save {
+                                    save_debounce.saved(Instant::now());
+                                }
+                                // Any command may have queued, resumed or reordered downloads
+                                schedule.notify_one();
+                            }
+                        }
+                        
+                        // Apply retention policies, then move old finished
+                        // downloads into the history archive
+                        _ = archive_interval.tick() => {
+                            if let Ok(config) = load_config() {
+                                let ctx = CommandContext {
+                                    downloads: &downloads,
+                                    queue: &queue,
+                                    _max_concurrent: &max_concurrent,
+                                    active_tasks: &active_tasks,
+                                    state_path: &state_path,
+                                    notify_tx: &notify_tx,
+                                };
+                                apply_retention(&ctx, config.retention.clone()).await;
+                                if let Some(max_age) = config.archive_after() {
+                                    archive_finished(&ctx, max_age).await;
+                                }
+                            }
                         }
                         
                         // Auto-save queue state periodically
                         _ = autosave_interval.tick() => {
                             debug!("Auto-saving download queue state");
-                            let downloads_clone = Arc::clone(&downloads);
-                            let state_path_clone = state_path.clone();
-                            let _ = save_queue_state(downloads_clone, state_path_clone).await;
+                            let _ = save_now().await;
+                            save_debounce.saved(Instant::now());
                         }
                         
-                        // Check for task completion
-                        _ = tokio::time::sleep(Duration::from_secs(1)) => {
-                            let downloads_clone = Arc::clone(&downloads);
-                            let queue_clone = Arc::clone(&queue);
-                            let concurrency_clone = Arc::clone(&concurrency_control);
-                            let active_tasks_clone = Arc::clone(&active_tasks);
-                            let notify_tx_clone = notify_tx.clone();
-                            
+                        // Start queued downloads once the queue changed or a slot was freed
+                        _ = schedule.notified() => {
                             check_and_process_queue(
-                                downloads_clone,
-                                queue_clone,
-                                concurrency_clone,
-                                active_tasks_clone,
-                                notify_tx_clone,
+                                Arc::clone(&downloads),
+                                Arc::clone(&queue),
+                                Arc::clone(&concurrency_control),
+                                Arc::clone(&active_tasks),
+                                notify_tx.clone(),
+                                Arc::clone(&schedule),
                             ).await;
                         }
+                        
+                        // Downloads paused at their deadline come back at their resume
+                        // time, and scheduled downloads start at theirs
+                        _ = async {
+                            match resume_in {
+                                Some(delay) => tokio::time::sleep(delay).await,
+                                None => std::future::pending().await,
+                            }
+                        } => {
+                            schedule.notify_one();
+                        }
+                        
+                        // A deferred save runs as soon as the debounce interval is over
+                        _ = async {
+                            match save_in {
+                                Some(delay) => tokio::time::sleep(delay).await,
+                                None => std::future::pending().await,
+                            }
+                        } => {}
+                    }
+                    
+                    if save_debounce.is_due(Instant::now()) {
+                        let _ = save_now().await;
+                        save_debounce.saved(Instant::now());
                     }
                     
                     // Check if we should stop the processor
@@ -504,185 +1127,440 @@ impl DownloadQueue {
                         break;
                     }
                 }
+                
+                // The state is written on the way out, so nothing changed
+                // since the last save is lost on shutdown
+                if let Err(e) = save_now().await {
+                    warn!("Failed to save the queue state on shutdown: {}", e);
+                }
             }
         });
+        *self.processor.lock().unwrap() = Some(processor);
         
         Ok(())
     }
     
-    /// Stop the queue processor
+    /// Stop the queue processor, waiting for it to save the queue state
+    /// before the running downloads are canceled
     pub async fn stop(&self) -> Result<(), AppError> {
-        // Set running flag to false first
+        // Set running flag to false first, and wake the processor to see it
         {
             let mut is_running = self.is_running.write().unwrap();
             *is_running = false;
         }
+        self.schedule.notify_one();
         
-        // Save queue state before stopping
-        self.save_state().await?;
+        let processor = self.processor.lock().unwrap().take();
+        if let Some(processor) = processor {
+            if let Err(e) = processor.await {
+                warn!("Download queue processor ended abnormally: {}", e);
+            }
+        }
         
-        // Cancel any active downloads
-        let tasks_to_cancel = {
-            let mut active_tasks = self.active_tasks.lock().unwrap();
-            active_tasks.drain().collect::<Vec<_>>()
-        };
+        self.cancel_active_tasks();
+        Ok(())
+    }
+    
+    /// Stop the queue processor without an async runtime: the state is
+    /// written on a separate thread, waiting at most `timeout` for it, and
+    /// then the running downloads are stopped. As with [`stop`](Self::stop),
+    /// they are saved unfinished and resume on the next start. Safe to call
+    /// from any thread, including a runtime worker or a panic hook, where
+    /// waiting on the runtime would deadlock.
+    #[allow(dead_code)]
+    pub fn stop_blocking(&self, timeout: Duration) -> Result<(), AppError> {
+        *self.is_running.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = false;
+        self.schedule.notify_one();
+        let flushed = self.flush_blocking(timeout);
+        self.abort_active_tasks();
+        flushed
+    }
+    
+    /// Write the queue state on a separate thread, waiting at most
+    /// `timeout`. A write still blocked on a lock (e.g. held by a thread
+    /// that panicked) is left to finish, or not, on its own. Nothing is
+    /// written while another Rustloader process owns the queue.
+    pub fn flush_blocking(&self, timeout: Duration) -> Result<(), AppError> {
+        if other_instance().is_some() {
+            return Ok(());
+        }
         
-        // Cancel each task outside the lock
-        for (id, handle) in tasks_to_cancel {
-            debug!("Cancelling download task for {}", id);
-            handle.abort();
-            
-            // Update download item status
-            if let Some(mut item) = self.get_download(id.clone()) {
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let downloads = Arc::clone(&self.downloads);
+        let queue = Arc::clone(&self.queue);
+        let state_path = self.state_path.clone();
+        std::thread::Builder::new()
+            .name("queue-flush".to_string())
+            .spawn(move || {
+                let _ = done_tx.send(persist_queue_state(&downloads, &queue, &state_path));
+            })?;
+        match done_rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(_) => Err(AppError::General(format!(
+                "Saving the queue state did not finish within {}s",
+                timeout.as_secs()
+            ))),
+        }
+    }
+    
+    /// Abort the running download tasks and mark their downloads canceled
+    fn cancel_active_tasks(&self) {
+        for id in self.abort_active_tasks() {
+            let mut downloads = self.downloads.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(item) = downloads.get_mut(&id) {
                 item.cancel();
-                let mut downloads = self.downloads.write().unwrap();
-                downloads.insert(id, item);
             }
         }
+    }
+    
+    /// Abort the running download tasks, leaving their downloads as they
+    /// are, and return their IDs
+    fn abort_active_tasks(&self) -> Vec<String> {
+        let tasks_to_cancel = {
+            let mut active_tasks = self.active_tasks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            active_tasks.drain().collect::<Vec<_>>()
+        };
         
-        Ok(())
+        // Abort each task outside the lock
+        tasks_to_cancel
+            .into_iter()
+            .map(|(id, handle)| {
+                debug!("Cancelling download task for {}", id);
+                handle.abort();
+                id
+            })
+            .collect()
     }
     
     /// Add a download to the queue
     pub async fn add_download(&self, item: DownloadItem) -> Result<(), AppError> {
         let cmd = QueueCommand::Add(item);
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Pause a download by ID
     pub async fn pause_download(&self, id: &str) -> Result<(), AppError> {
         let cmd = QueueCommand::Pause(id.to_string());
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Resume a download by ID
     pub async fn resume_download(&self, id: &str) -> Result<(), AppError> {
         let cmd = QueueCommand::Resume(id.to_string());
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Cancel a download by ID
     pub async fn cancel_download(&self, id: &str) -> Result<(), AppError> {
         let cmd = QueueCommand::Cancel(id.to_string());
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Pause all active downloads
     pub async fn pause_all(&self) -> Result<(), AppError> {
         let cmd = QueueCommand::PauseAll;
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Resume all paused downloads
     pub async fn resume_all(&self) -> Result<(), AppError> {
         let cmd = QueueCommand::ResumeAll;
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Set the priority of a download
     pub async fn set_priority(&self, id: &str, priority: DownloadPriority) -> Result<(), AppError> {
         let cmd = QueueCommand::SetPriority(id.to_string(), priority);
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
+    }
+    
+    /// Change when a queued or paused download may start. The download is
+    /// checked here so the caller learns about unknown or started ones.
+    pub async fn schedule(&self, id: &str, change: ScheduleChange) -> Result<(), AppError> {
+        ensure_queue_owner()?;
+        match self.downloads.read().unwrap().get(id) {
+            None => return Err(AppError::ValidationError(format!("No download found with ID '{}'", id))),
+            Some(item) if !matches!(item.status, DownloadStatus::Queued | DownloadStatus::Paused) => {
+                return Err(AppError::ValidationError(format!(
+                    "Download {} is {:?}; only queued and paused downloads can be scheduled",
+                    id, item.status
+                )));
+            }
+            Some(_) => {}
+        }
+        self.send(QueueCommand::Schedule(id.to_string(), change)).await
     }
     
     /// Remove all completed downloads from the queue
     pub async fn remove_completed(&self) -> Result<(), AppError> {
         let cmd = QueueCommand::RemoveCompleted;
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Clear all failed downloads from the queue
     pub async fn clear_failed(&self) -> Result<(), AppError> {
         let cmd = QueueCommand::ClearFailed;
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Move a download up in the queue (higher priority)
     #[allow(dead_code)]
     pub async fn move_up(&self, id: &str) -> Result<(), AppError> {
         let cmd = QueueCommand::MoveUp(id.to_string());
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
     /// Move a download down in the queue (lower priority)
     #[allow(dead_code)]
     pub async fn move_down(&self, id: &str) -> Result<(), AppError> {
         let cmd = QueueCommand::MoveDown(id.to_string());
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+        self.send(cmd).await
     }
     
-    /// Save the queue state
-    pub async fn save_state(&self) -> Result<(), AppError> {
-        let cmd = QueueCommand::SaveQueue;
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+    /// Put pending downloads in the given order, e.g. after a drag and drop.
+    /// The order is checked here so the caller learns about invalid IDs;
+    /// it is applied atomically by the queue processor.
+    pub async fn reorder(&self, ids_in_order: Vec<String>) -> Result<(), AppError> {
+        ensure_queue_owner()?;
+        {
+            let mut queue = self.queue.lock().unwrap().clone();
+            let downloads = self.downloads.read().unwrap();
+            sort_queue_ids(&mut queue, &downloads);
+            apply_queue_order(&mut queue, &downloads, &ids_in_order)?;
+        }
+        self.send(QueueCommand::Reorder(ids_in_order)).await
     }
     
-    /// Load the queue state
-    pub async fn load_state(&self) -> Result<(), AppError> {
-        let cmd = QueueCommand::LoadQueue;
-        self.command_tx.send(cmd).await.map_err(|e| {
-            AppError::General(format!("Failed to send queue command: {}", e))
-        })
+    /// Apply an action to several downloads at once, e.g. a multi-select
+    /// in the GUI. Listeners get a single notification for the whole batch.
+    pub async fn apply_batch(&self, action: BatchAction, ids: Vec<String>) -> Result<(), AppError> {
+        ensure_queue_owner()?;
+        if ids.is_empty() {
+            return Err(AppError::ValidationError("No downloads selected".to_string()));
+        }
+        {
+            let downloads = self.downloads.read().unwrap();
+            let unknown: Vec<&str> = ids
+                .iter()
+                .filter(|id| !downloads.contains_key(*id))
+                .map(String::as_str)
+                .collect();
+            if !unknown.is_empty() {
+                return Err(AppError::ValidationError(format!("Unknown download ID(s): {}", unknown.join(", "))));
+            }
+        }
+        self.send(QueueCommand::Batch(action, ids)).await
     }
     
-    /// Get a download item by ID
-    pub fn get_download(&self, id: String) -> Option<DownloadItem> {
-        let downloads = self.downloads.read().unwrap();
-        downloads.get(&id).cloned()
+    /// Undo the latest clear or bulk cancel. Returns the snapshot being
+    /// restored, or None when there is nothing to undo.
+    pub async fn undo(&self) -> Result<Option<QueueSnapshot>, AppError> {
+        ensure_queue_owner()?;
+        let Some(snapshot) = load_snapshots(&get_queue_snapshots_path(&self.state_path))?.pop() else {
+            return Ok(None);
+        };
+        self.send(QueueCommand::Undo).await?;
+        Ok(Some(snapshot))
     }
     
-    /// Get all downloads in the queue
-    pub fn get_all_downloads(&self) -> Vec<DownloadItem> {
-        let downloads = self.downloads.read().unwrap();
-        downloads.values().cloned().collect()
+    /// Move finished downloads older than `max_age` into the history
+    /// archive, returning how many will be moved
+    pub async fn archive_finished(&self, max_age: chrono::Duration) -> Result<usize, AppError> {
+        let cutoff = Utc::now() - max_age;
+        let count = self
+            .get_all_downloads()
+            .iter()
+            .filter(|item| item.is_finished() && item.finished_at.unwrap_or(item.added_at) < cutoff)
+            .count();
+        if count > 0 {
+            self.send(QueueCommand::ArchiveFinished(max_age)).await?;
+        }
+        Ok(count)
     }
     
-    /// Get active downloads
-    #[allow(dead_code)]
-    pub fn get_active_downloads(&self) -> Vec<DownloadItem> {
-        let downloads = self.downloads.read().unwrap();
-        downloads.values()
-            .filter(|item| item.is_active())
-            .cloned()
+    /// Downloads marked as downloading that have no running task
+    pub fn stuck_downloads(&self) -> Vec<DownloadItem> {
+        let live = live_task_ids(&self.active_tasks);
+        self.get_all_downloads()
+            .into_iter()
+            .filter(|item| item.status == DownloadStatus::Downloading && !live.contains(&item.id))
             .collect()
     }
     
-    /// Get paused downloads
-    #[allow(dead_code)]
-    pub fn get_paused_downloads(&self) -> Vec<DownloadItem> {
-        let downloads = self.downloads.read().unwrap();
-        downloads.values()
-            .filter(|item| item.is_paused())
-            .cloned()
-            .collect()
+    /// Queue stuck downloads again, or fail them when their partial files
+    /// are gone, returning what was done
+    pub async fn repair_stuck(&self) -> Result<Vec<Repair>, AppError> {
+        // Another process may be running the downloads this one sees as stuck
+        ensure_queue_owner()?;
+        
+        // Nothing loaded: this process runs no downloads, so repair the saved
+        // queue in place rather than loading it and starting them
+        if self.downloads.read().unwrap().is_empty() {
+            return self.repair_saved_queue().await;
+        }
+        
+        let stuck = self.stuck_downloads();
+        let repairs = tokio::task::spawn_blocking(move || plan_repairs(&stuck))
+            .await
+            .map_err(|e| AppError::General(format!("Failed to look for partial files: {}", e)))??;
+        if !repairs.is_empty() {
+            self.send(QueueCommand::Repair(repairs.clone())).await?;
+        }
+        Ok(repairs)
     }
     
-    /// Get completed downloads
-    #[allow(dead_code)]
+    /// Repair the downloads recorded as downloading in the saved queue state
+    async fn repair_saved_queue(&self) -> Result<Vec<Repair>, AppError> {
+        let saved = load_saved_downloads()?;
+        let stuck: Vec<DownloadItem> = saved
+            .iter()
+            .filter(|item| item.status == DownloadStatus::Downloading)
+            .cloned()
+            .collect();
+        let repairs = tokio::task::spawn_blocking(move || plan_repairs(&stuck))
+            .await
+            .map_err(|e| AppError::General(format!("Failed to look for partial files: {}", e)))??;
+        if repairs.is_empty() {
+            return Ok(repairs);
+        }
+        
+        // Queued downloads were saved in queue order
+        let mut downloads_map = DownloadMap::new();
+        let mut queue_ids = Vec::new();
+        for mut item in saved {
+            if let Some(repair) = repairs.iter().find(|repair| repair.id == item.id) {
+                apply_repair(&mut item, repair);
+            }
+            if item.status == DownloadStatus::Queued {
+                queue_ids.push(item.id.clone());
+            }
+            downloads_map.insert(item.id.clone(), item);
+        }
+        save_queue_state(
+            Arc::new(RwLock::new(downloads_map)),
+            Arc::new(Mutex::new(queue_ids)),
+            self.state_path.clone(),
+        )
+        .await?;
+        Ok(repairs)
+    }
+    
+    /// Apply the retention policies, returning what they expired; with
+    /// `dry_run` nothing is deleted or removed
+    pub async fn collect_garbage(&self, dry_run: bool) -> Result<Vec<GcItem>, AppError> {
+        let policies = load_config()?.retention;
+        validate_policies(&policies)?;
+        if !dry_run {
+            ensure_queue_owner()?;
+        }
+        
+        // Nothing loaded: look at the saved queue, and change it in place
+        // rather than loading it and starting its downloads
+        let loaded = !self.downloads.read().unwrap().is_empty();
+        let queued = if loaded { self.get_all_downloads() } else { load_saved_downloads()? };
+        let history_path = history_path()?;
+        let (plan, saved) = tokio::task::spawn_blocking(move || {
+            let archived = load_history(&history_path)?;
+            Ok::<_, AppError>((plan_gc(&policies, &queued, &archived, Utc::now()), queued))
+        })
+        .await
+        .map_err(|e| AppError::General(format!("Failed to apply retention policies: {}", e)))??;
+        if dry_run || plan.is_empty() {
+            return Ok(plan);
+        }
+        if loaded {
+            self.send(QueueCommand::CollectGarbage(plan.clone())).await?;
+            return Ok(plan);
+        }
+        
+        let plan_clone = plan.clone();
+        tokio::task::spawn_blocking(move || delete_expired_files(&plan_clone))
+            .await
+            .map_err(|e| AppError::General(format!("Failed to delete expired files: {}", e)))?;
+        let removed: HashSet<&str> = plan
+            .iter()
+            .filter(|expired| expired.action == GcAction::RemoveEntry)
+            .map(|expired| expired.id.as_str())
+            .collect();
+        // Queued downloads were saved in queue order
+        let mut downloads_map = DownloadMap::new();
+        let mut queue_ids = Vec::new();
+        for item in saved.into_iter().filter(|item| !removed.contains(item.id.as_str())) {
+            if item.status == DownloadStatus::Queued {
+                queue_ids.push(item.id.clone());
+            }
+            downloads_map.insert(item.id.clone(), item);
+        }
+        save_queue_state(
+            Arc::new(RwLock::new(downloads_map)),
+            Arc::new(Mutex::new(queue_ids)),
+            self.state_path.clone(),
+        )
+        .await?;
+        Ok(plan)
+    }
+    
+    /// Operations that can be undone, oldest first
+    pub fn undo_history(&self) -> Result<Vec<QueueSnapshot>, AppError> {
+        load_snapshots(&get_queue_snapshots_path(&self.state_path))
+    }
+    
+    /// Save the queue state
+    pub async fn save_state(&self) -> Result<(), AppError> {
+        let cmd = QueueCommand::SaveQueue;
+        self.send(cmd).await
+    }
+    
+    /// Load the queue state
+    pub async fn load_state(&self) -> Result<(), AppError> {
+        let cmd = QueueCommand::LoadQueue;
+        self.send(cmd).await
+    }
+    
+    /// Get a download item by ID
+    pub fn get_download(&self, id: String) -> Option<DownloadItem> {
+        let downloads = self.downloads.read().unwrap();
+        downloads.get(&id).cloned()
+    }
+    
+    /// Get the recorded speed samples for a download, oldest first
+    pub fn get_speed_history(&self, id: &str) -> Option<Vec<SpeedSample>> {
+        let downloads = self.downloads.read().unwrap();
+        downloads.get(id).map(|item| item.speed_history.samples())
+    }
+    
+    /// Get all downloads in the queue
+    pub fn get_all_downloads(&self) -> Vec<DownloadItem> {
+        snapshot_items(&self.downloads)
+    }
+    
+    /// All downloads as they are now, shared rather than copied
+    pub fn snapshot(&self) -> Vec<Arc<DownloadItem>> {
+        self.downloads.read().unwrap().snapshot()
+    }
+    
+    /// Get active downloads
+    #[allow(dead_code)]
+    pub fn get_active_downloads(&self) -> Vec<DownloadItem> {
+        let downloads = self.downloads.read().unwrap();
+        downloads.values()
+            .filter(|item| item.is_active())
+            .cloned()
+            .collect()
+    }
+    
+    /// Get paused downloads
+    #[allow(dead_code)]
+    pub fn get_paused_downloads(&self) -> Vec<DownloadItem> {
+        let downloads = self.downloads.read().unwrap();
+        downloads.values()
+            .filter(|item| item.is_paused())
+            .cloned()
+            .collect()
+    }
+    
+    /// Get completed downloads
+    #[allow(dead_code)]
     pub fn get_completed_downloads(&self) -> Vec<DownloadItem> {
         let downloads = self.downloads.read().unwrap();
         downloads.values()
@@ -761,8 +1639,9 @@ impl DownloadQueue {
             let diff = max as isize - current as isize;
             match diff.cmp(&0) {
                 std::cmp::Ordering::Greater => {
-                    // Add permits
+                    // Add permits, and fill them from the queue
                     self.concurrency_control.add_permits(diff as usize);
+                    self.schedule.notify_one();
                 },
                 std::cmp::Ordering::Less => {
                     // Close permits - note that this doesn't affect already acquired permits
@@ -785,35 +1664,122 @@ fn generate_download_id() -> String {
     format!("dl_{}_{}", timestamp, random)
 }
 
+/// Directory holding the queue state and other per-machine data
+pub fn get_data_dir() -> PathBuf {
+    data_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// Check a finished download for an identical file already in the library,
+/// linking it in when configured. Returns the existing file.
+async fn check_duplicate(outcome: &DownloadOutcome) -> Option<String> {
+    let path = outcome.file_path.clone()?;
+    match tokio::task::spawn_blocking(move || dedupe_download(&path)).await {
+        Ok(Ok(found)) => found.map(|duplicate| {
+            if let Some(saved) = duplicate.bytes_saved {
+                debug!("Deduplicated download, saving {} bytes", saved);
+            }
+            duplicate.original.to_string_lossy().into_owned()
+        }),
+        Ok(Err(e)) => {
+            warn!("Duplicate check failed: {}", e);
+            None
+        }
+        Err(e) => {
+            warn!("Duplicate check task failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Link a finished download into the folders of matching publish rules
+async fn publish_completed(outcome: &DownloadOutcome, url: String, format: String) -> Vec<String> {
+    let Some(path) = outcome.file_path.clone() else {
+        return Vec::new();
+    };
+    tokio::task::spawn_blocking(move || publish_download(&path, &url, &format))
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|link| link.to_string_lossy().into_owned())
+        .collect()
+}
+
 /// Get the path to store the queue state
-fn get_queue_state_path() -> PathBuf {
-    let mut path = dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."));
-    
-    path.push("rustloader");
-    fs::create_dir_all(&path).unwrap_or_default();
-    
-    path.push("download_queue.json");
-    path
+pub fn get_queue_state_path() -> PathBuf {
+    get_data_dir().join("download_queue.json")
+}
+
+/// The queue state file, its backup and the undo snapshots, whether or not
+/// they exist. The history archive is in the history database.
+pub fn get_queue_state_files() -> Vec<PathBuf> {
+    let state_path = get_queue_state_path();
+    let backup_path = get_queue_backup_path(&state_path);
+    let snapshots_path = get_queue_snapshots_path(&state_path);
+    vec![state_path, backup_path, snapshots_path]
+}
+
+/// Get the path of the last known-good copy of the queue state
+fn get_queue_backup_path(state_path: &Path) -> PathBuf {
+    state_path.with_extension("json.bak")
+}
+
+/// Get the path of the marker written with the first signed queue state
+fn get_queue_signed_marker_path(state_path: &Path) -> PathBuf {
+    state_path.with_extension("json.signed")
 }
 
 /// Command processing context
 struct CommandContext<'a> {
-    downloads: &'a Arc<RwLock<HashMap<String, DownloadItem>>>,
+    downloads: &'a Arc<RwLock<DownloadMap>>,
     queue: &'a Arc<Mutex<Vec<String>>>,
     _max_concurrent: &'a Arc<RwLock<usize>>, // Unused but kept for future use
-    concurrency_control: &'a Arc<Semaphore>,
     active_tasks: &'a Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
     state_path: &'a std::path::Path,
     notify_tx: &'a broadcast::Sender<()>,
 }
 
+/// Stop the running downloads and their yt-dlp processes, marking them to
+/// be resumed on wake. Returns how many were stopped.
+fn pause_for_sleep(ctx: &CommandContext<'_>) -> usize {
+    stop_running_downloads(ctx, "system sleep", |item| item.paused_for_sleep = true)
+}
+
+/// Stop the running downloads like [`pause_for_sleep`], marking them with
+/// `mark` so they can be told apart when resumed
+fn stop_running_downloads(ctx: &CommandContext<'_>, reason: &str, mark: impl Fn(&mut DownloadItem)) -> usize {
+    let mut paused_ids = Vec::new();
+    {
+        let mut downloads_map = ctx.downloads.write().unwrap();
+        for (id, item) in downloads_map.matching_mut(|item| item.status == DownloadStatus::Downloading) {
+            item.mark_paused();
+            mark(item);
+            item.speed = 0.0;
+            if let Some(token) = &item.cancel_token {
+                let _ = token.send(());
+            }
+            paused_ids.push(id.clone());
+        }
+    }
+    
+    // Aborting the task drops the child process, which kills it
+    let mut tasks = ctx.active_tasks.lock().unwrap();
+    for id in &paused_ids {
+        if let Some(handle) = tasks.remove(id) {
+            debug!("Stopping download {} for {}", id, reason);
+            handle.abort();
+        }
+    }
+    paused_ids.len()
+}
+
 /// Process a queue command
+#[tracing::instrument(name = "queue_command", skip_all, fields(command = cmd.name()))]
 async fn process_command(
     cmd: QueueCommand,
     ctx: &CommandContext<'_>,
 ) {
     debug!("Processing queue command: {:?}", cmd);
+    record_operation(cmd.name());
     
     match cmd {
         QueueCommand::Add(item) => {
@@ -839,21 +1805,6 @@ async fn process_command(
                 }
             }
             
-            // Process the queue
-            let downloads_clone = Arc::clone(ctx.downloads);
-            let queue_clone = Arc::clone(ctx.queue);
-            let concurrency_clone = Arc::clone(ctx.concurrency_control);
-            let active_tasks_clone = Arc::clone(ctx.active_tasks);
-            let notify_tx_clone = ctx.notify_tx.clone();
-            
-            check_and_process_queue(
-                downloads_clone,
-                queue_clone,
-                concurrency_clone,
-                active_tasks_clone,
-                notify_tx_clone,
-            ).await;
-            
             // Notify listeners
             let _ = ctx.notify_tx.send(());
         }
@@ -896,16 +1847,14 @@ async fn process_command(
             
             // Update download status in the downloads map
             {
+                let mut queue_vec = ctx.queue.lock().unwrap();
                 let mut downloads_map = ctx.downloads.write().unwrap();
                 if let Some(item) = downloads_map.get_mut(&id) {
                     if item.is_paused() {
                         item.mark_resumed();
                         should_notify = true;
                         
-                        // Add back to queue
-                        let mut queue_vec = ctx.queue.lock().unwrap();
-                        
-                        // Add to front if high priority
+                        // Add back to queue, to the front if high priority
                         if item.priority == DownloadPriority::High || item.priority == DownloadPriority::Critical {
                             queue_vec.insert(0, id.clone());
                         } else {
@@ -916,21 +1865,6 @@ async fn process_command(
             }
             
             if should_notify {
-                // Process the queue
-                let downloads_clone = Arc::clone(ctx.downloads);
-                let queue_clone = Arc::clone(ctx.queue);
-                let concurrency_clone = Arc::clone(ctx.concurrency_control);
-                let active_tasks_clone = Arc::clone(ctx.active_tasks);
-                let notify_tx_clone = ctx.notify_tx.clone();
-                
-                check_and_process_queue(
-                    downloads_clone,
-                    queue_clone,
-                    concurrency_clone,
-                    active_tasks_clone,
-                    notify_tx_clone,
-                ).await;
-                
                 let _ = ctx.notify_tx.send(());
             }
         }
@@ -981,15 +1915,13 @@ async fn process_command(
             {
                 let mut downloads_map = ctx.downloads.write().unwrap();
                 
-                for (id, item) in downloads_map.iter_mut() {
-                    if item.is_active() {
-                        item.mark_paused();
-                        paused_ids.push(id.clone());
-                        
-                        // If this download has a cancel token, send a cancel signal
-                        if let Some(token) = &item.cancel_token {
-                            let _ = token.send(());
-                        }
+                for (id, item) in downloads_map.matching_mut(DownloadItem::is_active) {
+                    item.mark_paused();
+                    paused_ids.push(id.clone());
+                    
+                    // If this download has a cancel token, send a cancel signal
+                    if let Some(token) = &item.cancel_token {
+                        let _ = token.send(());
                     }
                 }
             }
@@ -1016,7 +1948,64 @@ async fn process_command(
             }
         }
         
+        QueueCommand::SystemSleep => {
+            if pause_for_sleep(ctx) > 0 {
+                let _ = ctx.notify_tx.send(());
+            }
+        }
+        
+        QueueCommand::SystemWake => {
+            // Children still running across the suspend hold dead connections;
+            // stop them too, and restart everything from its partial file
+            pause_for_sleep(ctx);
+            let mut resumed_count = 0;
+            {
+                let mut queue_vec = ctx.queue.lock().unwrap();
+                let mut downloads_map = ctx.downloads.write().unwrap();
+                for (id, item) in downloads_map.matching_mut(|item| item.paused_for_sleep) {
+                    item.paused_for_sleep = false;
+                    item.mark_resumed();
+                    resumed_count += 1;
+                    // They were running before the suspend, so they go first
+                    queue_vec.retain(|queued| queued != id);
+                    queue_vec.insert(0, id.clone());
+                }
+            }
+            
+            if resumed_count > 0 {
+                debug!("Restarting {} download(s) after wake", resumed_count);
+                let _ = ctx.notify_tx.send(());
+            }
+        }
+        
+        QueueCommand::NetworkOutage => {
+            if stop_running_downloads(ctx, "the network outage", |item| item.paused_for_outage = true) > 0 {
+                let _ = ctx.notify_tx.send(());
+            }
+        }
+        
+        QueueCommand::NetworkRestored => {
+            let mut resumed_count = 0;
+            {
+                let mut queue_vec = ctx.queue.lock().unwrap();
+                let mut downloads_map = ctx.downloads.write().unwrap();
+                for (id, item) in downloads_map.matching_mut(|item| item.paused_for_outage) {
+                    item.paused_for_outage = false;
+                    item.mark_resumed();
+                    resumed_count += 1;
+                    // They were running when the queue was held, so they go first
+                    queue_vec.retain(|queued| queued != id);
+                    queue_vec.insert(0, id.clone());
+                }
+            }
+            
+            debug!("Network is back, restarting {} download(s)", resumed_count);
+            let _ = ctx.notify_tx.send(());
+        }
+        
         QueueCommand::ResumeAll => {
+            // Resuming by hand overrides a hold for network failures
+            circuit_breaker::close();
             let mut resumed_count = 0;
             
             // Resume all paused downloads and add to queue
@@ -1026,16 +2015,14 @@ async fn process_command(
                 let mut high_priority = Vec::new();
                 let mut normal_priority = Vec::new();
                 
-                for (id, item) in downloads_map.iter_mut() {
-                    if item.is_paused() {
-                        item.mark_resumed();
-                        resumed_count += 1;
-                        
-                        if item.priority == DownloadPriority::High || item.priority == DownloadPriority::Critical {
-                            high_priority.push(id.clone());
-                        } else {
-                            normal_priority.push(id.clone());
-                        }
+                for (id, item) in downloads_map.matching_mut(DownloadItem::is_paused) {
+                    item.mark_resumed();
+                    resumed_count += 1;
+                    
+                    if item.priority == DownloadPriority::High || item.priority == DownloadPriority::Critical {
+                        high_priority.push(id.clone());
+                    } else {
+                        normal_priority.push(id.clone());
                     }
                 }
                 
@@ -1050,21 +2037,6 @@ async fn process_command(
             }
             
             if resumed_count > 0 {
-                // Process the queue
-                let downloads_clone = Arc::clone(ctx.downloads);
-                let queue_clone = Arc::clone(ctx.queue);
-                let concurrency_clone = Arc::clone(ctx.concurrency_control);
-                let active_tasks_clone = Arc::clone(ctx.active_tasks);
-                let notify_tx_clone = ctx.notify_tx.clone();
-                
-                check_and_process_queue(
-                    downloads_clone,
-                    queue_clone,
-                    concurrency_clone,
-                    active_tasks_clone,
-                    notify_tx_clone,
-                ).await;
-                
                 let _ = ctx.notify_tx.send(());
             }
         }
@@ -1106,8 +2078,16 @@ async fn process_command(
             }
         }
         
+        QueueCommand::Schedule(id, change) => {
+            let mut downloads_map = ctx.downloads.write().unwrap();
+            if let Some(item) = downloads_map.get_mut(&id) {
+                change.apply(&mut item.scheduled_at, &mut item.start_window);
+                let _ = ctx.notify_tx.send(());
+            }
+        }
+        
         QueueCommand::RemoveCompleted => {
-            let mut removed_count = 0;
+            let mut removed = Vec::new();
             
             // Remove completed downloads
             {
@@ -1118,18 +2098,18 @@ async fn process_command(
                     .collect();
                 
                 for id in &completed_ids {
-                    downloads_map.remove(id);
-                    removed_count += 1;
+                    removed.extend(downloads_map.remove(id));
                 }
             }
             
-            if removed_count > 0 {
+            if !removed.is_empty() {
+                snapshot_operation(ctx, "clear-completed", removed).await;
                 let _ = ctx.notify_tx.send(());
             }
         }
         
         QueueCommand::ClearFailed => {
-            let mut cleared_count = 0;
+            let mut cleared = Vec::new();
             
             // Clear failed downloads
             {
@@ -1140,22 +2120,25 @@ async fn process_command(
                     .collect();
                 
                 for id in &failed_ids {
-                    downloads_map.remove(id);
-                    cleared_count += 1;
+                    cleared.extend(downloads_map.remove(id));
                 }
             }
             
-            if cleared_count > 0 {
+            if !cleared.is_empty() {
+                snapshot_operation(ctx, "clear-failed", cleared).await;
                 let _ = ctx.notify_tx.send(());
             }
         }
         
         QueueCommand::MoveUp(id) => {
             let mut queue_vec = ctx.queue.lock().unwrap();
+            let mut downloads_map = ctx.downloads.write().unwrap();
+            sort_queue_ids(&mut queue_vec, &downloads_map);
             
             if let Some(index) = queue_vec.iter().position(|qid| *qid == id) {
                 if index > 0 {
                     queue_vec.swap(index, index - 1);
+                    pin_queue_positions(&queue_vec, &mut downloads_map);
                     let _ = ctx.notify_tx.send(());
                 }
             }
@@ -1163,19 +2146,114 @@ async fn process_command(
         
         QueueCommand::MoveDown(id) => {
             let mut queue_vec = ctx.queue.lock().unwrap();
+            let mut downloads_map = ctx.downloads.write().unwrap();
+            sort_queue_ids(&mut queue_vec, &downloads_map);
             
             if let Some(index) = queue_vec.iter().position(|qid| *qid == id) {
                 if index < queue_vec.len() - 1 {
                     queue_vec.swap(index, index + 1);
+                    pin_queue_positions(&queue_vec, &mut downloads_map);
+                    let _ = ctx.notify_tx.send(());
+                }
+            }
+        }
+        
+        QueueCommand::Reorder(ids_in_order) => {
+            let result = {
+                let mut queue_vec = ctx.queue.lock().unwrap();
+                let mut downloads_map = ctx.downloads.write().unwrap();
+                sort_queue_ids(&mut queue_vec, &downloads_map);
+                let result = apply_queue_order(&mut queue_vec, &downloads_map, &ids_in_order);
+                if result.is_ok() {
+                    pin_queue_positions(&queue_vec, &mut downloads_map);
+                }
+                result
+            };
+            
+            match result {
+                Ok(()) => {
                     let _ = ctx.notify_tx.send(());
                 }
+                // A download may have started since the order was checked
+                Err(e) => warn!("Queue order not applied: {}", e),
+            }
+        }
+        
+        QueueCommand::Batch(action, ids) => {
+            let (outcome, before, queue_before) = {
+                let mut queue_vec = ctx.queue.lock().unwrap();
+                let mut downloads_map = ctx.downloads.write().unwrap();
+                let queue_before = queue_vec.clone();
+                let before: Vec<DownloadItem> = ids.iter().filter_map(|id| downloads_map.get(id).cloned()).collect();
+                let outcome = apply_batch_action(&mut queue_vec, &mut downloads_map, action, &ids);
+                (outcome, before, queue_before)
+            };
+            
+            if action == BatchAction::Cancel && !outcome.changed.is_empty() {
+                let canceled = before.into_iter().filter(|item| outcome.changed.contains(&item.id)).collect();
+                remember_snapshot(ctx, QueueSnapshot::new("cancel", canceled, queue_before)).await;
+            }
+            
+            {
+                let mut tasks = ctx.active_tasks.lock().unwrap();
+                for id in &outcome.stopped {
+                    if let Some(handle) = tasks.remove(id) {
+                        debug!("Stopping download {} ({:?})", id, action);
+                        handle.abort();
+                    }
+                }
+            }
+            
+            if outcome.changed.is_empty() {
+                return;
+            }
+            let _ = ctx.notify_tx.send(());
+        }
+        
+        QueueCommand::Undo => {
+            let snapshots_path = get_queue_snapshots_path(ctx.state_path);
+            let mut snapshots = match load_snapshots(&snapshots_path) {
+                Ok(snapshots) => snapshots,
+                Err(e) => {
+                    warn!("Can't read queue snapshots: {}", e);
+                    return;
+                }
+            };
+            let Some(snapshot) = snapshots.pop() else {
+                return;
+            };
+            let restored = {
+                let mut queue_vec = ctx.queue.lock().unwrap();
+                let mut downloads_map = ctx.downloads.write().unwrap();
+                restore_snapshot(&mut queue_vec, &mut downloads_map, &snapshot)
+            };
+            debug!("Undid {}: restored {} download(s)", snapshot.operation, restored.len());
+            if let Err(e) = save_snapshots(&snapshots_path, &snapshots) {
+                warn!("Failed to save queue snapshots: {}", e);
+            }
+            
+            if !restored.is_empty() {
+                let _ = ctx.notify_tx.send(());
             }
         }
         
+        QueueCommand::ArchiveFinished(max_age) => {
+            archive_finished(ctx, max_age).await;
+        }
+        
+        QueueCommand::Repair(repairs) => {
+            repair_downloads(ctx, repairs).await;
+        }
+        
+        QueueCommand::CollectGarbage(plan) => {
+            collect_garbage(ctx, plan).await;
+        }
+        
         QueueCommand::SaveQueue => {
             let downloads_clone = Arc::clone(ctx.downloads);
+            let queue_clone = Arc::clone(ctx.queue);
             let state_path_clone = ctx.state_path.to_path_buf();
-            let _ = save_queue_state(downloads_clone, state_path_clone).await;
+            let _ = save_queue_state(downloads_clone, queue_clone, state_path_clone).await;
         }
         
         QueueCommand::LoadQueue => {
@@ -1185,236 +2263,590 @@ async fn process_command(
     }
 }
 
-/// Check the queue and start downloads if slots are available
-async fn check_and_process_queue(
-    downloads: Arc<RwLock<HashMap<String, DownloadItem>>>,
-    queue: Arc<Mutex<Vec<String>>>,
-    concurrency_control: Arc<Semaphore>,
-    active_tasks: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
-    notify_tx: broadcast::Sender<()>,
-) {
-    // Get next download from queue
-    let mut next_download = None;
-    let mut next_id = String::new();
-    
-    // Get the next item from the queue
+/// Remember the downloads a clear removed so it can be undone
+async fn snapshot_operation(ctx: &CommandContext<'_>, operation: &str, removed: Vec<DownloadItem>) {
+    let queue_before = ctx.queue.lock().unwrap().clone();
+    remember_snapshot(ctx, QueueSnapshot::new(operation, removed, queue_before)).await;
+}
+
+/// Add a snapshot to the undo history
+async fn remember_snapshot(ctx: &CommandContext<'_>, snapshot: QueueSnapshot) {
+    let path = get_queue_snapshots_path(ctx.state_path);
+    match tokio::task::spawn_blocking(move || record_snapshot(&path, snapshot)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Failed to save queue snapshot: {}", e),
+        Err(e) => warn!("Queue snapshot task failed: {}", e),
+    }
+}
+
+/// IDs of downloads whose task is still running
+fn live_task_ids(active_tasks: &Mutex<HashMap<String, JoinHandle<()>>>) -> HashSet<String> {
+    active_tasks
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, task)| !task.is_finished())
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+/// Reset a stuck download as planned
+fn apply_repair(item: &mut DownloadItem, repair: &Repair) {
+    item.speed = 0.0;
+    match repair.action {
+        RepairAction::Requeue => item.status = DownloadStatus::Queued,
+        RepairAction::Fail => item.mark_failed(Some(LOST_PROGRESS_MESSAGE.to_string())),
+    }
+}
+
+/// Apply planned repairs to the downloads that still have no running task
+/// and haven't finished or been paused since
+async fn repair_downloads(ctx: &CommandContext<'_>, repairs: Vec<Repair>) {
+    let live = live_task_ids(ctx.active_tasks);
+    let mut repaired = 0;
     {
-        let mut queue_vec = queue.lock().unwrap();
-        if !queue_vec.is_empty() {
-            next_id = queue_vec[0].clone();
-            queue_vec.remove(0);
+        let mut queue_vec = ctx.queue.lock().unwrap();
+        let mut downloads_map = ctx.downloads.write().unwrap();
+        for repair in repairs {
+            if live.contains(&repair.id) {
+                continue;
+            }
+            let Some(item) = downloads_map.get_mut(&repair.id) else {
+                continue;
+            };
+            if item.is_finished() || item.is_paused() {
+                continue;
+            }
             
-            let downloads_map = downloads.read().unwrap();
-            next_download = downloads_map.get(&next_id).cloned();
+            apply_repair(item, &repair);
+            queue_vec.retain(|qid| *qid != repair.id);
+            if repair.action == RepairAction::Requeue {
+                queue_vec.push(repair.id.clone());
+            }
+            repaired += 1;
         }
     }
     
-    // Process the download if we got one
-    if let Some(mut item) = next_download {
-        debug!("Attempting to start download {}", item.id);
-        
-        // Check if semaphore has available permits
-        if concurrency_control.available_permits() > 0 {
-            // Mark as started and update in downloads map
-            item.mark_started();
-            let cancel_rx = item.create_cancel_token();
-            
-            {
-                let mut downloads_map = downloads.write().unwrap();
-                downloads_map.insert(item.id.clone(), item.clone());
-            }
-            
-            // Clone everything needed for the task
-            let item_id = item.id.clone();
-            let item_for_task = item.clone();
-            let downloads_for_task = Arc::clone(&downloads);
-            let active_tasks_for_task = Arc::clone(&active_tasks);
-            let notify_tx_for_task = notify_tx.clone();
-            let concurrency_control_for_task = Arc::clone(&concurrency_control);
-            
-            // Spawn the download task
-            let handle = tokio::spawn(async move {
-                // Acquire the permit inside the task to ensure it lives long enough
-                let _permit = concurrency_control_for_task.acquire().await.expect("Failed to acquire permit");
-                
-                // Execute the download
-                let result = execute_download(item_for_task, cancel_rx).await;
-                
-                // Update download status based on result
-                {
-                    let mut downloads_map = downloads_for_task.write().unwrap();
-                    
-                    if let Some(dl_item) = downloads_map.get_mut(&item_id) {
-                        match result {
-                            Ok(output_path) => {
-                                debug!("Download {} completed successfully", item_id);
-                                dl_item.mark_completed(Some(output_path));
-                            },
-                            Err(e) => {
-                                error!("Download {} failed: {}", item_id, e);
-                                dl_item.mark_failed(Some(e.to_string()));
-                            }
-                        }
-                    }
-                }
-                
-                // Remove from active tasks
-                {
-                    let mut tasks = active_tasks_for_task.lock().unwrap();
-                    tasks.remove(&item_id);
-                }
-                
-                // Notify listeners of state change
-                let _ = notify_tx_for_task.send(());
-            });
-            
-            // Store the task handle
-            {
-                let mut tasks = active_tasks.lock().unwrap();
-                tasks.insert(item.id.clone(), handle);
+    if repaired > 0 {
+        info!("Repaired {} stuck downloads", repaired);
+        let _ = save_queue_state(Arc::clone(ctx.downloads), Arc::clone(ctx.queue), ctx.state_path.to_path_buf()).await;
+        let _ = ctx.notify_tx.send(());
+    }
+}
+
+/// Plan what the retention policies expire in the queue and the history
+/// archive, and apply it
+async fn apply_retention(ctx: &CommandContext<'_>, policies: Vec<RetentionPolicy>) {
+    if policies.is_empty() {
+        return;
+    }
+    if let Err(e) = validate_policies(&policies) {
+        warn!("Skipping retention policies: {}", e);
+        return;
+    }
+    let queued = snapshot_items(ctx.downloads);
+    let history_path = match history_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Could not find the download history archive: {}", e);
+            return;
+        }
+    };
+    let plan = match tokio::task::spawn_blocking(move || {
+        let archived = load_history(&history_path).unwrap_or_else(|e| {
+            warn!("Could not read the download history archive: {}", e);
+            Vec::new()
+        });
+        plan_gc(&policies, &queued, &archived, Utc::now())
+    })
+    .await
+    {
+        Ok(plan) => plan,
+        Err(e) => {
+            warn!("Retention task failed: {}", e);
+            return;
+        }
+    };
+    collect_garbage(ctx, plan).await;
+}
+
+/// Delete expired files and remove expired queue entries. An entry is only
+/// removed if its download is still completed.
+async fn collect_garbage(ctx: &CommandContext<'_>, plan: Vec<GcItem>) {
+    if plan.is_empty() {
+        return;
+    }
+    let (plan, deleted) = match tokio::task::spawn_blocking(move || {
+        let deleted = delete_expired_files(&plan);
+        (plan, deleted)
+    })
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            warn!("Retention task failed: {}", e);
+            return;
+        }
+    };
+    
+    let removed = {
+        let mut queue_vec = ctx.queue.lock().unwrap();
+        let mut downloads_map = ctx.downloads.write().unwrap();
+        let mut removed = 0;
+        for expired in plan.iter().filter(|expired| expired.action == GcAction::RemoveEntry) {
+            if downloads_map.get(&expired.id).is_some_and(DownloadItem::is_completed) {
+                downloads_map.remove(&expired.id);
+                queue_vec.retain(|qid| *qid != expired.id);
+                removed += 1;
             }
-            
-            // Notify listeners
-            let _ = notify_tx.send(());
-            
-            // Process the next download non-recursively to avoid Send issues
-            let downloads_for_next = Arc::clone(&downloads);
-            let queue_for_next = Arc::clone(&queue);
-            let concurrency_for_next = Arc::clone(&concurrency_control);
-            let active_tasks_for_next = Arc::clone(&active_tasks);
-            let notify_tx_for_next = notify_tx.clone();
-            
-            // Use a static function that doesn't capture variables from its environment
-            tokio::spawn(process_queue_static(
-                downloads_for_next,
-                queue_for_next,
-                concurrency_for_next,
-                active_tasks_for_next,
-                notify_tx_for_next,
-            ));
-        } else {
-            debug!("No capacity for download {}, returning to queue", item.id);
-            // Put back in queue
-            let mut queue_vec = queue.lock().unwrap();
-            queue_vec.insert(0, next_id);
         }
+        removed
+    };
+    
+    if !deleted.is_empty() || removed > 0 {
+        info!("Retention policies deleted {} files and removed {} queue entries", deleted.len(), removed);
+        let _ = save_queue_state(Arc::clone(ctx.downloads), Arc::clone(ctx.queue), ctx.state_path.to_path_buf()).await;
+        let _ = ctx.notify_tx.send(());
     }
 }
 
-/// Processes the queue in a way that is Send-compatible
-async fn process_queue_static(
-    downloads: Arc<RwLock<HashMap<String, DownloadItem>>>,
+/// Move old finished downloads from the queue state into the history
+/// archive. If the archive can't be written they stay in the queue.
+async fn archive_finished(ctx: &CommandContext<'_>, max_age: chrono::Duration) {
+    let path = match history_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Could not find the download history archive: {}", e);
+            return;
+        }
+    };
+    let archived = {
+        let mut downloads_map = ctx.downloads.write().unwrap();
+        take_archivable(&mut downloads_map, max_age, Utc::now())
+    };
+    if archived.is_empty() {
+        return;
+    }
+    
+    let (archived, written) = match tokio::task::spawn_blocking(move || {
+        let written = append_history(&path, &archived);
+        (archived, written)
+    }).await {
+        Ok(result) => result,
+        Err(e) => {
+            warn!("History archive task failed: {}", e);
+            return;
+        }
+    };
+    
+    match written {
+        Ok(()) => {
+            info!("Archived {} finished downloads", archived.len());
+            let _ = save_queue_state(Arc::clone(ctx.downloads), Arc::clone(ctx.queue), ctx.state_path.to_path_buf()).await;
+            let _ = ctx.notify_tx.send(());
+        }
+        Err(e) => {
+            warn!("Failed to archive finished downloads: {}", e);
+            let mut downloads_map = ctx.downloads.write().unwrap();
+            for item in archived {
+                if !downloads_map.contains_key(&item.id) {
+                    downloads_map.insert(item.id.clone(), item);
+                }
+            }
+        }
+    }
+}
+
+/// Put a download in the pending queue: high and critical priority at the
+/// front, others at the back
+fn enqueue_by_priority(queue: &mut Vec<String>, id: &str, priority: DownloadPriority) {
+    queue.retain(|queued| queued != id);
+    if priority == DownloadPriority::High || priority == DownloadPriority::Critical {
+        queue.insert(0, id.to_string());
+    } else {
+        queue.push(id.to_string());
+    }
+}
+
+/// Apply a batch action to the named downloads. Downloads the action doesn't
+/// fit, such as pausing a finished one, are skipped.
+pub fn apply_batch_action(
+    queue: &mut Vec<String>,
+    downloads: &mut DownloadMap,
+    action: BatchAction,
+    ids: &[String],
+) -> BatchOutcome {
+    let mut outcome = BatchOutcome::default();
+    for id in ids {
+        let Some(item) = downloads.get_mut(id) else {
+            continue;
+        };
+        if outcome.changed.contains(id) {
+            continue;
+        }
+        let was_running = item.status == DownloadStatus::Downloading;
+        
+        let applied = match action {
+            BatchAction::Pause if item.is_active() => {
+                item.mark_paused();
+                item.speed = 0.0;
+                if let Some(token) = &item.cancel_token {
+                    let _ = token.send(());
+                }
+                queue.retain(|queued| queued != id);
+                true
+            }
+            BatchAction::Cancel if !item.is_finished() => {
+                item.cancel();
+                queue.retain(|queued| queued != id);
+                true
+            }
+            BatchAction::SetPriority(priority) if item.priority != priority => {
+                item.priority = priority;
+                if item.status == DownloadStatus::Queued && queue.contains(id) {
+                    enqueue_by_priority(queue, id, priority);
+                }
+                true
+            }
+            BatchAction::Retry if matches!(item.status, DownloadStatus::Failed | DownloadStatus::Canceled) => {
+                item.mark_requeued();
+                enqueue_by_priority(queue, id, item.priority);
+                true
+            }
+            _ => false,
+        };
+        
+        if applied {
+            outcome.changed.push(id.clone());
+            if was_running && matches!(action, BatchAction::Pause | BatchAction::Cancel) {
+                outcome.stopped.push(id.clone());
+            }
+        }
+    }
+    outcome
+}
+
+/// Rearrange the pending downloads named in `ids_in_order` among the queue
+/// slots they already occupy, leaving other downloads in place. Every ID
+/// must be a queued download, and no download may be moved ahead of one
+/// with a higher priority.
+pub fn apply_queue_order(
+    queue: &mut [String],
+    downloads: &DownloadMap,
+    ids_in_order: &[String],
+) -> Result<(), AppError> {
+    if ids_in_order.is_empty() {
+        return Err(AppError::ValidationError("No downloads to reorder".to_string()));
+    }
+    
+    let mut slots = Vec::with_capacity(ids_in_order.len());
+    let mut previous_priority = None;
+    for (index, id) in ids_in_order.iter().enumerate() {
+        if ids_in_order[..index].contains(id) {
+            return Err(AppError::ValidationError(format!("Download {} is listed twice", id)));
+        }
+        
+        let pending = downloads.get(id).filter(|item| item.status == DownloadStatus::Queued);
+        let priority = match (pending, queue.iter().position(|qid| qid == id)) {
+            (Some(item), Some(slot)) => {
+                slots.push(slot);
+                item.priority
+            }
+            _ => return Err(AppError::ValidationError(format!("Download {} is not waiting in the queue", id))),
+        };
+        
+        if previous_priority.replace(priority).is_some_and(|previous| priority > previous) {
+            return Err(AppError::ValidationError(format!(
+                "Download {} can't be placed behind a lower-priority download; change its priority instead",
+                id
+            )));
+        }
+    }
+    
+    slots.sort_unstable();
+    for (slot, id) in slots.into_iter().zip(ids_in_order) {
+        queue[slot] = id.clone();
+    }
+    Ok(())
+}
+
+/// Record the queue's current order as the downloads' manual positions, so
+/// the dispatch order keeps it
+fn pin_queue_positions(queue: &[String], downloads: &mut DownloadMap) {
+    for (position, id) in queue.iter().enumerate() {
+        if let Some(item) = downloads.get_mut(id) {
+            item.queue_position = Some(position as u64);
+        }
+    }
+}
+
+/// Put downloads paused at their deadline back in the queue once their
+/// resume time has come
+fn resume_due_downloads(downloads: &RwLock<DownloadMap>, queue: &Mutex<Vec<String>>) {
+    let now = Utc::now();
+    let mut queue_vec = queue.lock().unwrap();
+    let mut downloads_map = downloads.write().unwrap();
+    for (_, item) in downloads_map.matching_mut(|item| item.is_resume_due(now)) {
+        debug!("Resuming download {} after its deadline pause", item.id);
+        item.mark_resumed();
+        if !queue_vec.contains(&item.id) {
+            if item.priority == DownloadPriority::High || item.priority == DownloadPriority::Critical {
+                queue_vec.insert(0, item.id.clone());
+            } else {
+                queue_vec.push(item.id.clone());
+            }
+        }
+    }
+}
+
+/// How long until the first download paused at its deadline may resume
+fn next_resume_in(downloads: &RwLock<DownloadMap>) -> Option<Duration> {
+    let now = Utc::now();
+    downloads
+        .read()
+        .unwrap()
+        .values()
+        .filter(|item| item.is_paused())
+        .filter_map(|item| item.resume_at)
+        .min()
+        .map(|at| (at - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// How long until the first queued download held by its start time or
+/// window may start
+fn next_start_in(downloads: &RwLock<DownloadMap>) -> Option<Duration> {
+    let now = Local::now();
+    downloads
+        .read()
+        .unwrap()
+        .values()
+        .filter(|item| item.status == DownloadStatus::Queued)
+        .filter_map(|item| item.start_delay(&now))
+        .min()
+        .map(|delay| delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// A download slot, held by a download task for as long as it runs. It is
+/// given back and the scheduler woken when the task ends, even when aborted.
+struct DownloadSlot {
+    permit: Option<OwnedSemaphorePermit>,
+    schedule: Arc<Notify>,
+}
+
+impl Drop for DownloadSlot {
+    fn drop(&mut self) {
+        // Released first, so the woken scheduler finds the slot free
+        self.permit.take();
+        self.schedule.notify_one();
+    }
+}
+
+/// Start queued downloads until the queue is empty or every slot is taken
+#[tracing::instrument(name = "queue_tick", skip_all)]
+async fn check_and_process_queue(
+    downloads: Arc<RwLock<DownloadMap>>,
     queue: Arc<Mutex<Vec<String>>>,
     concurrency_control: Arc<Semaphore>,
     active_tasks: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
     notify_tx: broadcast::Sender<()>,
+    schedule: Arc<Notify>,
 ) {
-        // Process next download in queue
-        let mut next_download = None;
-        let mut next_id = String::new();
-        
-        // Get next download (similar logic to check_and_process_queue but standalone)
-        {
+    resume_due_downloads(&downloads, &queue);
+    {
+        // However downloads were added or moved, they start in dispatch order
+        let mut queue_vec = queue.lock().unwrap();
+        sort_queue_ids(&mut queue_vec, &downloads.read().unwrap());
+    }
+    if circuit_breaker::queue_held() {
+        debug!("Queue held after repeated network failures, not starting downloads");
+        return;
+    }
+    
+    loop {
+        // The slot is taken before an item leaves the queue, so nothing
+        // started ever waits for one
+        let Ok(permit) = Arc::clone(&concurrency_control).try_acquire_owned() else {
+            debug!("All download slots are busy");
+            return;
+        };
+        // Downloads waiting for their start time or window stay queued
+        let next_id = {
             let mut queue_vec = queue.lock().unwrap();
-            if !queue_vec.is_empty() {
-                next_id = queue_vec[0].clone();
-                queue_vec.remove(0);
-                
-                let downloads_map = downloads.read().unwrap();
-                next_download = downloads_map.get(&next_id).cloned();
-            }
-        }
+            let downloads_map = downloads.read().unwrap();
+            let now = Local::now();
+            let Some(index) = queue_vec
+                .iter()
+                .position(|id| downloads_map.get(id).map_or(true, |item| item.start_delay(&now).is_none()))
+            else {
+                return;
+            };
+            queue_vec.remove(index)
+        };
+        let Some(item) = downloads.read().unwrap().get(&next_id).cloned() else {
+            debug!("Skipping queued download {} that no longer exists", next_id);
+            continue;
+        };
+        
+        let slot = DownloadSlot {
+            permit: Some(permit),
+            schedule: Arc::clone(&schedule),
+        };
+        start_download(item, slot, &downloads, &active_tasks, &notify_tx);
+    }
+}
+
+/// Run a queued download in its own task, recording the result in the queue
+fn start_download(
+    mut item: DownloadItem,
+    slot: DownloadSlot,
+    downloads: &Arc<RwLock<DownloadMap>>,
+    active_tasks: &Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+    notify_tx: &broadcast::Sender<()>,
+) {
+    debug!("Starting download {}", item.id);
+    
+    // Mark as started and update in downloads map
+    item.mark_started();
+    let cancel_rx = item.create_cancel_token();
+    
+    {
+        let mut downloads_map = downloads.write().unwrap();
+        downloads_map.insert(item.id.clone(), item.clone());
+    }
+    
+    // Clone everything needed for the task
+    let item_id = item.id.clone();
+    let item_for_task = item.clone();
+    let downloads_for_task = Arc::clone(downloads);
+    let active_tasks_for_task = Arc::clone(active_tasks);
+    let notify_tx_for_task = notify_tx.clone();
+    
+    // Spawn the download task, traced from start to recording the result
+    let span = tracing::info_span!("download", id = %item.id, url = %item.url);
+    let handle = tokio::spawn(async move {
+        // Held until the task ends, however it ends
+        let _slot = slot;
+        
+        // Execute the download
+        let stderr_tail = StderrTail::new();
+        let (url, format) = (item_for_task.url.clone(), item_for_task.format.clone());
+        let result = execute_download_with_deadline(
+            item_for_task,
+            cancel_rx,
+            Arc::clone(&downloads_for_task),
+            notify_tx_for_task.clone(),
+            stderr_tail.clone(),
+        ).await;
+        let (duplicate_of, published_to) = match &result {
+            Ok(outcome) => (check_duplicate(outcome).await, publish_completed(outcome, url, format).await),
+            Err(_) => (None, Vec::new()),
+        };
         
-        // If we have a download and there's capacity, start it
-        if let Some(mut item) = next_download {
-            // Create a clone of the semaphore to avoid lifetime issues
-            let semaphore = Arc::clone(&concurrency_control);
+        // Update download status based on result
+        let history_entry = {
+            let _span = tracing::info_span!("record_result").entered();
+            let mut downloads_map = downloads_for_task.write().unwrap();
             
-            // Try to acquire a permit from the semaphore
-            if semaphore.available_permits() > 0 {
-                // Mark as started and update in downloads map
-                item.mark_started();
-                
-                let cancel_rx = item.create_cancel_token();
-                
-                {
-                    let mut downloads_map = downloads.write().unwrap();
-                    downloads_map.insert(item.id.clone(), item.clone());
-                }
-                
-                // Clone everything needed for the task
-                let item_id = item.id.clone();
-                let item_for_task = item.clone();
-                let downloads_for_task = Arc::clone(&downloads);
-                let active_tasks_for_task = Arc::clone(&active_tasks);
-                let notify_tx_for_task = notify_tx.clone();
-                let concurrency_control_for_task = Arc::clone(&concurrency_control);
-                
-                // Spawn the download task
-                let handle = tokio::spawn(async move {
-                    // Acquire permit inside the task
-                    let _permit = concurrency_control_for_task.acquire().await.expect("Failed to acquire permit");
-                    
-                    // Execute the download
-                    let result = execute_download(item_for_task, cancel_rx).await;
-                    
-                    // Update download status based on result
-                    {
-                        let mut downloads_map = downloads_for_task.write().unwrap();
-                        
-                        if let Some(dl_item) = downloads_map.get_mut(&item_id) {
-                            match result {
-                                Ok(output_path) => {
-                                    debug!("Download {} completed successfully", item_id);
-                                    dl_item.mark_completed(Some(output_path));
-                                },
-                                Err(e) => {
-                                    error!("Download {} failed: {}", item_id, e);
-                                    dl_item.mark_failed(Some(e.to_string()));
-                                }
-                            }
-                        }
+            if let Some(dl_item) = downloads_map.get_mut(&item_id) {
+                match result {
+                    Ok(outcome) => {
+                        debug!("Download {} completed successfully", item_id);
+                        circuit_breaker::record_download_result(None);
+                        dl_item.backend = outcome.backend;
+                        dl_item.duplicate_of = duplicate_of;
+                        dl_item.published_to = published_to;
+                        // The output path is yt-dlp's template; keep the file it wrote when known
+                        let saved = match outcome.file_path {
+                            Some(path) => path.to_string_lossy().into_owned(),
+                            None => outcome.output_path,
+                        };
+                        dl_item.mark_completed(Some(saved));
+                    },
+                    Err(AppError::DeadlineExceeded(reason)) if dl_item.deadline_action == DeadlineAction::Pause => {
+                        warn!("Download {} paused: {}", item_id, reason);
+                        dl_item.mark_deadline_paused(Utc::now());
                     }
-                    
-                    // Remove from active tasks
-                    {
-                        let mut tasks = active_tasks_for_task.lock().unwrap();
-                        tasks.remove(&item_id);
+                    Err(e) => {
+                        error!("Download {} failed: {}", item_id, e);
+                        dl_item.mark_failed(Some(e.to_string()));
+                        dl_item.error_report = Some(ErrorReport::new(&e).for_item(&item_id));
+                        dl_item.error_context = stderr_tail.lines();
+                        circuit_breaker::record_download_result(Some(classify_failure(&e.to_string(), &dl_item.error_context)));
                     }
-                    
-                    // Notify listeners of state change
-                    let _ = notify_tx_for_task.send(());
-                });
-                
-                // Store the task handle
-                {
-                    let mut tasks = active_tasks.lock().unwrap();
-                    tasks.insert(item.id.clone(), handle);
                 }
-                
-                // Notify listeners
-                let _ = notify_tx.send(());
-            } else {
-                // No capacity, put back in queue
-                let mut queue_vec = queue.lock().unwrap();
-                queue_vec.insert(0, next_id);
+            }
+            downloads_map.get(&item_id).and_then(HistoryEntry::from_item)
+        };
+        
+        // Keep the finished download in the history database
+        if let Some(entry) = history_entry {
+            let recorded = tokio::task::spawn_blocking(move || History::open_default()?.record(&entry)).await;
+            if let Ok(Err(e)) = recorded {
+                warn!("Could not record download {} in the history: {}", item_id, e);
             }
         }
+        
+        // Remove from active tasks
+        {
+            let mut tasks = active_tasks_for_task.lock().unwrap();
+            tasks.remove(&item_id);
+        }
+        
+        // Notify listeners of state change
+        let _ = notify_tx_for_task.send(());
+    }.instrument(span));
+    
+    // Store the task handle
+    {
+        let mut tasks = active_tasks.lock().unwrap();
+        tasks.insert(item.id.clone(), handle);
     }
+    
+    // Notify listeners
+    let _ = notify_tx.send(());
+}
 
-// Process_next_download has been replaced by the inline implementation in process_queue_static
+/// Build a callback that copies downloader progress into the queue entry,
+/// notifying listeners whenever a new speed sample is recorded
+fn progress_reporter(
+    id: String,
+    downloads: Arc<RwLock<DownloadMap>>,
+    notify_tx: broadcast::Sender<()>,
+) -> ProgressCallback {
+    Arc::new(move |downloaded, total, speed| {
+        let sampled = {
+            let mut downloads_map = downloads.write().unwrap();
+            match downloads_map.get_mut(&id) {
+                Some(item) => item.update_progress(downloaded, total, speed),
+                None => false,
+            }
+        };
+        
+        if sampled {
+            let _ = notify_tx.send(());
+        }
+    })
+}
 
 /// Execute a download and handle cancellation
+#[tracing::instrument(name = "download_attempt", skip_all, fields(url = %item.url, age_gate = ?item.age_gate_strategy))]
 async fn execute_download(
     item: DownloadItem,
     mut cancel_rx: broadcast::Receiver<()>,
-) -> Result<String, AppError> {
+    on_progress: ProgressCallback,
+    stderr_tail: StderrTail,
+) -> Result<DownloadOutcome, AppError> {
     // Launch the download
     use crate::downloader;
     
     // Create a variable to hold the download task
-    let url = item.url.clone();
+    let url = match &item.age_gate_strategy {
+        Some(strategy) => strategy.url(&item.url),
+        None => item.url.clone(),
+    };
     let quality = item.quality.clone();
+    let format_id = item.format_id.clone();
+    let format_preferences = item.format_preferences();
     let format_str = item.format.clone();
     let start_time = item.start_time.clone();
     let end_time = item.end_time.clone();
@@ -1423,6 +2855,19 @@ async fn execute_download(
     let output_dir = item.output_dir.clone();
     let force_download = item.force_download;
     let bitrate = item.bitrate.clone();
+    let transcode = item.transcode;
+    let ffmpeg_args = item.ffmpeg_args.clone();
+    let no_merge = item.no_merge;
+    let trim_silence = item.trim_silence;
+    let vertical = item.vertical;
+    let transliterate = item.transliterate;
+    let media_naming = item.media_naming;
+    let split_size = item.split_size;
+    let chapters = item.chapters.clone();
+    // The item's own proxy credentials; with isolation on, Tor gives it a
+    // circuit no other download shares
+    let proxy = load_config()?.proxy_template()?.map(|proxy| proxy.render(&item.id));
+    let request_headers = item.request_headers().with_proxy(proxy);
     let id = item.id.clone();
     
     // Save format for output path creation
@@ -1433,6 +2878,8 @@ async fn execute_download(
         downloader::download_video_free(
             &url,
             quality.as_deref(),
+            format_id.as_deref(),
+            format_preferences,
             &format_str,
             start_time.as_ref(),
             end_time.as_ref(),
@@ -1441,6 +2888,18 @@ async fn execute_download(
             output_dir.as_ref(),
             force_download,
             bitrate.as_ref(),
+            transcode,
+            &ffmpeg_args,
+            no_merge,
+            trim_silence,
+            vertical,
+            transliterate,
+            media_naming,
+            split_size,
+            chapters.as_deref(),
+            &request_headers,
+            Some(on_progress),
+            Some(stderr_tail),
         ).await
     });
     
@@ -1453,7 +2912,7 @@ async fn execute_download(
             match result {
                 Ok(download_result) => {
                     match download_result {
-                        Ok(path) => Ok(path),
+                        Ok(outcome) => Ok(outcome),
                         Err(e) => Err(e)
                     }
                 },
@@ -1471,19 +2930,397 @@ async fn execute_download(
     }
 }
 
+/// Run a download; when it fails like an outdated site extractor and
+/// `auto_update_deps` is enabled, update yt-dlp and retry it once
+async fn execute_download_with_update(
+    item: DownloadItem,
+    cancel_rx: broadcast::Receiver<()>,
+    downloads: Arc<RwLock<DownloadMap>>,
+    notify_tx: broadcast::Sender<()>,
+    stderr_tail: StderrTail,
+) -> Result<DownloadOutcome, AppError> {
+    // Subscribed before the first attempt so a cancel is never missed
+    let retry_cancel_rx = cancel_rx.resubscribe();
+    let on_progress = progress_reporter(item.id.clone(), Arc::clone(&downloads), notify_tx.clone());
+    let error = match execute_download(item.clone(), cancel_rx, on_progress, stderr_tail.clone()).await {
+        Err(e) if item.retry_count == 0 => e,
+        result => return result,
+    };
+
+    if classify_failure(&error.to_string(), &stderr_tail.lines()) != FailureKind::Extractor {
+        return Err(error);
+    }
+    if !load_config().map(|config| config.auto_update_deps).unwrap_or(false) {
+        warn!(
+            "Download {} failed with an extractor error; updating yt-dlp may fix it (enable auto_update_deps to do this automatically)",
+            item.id
+        );
+        return Err(error);
+    }
+
+    match tokio::task::spawn_blocking(update_ytdlp_once).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            warn!("Not retrying download {}: {}", item.id, e);
+            return Err(error);
+        }
+        Err(e) => {
+            warn!("yt-dlp update task failed: {}", e);
+            return Err(error);
+        }
+    }
+
+    debug!("Retrying download {} after updating yt-dlp", item.id);
+    {
+        let mut downloads_map = downloads.write().unwrap();
+        if let Some(dl_item) = downloads_map.get_mut(&item.id) {
+            dl_item.increment_retry_count();
+            dl_item.progress = 0.0;
+            dl_item.downloaded_bytes = 0;
+        }
+    }
+    let _ = notify_tx.send(());
+
+    let on_progress = progress_reporter(item.id.clone(), Arc::clone(&downloads), notify_tx);
+    execute_download(item, retry_cancel_rx, on_progress, stderr_tail).await
+}
+
+/// Run a download; when it stops at an age gate, retry it with each of
+/// [`fallback_strategies`] until one gets through. The strategy that
+/// worked is recorded in `age_gate_strategy`.
+async fn execute_download_with_age_gate(
+    item: DownloadItem,
+    cancel_rx: broadcast::Receiver<()>,
+    downloads: Arc<RwLock<DownloadMap>>,
+    notify_tx: broadcast::Sender<()>,
+    stderr_tail: StderrTail,
+) -> Result<DownloadOutcome, AppError> {
+    // Tells a cancel apart from a failure once an attempt returns
+    let mut cancelled_rx = cancel_rx.resubscribe();
+    // A strategy that worked before is tried first, as part of the item
+    let mut strategies = fallback_strategies(&item.url)
+        .into_iter()
+        .filter(|strategy| Some(strategy) != item.age_gate_strategy.as_ref());
+    let mut attempt = item.clone();
+
+    loop {
+        let error = match execute_download_with_update(
+            attempt.clone(),
+            cancel_rx.resubscribe(),
+            Arc::clone(&downloads),
+            notify_tx.clone(),
+            stderr_tail.clone(),
+        )
+        .await
+        {
+            Ok(outcome) => {
+                if let Some(dl_item) = downloads.write().unwrap().get_mut(&item.id) {
+                    dl_item.age_gate_strategy = attempt.age_gate_strategy.clone();
+                }
+                return Ok(outcome);
+            }
+            Err(e) => e,
+        };
+
+        let cancelled = !matches!(cancelled_rx.try_recv(), Err(broadcast::error::TryRecvError::Empty));
+        if cancelled || !is_age_gated(&error.to_string(), &stderr_tail.lines()) {
+            return Err(error);
+        }
+        let Some(strategy) = strategies.next() else {
+            return Err(error);
+        };
+
+        warn!("Download {} is age-restricted; trying the {}", item.id, strategy.describe());
+        stderr_tail.clear();
+        {
+            let mut downloads_map = downloads.write().unwrap();
+            if let Some(dl_item) = downloads_map.get_mut(&item.id) {
+                dl_item.progress = 0.0;
+                dl_item.downloaded_bytes = 0;
+            }
+        }
+        attempt.age_gate_strategy = Some(strategy);
+    }
+}
+
+/// Run a download, stopping it once it has run longer than its deadline.
+/// The overrun is reported as [`AppError::DeadlineExceeded`].
+async fn execute_download_with_deadline(
+    item: DownloadItem,
+    mut cancel_rx: broadcast::Receiver<()>,
+    downloads: Arc<RwLock<DownloadMap>>,
+    notify_tx: broadcast::Sender<()>,
+    stderr_tail: StderrTail,
+) -> Result<DownloadOutcome, AppError> {
+    let Some(deadline_secs) = item.deadline_secs else {
+        return execute_download_with_mirrors(item, cancel_rx, downloads, notify_tx, stderr_tail).await;
+    };
+
+    // The download listens on its own channel, fed by user cancels and the timer
+    let (stop_tx, stop_rx) = broadcast::channel(1);
+    let timed_out = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let timer = {
+        let timed_out = Arc::clone(&timed_out);
+        let id = item.id.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                Ok(()) = cancel_rx.recv() => {}
+                _ = tokio::time::sleep(Duration::from_secs(deadline_secs)) => {
+                    debug!("Download {} reached its deadline", id);
+                    timed_out.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+            }
+            let _ = stop_tx.send(());
+        })
+    };
+
+    let result = execute_download_with_mirrors(item, stop_rx, downloads, notify_tx, stderr_tail).await;
+    timer.abort();
+    if timed_out.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err(AppError::DeadlineExceeded(format!(
+            "not finished within {}",
+            crate::utils::format_duration(deadline_secs)
+        )));
+    }
+    result
+}
+
+/// Whether a failure may be down to the source, so a mirror could succeed.
+/// Problems with the options or the local setup fail the same way everywhere.
+pub fn should_try_mirror(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::DownloadError(_) | AppError::NetworkError { .. } | AppError::HttpError(_) | AppError::General(_)
+    )
+}
+
+/// Run a download from its URL, then from each mirror in turn until one
+/// succeeds. The source that worked is recorded in `downloaded_from`.
+async fn execute_download_with_mirrors(
+    item: DownloadItem,
+    cancel_rx: broadcast::Receiver<()>,
+    downloads: Arc<RwLock<DownloadMap>>,
+    notify_tx: broadcast::Sender<()>,
+    stderr_tail: StderrTail,
+) -> Result<DownloadOutcome, AppError> {
+    // Tells a cancel apart from a failure once an attempt returns
+    let mut cancelled_rx = cancel_rx.resubscribe();
+    let sources: Vec<String> = std::iter::once(item.url.clone()).chain(item.mirrors.iter().cloned()).collect();
+    let mut last_error = None;
+
+    for (index, source) in sources.iter().enumerate() {
+        if index > 0 {
+            warn!("Download {}: trying mirror {} of {}: {}", item.id, index, sources.len() - 1, source);
+            stderr_tail.clear();
+            let mut downloads_map = downloads.write().unwrap();
+            if let Some(dl_item) = downloads_map.get_mut(&item.id) {
+                dl_item.progress = 0.0;
+                dl_item.downloaded_bytes = 0;
+            }
+        }
+
+        let mut attempt = item.clone();
+        attempt.url = source.clone();
+        match execute_download_with_age_gate(
+            attempt,
+            cancel_rx.resubscribe(),
+            Arc::clone(&downloads),
+            notify_tx.clone(),
+            stderr_tail.clone(),
+        )
+        .await
+        {
+            Ok(outcome) => {
+                if let Some(dl_item) = downloads.write().unwrap().get_mut(&item.id) {
+                    dl_item.downloaded_from = Some(source.clone());
+                }
+                return Ok(outcome);
+            }
+            Err(e) => {
+                let cancelled = !matches!(cancelled_rx.try_recv(), Err(broadcast::error::TryRecvError::Empty));
+                if cancelled || !should_try_mirror(&e) {
+                    return Err(e);
+                }
+                warn!("Download {} failed from {}: {}", item.id, source, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| AppError::DownloadError("No source to download from".to_string())))
+}
+
+/// Serializable form of the queue, without runtime-specific fields
+#[derive(Serialize, Deserialize)]
+struct PersistedQueue {
+    downloads: Vec<DownloadItem>,
+}
+
+/// Append an HMAC signature line to the serialized queue state
+fn sign_queue_state(json: &str) -> Result<String, AppError> {
+    let signature = generate_hmac_signature(json.as_bytes(), &get_local_state_key())?;
+    Ok(format!("{}\n{}", json, general_purpose::STANDARD.encode(signature)))
+}
+
+/// Verify and parse queue state contents.
+///
+/// Files written before signing was introduced are plain JSON. They are
+/// accepted once, when `allow_unsigned` is set, with `force_download`
+/// cleared since it cannot be trusted.
+fn parse_queue_state(contents: &str, allow_unsigned: bool) -> Result<Vec<DownloadItem>, AppError> {
+    let contents = contents.trim_end();
+
+    if let Some((json, signature_b64)) = contents.rsplit_once('\n') {
+        if let Ok(signature) = general_purpose::STANDARD.decode(signature_b64.trim()) {
+            if !verify_hmac_signature(json.as_bytes(), &signature, &get_local_state_key())? {
+                return Err(AppError::SecurityViolation);
+            }
+            let data: PersistedQueue = serde_json::from_str(json).map_err(AppError::JsonError)?;
+            return Ok(data.downloads);
+        }
+    }
+
+    let data: PersistedQueue = serde_json::from_str(contents).map_err(AppError::JsonError)?;
+    if !allow_unsigned {
+        warn!("Queue state is unsigned, but signed state was written before");
+        return Err(AppError::SecurityViolation);
+    }
+    warn!("Queue state is unsigned; it will be signed on the next save");
+    Ok(data
+        .downloads
+        .into_iter()
+        .map(|mut item| {
+            item.force_download = false;
+            item
+        })
+        .collect())
+}
+
+/// Read and verify a single queue state file
+fn read_queue_state_file(path: &Path, allow_unsigned: bool) -> Result<Vec<DownloadItem>, AppError> {
+    let contents = read_state_file(path)?;
+    parse_queue_state(&contents, allow_unsigned)
+}
+
+/// Unsigned state is only migrated while no signed state or backup was ever
+/// written; afterwards it can only come from tampering
+fn unsigned_state_allowed(state_path: &Path) -> bool {
+    !get_queue_signed_marker_path(state_path).exists() && !get_queue_backup_path(state_path).exists()
+}
+
+/// Read the queue state, falling back to the backup copy when the main file
+/// is corrupted or has been tampered with. With `quarantine`, which only the
+/// queue's owner may set, the bad file is also moved aside; readers leave the
+/// files alone, since the owner may be about to write them.
+fn read_queue_state_with_fallback(state_path: &Path, quarantine: bool) -> Result<Vec<DownloadItem>, AppError> {
+    let err = match read_queue_state_file(state_path, unsigned_state_allowed(state_path)) {
+        Ok(items) => return Ok(items),
+        // Unreadable isn't invalid: e.g. the keychain holding the encryption key may be locked
+        Err(e) if !matches!(e, AppError::JsonError(_) | AppError::SecurityViolation) => return Err(e),
+        Err(e) => e,
+    };
+    error!("Queue state at {:?} is invalid: {}", state_path, err);
+
+    // Keep the damaged file around for inspection instead of overwriting it on the next save
+    if quarantine {
+        let corrupt_path = state_path.with_extension("json.corrupt");
+        if let Err(e) = fs::rename(state_path, &corrupt_path) {
+            warn!("Failed to move invalid queue state aside: {}", e);
+        }
+    }
+
+    let backup_path = get_queue_backup_path(state_path);
+    if backup_path.exists() {
+        match read_queue_state_file(&backup_path, false) {
+            Ok(items) => {
+                warn!("Recovered {} downloads from queue state backup {:?}", items.len(), backup_path);
+                return Ok(items);
+            }
+            Err(e) => error!("Queue state backup {:?} is also invalid: {}", backup_path, e),
+        }
+    }
+
+    Err(err)
+}
+
+/// Minimum time between queue saves; requests in between are merged into one
+pub const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Merges bursts of queue save requests into one write
+#[derive(Debug, Clone)]
+pub struct SaveDebounce {
+    interval: Duration,
+    last_save: Option<Instant>,
+    pending: bool,
+}
+
+impl SaveDebounce {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last_save: None, pending: false }
+    }
+
+    fn quiet_since(&self, now: Instant) -> bool {
+        self.last_save.map_or(true, |last| now.duration_since(last) >= self.interval)
+    }
+
+    /// Whether a requested save can run now. If not, it is remembered and
+    /// becomes due once the interval has passed.
+    pub fn request(&mut self, now: Instant) -> bool {
+        if self.quiet_since(now) {
+            return true;
+        }
+        self.pending = true;
+        false
+    }
+
+    /// Record that the state was just written
+    pub fn saved(&mut self, now: Instant) {
+        self.last_save = Some(now);
+        self.pending = false;
+    }
+
+    /// Whether a deferred save should run now
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.pending && self.quiet_since(now)
+    }
+
+    /// How long until a deferred save is due, if one is waiting
+    pub fn due_in(&self, now: Instant) -> Option<Duration> {
+        if !self.pending {
+            return None;
+        }
+        Some(self.last_save.map_or(Duration::ZERO, |last| (last + self.interval).saturating_duration_since(now)))
+    }
+}
+
 /// Save queue state to disk
+#[tracing::instrument(name = "save_queue", skip_all)]
 async fn save_queue_state(
-    downloads: Arc<RwLock<HashMap<String, DownloadItem>>>,
+    downloads: Arc<RwLock<DownloadMap>>,
+    queue: Arc<Mutex<Vec<String>>>,
     state_path: PathBuf,
 ) -> Result<(), AppError> {
-    // Create a serializable version of downloads without runtime-specific fields
-    #[derive(Serialize)]
-    struct SerializableQueue {
-        downloads: Vec<DownloadItem>,
-    }
-    
+    tokio::task::spawn_blocking(move || persist_queue_state(&downloads, &queue, &state_path))
+        .await
+        .map_err(|e| AppError::General(format!("Failed to save queue state: {}", e)))?
+}
+
+/// Held while the state file is written, so the processor's final save and a
+/// blocking flush never share the temporary file
+static PERSIST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Write the queue state file, blocking. Poisoned locks are still read, so
+/// the state can be saved after a panic elsewhere.
+fn persist_queue_state(
+    downloads: &RwLock<DownloadMap>,
+    queue: &Mutex<Vec<String>>,
+    state_path: &Path,
+) -> Result<(), AppError> {
+    let _persisting = PERSIST_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
     let downloads_data = {
-        let downloads_map = downloads.read().unwrap();
+        let queue_vec = queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let downloads_map = downloads.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let queue_position = |id: &str| queue_vec.iter().position(|qid| qid == id).unwrap_or(usize::MAX);
         
         let mut items: Vec<DownloadItem> = downloads_map.values().cloned().collect();
         
@@ -1495,7 +3332,9 @@ async fn save_queue_state(
                 (DownloadStatus::Downloading, _) => std::cmp::Ordering::Less,
                 (_, DownloadStatus::Downloading) => std::cmp::Ordering::Greater,
                 
-                (DownloadStatus::Queued, DownloadStatus::Queued) => b.priority.cmp(&a.priority),
+                // Queued downloads keep their queue order, e.g. after a reorder
+                (DownloadStatus::Queued, DownloadStatus::Queued) => b.priority.cmp(&a.priority)
+                    .then_with(|| queue_position(&a.id).cmp(&queue_position(&b.id))),
                 (DownloadStatus::Queued, _) => std::cmp::Ordering::Less,
                 (_, DownloadStatus::Queued) => std::cmp::Ordering::Greater,
                 
@@ -1508,7 +3347,7 @@ async fn save_queue_state(
             }
         });
         
-        SerializableQueue {
+        PersistedQueue {
             downloads: items,
         }
     };
@@ -1517,20 +3356,35 @@ async fn save_queue_state(
     let json = serde_json::to_string_pretty(&downloads_data)
         .map_err(AppError::JsonError)?;
     
-    // Write to file - spawn a tokio task for this
-    let path_str = state_path.to_string_lossy().to_string();
-    tokio::task::spawn_blocking(move || {
-        std::fs::write(state_path, json)
-    }).await.map_err(|e| AppError::General(format!("Failed to save queue state: {}", e)))?
-        .map_err(AppError::IoError)?;
+    let contents = sign_queue_state(&json)?;
+    
+    // Keep the previous state as a backup, but only if its signature still
+    // verifies, so a damaged file never replaces a good backup
+    if read_queue_state_file(state_path, false).is_ok() {
+        let backed_up = fs::read(state_path)
+            .map_err(AppError::from)
+            .and_then(|contents| write_atomic(&get_queue_backup_path(state_path), &contents));
+        if let Err(e) = backed_up {
+            warn!("Failed to back up queue state: {}", e);
+        }
+    }
+    write_state_file(state_path, &contents)?;
     
-    debug!("Queue state saved to {}", path_str);
+    // From now on unsigned state is never migrated again
+    let marker_path = get_queue_signed_marker_path(state_path);
+    if !marker_path.exists() {
+        if let Err(e) = write_atomic(&marker_path, b"") {
+            warn!("Failed to record that queue state is signed: {}", e);
+        }
+    }
+    
+    debug!("Queue state saved to {}", state_path.display());
     Ok(())
 }
 
 /// Load queue state from disk
 async fn load_queue_state(
-    downloads: Arc<RwLock<HashMap<String, DownloadItem>>>,
+    downloads: Arc<RwLock<DownloadMap>>,
     queue: Arc<Mutex<Vec<String>>>,
     state_path: PathBuf,
 ) -> Result<(), AppError> {
@@ -1539,44 +3393,31 @@ async fn load_queue_state(
         return Ok(());
     }
     
-    // Load JSON from file
+    // Load and verify the state file, falling back to the backup copy
     let path_str = state_path.to_string_lossy().to_string();
-    let json = tokio::fs::read_to_string(&state_path)
+    let loaded = tokio::task::spawn_blocking(move || read_queue_state_with_fallback(&state_path, true))
         .await
-        .map_err(AppError::IoError)?;
-    
-    // Deserialize
-    #[derive(Deserialize)]
-    struct SerializableQueue {
-        downloads: Vec<DownloadItem>,
-    }
-    
-    let data: SerializableQueue = serde_json::from_str(&json)
-        .map_err(AppError::JsonError)?;
+        .map_err(|e| AppError::General(format!("Failed to load queue state: {}", e)))??;
     
     // Update downloads map and queue
     {
-        let mut downloads_map = downloads.write().unwrap();
         let mut queue_vec = queue.lock().unwrap();
+        let mut downloads_map = downloads.write().unwrap();
         
         // Clear existing data
         downloads_map.clear();
         queue_vec.clear();
         
         // Add loaded items
-        for mut item in data.downloads {
+        for mut item in loaded {
             // Reset status for active downloads (they weren't properly closed)
             if item.status == DownloadStatus::Downloading {
                 item.status = DownloadStatus::Queued;
             }
             
-            // Add to queue if active or paused
+            // Queued downloads were saved highest priority first, in queue order
             if item.status == DownloadStatus::Queued {
-                if item.priority == DownloadPriority::High || item.priority == DownloadPriority::Critical {
-                    queue_vec.insert(0, item.id.clone());
-                } else {
-                    queue_vec.push(item.id.clone());
-                }
+                queue_vec.push(item.id.clone());
             }
             
             // Add to downloads map
@@ -1588,17 +3429,97 @@ async fn load_queue_state(
     Ok(())
 }
 
+/// Read the downloads recorded in the persisted queue state without starting the queue
+pub fn load_saved_downloads() -> Result<Vec<DownloadItem>, AppError> {
+    load_saved_downloads_from(&get_queue_state_path())
+}
+
+/// Read the downloads recorded in a queue state file, falling back to its
+/// backup. The files are left as they are, even when invalid.
+pub fn load_saved_downloads_from(state_path: &Path) -> Result<Vec<DownloadItem>, AppError> {
+    if !state_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    read_queue_state_with_fallback(state_path, false)
+}
+
+/// All downloads this install knows about: the live queue if it is running,
+/// otherwise the persisted queue state, followed by the history archive
+pub fn get_known_downloads() -> Result<Vec<DownloadItem>, AppError> {
+    let mut items = get_all_downloads();
+    if items.is_empty() {
+        items = load_saved_downloads()?;
+    }
+    
+    let archived = load_history(&history_path()?).unwrap_or_else(|e| {
+        warn!("Could not read the download history archive: {}", e);
+        Vec::new()
+    });
+    // A download in the queue is newer than its archived copy
+    let live: HashSet<String> = items.iter().map(|item| item.id.clone()).collect();
+    items.extend(archived.into_iter().filter(|item| !live.contains(&item.id)));
+    Ok(items)
+}
+
+/// Find a download by full ID or unique ID prefix (as shown by `queue list`)
+pub fn find_saved_download(id: &str) -> Result<Option<DownloadItem>, AppError> {
+    // The running queue answers an exact ID without copying every download
+    if other_instance().is_none() {
+        if let Some(item) = DOWNLOAD_QUEUE.get().and_then(|queue| queue.get_download(id.to_string())) {
+            return Ok(Some(item));
+        }
+    }
+
+    let items = get_known_downloads()?;
+
+    if let Some(item) = items.iter().find(|item| item.id == id) {
+        return Ok(Some(item.clone()));
+    }
+
+    let mut matches: Vec<DownloadItem> = items
+        .into_iter()
+        .filter(|item| item.id.starts_with(id))
+        .collect();
+
+    match matches.len() {
+        0 => Ok(None),
+        1 => Ok(matches.pop()),
+        n => Err(AppError::ValidationError(format!(
+            "Download ID '{}' is ambiguous ({} matches). Use the full ID.",
+            id, n
+        ))),
+    }
+}
+
 /// Initialize the download manager
 pub async fn init_download_manager() -> Result<Arc<DownloadQueue>, AppError> {
-    // Create the download queue
-    let queue = Arc::new(DownloadQueue::new(3));
+    // Create the download queue with the configured concurrency
+    let max_concurrent = load_config()
+        .ok()
+        .and_then(|config| config.max_concurrent_downloads)
+        .filter(|max| *max > 0)
+        .unwrap_or(DEFAULT_CONCURRENT_DOWNLOADS);
+    let queue = Arc::new(DownloadQueue::new(max_concurrent));
     
-    // Start the queue processor
-    queue.start().await?;
+    // Only the process owning the queue runs downloads and writes its state
+    if other_instance().is_none() {
+        queue.start().await?;
+    }
     
     Ok(queue)
 }
 
+/// Start running the queue in a process that was read-only until it took
+/// the instance lock over, from the state the previous owner saved
+#[allow(dead_code)]
+pub async fn take_over_queue() -> Result<(), AppError> {
+    ensure_queue_owner()?;
+    let queue = get_download_queue().await;
+    queue.start().await?;
+    queue.load_state().await
+}
+
 /// Global download queue instance
 static DOWNLOAD_QUEUE: Lazy<tokio::sync::OnceCell<Arc<DownloadQueue>>> = 
     Lazy::new(tokio::sync::OnceCell::new);
@@ -1611,17 +3532,18 @@ pub async fn get_download_queue() -> Arc<DownloadQueue> {
             Err(e) => {
                 error!("Failed to initialize download manager: {}", e);
                 // Fallback to a new empty queue
-                Arc::new(DownloadQueue::new(3))
+                Arc::new(DownloadQueue::new(DEFAULT_CONCURRENT_DOWNLOADS))
             }
         }
     }).await.clone()
 }
 
-/// Add a download to the global queue
 /// Download options struct to replace multiple parameters
 pub struct DownloadOptions<'a> {
     pub url: &'a str,
     pub quality: Option<&'a str>,
+    pub format_id: Option<&'a str>,
+    pub format_preferences: FormatPreferences,
     pub format: &'a str,
     pub start_time: Option<&'a String>,
     pub end_time: Option<&'a String>,
@@ -1630,7 +3552,22 @@ pub struct DownloadOptions<'a> {
     pub output_dir: Option<&'a String>,
     pub force_download: bool,
     pub bitrate: Option<&'a String>,
+    pub transcode: Option<TranscodeKind>,
+    pub ffmpeg_args: &'a [String],
+    pub no_merge: bool,
+    pub trim_silence: bool,
+    pub vertical: VerticalOptions,
+    pub transliterate: bool,
+    pub media_naming: Option<MediaNaming>,
+    pub split_size: Option<u64>,
+    pub chapters: Option<&'a str>,
+    pub request_headers: RequestHeaders,
     pub priority: Option<DownloadPriority>,
+    pub mirrors: &'a [String],
+    pub tags: &'a [String],
+    pub deadline_secs: Option<u64>,
+    pub deadline_action: DeadlineAction,
+    pub resume_after_secs: Option<u64>,
 }
 
 impl Default for DownloadOptions<'_> {
@@ -1638,6 +3575,8 @@ impl Default for DownloadOptions<'_> {
         Self {
             url: "",
             quality: None,
+            format_id: None,
+            format_preferences: FormatPreferences::default(),
             format: "mp4",
             start_time: None,
             end_time: None,
@@ -1646,22 +3585,77 @@ impl Default for DownloadOptions<'_> {
             output_dir: None,
             force_download: false,
             bitrate: None,
+            transcode: None,
+            ffmpeg_args: &[],
+            no_merge: false,
+            trim_silence: false,
+            vertical: VerticalOptions::default(),
+            transliterate: false,
+            media_naming: None,
+            split_size: None,
+            chapters: None,
+            request_headers: RequestHeaders::default(),
             priority: None,
+            mirrors: &[],
+            tags: &[],
+            deadline_secs: None,
+            deadline_action: DeadlineAction::Fail,
+            resume_after_secs: None,
         }
     }
 }
 
-pub async fn add_download_to_queue(
-    options: DownloadOptions<'_>,
-) -> Result<String, AppError> {
-    let queue = get_download_queue().await;
-    
-    // Create download item
-    let mut builder = DownloadItem::builder(options.url, options.format)
+/// A download that has been validated and probed but not queued yet, so
+/// the CLI and GUI can show what will be added and stop before anything is
+#[derive(Debug, Clone)]
+pub struct PreparedDownload {
+    /// The item as it will be queued, with title and estimated size filled in
+    pub item: DownloadItem,
+    /// What probing the URL found; `None` for playlists and clips, or when
+    /// the probe failed
+    pub estimate: Option<MediaEstimate>,
+    /// Pre-flight problems such as low disk space; they don't stop the download
+    pub warnings: Vec<String>,
+    /// ID of a pending download of the same video in the same format
+    pub conflict: Option<String>,
+}
+
+/// A pending download of the same video in the same format
+fn find_conflict(queue: &DownloadQueue, url: &str, format: &str) -> Option<String> {
+    let video_key = crate::video_id::video_key(url);
+    queue
+        .get_all_downloads()
+        .into_iter()
+        .find(|item| {
+            (item.is_active() || item.is_paused())
+                && item.format == format
+                && crate::video_id::video_key(&item.url) == video_key
+        })
+        .map(|item| item.id)
+}
+
+/// A download item carrying every option except those found by probing
+fn item_builder(options: &DownloadOptions<'_>, url: &str) -> DownloadItemBuilder {
+    let mut builder = DownloadItem::builder(url, options.format)
         .quality(options.quality)
+        .format_id(options.format_id)
+        .format_preferences(options.format_preferences)
         .playlist(options.use_playlist)
         .subtitles(options.download_subtitles)
-        .force_download(options.force_download);
+        .force_download(options.force_download)
+        .transcode(options.transcode)
+        .ffmpeg_args(options.ffmpeg_args)
+        .no_merge(options.no_merge)
+        .trim_silence(options.trim_silence)
+        .vertical(options.vertical)
+        .transliterate(options.transliterate)
+        .media_naming(options.media_naming)
+        .split_size(options.split_size)
+        .chapters(options.chapters)
+        .mirrors(options.mirrors)
+        .tags(options.tags)
+        .deadline(options.deadline_secs, options.deadline_action, options.resume_after_secs)
+        .request_headers(options.request_headers.clone());
     
     if let Some(dir) = options.output_dir {
         builder = builder.output_dir(Some(dir));
@@ -1683,7 +3677,95 @@ pub async fn add_download_to_queue(
         builder = builder.priority(p);
     }
     
-    let item = builder.build();
+    builder
+}
+
+/// First phase of adding a download: validate the options, look up the
+/// title and expected size, and check the queue for the same video.
+/// Nothing is added; pass the result to [`enqueue`].
+pub async fn prepare_download(
+    options: DownloadOptions<'_>,
+) -> Result<PreparedDownload, AppError> {
+    if options.url.trim().is_empty() {
+        return Err(AppError::ValidationError("No URL given".to_string()));
+    }
+    crate::utils::validate_time_range(
+        options.start_time.map(String::as_str),
+        options.end_time.map(String::as_str),
+        None,
+    )?;
+    if let Some(bitrate) = options.bitrate {
+        crate::utils::parse_bitrate(bitrate, options.format)?;
+    }
+    
+    let queue = get_download_queue().await;
+    let conflict = find_conflict(&queue, options.url, options.format);
+    
+    // Create download item
+    let mut builder = item_builder(&options, options.url);
+    
+    // Requested chapters must exist before the download is queued
+    if let Some(selection) = options.chapters {
+        resolve_chapters(options.url, selection, &options.request_headers).await?;
+    }
+    
+    // Estimate the size up front so the queue can show expected totals.
+    // Playlists and clips can't be sized from a single format, so skip them.
+    let mut estimate = None;
+    let mut warnings = Vec::new();
+    if !options.use_playlist && options.start_time.is_none() && options.end_time.is_none() && options.chapters.is_none() {
+        match crate::downloader::estimate_download(
+            options.url,
+            options.format,
+            options.quality,
+            options.format_id,
+            options.format_preferences,
+            &options.request_headers,
+        )
+        .await {
+            Ok(found) => {
+                if let Some(total_bytes) = found.total_bytes {
+                    let folder_type = if options.format == "mp3" { "audio" } else { "videos" };
+                    if let Ok(dir) = crate::utils::download_dir_path(options.output_dir.map(String::as_str), "rustloader", folder_type) {
+                        warnings = crate::guardrails::check_download(total_bytes, options.transcode, &dir);
+                    }
+                }
+                builder = builder
+                    .title(found.title.as_deref())
+                    .estimated_size(found.total_bytes);
+                estimate = Some(found);
+            }
+            Err(e) => debug!("Could not estimate size for {}: {}", options.url, e),
+        }
+    }
+    
+    Ok(PreparedDownload {
+        item: builder.build(),
+        estimate,
+        warnings,
+        conflict,
+    })
+}
+
+/// Second phase of adding a download: put a prepared download in the queue.
+/// The queue is checked for the same video again, since it may have
+/// changed while the caller was asking for confirmation.
+pub async fn enqueue(prepared: PreparedDownload) -> Result<String, AppError> {
+    let queue = get_download_queue().await;
+    let item = prepared.item;
+    
+    // Don't queue the same video in the same format twice while one is still pending
+    if let Some(existing) = find_conflict(&queue, &item.url, &item.format) {
+        return Err(AppError::ValidationError(format!(
+            "This video is already in the queue (ID: {})",
+            existing
+        )));
+    }
+    
+    if let Some(estimate) = &prepared.estimate {
+        crate::recent_urls::remember_url(&item.url, estimate.channel.as_deref());
+    }
+    
     let id = item.id.clone();
     
     // Add to queue
@@ -1692,6 +3774,91 @@ pub async fn add_download_to_queue(
     Ok(id)
 }
 
+/// Add a download to the global queue without confirmation. Pre-flight
+/// warnings are only logged, since nobody is asked about them.
+pub async fn add_download_to_queue(
+    options: DownloadOptions<'_>,
+) -> Result<String, AppError> {
+    let url = options.url.to_string();
+    let prepared = prepare_download(options).await?;
+    for warning in &prepared.warnings {
+        warn!("{}: {}", url, warning);
+    }
+    enqueue(prepared).await
+}
+
+/// A playlist whose videos have been listed but not queued yet
+#[derive(Debug, Clone)]
+pub struct PreparedPlaylist {
+    pub title: Option<String>,
+    /// One download per video, in playlist order
+    pub downloads: Vec<PreparedDownload>,
+}
+
+/// First phase of queueing a playlist video by video: list the playlist
+/// and prepare a download of each video with the playlist's options, so
+/// every video can be paused, cancelled and prioritized on its own. The
+/// videos aren't probed one by one, so they have no size estimate.
+/// Nothing is added; pass each download to [`enqueue`].
+pub async fn prepare_playlist(
+    options: DownloadOptions<'_>,
+) -> Result<PreparedPlaylist, AppError> {
+    if options.url.trim().is_empty() {
+        return Err(AppError::ValidationError("No URL given".to_string()));
+    }
+    crate::utils::validate_time_range(
+        options.start_time.map(String::as_str),
+        options.end_time.map(String::as_str),
+        None,
+    )?;
+    if let Some(bitrate) = options.bitrate {
+        crate::utils::parse_bitrate(bitrate, options.format)?;
+    }
+    
+    let playlist = fetch_playlist(options.url, &options.request_headers).await?;
+    if playlist.entries.is_empty() {
+        return Err(AppError::ValidationError(
+            "No videos found in this playlist".to_string(),
+        ));
+    }
+    
+    let queue = get_download_queue().await;
+    let downloads = playlist
+        .entries
+        .iter()
+        .map(|entry| PreparedDownload {
+            item: item_builder(&options, &entry.url)
+                .playlist(false)
+                .title(entry.title.as_deref())
+                .build(),
+            estimate: None,
+            warnings: Vec::new(),
+            conflict: find_conflict(&queue, &entry.url, options.format),
+        })
+        .collect();
+    
+    Ok(PreparedPlaylist {
+        title: playlist.title,
+        downloads,
+    })
+}
+
+/// Put a recorded download back in the queue under its original ID and
+/// options, so yt-dlp can continue from the partial files it left behind
+pub async fn requeue_download(item: &DownloadItem) -> Result<(), AppError> {
+    let queue = get_download_queue().await;
+    
+    match queue.get_download(item.id.clone()) {
+        Some(existing) if existing.is_paused() => queue.resume_download(&item.id).await,
+        Some(existing) if existing.is_active() => Ok(()),
+        _ => {
+            let mut item = item.clone();
+            item.mark_requeued();
+            queue.add_download(item).await
+        }
+    }
+}
+
 /// Pause all downloads
 pub async fn pause_all_downloads() -> Result<(), AppError> {
     let queue = get_download_queue().await;
@@ -1728,14 +3895,115 @@ pub async fn set_download_priority(id: &str, priority: DownloadPriority) -> Resu
     queue.set_priority(id, priority).await
 }
 
+/// Change when a queued or paused download may start
+pub async fn schedule_download(id: &str, change: ScheduleChange) -> Result<(), AppError> {
+    let queue = get_download_queue().await;
+    queue.schedule(id, change).await
+}
+
+/// Reorder pending downloads, e.g. after a drag and drop in the GUI
+#[allow(dead_code)]
+pub async fn reorder_queue(ids_in_order: Vec<String>) -> Result<(), AppError> {
+    let queue = get_download_queue().await;
+    queue.reorder(ids_in_order).await
+}
+
+/// Apply an action to several downloads at once
+#[allow(dead_code)]
+pub async fn batch_downloads(action: BatchAction, ids: Vec<String>) -> Result<(), AppError> {
+    let queue = get_download_queue().await;
+    queue.apply_batch(action, ids).await
+}
+
+/// Undo the latest clear or bulk cancel, returning what was undone
+pub async fn undo_queue_operation() -> Result<Option<QueueSnapshot>, AppError> {
+    let queue = get_download_queue().await;
+    queue.undo().await
+}
+
+/// Queue operations that can be undone, oldest first
+pub async fn get_undo_history() -> Result<Vec<QueueSnapshot>, AppError> {
+    let queue = get_download_queue().await;
+    queue.undo_history()
+}
+
+/// Get the recent speed samples of a download, oldest first
+#[allow(dead_code)]
+pub fn get_speed_history(id: &str) -> Vec<SpeedSample> {
+    DOWNLOAD_QUEUE
+        .get()
+        .and_then(|queue| queue.get_speed_history(id))
+        .unwrap_or_default()
+}
+
+/// The queue state file as last read in read-only mode
+struct SavedStateCache {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    downloads: Vec<Arc<DownloadItem>>,
+}
+
+static SAVED_STATE_CACHE: Lazy<Mutex<Option<SavedStateCache>>> = Lazy::new(|| Mutex::new(None));
+
+/// The downloads in the saved queue state. The file is only read and parsed
+/// again once the owning process has written it since the last call.
+fn saved_downloads_snapshot() -> Vec<Arc<DownloadItem>> {
+    let path = get_queue_state_path();
+    let stamp = fs::metadata(&path).and_then(|metadata| Ok((metadata.modified()?, metadata.len()))).ok();
+    let mut cache = SAVED_STATE_CACHE.lock().unwrap();
+    if let (Some(cached), Some((modified, len))) = (cache.as_ref(), stamp) {
+        if cached.path == path && cached.modified == modified && cached.len == len {
+            return cached.downloads.clone();
+        }
+    }
+    
+    let downloads: Vec<Arc<DownloadItem>> = load_saved_downloads_from(&path)
+        .unwrap_or_default()
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+    *cache = stamp.map(|(modified, len)| SavedStateCache { path, modified, len, downloads: downloads.clone() });
+    downloads
+}
+
 /// Get a list of all downloads
 pub fn get_all_downloads() -> Vec<DownloadItem> {
+    // Another process owns the queue; show the state it last saved
+    if other_instance().is_some() {
+        return saved_downloads_snapshot().into_iter().map(Arc::unwrap_or_clone).collect();
+    }
+    
     match DOWNLOAD_QUEUE.get() {
         Some(queue) => queue.get_all_downloads(),
         None => Vec::new(),
     }
 }
 
+/// All downloads as they are now, shared rather than copied; cheaper than
+/// [`get_all_downloads`] for frequent polling of a large queue
+pub fn downloads_snapshot() -> Vec<Arc<DownloadItem>> {
+    // Another process owns the queue; show the state it last saved
+    if other_instance().is_some() {
+        return saved_downloads_snapshot();
+    }
+    
+    match DOWNLOAD_QUEUE.get() {
+        Some(queue) => queue.snapshot(),
+        None => Vec::new(),
+    }
+}
+
+/// Bytes still to be downloaded across all unfinished downloads,
+/// based on known or estimated sizes
+pub fn get_remaining_bytes() -> u64 {
+    downloads_snapshot()
+        .iter()
+        .filter(|item| !item.is_finished())
+        .map(|item| item.total_bytes.saturating_sub(item.downloaded_bytes))
+        .sum()
+}
+
 /// Get download status by ID
 #[allow(dead_code)]
 pub fn get_download_status(id: &str) -> Option<DownloadStatus> {
@@ -1747,6 +4015,64 @@ pub fn get_download_status(id: &str) -> Option<DownloadStatus> {
     }
 }
 
+/// Watcher running the `--when-done` action, if one is set
+static COMPLETION_WATCHER: Lazy<Mutex<Option<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Set the action to run once every active download has finished, or clear
+/// it with None. The action runs once, after the queue next goes idle; if
+/// nothing is queued yet it waits for downloads to be added and finish.
+pub async fn set_queue_completion_action(action: Option<CompletionAction>) -> Result<(), AppError> {
+    ensure_queue_owner()?;
+    if let Some(action) = &action {
+        action.validate()?;
+    }
+    let queue = get_download_queue().await;
+
+    let mut watcher = COMPLETION_WATCHER.lock().unwrap();
+    if let Some(previous) = watcher.take() {
+        previous.abort();
+    }
+    if let Some(action) = action {
+        *watcher = Some(tokio::spawn(watch_for_queue_completion(queue, action)));
+    }
+    Ok(())
+}
+
+/// Wait up to `timeout` for a pending completion action to finish running
+pub async fn wait_for_completion_action(timeout: Duration) {
+    let handle = COMPLETION_WATCHER.lock().unwrap().take();
+    if let Some(handle) = handle {
+        if tokio::time::timeout(timeout, handle).await.is_err() {
+            warn!("Completion action still running after {}s", timeout.as_secs());
+        }
+    }
+}
+
+async fn watch_for_queue_completion(queue: Arc<DownloadQueue>, action: CompletionAction) {
+    let mut changes = queue.get_notification_receiver();
+    let mut batch: HashSet<String> = HashSet::new();
+    loop {
+        let downloads = queue.get_all_downloads();
+        batch.extend(downloads.iter().filter(|item| is_pending(item)).map(|item| item.id.clone()));
+        if !is_queue_busy(&downloads) && is_batch_finished(&downloads, &batch) {
+            let summary = QueueSummary::from_downloads(downloads.iter().filter(|item| batch.contains(&item.id)));
+            if let Err(e) = queue.save_state().await {
+                warn!("Could not save the queue before the completion action: {}", e);
+            }
+            if let Err(e) = run_completion_action(&action, summary).await {
+                error!("Completion action failed: {}", e);
+            }
+            return;
+        }
+
+        // Queue events can be missed when the channel lags, so poll as well
+        match tokio::time::timeout(Duration::from_secs(5), changes.recv()).await {
+            Err(_) | Ok(Ok(())) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => {}
+            Ok(Err(broadcast::error::RecvError::Closed)) => return,
+        }
+    }
+}
+
 /// Shutdown the download manager
 pub async fn shutdown_download_manager() -> Result<(), AppError> {
     if let Some(queue) = DOWNLOAD_QUEUE.get() {
@@ -1755,5 +4081,28 @@ pub async fn shutdown_download_manager() -> Result<(), AppError> {
     Ok(())
 }
 
+/// How long a blocking shutdown waits for the queue state to be written
+pub const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shutdown the download manager from synchronous code, e.g. when the GUI
+/// exits, waiting at most `timeout` for the queue state to be written
+#[allow(dead_code)]
+pub fn shutdown_download_manager_blocking(timeout: Duration) -> Result<(), AppError> {
+    match DOWNLOAD_QUEUE.get() {
+        Some(queue) => queue.stop_blocking(timeout),
+        None => Ok(()),
+    }
+}
+
+/// Write the queue state without stopping anything, waiting at most
+/// `timeout`. Used by the panic hook: a panicking task does not end the
+/// process, so the queue keeps running, but what it knows is saved.
+pub fn flush_download_queue_blocking(timeout: Duration) -> Result<(), AppError> {
+    match DOWNLOAD_QUEUE.get() {
+        Some(queue) => queue.flush_blocking(timeout),
+        None => Ok(()),
+    }
+}
+
 // The types are already public in this module,
 // so no need for re-export as they're already available when importing this module
\ No newline at end of file
@@ -0,0 +1,114 @@
+// src/download_map.rs
+//! The queue's downloads by ID. Each download sits behind an `Arc` and is
+//! only copied when it changes while a snapshot still holds it, so a
+//! snapshot of a 1,000-item queue copies pointers rather than downloads,
+//! and holds the queue lock for just as long.
+
+use crate::download_manager::DownloadItem;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+
+/// Downloads by ID, with cheap snapshots
+#[derive(Debug, Clone, Default)]
+pub struct DownloadMap {
+    items: HashMap<String, Arc<DownloadItem>>,
+}
+
+impl DownloadMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn contains_key<Q>(&self, id: &Q) -> bool
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.items.contains_key(id)
+    }
+
+    pub fn get<Q>(&self, id: &Q) -> Option<&DownloadItem>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.items.get(id).map(Arc::as_ref)
+    }
+
+    /// The download to change, copied first if a snapshot shares it
+    pub fn get_mut<Q>(&mut self, id: &Q) -> Option<&mut DownloadItem>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.items.get_mut(id).map(Arc::make_mut)
+    }
+
+    /// Add or replace a download, returning the one it replaced
+    pub fn insert(&mut self, id: String, item: DownloadItem) -> Option<DownloadItem> {
+        self.items.insert(id, Arc::new(item)).map(Arc::unwrap_or_clone)
+    }
+
+    pub fn remove<Q>(&mut self, id: &Q) -> Option<DownloadItem>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.items.remove(id).map(Arc::unwrap_or_clone)
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &DownloadItem> {
+        self.items.values().map(Arc::as_ref)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &DownloadItem)> {
+        self.items.iter().map(|(id, item)| (id, item.as_ref()))
+    }
+
+    /// The downloads matching `filter` with their IDs, for changing. The
+    /// filter sees the shared download, so only matches are copied when a
+    /// snapshot holds them.
+    pub fn matching_mut<F>(&mut self, mut filter: F) -> impl Iterator<Item = (&String, &mut DownloadItem)>
+    where
+        F: FnMut(&DownloadItem) -> bool,
+    {
+        self.items
+            .iter_mut()
+            .filter(move |(_, item)| filter(item))
+            .map(|(id, item)| (id, Arc::make_mut(item)))
+    }
+
+    /// Every download as it is now. Later changes to the map don't show up
+    /// in the snapshot.
+    pub fn snapshot(&self) -> Vec<Arc<DownloadItem>> {
+        self.items.values().cloned().collect()
+    }
+}
+
+impl FromIterator<(String, DownloadItem)> for DownloadMap {
+    fn from_iter<I: IntoIterator<Item = (String, DownloadItem)>>(iter: I) -> Self {
+        Self {
+            items: iter.into_iter().map(|(id, item)| (id, Arc::new(item))).collect(),
+        }
+    }
+}
+
+/// Copies of every download, made after the lock on `downloads` is released
+pub fn snapshot_items(downloads: &RwLock<DownloadMap>) -> Vec<DownloadItem> {
+    let snapshot = downloads.read().unwrap().snapshot();
+    snapshot.into_iter().map(Arc::unwrap_or_clone).collect()
+}
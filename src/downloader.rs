@@ -1,17 +1,43 @@
+use crate::bandwidth::{current_limit, format_rate_limit, RateLimitWatch, LIMIT_CHECK_INTERVAL, MIN_RESTART_CHANGE};
+use crate::chapters::{chapter_template, resolve_chapters, Chapter};
+use crate::cloud_share::resolve_share_link;
+use crate::config::load_config;
+use crate::dedupe::DuplicateNaming;
+use crate::environment;
 use crate::error::{AppError, NetworkErrorKind};
-use crate::utils::{format_output_path, initialize_download_dir, validate_bitrate, validate_path_safety, validate_time_format, validate_url};
+use crate::ffmpeg_args::{check_conflicts, join_args};
+use crate::ffmpeg_features::{check_ffmpeg_features, required_features};
+use crate::format_picker::{check_bitrate_available, probe_formats};
+use crate::format_selector::{FormatPreferences, FormatSelector};
+use crate::gatekeeper::{explain_killed, explain_launch_failure};
+use crate::guardrails::parse_size_limit;
+use crate::http_headers::RequestHeaders;
+use crate::media_naming::{apply_media_naming, MediaNaming};
+use crate::notifications::notify_download_complete;
+use crate::paths::platform_data_dir;
+use crate::process::CommandSpec;
+use crate::progress_parser::{parse_progress_line, ProgressLine, PROGRESS_TEMPLATE};
+use crate::promo::{PromoService, PromoSlot};
+use crate::quota::QuotaStatus;
+use crate::split::split_file;
+use crate::throughput_stats::{expected_speed, remember_throughput};
+use crate::transcode::TranscodeKind;
+use crate::transliterate::{transliterate_file, transliterate_name};
+use crate::utils::{format_id_output_path, format_output_path, initialize_download_dir, parse_bitrate, validate_path_safety, validate_time_range, validate_url, Bitrate};
+use crate::vertical::{probe_frame_size, reframe, reframe_filter, VerticalMode, VerticalOptions};
 use base64::{engine::general_purpose, Engine as _};
-use chrono::Local;
+use chrono::{DateTime, Local};
 use colored::*;
-use dirs_next as dirs;
 use humansize::{format_size, BINARY};
 use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, error, info, warn};
-use notify_rust::Notification;
 use once_cell::sync::Lazy;
 use rand::{thread_rng, Rng};
 use regex::Regex;
-use ring::{digest, hmac};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ffi::OsString;
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
@@ -19,20 +45,114 @@ use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as AsyncCommand;
 use tokio::time::sleep;
 
+pub mod direct;
+
+use direct::{direct_media_extension, download_direct, matches_format, DirectOptions, DirectProgress};
+
 // We don't need to re-export these types since they're not actually used in this module
 // The imports are available directly from download_manager when needed
 
 const FREE_MP3_BITRATE: &str = "128K";
+const FREE_MP3_BYTES_PER_SECOND: u64 = 128 * 1000 / 8;
+const AUDIO_FORMAT_SELECTOR: &str = "bestaudio[ext=m4a]";
+
+/// Receives (downloaded bytes, total bytes, speed in bytes/s) while a download runs
+pub type ProgressCallback = Arc<dyn Fn(u64, u64, f64) + Send + Sync>;
+
+/// Number of stderr lines kept to explain a failed download
+pub const STDERR_TAIL_LINES: usize = 50;
+
+/// The last lines yt-dlp wrote to stderr, shared with the caller so a
+/// failure can be shown with its context
+#[derive(Debug, Clone, Default)]
+pub struct StderrTail {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl StderrTail {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a line, dropping the oldest beyond [`STDERR_TAIL_LINES`]
+    pub fn push(&self, line: &str) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == STDERR_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+
+    /// Recorded lines, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// The downloader yt-dlp hands the transfer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadBackend {
+    /// aria2c as an external downloader
+    Aria2c,
+    /// yt-dlp's built-in downloader
+    Native,
+    /// Rustloader's own downloader for direct media links, without yt-dlp
+    Direct,
+}
+
+impl DownloadBackend {
+    /// Value for yt-dlp's `--downloader` option ("direct" is never passed
+    /// to yt-dlp)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DownloadBackend::Aria2c => "aria2c",
+            DownloadBackend::Native => "native",
+            DownloadBackend::Direct => "direct",
+        }
+    }
+}
+
+/// Result of a finished download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadOutcome {
+    pub output_path: String,
+    /// The file written, when it could be identified (not for playlists)
+    pub file_path: Option<PathBuf>,
+    /// Backend that completed the transfer; `None` when an existing file was kept
+    pub backend: Option<DownloadBackend>,
+}
+
+/// Whether a failed download's stderr points at aria2c rather than the
+/// source, so that yt-dlp's own downloader is worth a try
+pub fn is_aria2c_failure(stderr_output: &str) -> bool {
+    stderr_output.lines().any(|line| {
+        let line = line.to_lowercase();
+        line.contains("aria2c") && (line.contains("exited with code") || line.contains("error"))
+    })
+}
+
+fn aria2c_available() -> bool {
+    CommandSpec::version_probe("aria2c", "--version")
+        .to_command()
+        .and_then(|mut probe| probe.output().map_err(AppError::IoError))
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
 
 static FFMPEG_AVAILABLE: Lazy<bool> = Lazy::new(|| {
-    if std::process::Command::new("ffmpeg")
-        .arg("-version")
-        .output()
+    if CommandSpec::version_probe("ffmpeg", "-version")
+        .to_command()
+        .and_then(|mut probe| probe.output().map_err(AppError::IoError))
         .map(|o| o.status.success())
         .unwrap_or(false)
     {
@@ -54,12 +174,7 @@ static FFMPEG_AVAILABLE: Lazy<bool> = Lazy::new(|| {
         }
     }
     
-    #[cfg(target_os = "windows")]
-    let which_cmd = "where";
-    #[cfg(not(target_os = "windows"))]
-    let which_cmd = "which";
-
-    if let Ok(output) = std::process::Command::new(which_cmd).arg("ffmpeg").output() {
+    if let Ok(output) = CommandSpec::locate("ffmpeg").to_command().and_then(|mut command| Ok(command.output()?)) {
         if output.status.success() {
             let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
             if !path.is_empty() && std::path::Path::new(&path).exists() {
@@ -80,9 +195,80 @@ const STALL_DETECTION_SECONDS: u64 = 30; // Consider download stalled after 30s
 
 /// Constants for memory management
 const BUFFER_SIZE: usize = 64 * 1024; // 64 KB buffer size for optimal streaming
-const SPEED_SAMPLE_LIMIT: usize = 10; // Limit number of speed samples to control memory growth
 const SPEED_SAMPLE_INTERVAL_MS: u64 = 300; // Only sample speed every 300ms to reduce memory pressure
-const MEMORY_CLEANUP_INTERVAL_SECS: u64 = 60; // Cleanup unused memory every 60 seconds
+
+/// How often long downloads trim their progress buffers, and how hard.
+/// Set under `memory_cleanup` in config.json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryCleanupPolicy {
+    /// Seconds between cleanups; 0 turns them off
+    pub interval_secs: u64,
+    /// Speed samples averaged for the displayed speed
+    pub speed_sample_limit: usize,
+    /// Download age in seconds from which cleanups are medium intensity
+    pub medium_after_secs: u64,
+    /// Download age in seconds from which cleanups are high intensity
+    pub high_after_secs: u64,
+}
+
+impl Default for MemoryCleanupPolicy {
+    fn default() -> Self {
+        Self {
+            interval_secs: 60,
+            speed_sample_limit: 10,
+            medium_after_secs: 600,
+            high_after_secs: 3600,
+        }
+    }
+}
+
+/// How aggressively a cleanup trims spare capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CleanupIntensity {
+    Low,
+    Medium,
+    High,
+}
+
+impl MemoryCleanupPolicy {
+    /// At least one sample is always kept
+    pub fn sample_limit(&self) -> usize {
+        self.speed_sample_limit.max(1)
+    }
+
+    /// Intensity for a download that has been running `download_secs`
+    pub fn intensity(&self, download_secs: u64) -> CleanupIntensity {
+        if download_secs > self.high_after_secs {
+            CleanupIntensity::High
+        } else if download_secs > self.medium_after_secs {
+            CleanupIntensity::Medium
+        } else {
+            CleanupIntensity::Low
+        }
+    }
+
+    /// Capacity the speed sample buffer may reach before it is shrunk
+    pub fn capacity_threshold(&self, intensity: CleanupIntensity) -> usize {
+        match intensity {
+            CleanupIntensity::High => self.sample_limit(),
+            CleanupIntensity::Medium => self.sample_limit() * 2,
+            CleanupIntensity::Low => self.sample_limit() * 3,
+        }
+    }
+}
+
+/// What memory cleanup did during a download, logged at debug level
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MemoryStats {
+    pub cleanups: u64,
+    /// Cleanups that actually released spare capacity
+    pub buffers_shrunk: u64,
+    pub speed_samples: usize,
+    pub speed_sample_capacity: usize,
+    pub last_intensity: Option<CleanupIntensity>,
+}
 
 /// Enhanced download progress tracking with network resilience and memory optimization features
 struct DownloadProgress {
@@ -97,23 +283,31 @@ struct DownloadProgress {
     retry_count: AtomicU64,
     last_memory_cleanup: Mutex<Instant>,
     download_start_time: Mutex<Instant>,
+    cleanup_policy: MemoryCleanupPolicy,
+    cleanups: AtomicU64,
+    buffers_shrunk: AtomicU64,
+    last_intensity: Mutex<Option<CleanupIntensity>>,
 }
 
 impl DownloadProgress {
-    fn new() -> Self {
+    fn new(cleanup_policy: MemoryCleanupPolicy) -> Self {
         let now = Instant::now();
         Self {
             last_update: Mutex::new(now),
             downloaded_bytes: AtomicU64::new(0),
             total_bytes: AtomicU64::new(0),
             download_speed: Mutex::new(0.0),
-            last_speed_samples: Mutex::new(Vec::with_capacity(SPEED_SAMPLE_LIMIT)),
+            last_speed_samples: Mutex::new(Vec::with_capacity(cleanup_policy.sample_limit())),
             download_active: AtomicBool::new(true),
             last_progress_time: Mutex::new(now),
             resumable: AtomicBool::new(true),
             retry_count: AtomicU64::new(0),
             last_memory_cleanup: Mutex::new(now),
             download_start_time: Mutex::new(now),
+            cleanup_policy,
+            cleanups: AtomicU64::new(0),
+            buffers_shrunk: AtomicU64::new(0),
+            last_intensity: Mutex::new(None),
         }
     }
 
@@ -136,7 +330,8 @@ impl DownloadProgress {
         // Periodically perform memory cleanup during long downloads
         let should_cleanup = {
             let mut last_cleanup = self.last_memory_cleanup.lock().unwrap();
-            let cleanup_needed = now.duration_since(*last_cleanup).as_secs() >= MEMORY_CLEANUP_INTERVAL_SECS;
+            let interval = self.cleanup_policy.interval_secs;
+            let cleanup_needed = interval > 0 && now.duration_since(*last_cleanup).as_secs() >= interval;
             if cleanup_needed {
                 *last_cleanup = now;
             }
@@ -152,7 +347,11 @@ impl DownloadProgress {
         let mut last_update = self.last_update.lock().unwrap();
         let time_diff = now.duration_since(*last_update).as_millis();
 
-        if time_diff >= SPEED_SAMPLE_INTERVAL_MS as u128 && bytes_diff > 0 {
+        if current_downloaded == 0 {
+            // The first report of a resumed download includes the bytes
+            // already on disk, so it only starts the clock
+            *last_update = now;
+        } else if time_diff >= SPEED_SAMPLE_INTERVAL_MS as u128 && bytes_diff > 0 {
             let mut last_speed_samples = self.last_speed_samples.lock().unwrap();
             let mut speed = self.download_speed.lock().unwrap();
 
@@ -160,7 +359,7 @@ impl DownloadProgress {
             
             // Add the new sample and maintain fixed size to prevent memory growth
             last_speed_samples.push(current_speed);
-            if last_speed_samples.len() > SPEED_SAMPLE_LIMIT {
+            if last_speed_samples.len() > self.cleanup_policy.sample_limit() {
                 last_speed_samples.remove(0);
             }
             
@@ -183,46 +382,49 @@ impl DownloadProgress {
         };
         
         // For longer downloads, be more aggressive with memory cleanup
-        let cleanup_intensity = if download_duration_secs > 3600 {
-            // For downloads longer than 1 hour
-            "high"
-        } else if download_duration_secs > 600 {
-            // For downloads longer than 10 minutes
-            "medium"
-        } else {
-            "low"
-        };
+        let cleanup_intensity = self.cleanup_policy.intensity(download_duration_secs);
+        self.cleanups.fetch_add(1, Ordering::SeqCst);
+        *self.last_intensity.lock().unwrap() = Some(cleanup_intensity);
         
-        debug!("Performing memory cleanup with {} intensity after {} seconds of download", 
+        debug!("Performing memory cleanup with {:?} intensity after {} seconds of download", 
               cleanup_intensity, download_duration_secs);
         
         // Remove excess capacity from speed samples vector
         let mut samples = self.last_speed_samples.lock().unwrap();
         
-        let threshold = match cleanup_intensity {
-            "high" => SPEED_SAMPLE_LIMIT, // Very aggressive for long downloads
-            "medium" => SPEED_SAMPLE_LIMIT * 2,
-            _ => SPEED_SAMPLE_LIMIT * 3
-        };
+        let threshold = self.cleanup_policy.capacity_threshold(cleanup_intensity);
         
         if samples.capacity() > threshold {
-            debug!("Shrinking speed samples vector from capacity {} to {}", samples.capacity(), SPEED_SAMPLE_LIMIT);
+            debug!("Shrinking speed samples vector from capacity {} to {}", samples.capacity(), self.cleanup_policy.sample_limit());
             let current_samples = samples.clone();
             *samples = current_samples;
             samples.shrink_to_fit();
+            self.buffers_shrunk.fetch_add(1, Ordering::SeqCst);
         }
         
         // Force drop any large internal buffers
         drop(samples);
         
         // For high intensity cleanups, also call the system allocator
-        if cleanup_intensity == "high" {
+        if cleanup_intensity == CleanupIntensity::High {
             debug!("Requesting system memory optimization for long-running download ({}s)", download_duration_secs);
             // On some platforms we could potentially make system calls to release memory
             // but this is platform specific. For now we'll rely on Rust's allocator.
         }
     }
 
+    /// Counters for the debug log
+    fn memory_stats(&self) -> MemoryStats {
+        let samples = self.last_speed_samples.lock().unwrap();
+        MemoryStats {
+            cleanups: self.cleanups.load(Ordering::SeqCst),
+            buffers_shrunk: self.buffers_shrunk.load(Ordering::SeqCst),
+            speed_samples: samples.len(),
+            speed_sample_capacity: samples.capacity(),
+            last_intensity: *self.last_intensity.lock().unwrap(),
+        }
+    }
+
     /// Detect if the download has stalled (no progress for a specified time)
     fn is_stalled(&self) -> bool {
         let last_progress = self.last_progress_time.lock().unwrap();
@@ -242,7 +444,7 @@ impl DownloadProgress {
         speed_samples.clear();
         
         // Prevent memory leaks by releasing excess capacity
-        if speed_samples.capacity() > SPEED_SAMPLE_LIMIT {
+        if speed_samples.capacity() > self.cleanup_policy.sample_limit() {
             speed_samples.shrink_to_fit();
         }
         
@@ -290,6 +492,22 @@ impl DownloadProgress {
         *self.download_speed.lock().unwrap()
     }
 
+    /// Show `speed` until the first measurement replaces it
+    fn seed_speed(&self, speed: f64) {
+        if self.last_speed_samples.lock().unwrap().is_empty() {
+            *self.download_speed.lock().unwrap() = speed;
+        }
+    }
+
+    /// Average of the speeds measured so far, leaving out any seeded speed
+    fn measured_speed(&self) -> Option<f64> {
+        let samples = self.last_speed_samples.lock().unwrap();
+        if samples.is_empty() {
+            return None;
+        }
+        Some(samples.iter().sum::<f64>() / samples.len() as f64)
+    }
+
     fn get_eta(&self) -> Option<Duration> {
         let downloaded = self.downloaded_bytes.load(Ordering::SeqCst);
         let total = self.total_bytes.load(Ordering::SeqCst);
@@ -356,6 +574,20 @@ impl DownloadProgress {
     }
 }
 
+/// Saves the speed a download reached for its host once it ends, however it ends
+struct RememberThroughput {
+    url: String,
+    progress: Arc<DownloadProgress>,
+}
+
+impl Drop for RememberThroughput {
+    fn drop(&mut self) {
+        if let Some(speed) = self.progress.measured_speed() {
+            remember_throughput(&self.url, speed);
+        }
+    }
+}
+
 struct DownloadCounter {
     today_count: u32,
     date: String,
@@ -372,56 +604,7 @@ impl DownloadCounter {
     }
 
     fn get_counter_key() -> Vec<u8> {
-        let machine_id = match Self::get_machine_id() {
-            Ok(id) => id,
-            Err(_) => "DefaultCounterKey".to_string(),
-        };
-
-        let digest = digest::digest(&digest::SHA256, machine_id.as_bytes());
-        digest.as_ref()[..16].to_vec()
-    }
-
-    fn get_machine_id() -> Result<String, AppError> {
-        #[cfg(target_os = "linux")]
-        {
-            if let Ok(id) = fs::read_to_string("/etc/machine-id") {
-                return Ok(id.trim().to_string());
-            }
-        }
-
-        #[cfg(target_os = "macos")]
-        {
-            use std::process::Command;
-            if let Ok(output) = Command::new("ioreg")
-                .args(["-rd1", "-c", "IOPlatformExpertDevice"])
-                .output()
-            {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                if let Some(line) = stdout.lines().find(|line| line.contains("IOPlatformUUID")) {
-                    if let Some(uuid_start) = line.find("\"") {
-                        if let Some(uuid_end) = line[uuid_start + 1..].find("\"") {
-                            return Ok(line[uuid_start + 1..uuid_start + 1 + uuid_end].to_string());
-                        }
-                    }
-                }
-            }
-        }
-
-        #[cfg(target_os = "windows")]
-        {
-            use winreg::enums::*;
-            use winreg::RegKey;
-            if let Ok(key) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey("SOFTWARE\\Microsoft\\Cryptography") {
-                if let Ok(guid) = key.get_value::<String, _>("MachineGuid") {
-                    return Ok(guid);
-                }
-            }
-        }
-
-        match hostname::get() {
-            Ok(name) => Ok(name.to_string_lossy().to_string()),
-            Err(_) => Err(AppError::General("Could not determine machine ID".to_string())),
-        }
+        crate::security::get_local_state_key()
     }
 
     fn save_to_disk(&self) -> Result<(), AppError> {
@@ -523,19 +706,20 @@ impl DownloadCounter {
     }
 }
 
-fn get_counter_path() -> Result<PathBuf, AppError> {
-    let mut path = dirs::data_local_dir()
-        .ok_or_else(|| AppError::PathError("Could not find local data directory".to_string()))?;
-
-    path.push("rustloader");
-    fs::create_dir_all(&path)?;
+/// Path of the daily download counter. Like the trial, it stays in the
+/// platform data directory, so RUSTLOADER_DATA_DIR can't reset it.
+pub fn get_counter_path() -> Result<PathBuf, AppError> {
+    Ok(platform_data_dir()?.join("download_counter.dat"))
+}
 
-    path.push("download_counter.dat");
-    Ok(path)
+/// How much of the daily download limit is left today
+pub fn quota_status() -> Result<QuotaStatus, AppError> {
+    let counter = DownloadCounter::load_from_disk()?;
+    Ok(QuotaStatus::new(counter.today_count, counter.max_daily_downloads, Local::now()))
 }
 
 /// Check if there is an active network connection
-async fn check_network_connectivity() -> bool {
+pub async fn check_network_connectivity() -> bool {
     // Try to connect to multiple reliable hosts to check connectivity
     let hosts = [
         "1.1.1.1:53",        // Cloudflare DNS
@@ -559,6 +743,17 @@ async fn check_network_connectivity() -> bool {
     false
 }
 
+/// Wait until the bandwidth profiles or override move the limit `watch`
+/// applies, and return the new one (`None` = unlimited)
+async fn next_limit_change(watch: &mut RateLimitWatch) -> Option<u64> {
+    loop {
+        sleep(LIMIT_CHECK_INTERVAL).await;
+        if let Some(limit) = watch.changed() {
+            return limit;
+        }
+    }
+}
+
 /// Analyze error outputs to determine network error types
 fn analyze_network_error(error: &io::Error, stderr_output: &str) -> (NetworkErrorKind, String, bool) {
     let kind = match error.kind() {
@@ -636,41 +831,12 @@ fn analyze_network_error(error: &io::Error, stderr_output: &str) -> (NetworkErro
     (refined_kind, message, retriable)
 }
 
-struct DownloadPromo {
-    download_messages: Vec<String>,
-    completion_messages: Vec<String>,
-}
-
-impl DownloadPromo {
-    fn new() -> Self {
-        Self {
-            download_messages: vec![
-                "🤖 AI-powered video enhancement available in Rustloader Pro! 🤖".to_string(),
-                "💎 Get advanced subtitle features with Rustloader Pro! 💎".to_string(),
-                "✨ Unlock advanced post-processing with Rustloader Pro! ✨".to_string(),
-            ],
-            completion_messages: vec![
-                "✨ Enjoy your download! Upgrade to Pro for AI-powered features: rustloader.com/pro ✨".to_string(),
-                "🚀 Rustloader Pro removes daily limits and adds AI capabilities. Learn more: rustloader.com/pro 🚀".to_string(),
-                "💎 Thanks for using Rustloader! Upgrade to Pro for advanced features: rustloader.com/pro 💎".to_string(),
-            ],
-        }
-    }
-
-    fn get_random_download_message(&self) -> &str {
-        let idx = rand::thread_rng().gen_range(0..self.download_messages.len());
-        &self.download_messages[idx]
-    }
-
-    fn get_random_completion_message(&self) -> &str {
-        let idx = rand::thread_rng().gen_range(0..self.completion_messages.len());
-        &self.completion_messages[idx]
-    }
-}
-
-struct YtdlpCommandBuilder {
+/// Assembles the yt-dlp invocation for a download
+pub struct YtdlpCommandBuilder {
     format: String,
     quality: Option<String>,
+    format_id: Option<String>,
+    format_preferences: FormatPreferences,
     start_time: Option<String>,
     end_time: Option<String>,
     url: String,
@@ -679,13 +845,26 @@ struct YtdlpCommandBuilder {
     download_subtitles: bool,
     force_download: bool,
     bitrate: Option<String>,
+    transcode: Option<TranscodeKind>,
+    request_headers: RequestHeaders,
+    backend: DownloadBackend,
+    ffmpeg_args: Vec<String>,
+    rate_limit: Option<String>,
+    max_filesize: Option<String>,
+    no_merge: bool,
+    vertical: bool,
+    staging_dir: Option<String>,
+    embed_metadata: bool,
+    sections: Vec<String>,
 }
 
 impl YtdlpCommandBuilder {
-    fn new(url: &str, output_path: &str) -> Self {
+    pub fn new(url: &str, output_path: &str) -> Self {
         Self {
             format: "mp4".to_string(),
             quality: None,
+            format_id: None,
+            format_preferences: FormatPreferences::default(),
             start_time: None,
             end_time: None,
             url: url.to_string(),
@@ -694,57 +873,163 @@ impl YtdlpCommandBuilder {
             download_subtitles: false,
             force_download: false,
             bitrate: None,
+            transcode: None,
+            request_headers: RequestHeaders::default(),
+            backend: DownloadBackend::Native,
+            ffmpeg_args: Vec::new(),
+            rate_limit: None,
+            max_filesize: Some(crate::guardrails::DEFAULT_MAX_FILESIZE.to_string()),
+            no_merge: false,
+            vertical: false,
+            staging_dir: None,
+            embed_metadata: false,
+            sections: Vec::new(),
         }
     }
     
-    fn with_format(mut self, format: &str) -> Self {
+    pub fn with_format(mut self, format: &str) -> Self {
         self.format = format.to_string();
         self
     }
     
-    fn with_quality(mut self, quality: Option<&str>) -> Self {
+    pub fn with_quality(mut self, quality: Option<&str>) -> Self {
         self.quality = quality.map(|s| s.to_string());
         self
     }
     
-    fn with_time_range(mut self, start_time: Option<&String>, end_time: Option<&String>) -> Self {
+    pub fn with_format_id(mut self, format_id: Option<&str>) -> Self {
+        self.format_id = format_id.map(|s| s.to_string());
+        self
+    }
+    
+    pub fn with_format_preferences(mut self, preferences: FormatPreferences) -> Self {
+        self.format_preferences = preferences;
+        self
+    }
+    
+    pub fn with_time_range(mut self, start_time: Option<&String>, end_time: Option<&String>) -> Self {
         self.start_time = start_time.cloned();
         self.end_time = end_time.cloned();
         self
     }
     
-    fn with_playlist(mut self, use_playlist: bool) -> Self {
+    pub fn with_playlist(mut self, use_playlist: bool) -> Self {
         self.use_playlist = use_playlist;
         self
     }
     
-    fn with_subtitles(mut self, download_subtitles: bool) -> Self {
+    pub fn with_subtitles(mut self, download_subtitles: bool) -> Self {
         self.download_subtitles = download_subtitles;
         self
     }
     
-    fn with_force_download(mut self, force: bool) -> Self {
+    pub fn with_force_download(mut self, force: bool) -> Self {
         self.force_download = force;
         self
     }
     
-    fn with_bitrate(mut self, bitrate: Option<&String>) -> Self {
+    pub fn with_bitrate(mut self, bitrate: Option<&String>) -> Self {
         self.bitrate = bitrate.cloned();
         self
     }
     
-    fn build(self) -> AsyncCommand {
-        let mut command = AsyncCommand::new("yt-dlp");
-        
-        let ffmpeg_required = self.format == "mp3" || 
-                            self.start_time.is_some() || 
-                            self.end_time.is_some();
-        
-        if ffmpeg_required && !*FFMPEG_AVAILABLE {
+    pub fn with_transcode(mut self, transcode: Option<TranscodeKind>) -> Self {
+        self.transcode = transcode;
+        self
+    }
+    
+    pub fn with_request_headers(mut self, request_headers: &RequestHeaders) -> Self {
+        self.request_headers = request_headers.clone();
+        self
+    }
+    
+    pub fn with_backend(mut self, backend: DownloadBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+    
+    pub fn with_ffmpeg_args(mut self, ffmpeg_args: &[String]) -> Self {
+        self.ffmpeg_args = ffmpeg_args.to_vec();
+        self
+    }
+    
+    /// Bandwidth limit from the active profile, e.g. "2M"
+    pub fn with_rate_limit(mut self, rate_limit: Option<String>) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+    
+    /// Largest file yt-dlp may download; None removes the limit
+    pub fn with_max_filesize(mut self, max_filesize: Option<String>) -> Self {
+        self.max_filesize = max_filesize;
+        self
+    }
+    
+    /// Keep the video and audio streams as separate files instead of merging them
+    pub fn with_no_merge(mut self, no_merge: bool) -> Self {
+        self.no_merge = no_merge;
+        self
+    }
+    
+    /// The source is vertical video: quality limits and upscaling apply to its width
+    pub fn with_vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+    
+    /// Scratch directory where yt-dlp downloads, merges and post-processes
+    /// before moving the finished file to the output directory
+    pub fn with_staging_dir(mut self, staging_dir: Option<String>) -> Self {
+        self.staging_dir = staging_dir;
+        self
+    }
+    
+    /// Write the title and other metadata into the file's tags
+    pub fn with_embedded_metadata(mut self, embed_metadata: bool) -> Self {
+        self.embed_metadata = embed_metadata;
+        self
+    }
+    
+    /// Download only these chapters, each to its own file
+    pub fn with_chapters(mut self, chapters: &[Chapter]) -> Self {
+        self.sections = chapters.iter().map(Chapter::section_pattern).collect();
+        self
+    }
+    
+    /// The yt-dlp arguments for this download. Depends only on the
+    /// builder's settings, so the same options always give the same list.
+    pub fn build_args(&self) -> Result<Vec<OsString>, AppError> {
+        Ok(self.command_spec()?.to_args()?.into_iter().map(OsString::from).collect())
+    }
+    
+    /// Wrap the arguments in a command ready to spawn
+    pub fn build(&self) -> Result<AsyncCommand, AppError> {
+        self.command_spec()?.to_async_command()
+    }
+    
+    /// The full argv that would be run, program first, without running it
+    pub fn build_preview(&self) -> Result<Vec<String>, AppError> {
+        let mut argv = vec![CommandSpec::ytdlp().program().to_string()];
+        argv.extend(self.build_args()?.iter().map(|arg| arg.to_string_lossy().into_owned()));
+        Ok(argv)
+    }
+    
+    fn ffmpeg_required(&self) -> bool {
+        self.format == "mp3" ||
+            self.start_time.is_some() ||
+            self.end_time.is_some() ||
+            !self.sections.is_empty() ||
+            self.transcode.is_some() ||
+            !self.ffmpeg_args.is_empty()
+    }
+    
+    /// Tell the user about missing dependencies and the chosen options
+    pub fn announce(&self) {
+        if self.ffmpeg_required() && !*FFMPEG_AVAILABLE {
             if self.format == "mp3" {
                 println!("{}", "⚠️ ERROR: FFmpeg is required for audio conversion but not found. ⚠️".bright_red());
                 println!("{}", "The download will likely fail. Please install FFmpeg and try again.".bright_red());
-            } else if self.start_time.is_some() || self.end_time.is_some() {
+            } else if self.start_time.is_some() || self.end_time.is_some() || !self.sections.is_empty() {
                 println!("{}", "⚠️ ERROR: FFmpeg is required for time-based extraction but not found. ⚠️".bright_red());
                 println!("{}", "The download will likely fail. Please install FFmpeg and try again.".bright_red());
             } else {
@@ -752,150 +1037,206 @@ impl YtdlpCommandBuilder {
             }
         }
         
+        if self.format == "mp3" {
+            println!("{}", "⭐ Limited to 128kbps audio. Upgrade to Pro for studio-quality audio. ⭐".yellow());
+        } else if let Some(format_id) = &self.format_id {
+            println!("{}: {}", "Selected format".blue(), format_id);
+        } else if let Some(quality_value) = &self.quality {
+            let selector = FormatSelector::new()
+                .with_quality(Some(quality_value))
+                .with_preferences(self.format_preferences)
+                .with_vertical(self.vertical);
+            if !selector.is_default() {
+                println!("{}: {}", "Selected video quality".blue(), quality_value);
+            }
+        }
+        
+        if self.use_playlist {
+            println!("{}", "Playlist mode enabled - will download all videos in playlist".yellow());
+        }
+        if self.download_subtitles {
+            println!("{}", "Subtitles will be downloaded if available".blue());
+        }
+        if !self.ffmpeg_args.is_empty() {
+            println!("{}: {}", "Custom ffmpeg arguments".blue(), join_args(&self.ffmpeg_args));
+        }
+        if let Some(transcode) = self.transcode {
+            println!("{}: {}", "Post-processing".blue(), transcode.as_str());
+        }
+        if self.no_merge {
+            println!("{}", "Video and audio will be saved as separate files".blue());
+        }
+        if !self.sections.is_empty() {
+            println!("{}", format!("Each of the {} chapter(s) will be saved as a separate file", self.sections.len()).blue());
+        }
+    }
+    
+    fn command_spec(&self) -> Result<CommandSpec, AppError> {
+        let mut command = CommandSpec::ytdlp();
+        
+        // Generated and custom ffmpeg post-processing arguments, passed as one list
+        let mut ffmpeg_args: Vec<String> = Vec::new();
+        
         // Memory optimization for large files (>2GB)
-        command.arg("--buffer-size").arg(format!("{}K", BUFFER_SIZE / 1024));
+        command.option("--buffer-size", format!("{}K", BUFFER_SIZE / 1024));
         
         // Limit the number of concurrent fragments to prevent memory bloat
-        command.arg("--concurrent-fragments").arg("4");
+        command.option("--concurrent-fragments", "4");
         
         // Add file size limit check to avoid unexpected out-of-memory conditions
-        command.arg("--max-filesize").arg("10G"); // Set reasonable 10GB limit 
-        
-        let aria2c_available = std::process::Command::new("aria2c")
-            .arg("--version")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false);
+        if let Some(limit) = &self.max_filesize {
+            command.option("--max-filesize", limit);
+        }
         
-        if aria2c_available {
+        command.option("--downloader", self.backend.as_str());
+        if self.backend == DownloadBackend::Aria2c {
             // Configure aria2c for better memory handling
-            command.arg("--downloader").arg("aria2c");
-            command.arg("--downloader-args").arg("aria2c:-x4"); // Max 4 connections
-            command.arg("--downloader-args").arg(format!("aria2c:-k{}", BUFFER_SIZE / 1024)); // Use same buffer size 
-            command.arg("--downloader-args").arg("aria2c:--file-allocation=none"); // Avoid preallocation
-            command.arg("--downloader-args").arg("aria2c:--disk-cache=64M"); // Limit disk cache
-        } else {
-            command.arg("--downloader").arg("yt-dlp");
-            // Limit memory usage for internal downloader
-            command.arg("--limit-rate").arg("15M"); // Reasonable download rate limit to prevent memory spikes
+            command.option("--downloader-args", "aria2c:-x4"); // Max 4 connections
+            command.option("--downloader-args", format!("aria2c:-k{}", BUFFER_SIZE / 1024)); // Use same buffer size 
+            command.option("--downloader-args", "aria2c:--file-allocation=none"); // Avoid preallocation
+            command.option("--downloader-args", "aria2c:--disk-cache=64M"); // Limit disk cache
+        }
+
+        // Apply the time-of-day bandwidth profile, if any
+        match &self.rate_limit {
+            Some(limit) => {
+                command.option("--limit-rate", limit);
+            }
+            None if self.backend == DownloadBackend::Native => {
+                // Limit memory usage for internal downloader
+                command.option("--limit-rate", "15M"); // Reasonable download rate limit to prevent memory spikes
+            }
+            None => {}
         }
         
         if self.force_download {
-            command.arg("--no-continue");
-            command.arg("--no-part-file");
+            command.flag("--no-continue");
+            command.flag("--no-part-file");
         }
         
         if self.format == "mp3" {
             command
-                .arg("-f")
-                .arg("bestaudio[ext=m4a]")
-                .arg("--extract-audio")
-                .arg("--audio-format")
-                .arg("mp3");
-    
-            command.arg("--audio-quality").arg("7");
-            command
-                .arg("--postprocessor-args")
-                .arg(format!("ffmpeg:-b:a {}", FREE_MP3_BITRATE));
+                .option("-f", self.format_id.as_deref().unwrap_or(AUDIO_FORMAT_SELECTOR))
+                .flag("--extract-audio")
+                .option("--audio-format", "mp3");
     
-            println!("{}", "⭐ Limited to 128kbps audio. Upgrade to Pro for studio-quality audio. ⭐".yellow());
-        } else if let Some(quality_value) = &self.quality {
-            println!("{}: {}", "Selected video quality".blue(), quality_value);
-    
-            let format_string = match quality_value.as_str() {
-                "480" => "bestvideo[height<=480]+bestaudio/best[height<=480]/best",
-                "720" => "bestvideo[height<=720]+bestaudio/best[height<=720]/best",
-                "1080" => "bestvideo[height<=1080]+bestaudio/best[height<=1080]/best",
-                "2160" => "bestvideo[height<=2160]+bestaudio/best[height<=2160]/best",
-                _ => "best",
-            };
+            command.option("--audio-quality", "7");
+            ffmpeg_args.extend(["-b:a".to_string(), FREE_MP3_BITRATE.to_string()]);
+        } else if let Some(format_id) = &self.format_id {
+            if self.no_merge {
+                // "137+140" merges the two formats; "137,140" keeps both files
+                command.option("-f", format_id.replace('+', ","));
+            } else {
+                command.option("-f", format_id);
+            }
+        } else if self.no_merge {
+            let selector = FormatSelector::new()
+                .with_quality(self.quality.as_deref())
+                .with_preferences(self.format_preferences)
+                .with_vertical(self.vertical);
+            command.option("-f", selector.separate_expression());
+        } else {
+            let selector = FormatSelector::new()
+                .with_quality(self.quality.as_deref())
+                .with_preferences(self.format_preferences)
+                .with_vertical(self.vertical);
     
-            command.arg("-f").arg(format_string);
-            command.arg("--verbose");
+            if !selector.is_default() {
+                command.option("-f", selector.expression());
+                command.flag("--verbose");
+            }
         }
         
-        command.arg("-o").arg(&self.output_path);
+        let output = if !self.sections.is_empty() {
+            chapter_template(&self.output_path)
+        } else if self.no_merge && self.format != "mp3" {
+            separate_streams_template(&self.output_path)
+        } else {
+            self.output_path.clone()
+        };
+        let output_path = Path::new(&output);
+        match (&self.staging_dir, output_path.parent(), output_path.file_name()) {
+            // yt-dlp only stages files when the output template is relative
+            // to its home path
+            (Some(staging), Some(home), Some(name)) if !home.as_os_str().is_empty() => {
+                command.option("--paths", format!("home:{}", home.to_string_lossy()));
+                command.option("--paths", format!("temp:{}", staging));
+                command.option("-o", name.to_string_lossy());
+            }
+            _ => {
+                command.option("-o", &output);
+            }
+        }
         
         if self.use_playlist {
-            command.arg("--yes-playlist");
-            println!("{}", "Playlist mode enabled - will download all videos in playlist".yellow());
+            command.flag("--yes-playlist");
         } else {
-            command.arg("--no-playlist");
+            command.flag("--no-playlist");
         }
         
         if self.download_subtitles {
-            command.arg("--write-subs").arg("--sub-langs").arg("all");
-            println!("{}", "Subtitles will be downloaded if available".blue());
+            command.flag("--write-subs").option("--sub-langs", "all");
         }
         
-        if self.start_time.is_some() || self.end_time.is_some() {
-            let mut time_args = String::new();
-    
-            if let Some(start) = &self.start_time {
-                time_args.push_str(&format!("-ss {} ", start));
-            }
-    
-            if let Some(end) = &self.end_time {
-                time_args.push_str(&format!("-to {} ", end));
-            }
-    
-            if !time_args.is_empty() {
-                command
-                    .arg("--postprocessor-args")
-                    .arg(format!("ffmpeg:{}", time_args.trim()));
-            }
+        if self.embed_metadata {
+            command.flag("--embed-metadata");
         }
         
-        command.arg("--socket-timeout").arg("30");
-        command.arg("--retries").arg("10");
-        command.arg("--fragment-retries").arg("10");
-        command.arg("--throttled-rate").arg("100K");
-        command.arg("--newline");
-        command
-            .arg("--progress-template")
-            .arg("download:%(progress.downloaded_bytes)s/%(progress.total_bytes)s");
-        command.arg("--user-agent")
-            .arg("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36");
+        for section in &self.sections {
+            command.option("--download-sections", section);
+        }
         
-        command.arg(self.url);
+        let has_time_range = self.start_time.is_some() || self.end_time.is_some();
+        if let Some(start) = &self.start_time {
+            ffmpeg_args.extend(["-ss".to_string(), start.clone()]);
+        }
+        if let Some(end) = &self.end_time {
+            ffmpeg_args.extend(["-to".to_string(), end.clone()]);
+        }
         
-        command
-    }
-}
-
-fn extract_video_id(url: &str) -> Option<String> {
-    let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
-
-    if let Some(v_pos) = url.find("v=") {
-        let id_start = v_pos + 2;
-        let id_end = url[id_start..]
-            .find(|c: char| !is_valid_char(c))
-            .map_or(url.len(), |pos| id_start + pos);
-
-        let extracted = &url[id_start..id_end];
-
-        if extracted.len() >= 8 && extracted.len() <= 12 && extracted.chars().all(is_valid_char) {
-            return Some(extracted.to_string());
+        if !self.ffmpeg_args.is_empty() {
+            check_conflicts(&self.ffmpeg_args, &ffmpeg_args, has_time_range)?;
+            ffmpeg_args.extend(self.ffmpeg_args.iter().cloned());
         }
-    } else if url.contains("youtu.be/") {
-        let parts: Vec<&str> = url.split("youtu.be/").collect();
-        if parts.len() < 2 {
-            return None;
+        
+        if !ffmpeg_args.is_empty() {
+            command.option("--postprocessor-args", format!("ffmpeg:{}", join_args(&ffmpeg_args)));
         }
-
-        let id_part = parts[1];
-        let id_end = id_part
-            .find(|c: char| !is_valid_char(c))
-            .map_or(id_part.len(), |pos| pos);
-
-        let extracted = &id_part[..id_end];
-
-        if extracted.len() >= 8 && extracted.len() <= 12 && extracted.chars().all(is_valid_char) {
-            return Some(extracted.to_string());
+        
+        if let Some(transcode) = self.transcode {
+            command.option("--recode-video", &self.format);
+            command.option("--postprocessor-args", format!("VideoConvertor:{}", transcode.ffmpeg_args_for(self.vertical)));
         }
+        
+        command.option("--socket-timeout", "30");
+        command.option("--retries", "10");
+        command.option("--fragment-retries", "10");
+        command.option("--throttled-rate", "100K");
+        command.flag("--newline");
+        command.option("--progress-template", PROGRESS_TEMPLATE);
+        command.options(self.request_headers.ytdlp_options());
+        
+        command.operand(&self.url);
+        
+        Ok(command)
     }
+}
+
+/// Output template for downloads that keep the video and audio streams
+/// apart: the fixed extension is replaced so each stream is saved under its
+/// format ID with its real extension, e.g. "Title.f137.mp4" and "Title.f140.m4a"
+pub fn separate_streams_template(output_path: &str) -> String {
+    let path = Path::new(output_path);
+    let stem = match path.extension() {
+        Some(ext) => &output_path[..output_path.len() - ext.len() - 1],
+        None => output_path,
+    };
+    format!("{}.f%(format_id)s.%(ext)s", stem)
+}
 
-    None
+/// The backend a download starts with: aria2c when it is installed
+pub fn default_backend() -> DownloadBackend {
+    if aria2c_available() { DownloadBackend::Aria2c } else { DownloadBackend::Native }
 }
 
 fn sanitize_filename(filename: &str) -> Result<String, AppError> {
@@ -905,7 +1246,12 @@ fn sanitize_filename(filename: &str) -> Result<String, AppError> {
         .collect();
 
     if sanitized.is_empty() || sanitized.len() < filename.len() / 2 {
-        Err(AppError::ValidationError("Invalid filename after sanitization".to_string()))
+        Err(AppError::InvalidInput {
+            what: "file name".to_string(),
+            value: filename.to_string(),
+            rule: "fewer than half its characters are letters, digits, '-' or '_'".to_string(),
+            suggestion: Some(sanitized).filter(|name| !name.is_empty()),
+        })
     } else {
         Ok(sanitized)
     }
@@ -914,36 +1260,21 @@ fn sanitize_filename(filename: &str) -> Result<String, AppError> {
 fn clear_partial_downloads(url: &str) -> Result<(), AppError> {
     println!("{}", "Clearing partial downloads to avoid resumption errors...".blue());
 
-    let video_id = match extract_video_id(url) {
-        Some(id) => sanitize_filename(&id)?,
+    let video_id = match crate::video_id::extract_video_id(url) {
+        Some(video) => sanitize_filename(&video.id)?,
         None => {
             println!("{}", "Could not extract video ID, skipping partial download cleanup.".yellow());
             return Ok(());
         }
     };
 
-    if video_id.len() < 8 || video_id.len() > 12 {
+    // Very short IDs could match unrelated files
+    if video_id.len() < 6 || video_id.len() > 64 {
         println!("{}", "Extracted video ID has suspicious length, skipping cleanup.".yellow());
         return Ok(());
     }
 
-    let mut download_dirs = Vec::new();
-    
-    if let Some(mut home_path) = dirs::home_dir() {
-        home_path.push("Downloads");
-        home_path.push("rustloader");
-        download_dirs.push(home_path.clone());
-
-        let mut videos_path = home_path.clone();
-        videos_path.push("videos");
-        download_dirs.push(videos_path);
-
-        let mut audio_path = home_path;
-        audio_path.push("audio");
-        download_dirs.push(audio_path);
-    }
-
-    download_dirs.push(std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    let download_dirs = crate::cleanup::default_download_dirs();
 
     let mut total_removed = 0;
     for dir in download_dirs {
@@ -951,7 +1282,7 @@ fn clear_partial_downloads(url: &str) -> Result<(), AppError> {
             match safe_cleanup(&dir, &video_id) {
                 Ok(count) => {
                     if count > 0 {
-                        println!("{} {} {}", "Removed".green(), count, format!("partial files from {:?}", dir).green());
+                        println!("{} {} {}", "Moved".green(), count, format!("partial files from {:?} to the trash", dir).green());
                         total_removed += count;
                     }
                 }
@@ -963,7 +1294,7 @@ fn clear_partial_downloads(url: &str) -> Result<(), AppError> {
     }
 
     if total_removed > 0 {
-        println!("{} {}", "Total partial downloads removed:".green(), total_removed);
+        println!("{} {}", "Total partial downloads moved to the trash:".green(), total_removed);
     } else {
         println!("{}", "No partial downloads found to clean up.".blue());
     }
@@ -1002,7 +1333,7 @@ fn safe_cleanup(dir: &PathBuf, video_id: &str) -> Result<usize, AppError> {
                         if file_name_str.chars().all(|c| {
                             c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == ' '
                         }) {
-                            match std::fs::remove_file(&path) {
+                            match crate::cleanup::discard_file(&path, false) {
                                 Ok(_) => {
                                     count += 1;
                                 }
@@ -1022,13 +1353,13 @@ fn safe_cleanup(dir: &PathBuf, video_id: &str) -> Result<usize, AppError> {
     Ok(count)
 }
 
-async fn get_video_title(url: &str) -> Result<String, AppError> {
-    let mut command = AsyncCommand::new("yt-dlp");
-    command
-        .arg("--get-title")
-        .arg("--no-playlist")
-        .arg("--")
-        .arg(url);
+async fn get_video_title(url: &str, request_headers: &RequestHeaders) -> Result<String, AppError> {
+    let mut command = CommandSpec::ytdlp()
+        .flag("--get-title")
+        .flag("--no-playlist")
+        .options(request_headers.ytdlp_options())
+        .operand(url)
+        .to_async_command()?;
 
     let output = command.output().await.map_err(AppError::IoError)?;
 
@@ -1044,6 +1375,112 @@ async fn get_video_title(url: &str) -> Result<String, AppError> {
     Ok(title)
 }
 
+/// Duration of a video in seconds, if yt-dlp knows it
+async fn probe_duration(url: &str, request_headers: &RequestHeaders) -> Result<Option<f64>, AppError> {
+    let mut command = CommandSpec::ytdlp()
+        .flag("--dump-json")
+        .flag("--no-playlist")
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options())
+        .operand(url)
+        .to_async_command()?;
+
+    let output = command.output().await.map_err(AppError::IoError)?;
+    if !output.status.success() {
+        return Err(AppError::DownloadError("Failed to get video duration".to_string()));
+    }
+
+    // Live streams have no duration
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(AppError::JsonError)?;
+    Ok(info["duration"].as_f64())
+}
+
+/// Metadata gathered from yt-dlp before a download starts
+#[derive(Debug, Clone, Default)]
+pub struct MediaEstimate {
+    pub title: Option<String>,
+    /// Channel or uploader name
+    pub channel: Option<String>,
+    pub total_bytes: Option<u64>,
+    pub duration_secs: Option<f64>,
+    pub height: Option<u64>,
+}
+
+/// Probe a URL with `--dump-json` to learn its title and expected size
+/// for the format the download will actually select
+pub async fn estimate_download(
+    url: &str,
+    format: &str,
+    quality: Option<&str>,
+    format_id: Option<&str>,
+    format_preferences: FormatPreferences,
+    request_headers: &RequestHeaders,
+) -> Result<MediaEstimate, AppError> {
+    let mut spec = CommandSpec::ytdlp();
+    spec.flag("--dump-json")
+        .flag("--no-playlist")
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options());
+
+    if let Some(format_id) = format_id {
+        spec.option("-f", format_id);
+    } else if format == "mp3" {
+        spec.option("-f", AUDIO_FORMAT_SELECTOR);
+    } else {
+        let selector = FormatSelector::new()
+            .with_quality(quality)
+            .with_preferences(format_preferences);
+        if !selector.is_default() {
+            spec.option("-f", selector.expression());
+        }
+    }
+
+    spec.operand(url);
+
+    let output = spec.to_async_command()?.output().await.map_err(AppError::IoError)?;
+    if !output.status.success() {
+        return Err(AppError::DownloadError("Failed to probe media information".to_string()));
+    }
+
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(AppError::JsonError)?;
+
+    let total_bytes = if format == "mp3" {
+        // Audio is re-encoded at a fixed bitrate, so the source size doesn't apply
+        info["duration"]
+            .as_f64()
+            .map(|duration| (duration * FREE_MP3_BYTES_PER_SECOND as f64) as u64)
+    } else {
+        estimate_size_from_metadata(&info)
+    };
+
+    Ok(MediaEstimate {
+        title: info["title"].as_str().map(|s| s.to_string()),
+        channel: info["channel"]
+            .as_str()
+            .or_else(|| info["uploader"].as_str())
+            .map(|s| s.to_string()),
+        total_bytes,
+        duration_secs: info["duration"].as_f64(),
+        height: info["height"].as_u64(),
+    })
+}
+
+/// Expected size in bytes from yt-dlp metadata, using `filesize` or
+/// `filesize_approx`. Merged formats are summed over `requested_formats`.
+pub fn estimate_size_from_metadata(info: &serde_json::Value) -> Option<u64> {
+    fn format_size_of(format: &serde_json::Value) -> Option<u64> {
+        ["filesize", "filesize_approx"]
+            .iter()
+            .find_map(|key| format[*key].as_u64().or_else(|| format[*key].as_f64().map(|v| v as u64)))
+            .filter(|size| *size > 0)
+    }
+
+    match info["requested_formats"].as_array() {
+        Some(formats) if !formats.is_empty() => formats.iter().map(format_size_of).sum(),
+        _ => format_size_of(info),
+    }
+}
+
 fn check_if_video_exists(download_dir: &Path, format: &str, video_title: &str) -> Option<PathBuf> {
     let safe_title = regex::escape(video_title);
     let file_pattern = format!("{}.*\\.{}", safe_title, format);
@@ -1066,6 +1503,11 @@ fn check_if_video_exists(download_dir: &Path, format: &str, video_title: &str) -
 }
 
 fn prompt_for_redownload() -> Result<bool, AppError> {
+    if !environment::is_interactive() {
+        println!("Not downloading it again in non-interactive mode.");
+        return Ok(false);
+    }
+
     print!("This video has already been downloaded. Do you want to download it again? (y/n): ");
     io::stdout().flush().map_err(AppError::IoError)?;
 
@@ -1076,16 +1518,32 @@ fn prompt_for_redownload() -> Result<bool, AppError> {
     Ok(input == "y" || input == "yes")
 }
 
-fn format_output_path_with_timestamp<P: AsRef<Path>>(download_dir: P, format: &str, timestamp: &str) -> Result<String, AppError> {
-    validate_path_safety(download_dir.as_ref())?;
+/// Output template for a download whose title is already taken in
+/// `download_dir`, named according to `naming`
+pub fn duplicate_output_path<P: AsRef<Path>>(
+    download_dir: P,
+    format: &str,
+    video_title: &str,
+    naming: DuplicateNaming,
+    now: DateTime<Local>,
+) -> Result<String, AppError> {
+    let download_dir = download_dir.as_ref();
+    validate_path_safety(download_dir)?;
 
     match format {
         "mp3" | "mp4" | "webm" | "m4a" | "flac" | "wav" | "ogg" => {}
         _ => return Err(AppError::ValidationError(format!("Invalid output format: {}", format)))
     }
 
-    let filename_template = format!("%(title)s_duplicate_{}.{}", timestamp, format);
-    let path_buf = download_dir.as_ref().join(&filename_template);
+    let filename_template = match naming {
+        DuplicateNaming::Counter => {
+            let number = next_duplicate_number(download_dir, format, video_title);
+            format!("%(title)s ({}).{}", number, format)
+        }
+        DuplicateNaming::Timestamp => format!("%(title)s ({}).{}", now.format("%Y%m%d-%H%M%S"), format),
+        DuplicateNaming::Uploader => format!("%(uploader)s - %(title)s.{}", format),
+    };
+    let path_buf = download_dir.join(&filename_template);
 
     let path_str = path_buf
         .to_str()
@@ -1095,10 +1553,33 @@ fn format_output_path_with_timestamp<P: AsRef<Path>>(download_dir: P, format: &s
     Ok(path_str)
 }
 
+/// The first free number for "Title (N).ext" in `download_dir`
+fn next_duplicate_number(download_dir: &Path, format: &str, video_title: &str) -> u32 {
+    let pattern = format!(r"^{} \((\d+)\)\.{}$", regex::escape(video_title), regex::escape(format));
+    let Ok(re) = Regex::new(&pattern) else {
+        return 1;
+    };
+    fs::read_dir(download_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name();
+                    let captures = re.captures(name.to_str()?)?;
+                    captures[1].parse::<u32>().ok()
+                })
+                .max()
+                .map_or(1, |highest| highest + 1)
+        })
+        .unwrap_or(1)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn download_video_free(
     url: &str,
     quality: Option<&str>,
+    format_id: Option<&str>,
+    format_preferences: FormatPreferences,
     format: &str,
     start_time: Option<&String>,
     end_time: Option<&String>,
@@ -1107,21 +1588,71 @@ pub async fn download_video_free(
     output_dir: Option<&String>,
     force_download: bool,
     bitrate: Option<&String>,
-) -> Result<String, AppError> {
+    transcode: Option<TranscodeKind>,
+    ffmpeg_args: &[String],
+    no_merge: bool,
+    trim_silence: bool,
+    vertical: VerticalOptions,
+    transliterate: bool,
+    media_naming: Option<MediaNaming>,
+    split_size: Option<u64>,
+    chapters: Option<&str>,
+    request_headers: &RequestHeaders,
+    on_progress: Option<ProgressCallback>,
+    stderr_tail: Option<StderrTail>,
+) -> Result<DownloadOutcome, AppError> {
     validate_url(url)?;
+    if chapters.is_some() && (use_playlist || start_time.is_some() || end_time.is_some()) {
+        return Err(AppError::ValidationError(
+            "--chapters can't be combined with playlists or a time range".to_string(),
+        ));
+    }
     
-    if let Some(start) = start_time {
-        validate_time_format(start)?;
+    // A cloud share page isn't media; the file behind it is downloaded directly
+    let cloud_link = resolve_share_link(url, request_headers).await?;
+    let download_url = cloud_link.as_ref().map_or(url, |link| link.url.as_str());
+    if let Some(link) = &cloud_link {
+        println!("{}: {}", "Downloading the file shared on".blue(), link.provider.name());
     }
-
-    if let Some(end) = end_time {
-        validate_time_format(end)?;
+    
+    validate_time_range(start_time.map(String::as_str), end_time.map(String::as_str), None)?;
+    if (start_time.is_some() || end_time.is_some()) && !use_playlist {
+        match probe_duration(download_url, request_headers).await {
+            Ok(duration) => validate_time_range(start_time.map(String::as_str), end_time.map(String::as_str), duration)?,
+            Err(e) => debug!("Could not check the time range against the duration: {}", e),
+        }
     }
 
     if let Some(rate) = bitrate {
-        validate_bitrate(rate)?;
+        let requested = parse_bitrate(rate, format)?;
+        if requested != Bitrate::Best && !use_playlist {
+            let audio_only = format == "mp3";
+            match probe_formats(download_url, audio_only, request_headers).await {
+                Ok(formats) => check_bitrate_available(requested, &formats, audio_only)?,
+                Err(e) => debug!("Could not check the bitrate against available formats: {}", e),
+            }
+        }
     }
 
+    // Chapters are looked up before anything is counted or downloaded
+    let chapters = match chapters {
+        Some(selection) => {
+            let chapters = resolve_chapters(download_url, selection, request_headers).await?;
+            let titles: Vec<String> = chapters.iter().map(|c| format!("{}. {}", c.number, c.title)).collect();
+            println!("{}: {}", "Chapters".blue(), titles.join(", "));
+            chapters
+        }
+        None => Vec::new(),
+    };
+
+    check_ffmpeg_features(&required_features(
+        format,
+        start_time.is_some() || end_time.is_some() || !chapters.is_empty(),
+        transcode,
+        format_preferences.video_codec,
+        ffmpeg_args,
+    ))?;
+
     let mut counter = DownloadCounter::load_from_disk()?;
     if !force_download && !counter.can_download() {
         println!("{}", "⚠️ Daily download limit reached for free version ⚠️".bright_red());
@@ -1136,22 +1667,31 @@ pub async fn download_video_free(
     let folder_type = if format == "mp3" { "audio" } else { "videos" };
     let download_dir = initialize_download_dir(output_dir.map(|s| s.as_str()), "rustloader", folder_type)?;
     
-    let mut should_use_unique_filename = false;
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    // Title of an existing download this one must not overwrite, and how to rename it
+    let mut duplicate = None;
 
-    if !force_download && !use_playlist {
-        match get_video_title(url).await {
+    // A shared file has no title to check until it's downloaded, and
+    // chapters are saved under names of their own
+    if !force_download && !use_playlist && cloud_link.is_none() && chapters.is_empty() {
+        match get_video_title(url, request_headers).await {
             Ok(video_title) => {
+                // The existing file was saved under the transliterated title
+                let video_title = if transliterate { transliterate_name(&video_title) } else { video_title };
                 if let Some(existing_file) = check_if_video_exists(&download_dir, format, &video_title) {
                     println!("{}: {:?}", "Found existing download".yellow(), existing_file);
 
                     if !prompt_for_redownload()? {
                         println!("{}", "Download cancelled.".green());
-                        return Ok(existing_file.to_string_lossy().into_owned());
+                        return Ok(DownloadOutcome {
+                            output_path: existing_file.to_string_lossy().into_owned(),
+                            file_path: Some(existing_file),
+                            backend: None,
+                        });
                     }
 
-                    should_use_unique_filename = true;
-                    println!("{}: Will append timestamp to filename", "Duplicate download".blue());
+                    let naming = load_config().map(|config| config.duplicate_naming).unwrap_or_default();
+                    println!("{}: Saving under a new name ({} naming)", "Duplicate download".blue(), naming.as_str());
+                    duplicate = Some((video_title, naming));
                 }
             }
             Err(e) => {
@@ -1168,13 +1708,43 @@ pub async fn download_video_free(
         }
     }
 
-    let output_path = if should_use_unique_filename {
-        format_output_path_with_timestamp(&download_dir, format, &timestamp)?
+    let output_path = if media_naming.is_some() {
+        // Renamed for media centers once the metadata of each ID is known
+        format_id_output_path(&download_dir, format)?
+    } else if let Some((video_title, naming)) = &duplicate {
+        duplicate_output_path(&download_dir, format, video_title, *naming, Local::now())?
     } else {
         format_output_path(&download_dir, format)?
     };
 
-    let progress = Arc::new(DownloadProgress::new());
+    let user_config = load_config().unwrap_or_default();
+    let max_filesize = user_config.max_filesize_limit()?;
+    let staging_dir = match user_config.staging_path() {
+        Ok(Some(dir)) => match fs::create_dir_all(&dir) {
+            Ok(()) => {
+                println!("{}: {}", "Staging in".blue(), dir.display());
+                Some(dir.to_string_lossy().into_owned())
+            }
+            Err(e) => {
+                println!("{}: {}. Downloading directly.", "Warning: Could not use the staging directory".yellow(), e);
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            println!("{}: {}. Downloading directly.", "Warning: Invalid staging directory".yellow(), e);
+            None
+        }
+    };
+    let progress = Arc::new(DownloadProgress::new(user_config.memory_cleanup));
+    if let Some(speed) = expected_speed(url) {
+        debug!("Starting from the last measured speed of {}/s", format_size(speed as u64, BINARY));
+        progress.seed_speed(speed);
+    }
+    let _remember_throughput = RememberThroughput {
+        url: url.to_string(),
+        progress: progress.clone(),
+    };
     let pb = Arc::new(ProgressBar::new(100));
     pb.set_style(
         ProgressStyle::default_bar()
@@ -1185,17 +1755,87 @@ pub async fn download_video_free(
 
     pb.set_message(format!("Size: {} | Speed: {} | ETA: {}", "Calculating...", "Connecting...", "Calculating..."));
 
-    let promo = DownloadPromo::new();
-    println!("\n{}\n", promo.get_random_download_message().bright_yellow());
-
     println!("{}: {}", "Video quality".blue(), quality.unwrap_or("auto"));
     
+    // A media file linked directly needs nothing from yt-dlp unless it is
+    // cut, converted or staged, so the built-in downloader fetches it
+    let direct_extension = direct_media_extension(download_url).filter(|extension| {
+        cloud_link.is_none()
+            && !use_playlist
+            && start_time.is_none()
+            && end_time.is_none()
+            && chapters.is_empty()
+            && format_id.is_none()
+            && bitrate.is_none()
+            && transcode.is_none()
+            && ffmpeg_args.is_empty()
+            && vertical.mode == VerticalMode::Keep
+            && staging_dir.is_none()
+            && user_config.direct_connections() > 0
+            && matches_format(extension, format)
+    });
+    let direct_file = match direct_extension {
+        Some(extension) => match direct::probe(download_url, request_headers).await {
+            Ok(remote) => Some((remote, extension)),
+            Err(e) => {
+                debug!("Leaving {} to yt-dlp: {}", download_url, e);
+                None
+            }
+        },
+        None => None,
+    };
+    
+    // Vertical sources change how the quality limit, upscaling and
+    // reframing apply; only look when one of them is in play
+    let needs_shape = quality.is_some() || transcode.is_some() || vertical.mode != VerticalMode::Keep;
+    let frame_size = if format == "mp3" || use_playlist || !needs_shape || direct_file.is_some() {
+        None
+    } else {
+        probe_frame_size(url, request_headers).await
+    };
+    let is_vertical = frame_size.is_some_and(|size| size.is_vertical());
+    if let Some(size) = frame_size.filter(|size| size.is_vertical()) {
+        println!("{}: {}x{}", "Vertical video detected".blue(), size.width, size.height);
+    }
+    
+    // Prefer aria2c when installed; yt-dlp's own downloader is the fallback
+    // Cloud hosts throttle aria2c's parallel connections, so shared files
+    // use yt-dlp's own downloader
+    let mut backend = if cloud_link.is_some() { DownloadBackend::Native } else { default_backend() };
+    let mut fell_back = false;
+    let started_at = SystemTime::now();
+    
     // Execute the download with retries
     let mut retry_count = 0;
     let mut stderr_output = String::new();
     let mut successful = false;
     
-    'retry_loop: while retry_count <= MAX_RETRIES {
+    // The built-in downloader resumes and retries on its own
+    let mut direct_path = None;
+    if let Some((remote, extension)) = direct_file {
+        println!("{}", "Direct media link, downloading without yt-dlp...".green());
+        let options = DirectOptions {
+            dir: download_dir.clone(),
+            extension,
+            connections: user_config.direct_connections(),
+            rate_limit: current_limit(),
+            follow_bandwidth: true,
+            max_filesize: max_filesize.as_deref().map(parse_size_limit).transpose()?.flatten(),
+            force: force_download,
+        };
+        let on_direct_progress = direct_progress(Arc::clone(&progress), Arc::clone(&pb), on_progress.clone());
+        let path = download_direct(download_url, &remote, &options, request_headers, on_direct_progress).await?;
+        pb.finish_with_message("Download completed");
+        backend = DownloadBackend::Direct;
+        successful = true;
+        direct_path = Some(path);
+    }
+    
+    // yt-dlp can't change its limit, so a new one restarts it to resume
+    let mut limit_watch = RateLimitWatch::new(current_limit()).with_min_change(MIN_RESTART_CHANGE);
+    let mut resume_for_limit = false;
+    
+    'retry_loop: while !successful && retry_count <= MAX_RETRIES {
         if retry_count > 0 {
             // If we're retrying, first check network connectivity
             info!("Checking network connectivity before retry #{}", retry_count);
@@ -1254,18 +1894,43 @@ pub async fn download_video_free(
             }
         }
         
+        // Failure context should describe the latest attempt only
+        if let Some(tail) = &stderr_tail {
+            tail.clear();
+        }
+        
         // Build a fresh command for each attempt
-        let mut command = YtdlpCommandBuilder::new(url, &output_path)
+        let builder = YtdlpCommandBuilder::new(download_url, &output_path)
             .with_format(format)
             .with_quality(quality)
+            .with_format_id(format_id)
+            .with_format_preferences(format_preferences)
             .with_time_range(start_time, end_time)
             .with_playlist(use_playlist)
             .with_subtitles(download_subtitles)
-            .with_force_download(retry_count > 0 && !progress.is_resumable() || force_download)
+            // aria2c's partial files can't be resumed natively, so a fallback starts over
+            .with_force_download(!resume_for_limit && (retry_count > 0 && !progress.is_resumable() || force_download || fell_back))
             .with_bitrate(bitrate)
-            .build();
-
+            .with_transcode(transcode)
+            .with_request_headers(request_headers)
+            .with_backend(backend)
+            .with_ffmpeg_args(ffmpeg_args)
+            .with_rate_limit(limit_watch.applied().map(format_rate_limit))
+            .with_max_filesize(max_filesize.clone())
+            .with_no_merge(no_merge)
+            .with_vertical(is_vertical)
+            .with_staging_dir(staging_dir.clone())
+            // The file name loses the original title, the tags keep it
+            .with_embedded_metadata(transliterate)
+            .with_chapters(&chapters);
         if retry_count == 0 {
+            builder.announce();
+        }
+        let mut command = builder.build()?;
+
+        if resume_for_limit {
+            resume_for_limit = false;
+        } else if retry_count == 0 {
             println!("{}", "Starting download...".green());
         } else {
             println!("{}", format!("Restarting download (attempt {}/{})...", retry_count, MAX_RETRIES).yellow());
@@ -1283,6 +1948,10 @@ pub async fn download_video_free(
                     }
                     io::ErrorKind::PermissionDenied => {
                         error!("Permission denied when running yt-dlp: {}", e);
+                        if let Some(blocked) = explain_launch_failure("yt-dlp") {
+                            eprintln!("{}", blocked.to_string().red());
+                            return Err(blocked);
+                        }
                         eprintln!("{}", "Error: Permission denied when running yt-dlp. Check your file permissions.".red());
                         return Err(AppError::IoError(e));
                     }
@@ -1356,6 +2025,7 @@ pub async fn download_video_free(
             let mut lines = stdout_buffered.lines();
             let pb_clone = Arc::clone(&pb);
             let progress_clone = Arc::clone(&progress);
+            let on_progress_clone = on_progress.clone();
 
             tokio::spawn(async move {
                 // Preallocate a reasonable-sized string to avoid reallocations
@@ -1364,56 +2034,66 @@ pub async fn download_video_free(
                 // Track updates to limit processing frequency
                 let mut last_gui_update = Instant::now();
                 const GUI_UPDATE_INTERVAL_MS: u64 = 100; // Update UI every 100ms maximum
+                let mut seen_progress = false;
+                let mut warned_unreadable = false;
                 
                 while let Ok(Some(line)) = lines.next_line().await {
                     // Handle download progress updates
-                    if line.starts_with("download:") {
-                        if let Some(progress_str) = line.strip_prefix("download:") {
-                            // Process the download progress
-                            let now = Instant::now();
-                            let should_update_ui = now.duration_since(last_gui_update).as_millis() > 
-                                                  GUI_UPDATE_INTERVAL_MS as u128;
-                            
-                            // Parse progress info
-                            let parts: Vec<&str> = progress_str.split('/').collect();
-                            if parts.len() == 2 {
-                                if let (Ok(downloaded), Ok(total)) = (
-                                    parts[0].trim().parse::<u64>(),
-                                    parts[1].trim().parse::<u64>(),
-                                ) {
-                                    if total > 0 {
-                                        // Always update internal progress tracking
-                                        progress_clone.update(downloaded, total);
-                                        
-                                        // But only update UI at specified intervals to reduce CPU/memory usage
-                                        if should_update_ui {
-                                            let percentage = progress_clone.get_percentage();
-                                            pb_clone.set_position(percentage);
-                                            
-                                            // Format message only when updating UI
-                                            line_buffer.clear();
-                                            let size = progress_clone.format_file_size();
-                                            let speed = progress_clone.format_speed();
-                                            let eta = progress_clone.format_eta();
-                                            
-                                            // Use string concatenation to avoid allocations
-                                            line_buffer.push_str("Size: ");
-                                            line_buffer.push_str(&size);
-                                            line_buffer.push_str(" | Speed: ");
-                                            line_buffer.push_str(&speed);
-                                            line_buffer.push_str(" | ETA: ");
-                                            line_buffer.push_str(&eta);
-                                            
-                                            pb_clone.set_message(line_buffer.clone());
-                                            last_gui_update = now;
-                                        }
-                                    }
-                                }
+                    let update = match parse_progress_line(&line) {
+                        ProgressLine::Progress(update) => update,
+                        ProgressLine::Unreadable => {
+                            if !warned_unreadable {
+                                warn!("Could not read yt-dlp's progress output, progress may not update: {}", line);
+                                warned_unreadable = true;
                             }
+                            continue;
+                        }
+                        ProgressLine::Other => {
+                            // Only print non-progress messages
+                            println!("{}", line);
+                            continue;
+                        }
+                    };
+                    if !seen_progress {
+                        debug!("yt-dlp reports progress as {}", update.format.describe());
+                        seen_progress = true;
+                    }
+                    
+                    let now = Instant::now();
+                    let should_update_ui = now.duration_since(last_gui_update).as_millis() > 
+                                          GUI_UPDATE_INTERVAL_MS as u128;
+                    let (downloaded, Some(total)) = (update.downloaded, update.total) else {
+                        continue;
+                    };
+                    
+                    // Always update internal progress tracking
+                    progress_clone.update(downloaded, total);
+                    
+                    // But only update UI at specified intervals to reduce CPU/memory usage
+                    if should_update_ui {
+                        let percentage = progress_clone.get_percentage();
+                        pb_clone.set_position(percentage);
+                        
+                        // Format message only when updating UI
+                        line_buffer.clear();
+                        let size = progress_clone.format_file_size();
+                        let speed = progress_clone.format_speed();
+                        let eta = progress_clone.format_eta();
+                        
+                        // Use string concatenation to avoid allocations
+                        line_buffer.push_str("Size: ");
+                        line_buffer.push_str(&size);
+                        line_buffer.push_str(" | Speed: ");
+                        line_buffer.push_str(&speed);
+                        line_buffer.push_str(" | ETA: ");
+                        line_buffer.push_str(&eta);
+                        
+                        pb_clone.set_message(line_buffer.clone());
+                        last_gui_update = now;
+                        
+                        if let Some(callback) = &on_progress_clone {
+                            callback(downloaded, total, progress_clone.get_speed());
                         }
-                    } else {
-                        // Only print non-progress messages
-                        println!("{}", line);
                     }
                 }
                 
@@ -1431,12 +2111,17 @@ pub async fn download_video_free(
             let mut lines = stderr_reader.lines();
             let stderr_tx_clone = stderr_tx.clone();
             let progress_clone = Arc::clone(&progress);
+            let stderr_tail_clone = stderr_tail.clone();
 
             tokio::spawn(async move {
                 // Preallocate a reasonable buffer size for stderr output
                 let mut error_buffer = String::with_capacity(512);
                 
                 while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(tail) = &stderr_tail_clone {
+                        tail.push(&line);
+                    }
+                    
                     // Only store important error messages for analysis
                     // This reduces memory usage for long-running downloads with many warnings
                     let is_important_error = line.contains("Error") || 
@@ -1444,7 +2129,8 @@ pub async fn download_video_free(
                                             line.contains("HTTP") ||
                                             line.contains("Connection") ||
                                             line.contains("Resuming") ||
-                                            line.contains("Unable to download");
+                                            line.contains("Unable to download") ||
+                                            line.contains("aria2c");
                                             
                     if is_important_error {
                         // Save important stderr output for later analysis
@@ -1512,18 +2198,23 @@ pub async fn download_video_free(
             collected
         });
 
-        // Wait for either child process to complete or stall detection to trigger
-        let (status_result, is_stalled) = tokio::select! {
-            status = child.wait() => (status, false),
+        // Wait for the child process to complete, stall detection to trigger
+        // or the bandwidth limit to change
+        let (status_result, is_stalled, new_limit) = tokio::select! {
+            status = child.wait() => (status, false, None),
             stalled = stall_detection => {
                 if stalled.unwrap_or(false) {
                     // Kill the process if stalled
                     let _ = child.kill().await;
-                    (Err(io::Error::new(io::ErrorKind::TimedOut, "Download stalled")), true)
+                    (Err(io::Error::new(io::ErrorKind::TimedOut, "Download stalled")), true, None)
                 } else {
-                    (Err(io::Error::new(io::ErrorKind::Other, "Stall detector exited unexpectedly")), false)
+                    (Err(io::Error::new(io::ErrorKind::Other, "Stall detector exited unexpectedly")), false, None)
                 }
             }
+            limit = next_limit_change(&mut limit_watch) => {
+                let _ = child.kill().await;
+                (child.wait().await, false, Some(limit))
+            }
         };
 
         // Signal the stall detector to stop
@@ -1532,6 +2223,16 @@ pub async fn download_video_free(
         // Get collected stderr output
         stderr_output = stderr_collector.await.unwrap_or_default();
         
+        // Not a failure, so it doesn't count as a retry
+        if let Some(limit) = new_limit {
+            let shown = limit.map(format_rate_limit).unwrap_or_else(|| "unlimited".to_string());
+            info!("Bandwidth limit changed to {}, restarting yt-dlp", shown);
+            println!("{} {}", "Bandwidth limit changed, resuming at".blue(), shown);
+            resume_for_limit = true;
+            progress.prepare_for_retry();
+            continue 'retry_loop;
+        }
+        
         match status_result {
             Ok(status) => {
                 if status.success() {
@@ -1540,6 +2241,10 @@ pub async fn download_video_free(
                     successful = true;
                     break 'retry_loop;
                 } else {
+                    if let Some(blocked) = explain_killed("yt-dlp", &status) {
+                        error!("yt-dlp was blocked by Gatekeeper");
+                        return Err(blocked);
+                    }
                     let exit_code = status.code().unwrap_or(0);
                     warn!("Download failed with exit code {}", exit_code);
                     
@@ -1549,6 +2254,15 @@ pub async fn download_video_free(
                         return Err(AppError::DownloadError(
                             "Download failed, likely due to missing or incompatible ffmpeg. Please install ffmpeg and try again.".to_string(),
                         ));
+                    } else if backend == DownloadBackend::Aria2c && is_aria2c_failure(&stderr_output) {
+                        // Some streams (e.g. certain HLS manifests) trip up aria2c but work natively.
+                        // This switch happens once and doesn't count as a retry.
+                        warn!("aria2c failed, retrying with the native downloader");
+                        println!("{}", "aria2c could not download this video. Retrying with the built-in downloader...".yellow());
+                        backend = DownloadBackend::Native;
+                        fell_back = true;
+                        progress.prepare_for_retry();
+                        continue 'retry_loop;
                     } else if start_time.is_some() || end_time.is_some() && !*FFMPEG_AVAILABLE {
                         error!("Time-based download failed due to missing ffmpeg");
                         return Err(AppError::DownloadError(
@@ -1628,13 +2342,146 @@ pub async fn download_video_free(
         counter.increment()?;
     }
 
-    let _ = Notification::new()
-        .summary("Download Complete")
-        .body(&format!("{} file downloaded successfully.", format.to_uppercase()))
-        .show();
-
     println!("{} {} {}", "Download completed successfully.".green(), format.to_uppercase(), "file saved.".green());
-    println!("\n{}\n", promo.get_random_completion_message().bright_yellow());
+    debug!("Memory cleanup stats: {:?}", progress.memory_stats());
+    // Pro users see no upgrade messages; free users see one every few downloads
+    let mut promo = PromoService::load();
+    promo.note_download();
+    if let Some(message) = promo.message(PromoSlot::Completion) {
+        println!("\n{}\n", message.bright_yellow());
+    }
 
-    Ok(output_path)
+    // yt-dlp only reports the template, so find the file it just wrote
+    if let (true, Some(naming)) = (use_playlist, media_naming) {
+        let files = crate::file_actions::files_since(&download_dir, format, started_at);
+        match apply_media_naming(url, true, files, naming, request_headers).await {
+            Ok(renamed) => println!("{} {} file(s) for media centers", "Named".blue(), renamed.len()),
+            Err(e) => println!("{}: {}", "Warning: Could not name the files for media centers".yellow(), e),
+        }
+    }
+    let file_path = if direct_path.is_some() {
+        direct_path
+    } else if use_playlist {
+        None
+    } else if no_merge {
+        println!("{}", "Video and audio were saved separately. Combine them later with: rustloader mux <video> <audio>".blue());
+        None
+    } else if !chapters.is_empty() {
+        println!("{} {} {}", "Saved".blue(), chapters.len(), "chapter file(s)".blue());
+        None
+    } else {
+        crate::file_actions::newest_file_since(&download_dir, format, started_at)
+    };
+    if let (VerticalMode::Pad | VerticalMode::Crop, Some(file)) = (vertical.mode, &file_path) {
+        match frame_size.and_then(|size| reframe_filter(size, vertical)) {
+            Some(filter) => {
+                println!("{} {} ({})", "Reframing to".blue(), vertical.target, vertical.mode.as_str());
+                if let Err(e) = reframe(file, &filter).await {
+                    println!("{}: {}", "Warning: Could not reframe the video".yellow(), e);
+                }
+            }
+            None if frame_size.is_none() => {
+                println!("{}", "Warning: Could not determine the video size, keeping the original frame.".yellow());
+            }
+            None => debug!("Video already has the {} aspect ratio", vertical.target),
+        }
+    }
+    if let (true, Some(file)) = (trim_silence, &file_path) {
+        match crate::silence::trim_silence(file, &user_config.silence_trim).await {
+            Ok(secs) if secs > 0.0 => println!("{} {:.1}s", "Trimmed silence:".blue(), secs),
+            Ok(_) => println!("{}", "No leading or trailing silence found.".blue()),
+            Err(e) => println!("{}: {}", "Warning: Could not trim silence".yellow(), e),
+        }
+    }
+    let file_path = match (media_naming, file_path) {
+        (Some(naming), Some(file)) => match apply_media_naming(url, false, vec![file.clone()], naming, request_headers).await {
+            Ok(mut renamed) => {
+                let renamed = renamed.pop().unwrap_or(file);
+                println!("{}: {}", "Saved as".blue(), renamed.display());
+                Some(renamed)
+            }
+            Err(e) => {
+                println!("{}: {}", "Warning: Could not name the file for media centers".yellow(), e);
+                Some(file)
+            }
+        },
+        (_, file_path) => file_path,
+    };
+    let file_path = match (transliterate, file_path) {
+        (true, Some(file)) => match transliterate_file(&file) {
+            Ok(renamed) => {
+                if renamed != file {
+                    println!("{}: {}", "Saved as".blue(), renamed.display());
+                }
+                Some(renamed)
+            }
+            Err(e) => {
+                println!("{}: {}", "Warning: Could not transliterate the file name".yellow(), e);
+                Some(file)
+            }
+        },
+        (_, file_path) => file_path,
+    };
+    let file_path = match file_path {
+        Some(file) => Some(crate::plugins::post_process_with_plugins(&file, format, url, &download_dir).await),
+        None => None,
+    };
+    let file_path = match (split_size, file_path) {
+        (Some(part_size), Some(file)) => match split_file(&file, part_size) {
+            Ok(Some(manifest)) => {
+                println!("{}: {}", "Split into parts listed in".blue(), manifest.display());
+                Some(manifest)
+            }
+            Ok(None) => Some(file),
+            Err(e) => {
+                println!("{}: {}", "Warning: Could not split the file".yellow(), e);
+                Some(file)
+            }
+        },
+        (_, file_path) => file_path,
+    };
+    notify_download_complete(format, file_path.as_deref());
+    crate::plugins::notify_plugins(file_path.as_deref(), format, url).await;
+
+    // yt-dlp's output is a template; the built-in downloader's is the file
+    let output_path = match (backend, &file_path) {
+        (DownloadBackend::Direct, Some(file)) => file.to_string_lossy().into_owned(),
+        _ => output_path,
+    };
+    Ok(DownloadOutcome {
+        output_path,
+        file_path,
+        backend: Some(backend),
+    })
+}
+
+/// Report the built-in downloader's progress like yt-dlp's: to the progress
+/// tracker, the progress bar and the caller's callback, at most every 100ms
+fn direct_progress(
+    progress: Arc<DownloadProgress>,
+    pb: Arc<ProgressBar>,
+    on_progress: Option<ProgressCallback>,
+) -> DirectProgress {
+    const UI_UPDATE_INTERVAL_MS: u128 = 100;
+    let last_ui_update: Mutex<Option<Instant>> = Mutex::new(None);
+    Arc::new(move |downloaded, total| {
+        progress.update(downloaded, total);
+        {
+            let mut last = last_ui_update.lock().unwrap();
+            if last.is_some_and(|at| at.elapsed().as_millis() < UI_UPDATE_INTERVAL_MS) && downloaded != total {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+        pb.set_position(progress.get_percentage());
+        pb.set_message(format!(
+            "Size: {} | Speed: {} | ETA: {}",
+            progress.format_file_size(),
+            progress.format_speed(),
+            progress.format_eta()
+        ));
+        if let Some(callback) = &on_progress {
+            callback(downloaded, total, progress.get_speed());
+        }
+    })
 }
\ No newline at end of file
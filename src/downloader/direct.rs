@@ -0,0 +1,623 @@
+// src/downloader/direct.rs
+//! Built-in downloader for direct media links, e.g. an `.mp4` or `.mp3` on a
+//! web server or CDN. There is nothing for yt-dlp to extract or convert in
+//! such a file, so it is fetched over HTTP instead: partial downloads resume
+//! with range requests, and large files are split over several connections
+//! when the server accepts ranges.
+//!
+//! Partial data is kept next to the final file as `<name>.part`, or as
+//! `<name>.part-Frag<i>of<n>` per connection, so `cleanup --scan` finds it
+//! like yt-dlp's. `<name>.ytdl` records the version of the file it came
+//! from, and range requests carry `If-Range`, so a file replaced on the
+//! server is downloaded again instead of spliced onto old data.
+
+use crate::bandwidth::{RateLimitWatch, LIMIT_CHECK_INTERVAL};
+use crate::error::{AppError, NetworkErrorKind};
+use crate::http_headers::RequestHeaders;
+use crate::media_naming::clean_name;
+use log::{debug, warn};
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::proxy::client_builder;
+use reqwest::{Client, Response, StatusCode, Url};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout};
+
+/// File extensions treated as direct media links
+pub const DIRECT_EXTENSIONS: [&str; 13] = [
+    "mp4", "m4v", "webm", "mkv", "mov", "avi", "mp3", "m4a", "aac", "ogg", "opus", "flac", "wav",
+];
+
+/// Connections per download unless configured
+pub const DEFAULT_DIRECT_CONNECTIONS: usize = 4;
+
+/// Most connections one download opens
+pub const MAX_DIRECT_CONNECTIONS: usize = 16;
+
+/// Smallest share of a file given its own connection
+pub const MIN_SEGMENT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Attempts per segment before the download fails; each resumes the last
+const SEGMENT_ATTEMPTS: u32 = 3;
+
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// A read that waits longer than this fails the attempt
+const READ_TIMEOUT_SECS: u64 = 30;
+
+/// Receives (downloaded bytes, total bytes or 0 when unknown) as data arrives
+pub type DirectProgress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// The extension of a direct media link: an http(s) URL whose path ends in
+/// one of [`DIRECT_EXTENSIONS`], lowercased
+pub fn direct_media_extension(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let name = url.path_segments()?.next_back()?;
+    let (_, extension) = name.rsplit_once('.')?;
+    let extension = extension.to_ascii_lowercase();
+    DIRECT_EXTENSIONS.contains(&extension.as_str()).then_some(extension)
+}
+
+/// Whether a file with `extension` already is the requested `format`, so
+/// it can be saved as is
+pub fn matches_format(extension: &str, format: &str) -> bool {
+    extension == format || (format == "mp4" && extension == "m4v")
+}
+
+/// What the server tells about a direct media file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteFile {
+    /// Size in bytes, when the server sends it
+    pub size: Option<u64>,
+    /// Whether the server answers range requests, so the file can resume
+    /// and be split over several connections
+    pub accepts_ranges: bool,
+    /// File name from Content-Disposition, if any
+    pub file_name: Option<String>,
+    /// Strong ETag, or else Last-Modified, identifying this version of the
+    /// file, if the server sends one
+    pub validator: Option<String>,
+}
+
+/// Options for a direct download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectOptions {
+    /// Directory the file is saved in
+    pub dir: PathBuf,
+    /// Extension of the saved file
+    pub extension: String,
+    /// Connections to split a large file over
+    pub connections: usize,
+    /// Bytes per second over all connections, if limited
+    pub rate_limit: Option<u64>,
+    /// Switch to the limit of the bandwidth profiles or override when it
+    /// changes during the download
+    pub follow_bandwidth: bool,
+    /// Largest file to download
+    pub max_filesize: Option<u64>,
+    /// Start over instead of resuming partial data
+    pub force: bool,
+}
+
+/// Ask the server about `url` with a one-byte range request. Fails for web
+/// pages and errors, so the caller can leave the URL to yt-dlp.
+pub async fn probe(url: &str, request_headers: &RequestHeaders) -> Result<RemoteFile, AppError> {
+    let response = build_client(request_headers)?
+        .get(url)
+        .header(header::RANGE, "bytes=0-0")
+        .send()
+        .await?
+        .error_for_status()?;
+    let headers = response.headers();
+    let content_type = header_str(headers, header::CONTENT_TYPE).unwrap_or_default();
+    if content_type.starts_with("text/html") {
+        return Err(AppError::ValidationError(format!("{} is a web page, not a media file", url)));
+    }
+    let accepts_ranges = response.status() == StatusCode::PARTIAL_CONTENT;
+    let size = if accepts_ranges {
+        header_str(headers, header::CONTENT_RANGE).and_then(content_range_total)
+    } else {
+        response.content_length()
+    };
+    // If-Range only takes strong ETags
+    let validator = header_str(headers, header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header_str(headers, header::LAST_MODIFIED))
+        .map(str::to_string);
+    Ok(RemoteFile {
+        size,
+        accepts_ranges,
+        file_name: header_str(headers, header::CONTENT_DISPOSITION).and_then(content_disposition_file_name),
+        validator,
+    })
+}
+
+/// Download `url` into `options.dir`, resuming partial data from an earlier
+/// attempt, and return the saved file
+pub async fn download_direct(
+    url: &str,
+    remote: &RemoteFile,
+    options: &DirectOptions,
+    request_headers: &RequestHeaders,
+    on_progress: DirectProgress,
+) -> Result<PathBuf, AppError> {
+    if let Some(path) = download_version(url, remote, options, request_headers, Arc::clone(&on_progress)).await? {
+        return Ok(path);
+    }
+    warn!("{} changed on the server during the download, starting over", url);
+    let remote = probe(url, request_headers).await?;
+    download_version(url, &remote, options, request_headers, on_progress)
+        .await?
+        .ok_or_else(|| AppError::DownloadError(format!("{} keeps changing on the server", url)))
+}
+
+/// Download the version of `url` described by `remote`; `None` when the
+/// server has replaced it since
+async fn download_version(
+    url: &str,
+    remote: &RemoteFile,
+    options: &DirectOptions,
+    request_headers: &RequestHeaders,
+    on_progress: DirectProgress,
+) -> Result<Option<PathBuf>, AppError> {
+    if let (Some(size), Some(limit)) = (remote.size, options.max_filesize) {
+        if size > limit {
+            return Err(AppError::DownloadError(format!(
+                "The file is {} bytes, more than the {} byte size limit",
+                size, limit
+            )));
+        }
+    }
+
+    let name = file_name(url, remote.file_name.as_deref(), &options.extension);
+    let dest = available_path(&options.dir.join(&name), options.force);
+    let segments = match (remote.size, remote.accepts_ranges) {
+        (Some(size), true) => plan_segments(size, options.connections),
+        _ => Vec::new(),
+    };
+    let parts: Vec<(PathBuf, Option<Range<u64>>)> = if segments.len() > 1 {
+        let count = segments.len();
+        segments
+            .into_iter()
+            .enumerate()
+            .map(|(index, range)| (fragment_path(&dest, index, count), Some(range)))
+            .collect()
+    } else {
+        vec![(part_path(&dest), None)]
+    };
+
+    // Partial data only resumes when it came from the same version of the file
+    let version_path = validator_path(&dest);
+    let same_version = fs::read_to_string(&version_path).await.ok() == remote.validator;
+    let mut done = Vec::with_capacity(parts.len());
+    for (path, range) in &parts {
+        let have = if options.force || !remote.accepts_ranges || !same_version {
+            0
+        } else {
+            fs::metadata(path).await.map(|meta| meta.len()).unwrap_or(0)
+        };
+        // More data than the part holds came from a different file or split
+        let have = match range {
+            Some(range) if have > range.end - range.start => 0,
+            None if remote.size.is_some_and(|size| have > size) => 0,
+            _ => have,
+        };
+        done.push(AtomicU64::new(have));
+    }
+    let resumed: u64 = done.iter().map(|bytes| bytes.load(Ordering::SeqCst)).sum();
+    if resumed > 0 {
+        debug!("Resuming {} from {} bytes over {} part(s)", url, resumed, parts.len());
+    }
+    match &remote.validator {
+        Some(validator) if remote.accepts_ranges => fs::write(&version_path, validator).await?,
+        _ => remove_if_present(&version_path).await?,
+    }
+
+    let transfer = Arc::new(Transfer {
+        done,
+        total: remote.size.unwrap_or(0),
+        max_filesize: options.max_filesize,
+        throttle: Mutex::new(Throttle::new(options.rate_limit)),
+        validator: remote.validator.clone(),
+        replaced: AtomicBool::new(false),
+        on_progress,
+    });
+    transfer.report();
+    if options.follow_bandwidth {
+        follow_bandwidth(&transfer, options.rate_limit);
+    }
+
+    let client = build_client(request_headers)?;
+    let mut tasks = JoinSet::new();
+    for (index, (path, range)) in parts.iter().cloned().enumerate() {
+        let client = client.clone();
+        let url = url.to_string();
+        let transfer = Arc::clone(&transfer);
+        tasks.spawn(async move { fetch_part(&client, &url, index, range, &path, &transfer).await });
+    }
+    while let Some(result) = tasks.join_next().await {
+        // Dropping the set aborts the other connections
+        let fetched = result.map_err(|e| AppError::DownloadError(format!("Download connection failed: {}", e)))?;
+        if fetched.is_err() && transfer.replaced.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        fetched?;
+    }
+
+    let (first, _) = &parts[0];
+    for (path, _) in &parts[1..] {
+        append_file(first, path).await?;
+        fs::remove_file(path).await?;
+    }
+    fs::rename(first, &dest).await?;
+    remove_if_present(&version_path).await?;
+    Ok(Some(dest))
+}
+
+/// Split `size` bytes into at most `connections` ranges of at least
+/// [`MIN_SEGMENT_BYTES`] each. Empty for an empty file.
+pub fn plan_segments(size: u64, connections: usize) -> Vec<Range<u64>> {
+    let connections = connections.clamp(1, MAX_DIRECT_CONNECTIONS) as u64;
+    let count = (size / MIN_SEGMENT_BYTES).clamp(1, connections);
+    let segment = size.div_ceil(count);
+    (0..count)
+        .map(|index| index * segment..((index + 1) * segment).min(size))
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Where a single-connection download keeps its partial data
+pub fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Where connection `index` of `count` keeps its partial data. The count is
+/// part of the name so a different split never resumes the wrong bytes.
+pub fn fragment_path(dest: &Path, index: usize, count: usize) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(format!(".part-Frag{}of{}", index, count));
+    PathBuf::from(name)
+}
+
+/// Where the version of the file that partial data for `dest` came from is
+/// kept, named like yt-dlp's resume data
+pub fn validator_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(".ytdl");
+    PathBuf::from(name)
+}
+
+/// The total size from a Content-Range header, e.g. 1234 for "bytes 0-0/1234"
+pub fn content_range_total(value: &str) -> Option<u64> {
+    value.trim().strip_prefix("bytes ")?.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// The file name from a Content-Disposition header such as
+/// `attachment; filename="clip.mp4"`
+pub fn content_disposition_file_name(value: &str) -> Option<String> {
+    value.split(';').map(str::trim).find_map(|param| {
+        let (key, name) = param.split_once('=')?;
+        let name = name.trim().trim_matches('"');
+        (key.trim().eq_ignore_ascii_case("filename") && !name.is_empty()).then(|| name.to_string())
+    })
+}
+
+/// A safe name for the saved file: the server's name or the last part of
+/// the URL path, ending in `extension`
+pub fn file_name(url: &str, disposition_name: Option<&str>, extension: &str) -> String {
+    let from_url = || {
+        let url = Url::parse(url).ok()?;
+        let segment = url.path_segments()?.next_back()?.to_string();
+        Some(percent_decode(&segment))
+    };
+    let raw = disposition_name.map(str::to_string).or_else(from_url).unwrap_or_default();
+    // Only the last component, whatever separators the server sent
+    let raw = raw.rsplit(['/', '\\']).next().unwrap_or_default();
+    let stem = match raw.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => raw,
+    };
+    format!("{}.{}", clean_name(stem), extension)
+}
+
+/// `dest`, or with " (2)", " (3)", ... added when a finished file of that
+/// name exists and is to be kept
+fn available_path(dest: &Path, force: bool) -> PathBuf {
+    if force || !dest.exists() {
+        return dest.to_path_buf();
+    }
+    let stem = dest.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = dest.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    (2..)
+        .map(|n| dest.with_file_name(format!("{} ({}).{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .expect("an unused name")
+}
+
+/// Decode %XX escapes, keeping invalid ones as they are
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn header_str(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn build_client(request_headers: &RequestHeaders) -> Result<Client, AppError> {
+    let mut headers = HeaderMap::new();
+    for extra in &request_headers.headers {
+        let name = HeaderName::from_bytes(extra.name.as_bytes())
+            .map_err(|e| AppError::ValidationError(format!("Invalid header name {}: {}", extra.name, e)))?;
+        let value = HeaderValue::from_str(&extra.value)
+            .map_err(|e| AppError::ValidationError(format!("Invalid value for header {}: {}", extra.name, e)))?;
+        headers.insert(name, value);
+    }
+    Ok(client_builder(request_headers.proxy.as_deref())?
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .user_agent(request_headers.user_agent())
+        .default_headers(headers)
+        .build()?)
+}
+
+/// Progress shared by the connections of one download
+struct Transfer {
+    /// Bytes on disk per part
+    done: Vec<AtomicU64>,
+    total: u64,
+    max_filesize: Option<u64>,
+    throttle: Mutex<Throttle>,
+    /// Sent as If-Range, so a replaced file comes back whole
+    validator: Option<String>,
+    /// Set when the server sent a different version of the file
+    replaced: AtomicBool,
+    on_progress: DirectProgress,
+}
+
+/// Bytes received since the rate limit last changed
+struct Throttle {
+    rate: Option<u64>,
+    since: Instant,
+    bytes: u64,
+}
+
+impl Throttle {
+    fn new(rate: Option<u64>) -> Self {
+        Self { rate: rate.filter(|rate| *rate > 0), since: Instant::now(), bytes: 0 }
+    }
+}
+
+impl Transfer {
+    fn downloaded(&self) -> u64 {
+        self.done.iter().map(|bytes| bytes.load(Ordering::SeqCst)).sum()
+    }
+
+    fn report(&self) {
+        (self.on_progress)(self.downloaded(), self.total);
+    }
+
+    /// Limit all connections to `rate` bytes per second from now on
+    fn set_rate_limit(&self, rate: Option<u64>) {
+        *self.throttle.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Throttle::new(rate);
+    }
+
+    /// Count `bytes` written by part `index`, then wait if the rate limit
+    /// is exceeded
+    async fn advance(&self, index: usize, bytes: u64) -> Result<(), AppError> {
+        self.done[index].fetch_add(bytes, Ordering::SeqCst);
+        if let Some(limit) = self.max_filesize {
+            if self.downloaded() > limit {
+                return Err(AppError::DownloadError(format!(
+                    "The file is larger than the {} byte size limit",
+                    limit
+                )));
+            }
+        }
+        self.report();
+        let wait = {
+            let mut throttle = self.throttle.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            throttle.bytes += bytes;
+            throttle.rate.and_then(|rate| {
+                Duration::from_secs_f64(throttle.bytes as f64 / rate as f64).checked_sub(throttle.since.elapsed())
+            })
+        };
+        if let Some(wait) = wait {
+            sleep(wait).await;
+        }
+        Ok(())
+    }
+}
+
+/// Apply bandwidth profile and override changes to `transfer` until it is
+/// dropped
+fn follow_bandwidth(transfer: &Arc<Transfer>, rate_limit: Option<u64>) {
+    let transfer = Arc::downgrade(transfer);
+    tokio::spawn(async move {
+        let mut watch = RateLimitWatch::new(rate_limit);
+        loop {
+            sleep(LIMIT_CHECK_INTERVAL).await;
+            let Some(transfer) = transfer.upgrade() else { break };
+            if let Some(limit) = watch.changed() {
+                debug!("Bandwidth limit changed to {:?} bytes/s", limit);
+                transfer.set_rate_limit(limit);
+            }
+        }
+    });
+}
+
+/// Fetch one part, retrying from where the previous attempt stopped
+async fn fetch_part(
+    client: &Client,
+    url: &str,
+    index: usize,
+    range: Option<Range<u64>>,
+    path: &Path,
+    transfer: &Transfer,
+) -> Result<(), AppError> {
+    let mut attempt = 1;
+    loop {
+        match fetch_part_once(client, url, index, range.clone(), path, transfer).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < SEGMENT_ATTEMPTS && is_retriable(&e) => {
+                warn!("Direct download of {} interrupted (attempt {}/{}): {}", url, attempt, SEGMENT_ATTEMPTS, e);
+                sleep(Duration::from_secs(2 * attempt as u64)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn fetch_part_once(
+    client: &Client,
+    url: &str,
+    index: usize,
+    range: Option<Range<u64>>,
+    path: &Path,
+    transfer: &Transfer,
+) -> Result<(), AppError> {
+    let have = transfer.done[index].load(Ordering::SeqCst);
+    let (start, end) = match &range {
+        Some(range) => (range.start + have, Some(range.end)),
+        None => (have, None),
+    };
+    let complete = match end {
+        Some(end) => start >= end,
+        None => transfer.total > 0 && start >= transfer.total,
+    };
+    if complete {
+        return Ok(());
+    }
+
+    let mut request = client.get(url);
+    let ranged = start > 0 || end.is_some();
+    if ranged {
+        let last = end.map(|end| (end - 1).to_string()).unwrap_or_default();
+        request = request.header(header::RANGE, format!("bytes={}-{}", start, last));
+        if let Some(validator) = &transfer.validator {
+            request = request.header(header::IF_RANGE, validator.as_str());
+        }
+    }
+    let mut response = request.send().await?.error_for_status()?;
+    // Partial data is only kept when the server sent the rest of it
+    let append = match response.status() {
+        StatusCode::PARTIAL_CONTENT => have > 0,
+        // If-Range didn't match: the file was replaced, and its size and
+        // split may have changed with it
+        StatusCode::OK if ranged && transfer.validator.is_some() => {
+            transfer.replaced.store(true, Ordering::SeqCst);
+            return Err(AppError::DownloadError(format!("{} changed on the server", url)));
+        }
+        // The server ignored the range: a whole file starts over, a part can't
+        _ if range.is_none() => false,
+        status => {
+            return Err(AppError::DownloadError(format!(
+                "The server stopped accepting range requests ({})",
+                status
+            )))
+        }
+    };
+    if !append && have > 0 {
+        debug!("{} can't resume, starting over", url);
+        transfer.done[index].store(0, Ordering::SeqCst);
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .await?;
+    write_body(&mut response, &mut file, index, transfer).await?;
+    file.flush().await?;
+
+    let written = transfer.done[index].load(Ordering::SeqCst);
+    let expected = match &range {
+        Some(range) => Some(range.end - range.start),
+        None => (transfer.total > 0).then_some(transfer.total),
+    };
+    match expected {
+        Some(expected) if written < expected => Err(AppError::NetworkError {
+            kind: NetworkErrorKind::ConnectionInterrupted,
+            message: format!("Connection closed after {} of {} bytes", written, expected),
+            retriable: true,
+        }),
+        _ => Ok(()),
+    }
+}
+
+async fn write_body(
+    response: &mut Response,
+    file: &mut fs::File,
+    index: usize,
+    transfer: &Transfer,
+) -> Result<(), AppError> {
+    loop {
+        let chunk = timeout(Duration::from_secs(READ_TIMEOUT_SECS), response.chunk())
+            .await
+            .map_err(|_| AppError::NetworkError {
+                kind: NetworkErrorKind::Timeout,
+                message: format!("No data for {} seconds", READ_TIMEOUT_SECS),
+                retriable: true,
+            })??;
+        let Some(chunk) = chunk else {
+            return Ok(());
+        };
+        file.write_all(&chunk).await?;
+        transfer.advance(index, chunk.len() as u64).await?;
+    }
+}
+
+async fn remove_if_present(path: &Path) -> Result<(), AppError> {
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Append the contents of `part` to `into`
+async fn append_file(into: &Path, part: &Path) -> Result<(), AppError> {
+    let mut target = OpenOptions::new().append(true).open(into).await?;
+    let mut source = fs::File::open(part).await?;
+    tokio::io::copy(&mut source, &mut target).await?;
+    target.flush().await?;
+    Ok(())
+}
+
+fn is_retriable(error: &AppError) -> bool {
+    match error {
+        AppError::HttpError(e) => e.is_timeout() || e.is_connect() || e.is_body() || e.is_request(),
+        AppError::NetworkError { retriable, .. } => *retriable,
+        _ => false,
+    }
+}
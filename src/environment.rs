@@ -0,0 +1,138 @@
+// src/environment.rs
+//! Detection of containers, CI runners and headless sessions. There is
+//! nobody to answer prompts, no notification daemon and usually no color
+//! support in these, so Rustloader turns those off and picks defaults.
+
+use log::debug;
+use once_cell::sync::Lazy;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set to 1 or true to never prompt, e.g. in docker-compose deployments
+pub const NON_INTERACTIVE_ENV: &str = "RUSTLOADER_NON_INTERACTIVE";
+
+/// Variables CI services set on their runners
+const CI_VARIABLES: &[&str] = &[
+    "CI",
+    "GITHUB_ACTIONS",
+    "GITLAB_CI",
+    "BUILDKITE",
+    "CIRCLECI",
+    "JENKINS_URL",
+    "TEAMCITY_VERSION",
+    "TF_BUILD",
+];
+
+static DETECTED: Lazy<RunEnvironment> = Lazy::new(|| {
+    let environment = RunEnvironment::detect();
+    debug!("Detected run environment: {:?}", environment);
+    environment
+});
+
+static FORCE_NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Where Rustloader is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunEnvironment {
+    /// Inside Docker, Podman, Kubernetes or another container runtime
+    pub container: bool,
+    /// On a CI runner
+    pub ci: bool,
+    /// A graphical session is available
+    pub display: bool,
+    /// Stdin is a terminal someone can type into
+    pub terminal: bool,
+}
+
+impl RunEnvironment {
+    /// Inspect the current process
+    pub fn detect() -> Self {
+        let env = |name: &str| std::env::var(name).ok();
+        let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+        let container = Path::new("/.dockerenv").exists()
+            || Path::new("/run/.containerenv").exists()
+            || env("container").is_some()
+            || env("KUBERNETES_SERVICE_HOST").is_some()
+            || is_container_cgroup(&cgroup);
+
+        Self {
+            container,
+            ci: is_ci(env),
+            display: has_display(env),
+            terminal: std::io::stdin().is_terminal(),
+        }
+    }
+
+    /// No graphical session, or one nobody is watching
+    pub fn is_headless(&self) -> bool {
+        self.container || self.ci || !self.display
+    }
+
+    /// Whether desktop notifications can be shown
+    pub fn notifications_available(&self) -> bool {
+        !self.is_headless()
+    }
+
+    /// Whether output should be colored
+    pub fn color_enabled(&self) -> bool {
+        self.terminal && !self.container && !self.ci
+    }
+
+    /// Whether questions can be asked on stdin
+    pub fn interactive(&self) -> bool {
+        self.terminal && !self.container && !self.ci
+    }
+}
+
+/// Whether the variables describe a CI runner. `CI=false` and `CI=0` don't count.
+pub fn is_ci(env: impl Fn(&str) -> Option<String>) -> bool {
+    CI_VARIABLES.iter().any(|name| {
+        env(name).is_some_and(|value| !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false"))
+    })
+}
+
+/// Whether the contents of /proc/1/cgroup belong to a container
+pub fn is_container_cgroup(cgroup: &str) -> bool {
+    ["docker", "kubepods", "containerd", "libpod", "lxc"]
+        .iter()
+        .any(|runtime| cgroup.contains(runtime))
+}
+
+/// Whether a graphical session is available. Only X11 and Wayland sessions
+/// are announced through variables; macOS and Windows always have a desktop.
+pub fn has_display(env: impl Fn(&str) -> Option<String>) -> bool {
+    if cfg!(any(windows, target_os = "macos")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|name| env(name).is_some_and(|value| !value.is_empty()))
+}
+
+/// The environment detected at startup
+pub fn current() -> RunEnvironment {
+    *DETECTED
+}
+
+/// Never prompt for the rest of the run (`--non-interactive`)
+pub fn set_non_interactive() {
+    FORCE_NON_INTERACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Whether the user can be asked questions. False in containers, on CI,
+/// without a terminal, and when non-interactive mode was requested.
+pub fn is_interactive() -> bool {
+    let requested = FORCE_NON_INTERACTIVE.load(Ordering::Relaxed)
+        || std::env::var(NON_INTERACTIVE_ENV)
+            .is_ok_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"));
+    !requested && current().interactive()
+}
+
+/// Turn off color when the environment can't show it. NO_COLOR and
+/// CLICOLOR_FORCE are honoured by the colored crate itself.
+pub fn apply_output_defaults() {
+    if !current().color_enabled() && std::env::var_os("CLICOLOR_FORCE").is_none() {
+        colored::control::set_override(false);
+    }
+}
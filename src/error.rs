@@ -1,6 +1,7 @@
 // src/error.rs
 
 use reqwest::Error as ReqwestError;
+use serde::{Deserialize, Serialize};
 use serde_json::Error as SerdeError;
 use std::fmt;
 use std::io;
@@ -69,6 +70,26 @@ pub enum AppError {
     #[error("Path error: {0}")]
     PathError(String),
 
+    /// An input value that breaks a rule, with a corrected value to use
+    /// instead when one can be worked out
+    #[error("Invalid {what} {}: {rule}{}", shown(.value), hint(.suggestion))]
+    InvalidInput {
+        /// What the value is, e.g. "file name"
+        what: String,
+        value: String,
+        rule: String,
+        suggestion: Option<String>,
+    },
+
+    /// A path that breaks a rule, with a corrected path to use instead when
+    /// one can be worked out
+    #[error("Invalid path {}: {rule}{}", shown(.path), hint(.suggestion))]
+    InvalidPath {
+        path: String,
+        rule: String,
+        suggestion: Option<String>,
+    },
+
     /// General application errors
     #[error("Application error: {0}")]
     General(String),
@@ -79,7 +100,6 @@ pub enum AppError {
 
     /// Error for when a feature requires the Pro version
     #[error("Premium feature: {0}")]
-    #[allow(dead_code)]
     PremiumFeature(String),
 
     /// Error for security violations (tampering, path traversal, etc.)
@@ -94,10 +114,22 @@ pub enum AppError {
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] SerdeError),
 
+    /// Download history database errors
+    #[error("History database error: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+
     /// License errors
     #[error("License error: {0}")]
     LicenseError(String),
 
+    /// Another Rustloader process owns the download queue
+    #[error("The download queue is in use by {0}. Close it or run this command there.")]
+    InstanceLocked(String),
+
+    /// A download ran longer than its wall-clock deadline
+    #[error("Deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
     /// Parse errors
     #[allow(dead_code)]
     #[error("Parse error: {0}")]
@@ -112,6 +144,168 @@ pub enum AppError {
     },
 }
 
+impl AppError {
+    /// The corrected value an error suggests, if any
+    pub fn suggestion(&self) -> Option<&str> {
+        match self {
+            AppError::InvalidInput { suggestion, .. } | AppError::InvalidPath { suggestion, .. } => {
+                suggestion.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Stable identifier of the kind of error, e.g. "missing_dependency"
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::MissingDependency(_) => "missing_dependency",
+            AppError::DownloadError(_) => "download_failed",
+            AppError::ValidationError(_) => "validation_failed",
+            AppError::IoError(_) => "io_error",
+            AppError::TimeFormatError(_) => "invalid_time",
+            AppError::PathError(_) => "path_error",
+            AppError::InvalidInput { .. } => "invalid_input",
+            AppError::InvalidPath { .. } => "invalid_path",
+            AppError::General(_) => "general",
+            AppError::DailyLimitExceeded => "daily_limit_exceeded",
+            AppError::PremiumFeature(_) => "premium_feature",
+            AppError::SecurityViolation => "security_violation",
+            AppError::HttpError(_) => "http_error",
+            AppError::JsonError(_) => "json_error",
+            AppError::DatabaseError(_) => "database_error",
+            AppError::LicenseError(_) => "license_error",
+            AppError::InstanceLocked(_) => "instance_locked",
+            AppError::DeadlineExceeded(_) => "deadline_exceeded",
+            AppError::ParseError(_) => "parse_error",
+            AppError::NetworkError { kind, .. } => match kind {
+                NetworkErrorKind::ConnectionInterrupted => "network_interrupted",
+                NetworkErrorKind::Timeout => "network_timeout",
+                NetworkErrorKind::ServerError(_) => "network_server_error",
+                NetworkErrorKind::DnsResolutionFailure => "network_dns_failure",
+                NetworkErrorKind::ConnectivityIssue => "network_offline",
+                NetworkErrorKind::RateLimited => "network_rate_limited",
+                NetworkErrorKind::ContentUnavailable => "content_unavailable",
+                NetworkErrorKind::Other => "network_error",
+            },
+        }
+    }
+
+    /// What kind of problem the error is
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            AppError::NetworkError { .. } | AppError::HttpError(_) => ErrorCategory::Network,
+            AppError::DownloadError(_) | AppError::DeadlineExceeded(_) => ErrorCategory::Download,
+            AppError::MissingDependency(_) => ErrorCategory::Dependency,
+            AppError::ValidationError(_)
+            | AppError::TimeFormatError(_)
+            | AppError::InvalidInput { .. }
+            | AppError::ParseError(_) => ErrorCategory::Input,
+            AppError::PathError(_) | AppError::InvalidPath { .. } => ErrorCategory::Settings,
+            AppError::IoError(_) | AppError::DatabaseError(_) => ErrorCategory::Storage,
+            AppError::DailyLimitExceeded | AppError::PremiumFeature(_) | AppError::LicenseError(_) => {
+                ErrorCategory::License
+            }
+            AppError::General(_)
+            | AppError::SecurityViolation
+            | AppError::JsonError(_)
+            | AppError::InstanceLocked(_) => ErrorCategory::Internal,
+        }
+    }
+
+    /// Whether trying the same thing again may work
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            AppError::NetworkError { retriable, .. } => *retriable,
+            AppError::HttpError(e) => e.status().map_or(true, |status| {
+                status.is_server_error() || status.as_u16() == 408 || status.as_u16() == 429
+            }),
+            AppError::DownloadError(_) | AppError::DeadlineExceeded(_) | AppError::InstanceLocked(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// What kind of problem an error is. The GUI picks the action an error
+/// toast offers from it: network and download errors offer "Retry" when
+/// retriable, dependency errors "Install dependency" and settings errors
+/// "Open settings".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The connection or the server failed
+    Network,
+    /// yt-dlp or the download itself failed
+    Download,
+    /// yt-dlp or ffmpeg is missing or too old
+    Dependency,
+    /// A value the user entered is invalid
+    Input,
+    /// A configured path or option is unusable
+    Settings,
+    /// Reading or writing local files failed
+    Storage,
+    /// Needs Rustloader Pro or a valid license
+    License,
+    /// Anything else
+    Internal,
+}
+
+/// An error in the form the GUI receives it, serialized as
+/// `{code, category, message, item_id, retriable}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    /// Stable identifier from [`AppError::code`]
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+    /// The download the error belongs to, if any
+    #[serde(default)]
+    pub item_id: Option<String>,
+    pub retriable: bool,
+}
+
+impl ErrorReport {
+    /// The report for `error`, not tied to a download
+    pub fn new(error: &AppError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            category: error.category(),
+            message: error.to_string(),
+            item_id: None,
+            retriable: error.is_retriable(),
+        }
+    }
+
+    /// Tie the report to the download `item_id`
+    pub fn for_item(mut self, item_id: &str) -> Self {
+        self.item_id = Some(item_id.to_string());
+        self
+    }
+}
+
+/// Longest offending value shown in a message
+const MAX_SHOWN_CHARS: usize = 80;
+
+/// Quote an offending value for a message: control characters are escaped
+/// and long values shortened, so the message stays on one line and can be
+/// pasted into a bug report or email as is
+fn shown(value: &str) -> String {
+    let escaped: String = value.chars().flat_map(char::escape_debug).collect();
+    if escaped.chars().count() > MAX_SHOWN_CHARS {
+        let short: String = escaped.chars().take(MAX_SHOWN_CHARS).collect();
+        format!("\"{}…\"", short)
+    } else {
+        format!("\"{}\"", escaped)
+    }
+}
+
+fn hint(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(" (try {})", shown(suggestion)),
+        None => String::new(),
+    }
+}
+
 /// Convert a string error to AppError::General
 impl From<String> for AppError {
     fn from(error: String) -> Self {
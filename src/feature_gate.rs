@@ -0,0 +1,125 @@
+// src/feature_gate.rs
+//! Free-version feature gating. The options only Rustloader Pro offers are
+//! checked together right after the command line is parsed, so a free user
+//! who asks for several of them is told about all of them in one message.
+//! Templates, saved defaults and an interactively picked format can ask for
+//! them too, so the fully resolved options are checked again just before a
+//! download is started or queued.
+
+use crate::cli::PRO_QUALITIES;
+use crate::error::AppError;
+use crate::utils::{parse_bitrate, Bitrate};
+use clap::ArgMatches;
+use std::fmt;
+
+/// Highest MP3 bitrate of the free version, in kbps
+pub const FREE_MAX_MP3_KBPS: f64 = 128.0;
+
+/// Tallest video the free version downloads, in pixels
+pub const FREE_MAX_HEIGHT: u64 = 1080;
+
+/// An option on the command line that needs Rustloader Pro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatedOption {
+    /// The option and its value, e.g. "--quality 2160"
+    pub option: String,
+    /// What it asks for, e.g. "4K video"
+    pub feature: &'static str,
+}
+
+impl fmt::Display for GatedOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.option, self.feature)
+    }
+}
+
+/// The download options that decide whether Pro is needed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestedFeatures {
+    pub format: Option<String>,
+    pub quality: Option<String>,
+    pub bitrate: Option<String>,
+    pub transcode: Option<String>,
+    /// Height of a format picked with `--interactive`
+    pub format_height: Option<u64>,
+}
+
+impl RequestedFeatures {
+    /// Read the options from the `download` subcommand or the top-level
+    /// arguments, whichever was used
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let download_matches = matches.subcommand_matches("download");
+        let value = |id: &str| {
+            download_matches
+                .and_then(|m| m.try_get_one::<String>(id).ok().flatten())
+                .or_else(|| matches.try_get_one::<String>(id).ok().flatten())
+                .cloned()
+        };
+        Self {
+            format: value("format"),
+            quality: value("quality"),
+            bitrate: value("video-bitrate"),
+            transcode: value("transcode"),
+            format_height: None,
+        }
+    }
+}
+
+/// The requested options the free version doesn't offer, in command-line
+/// order. Values that don't parse are left to the usual validation.
+pub fn gated_options(requested: &RequestedFeatures) -> Vec<GatedOption> {
+    let mut gated = Vec::new();
+
+    if let Some(quality) = requested.quality.as_deref().filter(|quality| PRO_QUALITIES.contains(quality)) {
+        gated.push(GatedOption {
+            option: format!("--quality {}", quality),
+            feature: if quality == "2160" { "4K video" } else { "1440p video" },
+        });
+    }
+
+    if let Some(height) = requested.format_height.filter(|height| *height > FREE_MAX_HEIGHT) {
+        gated.push(GatedOption {
+            option: format!("--interactive {}p format", height),
+            feature: if height >= 2160 { "4K video" } else { "1440p video" },
+        });
+    }
+
+    if requested.format.as_deref() == Some("mp3") {
+        if let Some(value) = &requested.bitrate {
+            let above_free = match parse_bitrate(value, "mp3") {
+                Ok(Bitrate::Best) => true,
+                Ok(Bitrate::Kbps(kbps)) => kbps > FREE_MAX_MP3_KBPS,
+                Err(_) => false,
+            };
+            if above_free {
+                gated.push(GatedOption {
+                    option: format!("--bitrate {}", value),
+                    feature: "MP3 above 128K",
+                });
+            }
+        }
+    }
+
+    if requested.transcode.as_deref() == Some("upscale-2160") {
+        gated.push(GatedOption {
+            option: "--transcode upscale-2160".to_string(),
+            feature: "4K upscaling",
+        });
+    }
+
+    gated
+}
+
+/// Fail with one error listing every gated option, unless running as Pro
+pub fn check_free_features(requested: &RequestedFeatures, is_pro: bool) -> Result<(), AppError> {
+    if is_pro {
+        return Ok(());
+    }
+    let gated = gated_options(requested);
+    if gated.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::PremiumFeature(
+        gated.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "),
+    ))
+}
@@ -0,0 +1,225 @@
+// src/ffmpeg_args.rs
+//! Validation of user-supplied ffmpeg arguments for the post-processing stage.
+//!
+//! Only a fixed set of encoding and filter options is accepted, each with a
+//! single value, so the arguments can't add inputs or outputs. Filtergraphs
+//! may only use filters from an allowlist of ones that take no file or
+//! library path. Options Rustloader already generates for the download
+//! (bitrate, time range) can't be overridden.
+
+use crate::error::AppError;
+
+/// ffmpeg options accepted in `--ffmpeg-args`; each takes one value
+const ALLOWED_FLAGS: &[&str] = &[
+    "-ac",
+    "-af",
+    "-ar",
+    "-aspect",
+    "-b:a",
+    "-b:v",
+    "-bufsize",
+    "-crf",
+    "-filter:a",
+    "-filter:v",
+    "-g",
+    "-level",
+    "-maxrate",
+    "-metadata",
+    "-movflags",
+    "-pix_fmt",
+    "-preset",
+    "-profile:v",
+    "-r",
+    "-ss",
+    "-t",
+    "-to",
+    "-tune",
+    "-vf",
+];
+
+/// Options that select a time range, which `--start-time`/`--end-time` also set
+pub const TIME_RANGE_FLAGS: &[&str] = &["-ss", "-t", "-to"];
+
+/// Options whose value is a filtergraph
+const FILTER_FLAGS: &[&str] = &["-af", "-filter:a", "-filter:v", "-vf"];
+
+/// Filters accepted in a filtergraph. None of them read files or load
+/// libraries, unlike e.g. `movie`, `subtitles`, `lut3d`, `drawtext` or `ladspa`.
+const ALLOWED_FILTERS: &[&str] = &[
+    "acompressor",
+    "adelay",
+    "aecho",
+    "afade",
+    "aformat",
+    "anull",
+    "apad",
+    "aresample",
+    "areverse",
+    "asetpts",
+    "atempo",
+    "atrim",
+    "bandpass",
+    "bass",
+    "bwdif",
+    "chromakey",
+    "colorchannelmixer",
+    "crop",
+    "dynaudnorm",
+    "eq",
+    "equalizer",
+    "fade",
+    "format",
+    "fps",
+    "gblur",
+    "highpass",
+    "hflip",
+    "hqdn3d",
+    "hue",
+    "loudnorm",
+    "lowpass",
+    "negate",
+    "null",
+    "pad",
+    "pan",
+    "reverse",
+    "rotate",
+    "scale",
+    "setdar",
+    "setpts",
+    "setsar",
+    "silenceremove",
+    "transpose",
+    "treble",
+    "trim",
+    "unsharp",
+    "vflip",
+    "volume",
+    "yadif",
+];
+
+/// Split an argument string like a shell would, honouring single and double
+/// quotes so filter expressions can contain spaces
+pub fn split_args(input: &str) -> Result<Vec<String>, AppError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote = None;
+
+    for c in input.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_token = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_token {
+                    args.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(AppError::ValidationError("Unterminated quote in --ffmpeg-args".to_string()));
+    }
+    if in_token {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Parse and validate `--ffmpeg-args`
+pub fn parse_ffmpeg_args(input: &str) -> Result<Vec<String>, AppError> {
+    let args = split_args(input)?;
+    if args.len() % 2 != 0 {
+        return Err(AppError::ValidationError(
+            "--ffmpeg-args must be option/value pairs, e.g. \"-vf hflip\"".to_string(),
+        ));
+    }
+
+    for pair in args.chunks(2) {
+        let (flag, value) = (&pair[0], &pair[1]);
+        if !ALLOWED_FLAGS.contains(&flag.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "ffmpeg option {} is not allowed in --ffmpeg-args",
+                flag
+            )));
+        }
+        if value.is_empty() || value.contains('\0') || (value.starts_with('-') && value.parse::<f64>().is_err()) {
+            return Err(AppError::ValidationError(format!("Invalid value for {}: {:?}", flag, value)));
+        }
+        if FILTER_FLAGS.contains(&flag.as_str()) {
+            check_filtergraph(value)?;
+        }
+    }
+
+    Ok(args)
+}
+
+/// Check every filter in a filtergraph is on the allowlist. Quoted or escaped
+/// separators are split too, which can only reject more, never less.
+fn check_filtergraph(graph: &str) -> Result<(), AppError> {
+    for filter in graph.split([',', ';']) {
+        let name = strip_labels(filter).split('=').next().unwrap_or_default().trim();
+        if !ALLOWED_FILTERS.contains(&name) {
+            return Err(AppError::ValidationError(format!(
+                "The {:?} filter is not allowed in --ffmpeg-args",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A filter without its input and output pad labels, e.g. `[in]scale=640:-2[out]`
+fn strip_labels(filter: &str) -> &str {
+    let mut filter = filter.trim();
+    while let (Some(rest), Some(end)) = (filter.strip_prefix('['), filter.find(']')) {
+        filter = rest[end..].trim_start();
+    }
+    while let (true, Some(start)) = (filter.ends_with(']'), filter.rfind('[')) {
+        filter = filter[..start].trim_end();
+    }
+    filter
+}
+
+/// Check that custom arguments don't override options generated for the
+/// download. `generated` holds the generated option/value pairs.
+pub fn check_conflicts(custom: &[String], generated: &[String], has_time_range: bool) -> Result<(), AppError> {
+    for flag in custom.iter().step_by(2) {
+        if has_time_range && TIME_RANGE_FLAGS.contains(&flag.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "--ffmpeg-args can't use {} together with --start-time/--end-time",
+                flag
+            )));
+        }
+        if generated.iter().step_by(2).any(|generated_flag| generated_flag == flag) {
+            return Err(AppError::ValidationError(format!(
+                "--ffmpeg-args can't override {}, which is set for this download",
+                flag
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Join arguments into one string that yt-dlp splits back into the same list
+pub fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '\'' || c == '"' || c == '\\') {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r#"'"'"'"#))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,155 @@
+// src/ffmpeg_features.rs
+//! Minimum ffmpeg versions per feature. Each feature is checked when a
+//! download asks for it, so an old ffmpeg only blocks the features it can't
+//! handle instead of every download.
+
+use crate::dependency_validator::is_minimum_version;
+use crate::error::AppError;
+use crate::format_selector::VideoCodec;
+use crate::process::CommandSpec;
+use crate::transcode::TranscodeKind;
+use log::debug;
+use once_cell::sync::Lazy;
+
+/// Version of the ffmpeg on PATH, probed once; None when it isn't installed
+/// or doesn't report a release number (e.g. git snapshots)
+static FFMPEG_VERSION: Lazy<Option<String>> = Lazy::new(|| {
+    let output = CommandSpec::version_probe("ffmpeg", "-version")
+        .to_command()
+        .and_then(|mut probe| probe.output().map_err(AppError::IoError))
+        .ok()?;
+    let version = parse_ffmpeg_version(&String::from_utf8_lossy(&output.stdout));
+    debug!("Detected ffmpeg version {:?}", version);
+    version
+});
+
+/// Something a download can ask ffmpeg to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfmpegFeature {
+    /// Converting to mp3
+    AudioExtraction,
+    /// Cutting with --start-time/--end-time
+    TimeRange,
+    /// Re-encoding to HEVC
+    HevcTranscode,
+    /// Upscaling with the lanczos scaler
+    Upscale,
+    /// Decoding AV1 video for re-encoding
+    Av1Decode,
+    /// EBU R128 loudness normalization
+    Loudnorm,
+}
+
+impl FfmpegFeature {
+    /// Oldest ffmpeg release that handles this feature reliably
+    pub fn min_version(&self) -> &'static str {
+        match self {
+            FfmpegFeature::AudioExtraction
+            | FfmpegFeature::TimeRange
+            | FfmpegFeature::HevcTranscode
+            | FfmpegFeature::Upscale => "4.0.0",
+            FfmpegFeature::Loudnorm => "4.3.0",
+            FfmpegFeature::Av1Decode => "5.0.0",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            FfmpegFeature::AudioExtraction => "converting to mp3",
+            FfmpegFeature::TimeRange => "cutting a time range",
+            FfmpegFeature::HevcTranscode => "re-encoding to HEVC",
+            FfmpegFeature::Upscale => "upscaling",
+            FfmpegFeature::Av1Decode => "decoding AV1 video",
+            FfmpegFeature::Loudnorm => "loudness normalization (loudnorm)",
+        }
+    }
+}
+
+/// Every feature, for reporting what an installed ffmpeg can do
+pub const ALL_FEATURES: &[FfmpegFeature] = &[
+    FfmpegFeature::AudioExtraction,
+    FfmpegFeature::TimeRange,
+    FfmpegFeature::HevcTranscode,
+    FfmpegFeature::Upscale,
+    FfmpegFeature::Av1Decode,
+    FfmpegFeature::Loudnorm,
+];
+
+/// Features of ffmpeg a download with these options will use
+pub fn required_features(
+    format: &str,
+    has_time_range: bool,
+    transcode: Option<TranscodeKind>,
+    video_codec: Option<VideoCodec>,
+    ffmpeg_args: &[String],
+) -> Vec<FfmpegFeature> {
+    let mut features = Vec::new();
+    if format == "mp3" {
+        features.push(FfmpegFeature::AudioExtraction);
+    }
+    if has_time_range {
+        features.push(FfmpegFeature::TimeRange);
+    }
+    match transcode {
+        Some(TranscodeKind::Hevc) => features.push(FfmpegFeature::HevcTranscode),
+        Some(TranscodeKind::Upscale1080 | TranscodeKind::Upscale2160) => features.push(FfmpegFeature::Upscale),
+        None => {}
+    }
+    // Merging streams copies them; only re-encoding decodes the AV1 source
+    if video_codec == Some(VideoCodec::Av1) && transcode.is_some() {
+        features.push(FfmpegFeature::Av1Decode);
+    }
+    if ffmpeg_args.iter().any(|arg| arg.to_lowercase().contains("loudnorm")) {
+        features.push(FfmpegFeature::Loudnorm);
+    }
+    features
+}
+
+/// Release number from `ffmpeg -version` output, e.g. "6.1.1" from
+/// "ffmpeg version n6.1.1 Copyright ..."
+pub fn parse_ffmpeg_version(output: &str) -> Option<String> {
+    let first_line = output.lines().next()?;
+    let version = first_line.strip_prefix("ffmpeg version ")?.split_whitespace().next()?;
+    let version = version.trim_start_matches('n');
+    let release: String = version
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let release = release.trim_end_matches('.');
+    (release.contains('.') && release.split('.').all(|part| !part.is_empty())).then(|| release.to_string())
+}
+
+/// Features `version` is too old for
+pub fn unsupported_features(version: &str, features: &[FfmpegFeature]) -> Vec<FfmpegFeature> {
+    features
+        .iter()
+        .copied()
+        .filter(|feature| !is_minimum_version(version, feature.min_version()))
+        .collect()
+}
+
+/// Check that ffmpeg `version` supports every feature, naming the first
+/// one it doesn't. An unknown version passes; a missing ffmpeg is reported
+/// elsewhere.
+pub fn check_feature_support(version: Option<&str>, features: &[FfmpegFeature]) -> Result<(), AppError> {
+    let Some(version) = version else {
+        return Ok(());
+    };
+    match unsupported_features(version, features).first() {
+        Some(feature) => Err(AppError::MissingDependency(format!(
+            "ffmpeg {} or newer is needed for {} (found {})",
+            feature.min_version(),
+            feature.description(),
+            version
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Check the installed ffmpeg supports every feature a download needs
+pub fn check_ffmpeg_features(features: &[FfmpegFeature]) -> Result<(), AppError> {
+    if features.is_empty() {
+        return Ok(());
+    }
+    check_feature_support(FFMPEG_VERSION.as_deref(), features)
+}
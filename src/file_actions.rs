@@ -0,0 +1,132 @@
+// src/file_actions.rs
+
+use crate::cleanup::normalize_name;
+use crate::download_manager::{find_saved_download, DownloadItem};
+use crate::error::AppError;
+use crate::process::CommandSpec;
+use crate::security::validate_existing_path;
+use log::debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::SystemTime;
+
+/// Find the file a completed download produced. The stored output path is
+/// usually the yt-dlp template (`<dir>/%(title)s.mp4`), so when it doesn't
+/// name a real file, look in its directory for a file matching the title.
+#[allow(dead_code)]
+pub fn locate_output_file(item: &DownloadItem) -> Result<PathBuf, AppError> {
+    let output_path = item.output_path.as_deref().ok_or_else(|| {
+        AppError::ValidationError(format!("Download {} has no output file yet", item.id))
+    })?;
+
+    let path = PathBuf::from(output_path);
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    let dir = path
+        .parent()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| AppError::PathError(format!("Output folder not found: {}", output_path)))?;
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or(&item.format);
+    let title = item.title.as_deref().map(normalize_name).unwrap_or_default();
+
+    if !title.is_empty() {
+        for entry in fs::read_dir(dir)?.flatten() {
+            let candidate = entry.path();
+            let matches_title = candidate
+                .file_stem()
+                .map(|stem| normalize_name(&stem.to_string_lossy()) == title)
+                .unwrap_or(false);
+            let matches_ext = candidate.extension().is_some_and(|ext| ext == extension);
+            if candidate.is_file() && matches_title && matches_ext {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    Err(AppError::PathError(format!(
+        "Downloaded file for {} not found in {}",
+        item.id,
+        dir.display()
+    )))
+}
+
+/// Files with `extension` in `dir` written since `since`, such as the
+/// videos of a playlist download
+pub fn files_since(dir: &Path, extension: &str, since: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == extension))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            (metadata.is_file() && modified >= since).then(|| (modified, entry.path()))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// The most recently modified file in `dir` with the given extension that
+/// was written at or after `since`, i.e. the file a download just produced
+pub fn newest_file_since(dir: &Path, extension: &str, since: SystemTime) -> Option<PathBuf> {
+    files_since(dir, extension, since).pop()
+}
+
+/// Resolve a download ID (or unique prefix) from the queue or saved state to
+/// its output file, validated as safe to open
+#[allow(dead_code)]
+pub fn resolve_download_file(id: &str) -> Result<PathBuf, AppError> {
+    let item = find_saved_download(id)?
+        .ok_or_else(|| AppError::ValidationError(format!("No download found with ID '{}'", id)))?;
+    validate_existing_path(&locate_output_file(&item)?)
+}
+
+/// Show a file selected in the platform file manager
+#[allow(dead_code)]
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    let path = validate_existing_path(path)?;
+    debug!("Revealing {} in file manager", path.display());
+
+    let mut spec = CommandSpec::opener();
+
+    #[cfg(target_os = "macos")]
+    spec.flag("-R").operand(path.to_string_lossy());
+
+    #[cfg(target_os = "windows")]
+    spec.operand(format!("/select,{}", path.display()));
+
+    // Most Linux file managers can't select a file, so open its folder
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    spec.operand(path.parent().unwrap_or(&path).to_string_lossy());
+
+    launch(&spec)
+}
+
+/// Open a file with its default application
+#[allow(dead_code)]
+pub fn open_file(path: &Path) -> Result<(), AppError> {
+    let path = validate_existing_path(path)?;
+    if !path.is_file() {
+        return Err(AppError::PathError(format!("Not a file: {}", path.display())));
+    }
+    debug!("Opening {}", path.display());
+
+    launch(CommandSpec::opener().operand(path.to_string_lossy()))
+}
+
+/// Start a helper program without waiting for it to exit
+fn launch(spec: &CommandSpec) -> Result<(), AppError> {
+    spec.to_command()?
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(AppError::IoError)
+}
@@ -0,0 +1,216 @@
+// src/format_picker.rs
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::process::CommandSpec;
+use crate::utils::Bitrate;
+use colored::*;
+use dialoguer::{theme::ColorfulTheme, Select};
+use humansize::{format_size, BINARY};
+use serde_json::Value;
+
+/// A downloadable format as reported by `yt-dlp --dump-json`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatInfo {
+    pub format_id: String,
+    pub ext: String,
+    pub resolution: String,
+    pub height: Option<u64>,
+    pub width: Option<u64>,
+    pub fps: Option<f64>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub filesize: Option<u64>,
+    pub tbr: Option<f64>,
+    /// Bitrate of the video stream alone, in kbps
+    pub vbr: Option<f64>,
+}
+
+impl FormatInfo {
+    /// Whether the format contains a video stream
+    pub fn has_video(&self) -> bool {
+        self.vcodec.is_some()
+    }
+
+    /// Whether the format contains an audio stream
+    pub fn has_audio(&self) -> bool {
+        self.acodec.is_some()
+    }
+
+    /// yt-dlp format selector for this format. Video-only formats are merged
+    /// with the best audio so the result isn't silent.
+    pub fn selector(&self) -> String {
+        if self.has_video() && !self.has_audio() {
+            format!("{id}+bestaudio/{id}", id = self.format_id)
+        } else {
+            self.format_id.clone()
+        }
+    }
+
+    /// One-line description for the selection menu
+    pub fn label(&self) -> String {
+        let codecs = match (&self.vcodec, &self.acodec) {
+            (Some(v), Some(a)) => format!("{} + {}", short_codec(v), short_codec(a)),
+            (Some(v), None) => format!("{} (+ best audio)", short_codec(v)),
+            (None, Some(a)) => short_codec(a).to_string(),
+            (None, None) => "unknown".to_string(),
+        };
+        let size = self
+            .filesize
+            .map(|s| format_size(s, BINARY))
+            .unwrap_or_else(|| "?".to_string());
+
+        format!(
+            "{:<8} {:<11} {:<5} {:<24} {:>10}",
+            self.format_id, self.resolution, self.ext, codecs, size
+        )
+    }
+}
+
+/// Codec name without profile details (e.g. "avc1.64001F" -> "avc1")
+pub fn short_codec(codec: &str) -> &str {
+    codec.split('.').next().unwrap_or(codec)
+}
+
+fn codec_field(format: &Value, key: &str) -> Option<String> {
+    format[key]
+        .as_str()
+        .filter(|c| !c.is_empty() && *c != "none")
+        .map(|c| c.to_string())
+}
+
+/// Extract the selectable formats from yt-dlp metadata, best first.
+/// Audio downloads only list audio-only formats; video downloads list
+/// every format with a video stream.
+pub fn parse_formats(info: &Value, audio_only: bool) -> Vec<FormatInfo> {
+    let mut formats: Vec<FormatInfo> = info["formats"]
+        .as_array()
+        .map(|formats| formats.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|format| {
+            let format_id = format["format_id"].as_str()?.to_string();
+            let vcodec = codec_field(format, "vcodec");
+            let acodec = codec_field(format, "acodec");
+
+            // Skip storyboards and other non-media entries
+            if vcodec.is_none() && acodec.is_none() {
+                return None;
+            }
+
+            let height = format["height"].as_u64();
+            let resolution = match height {
+                Some(h) if vcodec.is_some() => format!("{}p", h),
+                _ if vcodec.is_none() => "audio only".to_string(),
+                _ => format["resolution"].as_str().unwrap_or("?").to_string(),
+            };
+
+            Some(FormatInfo {
+                format_id,
+                ext: format["ext"].as_str().unwrap_or("?").to_string(),
+                resolution,
+                height,
+                width: format["width"].as_u64(),
+                fps: format["fps"].as_f64(),
+                vcodec,
+                acodec,
+                filesize: crate::downloader::estimate_size_from_metadata(format),
+                tbr: format["tbr"].as_f64(),
+                vbr: format["vbr"].as_f64(),
+            })
+        })
+        .filter(|format| if audio_only { !format.has_video() } else { format.has_video() })
+        .collect();
+
+    formats.sort_by(|a, b| {
+        b.height
+            .cmp(&a.height)
+            .then(b.tbr.partial_cmp(&a.tbr).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    formats
+}
+
+/// Check a requested bitrate against what the source offers. Re-encoding
+/// above the best available stream only inflates the file, so that is refused.
+/// Formats without a reported bitrate can't be judged and are ignored.
+pub fn check_bitrate_available(bitrate: Bitrate, formats: &[FormatInfo], audio_only: bool) -> Result<(), AppError> {
+    let Bitrate::Kbps(requested) = bitrate else {
+        return Ok(());
+    };
+    let best = formats
+        .iter()
+        .filter(|format| format.tbr.is_some())
+        .max_by(|a, b| a.tbr.partial_cmp(&b.tbr).unwrap_or(std::cmp::Ordering::Equal));
+    let Some(best) = best else {
+        return Ok(());
+    };
+
+    let available = best.tbr.unwrap_or_default().round();
+    if requested > available {
+        return Err(AppError::ValidationError(format!(
+            "Bitrate {} is higher than the best available {} stream ({}, {}, format {}). Use 'best' or {} or lower.",
+            bitrate,
+            if audio_only { "audio" } else { "video" },
+            Bitrate::Kbps(available),
+            best.resolution,
+            best.format_id,
+            Bitrate::Kbps(available)
+        )));
+    }
+    Ok(())
+}
+
+/// Probe the formats available for a URL
+pub async fn probe_formats(
+    url: &str,
+    audio_only: bool,
+    request_headers: &RequestHeaders,
+) -> Result<Vec<FormatInfo>, AppError> {
+    let output = CommandSpec::ytdlp()
+        .flag("--dump-json")
+        .flag("--no-playlist")
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options())
+        .operand(url)
+        .to_async_command()?
+        .output()
+        .await
+        .map_err(AppError::IoError)?;
+
+    if !output.status.success() {
+        return Err(AppError::DownloadError("Failed to list available formats".to_string()));
+    }
+
+    let info: Value = serde_json::from_slice(&output.stdout).map_err(AppError::JsonError)?;
+    Ok(parse_formats(&info, audio_only))
+}
+
+/// List the available formats and let the user pick one.
+/// Returns the picked format, or `None` if the user cancelled.
+pub async fn pick_format(
+    url: &str,
+    audio_only: bool,
+    request_headers: &RequestHeaders,
+) -> Result<Option<FormatInfo>, AppError> {
+    println!("{}", "Fetching available formats...".blue());
+    let mut formats = probe_formats(url, audio_only, request_headers).await?;
+
+    if formats.is_empty() {
+        return Err(AppError::DownloadError("No downloadable formats found".to_string()));
+    }
+
+    let items: Vec<String> = formats.iter().map(FormatInfo::label).collect();
+    println!(
+        "  {:<8} {:<11} {:<5} {:<24} {:>10}",
+        "ID", "Resolution", "Ext", "Codecs", "Size"
+    );
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select a format (Esc to cancel)")
+        .items(&items)
+        .default(0)
+        .interact_opt()
+        .map_err(|e| AppError::General(format!("Format selection failed: {}", e)))?;
+
+    Ok(selection.map(|index| formats.swap_remove(index)))
+}
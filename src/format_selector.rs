@@ -0,0 +1,165 @@
+// src/format_selector.rs
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+/// Audio quality preference for video downloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioQuality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl AudioQuality {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "low" => Ok(AudioQuality::Low),
+            "medium" => Ok(AudioQuality::Medium),
+            "high" => Ok(AudioQuality::High),
+            _ => Err(AppError::ValidationError(format!("Unknown audio quality: {}", value))),
+        }
+    }
+
+    /// yt-dlp filter limiting the audio bitrate
+    fn filter(&self) -> &'static str {
+        match self {
+            AudioQuality::Low => "[abr<=64]",
+            AudioQuality::Medium => "[abr<=128]",
+            AudioQuality::High => "",
+        }
+    }
+}
+
+/// Preferred video codec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    Avc1,
+    Vp9,
+    Av1,
+}
+
+impl VideoCodec {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "avc1" | "h264" => Ok(VideoCodec::Avc1),
+            "vp9" => Ok(VideoCodec::Vp9),
+            "av1" => Ok(VideoCodec::Av1),
+            _ => Err(AppError::ValidationError(format!("Unknown video codec: {}", value))),
+        }
+    }
+
+    /// yt-dlp filter matching this codec (YouTube reports VP9 as both "vp9" and "vp09")
+    fn filter(&self) -> &'static str {
+        match self {
+            VideoCodec::Avc1 => "[vcodec^=avc1]",
+            VideoCodec::Vp9 => "[vcodec~='^vp0?9']",
+            VideoCodec::Av1 => "[vcodec^=av01]",
+        }
+    }
+}
+
+/// Audio and codec preferences carried alongside the video quality
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FormatPreferences {
+    pub audio_quality: Option<AudioQuality>,
+    pub video_codec: Option<VideoCodec>,
+}
+
+/// Builds yt-dlp format expressions from independent video height, audio
+/// quality and codec preferences. Each preference degrades gracefully:
+/// the expression falls back to any codec, then to unrestricted audio,
+/// then to the best single-file format.
+#[derive(Debug, Clone, Default)]
+pub struct FormatSelector {
+    max_height: Option<u32>,
+    audio_quality: AudioQuality,
+    video_codec: Option<VideoCodec>,
+    vertical: bool,
+}
+
+impl FormatSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit video height using a quality value such as "1080"
+    pub fn with_quality(mut self, quality: Option<&str>) -> Self {
+        self.max_height = quality.and_then(|q| q.trim_end_matches('p').parse().ok());
+        self
+    }
+
+    /// For vertical sources the quality limits the width instead, so "1080"
+    /// selects 1080x1920 rather than 608x1080
+    pub fn with_vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    pub fn with_preferences(mut self, preferences: FormatPreferences) -> Self {
+        if let Some(audio_quality) = preferences.audio_quality {
+            self.audio_quality = audio_quality;
+        }
+        self.video_codec = preferences.video_codec;
+        self
+    }
+
+    /// Whether any preference was set; otherwise yt-dlp's default selection applies
+    pub fn is_default(&self) -> bool {
+        self.max_height.is_none() && self.audio_quality == AudioQuality::High && self.video_codec.is_none()
+    }
+
+    /// Video and audio alternatives, most preferred first
+    fn streams(&self) -> (Vec<String>, Vec<String>) {
+        let height = self.size_filter();
+
+        let mut videos = Vec::new();
+        if let Some(codec) = self.video_codec {
+            videos.push(format!("bestvideo{}{}", height, codec.filter()));
+        }
+        videos.push(format!("bestvideo{}", height));
+
+        let mut audios = Vec::new();
+        let audio_filter = self.audio_quality.filter();
+        if !audio_filter.is_empty() {
+            audios.push(format!("bestaudio{}", audio_filter));
+        }
+        audios.push("bestaudio".to_string());
+
+        (videos, audios)
+    }
+
+    fn size_filter(&self) -> String {
+        let dimension = if self.vertical { "width" } else { "height" };
+        self.max_height
+            .map(|h| format!("[{}<={}]", dimension, h))
+            .unwrap_or_default()
+    }
+
+    /// The yt-dlp `-f` expression
+    pub fn expression(&self) -> String {
+        let height = self.size_filter();
+        let (videos, audios) = self.streams();
+
+        let mut alternatives: Vec<String> = videos
+            .iter()
+            .flat_map(|video| audios.iter().map(move |audio| format!("{}+{}", video, audio)))
+            .collect();
+        if !height.is_empty() {
+            alternatives.push(format!("best{}", height));
+        }
+        alternatives.push("best".to_string());
+
+        alternatives.join("/")
+    }
+
+    /// A `-f` expression that downloads the video and audio streams as two
+    /// separate files instead of merging them
+    pub fn separate_expression(&self) -> String {
+        let (videos, audios) = self.streams();
+        format!("{},{}", videos.join("/"), audios.join("/"))
+    }
+}
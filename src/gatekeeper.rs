@@ -0,0 +1,205 @@
+// src/gatekeeper.rs
+//! macOS Gatekeeper handling for yt-dlp and ffmpeg binaries: clearing the
+//! quarantine attribute from binaries Rustloader installs, checking code
+//! signatures, and explaining launches Gatekeeper blocked instead of
+//! surfacing a bare "permission denied". Elsewhere these are no-ops.
+
+use crate::error::AppError;
+#[cfg(target_os = "macos")]
+use crate::process::CommandSpec;
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+/// Extended attribute macOS sets on files downloaded by browsers and other
+/// quarantine-aware apps
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub const QUARANTINE_ATTRIBUTE: &str = "com.apple.quarantine";
+
+/// Result of `codesign --verify`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum SignatureStatus {
+    Valid,
+    Unsigned,
+    /// Signed, but the signature doesn't match the file (modified or damaged)
+    Invalid(String),
+    /// Not checked: not on macOS, or codesign couldn't run
+    Unknown,
+}
+
+/// Interpret the outcome of `codesign --verify --strict <file>`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn parse_codesign_output(success: bool, stderr: &str) -> SignatureStatus {
+    if success {
+        SignatureStatus::Valid
+    } else if stderr.contains("not signed at all") {
+        SignatureStatus::Unsigned
+    } else {
+        SignatureStatus::Invalid(stderr.trim().to_string())
+    }
+}
+
+/// Find a program the way the shell would, through PATH
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    })
+}
+
+/// Whether a file carries the quarantine attribute
+#[cfg(target_os = "macos")]
+pub fn is_quarantined(path: &Path) -> bool {
+    CommandSpec::system("xattr")
+        .flag("-p")
+        .operand(QUARANTINE_ATTRIBUTE)
+        .operand(path.to_string_lossy())
+        .to_command()
+        .and_then(|mut command| Ok(command.output()?))
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+#[allow(dead_code)]
+#[cfg(not(target_os = "macos"))]
+pub fn is_quarantined(_path: &Path) -> bool {
+    false
+}
+
+/// Remove the quarantine attribute so Gatekeeper doesn't block the binary
+#[cfg(target_os = "macos")]
+pub fn remove_quarantine(path: &Path) -> Result<(), AppError> {
+    if !is_quarantined(path) {
+        return Ok(());
+    }
+    let output = CommandSpec::system("xattr")
+        .flag("-d")
+        .operand(QUARANTINE_ATTRIBUTE)
+        .operand(path.to_string_lossy())
+        .to_command()?
+        .output()?;
+    if output.status.success() {
+        debug!("Removed quarantine attribute from {}", path.display());
+        Ok(())
+    } else {
+        Err(AppError::General(format!(
+            "Could not remove the quarantine attribute from {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn remove_quarantine(_path: &Path) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Check a binary's code signature
+#[cfg(target_os = "macos")]
+pub fn verify_signature(path: &Path) -> SignatureStatus {
+    match CommandSpec::system("codesign")
+        .flag("--verify")
+        .flag("--strict")
+        .operand(path.to_string_lossy())
+        .to_command()
+        .and_then(|mut command| Ok(command.output()?))
+    {
+        Ok(output) => parse_codesign_output(output.status.success(), &String::from_utf8_lossy(&output.stderr)),
+        Err(e) => {
+            debug!("codesign unavailable: {}", e);
+            SignatureStatus::Unknown
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn verify_signature(_path: &Path) -> SignatureStatus {
+    SignatureStatus::Unknown
+}
+
+/// Make a binary Rustloader just installed runnable: executable, out of
+/// quarantine, and not carrying a broken signature
+pub fn prepare_installed_binary(path: &Path) -> Result<(), AppError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    remove_quarantine(path)?;
+    match verify_signature(path) {
+        SignatureStatus::Invalid(reason) => {
+            warn!("{} has an invalid code signature: {}", path.display(), reason);
+            Err(AppError::General(format!(
+                "{} has an invalid code signature and was not installed",
+                path.display()
+            )))
+        }
+        SignatureStatus::Unsigned => {
+            debug!("{} is not code signed", path.display());
+            Ok(())
+        }
+        SignatureStatus::Valid | SignatureStatus::Unknown => Ok(()),
+    }
+}
+
+/// Explanation for a binary Gatekeeper refused to run
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn blocked_launch_message(program: &str, path: &Path, quarantined: bool, signature: &SignatureStatus) -> String {
+    let mut message = format!("macOS blocked {} ({}) from running.", program, path.display());
+    if quarantined {
+        message.push_str(&format!(
+            " It is quarantined because it was downloaded from the internet; if you trust it, run: xattr -d {} \"{}\"",
+            QUARANTINE_ATTRIBUTE,
+            path.display()
+        ));
+    }
+    match signature {
+        SignatureStatus::Invalid(_) => message.push_str(" Its code signature is invalid; reinstall it from a trusted source."),
+        SignatureStatus::Unsigned if !quarantined => {
+            message.push_str(" It is not code signed; allow it under System Settings > Privacy & Security.")
+        }
+        _ => {}
+    }
+    message.push_str(&format!(" Reinstalling with 'rustloader install {}' also fixes this.", program));
+    message
+}
+
+/// A clear error when `program` failed to start or was killed at launch
+/// because of Gatekeeper, or None when that isn't the cause
+#[cfg(target_os = "macos")]
+pub fn explain_launch_failure(program: &str) -> Option<AppError> {
+    let path = find_program(program)?;
+    let quarantined = is_quarantined(&path);
+    let signature = verify_signature(&path);
+    if !quarantined && matches!(signature, SignatureStatus::Valid | SignatureStatus::Unknown) {
+        return None;
+    }
+    Some(AppError::General(blocked_launch_message(program, &path, quarantined, &signature)))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn explain_launch_failure(_program: &str) -> Option<AppError> {
+    None
+}
+
+/// Gatekeeper kills blocked binaries with SIGKILL as they start
+pub fn explain_killed(program: &str, status: &ExitStatus) -> Option<AppError> {
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if status.signal() == Some(9) {
+            return explain_launch_failure(program);
+        }
+    }
+    let _ = (program, status);
+    None
+}
@@ -0,0 +1,169 @@
+// src/guardrails.rs
+//! Pre-flight checks for large downloads: the configurable yt-dlp file size
+//! limit, free disk space and available memory, taking a planned transcode
+//! into account.
+
+use crate::config::load_config;
+use crate::error::AppError;
+use crate::transcode::TranscodeKind;
+use humansize::{format_size, BINARY};
+use std::path::Path;
+
+/// File size limit passed to yt-dlp unless `max_filesize` is configured
+pub const DEFAULT_MAX_FILESIZE: &str = "10G";
+/// Disk space to keep free beyond what the download itself needs
+pub const DISK_HEADROOM_BYTES: u64 = 1024 * 1024 * 1024;
+/// Below this much available memory a transcode risks swapping or being killed
+pub const TRANSCODE_MIN_MEMORY_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Parse a size limit in the notation yt-dlp accepts ("500M", "1.5G",
+/// "2048"). "none", "unlimited" and "0" mean no limit.
+pub fn parse_size_limit(value: &str) -> Result<Option<u64>, AppError> {
+    let value = value.trim();
+    if value == "0" || value.eq_ignore_ascii_case("none") || value.eq_ignore_ascii_case("unlimited") {
+        return Ok(None);
+    }
+
+    let invalid = || AppError::ValidationError(format!("Invalid size limit '{}', expected e.g. 500M or 10G", value));
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        "T" => 1024 * 1024 * 1024 * 1024,
+        _ => return Err(invalid()),
+    };
+    if !number.is_finite() || number <= 0.0 {
+        return Err(invalid());
+    }
+    Ok(Some((number * multiplier as f64) as u64))
+}
+
+/// Free disk space and memory at the time of the check; None where the
+/// platform can't tell
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Resources {
+    pub free_disk_bytes: Option<u64>,
+    pub available_memory_bytes: Option<u64>,
+}
+
+impl Resources {
+    /// Measure the disk holding `dir` (or its nearest existing parent) and
+    /// the memory available to new processes
+    pub fn probe(dir: &Path) -> Self {
+        let free_disk_bytes = dir
+            .ancestors()
+            .find(|path| path.exists())
+            .and_then(|path| fs2::available_space(path).ok());
+
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        let available_memory_bytes = Some(system.available_memory()).filter(|bytes| *bytes > 0);
+
+        Resources { free_disk_bytes, available_memory_bytes }
+    }
+}
+
+/// Disk space a download of `estimated_bytes` needs at its peak: yt-dlp keeps
+/// the separate streams until they are merged, and a transcode writes a
+/// second copy before the original is removed
+pub fn required_disk_bytes(estimated_bytes: u64, transcode: bool) -> u64 {
+    let copies = if transcode { 3 } else { 2 };
+    estimated_bytes.saturating_mul(copies)
+}
+
+/// Problems to warn about before downloading `estimated_bytes`
+pub fn preflight_warnings(
+    estimated_bytes: u64,
+    max_filesize: Option<u64>,
+    transcode: Option<TranscodeKind>,
+    resources: &Resources,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if let Some(limit) = max_filesize {
+        if estimated_bytes > limit {
+            warnings.push(format!(
+                "The estimated size ({}) is over the {} file size limit, so yt-dlp will skip it. Raise max_filesize in config.json to allow it.",
+                format_size(estimated_bytes, BINARY),
+                format_size(limit, BINARY)
+            ));
+        }
+    }
+
+    if let Some(free) = resources.free_disk_bytes {
+        let needed = required_disk_bytes(estimated_bytes, transcode.is_some());
+        if free < needed.saturating_add(DISK_HEADROOM_BYTES) {
+            warnings.push(format!(
+                "This download needs up to {} of disk space{} but only {} is free.",
+                format_size(needed, BINARY),
+                if transcode.is_some() { " while merging and transcoding" } else { " while merging" },
+                format_size(free, BINARY)
+            ));
+        }
+    }
+
+    if let (Some(kind), Some(available)) = (transcode, resources.available_memory_bytes) {
+        if available < TRANSCODE_MIN_MEMORY_BYTES {
+            warnings.push(format!(
+                "Only {} of memory is available; the {} transcode may be very slow or fail.",
+                format_size(available, BINARY),
+                kind.as_str()
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Problems to warn about when the download is staged: the peak space for
+/// merging and transcoding is needed in the staging directory, and only the
+/// finished file in the output directory
+pub fn staged_preflight_warnings(
+    estimated_bytes: u64,
+    max_filesize: Option<u64>,
+    transcode: Option<TranscodeKind>,
+    staging: &Resources,
+    output: &Resources,
+) -> Vec<String> {
+    let mut warnings: Vec<String> = preflight_warnings(estimated_bytes, max_filesize, transcode, staging)
+        .into_iter()
+        .map(|warning| warning.replace("of disk space", "in the staging directory"))
+        .collect();
+
+    if let Some(free) = output.free_disk_bytes {
+        if free < estimated_bytes.saturating_add(DISK_HEADROOM_BYTES) {
+            warnings.push(format!(
+                "The finished file needs about {} in the download directory but only {} is free.",
+                format_size(estimated_bytes, BINARY),
+                format_size(free, BINARY)
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Pre-flight warnings for a download into `dir`, using the configured
+/// file size limit, staging directory and the resources available right now
+pub fn check_download(estimated_bytes: u64, transcode: Option<TranscodeKind>, dir: &Path) -> Vec<String> {
+    let config = load_config().unwrap_or_default();
+    let limit = config
+        .max_filesize_limit()
+        .ok()
+        .flatten()
+        .and_then(|value| parse_size_limit(&value).ok().flatten());
+    match config.staging_path().ok().flatten() {
+        Some(staging) => staged_preflight_warnings(
+            estimated_bytes,
+            limit,
+            transcode,
+            &Resources::probe(&staging),
+            &Resources::probe(dir),
+        ),
+        None => preflight_warnings(estimated_bytes, limit, transcode, &Resources::probe(dir)),
+    }
+}
@@ -0,0 +1,357 @@
+// src/history.rs
+//! Download history database. Every download the queue finishes, completed
+//! or failed, is recorded in an SQLite database in the data directory, so
+//! `rustloader history` and the GUI can list and search past downloads
+//! without reading the queue state file. Entries stay until cleared, even
+//! after the queue entry is removed by a retention policy.
+//!
+//! It is also the archive of finished downloads moved out of the queue state
+//! (see [`crate::download_history`]), so each download is kept in one place.
+//! With `encrypt_state` on, the URL, title, output path, error and archived
+//! download are sealed like the state files; the other columns hold nothing
+//! that identifies what was downloaded.
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::error::AppError;
+use crate::paths::data_dir;
+use crate::state_crypto::{decrypt, encrypt, encryption_enabled, is_encrypted, state_secret};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File name of the history database in the data directory
+pub const HISTORY_FILE: &str = "history.sqlite3";
+
+/// Entries shown by `history list` and `history search` unless asked otherwise
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// How long to wait for another process (CLI or GUI) writing the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS downloads (
+        id          TEXT PRIMARY KEY,
+        url         TEXT NOT NULL,
+        title       TEXT,
+        format      TEXT NOT NULL,
+        status      TEXT NOT NULL,
+        file_size   INTEGER,
+        output_path TEXT,
+        error       TEXT,
+        added_at    TEXT NOT NULL,
+        started_at  TEXT,
+        finished_at TEXT NOT NULL,
+        item        TEXT
+    );
+    CREATE INDEX IF NOT EXISTS downloads_finished_at ON downloads (finished_at);
+";
+
+const COLUMNS: &str =
+    "id, url, title, format, status, file_size, output_path, error, added_at, started_at, finished_at";
+
+/// Where the history database is kept
+pub fn history_path() -> Result<PathBuf, AppError> {
+    Ok(data_dir()?.join(HISTORY_FILE))
+}
+
+/// One finished download
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryEntry {
+    /// Queue ID of the download
+    pub id: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub format: String,
+    /// "completed" or "failed"; "canceled" only for archived downloads,
+    /// which aren't listed
+    pub status: String,
+    /// Size in bytes, when known
+    pub file_size: Option<u64>,
+    pub output_path: Option<String>,
+    /// Why a failed download failed
+    pub error: Option<String>,
+    pub added_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl HistoryEntry {
+    /// The entry for a completed or failed download; None while it is still
+    /// queued, running or paused, and for canceled downloads
+    pub fn from_item(item: &DownloadItem) -> Option<Self> {
+        match item.status {
+            DownloadStatus::Completed | DownloadStatus::Failed => Some(Self::archived(item)),
+            _ => None,
+        }
+    }
+
+    /// The entry for a download archived out of the queue state, which may
+    /// also have been canceled
+    fn archived(item: &DownloadItem) -> Self {
+        let status = match item.status {
+            DownloadStatus::Completed => "completed",
+            DownloadStatus::Failed => "failed",
+            _ => "canceled",
+        };
+        Self {
+            id: item.id.clone(),
+            url: item.url.clone(),
+            title: item.title.clone(),
+            format: item.format.clone(),
+            status: status.to_string(),
+            file_size: Some(item.total_bytes.max(item.downloaded_bytes)).filter(|&bytes| bytes > 0),
+            output_path: item.output_path.clone(),
+            error: item.error_message.clone(),
+            added_at: item.added_at,
+            started_at: item.started_at,
+            finished_at: item.finished_at.unwrap_or_else(Utc::now),
+        }
+    }
+
+    /// The title, or the URL when the title was never fetched
+    pub fn display_name(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.url)
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            title: row.get(2)?,
+            format: row.get(3)?,
+            status: row.get(4)?,
+            file_size: row.get::<_, Option<i64>>(5)?.map(|bytes| bytes.max(0) as u64),
+            output_path: row.get(6)?,
+            error: row.get(7)?,
+            added_at: row.get(8)?,
+            started_at: row.get(9)?,
+            finished_at: row.get(10)?,
+        })
+    }
+}
+
+/// An open history database
+pub struct History {
+    conn: Connection,
+    /// Whether new values are sealed, as `encrypt_state` asks
+    encrypt: bool,
+    /// The state encryption secret, fetched from the keychain when first needed
+    secret: OnceCell<Vec<u8>>,
+}
+
+impl History {
+    /// Open the database at `path`, creating it if missing
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch(SCHEMA)?;
+        // Databases from before the archive moved in lack its column
+        if conn.prepare("SELECT item FROM downloads LIMIT 0").is_err() {
+            conn.execute_batch("ALTER TABLE downloads ADD COLUMN item TEXT")?;
+        }
+        Ok(Self { conn, encrypt: encryption_enabled(), secret: OnceCell::new() })
+    }
+
+    /// Open the database in the data directory
+    pub fn open_default() -> Result<Self, AppError> {
+        Self::open(&history_path()?)
+    }
+
+    fn secret(&self) -> Result<&[u8], AppError> {
+        if self.secret.get().is_none() {
+            let secret = state_secret(self.encrypt)?.ok_or_else(|| {
+                AppError::General("The download history is encrypted but the key is missing from the OS keychain".to_string())
+            })?;
+            let _ = self.secret.set(secret);
+        }
+        Ok(self.secret.get().map(Vec::as_slice).unwrap_or_default())
+    }
+
+    fn seal(&self, value: &str) -> Result<String, AppError> {
+        if self.encrypt {
+            encrypt(value, self.secret()?)
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    fn unseal(&self, value: String) -> Result<String, AppError> {
+        if is_encrypted(&value) {
+            decrypt(&value, self.secret()?)
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn seal_opt(&self, value: Option<&str>) -> Result<Option<String>, AppError> {
+        value.map(|value| self.seal(value)).transpose()
+    }
+
+    fn unseal_opt(&self, value: Option<String>) -> Result<Option<String>, AppError> {
+        value.map(|value| self.unseal(value)).transpose()
+    }
+
+    fn unseal_entry(&self, mut entry: HistoryEntry) -> Result<HistoryEntry, AppError> {
+        entry.url = self.unseal(entry.url)?;
+        entry.title = self.unseal_opt(entry.title)?;
+        entry.output_path = self.unseal_opt(entry.output_path)?;
+        entry.error = self.unseal_opt(entry.error)?;
+        Ok(entry)
+    }
+
+    fn insert(&self, entry: &HistoryEntry, item: Option<&DownloadItem>) -> Result<(), AppError> {
+        let item = item.map(serde_json::to_string).transpose()?;
+        self.conn.execute(
+            &format!(
+                "INSERT OR REPLACE INTO downloads ({}, item) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                COLUMNS
+            ),
+            params![
+                entry.id,
+                self.seal(&entry.url)?,
+                self.seal_opt(entry.title.as_deref())?,
+                entry.format,
+                entry.status,
+                entry.file_size.map(|bytes| bytes.min(i64::MAX as u64) as i64),
+                self.seal_opt(entry.output_path.as_deref())?,
+                self.seal_opt(entry.error.as_deref())?,
+                entry.added_at,
+                entry.started_at,
+                entry.finished_at,
+                self.seal_opt(item.as_deref())?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record a finished download. A download recorded again (it was
+    /// retried) replaces its older entry.
+    pub fn record(&self, entry: &HistoryEntry) -> Result<(), AppError> {
+        self.insert(entry, None)
+    }
+
+    /// Keep downloads moved out of the queue state, canceled ones included,
+    /// with everything the queue knew about them. A download archived again
+    /// replaces its older entry.
+    pub fn archive(&self, items: &[DownloadItem]) -> Result<(), AppError> {
+        let transaction = self.conn.unchecked_transaction()?;
+        for item in items {
+            self.insert(&HistoryEntry::archived(item), Some(item))?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The downloads moved out of the queue state, oldest first
+    pub fn archived(&self) -> Result<Vec<DownloadItem>, AppError> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT item FROM downloads WHERE item IS NOT NULL ORDER BY finished_at")?;
+        let sealed = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        sealed
+            .into_iter()
+            .map(|item| Ok(serde_json::from_str(&self.unseal(item)?)?))
+            .collect()
+    }
+
+    /// The `limit` most recently finished downloads, newest first
+    pub fn list(&self, limit: usize) -> Result<Vec<HistoryEntry>, AppError> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {} FROM downloads WHERE status != 'canceled' ORDER BY finished_at DESC LIMIT ?1",
+            COLUMNS
+        ))?;
+        let entries = statement
+            .query_map(params![limit.min(i64::MAX as usize) as i64], HistoryEntry::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        entries.into_iter().map(|entry| self.unseal_entry(entry)).collect()
+    }
+
+    /// Downloads whose title, URL or output path contains `query`, ignoring
+    /// case, newest first. Sealed columns can't be matched in SQL, so the
+    /// entries are filtered here.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>, AppError> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Err(AppError::ValidationError("Search text cannot be empty".to_string()));
+        }
+        let mut matches = Vec::new();
+        for entry in self.list(usize::MAX)? {
+            if matches.len() == limit {
+                break;
+            }
+            let fields = [Some(entry.url.as_str()), entry.title.as_deref(), entry.output_path.as_deref()];
+            if fields.into_iter().flatten().any(|field| field.to_lowercase().contains(&query)) {
+                matches.push(entry);
+            }
+        }
+        Ok(matches)
+    }
+    /// Seal or unseal every entry as `encrypt_state` now asks, returning how
+    /// many were rewritten
+    pub fn reseal(&self) -> Result<usize, AppError> {
+        let rows = {
+            let mut statement = self.conn.prepare("SELECT id, url, title, output_path, error, item FROM downloads")?;
+            let rows = statement
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+
+        let reseal = |value: Option<String>| self.seal_opt(self.unseal_opt(value)?.as_deref());
+        let transaction = self.conn.unchecked_transaction()?;
+        let mut rewritten = 0;
+        for (id, url, title, output_path, error, item) in rows {
+            if is_encrypted(&url) == self.encrypt {
+                continue;
+            }
+            self.conn.execute(
+                "UPDATE downloads SET url = ?2, title = ?3, output_path = ?4, error = ?5, item = ?6 WHERE id = ?1",
+                params![
+                    id,
+                    self.seal(&self.unseal(url)?)?,
+                    reseal(title)?,
+                    reseal(output_path)?,
+                    reseal(error)?,
+                    reseal(item)?,
+                ],
+            )?;
+            rewritten += 1;
+        }
+        transaction.commit()?;
+        Ok(rewritten)
+    }
+
+    /// Delete the downloads finished before `before`, or all of them,
+    /// returning how many were deleted
+    pub fn clear(&self, before: Option<DateTime<Utc>>) -> Result<usize, AppError> {
+        let deleted = match before {
+            Some(before) => self
+                .conn
+                .execute("DELETE FROM downloads WHERE finished_at < ?1", params![before])?,
+            None => self.conn.execute("DELETE FROM downloads", [])?,
+        };
+        Ok(deleted)
+    }
+
+    /// Number of recorded downloads
+    pub fn count(&self) -> Result<usize, AppError> {
+        let count: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM downloads WHERE status != 'canceled'", [], |row| row.get(0))?;
+        Ok(count.max(0) as usize)
+    }
+}
@@ -0,0 +1,148 @@
+// src/http_headers.rs
+
+use crate::error::AppError;
+use crate::region_overrides::RegionOverrides;
+use serde::{Deserialize, Serialize};
+
+/// User agent sent when none is configured
+pub const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// A validated HTTP header passed to yt-dlp
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+impl HttpHeader {
+    /// Create a header, rejecting names that aren't HTTP tokens and values
+    /// containing control characters (which could inject extra headers)
+    pub fn new(name: &str, value: &str) -> Result<Self, AppError> {
+        let name = name.trim();
+        let value = value.trim();
+
+        if name.is_empty() || !name.chars().all(is_token_char) {
+            return Err(AppError::ValidationError(format!("Invalid header name: {:?}", name)));
+        }
+        if value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(AppError::ValidationError(format!("Invalid value for header {}", name)));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+
+    /// Parse a `--header` value such as `Referer: https://example.com/`
+    pub fn parse(raw: &str) -> Result<Self, AppError> {
+        let (name, value) = raw.split_once(':').ok_or_else(|| {
+            AppError::ValidationError(format!("Header must look like 'Name: value', got {:?}", raw))
+        })?;
+        Self::new(name, value)
+    }
+}
+
+/// Characters allowed in a header name (RFC 9110 token)
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+/// Custom user agent and extra headers for a download
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestHeaders {
+    pub user_agent: Option<String>,
+    pub headers: Vec<HttpHeader>,
+    /// Proxy URL rendered for this download, see `proxy::ProxyTemplate`
+    pub proxy: Option<String>,
+    /// yt-dlp `--extractor-args`, set when retrying past an age gate
+    pub extractor_args: Option<String>,
+    /// Player clients and geo-bypass country chosen for this download
+    pub region: RegionOverrides,
+}
+
+impl RequestHeaders {
+    /// Build from command-line values. A `User-Agent` passed as a header is
+    /// treated like `--user-agent`, with the explicit option taking precedence.
+    pub fn from_args<'a>(
+        user_agent: Option<&str>,
+        headers: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, AppError> {
+        let mut request_headers = Self::default();
+
+        for raw in headers {
+            let header = HttpHeader::parse(raw)?;
+            if header.name.eq_ignore_ascii_case("user-agent") {
+                request_headers.user_agent = Some(header.value);
+            } else {
+                request_headers.headers.push(header);
+            }
+        }
+
+        if let Some(user_agent) = user_agent {
+            request_headers.user_agent = Some(user_agent.trim().to_string());
+        }
+
+        if let Some(user_agent) = &request_headers.user_agent {
+            // yt-dlp would read a leading '-' as another option
+            if user_agent.is_empty() || user_agent.starts_with('-') || user_agent.chars().any(char::is_control) {
+                return Err(AppError::ValidationError(format!("Invalid user agent: {:?}", user_agent)));
+            }
+        }
+
+        Ok(request_headers)
+    }
+
+    /// Send the requests through `proxy`
+    pub fn with_proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Pass `extractor_args` to yt-dlp
+    pub fn with_extractor_args(mut self, extractor_args: Option<String>) -> Self {
+        self.extractor_args = extractor_args;
+        self
+    }
+
+    /// Apply per-download region overrides
+    pub fn with_region(mut self, region: RegionOverrides) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// The user agent to send
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+
+    /// yt-dlp options carrying these headers; yt-dlp forwards them to its
+    /// native and external downloaders
+    pub fn ytdlp_options(&self) -> Vec<(&'static str, String)> {
+        let mut options = vec![("--user-agent", self.user_agent().to_string())];
+        for header in &self.headers {
+            options.push(("--add-header", format!("{}:{}", header.name, header.value)));
+        }
+        if let Some(proxy) = &self.proxy {
+            options.push(("--proxy", proxy.clone()));
+        }
+        // An age-gate retry picks its own player client, replacing the chosen one
+        if let Some(extractor_args) = self.extractor_args.clone().or_else(|| self.region.extractor_args()) {
+            options.push(("--extractor-args", extractor_args));
+        }
+        if let Some(country) = &self.region.geo_bypass_country {
+            options.push(("--geo-bypass-country", country.clone()));
+        }
+        options
+    }
+
+    /// The same options as a flat argument list
+    #[allow(dead_code)]
+    pub fn ytdlp_args(&self) -> Vec<String> {
+        self.ytdlp_options()
+            .into_iter()
+            .flat_map(|(flag, value)| [flag.to_string(), value])
+            .collect()
+    }
+}
@@ -0,0 +1,184 @@
+// src/init_wizard.rs
+//! First-run setup for terminal users, the CLI counterpart of the GUI onboarding
+
+use crate::cli::{PRO_QUALITIES, VIDEO_QUALITIES};
+use crate::config::{get_config_path, load_config, save_config, AppConfig};
+use crate::dependency_validator::{install_or_update_dependency, validate_dependencies};
+use crate::download_manager::DEFAULT_CONCURRENT_DOWNLOADS;
+use crate::environment;
+use crate::error::AppError;
+use crate::license::is_pro_version;
+use crate::security::validate_path_safety;
+use colored::*;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Formats offered for downloads
+const FORMATS: &[&str] = &["mp4", "mp3"];
+/// Queue concurrency offered
+const MAX_CONCURRENT_DOWNLOADS: usize = 10;
+
+/// Reads answers from `input`, echoing prompts to `output`
+struct Prompter<'a, R: BufRead, W: Write> {
+    input: &'a mut R,
+    output: &'a mut W,
+}
+
+impl<R: BufRead, W: Write> Prompter<'_, R, W> {
+    /// Ask a question; an empty answer keeps `default`
+    fn ask(&mut self, question: &str, default: &str) -> Result<String, AppError> {
+        if default.is_empty() {
+            write!(self.output, "{}: ", question)?;
+        } else {
+            write!(self.output, "{} [{}]: ", question, default)?;
+        }
+        self.output.flush()?;
+
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            return Err(AppError::ValidationError("Setup cancelled".to_string()));
+        }
+        let answer = answer.trim();
+        Ok(if answer.is_empty() { default } else { answer }.to_string())
+    }
+
+    /// Ask until the answer is one of `choices`
+    fn choose(&mut self, question: &str, choices: &[&str], default: &str) -> Result<String, AppError> {
+        let question = format!("{} ({})", question, choices.join("/"));
+        loop {
+            let answer = self.ask(&question, default)?.to_lowercase();
+            if choices.contains(&answer.as_str()) {
+                return Ok(answer);
+            }
+            writeln!(self.output, "{}", format!("Please enter one of: {}", choices.join(", ")).yellow())?;
+        }
+    }
+
+    /// Ask a yes/no question
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool, AppError> {
+        let answer = self.choose(question, &["y", "n"], if default { "y" } else { "n" })?;
+        Ok(answer == "y")
+    }
+
+    /// Ask for a number in `1..=max`
+    fn number(&mut self, question: &str, max: usize, default: usize) -> Result<usize, AppError> {
+        loop {
+            let answer = self.ask(&format!("{} (1-{})", question, max), &default.to_string())?;
+            match answer.parse::<usize>() {
+                Ok(value) if (1..=max).contains(&value) => return Ok(value),
+                _ => writeln!(self.output, "{}", format!("Please enter a number from 1 to {}", max).yellow())?,
+            }
+        }
+    }
+}
+
+/// Walk through the download settings, starting from `config`, and return
+/// the updated configuration. Pressing enter keeps the current value. The
+/// qualities only Pro offers are left out unless `is_pro`.
+pub fn configure<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    mut config: AppConfig,
+    is_pro: bool,
+) -> Result<AppConfig, AppError> {
+    let mut prompter = Prompter { input, output };
+
+    config.download_dir = loop {
+        let dir = prompter.ask(
+            "Download directory (leave empty for the default)",
+            config.download_dir.as_deref().unwrap_or(""),
+        )?;
+        if dir.is_empty() {
+            break None;
+        }
+        match validate_path_safety(Path::new(&dir)) {
+            Ok(_) => break Some(dir),
+            Err(e) => writeln!(prompter.output, "{}: {}", "Unusable directory".yellow(), e)?,
+        }
+    };
+
+    config.default_format = Some(prompter.choose(
+        "Default format",
+        FORMATS,
+        config.default_format.as_deref().unwrap_or("mp4"),
+    )?);
+
+    let qualities: Vec<&str> = VIDEO_QUALITIES
+        .into_iter()
+        .filter(|quality| is_pro || !PRO_QUALITIES.contains(quality))
+        .collect();
+    config.default_quality = Some(prompter.choose(
+        "Default video quality",
+        &qualities,
+        config
+            .default_quality
+            .as_deref()
+            .filter(|quality| qualities.contains(quality))
+            .unwrap_or("1080"),
+    )?);
+
+    config.max_concurrent_downloads = Some(prompter.number(
+        "Downloads to run at once",
+        MAX_CONCURRENT_DOWNLOADS,
+        config.max_concurrent_downloads.unwrap_or(DEFAULT_CONCURRENT_DOWNLOADS),
+    )?);
+
+    config.notifications = Some(prompter.confirm(
+        "Show a desktop notification when a download finishes?",
+        config.notifications_enabled(),
+    )?);
+
+    Ok(config)
+}
+
+/// Run `rustloader init`: configure defaults, save them, then offer to
+/// install missing dependencies
+pub fn run_init_wizard() -> Result<(), AppError> {
+    if !environment::is_interactive() {
+        return Err(AppError::ValidationError(
+            "'rustloader init' needs a terminal; edit config.json instead (see 'rustloader paths')".to_string(),
+        ));
+    }
+    println!("{}", "Rustloader setup".bright_cyan().bold());
+    println!("Press enter to keep the value shown in brackets.\n");
+
+    let config = configure(&mut io::stdin().lock(), &mut io::stdout(), load_config().unwrap_or_default(), is_pro_version())?;
+    save_config(&config)?;
+    println!("{} {:?}", "Settings saved to".green(), get_config_path()?);
+
+    println!("\n{}", "Checking dependencies...".blue());
+    let dependencies = validate_dependencies().unwrap_or_default();
+    for name in ["yt-dlp", "ffmpeg"] {
+        let action = match dependencies.get(name) {
+            Some(info) if info.is_min_version && !info.is_vulnerable => {
+                println!("{} {}", name, "is installed.".green());
+                continue;
+            }
+            Some(_) => "update",
+            None => "install",
+        };
+
+        // Installers may prompt themselves, so stdin is only locked while asking
+        let install = {
+            let mut prompter = Prompter { input: &mut io::stdin().lock(), output: &mut io::stdout() };
+            prompter.confirm(&format!("{} {} now?", capitalize(action), name), true)?
+        };
+        if install {
+            match install_or_update_dependency(name) {
+                Ok(_) => println!("{} {}", name, "is ready.".green()),
+                Err(e) => println!("{}: {}", format!("Could not {} {}", action, name).yellow(), e),
+            }
+        }
+    }
+
+    println!("\n{}", "Setup complete. Run 'rustloader <URL>' to start downloading.".green());
+    Ok(())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
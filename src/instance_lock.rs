@@ -0,0 +1,154 @@
+// src/instance_lock.rs
+//! Single-writer guard for the download queue.
+//!
+//! The first CLI or GUI process to run the queue takes an exclusive lock on
+//! a file in the data directory and owns the queue state. Later processes
+//! find the lock taken and run read-only: they can inspect downloads, but
+//! changes to the queue are refused so two processes never write the state
+//! file at once. CLI commands that only read take no lock, and the GUI keeps
+//! retrying so it takes over once a CLI run ends.
+
+use crate::download_manager::get_data_dir;
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Lock file name, in the data directory
+pub const LOCK_FILE: &str = "rustloader.lock";
+const INFO_FILE: &str = "instance.json";
+
+/// How often a read-only GUI tries to take the lock over
+#[allow(dead_code)]
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The kind of process holding the lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceKind {
+    Cli,
+    Gui,
+}
+
+/// Who holds the lock, written next to the lock file for other processes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    pub pid: u32,
+    pub kind: InstanceKind,
+    pub started_at: DateTime<Utc>,
+}
+
+impl InstanceInfo {
+    fn current(kind: InstanceKind) -> Self {
+        Self {
+            pid: std::process::id(),
+            kind,
+            started_at: Utc::now(),
+        }
+    }
+
+    /// Short description such as "the GUI (pid 4242)"
+    pub fn describe(&self) -> String {
+        let kind = match self.kind {
+            InstanceKind::Cli => "a CLI process",
+            InstanceKind::Gui => "the GUI",
+        };
+        format!("{} (pid {})", kind, self.pid)
+    }
+}
+
+/// Held for the lifetime of the process that owns the queue; the lock is
+/// released when this is dropped or the process exits
+#[derive(Debug)]
+pub struct InstanceLock {
+    file: File,
+    info_path: PathBuf,
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.info_path);
+        // fs2's unlock, not the one std added in 1.89
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Set while another process owns the queue, and cleared when this one
+/// takes the lock over. `Some(None)` means the owner is running but didn't
+/// record its details.
+static OTHER_INSTANCE: RwLock<Option<Option<InstanceInfo>>> = RwLock::new(None);
+
+/// Try to take the lock in `dir`. Returns `Ok(Err(holder))` when another
+/// process already holds it.
+pub fn try_lock_in(dir: &Path, kind: InstanceKind) -> Result<Result<InstanceLock, Option<InstanceInfo>>, AppError> {
+    fs::create_dir_all(dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    let info_path = dir.join(INFO_FILE);
+
+    if file.try_lock_exclusive().is_err() {
+        let holder = fs::read_to_string(&info_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok());
+        return Ok(Err(holder));
+    }
+
+    let info = InstanceInfo::current(kind);
+    if let Err(e) = fs::write(&info_path, serde_json::to_string(&info)?) {
+        warn!("Could not record instance details: {}", e);
+    }
+
+    Ok(Ok(InstanceLock { file, info_path }))
+}
+
+/// Take the instance lock for this process. When another process holds it,
+/// this process is switched to read-only queue access and `None` is returned;
+/// calling this again once the lock is free switches it back.
+pub fn acquire_instance_lock(kind: InstanceKind) -> Result<Option<InstanceLock>, AppError> {
+    match try_lock_in(&get_data_dir(), kind)? {
+        Ok(lock) => {
+            debug!("Acquired instance lock as {:?}", kind);
+            *OTHER_INSTANCE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+            Ok(Some(lock))
+        }
+        Err(holder) => {
+            let description = holder
+                .as_ref()
+                .map(InstanceInfo::describe)
+                .unwrap_or_else(|| "another process".to_string());
+            let mut other = OTHER_INSTANCE.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // Retries stay quiet
+            if other.is_none() {
+                warn!("Download queue is owned by {}; running read-only", description);
+            }
+            *other = Some(holder);
+            Ok(None)
+        }
+    }
+}
+
+/// Details of the process that owns the queue, when it isn't this one
+pub fn other_instance() -> Option<Option<InstanceInfo>> {
+    OTHER_INSTANCE.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Fail with [`AppError::InstanceLocked`] if another process owns the queue
+pub fn ensure_queue_owner() -> Result<(), AppError> {
+    match other_instance() {
+        None => Ok(()),
+        Some(holder) => Err(AppError::InstanceLocked(
+            holder
+                .as_ref()
+                .map(InstanceInfo::describe)
+                .unwrap_or_else(|| "another process".to_string()),
+        )),
+    }
+}
@@ -0,0 +1,184 @@
+// src/ipc.rs
+//! Local stream of queue events for apps embedding Rustloader, without
+//! HTTP. Set `"event_stream": true` in config.json and the process running
+//! the queue serves the same NDJSON events as `rustloader queue follow` on
+//! a Unix socket (`events.sock` in the data directory, shown by
+//! `rustloader paths`) or, on Windows, the named pipe `\\.\pipe\rustloader-events`.
+//!
+//! Each client first gets a `state_changed` event for every download in
+//! the queue, then live events as they happen, one JSON object per line.
+//! A client that falls behind skips the events it missed.
+//!
+//! A minimal client:
+//!
+//! ```no_run
+//! use std::io::{BufRead, BufReader};
+//! # #[cfg(unix)]
+//! # fn main() -> std::io::Result<()> {
+//! let socket = std::os::unix::net::UnixStream::connect("/path/to/events.sock")?;
+//! for line in BufReader::new(socket).lines() {
+//!     let event: serde_json::Value = serde_json::from_str(&line?)?;
+//!     println!("{} {}", event["event"], event["id"]);
+//! }
+//! # Ok(())
+//! # }
+//! # #[cfg(not(unix))]
+//! # fn main() {}
+//! ```
+
+use crate::download_map::{snapshot_items, DownloadMap};
+use crate::error::AppError;
+use crate::queue_events::{diff_queue_events, progress_events, QueueEvent};
+use chrono::Utc;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Socket file in the data directory
+pub const SOCKET_FILE: &str = "events.sock";
+
+/// Named pipe used on Windows
+pub const PIPE_NAME: &str = r"\\.\pipe\rustloader-events";
+
+/// Interval between progress snapshots of running downloads
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Events buffered for each client before a slow one starts skipping
+const CLIENT_BUFFER: usize = 256;
+
+type Downloads = Arc<RwLock<DownloadMap>>;
+
+/// Where clients connect: the socket file, or the pipe name on Windows
+pub fn event_stream_path() -> Result<PathBuf, AppError> {
+    if cfg!(windows) {
+        Ok(PathBuf::from(PIPE_NAME))
+    } else {
+        Ok(crate::paths::data_dir()?.join(SOCKET_FILE))
+    }
+}
+
+/// `event` as one NDJSON line
+pub fn encode_event(event: &QueueEvent) -> Result<String, AppError> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Serve the queue's events to local clients for as long as
+/// `notifications` is open
+pub fn spawn_event_stream(downloads: Downloads, mut notifications: broadcast::Receiver<()>) {
+    let (lines, _) = broadcast::channel::<Arc<str>>(CLIENT_BUFFER);
+
+    let server_downloads = Arc::clone(&downloads);
+    let server_lines = lines.clone();
+    tokio::spawn(async move {
+        if let Err(e) = serve(server_downloads, server_lines).await {
+            warn!("Event stream stopped: {}", e);
+        }
+    });
+
+    tokio::spawn(async move {
+        let snapshot = || snapshot_items(&downloads);
+        let mut known = HashMap::new();
+        diff_queue_events(&mut known, &snapshot(), Utc::now());
+        let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+
+        loop {
+            let events = tokio::select! {
+                notification = notifications.recv() => {
+                    match notification {
+                        Ok(()) | Err(RecvError::Lagged(_)) => diff_queue_events(&mut known, &snapshot(), Utc::now()),
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                _ = ticker.tick() => progress_events(&snapshot(), Utc::now()),
+            };
+            // Nothing to encode while no client is connected
+            if lines.receiver_count() == 0 {
+                continue;
+            }
+            for event in &events {
+                match encode_event(event) {
+                    Ok(line) => {
+                        let _ = lines.send(Arc::from(line));
+                    }
+                    Err(e) => warn!("Could not encode queue event: {}", e),
+                }
+            }
+        }
+    });
+}
+
+/// Write the current state, then every line from `lines`, to `client`
+async fn stream_to<W>(mut client: W, mut lines: broadcast::Receiver<Arc<str>>, downloads: Downloads) -> Result<(), AppError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let current = snapshot_items(&downloads);
+    for event in diff_queue_events(&mut HashMap::new(), &current, Utc::now()) {
+        client.write_all(encode_event(&event)?.as_bytes()).await?;
+    }
+    loop {
+        match lines.recv().await {
+            Ok(line) => client.write_all(line.as_bytes()).await?,
+            Err(RecvError::Lagged(skipped)) => debug!("Event stream client fell behind by {} events", skipped),
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn serve(downloads: Downloads, lines: broadcast::Sender<Arc<str>>) -> Result<(), AppError> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::UnixListener;
+
+    let path = event_stream_path()?;
+    // Left behind by a previous run; this process owns the queue now
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    // Other users on the machine shouldn't see what is being downloaded
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    info!("Serving queue events on {}", path.display());
+
+    loop {
+        let (client, _) = listener.accept().await?;
+        let receiver = lines.subscribe();
+        let downloads = Arc::clone(&downloads);
+        tokio::spawn(async move {
+            if let Err(e) = stream_to(client, receiver, downloads).await {
+                debug!("Event stream client disconnected: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+async fn serve(downloads: Downloads, lines: broadcast::Sender<Arc<str>>) -> Result<(), AppError> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(PIPE_NAME)?;
+    info!("Serving queue events on {}", PIPE_NAME);
+
+    loop {
+        server.connect().await?;
+        // The next client connects to a fresh instance
+        let client = std::mem::replace(
+            &mut server,
+            ServerOptions::new().reject_remote_clients(true).create(PIPE_NAME)?,
+        );
+        let receiver = lines.subscribe();
+        let downloads = Arc::clone(&downloads);
+        tokio::spawn(async move {
+            if let Err(e) = stream_to(client, receiver, downloads).await {
+                debug!("Event stream client disconnected: {}", e);
+            }
+        });
+    }
+}
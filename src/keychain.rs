@@ -0,0 +1,48 @@
+// src/keychain.rs
+//! Secrets kept in the operating system keychain (macOS Keychain, Windows
+//! Credential Manager, Secret Service on Linux)
+
+use crate::error::AppError;
+use base64::{engine::general_purpose, Engine as _};
+use log::info;
+use ring::rand::{SecureRandom, SystemRandom};
+
+const SERVICE: &str = "rustloader";
+const SECRET_LEN: usize = 32;
+
+fn entry(account: &str) -> Result<keyring::Entry, AppError> {
+    keyring::Entry::new(SERVICE, account)
+        .map_err(|e| AppError::General(format!("OS keychain is unavailable: {}", e)))
+}
+
+/// Read a secret from the keychain, if one has been stored
+pub fn get_secret(account: &str) -> Result<Option<Vec<u8>>, AppError> {
+    match entry(account)?.get_password() {
+        Ok(encoded) => general_purpose::STANDARD
+            .decode(encoded)
+            .map(Some)
+            .map_err(|_| AppError::General(format!("Keychain secret '{}' is malformed", account))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::General(format!("Could not read keychain secret '{}': {}", account, e))),
+    }
+}
+
+/// Read a secret from the keychain, generating and storing a random one
+/// the first time
+pub fn get_or_create_secret(account: &str) -> Result<Vec<u8>, AppError> {
+    if let Some(secret) = get_secret(account)? {
+        return Ok(secret);
+    }
+
+    let mut secret = vec![0u8; SECRET_LEN];
+    SystemRandom::new()
+        .fill(&mut secret)
+        .map_err(|_| AppError::General("Failed to generate a random secret".to_string()))?;
+
+    entry(account)?
+        .set_password(&general_purpose::STANDARD.encode(&secret))
+        .map_err(|e| AppError::General(format!("Could not store keychain secret '{}': {}", account, e)))?;
+    info!("Created keychain secret '{}'", account);
+
+    Ok(secret)
+}
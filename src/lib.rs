@@ -5,23 +5,102 @@ use serde::{Serialize, Deserialize};
 use once_cell::sync::Lazy;
 
 // Make modules accessible in tests
+pub mod age_gate;
+pub mod bandwidth;
+pub mod chapters;
+pub mod cleanup;
+pub mod circuit_breaker;
 pub mod cli;
+pub mod cloud_share;
+pub mod completion;
+pub mod config;
+pub mod crash_report;
+pub mod dedupe;
 pub mod dependency_validator;
+#[cfg(feature = "platform-integration")]
+pub mod desktop_progress;
 pub mod downloader;
+pub mod download_history;
 pub mod download_manager;
+pub mod download_map;
+pub mod environment;
 pub mod error;
+pub mod feature_gate;
+pub mod ffmpeg_args;
+pub mod ffmpeg_features;
+pub mod file_actions;
+pub mod format_picker;
+pub mod format_selector;
+pub mod gatekeeper;
+pub mod guardrails;
+pub mod history;
+pub mod http_headers;
+pub mod init_wizard;
+pub mod instance_lock;
+pub mod ipc;
+pub mod keychain;
 pub mod license;
+pub mod media_naming;
+pub mod media_preview;
+pub mod metadata_cache;
+pub mod mux;
+pub mod notifications;
+pub mod paths;
+pub mod player;
+pub mod playlist;
+pub mod plugins;
+pub mod power;
+pub mod process;
+pub mod progress_parser;
+pub mod promo;
+pub mod provider_health;
+pub mod proxy;
+pub mod publish;
+pub mod quality_compare;
+pub mod queue_changes;
+pub mod queue_eta;
+pub mod queue_events;
+pub mod queue_list;
+pub mod queue_order;
+pub mod queue_repair;
+pub mod queue_schedule;
+pub mod queue_undo;
+pub mod queue_wait;
+pub mod quota;
+pub mod recent_urls;
+pub mod region_overrides;
+pub mod retention;
 pub mod security;
+pub mod silence;
+pub mod site_stats;
+pub mod smart_defaults;
+pub mod snapshot;
+pub mod split;
+pub mod state_crypto;
+pub mod stdout_stream;
+pub mod templates;
+pub mod throughput_stats;
+pub mod trace_file;
+pub mod transcode;
+pub mod transliterate;
+pub mod url_resolver;
 pub mod utils;
 pub mod version;
+pub mod vertical;
+pub mod video_id;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm_plugins;
+pub mod webhooks;
+pub mod windows_integration;
 
 // Re-export download manager types for easier use
 pub use crate::download_manager::{
     DownloadItem, DownloadPriority, DownloadQueue, DownloadStatus, 
-    add_download_to_queue, pause_all_downloads, resume_all_downloads,
+    PreparedDownload, add_download_to_queue, prepare_download, enqueue,
+    pause_all_downloads, resume_all_downloads,
     pause_download, resume_download, cancel_download, 
     set_download_priority, get_all_downloads, get_download_status,
-    shutdown_download_manager,
+    shutdown_download_manager, shutdown_download_manager_blocking,
 };
 
 // Progress tracking state using atomics for thread safety
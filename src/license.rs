@@ -3,9 +3,10 @@
 use crate::error::AppError;
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Local, Utc};
-use dirs_next as dirs;
+use crate::paths::{config_dir, platform_config_dir, platform_data_dir};
 use ring::{digest, hmac};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -19,10 +20,79 @@ pub struct LicenseInfo {
     pub machine_id: String,
 }
 
+/// Features unlocked by a Pro license
+#[allow(dead_code)]
+pub const PRO_FEATURES: [&str; 5] = [
+    "unlimited-downloads",
+    "high-resolution-video",
+    "high-fidelity-audio",
+    "multi-threaded-downloads",
+    "priority-support",
+];
+
+/// Length of the one-time trial
+pub const TRIAL_DAYS: i64 = 14;
+
+/// How far the clock may go back since the trial was last seen, e.g. after
+/// a time sync, before it counts as rolled back
+const TRIAL_CLOCK_TOLERANCE_MINUTES: i64 = 5;
+
+/// How often the trial record's last-seen time is brought up to date
+const TRIAL_LAST_SEEN_INTERVAL_MINUTES: i64 = 60;
+
+/// A one-time trial of the Pro features, bound to this machine
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrialInfo {
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub machine_id: String,
+    /// When the trial was last checked, so setting the clock back is noticed
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+impl TrialInfo {
+    pub fn new(started_at: DateTime<Utc>, machine_id: &str) -> Self {
+        TrialInfo {
+            started_at,
+            expires_at: started_at + chrono::Duration::days(TRIAL_DAYS),
+            machine_id: machine_id.to_string(),
+            last_seen: Some(started_at),
+        }
+    }
+
+    /// Reject a clock set before the trial started or back from when it was last seen
+    pub fn check_clock_at(&self, now: DateTime<Utc>) -> Result<(), String> {
+        if now < self.started_at {
+            return Err("System clock is set before the trial started".to_string());
+        }
+        let tolerance = chrono::Duration::minutes(TRIAL_CLOCK_TOLERANCE_MINUTES);
+        if self.last_seen.is_some_and(|seen| now + tolerance < seen) {
+            return Err("System clock was set back since the trial was last used".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whole days left at `now`, counting a partial day as one
+    pub fn days_left_at(&self, now: DateTime<Utc>) -> i64 {
+        if now >= self.expires_at {
+            return 0;
+        }
+        let seconds = (self.expires_at - now).num_seconds();
+        (seconds + 86_399) / 86_400
+    }
+
+    pub fn days_left(&self) -> i64 {
+        self.days_left_at(Utc::now())
+    }
+}
+
 // License verification result
 pub enum LicenseStatus {
     Free,
     Pro(LicenseInfo),
+    /// Pro features from an active trial
+    Trial(TrialInfo),
     Invalid(String), // Contains the reason for invalidity
 }
 
@@ -185,16 +255,129 @@ fn get_verification_key() -> Vec<u8> {
     final_digest.as_ref().to_vec()
 }
 
-// Path to the license file
-fn get_license_path() -> Result<PathBuf, AppError> {
-    let mut path = dirs::config_dir()
-        .ok_or_else(|| AppError::PathError("Could not find config directory".to_string()))?;
+/// Path to the license file
+pub fn get_license_path() -> Result<PathBuf, AppError> {
+    Ok(config_dir()?.join("license.dat"))
+}
 
-    path.push("rustloader");
-    fs::create_dir_all(&path)?;
+/// Path to the per-key activation counts, kept across deactivations
+pub fn get_activations_path() -> Result<PathBuf, AppError> {
+    Ok(config_dir()?.join("license_activations.json"))
+}
 
-    path.push("license.dat");
-    Ok(path)
+// Activation counts are keyed by a hash so the file doesn't hold license keys
+fn activation_key(license_key: &str) -> String {
+    digest::digest(&digest::SHA256, license_key.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn load_activation_counts() -> HashMap<String, u32> {
+    get_activations_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// How many times a license key has been activated on this machine
+#[allow(dead_code)]
+pub fn activation_count(license_key: &str) -> u32 {
+    load_activation_counts().get(&activation_key(license_key)).copied().unwrap_or(0)
+}
+
+fn record_activation(license_key: &str) -> Result<(), AppError> {
+    let mut counts = load_activation_counts();
+    *counts.entry(activation_key(license_key)).or_insert(0) += 1;
+    fs::write(get_activations_path()?, serde_json::to_string_pretty(&counts)?)?;
+    Ok(())
+}
+
+/// Where the trial record is kept. A copy in the data directory means
+/// deleting the config directory doesn't start a new trial. Both are the
+/// platform directories, so pointing RUSTLOADER_CONFIG_DIR/DATA_DIR at an
+/// empty folder doesn't either.
+pub fn get_trial_paths() -> Result<[PathBuf; 2], AppError> {
+    Ok([platform_config_dir()?.join("trial.dat"), platform_data_dir()?.join("trial.dat")])
+}
+
+fn save_trial(trial: &TrialInfo) -> Result<(), AppError> {
+    let encoded = encode_trial(trial)?;
+    for path in get_trial_paths()? {
+        fs::write(path, &encoded)?;
+    }
+    Ok(())
+}
+
+// Trial records use the license file format: base64 of the JSON and its HMAC
+fn encode_trial(trial: &TrialInfo) -> Result<String, AppError> {
+    let trial_json = serde_json::to_string(trial)?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &get_verification_key());
+    let signature = hmac::sign(&key, trial_json.as_bytes());
+    let full_data = format!("{}\n{}", trial_json, general_purpose::STANDARD.encode(signature.as_ref()));
+    Ok(general_purpose::STANDARD.encode(full_data))
+}
+
+fn decode_trial(encoded: &str) -> Result<TrialInfo, String> {
+    let tampered = || "Trial record has been modified".to_string();
+    let full_data = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|data| String::from_utf8(data).ok())
+        .ok_or_else(tampered)?;
+    let (trial_json, signature) = full_data.split_once('\n').ok_or_else(tampered)?;
+    let sig_bytes = general_purpose::STANDARD.decode(signature).map_err(|_| tampered())?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &get_verification_key());
+    hmac::verify(&key, trial_json.as_bytes(), &sig_bytes).map_err(|_| tampered())?;
+    serde_json::from_str(trial_json).map_err(|_| tampered())
+}
+
+/// The trial recorded on this machine, if one was ever started. A record
+/// that fails verification is an error rather than "no trial", so editing
+/// it can't buy a fresh one.
+pub fn load_trial() -> Result<Option<Result<TrialInfo, String>>, AppError> {
+    for path in get_trial_paths()? {
+        if !path.exists() {
+            continue;
+        }
+        let now = Utc::now();
+        let trial = decode_trial(&fs::read_to_string(&path)?).and_then(|trial| {
+            if trial.machine_id != get_machine_id().map_err(|e| e.to_string())? {
+                return Err("Trial was started on a different machine".to_string());
+            }
+            trial.check_clock_at(now)?;
+            Ok(trial)
+        });
+        if let Ok(trial) = &trial {
+            let interval = chrono::Duration::minutes(TRIAL_LAST_SEEN_INTERVAL_MINUTES);
+            if trial.last_seen.map_or(true, |seen| now - seen >= interval) {
+                save_trial(&TrialInfo { last_seen: Some(now), ..trial.clone() })?;
+            }
+        }
+        return Ok(Some(trial));
+    }
+    Ok(None)
+}
+
+/// Start the one-time trial of the Pro features
+pub fn start_trial() -> Result<TrialInfo, AppError> {
+    // The trial only applies without a license, so don't start one that can't be used
+    if get_license_path()?.exists() {
+        return Err(AppError::LicenseError(
+            "A license is already installed".to_string(),
+        ));
+    }
+    if load_trial()?.is_some() {
+        return Err(AppError::LicenseError(
+            "The trial has already been used on this machine".to_string(),
+        ));
+    }
+
+    let trial = TrialInfo::new(Utc::now(), &get_machine_id()?);
+    save_trial(&trial)?;
+    Ok(trial)
 }
 
 // Improved license verification with server check and additional validations
@@ -303,9 +486,13 @@ pub fn save_license(license: &LicenseInfo) -> Result<(), AppError> {
 pub fn load_license() -> Result<LicenseStatus, AppError> {
     let license_path = get_license_path()?;
 
-    // Check if license file exists
+    // Without a license, an active trial still unlocks Pro
     if !license_path.exists() {
-        return Ok(LicenseStatus::Free);
+        return Ok(match load_trial()? {
+            Some(Ok(trial)) if trial.days_left() > 0 => LicenseStatus::Trial(trial),
+            Some(Err(reason)) => LicenseStatus::Invalid(reason),
+            _ => LicenseStatus::Free,
+        });
     }
 
     // Read and decode the license file
@@ -375,7 +562,7 @@ pub fn load_license() -> Result<LicenseStatus, AppError> {
 
 // Check if the current installation is Pro
 pub fn is_pro_version() -> bool {
-    matches!(load_license(), Ok(LicenseStatus::Pro(_)))
+    matches!(load_license(), Ok(LicenseStatus::Pro(_) | LicenseStatus::Trial(_)))
 }
 
 // Activate a license key
@@ -396,15 +583,142 @@ pub fn activate_license(license_key: &str, email: &str) -> Result<LicenseStatus,
 
     // Save license to disk
     save_license(&license)?;
+    if let Err(e) = record_activation(license_key) {
+        log::warn!("Failed to record license activation: {}", e);
+    }
 
     Ok(LicenseStatus::Pro(license))
 }
 
+/// Remove the license from this machine, returning to the free version.
+/// Returns false if no license was installed.
+#[allow(dead_code)]
+pub fn deactivate_license() -> Result<bool, AppError> {
+    let license_path = get_license_path()?;
+    if !license_path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(license_path)?;
+    Ok(true)
+}
+
+/// Which kind of license is in effect
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseTier {
+    Free,
+    Pro,
+    /// Pro features from the time-limited trial
+    Trial,
+    /// A license is installed but doesn't verify; the free version applies
+    Invalid,
+}
+
+/// Everything a license panel shows, in a form the GUI can render directly
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LicenseState {
+    pub tier: LicenseTier,
+    pub email: Option<String>,
+    /// The key with all but its last segment hidden
+    pub key_hint: Option<String>,
+    pub activated_at: Option<DateTime<Utc>>,
+    /// None for perpetual licenses
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Days until the trial ends
+    pub trial_days_left: Option<i64>,
+    /// Pro features in effect
+    pub features: Vec<String>,
+    /// Times this key has been activated on this machine
+    pub activation_count: u32,
+    /// Why the installed license was rejected
+    pub reason: Option<String>,
+}
+
+impl LicenseState {
+    pub fn from_status(status: &LicenseStatus) -> Self {
+        let free = LicenseState {
+            tier: LicenseTier::Free,
+            email: None,
+            key_hint: None,
+            activated_at: None,
+            expires_at: None,
+            trial_days_left: None,
+            features: Vec::new(),
+            activation_count: 0,
+            reason: None,
+        };
+        match status {
+            LicenseStatus::Free => free,
+            LicenseStatus::Invalid(reason) => LicenseState {
+                tier: LicenseTier::Invalid,
+                reason: Some(reason.clone()),
+                ..free
+            },
+            LicenseStatus::Pro(license) => LicenseState {
+                tier: LicenseTier::Pro,
+                email: Some(license.user_email.clone()),
+                key_hint: Some(mask_license_key(&license.license_key)),
+                activated_at: Some(license.activation_date),
+                expires_at: license.expiration_date,
+                trial_days_left: None,
+                features: PRO_FEATURES.iter().map(|feature| feature.to_string()).collect(),
+                activation_count: activation_count(&license.license_key),
+                reason: None,
+            },
+            LicenseStatus::Trial(trial) => LicenseState {
+                tier: LicenseTier::Trial,
+                activated_at: Some(trial.started_at),
+                expires_at: Some(trial.expires_at),
+                trial_days_left: Some(trial.days_left()),
+                features: PRO_FEATURES.iter().map(|feature| feature.to_string()).collect(),
+                ..free
+            },
+        }
+    }
+}
+
+/// Hide a license key except its last segment, e.g. "PRO-****-****-AbCd1234"
+#[allow(dead_code)]
+pub fn mask_license_key(license_key: &str) -> String {
+    let segments: Vec<&str> = license_key.split('-').collect();
+    match segments.split_last() {
+        Some((last, rest)) if !rest.is_empty() => {
+            let mut masked: Vec<String> = vec![rest[0].to_string()];
+            masked.extend(rest[1..].iter().map(|segment| "*".repeat(segment.len().min(4))));
+            masked.push(last.to_string());
+            masked.join("-")
+        }
+        _ => "*".repeat(license_key.len().min(8)),
+    }
+}
+
+/// The license state of this installation
+#[allow(dead_code)]
+pub fn license_state() -> Result<LicenseState, AppError> {
+    Ok(LicenseState::from_status(&load_license()?))
+}
+
 // Function to display license information
 pub fn display_license_info() -> Result<(), AppError> {
     match load_license()? {
         LicenseStatus::Free => {
             println!("License: Free Version");
+            if load_trial()?.is_some() {
+                println!("Your Pro trial has ended");
+            } else {
+                println!("Try Pro free for {} days: rustloader license trial", TRIAL_DAYS);
+            }
+            println!("Upgrade to Pro: rustloader.com/pro");
+        }
+        LicenseStatus::Trial(trial) => {
+            let days_left = trial.days_left();
+            println!("License: Pro Trial");
+            println!(
+                "{} day{} left (ends {})",
+                days_left,
+                if days_left == 1 { "" } else { "s" },
+                trial.expires_at.with_timezone(&Local).format("%Y-%m-%d")
+            );
             println!("Upgrade to Pro: rustloader.com/pro");
         }
         LicenseStatus::Pro(license) => {
@@ -1,67 +1,184 @@
 // src/main.rs
 
+mod age_gate;
+mod bandwidth;
+mod chapters;
+mod cleanup;
+mod circuit_breaker;
 mod cli;
+mod cloud_share;
+mod completion;
+mod config;
+mod crash_report;
+mod dedupe;
 mod dependency_validator;
+#[cfg(feature = "platform-integration")]
+mod desktop_progress;
 mod downloader;
+mod download_history;
 mod download_manager;
+mod download_map;
+mod environment;
 mod error;
+mod feature_gate;
+mod ffmpeg_args;
+mod ffmpeg_features;
+mod file_actions;
+mod format_picker;
+mod format_selector;
+mod gatekeeper;
+mod guardrails;
+mod history;
+mod http_headers;
+mod init_wizard;
+mod instance_lock;
+mod ipc;
+mod keychain;
 mod license;
+mod media_naming;
+mod metadata_cache;
+mod mux;
+mod notifications;
+mod paths;
+mod player;
+mod playlist;
+mod plugins;
+mod power;
+mod process;
+mod progress_parser;
+mod promo;
+mod provider_health;
+mod proxy;
+mod publish;
+mod quality_compare;
+mod queue_changes;
+mod queue_eta;
+mod queue_events;
+mod queue_list;
+mod queue_order;
+mod queue_repair;
+mod queue_schedule;
+mod queue_undo;
+mod queue_wait;
+mod quota;
+mod recent_urls;
+mod region_overrides;
+mod retention;
 mod security;
+mod silence;
+mod site_stats;
+mod smart_defaults;
+mod snapshot;
+mod split;
+mod state_crypto;
+mod stdout_stream;
+mod templates;
+mod throughput_stats;
+mod trace_file;
+mod transcode;
+mod transliterate;
+mod url_resolver;
 mod utils;
 mod version;
+mod vertical;
+mod video_id;
+#[cfg(feature = "wasm-plugins")]
+mod wasm_plugins;
+mod webhooks;
+mod windows_integration;
 
 // Import modules
+use age_gate::{fallback_strategies, is_age_gated, AgeGateStrategy};
+use bandwidth::{format_rate_limit, get_bandwidth_status, set_bandwidth_override, LIMIT_CHECK_INTERVAL};
+use chapters::{parse_chapter_selection, resolve_chapters};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use cleanup::{discard_file, scan_partial_files, PartialFile};
 use cli::build_cli;
+use completion::{is_queue_busy, CompletionAction};
 use colored::*;
-use dependency_validator::{install_or_update_dependency, validate_dependencies};
-use downloader::download_video_free;
+use config::{load_config, save_config};
+use dedupe::{dedupe_download, deduplicate, load_dedupe_stats, DuplicateHandling, DuplicateNaming};
+use dependency_validator::{install_or_update_dependency, update_ytdlp_once, validate_dependencies};
+use downloader::{default_backend, download_video_free, estimate_download, quota_status, DownloadOutcome, MediaEstimate, StderrTail, YtdlpCommandBuilder};
 use download_manager::{
-    DownloadOptions, DownloadPriority, add_download_to_queue, pause_all_downloads, resume_all_downloads,
-    get_download_queue, get_all_downloads, shutdown_download_manager,
+    DeadlineAction, DownloadOptions, DownloadPriority, add_download_to_queue, enqueue, pause_all_downloads, prepare_download, prepare_playlist,
+    resume_all_downloads,
+    find_saved_download, get_download_queue, get_all_downloads, get_known_downloads, get_remaining_bytes, requeue_download,
+    set_queue_completion_action, should_try_mirror, shutdown_download_manager, wait_for_completion_action,
+    DownloadItem, DownloadStatus, PreparedPlaylist,
 };
 use error::AppError;
+use ffmpeg_args::{check_conflicts, join_args, parse_ffmpeg_args};
+use format_picker::pick_format;
+use format_selector::{AudioQuality, FormatPreferences, VideoCodec};
+use history::{History, HistoryEntry, DEFAULT_HISTORY_LIMIT};
+use http_headers::RequestHeaders;
+use init_wizard::run_init_wizard;
+use instance_lock::{acquire_instance_lock, InstanceKind, InstanceLock};
+use humansize::{format_size, BINARY};
 use license::{activate_license, display_license_info, is_pro_version, LicenseStatus};
 use log::{debug, error, info, warn};
-use rand::Rng;
-use utils::check_for_updates;
+use media_naming::MediaNaming;
+use quality_compare::{fetch_quality_comparison, QualityComparison};
+use queue_eta::{get_queue_eta, QueueEta};
+use queue_events::follow_queue;
+use queue_wait::{wait_for_downloads, WaitTarget};
+use queue_list::{filter_by_status, parse_status, sort_downloads, QueueSort};
+use queue_order::list_order;
+use queue_repair::RepairAction;
+use queue_schedule::{parse_start_time, ScheduleChange, TimeWindow};
+use provider_health::{check_providers, select_canaries, ProviderCheck, ProviderStatus};
+use recent_urls::{remember_url, suggest_urls};
+use region_overrides::RegionOverrides;
+use retention::{parse_tag, GcAction};
+use process::{shell_join, CommandSpec};
+use player::{find_player, playable_file, MIN_PLAYABLE_BYTES, PLAY_FORMAT};
+use publish::{publish_download, LinkKind, PublishRule};
+use site_stats::{aggregate_site_stats, classify_failure, FailureKind, SiteStats};
+use smart_defaults::SmartDefaults;
+use snapshot::{parse_timestamp, take_snapshots, ImageFormat, SnapshotRequest};
+use state_crypto::{encryption_enabled, set_encryption};
+use stdout_stream::{claim_stdout, stream_file, stream_staging_dir, stream_target, validate_stream_options, StreamTarget};
+use templates::{
+    delete_template, get_template, is_job_file, list_templates, load_job_file, save_template_from_download, JobTemplate,
+};
+use transcode::{encode_speed, estimate_transcode_secs, TranscodeKind, CONFIRM_THRESHOLD_SECS};
+use url_resolver::resolve_url;
+use utils::{check_for_updates, download_dir_path, format_duration, format_id_output_path, format_output_path, parse_bitrate, parse_duration, validate_url};
+use vertical::{AspectRatio, VerticalMode, VerticalOptions};
+use windows_integration::{is_protocol_link, parse_protocol_link, register_handlers, unregister_handlers};
 
 // Import env_logger for initialization
 use env_logger::Builder;
 use log::LevelFilter;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 // Logo and version information
 const VERSION: &str = "1.0.0";
 // Remove static IS_PRO flag and replace with dynamic license check
 
-// Only include the startup messages for main.rs since that's all we use here
-struct StartupPromo {
-    messages: Vec<String>,
-}
-
-impl StartupPromo {
-    fn new() -> Self {
-        Self {
-            messages: vec![
-                "🚀 Rustloader Pro offers 4K video downloads and 5X faster speeds! 🚀".to_string(),
-                "💎 Upgrade to Rustloader Pro for AI-powered video upscaling! 💎".to_string(),
-                "🎵 Enjoy high-quality 320kbps MP3 and FLAC with Rustloader Pro! 🎵".to_string(),
-                "🔥 Rustloader Pro removes daily download limits! 🔥".to_string(),
-            ],
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("{}: {}", "Error".red().bold(), e);
+        // Validation errors that can work out a corrected value say what to use instead
+        if let Some(suggestion) = e.suggestion() {
+            eprintln!("{} {}", "Try:".yellow(), suggestion);
         }
-    }
-
-    fn get_random_message(&self) -> &str {
-        let idx = rand::thread_rng().gen_range(0..self.messages.len());
-        &self.messages[idx]
+        std::process::exit(1);
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), AppError> {
+async fn run() -> Result<(), AppError> {
     // Initialize the logger with a custom format
     init_logger();
     
+    // A panic leaves a crash report in the data directory
+    crash_report::install_panic_hook();
+    
     // Log application startup
     info!("Rustloader starting up - version {}", VERSION);
     debug!("Debug logging enabled");
@@ -69,6 +186,227 @@ async fn main() -> Result<(), AppError> {
     // Initialize security module
     security::init();
     
+    // Parse command-line arguments
+    let matches = build_cli().get_matches();
+    
+    // Containers and CI runners get no prompts, colors or notifications
+    if matches.get_flag("non-interactive") {
+        environment::set_non_interactive();
+    }
+    environment::apply_output_defaults();
+    
+    // Spans are recorded until main returns and the guard writes out the rest
+    let _trace_guard = match matches.get_one::<String>("trace-file") {
+        Some(path) => Some(trace_file::start_trace_file(std::path::Path::new(path))?),
+        None => None,
+    };
+    
+    // Shell completion hook: print matching recent URLs, one per line, with no banner
+    if let Some(suggest_matches) = matches.subcommand_matches("suggest-urls") {
+        let prefix = suggest_matches.get_one::<String>("prefix").map(String::as_str).unwrap_or("");
+        for url in suggest_urls(prefix)? {
+            println!("{}", url);
+        }
+        return Ok(());
+    }
+    
+    // Stream queue events for external monitors. This runs before any banner
+    // output, and logs go to stderr, so stdout stays pure NDJSON.
+    if let Some(follow_matches) = matches
+        .subcommand_matches("queue")
+        .and_then(|queue_matches| queue_matches.subcommand_matches("follow"))
+    {
+        let interval = *follow_matches.get_one::<u64>("interval").unwrap();
+        let stdout = std::io::stdout();
+        follow_queue(Duration::from_secs(interval), |event| {
+            let mut out = stdout.lock();
+            writeln!(out, "{}", serde_json::to_string(event)?)?;
+            out.flush()?;
+            Ok(())
+        })
+        .await?;
+        return Ok(());
+    }
+    
+    // Block until downloads finish, for scripts; the exit code reports how they ended
+    if let Some(wait_matches) = matches
+        .subcommand_matches("queue")
+        .and_then(|queue_matches| queue_matches.subcommand_matches("wait"))
+    {
+        let timeout = wait_matches
+            .get_one::<String>("timeout")
+            .map(|value| parse_duration(value))
+            .transpose()?
+            .map(Duration::from_secs);
+        // Unless another instance runs the queue, this process has to
+        let _instance_lock = take_instance_lock();
+        let owner = instance_lock::other_instance().is_none();
+        if owner {
+            get_download_queue().await.load_state().await?;
+        }
+        let target = if wait_matches.get_flag("all") {
+            WaitTarget::All
+        } else {
+            let id = wait_matches.get_one::<String>("id").unwrap();
+            let item = find_saved_download(id)?
+                .ok_or_else(|| AppError::ValidationError(format!("No download found with ID '{}'", id)))?;
+            WaitTarget::Download(item.id)
+        };
+        
+        let outcome = wait_for_downloads(&target, timeout, Duration::from_secs(1), || match &target {
+            WaitTarget::All => Ok(get_all_downloads()),
+            // Finished downloads may have moved to the history archive
+            WaitTarget::Download(_) => get_known_downloads(),
+        })
+        .await?;
+        if owner {
+            shutdown_download_manager().await?;
+        }
+        match &target {
+            WaitTarget::Download(id) => println!("{} {}", id, outcome.as_str()),
+            WaitTarget::All => println!("queue {}", outcome.as_str()),
+        }
+        std::process::exit(outcome.exit_code());
+    }
+    
+    // Details of a single download, kept free of banners so the JSON can be piped
+    if let Some(show_matches) = matches
+        .subcommand_matches("queue")
+        .and_then(|queue_matches| queue_matches.subcommand_matches("show"))
+    {
+        let id = show_matches.get_one::<String>("id").unwrap();
+        let item = find_saved_download(id)?
+            .ok_or_else(|| AppError::ValidationError(format!("No download found with ID '{}'", id)))?;
+        if show_matches.get_flag("json") {
+            println!("{}", serde_json::to_string_pretty(&item)?);
+        } else {
+            print_download_details(&item);
+        }
+        return Ok(());
+    }
+    
+    // Per-site history statistics, also free of banners for --json
+    if let Some(sites_matches) = matches
+        .subcommand_matches("stats")
+        .and_then(|stats_matches| stats_matches.subcommand_matches("sites"))
+    {
+        let stats = aggregate_site_stats(&get_known_downloads()?);
+        if sites_matches.get_flag("json") {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+        } else {
+            print_site_stats(&stats);
+        }
+        return Ok(());
+    }
+    
+    // Quality tiers of one video, free of banners for --json
+    if let Some(compare_matches) = matches.subcommand_matches("compare") {
+        let url = compare_matches.get_one::<String>("url").expect("required argument");
+        validate_url(url)?;
+        let proxy = load_config()?.proxy_template()?.map(|proxy| proxy.render(&proxy::new_isolation_key()));
+        let request_headers = RequestHeaders::default().with_proxy(proxy);
+        let comparison = fetch_quality_comparison(url, &request_headers).await?;
+        if compare_matches.get_flag("json") {
+            println!("{}", serde_json::to_string_pretty(&comparison)?);
+        } else {
+            print_quality_comparison(&comparison);
+        }
+        return Ok(());
+    }
+    
+    // Canary extractions per site, free of banners for --json
+    if let Some(status_matches) = matches
+        .subcommand_matches("providers")
+        .and_then(|providers_matches| providers_matches.subcommand_matches("status"))
+    {
+        let sites: Vec<String> = status_matches
+            .get_many::<String>("site")
+            .map(|sites| sites.cloned().collect())
+            .unwrap_or_default();
+        let canaries = select_canaries(&sites)?;
+        let proxy = load_config()?.proxy_template()?.map(|proxy| proxy.render(&proxy::new_isolation_key()));
+        let request_headers = RequestHeaders::default().with_proxy(proxy);
+        let json = status_matches.get_flag("json");
+        if !json {
+            println!("{}", format!("Checking {} sites with the installed yt-dlp...", canaries.len()).blue());
+        }
+        let checks = check_providers(&canaries, &request_headers).await?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&checks)?);
+        } else {
+            print_provider_checks(&checks);
+        }
+        return Ok(());
+    }
+    
+    // Paths in use, free of banners so they can be used in scripts
+    if let Some(paths_matches) = matches.subcommand_matches("paths") {
+        let paths = paths_in_use()?;
+        if paths_matches.get_flag("json") {
+            let map: serde_json::Map<String, serde_json::Value> = paths
+                .iter()
+                .map(|(name, path)| (name.to_string(), path.to_string_lossy().into_owned().into()))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&map)?);
+        } else {
+            print_paths(&paths);
+        }
+        return Ok(());
+    }
+    
+    // Daily download limit, free of banners for --json
+    if let Some(limits_matches) = matches.subcommand_matches("limits") {
+        let quota = quota_status()?;
+        if limits_matches.get_flag("json") {
+            println!("{}", serde_json::to_string_pretty(&quota)?);
+        } else {
+            let line = quota.describe(chrono::Local::now());
+            if quota.remaining == 0 {
+                println!("{}", line.yellow());
+                println!("{}", "🚀 Upgrade to Rustloader Pro for unlimited downloads: rustloader.com/pro 🚀".bright_yellow());
+            } else {
+                println!("{}", line.green());
+            }
+        }
+        return Ok(());
+    }
+
+    // Finished downloads from the history database, free of banners for --json
+    if let Some(history_matches) = matches.subcommand_matches("history") {
+        let history = History::open_default()?;
+        match history_matches.subcommand() {
+            Some((name @ ("list" | "search"), list_matches)) => {
+                let limit = list_matches.get_one::<usize>("limit").copied().unwrap_or(DEFAULT_HISTORY_LIMIT);
+                let entries = if name == "search" {
+                    history.search(list_matches.get_one::<String>("query").expect("required argument"), limit)?
+                } else {
+                    history.list(limit)?
+                };
+                if list_matches.get_flag("json") {
+                    println!("{}", serde_json::to_string_pretty(&entries)?);
+                } else {
+                    print_history(&entries, history.count()?);
+                }
+            }
+            Some(("clear", clear_matches)) => {
+                let older_than = clear_matches.get_one::<u32>("older-than").copied();
+                let prompt = match older_than {
+                    Some(days) => format!("Delete history entries older than {} days?", days),
+                    None => format!("Delete all {} history entries?", history.count()?),
+                };
+                if !clear_matches.get_flag("yes") && !confirm(&prompt, false)? {
+                    println!("{}", "History left as it was.".blue());
+                    return Ok(());
+                }
+                let before = older_than.map(|days| chrono::Utc::now() - chrono::Duration::days(days as i64));
+                let deleted = history.clear(before)?;
+                println!("{}", format!("Deleted {} history entries. Downloaded files were kept.", deleted).green());
+            }
+            _ => unreachable!("history requires a subcommand"),
+        }
+        return Ok(());
+    }
+
     // Display logo and welcome message
     print_logo();
 
@@ -96,11 +434,157 @@ async fn main() -> Result<(), AppError> {
         println!("{}", "Rustloader - Video Downloader".bright_cyan().bold());
         println!("{}", format!("Version: {} (Free)", VERSION).cyan());
 
-        // Display a promotional message for the free version
-        let promo = StartupPromo::new();
-        let message = promo.get_random_message();
-        debug!("Selected promotional message: {}", message);
-        println!("\n{}\n", message.bright_yellow());
+        // Display a promotional message for the free version, within the frequency cap
+        if let Some(message) = promo::PromoService::load().message(promo::PromoSlot::Startup) {
+            debug!("Selected promotional message: {}", message);
+            println!("\n{}\n", message.bright_yellow());
+        }
+    }
+
+    // Options only Pro offers are reported together, before any work starts
+    check_pro_options(&feature_gate::RequestedFeatures::from_matches(&matches), is_pro)?;
+
+    // The setup wizard handles dependencies itself
+    if matches.subcommand_matches("init").is_some() {
+        return run_init_wizard();
+    }
+
+    if let Some(dedupe_matches) = matches.subcommand_matches("dedupe") {
+        if let Some(mode_matches) = dedupe_matches.subcommand_matches("mode") {
+            let handling = match mode_matches.get_one::<String>("mode").map(String::as_str) {
+                Some("ask") => DuplicateHandling::Ask,
+                Some("hardlink") => DuplicateHandling::Hardlink,
+                _ => DuplicateHandling::Off,
+            };
+            let mut config = load_config().unwrap_or_default();
+            config.duplicate_handling = handling;
+            save_config(&config)?;
+            println!("{} {}", "Duplicate handling set to".green(), handling.as_str());
+        } else if let Some(naming_matches) = dedupe_matches.subcommand_matches("naming") {
+            let naming = DuplicateNaming::parse(naming_matches.get_one::<String>("naming").unwrap())?;
+            let mut config = load_config().unwrap_or_default();
+            config.duplicate_naming = naming;
+            save_config(&config)?;
+            println!("{} {}", "Duplicate file naming set to".green(), naming.as_str());
+        } else {
+            let config = load_config().unwrap_or_default();
+            let stats = load_dedupe_stats()?;
+            println!("{}", "Duplicate Detection:".bright_cyan().bold());
+            println!("Mode:         {}", config.duplicate_handling.as_str());
+            println!("Naming:       {}", config.duplicate_naming.as_str());
+            println!("Files linked: {}", stats.files_linked);
+            println!("Space saved:  {}", format_size(stats.bytes_saved, BINARY));
+        }
+        return Ok(());
+    }
+
+    if matches.subcommand_matches("plugins").is_some() {
+        let root = plugins::plugins_dir()?;
+        let found = plugins::discover_plugins(&root);
+        #[cfg(feature = "wasm-plugins")]
+        let wasm_found = wasm_plugins::discover_wasm_plugins(&root);
+        #[cfg(feature = "wasm-plugins")]
+        let nothing_installed = found.is_empty() && wasm_found.is_empty();
+        #[cfg(not(feature = "wasm-plugins"))]
+        let nothing_installed = found.is_empty();
+        if nothing_installed {
+            println!("{} {}", "No plugins installed in".blue(), root.display());
+            return Ok(());
+        }
+        println!("{}", "Plugins:".bright_cyan().bold());
+        for (path, plugin) in found {
+            match plugin {
+                Ok(plugin) => {
+                    let capabilities: Vec<&str> =
+                        plugin.manifest.capabilities.iter().map(|capability| capability.as_str()).collect();
+                    println!(
+                        "  {} {} ({})",
+                        plugin.manifest.name.green(),
+                        plugin.manifest.version,
+                        capabilities.join(", ")
+                    );
+                }
+                Err(e) => println!("  {} {}", path.display().to_string().red(), e),
+            }
+        }
+        #[cfg(feature = "wasm-plugins")]
+        for (path, plugin) in wasm_found {
+            match plugin {
+                Ok(plugin) => {
+                    let hooks: Vec<&str> = plugin.manifest.hooks.iter().map(|hook| hook.as_str()).collect();
+                    println!(
+                        "  {} {} (wasm: {})",
+                        plugin.manifest.name.green(),
+                        plugin.manifest.version,
+                        hooks.join(", ")
+                    );
+                }
+                Err(e) => println!("  {} {}", path.display().to_string().red(), e),
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(publish_matches) = matches.subcommand_matches("publish") {
+        let mut config = load_config().unwrap_or_default();
+        if let Some(add_matches) = publish_matches.subcommand_matches("add") {
+            let rule = PublishRule {
+                format: add_matches.get_one::<String>("format").cloned(),
+                url_contains: add_matches.get_one::<String>("url-contains").cloned(),
+                targets: add_matches.get_many::<String>("target").unwrap().cloned().collect(),
+                link: if add_matches.get_flag("symlink") { LinkKind::Symlink } else { LinkKind::Hardlink },
+            };
+            println!("{} {}", "Publish rule added:".green(), rule.describe());
+            config.publish_rules.push(rule);
+            save_config(&config)?;
+        } else if let Some(remove_matches) = publish_matches.subcommand_matches("remove") {
+            let number = *remove_matches.get_one::<usize>("number").unwrap();
+            if number == 0 || number > config.publish_rules.len() {
+                return Err(AppError::ValidationError(format!("No publish rule number {}", number)));
+            }
+            let rule = config.publish_rules.remove(number - 1);
+            save_config(&config)?;
+            println!("{} {}", "Publish rule removed:".green(), rule.describe());
+        } else if config.publish_rules.is_empty() {
+            println!("{}", "No publish rules. Add one with 'rustloader publish add <folder>'.".blue());
+        } else {
+            println!("{}", "Publish Rules:".bright_cyan().bold());
+            for (index, rule) in config.publish_rules.iter().enumerate() {
+                println!("  {}. {}", index + 1, rule.describe());
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(service_matches) = matches.subcommand_matches("service") {
+        if service_matches.subcommand_matches("install").is_some() {
+            register_handlers()?;
+            println!("{}", "rustloader:// links and .rustloader job files now open with Rustloader.".green());
+        } else {
+            unregister_handlers()?;
+            println!("{}", "Link and job file registration removed.".green());
+        }
+        return Ok(());
+    }
+
+    if let Some(encrypt_matches) = matches.subcommand_matches("encrypt-state") {
+        match encrypt_matches.get_one::<String>("mode").map(String::as_str) {
+            Some(mode) => {
+                let enabled = mode == "on";
+                let _instance_lock = take_instance_lock();
+                let rewritten = set_encryption(enabled)?;
+                println!(
+                    "{} ({} file(s) rewritten)",
+                    if enabled { "State encryption enabled".green() } else { "State encryption disabled".green() },
+                    rewritten
+                );
+            }
+            None => println!(
+                "State encryption is {}",
+                if encryption_enabled() { "on".green() } else { "off".yellow() }
+            ),
+        }
+        return Ok(());
     }
 
     // Perform enhanced dependency validation
@@ -120,10 +604,7 @@ async fn main() -> Result<(), AppError> {
                 if !info.is_min_version || info.is_vulnerable {
                     has_issues = true;
                     println!("{}", "yt-dlp needs to be updated.".yellow());
-                    println!("Would you like to update yt-dlp now? (y/n):");
-                    let mut input = String::new();
-                    std::io::stdin().read_line(&mut input)?;
-                    if input.trim().eq_ignore_ascii_case("y") {
+                    if confirm("Would you like to update yt-dlp now?", false)? {
                         install_or_update_dependency("yt-dlp")?;
                     } else {
                         println!("{}", "Continuing with the current version. Some features may not work correctly.".yellow());
@@ -131,13 +612,8 @@ async fn main() -> Result<(), AppError> {
                 }
             } else {
                 has_issues = true;
-                println!(
-                    "{}",
-                    "yt-dlp is not installed. Would you like to install it now? (y/n):".yellow()
-                );
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
-                if input.trim().eq_ignore_ascii_case("y") {
+                // Nothing can be downloaded without it, so install unattended too
+                if confirm("yt-dlp is not installed. Would you like to install it now?", true)? {
                     install_or_update_dependency("yt-dlp")?;
                 } else {
                     println!(
@@ -155,10 +631,7 @@ async fn main() -> Result<(), AppError> {
                 if !info.is_min_version || info.is_vulnerable {
                     has_issues = true;
                     println!("{}", "ffmpeg needs to be updated.".yellow());
-                    println!("Would you like to attempt to update ffmpeg now? (y/n):");
-                    let mut input = String::new();
-                    std::io::stdin().read_line(&mut input)?;
-                    if input.trim().eq_ignore_ascii_case("y") {
+                    if confirm("Would you like to attempt to update ffmpeg now?", false)? {
                         install_or_update_dependency("ffmpeg")?;
                     } else {
                         println!("{}", "Continuing with the current version. Some features may not work correctly.".yellow());
@@ -171,10 +644,7 @@ async fn main() -> Result<(), AppError> {
                     "{}",
                     "ffmpeg was not detected. Some features may not work properly.".yellow()
                 );
-                println!("{}", "Attempting to continue without verified ffmpeg. Do you want to try to install it? (y/n):".yellow());
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
-                if input.trim().eq_ignore_ascii_case("y") {
+                if confirm("Attempting to continue without verified ffmpeg. Do you want to try to install it?", false)? {
                     match install_or_update_dependency("ffmpeg") {
                         Ok(_) => println!("{}", "ffmpeg installed successfully.".green()),
                         Err(e) => println!(
@@ -197,10 +667,7 @@ async fn main() -> Result<(), AppError> {
         }
         Err(e) => {
             println!("{}: {}", "Dependency validation had issues".yellow(), e);
-            println!("Would you like to continue anyway? (y/n):");
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
+            if !confirm("Would you like to continue anyway?", true)? {
                 return Err(e);
             } else {
                 println!(
@@ -211,14 +678,16 @@ async fn main() -> Result<(), AppError> {
         }
     }
 
-    // Parse command-line arguments
-    let matches = build_cli().get_matches();
-
     // Check for license activation command
     if let Some(key) = matches.get_one::<String>("activate-license") {
         println!("{}", "License activation process started...".blue());
 
         // Get email for activation
+        if !environment::is_interactive() {
+            return Err(AppError::ValidationError(
+                "License activation asks for your email address; run it from a terminal".to_string(),
+            ));
+        }
         println!("Please enter your email address:");
         let mut email = String::new();
         std::io::stdin().read_line(&mut email)?;
@@ -264,37 +733,72 @@ async fn main() -> Result<(), AppError> {
         return display_license_info();
     }
 
+    if let Some(license_matches) = matches.subcommand_matches("license") {
+        if license_matches.subcommand_matches("trial").is_some() {
+            let trial = license::start_trial()?;
+            println!("{}", "Pro trial started!".green());
+            println!(
+                "All Pro features are unlocked for {} days, until {}.",
+                license::TRIAL_DAYS,
+                trial.expires_at.with_timezone(&chrono::Local).format("%Y-%m-%d")
+            );
+            println!("\nPlease restart Rustloader to use Pro features.");
+            return Ok(());
+        }
+        return display_license_info();
+    }
+
+    // Only one process may write the queue state; others run read-only.
+    // The commands above only read it, so they take no lock. This one is
+    // held until main returns.
+    let _instance_lock = take_instance_lock();
+    
     // Initialize download manager
     info!("Initializing download manager");
     let download_queue = get_download_queue().await;
 
-    // Register a shutdown handler for the download manager
-    let original_hook = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |panic_info| {
-        let _ = tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(shutdown_download_manager());
-        original_hook(panic_info);
-    }));
-
     // Handle queue-related commands
     if let Some(queue_matches) = matches.subcommand_matches("queue") {
         // Handle queue subcommands
-        if queue_matches.subcommand_matches("list").is_some() {
-            // List all downloads in the queue
-            let downloads = get_all_downloads();
+        if let Some(list_matches) = queue_matches.subcommand_matches("list") {
+            // List downloads in the queue, optionally filtered and sorted
+            let statuses = list_matches
+                .get_many::<String>("status")
+                .map(|values| values.map(|value| parse_status(value)).collect::<Result<Vec<_>, _>>())
+                .transpose()?
+                .unwrap_or_default();
+            let mut downloads = filter_by_status(get_all_downloads(), &statuses);
+            match list_matches.get_one::<String>("sort") {
+                Some(sort) => sort_downloads(&mut downloads, QueueSort::parse(sort)?),
+                // By default, the order the queue will actually run them in
+                None => downloads.sort_by(list_order),
+            }
+            
             if downloads.is_empty() {
-                println!("{}", "No downloads in queue.".blue());
+                if statuses.is_empty() {
+                    println!("{}", "No downloads in queue.".blue());
+                } else {
+                    println!("{}", "No downloads with that status.".blue());
+                }
             } else {
                 println!("{}", "Download Queue:".bright_cyan().bold());
-                println!("{}", "-".repeat(80));
-                println!("{:<10} {:<20} {:<12} {:<10} {:<12} {:<15}", 
-                    "ID", "Title", "Status", "Progress", "Priority", "Added");
-                println!("{}", "-".repeat(80));
+                println!("{}", "-".repeat(112));
+                println!("{:<10} {:<20} {:<12} {:<10} {:<12} {:<12} {:<9} {:<9} {:<15}", 
+                    "ID", "Title", "Status", "Progress", "Size", "Priority", "Elapsed", "ETA", "Added");
+                println!("{}", "-".repeat(112));
                 
                 let download_count = downloads.len();
+                let remaining_bytes = get_remaining_bytes();
+                let queue_eta = get_queue_eta();
+                let now = chrono::Utc::now();
+                // Queued downloads are numbered in the order they will start
+                let number_queued = list_matches.get_one::<String>("sort").is_none();
+                let mut queued_position = 0;
                 
                 for dl in downloads {
+                    let elapsed_display = queue_list::elapsed(&dl, now)
+                        .map(|elapsed| format_duration(elapsed.as_secs()))
+                        .unwrap_or_else(|| "-".to_string());
                     let title = dl.title.unwrap_or(format!("URL: {}", dl.url));
                     let title_display = if title.len() > 18 { 
                         format!("{}...", &title[0..15]) 
@@ -303,18 +807,61 @@ async fn main() -> Result<(), AppError> {
                     };
                     
                     let id_short = &dl.id[0..8];
-                    println!("{:<10} {:<20} {:<12} {:<10} {:<12} {:<15}",
+                    let size_display = if dl.total_bytes > 0 {
+                        format_size(dl.total_bytes, BINARY)
+                    } else {
+                        "-".to_string()
+                    };
+                    let eta_display = queue_eta
+                        .eta(&dl.id)
+                        .map(|eta| format_duration(eta.as_secs()))
+                        .unwrap_or_else(|| "-".to_string());
+                    let status_display = if number_queued && dl.status == DownloadStatus::Queued {
+                        queued_position += 1;
+                        format!("Queued #{}", queued_position)
+                    } else if dl.streaming && dl.status == DownloadStatus::Downloading {
+                        "Streaming".to_string()
+                    } else {
+                        format!("{:?}", dl.status)
+                    };
+                    println!("{:<10} {:<20} {:<12} {:<10} {:<12} {:<12} {:<9} {:<9} {:<15}",
                         id_short,
                         title_display,
-                        format!("{:?}", dl.status),
+                        status_display,
                         format!("{:.1}%", dl.progress),
+                        size_display,
                         format!("{:?}", dl.priority),
+                        elapsed_display,
+                        eta_display,
                         dl.added_at.format("%Y-%m-%d %H:%M").to_string()
                     );
                 }
-                println!("{}", "-".repeat(80));
+                println!("{}", "-".repeat(112));
                 println!("Total Downloads: {}", download_count);
+                if remaining_bytes > 0 {
+                    println!("Remaining: ~{}", format_size(remaining_bytes, BINARY));
+                }
+                print_queue_finish(&queue_eta);
+            }
+            return Ok(());
+        } else if queue_matches.subcommand_matches("stats").is_some() {
+            let downloads = get_all_downloads();
+            let count = |status: DownloadStatus| downloads.iter().filter(|dl| dl.status == status).count();
+            let queue_eta = get_queue_eta();
+
+            println!("{}", "Queue Statistics:".bright_cyan().bold());
+            println!("Downloading: {}", count(DownloadStatus::Downloading));
+            println!("Queued:      {}", count(DownloadStatus::Queued));
+            println!("Paused:      {}", count(DownloadStatus::Paused));
+            println!("Completed:   {}", count(DownloadStatus::Completed));
+            println!("Failed:      {}", count(DownloadStatus::Failed));
+            println!("Canceled:    {}", count(DownloadStatus::Canceled));
+            println!("Remaining:   ~{}", format_size(get_remaining_bytes(), BINARY));
+            match queue_eta.throughput {
+                Some(rate) => println!("Throughput:  ~{}/s", format_size(rate as u64, BINARY)),
+                None => println!("Throughput:  unknown"),
             }
+            print_queue_finish(&queue_eta);
             return Ok(());
         } else if queue_matches.subcommand_matches("pause-all").is_some() {
             // Pause all active downloads
@@ -412,6 +959,42 @@ async fn main() -> Result<(), AppError> {
                 }
             }
             return Ok(());
+        } else if let Some(schedule_matches) = queue_matches.subcommand_matches("schedule") {
+            // Hold a download until its start time or window
+            let id = schedule_matches.get_one::<String>("id").unwrap();
+            let now = chrono::Local::now();
+            let change = ScheduleChange {
+                at: schedule_matches
+                    .get_one::<String>("at")
+                    .map(|value| parse_start_time(value, &now))
+                    .transpose()?,
+                window: schedule_matches
+                    .get_one::<String>("window")
+                    .map(|value| TimeWindow::parse(value))
+                    .transpose()?,
+                clear: schedule_matches.get_flag("clear"),
+            };
+            info!("Scheduling download {}: {:?}", id, change);
+            
+            match download_manager::schedule_download(id, change).await {
+                Ok(_) => {
+                    if change.clear {
+                        println!("{}", format!("Download {} may start at any time.", id).green());
+                    }
+                    if let Some(at) = change.at {
+                        let at = at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+                        println!("{}", format!("Download {} starts no earlier than {}.", id, at).green());
+                    }
+                    if let Some(window) = change.window {
+                        println!("{}", format!("Download {} only starts between {} each day.", id, window).green());
+                    }
+                },
+                Err(e) => {
+                    println!("{}: {}", "Error scheduling download".red(), e);
+                    return Err(e);
+                }
+            }
+            return Ok(());
         } else if queue_matches.subcommand_matches("clear-completed").is_some() {
             // Clear completed downloads
             info!("Clearing completed downloads");
@@ -440,117 +1023,922 @@ async fn main() -> Result<(), AppError> {
                 }
             }
             return Ok(());
-        }
-    }
-    
-    // Handle download subcommand or direct URL (backward compatibility)
-    let download_matches = matches.subcommand_matches("download");
-    
-    // Determine URL and options from either download subcommand or direct args
-    let (url, quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, priority) =
-        if let Some(dl_matches) = download_matches {
-            // Get options from download subcommand
-            let url = dl_matches.get_one::<String>("url").unwrap();
-            let quality = dl_matches.get_one::<String>("quality").map(|q| q.as_str());
-            let format = dl_matches
-                .get_one::<String>("format")
-                .map(|f| f.as_str())
-                .unwrap_or("mp4");
-            let start_time = dl_matches.get_one::<String>("start-time");
-            let end_time = dl_matches.get_one::<String>("end-time");
-            let use_playlist = dl_matches.get_flag("playlist");
-            let download_subtitles = dl_matches.get_flag("subtitles");
-            let output_dir = dl_matches.get_one::<String>("output-dir");
+        } else if let Some(undo_matches) = queue_matches.subcommand_matches("undo") {
+            if undo_matches.get_flag("list") {
+                let history = download_manager::get_undo_history().await?;
+                if history.is_empty() {
+                    println!("{}", "Nothing to undo.".blue());
+                } else {
+                    println!("{}", "Operations that can be undone (newest first):".bright_cyan().bold());
+                    for snapshot in history.iter().rev() {
+                        println!("  {}", snapshot.describe());
+                    }
+                }
+                return Ok(());
+            }
             
-            // Only allow force download in development mode
-            let force_download = if cfg!(debug_assertions) {
-                dl_matches.get_flag("force")
-            } else {
-                false
+            match download_manager::undo_queue_operation().await? {
+                Some(snapshot) => println!("{} {}", "Undoing".green(), snapshot.describe()),
+                None => println!("{}", "Nothing to undo.".blue()),
+            }
+            return Ok(());
+        } else if let Some(archive_matches) = queue_matches.subcommand_matches("archive") {
+            let days = match archive_matches.get_one::<u32>("older-than") {
+                Some(days) => *days,
+                None => match load_config()?.archive_after() {
+                    Some(age) => age.num_days() as u32,
+                    None => {
+                        println!("Archiving is turned off (queue_archive_after_days is 0); pass --older-than to archive anyway.");
+                        return Ok(());
+                    }
+                },
             };
-            
-            let bitrate = dl_matches.get_one::<String>("video-bitrate");
-            let use_queue = dl_matches.get_flag("add-to-queue");
-            
-            // Parse priority
-            let default_priority = String::from("normal");
-            let priority_str = dl_matches.get_one::<String>("priority").unwrap_or(&default_priority).as_str();
-            let priority = match priority_str {
-                "low" => DownloadPriority::Low,
-                "normal" => DownloadPriority::Normal,
-                "high" => DownloadPriority::High,
-                "critical" => DownloadPriority::Critical,
-                _ => DownloadPriority::Normal,
+            let archived = download_queue.archive_finished(chrono::Duration::days(days as i64)).await?;
+            if archived == 0 {
+                println!("{}", format!("No downloads finished more than {} days ago.", days).blue());
+            } else {
+                println!(
+                    "{}",
+                    format!("Moved {} finished download{} to the history archive.", archived, if archived == 1 { "" } else { "s" }).green()
+                );
+            }
+            return Ok(());
+        } else if queue_matches.subcommand_matches("repair").is_some() {
+            let repairs = download_queue.repair_stuck().await?;
+            if repairs.is_empty() {
+                println!("{}", "No stuck downloads found.".blue());
+                return Ok(());
+            }
+            for repair in &repairs {
+                println!("  {}", repair.describe());
+            }
+            let requeued = repairs.iter().filter(|repair| repair.action == RepairAction::Requeue).count();
+            println!(
+                "{}",
+                format!("Repaired {} download(s): {} queued again, {} failed.", repairs.len(), requeued, repairs.len() - requeued).green()
+            );
+            return Ok(());
+        } else if let Some(run_matches) = queue_matches.subcommand_matches("run") {
+            // Keep the process alive so the queue worker can run the downloads
+            instance_lock::ensure_queue_owner()?;
+            download_queue.load_state().await?;
+            let until_empty = run_matches.get_flag("until-empty");
+            let when_done = match run_matches.get_many::<String>("when-done") {
+                Some(values) => {
+                    let values: Vec<&str> = values.map(String::as_str).collect();
+                    Some(CompletionAction::parse(&values)?)
+                }
+                None => None,
             };
+            if let Some(action) = &when_done {
+                println!("{}", format!("When the queue finishes: {}", action.describe()).blue());
+            }
+            set_queue_completion_action(when_done.clone()).await?;
+            println!("{}", "Processing the download queue. Stop with Ctrl+C.".blue());
             
-            (url, quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, Some(priority))
-        } else {
-            // Get options from direct arguments (backward compatibility)
-            let url = matches.get_one::<String>("url").unwrap();
-            let quality = matches.get_one::<String>("quality").map(|q| q.as_str());
-            let format = matches
-                .get_one::<String>("format")
-                .map(|f| f.as_str())
-                .unwrap_or("mp4");
-            let start_time = matches.get_one::<String>("start-time");
-            let end_time = matches.get_one::<String>("end-time");
-            let use_playlist = matches.get_flag("playlist");
-            let download_subtitles = matches.get_flag("subtitles");
-            let output_dir = matches.get_one::<String>("output-dir");
+            let mut had_downloads = false;
+            loop {
+                tokio::select! {
+                    _ = shutdown_signal() => {
+                        info!("Stopping the queue runner");
+                        break;
+                    }
+                    _ = tokio::time::sleep(Duration::from_secs(2)) => {}
+                }
+                // Downloads paused until a resume time still count as work left
+                let pending = is_queue_busy(&get_all_downloads());
+                had_downloads |= pending;
+                if until_empty && !pending {
+                    break;
+                }
+            }
             
-            // Only allow force download in development mode
-            let force_download = if cfg!(debug_assertions) {
-                let is_forced = matches.get_flag("force");
-                if is_forced {
-                    warn!("Development mode force flag enabled - daily limits will be bypassed");
-                    debug!("Force flag should only be used in development environments");
-                    println!(
-                        "{}",
-                        "⚠️ WARNING: Development mode force flag enabled! Daily limits bypassed. ⚠️"
-                            .bright_red()
-                    );
-                    println!(
-                        "{}",
-                        "This flag should never be used in production environments.".bright_red()
-                    );
+            // Give the completion action a chance to run before exiting
+            if when_done.is_some() && had_downloads {
+                wait_for_completion_action(Duration::from_secs(60)).await;
+            }
+            shutdown_download_manager().await?;
+            let failed = get_all_downloads().iter().filter(|item| item.is_failed()).count();
+            if failed > 0 {
+                println!("{}", format!("{} download(s) failed; see 'rustloader queue list'.", failed).yellow());
+            }
+            return Ok(());
+        } else if let Some(bandwidth_matches) = queue_matches.subcommand_matches("bandwidth") {
+            // Override the bandwidth limit or show the active profile
+            if let Some(limit) = bandwidth_matches.get_one::<String>("limit") {
+                info!("Setting bandwidth override: {}", limit);
+                match set_bandwidth_override(Some(limit)) {
+                    Ok(_) => {
+                        if limit.eq_ignore_ascii_case("auto") {
+                            println!("{}", "Bandwidth override cleared. Following configured profiles.".green());
+                        } else {
+                            println!("{}", format!("Bandwidth limit set to {}.", limit).green());
+                            println!("Running downloads switch to it within {} seconds.", LIMIT_CHECK_INTERVAL.as_secs());
+                        }
+                    },
+                    Err(e) => {
+                        println!("{}: {}", "Error setting bandwidth limit".red(), e);
+                        return Err(e);
+                    }
                 }
-                is_forced
             } else {
-                false
-            };
-            
-            let bitrate = matches.get_one::<String>("video-bitrate");
-            
-            // Default to direct download for backward compatibility
-            let use_queue = false;
-            let priority = None; // Use default priority
-            
-            (url, quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, priority)
-        };
-
-    // Check for update results
-    if let Ok(Ok(true)) = update_check.await {
-        info!("Update check completed: new version available");
-        println!(
-            "{}",
-            "A new version of Rustloader is available! Visit rustloader.com to upgrade."
-                .bright_yellow()
-        );
-    } else {
-        debug!("No updates available or update check failed");
+                let status = get_bandwidth_status()?;
+                println!("{}", "Bandwidth:".bright_cyan().bold());
+                println!("Profiles configured: {}", status.profile_count);
+                println!("Active profile: {}", status.active_profile.unwrap_or_else(|| "none".to_string()));
+                if let Some(override_limit) = status.override_limit {
+                    println!("Override: {}", override_limit);
+                }
+                match status.current_limit {
+                    Some(limit) => println!("Current limit: {}/s", format_rate_limit(limit)),
+                    None => println!("Current limit: unlimited"),
+                }
+                println!("Limits are applied when a download starts.");
+            }
+            return Ok(());
+        }
     }
-
-    // Process the download
-    info!("Starting download process for URL: {}", url);
-    debug!("Download parameters: quality={:?}, format={}, start_time={:?}, end_time={:?}, playlist={}, subtitles={}, output_dir={:?}, force={}, bitrate={:?}, use_queue={}, priority={:?}",
-           quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, priority);
     
-    if use_queue {
-        // Add to download queue instead of downloading immediately
-        info!("Adding download to queue: {}", url);
-        let download_options = DownloadOptions {
-            url,
+    // Retention policies, applied now instead of by the hourly maintenance
+    if let Some(gc_matches) = matches.subcommand_matches("gc") {
+        let dry_run = gc_matches.get_flag("dry-run");
+        let expired = download_queue.collect_garbage(dry_run).await?;
+        if expired.is_empty() {
+            println!("{}", "Nothing has expired.".green());
+            return Ok(());
+        }
+        println!("{}", if dry_run { "Retention policies would:" } else { "Retention policies applied:" }.bright_cyan().bold());
+        for item in &expired {
+            println!("  {}", item.describe());
+        }
+        let files = expired.iter().filter(|item| item.action == GcAction::DeleteFile);
+        let bytes: u64 = files.clone().map(|item| item.bytes).sum();
+        let summary = format!(
+            "{} file(s) ({}) and {} queue entries",
+            files.count(),
+            format_size(bytes, BINARY),
+            expired.iter().filter(|item| item.action == GcAction::RemoveEntry).count()
+        );
+        if dry_run {
+            println!("{}", format!("Would delete {}. Run without --dry-run to apply.", summary).yellow());
+        } else {
+            println!("{}", format!("Deleted {}.", summary).green());
+        }
+        return Ok(());
+    }
+    
+    // Play a video while it downloads, pointing a player at the growing file
+    if let Some(play_matches) = matches.subcommand_matches("play") {
+        let url = play_matches.get_one::<String>("url").expect("required argument");
+        let configured_player = load_config()?.player;
+        let player = find_player(
+            play_matches
+                .get_one::<String>("player")
+                .map(String::as_str)
+                .or(configured_player.as_deref()),
+        )?;
+        instance_lock::ensure_queue_owner()?;
+        download_queue.load_state().await?;
+        let url = resolve_url(url).await;
+        let mut prepared = prepare_download(DownloadOptions {
+            url: &url,
+            format_id: Some(PLAY_FORMAT),
+            priority: Some(DownloadPriority::Critical),
+            ..DownloadOptions::default()
+        })
+        .await?;
+        if let Some(conflict) = prepared.conflict.take() {
+            return Err(AppError::ValidationError(conflict));
+        }
+        prepared.item.streaming = true;
+        if let Some(title) = &prepared.item.title {
+            println!("Title:       {}", title);
+        }
+        let id = enqueue(prepared).await?;
+        let find = |id: &str| download_queue.get_download(id.to_string());
+        
+        println!("{}", format!("Buffering {} before opening {}...", format_size(MIN_PLAYABLE_BYTES, BINARY), player.display()).blue());
+        let file = loop {
+            tokio::select! {
+                _ = shutdown_signal() => {
+                    shutdown_download_manager().await?;
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+            }
+            let item = find(&id).ok_or_else(|| AppError::General("The download is no longer in the queue".to_string()))?;
+            if item.is_failed() || item.is_canceled() {
+                shutdown_download_manager().await?;
+                return Err(AppError::DownloadError(
+                    item.error_message.unwrap_or_else(|| "The download stopped before it could be played".to_string()),
+                ));
+            }
+            if let Some(file) = playable_file(&item) {
+                break file;
+            }
+        };
+        
+        let mut child = CommandSpec::player(&player)
+            .operand(file.to_string_lossy())
+            .to_command()?
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map_err(|e| AppError::General(format!("Could not start {}: {}", player.display(), e)))?;
+        println!("{} {}", "Playing".green(), file.display());
+        
+        // Keep downloading while the player runs; the download finishes
+        // even if the player is closed first
+        let mut player_running = true;
+        let mut download_running = true;
+        while player_running || download_running {
+            tokio::select! {
+                _ = shutdown_signal() => break,
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+            }
+            if player_running && !matches!(child.try_wait(), Ok(None)) {
+                player_running = false;
+                if download_running {
+                    println!("{}", "Player closed; the download continues. Stop with Ctrl+C.".blue());
+                }
+            }
+            if download_running {
+                match find(&id) {
+                    Some(item) if !item.is_finished() => {}
+                    Some(item) if item.is_completed() => {
+                        download_running = false;
+                        println!("{} {}", "Download completed:".green(), item.output_path.as_deref().unwrap_or("-"));
+                    }
+                    Some(item) => {
+                        download_running = false;
+                        println!("{}: {}", "Download failed".red(), item.error_message.as_deref().unwrap_or("unknown error"));
+                    }
+                    None => download_running = false,
+                }
+            }
+        }
+        shutdown_download_manager().await?;
+        return Ok(());
+    }
+    
+    // Handle template management commands
+    if let Some(template_matches) = matches.subcommand_matches("template") {
+        if template_matches.subcommand_matches("list").is_some() {
+            let templates = list_templates()?;
+            if templates.is_empty() {
+                println!("{}", "No templates saved.".blue());
+            } else {
+                println!("{}", "Job Templates:".bright_cyan().bold());
+                for (name, template) in templates {
+                    println!(
+                        "  {:<20} format={} quality={}",
+                        name,
+                        template.format.as_deref().unwrap_or("mp4"),
+                        template.quality.as_deref().unwrap_or("auto")
+                    );
+                }
+            }
+            return Ok(());
+        } else if let Some(show_matches) = template_matches.subcommand_matches("show") {
+            let name = show_matches.get_one::<String>("name").unwrap();
+            let template = get_template(name)?;
+            println!("{}", format!("Template: {}", name).bright_cyan().bold());
+            println!("{}", serde_json::to_string_pretty(&template)?);
+            return Ok(());
+        } else if let Some(save_matches) = template_matches.subcommand_matches("save-from") {
+            let id = save_matches.get_one::<String>("id").unwrap();
+            let name = save_matches.get_one::<String>("name").unwrap();
+            info!("Saving template {} from download {}", name, id);
+
+            match save_template_from_download(id, name) {
+                Ok(_) => {
+                    println!("{}", format!("Template '{}' saved from download {}.", name, id).green());
+                    println!("Use '--template {}' to apply it.", name);
+                },
+                Err(e) => {
+                    println!("{}: {}", "Error saving template".red(), e);
+                    return Err(e);
+                }
+            }
+            return Ok(());
+        } else if let Some(delete_matches) = template_matches.subcommand_matches("delete") {
+            let name = delete_matches.get_one::<String>("name").unwrap();
+            delete_template(name)?;
+            println!("{}", format!("Template '{}' deleted.", name).green());
+            return Ok(());
+        }
+    }
+
+    if let Some(mux_matches) = matches.subcommand_matches("mux") {
+        let video = mux_matches.get_one::<String>("video").expect("required argument");
+        let audio = mux_matches.get_one::<String>("audio").expect("required argument");
+        let output = mux_matches.get_one::<String>("output").map(Path::new);
+        let saved = mux::mux(Path::new(video), Path::new(audio), output)?;
+        println!("{} {}", "Saved".green(), saved.display());
+        return Ok(());
+    }
+
+    if let Some(join_matches) = matches.subcommand_matches("join") {
+        let file = join_matches.get_one::<String>("file").expect("required argument");
+        let output = join_matches.get_one::<String>("output").map(Path::new);
+        let saved = split::join_parts(Path::new(file), output)?;
+        println!("{} {}", "Saved".green(), saved.display());
+        return Ok(());
+    }
+
+    if let Some(snapshot_matches) = matches.subcommand_matches("snapshot") {
+        let source = snapshot_matches.get_one::<String>("source").expect("required argument");
+        let output_dir = snapshot_matches.get_one::<String>("output-dir").map(String::as_str);
+        let request = SnapshotRequest {
+            at: parse_timestamp(snapshot_matches.get_one::<String>("at").expect("required argument"))?,
+            count: *snapshot_matches.get_one::<u32>("count").expect("has a default"),
+            interval: *snapshot_matches.get_one::<f64>("interval").expect("has a default"),
+            image_format: ImageFormat::parse(snapshot_matches.get_one::<String>("image-format").expect("has a default"))?,
+            output_dir: download_dir_path(output_dir, "rustloader", "snapshots")?,
+        };
+        for path in take_snapshots(source, &request, &RequestHeaders::default()).await? {
+            println!("{} {}", "Saved".green(), path.display());
+        }
+        return Ok(());
+    }
+
+    if let Some(cleanup_matches) = matches.subcommand_matches("cleanup") {
+        if !cleanup_matches.get_flag("scan") {
+            println!("Use 'rustloader cleanup --scan' to look for leftover partial downloads.");
+            return Ok(());
+        }
+
+        let assume_yes = cleanup_matches.get_flag("yes");
+        let permanent = cleanup_matches.get_flag("permanent");
+        let downloads = get_known_downloads().unwrap_or_else(|e| {
+            warn!("Could not read download history: {}", e);
+            Vec::new()
+        });
+        let partial_files = scan_partial_files(&downloads)?;
+
+        if partial_files.is_empty() {
+            println!("{}", "No partial downloads found.".blue());
+            return Ok(());
+        }
+
+        println!("{}", "Partial Downloads:".bright_cyan().bold());
+        for file in &partial_files {
+            let owner = match &file.download {
+                Some(item) if file.is_resumable() => format!("resumable ({})", &item.id[0..8]),
+                Some(item) => format!("finished ({})", &item.id[0..8]),
+                None => "orphaned".to_string(),
+            };
+            println!("  {:<12} {:<20} {}", format_size(file.size, BINARY), owner, file.path.display());
+        }
+
+        // Offer to resume each unfinished download once, however many parts it left behind
+        let mut resumable: Vec<&DownloadItem> = partial_files
+            .iter()
+            .filter(|file| file.is_resumable())
+            .filter_map(|file| file.download.as_ref())
+            .collect();
+        resumable.sort_by(|a, b| a.id.cmp(&b.id));
+        resumable.dedup_by(|a, b| a.id == b.id);
+
+        for item in resumable {
+            let name = item.title.clone().unwrap_or_else(|| item.url.clone());
+            if assume_yes || confirm(&format!("Resume '{}'?", name), false)? {
+                match requeue_download(item).await {
+                    Ok(_) => println!("{}", format!("Queued '{}' to resume.", name).green()),
+                    Err(e) => println!("{}: {}", "Failed to resume download".red(), e),
+                }
+            }
+        }
+
+        let removable: Vec<&PartialFile> = partial_files.iter().filter(|file| !file.is_resumable()).collect();
+        if !removable.is_empty() {
+            let total: u64 = removable.iter().map(|file| file.size).sum();
+            let prompt = format!(
+                "{} {} orphaned partial file(s) ({})?",
+                if permanent { "Permanently delete" } else { "Move to the trash" },
+                removable.len(),
+                format_size(total, BINARY)
+            );
+            if assume_yes || confirm(&prompt, false)? {
+                let mut removed = 0;
+                let mut failed = 0;
+                for file in removable {
+                    match discard_file(&file.path, permanent) {
+                        Ok(_) => removed += 1,
+                        Err(e) => {
+                            failed += 1;
+                            println!("{}: {:?} - {}", "Failed to remove file".red(), file.path, e);
+                        }
+                    }
+                }
+                if failed > 0 && !permanent {
+                    println!("{}", "Use --permanent to delete files the trash can't take.".yellow());
+                }
+                let label = if permanent { "Partial files deleted:" } else { "Partial files moved to the trash:" };
+                println!("{} {}", label.green(), removed);
+            }
+        }
+        return Ok(());
+    }
+
+    // Handle download subcommand or direct URL (backward compatibility)
+    let download_matches = matches.subcommand_matches("download");
+
+    // `--output -` streams the media, so stdout is claimed before anything is printed
+    let stream = stream_target(
+        download_matches
+            .and_then(|m| m.get_one::<String>("output-dir"))
+            .or_else(|| matches.get_one::<String>("output-dir"))
+            .map(String::as_str),
+    );
+    let mut stream_out = match &stream {
+        Some(StreamTarget::Stdout) => Some(claim_stdout()?),
+        _ => None,
+    };
+
+    // rustloader:// links and .rustloader job files opened from the desktop
+    let url_arg = download_matches
+        .and_then(|m| m.get_one::<String>("url"))
+        .or_else(|| matches.get_one::<String>("url"));
+    let job = match url_arg {
+        Some(arg) if is_job_file(arg) => {
+            info!("Opening job file: {}", arg);
+            Some(load_job_file(std::path::Path::new(arg))?)
+        }
+        _ => None,
+    };
+    let opened_url = match (&job, url_arg) {
+        (Some(job), _) => Some(job.url.clone()),
+        (None, Some(arg)) if is_protocol_link(arg) => Some(parse_protocol_link(arg)?),
+        _ => None,
+    };
+    
+    // Load the job template, if one was requested; explicit options override it
+    let template_name = download_matches
+        .and_then(|m| m.get_one::<String>("template"))
+        .or_else(|| matches.get_one::<String>("template"));
+    // Options picked for this download, which suggestions from history never override
+    let options_chosen = template_name.is_some()
+        || job.is_some()
+        || ["format", "quality", "video-bitrate"].iter().any(|arg| {
+            download_matches.is_some_and(|m| m.value_source(arg) == Some(ValueSource::CommandLine))
+                || matches.value_source(arg) == Some(ValueSource::CommandLine)
+        });
+    let template = match (template_name, job) {
+        (Some(name), _) => {
+            info!("Applying job template: {}", name);
+            println!("{}: {}", "Using template".blue(), name);
+            get_template(name)?
+        }
+        (None, Some(job)) => job.options,
+        (None, None) => JobTemplate::default(),
+    };
+    
+    // Defaults chosen in `rustloader init` apply after explicit options and the template
+    let user_config = load_config().unwrap_or_default();
+    
+    let transcode = match download_matches
+        .and_then(|m| m.get_one::<String>("transcode"))
+        .or_else(|| matches.get_one::<String>("transcode"))
+    {
+        Some(value) => Some(TranscodeKind::parse(value)?),
+        None => template.transcode,
+    };
+    let assume_yes = download_matches.is_some_and(|m| m.get_flag("yes")) || matches.get_flag("yes");
+    let print_cmd = download_matches.is_some_and(|m| m.get_flag("print-cmd")) || matches.get_flag("print-cmd");
+    let ffmpeg_args = download_matches
+        .and_then(|m| m.get_one::<String>("ffmpeg-args"))
+        .or_else(|| matches.get_one::<String>("ffmpeg-args"))
+        .map(String::as_str)
+        .or(template.ffmpeg_args.as_deref())
+        .map(parse_ffmpeg_args)
+        .transpose()?
+        .unwrap_or_default();
+    let no_merge = download_matches.is_some_and(|m| m.get_flag("no-merge")) || matches.get_flag("no-merge");
+    let trim_silence = download_matches.is_some_and(|m| m.get_flag("trim-silence")) || matches.get_flag("trim-silence");
+    let transliterate = download_matches.is_some_and(|m| m.get_flag("transliterate")) || matches.get_flag("transliterate");
+    let media_naming = (download_matches.is_some_and(|m| m.get_flag("media-center-naming")) || matches.get_flag("media-center-naming"))
+        .then(|| MediaNaming {
+            nfo: download_matches.is_some_and(|m| m.get_flag("nfo")) || matches.get_flag("nfo"),
+        });
+    let split_size = download_matches
+        .and_then(|m| m.get_one::<String>("split-size"))
+        .or_else(|| matches.get_one::<String>("split-size"))
+        .map(|value| split::parse_split_size(value))
+        .transpose()?;
+    let chapters = download_matches
+        .and_then(|m| m.get_one::<String>("chapters"))
+        .or_else(|| matches.get_one::<String>("chapters"))
+        .map(String::as_str);
+    if let Some(selection) = chapters {
+        parse_chapter_selection(selection)?;
+    }
+    let vertical = VerticalOptions {
+        mode: download_matches
+            .and_then(|m| m.get_one::<String>("vertical-mode"))
+            .or_else(|| matches.get_one::<String>("vertical-mode"))
+            .map(|value| VerticalMode::parse(value))
+            .transpose()?
+            .unwrap_or_default(),
+        target: download_matches
+            .and_then(|m| m.get_one::<String>("target-aspect"))
+            .or_else(|| matches.get_one::<String>("target-aspect"))
+            .map(|value| AspectRatio::parse(value))
+            .transpose()?
+            .unwrap_or_default(),
+    };
+    let auto_update_deps = download_matches.is_some_and(|m| m.get_flag("auto-update-deps"))
+        || matches.get_flag("auto-update-deps")
+        || user_config.auto_update_deps;
+    
+    // Custom user agent and headers, e.g. a Referer required by embedded players
+    let request_headers = RequestHeaders::from_args(
+        download_matches
+            .and_then(|m| m.get_one::<String>("user-agent"))
+            .or_else(|| matches.get_one::<String>("user-agent"))
+            .map(String::as_str)
+            .or(template.user_agent.as_deref()),
+        download_matches
+            .and_then(|m| m.get_many::<String>("header"))
+            .or_else(|| matches.get_many::<String>("header"))
+            .map_or_else(|| template.headers.iter().collect(), |values| values.collect::<Vec<_>>())
+            .into_iter()
+            .map(String::as_str),
+    )?
+    .with_region(RegionOverrides::from_args(
+        download_matches
+            .and_then(|m| m.get_one::<String>("player-client"))
+            .or_else(|| matches.get_one::<String>("player-client"))
+            .map(String::as_str)
+            .or(template.player_client.as_deref()),
+        download_matches
+            .and_then(|m| m.get_one::<String>("geo-bypass-country"))
+            .or_else(|| matches.get_one::<String>("geo-bypass-country"))
+            .map(String::as_str)
+            .or(template.geo_bypass_country.as_deref()),
+    )?)
+    // Queued items render their own credentials when they start; this one
+    // covers the lookups and a direct download made by this run
+    .with_proxy(user_config.proxy_template()?.map(|proxy| proxy.render(&proxy::new_isolation_key())));
+    
+    // Alternate sources for the same content, tried in order if the URL fails
+    let mirrors: Vec<String> = download_matches
+        .and_then(|m| m.get_many::<String>("mirror"))
+        .or_else(|| matches.get_many::<String>("mirror"))
+        .map_or_else(|| template.mirrors.clone(), |values| values.cloned().collect());
+    let tags = download_matches
+        .and_then(|m| m.get_many::<String>("tag"))
+        .or_else(|| matches.get_many::<String>("tag"))
+        .map_or_else(|| template.tags.iter().collect(), |values| values.collect::<Vec<_>>())
+        .into_iter()
+        .map(|tag| parse_tag(tag))
+        .collect::<Result<Vec<String>, AppError>>()?;
+    for mirror in &mirrors {
+        validate_url(mirror)?;
+    }
+    
+    // Wall-clock budget for each run of a queued download
+    let deadline_secs = download_matches
+        .and_then(|m| m.get_one::<String>("deadline"))
+        .or(template.deadline.as_ref())
+        .map(|value| parse_duration(value))
+        .transpose()?;
+    let deadline_action = download_matches
+        .and_then(|m| m.get_one::<String>("on-deadline"))
+        .map(|value| DeadlineAction::parse(value))
+        .transpose()?
+        .or(template.on_deadline)
+        .unwrap_or_default();
+    let resume_after_secs = download_matches
+        .and_then(|m| m.get_one::<String>("resume-after"))
+        .or(template.resume_after.as_ref())
+        .map(|value| parse_duration(value))
+        .transpose()?;
+    if resume_after_secs.is_some() && deadline_action != DeadlineAction::Pause {
+        return Err(AppError::ValidationError(
+            "--resume-after only applies with --on-deadline pause".to_string(),
+        ));
+    }
+    
+    // Audio quality and codec preferences, falling back to the template
+    let format_preferences = FormatPreferences {
+        audio_quality: match download_matches
+            .and_then(|m| m.get_one::<String>("audio-quality"))
+            .or_else(|| matches.get_one::<String>("audio-quality"))
+        {
+            Some(value) => Some(AudioQuality::parse(value)?),
+            None => template.audio_quality,
+        },
+        video_codec: match download_matches
+            .and_then(|m| m.get_one::<String>("codec"))
+            .or_else(|| matches.get_one::<String>("codec"))
+        {
+            Some(value) => Some(VideoCodec::parse(value)?),
+            None => template.video_codec,
+        },
+    };
+    
+    // Determine URL and options from either download subcommand or direct args
+    let (url, quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, priority, interactive) =
+        if let Some(dl_matches) = download_matches {
+            // Get options from download subcommand
+            let url = dl_matches.get_one::<String>("url").unwrap();
+            let quality = dl_matches
+                .get_one::<String>("quality")
+                .map(|q| q.as_str())
+                .or(template.quality.as_deref())
+                .or(user_config.default_quality.as_deref());
+            let format = dl_matches
+                .get_one::<String>("format")
+                .map(|f| f.as_str())
+                .or(template.format.as_deref())
+                .or(user_config.default_format.as_deref())
+                .unwrap_or("mp4");
+            let start_time = dl_matches.get_one::<String>("start-time").or(template.start_time.as_ref());
+            let end_time = dl_matches.get_one::<String>("end-time").or(template.end_time.as_ref());
+            let use_playlist = explicit_flag(dl_matches, "playlist", "no-playlist").or(template.use_playlist).unwrap_or(false);
+            let download_subtitles = explicit_flag(dl_matches, "subtitles", "no-subtitles")
+                .or(template.download_subtitles)
+                .unwrap_or(false);
+            let output_dir = dl_matches
+                .get_one::<String>("output-dir")
+                .or(template.output_dir.as_ref())
+                .or(user_config.download_dir.as_ref());
+            
+            // Only allow force download in development mode
+            let force_download = if cfg!(debug_assertions) {
+                dl_matches.get_flag("force")
+            } else {
+                false
+            };
+            
+            let bitrate = dl_matches.get_one::<String>("video-bitrate").or(template.bitrate.as_ref());
+            let use_queue = dl_matches.get_flag("add-to-queue");
+            
+            // Parse priority
+            let default_priority = String::from("normal");
+            let priority_str = dl_matches.get_one::<String>("priority").unwrap_or(&default_priority).as_str();
+            let priority = match priority_str {
+                "low" => DownloadPriority::Low,
+                "normal" => DownloadPriority::Normal,
+                "high" => DownloadPriority::High,
+                "critical" => DownloadPriority::Critical,
+                _ => DownloadPriority::Normal,
+            };
+            
+            // The template priority only applies when --priority wasn't given explicitly
+            let priority = match (dl_matches.value_source("priority"), template.priority) {
+                (Some(ValueSource::CommandLine), _) | (_, None) => priority,
+                (_, Some(template_priority)) => template_priority,
+            };
+            
+            let interactive = dl_matches.get_flag("interactive");
+            
+            (url, quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, Some(priority), interactive)
+        } else {
+            // Get options from direct arguments (backward compatibility)
+            let url = matches.get_one::<String>("url").unwrap();
+            let quality = matches
+                .get_one::<String>("quality")
+                .map(|q| q.as_str())
+                .or(template.quality.as_deref())
+                .or(user_config.default_quality.as_deref());
+            let format = matches
+                .get_one::<String>("format")
+                .map(|f| f.as_str())
+                .or(template.format.as_deref())
+                .or(user_config.default_format.as_deref())
+                .unwrap_or("mp4");
+            let start_time = matches.get_one::<String>("start-time").or(template.start_time.as_ref());
+            let end_time = matches.get_one::<String>("end-time").or(template.end_time.as_ref());
+            let use_playlist = explicit_flag(&matches, "playlist", "no-playlist").or(template.use_playlist).unwrap_or(false);
+            let download_subtitles = explicit_flag(&matches, "subtitles", "no-subtitles")
+                .or(template.download_subtitles)
+                .unwrap_or(false);
+            let output_dir = matches
+                .get_one::<String>("output-dir")
+                .or(template.output_dir.as_ref())
+                .or(user_config.download_dir.as_ref());
+            
+            // Only allow force download in development mode
+            let force_download = if cfg!(debug_assertions) {
+                let is_forced = matches.get_flag("force");
+                if is_forced {
+                    warn!("Development mode force flag enabled - daily limits will be bypassed");
+                    debug!("Force flag should only be used in development environments");
+                    println!(
+                        "{}",
+                        "⚠️ WARNING: Development mode force flag enabled! Daily limits bypassed. ⚠️"
+                            .bright_red()
+                    );
+                    println!(
+                        "{}",
+                        "This flag should never be used in production environments.".bright_red()
+                    );
+                }
+                is_forced
+            } else {
+                false
+            };
+            
+            let bitrate = matches.get_one::<String>("video-bitrate").or(template.bitrate.as_ref());
+            
+            // Default to direct download for backward compatibility
+            let use_queue = false;
+            let priority = template.priority; // Use default priority unless the template sets one
+            
+            let interactive = matches.get_flag("interactive");
+            
+            (url, quality, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, priority, interactive)
+        };
+
+    // A streamed download is saved privately first, then written out whole
+    if stream.is_some() {
+        validate_stream_options(use_playlist, use_queue, split_size)?;
+        if chapters.is_some() {
+            return Err(AppError::ValidationError("--chapters saves several files, so it can't be streamed".to_string()));
+        }
+    }
+    let stream_dir = stream.as_ref().map(|_| stream_staging_dir().to_string_lossy().into_owned());
+    let output_dir = stream_dir.as_ref().or(output_dir);
+
+    let url = opened_url.as_deref().unwrap_or(url);
+
+    // Check for update results
+    if let Ok(Ok(true)) = update_check.await {
+        info!("Update check completed: new version available");
+        println!(
+            "{}",
+            "A new version of Rustloader is available! Visit rustloader.com to upgrade."
+                .bright_yellow()
+        );
+    } else {
+        debug!("No updates available or update check failed");
+    }
+
+    // Resolve shortened and embed URLs before anything validates them
+    let resolved_url = resolve_url(url).await;
+    // Provider plugins get a chance at hosts yt-dlp doesn't know
+    let resolved_url = plugins::resolve_with_plugins(&resolved_url).await;
+    let url = resolved_url.as_str();
+    remember_url(url, None);
+    
+    // Options the last downloads from this channel agreed on
+    let suggestion = if user_config.smart_defaults != SmartDefaults::Off && !options_chosen && !use_playlist {
+        smart_defaults::suggest_options(url, &request_headers).await.unwrap_or_else(|e| {
+            debug!("No suggested options: {}", e);
+            None
+        })
+    } else {
+        None
+    };
+    let (format, quality, bitrate) = match &suggestion {
+        Some(suggested) if user_config.smart_defaults == SmartDefaults::Apply => {
+            println!(
+                "{} {} {}",
+                "Using".blue(),
+                suggested.as_flags(),
+                format!("(like your last {} downloads from {})", suggested.based_on, suggested.channel).dimmed()
+            );
+            (suggested.format.as_str(), suggested.quality.as_deref(), suggested.bitrate.as_ref())
+        }
+        Some(suggested) => {
+            println!(
+                "{} your last {} downloads from {} used {}",
+                "Hint:".blue(),
+                suggested.based_on,
+                suggested.channel,
+                suggested.as_flags()
+            );
+            println!(
+                "{}",
+                "Set \"smart_defaults\" to \"apply\" in config.json to use them automatically, or \"off\" to stop these hints."
+                    .dimmed()
+            );
+            (format, quality, bitrate)
+        }
+        None => (format, quality, bitrate),
+    };
+    
+    // Let the user choose an exact format instead of a quality preset
+    let picked_format = if interactive && !environment::is_interactive() {
+        println!("{}", "No terminal to pick a format on; using the quality settings instead.".yellow());
+        None
+    } else if interactive {
+        match pick_format(url, format == "mp3", &request_headers).await? {
+            Some(picked) => Some(picked),
+            None => {
+                println!("{}", "Download cancelled.".yellow());
+                return Ok(());
+            }
+        }
+    } else {
+        None
+    };
+    let format_id = picked_format.as_ref().map(|picked| picked.selector());
+    let format_id = format_id.as_deref();
+    
+    check_conflicts(&ffmpeg_args, &[], start_time.is_some() || end_time.is_some())?;
+    if let Some(rate) = bitrate {
+        parse_bitrate(rate, format)?;
+    }
+    
+    // Show what would be run instead of running it
+    if print_cmd {
+        let folder_type = if format == "mp3" { "audio" } else { "videos" };
+        let download_dir = download_dir_path(output_dir.map(String::as_str), "rustloader", folder_type)?;
+        let output_path = if media_naming.is_some() {
+            format_id_output_path(&download_dir, format)?
+        } else {
+            format_output_path(&download_dir, format)?
+        };
+        let argv = YtdlpCommandBuilder::new(url, &output_path)
+            .with_format(format)
+            .with_quality(quality)
+            .with_format_id(format_id)
+            .with_format_preferences(format_preferences)
+            .with_time_range(start_time, end_time)
+            .with_playlist(use_playlist)
+            .with_subtitles(download_subtitles)
+            .with_force_download(force_download)
+            .with_bitrate(bitrate)
+            .with_transcode(transcode)
+            .with_request_headers(&request_headers)
+            .with_backend(default_backend())
+            .with_ffmpeg_args(&ffmpeg_args)
+            .with_rate_limit(bandwidth::current_rate_limit())
+            .with_max_filesize(user_config.max_filesize_limit()?)
+            .with_no_merge(no_merge)
+            .with_embedded_metadata(transliterate)
+            .with_staging_dir(user_config.staging_path()?.map(|dir| dir.to_string_lossy().into_owned()))
+            .with_chapters(&match chapters {
+                Some(selection) => resolve_chapters(url, selection, &request_headers).await?,
+                None => Vec::new(),
+            })
+            .build_preview()?;
+        println!("{}", shell_join(&argv));
+        return Ok(());
+    }
+    
+    if transcode.is_some() && format == "mp3" {
+        return Err(AppError::ValidationError("--transcode only applies to video downloads".to_string()));
+    }
+    if no_merge && format == "mp3" {
+        return Err(AppError::ValidationError("--no-merge only applies to video downloads".to_string()));
+    }
+    if trim_silence {
+        if format != "mp3" {
+            return Err(AppError::ValidationError("--trim-silence only applies to audio downloads (--format mp3)".to_string()));
+        }
+        if use_playlist {
+            return Err(AppError::ValidationError("--trim-silence can't be used with playlists".to_string()));
+        }
+        user_config.silence_trim.validate()?;
+    }
+    if vertical.mode != VerticalMode::Keep {
+        if format == "mp3" {
+            return Err(AppError::ValidationError("--vertical-mode only applies to video downloads".to_string()));
+        }
+        if use_playlist || no_merge {
+            return Err(AppError::ValidationError("--vertical-mode pad/crop can't be used with playlists or --no-merge".to_string()));
+        }
+        if transcode.is_some() {
+            return Err(AppError::ValidationError("--vertical-mode pad/crop can't be combined with --transcode".to_string()));
+        }
+    }
+    if transliterate && (use_playlist || no_merge) {
+        return Err(AppError::ValidationError("--transliterate can't be used with playlists or --no-merge".to_string()));
+    }
+    if media_naming.is_some() && no_merge {
+        return Err(AppError::ValidationError("--media-center-naming can't be used with --no-merge".to_string()));
+    }
+    if split_size.is_some() && (use_playlist || no_merge) {
+        return Err(AppError::ValidationError("--split-size can't be used with playlists or --no-merge".to_string()));
+    }
+    
+    // Templates, saved defaults and the picked format can ask for Pro options
+    // the command line didn't, so check what will actually be downloaded
+    check_pro_options(
+        &feature_gate::RequestedFeatures {
+            format: Some(format.to_string()),
+            quality: quality.map(str::to_string),
+            bitrate: bitrate.cloned(),
+            transcode: transcode.map(|kind| kind.as_str().to_string()),
+            format_height: picked_format.as_ref().and_then(|picked| picked.height),
+        },
+        is_pro,
+    )?;
+    
+    // A queued download is validated and probed before anything is added,
+    // so problems show up here rather than later in the queue. A queued
+    // playlist is listed and added as one download per video.
+    let (prepared, playlist) = if use_queue {
+        let options = DownloadOptions {
+            url,
             quality,
+            format_id,
+            format_preferences,
             format,
             start_time,
             end_time,
@@ -559,9 +1947,99 @@ async fn main() -> Result<(), AppError> {
             output_dir,
             force_download,
             bitrate,
+            transcode,
+            ffmpeg_args: &ffmpeg_args,
+            no_merge,
+            trim_silence,
+            vertical,
+            transliterate,
+            media_naming,
+            split_size,
+            chapters,
+            request_headers: request_headers.clone(),
             priority,
+            mirrors: &mirrors,
+            tags: &tags,
+            deadline_secs,
+            deadline_action,
+            resume_after_secs,
         };
-        match add_download_to_queue(download_options).await {
+        if use_playlist {
+            (None, Some(prepare_playlist(options).await?))
+        } else {
+            let prepared = prepare_download(options).await?;
+            if let Some(existing) = &prepared.conflict {
+                return Err(AppError::ValidationError(format!(
+                    "This video is already in the queue (ID: {})",
+                    existing
+                )));
+            }
+            (Some(prepared), None)
+        }
+    } else {
+        (None, None)
+    };
+    
+    // Probe a single video once for the pre-flight checks and the transcode
+    // estimate; a queued download was probed while it was prepared
+    let estimate = if let Some(prepared) = &prepared {
+        prepared.estimate.clone()
+    } else if use_playlist || start_time.is_some() || end_time.is_some() {
+        None
+    } else {
+        match estimate_download(url, format, quality, format_id, format_preferences, &request_headers).await {
+            Ok(estimate) => Some(estimate),
+            Err(e) => {
+                debug!("Could not estimate the download: {}", e);
+                None
+            }
+        }
+    };
+    
+    // Warn before a download that would hit the size limit or run out of disk or memory
+    let warnings = match (&prepared, estimate.as_ref().and_then(|estimate| estimate.total_bytes)) {
+        (Some(prepared), _) => prepared.warnings.clone(),
+        (None, Some(total_bytes)) => {
+            let folder_type = if format == "mp3" { "audio" } else { "videos" };
+            let download_dir = download_dir_path(output_dir.map(String::as_str), "rustloader", folder_type)?;
+            guardrails::check_download(total_bytes, transcode, &download_dir)
+        }
+        (None, None) => Vec::new(),
+    };
+    for warning in &warnings {
+        println!("{} {}", "Warning:".yellow(), warning);
+    }
+    if !warnings.is_empty() && !assume_yes && !confirm("Download anyway?", true)? {
+        println!("{}", "Download cancelled.".yellow());
+        return Ok(());
+    }
+    
+    // Heavy post-processing can take far longer than the download itself
+    if let Some(kind) = transcode {
+        if !assume_yes && !confirm_transcode(estimate.as_ref(), use_playlist, kind)? {
+            println!("{}", "Download cancelled.".yellow());
+            return Ok(());
+        }
+    }
+    
+    // Process the download
+    info!("Starting download process for URL: {}", url);
+    debug!("Download parameters: quality={:?}, format_id={:?}, format={}, start_time={:?}, end_time={:?}, playlist={}, subtitles={}, output_dir={:?}, force={}, bitrate={:?}, use_queue={}, priority={:?}",
+           quality, format_id, format, start_time, end_time, use_playlist, download_subtitles, output_dir, force_download, bitrate, use_queue, priority);
+    
+    if let Some(playlist) = playlist {
+        info!("Adding playlist videos to queue: {}", url);
+        enqueue_playlist(playlist).await?;
+    } else if let Some(prepared) = prepared {
+        // Add to download queue instead of downloading immediately
+        info!("Adding download to queue: {}", url);
+        if let Some(title) = &prepared.item.title {
+            println!("Title:       {}", title);
+        }
+        if let Some(bytes) = prepared.estimate.as_ref().and_then(|estimate| estimate.total_bytes) {
+            println!("Size:        ~{}", format_size(bytes, BINARY));
+        }
+        match enqueue(prepared).await {
             Ok(id) => {
                 println!("{}", "Download added to queue successfully.".green());
                 println!("Download ID: {}", id);
@@ -574,36 +2052,113 @@ async fn main() -> Result<(), AppError> {
             }
         }
     } else {
-        // Perform direct download using the free version function
-        match download_video_free(
-            url,
-            quality,
-            format,
-            start_time,
-            end_time,
-            use_playlist,
-            download_subtitles,
-            output_dir,
-            force_download,
-            bitrate,
-        )
-        .await
-        {
-            Ok(path) => {
-                info!("Download completed successfully: {}", path);
-                println!("{} {}", "Process completed successfully. File saved at".green(), path);
+        // Perform direct download using the free version function, retrying
+        // once if an outdated extractor was updated, then trying each mirror
+        let stderr_tail = StderrTail::new();
+        let sources: Vec<&str> = std::iter::once(url).chain(mirrors.iter().map(String::as_str)).collect();
+        let mut source_index = 0;
+        let mut retried = false;
+        let mut age_gate_strategies = fallback_strategies(sources[0]).into_iter();
+        let mut age_gate: Option<AgeGateStrategy> = None;
+        let result = loop {
+            let source = sources[source_index];
+            let attempt_url = age_gate.as_ref().map(|strategy| strategy.url(source));
+            let attempt_headers = request_headers
+                .clone()
+                .with_extractor_args(age_gate.as_ref().and_then(AgeGateStrategy::extractor_args));
+            let result = download_video_free(
+                attempt_url.as_deref().unwrap_or(source),
+                quality,
+                format_id,
+                format_preferences,
+                format,
+                start_time,
+                end_time,
+                use_playlist,
+                download_subtitles,
+                output_dir,
+                force_download,
+                bitrate,
+                transcode,
+                &ffmpeg_args,
+                no_merge,
+                trim_silence,
+                vertical,
+                transliterate,
+                media_naming,
+                split_size,
+                chapters,
+                &attempt_headers,
+                None,
+                Some(stderr_tail.clone()),
+            )
+            .await;
+            if !retried && update_after_extractor_failure(&result, &stderr_tail, auto_update_deps)? {
+                retried = true;
+                println!("{}", "Retrying the download with the updated yt-dlp...".blue());
+                continue;
+            }
+            if let Err(e) = &result {
+                if is_age_gated(&e.to_string(), &stderr_tail.lines()) {
+                    if let Some(strategy) = age_gate_strategies.next() {
+                        println!("{} {}", "Age-restricted video; trying the".blue(), strategy.describe());
+                        age_gate = Some(strategy);
+                        stderr_tail.clear();
+                        continue;
+                    }
+                }
+            }
+            match &result {
+                Err(e) if source_index + 1 < sources.len() && should_try_mirror(e) => {
+                    println!("{}: {}", "Download failed".yellow(), e);
+                    source_index += 1;
+                    retried = false;
+                    age_gate_strategies = fallback_strategies(sources[source_index]).into_iter();
+                    age_gate = None;
+                    stderr_tail.clear();
+                    println!("{} {}", "Trying mirror".blue(), sources[source_index]);
+                }
+                _ => break result,
+            }
+        };
+        if let (Err(_), Some(dir)) = (&result, &stream_dir) {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+        match result {
+            Ok(outcome) => {
+                info!("Download completed successfully: {} ({:?})", outcome.output_path, outcome.backend);
+                if stream.is_none() {
+                    println!("{} {}", "Process completed successfully. File saved at".green(), outcome.output_path);
+                }
+                if source_index > 0 {
+                    println!("{} {}", "Downloaded from mirror".green(), sources[source_index]);
+                }
+                if let Some(strategy) = &age_gate {
+                    println!("{} {}", "Passed the age gate with the".green(), strategy.describe());
+                }
+                if let (Some(target), Some(path)) = (&stream, &outcome.file_path) {
+                    let streamed = stream_file(path, target, stream_out.as_mut());
+                    if let Some(dir) = &stream_dir {
+                        let _ = std::fs::remove_dir_all(dir);
+                    }
+                    let bytes = streamed?;
+                    println!("{} {} to {}", "Streamed".green(), format_size(bytes, BINARY), target.describe());
+                } else if let Some(path) = &outcome.file_path {
+                    handle_duplicate(path)?;
+                    for link in publish_download(path, url, format) {
+                        println!("{} {}", "Published to".green(), link.display());
+                    }
+                }
             },
             Err(AppError::DailyLimitExceeded) => {
                 error!("Daily download limit exceeded for free version");
-                println!("{}", "Would you like to add to download queue instead? (y/n)".yellow());
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
-                
-                if input.trim().eq_ignore_ascii_case("y") {
+                if stream.is_none() && confirm("Would you like to add to download queue instead?", false)? {
                     info!("Adding to queue instead after daily limit exceeded");
                     let download_options = DownloadOptions {
                         url,
                         quality,
+                        format_id,
+                        format_preferences,
                         format,
                         start_time,
                         end_time,
@@ -612,8 +2167,28 @@ async fn main() -> Result<(), AppError> {
                         output_dir,
                         force_download,
                         bitrate,
+                        transcode,
+                        ffmpeg_args: &ffmpeg_args,
+                        no_merge,
+                        trim_silence,
+                        vertical,
+                        transliterate,
+                        media_naming,
+                        split_size,
+                        chapters,
+                        request_headers: request_headers.clone(),
                         priority: None, // Use default priority
+                        mirrors: &mirrors,
+                        tags: &tags,
+                        deadline_secs,
+                        deadline_action,
+                        resume_after_secs,
                     };
+                    if use_playlist {
+                        enqueue_playlist(prepare_playlist(download_options).await?).await?;
+                        println!("Downloads will resume when you have available download slots.");
+                        return Ok(());
+                    }
                     match add_download_to_queue(download_options).await {
                         Ok(id) => {
                             println!("{}", "Download added to queue successfully.".green());
@@ -722,3 +2297,537 @@ fn print_logo() {
         .bright_cyan()
     );
 }
+
+/// Take the instance lock so this process may run the queue, unless another
+/// process already does
+fn take_instance_lock() -> Option<InstanceLock> {
+    match acquire_instance_lock(InstanceKind::Cli) {
+        Ok(lock) => lock,
+        Err(e) => {
+            warn!("Could not take the instance lock, continuing without it: {}", e);
+            None
+        }
+    }
+}
+
+/// A switch given on the command line, either way; `None` leaves it to the template
+fn explicit_flag(matches: &ArgMatches, on: &str, off: &str) -> Option<bool> {
+    if matches.get_flag(on) {
+        Some(true)
+    } else if matches.get_flag(off) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Report every requested option the free version doesn't offer in one message
+fn check_pro_options(requested: &feature_gate::RequestedFeatures, is_pro: bool) -> Result<(), AppError> {
+    if let Err(e) = feature_gate::check_free_features(requested, is_pro) {
+        error!("{}", e);
+        eprintln!("{}", "These options need Rustloader Pro:".red().bold());
+        for option in feature_gate::gated_options(requested) {
+            eprintln!("  • {}", option);
+        }
+        println!(
+            "{}",
+            "🚀 Upgrade to Rustloader Pro to access these features: rustloader.com/pro 🚀".bright_yellow()
+        );
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Estimate how long a transcode will take and ask for confirmation when it
+/// exceeds the threshold
+fn confirm_transcode(estimate: Option<&MediaEstimate>, use_playlist: bool, kind: TranscodeKind) -> Result<bool, AppError> {
+    if use_playlist {
+        return confirm(&format!(
+            "Transcoding ({}) every video in a playlist may take a long time. Continue?",
+            kind.as_str()
+        ), true);
+    }
+
+    let duration = match estimate.and_then(|estimate| estimate.duration_secs) {
+        Some(duration) => duration,
+        None => {
+            warn!("Could not determine video duration for transcode estimate");
+            return confirm("Could not estimate the transcode time. Continue?", true);
+        }
+    };
+
+    println!("{}", "Estimating transcode time...".blue());
+    let speed = encode_speed(kind.encoder())?;
+    let secs = estimate_transcode_secs(speed, kind, duration, estimate.and_then(|estimate| estimate.height));
+    println!("{}: ~{}", "Estimated transcode time".blue(), format_duration(secs as u64));
+
+    if secs <= CONFIRM_THRESHOLD_SECS {
+        return Ok(true);
+    }
+    confirm("This transcode will take a long time. Continue? (use --yes to skip this prompt)", true)
+}
+
+/// Resolve on Ctrl+C, or on SIGTERM as sent by `docker stop`
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Ask a yes/no question on the terminal. When nobody can answer
+/// (containers, CI, --non-interactive) `default` is used instead.
+fn confirm(prompt: &str, default: bool) -> Result<bool, AppError> {
+    if !environment::is_interactive() {
+        let answer = if default { "yes" } else { "no" };
+        println!("{} ({}, non-interactive)", prompt, answer);
+        return Ok(default);
+    }
+    println!("{}", format!("{} (y/n)", prompt).yellow());
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// After a download failed like an outdated site extractor, update yt-dlp
+/// (asking first unless `auto_update` is set). Returns whether to retry.
+fn update_after_extractor_failure(
+    result: &Result<DownloadOutcome, AppError>,
+    stderr_tail: &StderrTail,
+    auto_update: bool,
+) -> Result<bool, AppError> {
+    let error = match result {
+        Err(e) => e,
+        Ok(_) => return Ok(false),
+    };
+    if classify_failure(&error.to_string(), &stderr_tail.lines()) != FailureKind::Extractor {
+        return Ok(false);
+    }
+
+    println!("{}", "The download failed in yt-dlp's site extractor, which usually means yt-dlp is out of date.".yellow());
+    if !auto_update && !confirm("Update yt-dlp and try again?", false)? {
+        return Ok(false);
+    }
+    match update_ytdlp_once() {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            warn!("Not retrying after failed yt-dlp update: {}", e);
+            println!("{}: {}", "Could not update yt-dlp".red(), e);
+            Ok(false)
+        }
+    }
+}
+
+/// Report a finished download that is identical to a file already in the
+/// library, and offer to replace it with a hardlink when configured to ask
+fn handle_duplicate(path: &std::path::Path) -> Result<(), AppError> {
+    let duplicate = match dedupe_download(path) {
+        Ok(Some(duplicate)) => duplicate,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            warn!("Duplicate check failed: {}", e);
+            return Ok(());
+        }
+    };
+
+    println!("{} {:?}", "Identical to an existing file:".yellow(), duplicate.original);
+    let saved = match duplicate.bytes_saved {
+        Some(saved) => saved,
+        None if confirm("Replace the new copy with a hardlink to it?", false)? => deduplicate(path, &duplicate.original)?,
+        None => return Ok(()),
+    };
+    println!("{} {}", "Linked to the existing file, saving".green(), format_size(saved, BINARY));
+    Ok(())
+}
+
+/// Queue each video of a prepared playlist as its own download. Videos
+/// already in the queue are skipped; it fails only if all of them are.
+async fn enqueue_playlist(playlist: PreparedPlaylist) -> Result<(), AppError> {
+    if let Some(title) = &playlist.title {
+        println!("Playlist:    {}", title);
+    }
+    println!("Videos:      {}", playlist.downloads.len());
+    
+    let total = playlist.downloads.len();
+    let mut added = 0;
+    for prepared in playlist.downloads {
+        let label = prepared.item.title.clone().unwrap_or_else(|| prepared.item.url.clone());
+        if let Some(existing) = &prepared.conflict {
+            println!("{} {} (already queued as {})", "Skipped".yellow(), label, existing);
+            continue;
+        }
+        match enqueue(prepared).await {
+            Ok(id) => {
+                added += 1;
+                println!("  {}  {}", id, label);
+            }
+            Err(AppError::ValidationError(e)) => println!("{} {} ({})", "Skipped".yellow(), label, e),
+            Err(e) => {
+                error!("Failed to add playlist video to queue: {}", e);
+                return Err(e);
+            }
+        }
+    }
+    
+    if added == 0 {
+        return Err(AppError::ValidationError(
+            "Every video of this playlist is already in the queue".to_string(),
+        ));
+    }
+    println!("{}", format!("Added {} of {} videos to the queue.", added, total).green());
+    println!("Use 'rustloader queue list' to view all downloads.");
+    Ok(())
+}
+
+/// Every file and directory Rustloader reads or writes, with a short name for each
+fn paths_in_use() -> Result<Vec<(&'static str, PathBuf)>, AppError> {
+    let config_dir = paths::config_dir()?;
+    let data_dir = paths::data_dir()?;
+    let mut paths = vec![
+        ("config_dir", config_dir),
+        ("config", config::get_config_path()?),
+        ("license", license::get_license_path()?),
+        ("license_activations", license::get_activations_path()?),
+        ("recent_urls", recent_urls::get_recent_sources_path()?),
+        ("data_dir", data_dir.clone()),
+    ];
+    let [trial, trial_copy] = license::get_trial_paths()?;
+    paths.push(("trial", trial));
+    paths.push(("trial_copy", trial_copy));
+    let mut state_files = download_manager::get_queue_state_files().into_iter();
+    if let Some(state) = state_files.next() {
+        paths.push(("queue_state", state));
+    }
+    if let Some(backup) = state_files.next() {
+        paths.push(("queue_state_backup", backup));
+    }
+    if let Some(snapshots) = state_files.next() {
+        paths.push(("queue_snapshots", snapshots));
+    }
+    paths.push(("history_database", history::history_path()?));
+    paths.push(("download_counter", downloader::get_counter_path()?));
+    paths.push(("promo_state", promo::promo_state_path()?));
+    paths.push(("message_packs", promo::messages_dir()?));
+    paths.push(("dedupe_stats", dedupe::stats_path()));
+    paths.push(("instance_lock", data_dir.join(instance_lock::LOCK_FILE)));
+    if let Some(downloads) = paths::default_download_dir() {
+        paths.push(("downloads", downloads));
+    }
+    let config = load_config().unwrap_or_default();
+    if let Some(staging) = config.staging_path()? {
+        paths.push(("staging", staging));
+    }
+    if config.event_stream {
+        paths.push(("event_stream", ipc::event_stream_path()?));
+    }
+    Ok(paths)
+}
+
+fn print_paths(entries: &[(&'static str, PathBuf)]) {
+    println!("{}", "Paths in use:".bright_cyan().bold());
+    for (name, path) in entries {
+        let marker = if path.exists() { "".normal() } else { " (not created yet)".dimmed() };
+        println!("{:<20} {}{}", name, path.display(), marker);
+    }
+
+    for var in [paths::CONFIG_DIR_ENV, paths::DATA_DIR_ENV] {
+        match std::env::var_os(var) {
+            Some(_) if paths::dir_from_env(var).is_none() => {
+                println!("{}", format!("{} is set but not an absolute path, so it is ignored", var).yellow())
+            }
+            Some(value) => println!("{}", format!("{} = {}", var, value.to_string_lossy()).blue()),
+            None => {}
+        }
+    }
+    println!("Logs are written to stderr; set RUST_LOG to change the level.");
+}
+
+/// Print finished downloads, newest first, out of `total` recorded
+fn print_history(entries: &[HistoryEntry], total: usize) {
+    if entries.is_empty() {
+        println!("{}", "No finished downloads found.".blue());
+        return;
+    }
+    for entry in entries {
+        let status = match entry.status.as_str() {
+            "completed" => "completed".green(),
+            other => other.red(),
+        };
+        let size = entry.file_size.map(|bytes| format_size(bytes, BINARY)).unwrap_or_else(|| "-".to_string());
+        println!(
+            "{}  {:<9}  {:>10}  {}",
+            entry.finished_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            status,
+            size,
+            entry.display_name().bold()
+        );
+        println!("    {} ({})", entry.url.dimmed(), entry.format);
+        match (&entry.output_path, &entry.error) {
+            (Some(path), _) if entry.status == "completed" => println!("    {}", path),
+            (_, Some(error)) => println!("    {}", error.red()),
+            _ => {}
+        }
+    }
+    if entries.len() < total {
+        println!("{}", format!("Showing {} of {} finished downloads.", entries.len(), total).dimmed());
+    }
+}
+
+/// Print the per-site history table
+fn print_quality_comparison(comparison: &QualityComparison) {
+    if let Some(title) = &comparison.title {
+        println!("{}", title.bright_cyan().bold());
+    }
+    if let Some(duration) = comparison.duration_secs {
+        println!("Duration: {}", format_duration(duration as u64));
+    }
+    println!(
+        "{:<8} {:<8} {:<6} {:<6} {:>5} {:>10} {:>11} {:>8}",
+        "Quality", "ID", "Video", "Audio", "FPS", "Bitrate", "Est. Size", "Bits/px"
+    );
+    println!("{}", "-".repeat(70));
+    for tier in &comparison.tiers {
+        let fps = tier.fps.map(|fps| format!("{:.0}", fps)).unwrap_or_else(|| "-".to_string());
+        let bitrate = tier.video_kbps.map(|kbps| format!("{:.0}k", kbps)).unwrap_or_else(|| "-".to_string());
+        let size = tier.estimated_bytes.map(|bytes| format_size(bytes, BINARY)).unwrap_or_else(|| "?".to_string());
+        let bpp = tier.bits_per_pixel.map(|bpp| format!("{:.3}", bpp)).unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<8} {:<8} {:<6} {:<6} {:>5} {:>10} {:>11} {:>8}",
+            format!("{}p", tier.height),
+            tier.format_id,
+            tier.vcodec,
+            tier.acodec.as_deref().unwrap_or("-"),
+            fps,
+            bitrate,
+            size,
+            bpp
+        );
+    }
+    println!("\nSizes include the best audio for video-only formats. Lower bits/px means stronger compression.");
+}
+
+fn print_site_stats(stats: &[SiteStats]) {
+    if stats.is_empty() {
+        println!("{}", "No finished downloads in the history yet.".blue());
+        return;
+    }
+
+    println!("{}", "Downloads by Site:".bright_cyan().bold());
+    println!("{:<24} {:>9} {:>8} {:>12}  Common Failures", "Site", "Finished", "Success", "Avg Speed");
+    println!("{}", "-".repeat(90));
+    for site in stats {
+        let speed = site
+            .average_speed
+            .map(|speed| format!("{}/s", format_size(speed as u64, BINARY)))
+            .unwrap_or_else(|| "-".to_string());
+        let failures = site
+            .failure_kinds
+            .iter()
+            .take(3)
+            .map(|(kind, count)| format!("{} ({})", kind.as_str(), count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let success = format!("{:.0}%", site.success_rate * 100.0);
+        let success = if site.success_rate < 0.5 { success.red() } else { success.green() };
+        println!(
+            "{:<24} {:>9} {:>8} {:>12}  {}",
+            site.site,
+            site.completed + site.failed,
+            success,
+            speed,
+            failures
+        );
+    }
+
+    let broken: Vec<&str> = stats
+        .iter()
+        .filter(|site| site.needs_ytdlp_update())
+        .map(|site| site.site.as_str())
+        .collect();
+    if !broken.is_empty() {
+        println!(
+            "\n{} {}. {}",
+            "Most recent failures look like extractor problems for".yellow(),
+            broken.join(", "),
+            "Updating yt-dlp may fix them.".yellow()
+        );
+    }
+}
+
+/// Print the result of `providers status`
+fn print_provider_checks(checks: &[ProviderCheck]) {
+    println!("{}", "Provider Status:".bright_cyan().bold());
+    println!("{:<20} {:<12} {:>8}  Error", "Site", "Status", "Time");
+    println!("{}", "-".repeat(90));
+    for check in checks {
+        let status = match check.status {
+            ProviderStatus::Working => check.status.as_str().green(),
+            ProviderStatus::Broken => check.status.as_str().red(),
+            ProviderStatus::Unreachable => check.status.as_str().yellow(),
+        };
+        let error = match (&check.failure, &check.error) {
+            (Some(kind), Some(error)) => format!("{}: {}", kind.as_str(), error),
+            (Some(kind), None) => kind.as_str().to_string(),
+            _ => String::new(),
+        };
+        println!(
+            "{:<20} {:<12} {:>7.1}s  {}",
+            check.site,
+            status,
+            check.elapsed_ms as f64 / 1000.0,
+            error
+        );
+    }
+
+    let broken: Vec<&str> = checks
+        .iter()
+        .filter(|check| check.needs_ytdlp_update())
+        .map(|check| check.site.as_str())
+        .collect();
+    if !broken.is_empty() {
+        println!(
+            "\n{} {}. {}",
+            "The installed yt-dlp can't extract".yellow(),
+            broken.join(", "),
+            "Updating it may fix them.".yellow()
+        );
+    } else if checks.iter().all(|check| check.status == ProviderStatus::Working) {
+        println!("\n{}", "All checked sites work with the installed yt-dlp.".green());
+    }
+}
+
+/// Print when the queue is expected to finish, if it can be estimated
+fn print_queue_finish(queue_eta: &QueueEta) {
+    let finishes_at = match queue_eta.finishes_at {
+        Some(time) => time.with_timezone(&chrono::Local),
+        None => return,
+    };
+
+    let format = if finishes_at.date_naive() == chrono::Local::now().date_naive() {
+        "%H:%M"
+    } else {
+        "%Y-%m-%d %H:%M"
+    };
+    print!("Queue finishes at ~{}", finishes_at.format(format));
+    if queue_eta.unknown_sizes > 0 {
+        print!(" ({} download(s) of unknown size not included)", queue_eta.unknown_sizes);
+    }
+    println!();
+}
+
+/// Print every recorded detail of a download for `queue show`
+fn print_download_details(item: &DownloadItem) {
+    let optional = |value: Option<&str>| value.unwrap_or("-").to_string();
+    let time = |value: Option<chrono::DateTime<chrono::Utc>>| {
+        value.map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string()).unwrap_or_else(|| "-".to_string())
+    };
+
+    println!("{}", "Download Details:".bright_cyan().bold());
+    println!("ID:          {}", item.id);
+    println!("URL:         {}", item.url);
+    for mirror in &item.mirrors {
+        println!("Mirror:      {}", mirror);
+    }
+    if let Some(source) = item.downloaded_from.as_ref().filter(|source| **source != item.url) {
+        println!("Source:      {} (mirror)", source);
+    }
+    if let Some(strategy) = &item.age_gate_strategy {
+        println!("Age gate:    passed with the {}", strategy.describe());
+    }
+    if !item.region.is_empty() {
+        println!("Region:      {}", item.region.describe());
+    }
+    println!("Title:       {}", optional(item.title.as_deref()));
+    if !item.tags.is_empty() {
+        println!("Tags:        {}", item.tags.join(", "));
+    }
+    println!("Status:      {:?}", item.status);
+    println!("Priority:    {:?}", item.priority);
+    println!("Format:      {}", item.format);
+    println!("Quality:     {}", optional(item.quality.as_deref()));
+    if let Some(format_id) = &item.format_id {
+        println!("Format ID:   {}", format_id);
+    }
+    if item.start_time.is_some() || item.end_time.is_some() {
+        println!("Clip:        {} - {}", optional(item.start_time.as_deref()), optional(item.end_time.as_deref()));
+    }
+    println!("Playlist:    {}", item.use_playlist);
+    println!("Subtitles:   {}", item.download_subtitles);
+    println!("Output dir:  {}", optional(item.output_dir.as_deref()));
+    if let Some(transcode) = item.transcode {
+        println!("Transcode:   {}", transcode.as_str());
+    }
+    if item.no_merge {
+        println!("Streams:     separate video and audio files");
+    }
+    if item.trim_silence {
+        println!("Silence:     trimmed");
+    }
+    if item.vertical.mode != VerticalMode::Keep {
+        println!("Reframe:     {} to {}", item.vertical.mode.as_str(), item.vertical.target);
+    }
+    if item.transliterate {
+        println!("File name:   transliterated to ASCII");
+    }
+    if let Some(naming) = item.media_naming {
+        println!("File name:   media center episode naming{}", if naming.nfo { " with .nfo" } else { "" });
+    }
+    if let Some(split_size) = item.split_size {
+        println!("Split:       into parts of at most {}", format_size(split_size, BINARY));
+    }
+    if let Some(chapters) = &item.chapters {
+        println!("Chapters:    {}", chapters);
+    }
+    if !item.ffmpeg_args.is_empty() {
+        println!("ffmpeg args: {}", join_args(&item.ffmpeg_args));
+    }
+    println!("Added:       {}", time(Some(item.added_at)));
+    println!("Started:     {}", time(item.started_at));
+    println!("Finished:    {}", time(item.finished_at));
+    println!(
+        "Progress:    {:.1}% ({} of {})",
+        item.progress,
+        format_size(item.downloaded_bytes, BINARY),
+        if item.total_bytes > 0 { format_size(item.total_bytes, BINARY) } else { "unknown".to_string() }
+    );
+    println!("Retries:     {}", item.retry_count);
+    if let Some(deadline) = item.deadline_secs {
+        println!("Deadline:    {} (then {})", format_duration(deadline), item.deadline_action.as_str());
+    }
+    if let Some(resume_at) = item.resume_at {
+        println!("Resumes at:  {}", time(Some(resume_at)));
+    }
+    if let Some(scheduled_at) = item.scheduled_at {
+        println!("Starts at:   {}", time(Some(scheduled_at)));
+    }
+    if let Some(window) = item.start_window {
+        println!("Window:      {} local time", window);
+    }
+    if let Some(backend) = item.backend {
+        println!("Downloader:  {}", backend.as_str());
+    }
+    println!("Output:      {}", optional(item.output_path.as_deref()));
+    if let Some(original) = &item.duplicate_of {
+        println!("Duplicate:   identical to {}", original);
+    }
+    for link in &item.published_to {
+        println!("Published:   {}", link);
+    }
+    if let Some(error) = &item.error_message {
+        println!("{} {}", "Error:".red(), error);
+    }
+    if !item.error_context.is_empty() {
+        println!("{}", "Last yt-dlp output:".yellow());
+        for line in &item.error_context {
+            println!("  {}", line.dimmed());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// src/media_naming.rs
+//! `--media-center-naming`: names episodes the way Jellyfin, Plex and Kodi
+//! expect, e.g. "Show - S01E03 - Title.mp4", optionally with an `.nfo`
+//! sidecar. The season and episode come from the site's series metadata,
+//! an SxxEyy-style pattern in the title, or the position in a playlist.
+//!
+//! Files are downloaded under their video ID and renamed once yt-dlp's
+//! metadata for each ID is known.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::process::CommandSpec;
+use crate::utils::safe_file_name;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest title kept in a file name, in characters
+const MAX_TITLE_CHARS: usize = 150;
+
+/// Season and episode patterns in titles, with the season in group 1 and
+/// the episode in group 2
+static EPISODE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        r"(?i)\bS(\d{1,2})\s*E(\d{1,3})\b",
+        r"(?i)\b(\d{1,2})x(\d{2,3})\b",
+        r"(?i)\bseason\s*(\d{1,2})\s*[,.\-]?\s*episode\s*(\d{1,3})\b",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("episode pattern is valid"))
+    .collect()
+});
+
+/// How downloads are named for media centers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaNaming {
+    /// Also write an `.nfo` sidecar next to each episode
+    #[serde(default)]
+    pub nfo: bool,
+}
+
+/// The yt-dlp metadata used for naming
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MediaMetadata {
+    pub id: String,
+    pub title: String,
+    pub series: Option<String>,
+    pub season_number: Option<u32>,
+    pub episode_number: Option<u32>,
+    /// Episode title, on sites that have one apart from the video title
+    pub episode: Option<String>,
+    pub playlist_title: Option<String>,
+    pub playlist_index: Option<u32>,
+    pub channel: Option<String>,
+    pub uploader: Option<String>,
+    /// YYYYMMDD
+    pub upload_date: Option<String>,
+    pub description: Option<String>,
+}
+
+/// A detected episode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeInfo {
+    pub show: String,
+    pub season: u32,
+    pub episode: u32,
+    pub title: String,
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+}
+
+/// Separators left around a title once the episode pattern is cut out
+fn trim_separators(text: &str) -> &str {
+    text.trim_matches(|c: char| c.is_whitespace() || "-_|:.,·–—".contains(c))
+}
+
+/// Season and episode of `metadata`, from series metadata, a pattern in the
+/// title or the playlist position, in that order
+pub fn detect_episode(metadata: &MediaMetadata) -> Option<EpisodeInfo> {
+    let fallback_show = non_empty(&metadata.series)
+        .or_else(|| non_empty(&metadata.playlist_title))
+        .or_else(|| non_empty(&metadata.channel))
+        .or_else(|| non_empty(&metadata.uploader));
+
+    if let (Some(show), Some(season), Some(episode)) =
+        (non_empty(&metadata.series), metadata.season_number, metadata.episode_number)
+    {
+        let title = non_empty(&metadata.episode).unwrap_or_else(|| metadata.title.trim().to_string());
+        return Some(EpisodeInfo { show, season, episode, title });
+    }
+
+    for pattern in EPISODE_PATTERNS.iter() {
+        let Some(captures) = pattern.captures(&metadata.title) else {
+            continue;
+        };
+        let (Ok(season), Ok(episode)) = (captures[1].parse(), captures[2].parse()) else {
+            continue;
+        };
+        let found = captures.get(0).expect("group 0 always matches");
+        let before = trim_separators(&metadata.title[..found.start()]);
+        let after = trim_separators(&metadata.title[found.end()..]);
+        let Some(show) = (!before.is_empty()).then(|| before.to_string()).or_else(|| fallback_show.clone()) else {
+            continue;
+        };
+        let title = if after.is_empty() {
+            format!("Episode {}", episode)
+        } else {
+            after.to_string()
+        };
+        return Some(EpisodeInfo { show, season, episode, title });
+    }
+
+    match (non_empty(&metadata.playlist_title), metadata.playlist_index) {
+        (Some(show), Some(index)) => Some(EpisodeInfo {
+            show,
+            season: metadata.season_number.unwrap_or(1),
+            episode: index,
+            title: metadata.title.trim().to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// `name` made safe as a file name on any filesystem, shortened to
+/// [`MAX_TITLE_CHARS`]
+pub fn clean_name(name: &str) -> String {
+    safe_file_name(name, Some(MAX_TITLE_CHARS))
+}
+
+/// File name for an episode, e.g. "Show - S01E03 - Title.mp4"
+pub fn episode_file_name(episode: &EpisodeInfo, extension: &str) -> String {
+    format!(
+        "{} - S{:02}E{:02} - {}.{}",
+        clean_name(&episode.show),
+        episode.season,
+        episode.episode,
+        clean_name(&episode.title),
+        extension
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .fold(String::with_capacity(text.len()), |mut out, c| {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&apos;"),
+                c => out.push(c),
+            }
+            out
+        })
+}
+
+/// Kodi-style `episodedetails` NFO for an episode
+pub fn episode_nfo(episode: &EpisodeInfo, metadata: &MediaMetadata) -> String {
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<episodedetails>\n");
+    nfo.push_str(&format!("  <title>{}</title>\n", xml_escape(&episode.title)));
+    nfo.push_str(&format!("  <showtitle>{}</showtitle>\n", xml_escape(&episode.show)));
+    nfo.push_str(&format!("  <season>{}</season>\n", episode.season));
+    nfo.push_str(&format!("  <episode>{}</episode>\n", episode.episode));
+    if let Some(date) = metadata.upload_date.as_deref().filter(|date| date.len() == 8 && date.chars().all(|c| c.is_ascii_digit())) {
+        nfo.push_str(&format!("  <aired>{}-{}-{}</aired>\n", &date[..4], &date[4..6], &date[6..]));
+    }
+    if let Some(plot) = non_empty(&metadata.description) {
+        nfo.push_str(&format!("  <plot>{}</plot>\n", xml_escape(&plot)));
+    }
+    nfo.push_str("</episodedetails>\n");
+    nfo
+}
+
+/// Rename a file downloaded under its video ID. Episodes get media center
+/// names (and an NFO if asked for); anything else is named after its title.
+/// An existing file is never overwritten.
+pub fn rename_download(path: &Path, metadata: &MediaMetadata, naming: MediaNaming) -> Result<PathBuf, AppError> {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    let episode = detect_episode(metadata);
+    let name = match &episode {
+        Some(episode) => episode_file_name(episode, &extension),
+        None => format!("{}.{}", clean_name(&metadata.title), extension),
+    };
+    let target = path.with_file_name(name);
+    if target != path {
+        if target.exists() {
+            return Err(AppError::ValidationError(format!("{} already exists", target.display())));
+        }
+        fs::rename(path, &target)?;
+        info!("Renamed {:?} to {:?}", path, target);
+    }
+
+    if let (true, Some(episode)) = (naming.nfo, &episode) {
+        fs::write(target.with_extension("nfo"), episode_nfo(episode, metadata))?;
+    }
+    Ok(target)
+}
+
+/// yt-dlp's metadata for `url`, one entry per video
+pub async fn fetch_metadata(url: &str, use_playlist: bool, request_headers: &RequestHeaders) -> Result<Vec<MediaMetadata>, AppError> {
+    let mut spec = CommandSpec::ytdlp();
+    spec.flag("--dump-json")
+        .flag(if use_playlist { "--yes-playlist" } else { "--no-playlist" })
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options())
+        .operand(url);
+
+    let output = spec.to_async_command()?.output().await.map_err(AppError::IoError)?;
+    if !output.status.success() {
+        return Err(AppError::DownloadError("Failed to get metadata for media center naming".to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Rename the files of a finished download, matched to their metadata by
+/// video ID. Returns where each file is now, in the given order; files that
+/// couldn't be renamed keep their path.
+pub async fn apply_media_naming(
+    url: &str,
+    use_playlist: bool,
+    files: Vec<PathBuf>,
+    naming: MediaNaming,
+    request_headers: &RequestHeaders,
+) -> Result<Vec<PathBuf>, AppError> {
+    let metadata = fetch_metadata(url, use_playlist, request_headers).await?;
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let stem = file.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            let Some(entry) = metadata.iter().find(|entry| entry.id == stem) else {
+                return file;
+            };
+            rename_download(&file, entry, naming).unwrap_or_else(|e| {
+                warn!("Could not rename {:?} for media centers: {}", file, e);
+                file
+            })
+        })
+        .collect())
+}
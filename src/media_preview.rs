@@ -0,0 +1,278 @@
+// src/media_preview.rs
+//! Everything the GUI shows about a URL before it is downloaded: title,
+//! thumbnail, available formats, expected size per quality and warnings
+//! about restricted content.
+
+use crate::downloader::estimate_size_from_metadata;
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::metadata_cache::{fetch_media_info, MetadataCache};
+use crate::proxy::client_builder;
+use base64::{engine::general_purpose, Engine as _};
+use log::debug;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Video qualities offered for download, matching `--quality`
+const PREVIEW_QUALITIES: &[u64] = &[360, 480, 720, 1080, 1440, 2160];
+/// Thumbnails larger than this are not inlined
+const MAX_THUMBNAIL_BYTES: usize = 2 * 1024 * 1024;
+const THUMBNAIL_TIMEOUT_SECS: u64 = 10;
+
+/// Inlined thumbnails (as data URLs) by thumbnail URL
+static THUMBNAILS: Lazy<MetadataCache<String>> = Lazy::new(|| MetadataCache::new(Duration::from_secs(30 * 60), 64));
+
+/// Reasons a video may not download as expected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewWarning {
+    /// Age-gated; cookies from a signed-in browser may be needed
+    AgeRestricted,
+    /// Not available in the current region
+    RegionBlocked,
+    /// Private or otherwise requires signing in
+    LoginRequired,
+    /// Only available to paying members or subscribers
+    PremiumOnly,
+    /// A live stream still in progress
+    Live,
+    /// A scheduled stream or premiere that hasn't started
+    Upcoming,
+}
+
+/// Counts and ranges of the formats a video offers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormatsSummary {
+    pub video_formats: usize,
+    pub audio_formats: usize,
+    pub max_height: Option<u64>,
+    /// Containers offered, e.g. ["mp4", "webm"]
+    pub containers: Vec<String>,
+}
+
+/// Expected download size at one quality
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityEstimate {
+    /// Height in pixels, as passed to `--quality`
+    pub quality: u64,
+    /// Expected bytes for the best video at this quality plus the best audio
+    pub bytes: Option<u64>,
+}
+
+/// Preview of a URL for display before downloading
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaPreview {
+    pub url: String,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub duration_secs: Option<f64>,
+    pub view_count: Option<u64>,
+    pub like_count: Option<u64>,
+    /// Upload date as YYYY-MM-DD
+    pub upload_date: Option<String>,
+    pub thumbnail_url: Option<String>,
+    /// The thumbnail as a `data:` URL, when it could be fetched
+    pub thumbnail_data: Option<String>,
+    pub formats: FormatsSummary,
+    pub size_estimates: Vec<QualityEstimate>,
+    pub chapter_count: usize,
+    pub warnings: Vec<PreviewWarning>,
+    /// False when the metadata couldn't be read, e.g. because the video is
+    /// region-blocked; `warnings` then says why
+    pub available: bool,
+}
+
+fn format_size_of(format: &Value) -> Option<u64> {
+    ["filesize", "filesize_approx"]
+        .iter()
+        .find_map(|key| format[*key].as_u64().or_else(|| format[*key].as_f64().map(|v| v as u64)))
+        .filter(|size| *size > 0)
+}
+
+fn has_stream(format: &Value, key: &str) -> bool {
+    format[key].as_str().is_some_and(|codec| !codec.is_empty() && codec != "none")
+}
+
+fn summarize_formats(formats: &[Value]) -> FormatsSummary {
+    let mut summary = FormatsSummary::default();
+    let mut containers = BTreeSet::new();
+
+    for format in formats {
+        if has_stream(format, "vcodec") {
+            summary.video_formats += 1;
+            summary.max_height = summary.max_height.max(format["height"].as_u64());
+            if let Some(ext) = format["ext"].as_str() {
+                containers.insert(ext.to_string());
+            }
+        } else if has_stream(format, "acodec") {
+            summary.audio_formats += 1;
+        }
+    }
+
+    summary.containers = containers.into_iter().collect();
+    summary
+}
+
+/// Expected sizes for each offered quality up to the best one available.
+/// Video-only formats are counted together with the largest audio format.
+fn estimate_sizes(formats: &[Value], max_height: Option<u64>) -> Vec<QualityEstimate> {
+    let best_audio = formats
+        .iter()
+        .filter(|format| !has_stream(format, "vcodec") && has_stream(format, "acodec"))
+        .filter_map(format_size_of)
+        .max();
+
+    PREVIEW_QUALITIES
+        .iter()
+        .filter(|quality| max_height.is_some_and(|max| **quality <= max))
+        .map(|quality| {
+            let bytes = formats
+                .iter()
+                .filter(|format| has_stream(format, "vcodec"))
+                .filter(|format| format["height"].as_u64().is_some_and(|height| height <= *quality))
+                .filter_map(|format| {
+                    let size = format_size_of(format)?;
+                    let height = format["height"].as_u64()?;
+                    let audio = if has_stream(format, "acodec") { Some(0) } else { best_audio };
+                    Some((height, size + audio?))
+                })
+                .max()
+                .map(|(_, size)| size);
+            QualityEstimate { quality: *quality, bytes }
+        })
+        .collect()
+}
+
+fn warnings_from_info(info: &Value) -> Vec<PreviewWarning> {
+    let mut warnings = Vec::new();
+    if info["age_limit"].as_u64().is_some_and(|limit| limit >= 18) {
+        warnings.push(PreviewWarning::AgeRestricted);
+    }
+    match info["availability"].as_str() {
+        Some("needs_auth") | Some("private") => warnings.push(PreviewWarning::LoginRequired),
+        Some("premium_only") | Some("subscriber_only") => warnings.push(PreviewWarning::PremiumOnly),
+        _ => {}
+    }
+    match info["live_status"].as_str() {
+        Some("is_live") => warnings.push(PreviewWarning::Live),
+        Some("is_upcoming") => warnings.push(PreviewWarning::Upcoming),
+        _ => {}
+    }
+    warnings
+}
+
+/// Recognize why yt-dlp refused to read a video's metadata
+pub fn warning_from_error(stderr: &str) -> Option<PreviewWarning> {
+    let stderr = stderr.to_lowercase();
+    if stderr.contains("available in your country") || stderr.contains("geo restrict") || stderr.contains("geo-restrict") {
+        Some(PreviewWarning::RegionBlocked)
+    } else if stderr.contains("confirm your age") || stderr.contains("age-restricted") || stderr.contains("inappropriate for some users") {
+        Some(PreviewWarning::AgeRestricted)
+    } else if stderr.contains("members-only") || stderr.contains("premium") {
+        Some(PreviewWarning::PremiumOnly)
+    } else if stderr.contains("private video") || stderr.contains("sign in") || stderr.contains("login required") {
+        Some(PreviewWarning::LoginRequired)
+    } else {
+        None
+    }
+}
+
+/// Build a preview from `yt-dlp --dump-json` metadata
+pub fn preview_from_info(url: &str, info: &Value) -> MediaPreview {
+    let formats = info["formats"].as_array().cloned().unwrap_or_default();
+    let summary = summarize_formats(&formats);
+    let mut size_estimates = estimate_sizes(&formats, summary.max_height);
+    // Without a format list, the size of the default selection is all there is
+    if size_estimates.is_empty() {
+        if let (Some(height), Some(bytes)) = (info["height"].as_u64(), estimate_size_from_metadata(info)) {
+            size_estimates.push(QualityEstimate { quality: height, bytes: Some(bytes) });
+        }
+    }
+
+    let upload_date = info["upload_date"].as_str().map(|date| {
+        if date.len() == 8 {
+            format!("{}-{}-{}", &date[0..4], &date[4..6], &date[6..8])
+        } else {
+            date.to_string()
+        }
+    });
+
+    MediaPreview {
+        url: url.to_string(),
+        title: info["title"].as_str().map(|s| s.to_string()),
+        uploader: info["uploader"]
+            .as_str()
+            .or_else(|| info["channel"].as_str())
+            .map(|s| s.to_string()),
+        duration_secs: info["duration"].as_f64(),
+        view_count: info["view_count"].as_u64(),
+        like_count: info["like_count"].as_u64(),
+        upload_date,
+        thumbnail_url: info["thumbnail"].as_str().map(|s| s.to_string()),
+        thumbnail_data: None,
+        formats: summary,
+        size_estimates,
+        chapter_count: info["chapters"].as_array().map_or(0, |chapters| chapters.len()),
+        warnings: warnings_from_info(info),
+        available: true,
+    }
+}
+
+/// Download a thumbnail and encode it as a `data:` URL
+async fn fetch_thumbnail(thumbnail_url: &str) -> Result<String, AppError> {
+    if let Some(data) = THUMBNAILS.get(thumbnail_url) {
+        return Ok(data);
+    }
+
+    let client = client_builder(None)?
+        .timeout(Duration::from_secs(THUMBNAIL_TIMEOUT_SECS))
+        .build()?;
+    let response = client.get(thumbnail_url).send().await?.error_for_status()?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| value.starts_with("image/"))
+        .unwrap_or("image/jpeg")
+        .to_string();
+    let bytes = response.bytes().await?;
+    if bytes.len() > MAX_THUMBNAIL_BYTES {
+        return Err(AppError::ValidationError("Thumbnail is too large to preview".to_string()));
+    }
+
+    let data = format!("data:{};base64,{}", content_type, general_purpose::STANDARD.encode(&bytes));
+    THUMBNAILS.insert(thumbnail_url, data.clone());
+    Ok(data)
+}
+
+/// Preview a URL for the GUI. Videos yt-dlp refuses for a known reason
+/// (region, age, login) come back as unavailable previews with a warning
+/// instead of an error.
+pub async fn get_media_preview(url: &str, request_headers: &RequestHeaders) -> Result<MediaPreview, AppError> {
+    let info = match fetch_media_info(url, request_headers).await {
+        Ok(info) => info,
+        Err(AppError::DownloadError(stderr)) => match warning_from_error(&stderr) {
+            Some(warning) => {
+                return Ok(MediaPreview {
+                    url: url.to_string(),
+                    warnings: vec![warning],
+                    ..MediaPreview::default()
+                })
+            }
+            None => return Err(AppError::DownloadError(stderr)),
+        },
+        Err(e) => return Err(e),
+    };
+
+    let mut preview = preview_from_info(url, &info);
+    if let Some(thumbnail_url) = &preview.thumbnail_url {
+        match fetch_thumbnail(thumbnail_url).await {
+            Ok(data) => preview.thumbnail_data = Some(data),
+            Err(e) => debug!("Could not fetch thumbnail {}: {}", thumbnail_url, e),
+        }
+    }
+    Ok(preview)
+}
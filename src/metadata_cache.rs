@@ -0,0 +1,100 @@
+// src/metadata_cache.rs
+//! Short-lived in-memory cache of media metadata, so repeated lookups of the
+//! same URL (preview, size estimate, download) don't each run yt-dlp.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::process::CommandSpec;
+use crate::video_id::video_key;
+use log::debug;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long cached metadata stays valid; stream URLs in it expire eventually
+const METADATA_TTL: Duration = Duration::from_secs(10 * 60);
+/// Entries kept before the oldest are evicted
+const MAX_ENTRIES: usize = 64;
+
+/// A size-bounded cache whose entries expire after a fixed time
+pub struct MetadataCache<V: Clone> {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> MetadataCache<V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached value for `key`, if present and not expired
+    pub fn get(&self, key: &str) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((stored_at, value)) if stored_at.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        if entries.len() >= self.capacity && !entries.contains_key(key) {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, (stored_at, _))| *stored_at)
+                .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key.to_string(), (Instant::now(), value));
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// `yt-dlp --dump-json` output by video
+static MEDIA_INFO: Lazy<MetadataCache<Value>> = Lazy::new(|| MetadataCache::new(METADATA_TTL, MAX_ENTRIES));
+
+/// Fetch `yt-dlp --dump-json` metadata for a single video, using the cache
+/// when the same video was looked up recently
+pub async fn fetch_media_info(url: &str, request_headers: &RequestHeaders) -> Result<Value, AppError> {
+    let key = video_key(url).archive_key();
+    if let Some(info) = MEDIA_INFO.get(&key) {
+        debug!("Using cached metadata for {}", url);
+        return Ok(info);
+    }
+
+    let mut spec = CommandSpec::ytdlp();
+    spec.flag("--dump-json")
+        .flag("--no-playlist")
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options())
+        .operand(url);
+
+    let output = spec.to_async_command()?.output().await.map_err(AppError::IoError)?;
+    if !output.status.success() {
+        return Err(AppError::DownloadError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let info: Value = serde_json::from_slice(&output.stdout)?;
+    MEDIA_INFO.insert(&key, info.clone());
+    Ok(info)
+}
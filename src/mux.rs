@@ -0,0 +1,107 @@
+// src/mux.rs
+//! Combine a video-only and an audio-only file, as saved by `--no-merge`,
+//! into one file without re-encoding.
+
+use crate::error::AppError;
+use crate::process::CommandSpec;
+use crate::security::validate_path_safety;
+use log::info;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Container that can hold both streams as they are: MP4 for H.264/AAC
+/// pairs, WebM for VP9/AV1 with Opus or Vorbis, Matroska for anything else
+pub fn container_for(video: &Path, audio: &Path) -> &'static str {
+    let extension = |path: &Path| {
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    };
+    match (extension(video).as_str(), extension(audio).as_str()) {
+        ("mp4" | "m4v", "m4a" | "mp4" | "aac") => "mp4",
+        ("webm", "webm" | "opus" | "ogg") => "webm",
+        _ => "mkv",
+    }
+}
+
+/// Output next to the video file, named after it without the ".f<format id>"
+/// suffix `--no-merge` adds: "Title.f137.mp4" + "Title.f140.m4a" give "Title.mp4"
+pub fn default_output(video: &Path, audio: &Path) -> PathBuf {
+    let stem = video
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "muxed".to_string());
+    let stem = match stem.rsplit_once(".f") {
+        Some((title, format_id)) if !title.is_empty() && is_format_id(format_id) => title.to_string(),
+        _ => stem,
+    };
+    video.with_file_name(format!("{}.{}", stem, container_for(video, audio)))
+}
+
+/// Whether `value` looks like a yt-dlp format ID ("137", "hls-1080p")
+/// rather than part of a title such as "Title.final"
+fn is_format_id(value: &str) -> bool {
+    value.chars().any(|c| c.is_ascii_digit()) && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// The ffmpeg invocation copying the first video stream of `video` and the
+/// first audio stream of `audio` into `output`. Never overwrites `output`.
+pub fn mux_command(video: &Path, audio: &Path, output: &Path) -> CommandSpec {
+    let mut command = CommandSpec::ffmpeg();
+    command
+        .flag("-hide_banner")
+        .option("-loglevel", "error")
+        .flag("-n")
+        .option("-i", video.to_string_lossy())
+        .option("-i", audio.to_string_lossy())
+        .option("-map", "0:v:0")
+        .option("-map", "1:a:0")
+        .option("-c", "copy")
+        .operand(output.to_string_lossy());
+    command
+}
+
+/// Combine `video` and `audio` into `output` (or the default name next to
+/// the video) and return the path written
+pub fn mux(video: &Path, audio: &Path, output: Option<&Path>) -> Result<PathBuf, AppError> {
+    let video = canonical_input(video)?;
+    let audio = canonical_input(audio)?;
+    if video == audio {
+        return Err(AppError::ValidationError("The video and audio files must be different".to_string()));
+    }
+
+    let output = match output {
+        Some(path) if path.is_absolute() => path.to_path_buf(),
+        Some(path) => std::env::current_dir()?.join(path),
+        None => default_output(&video, &audio),
+    };
+    if let Some(dir) = output.parent() {
+        validate_path_safety(dir)?;
+    }
+    if output.exists() {
+        return Err(AppError::ValidationError(format!("{} already exists", output.display())));
+    }
+
+    info!("Muxing {:?} and {:?} into {:?}", video, audio, output);
+    let result = mux_command(&video, &audio, &output)
+        .to_command()?
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|_| AppError::MissingDependency("ffmpeg".to_string()))?;
+
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(AppError::General(format!("ffmpeg could not combine the files: {}", stderr.trim())));
+    }
+    Ok(output)
+}
+
+fn canonical_input(path: &Path) -> Result<PathBuf, AppError> {
+    if !path.is_file() {
+        return Err(AppError::ValidationError(format!("File not found: {}", path.display())));
+    }
+    let path = path.canonicalize()?;
+    validate_path_safety(&path)?;
+    Ok(path)
+}
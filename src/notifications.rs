@@ -0,0 +1,173 @@
+// src/notifications.rs
+//! Desktop notifications for finished downloads. On Windows these are native
+//! toasts with buttons to open the file or its folder; elsewhere they go
+//! through the platform notification daemon.
+
+use crate::config::load_config;
+use crate::environment;
+use log::debug;
+use notify_rust::Notification;
+use std::path::Path;
+
+/// Notify that a download finished, if notifications are enabled.
+/// `file_path` is the downloaded file, when it is known.
+pub fn notify_download_complete(format: &str, file_path: Option<&Path>) {
+    if !load_config().map_or(true, |config| config.notifications_enabled()) {
+        return;
+    }
+    if !environment::current().notifications_available() {
+        debug!("No desktop session, skipping the notification");
+        return;
+    }
+
+    let title = "Download Complete";
+    let body = match file_path.and_then(|path| path.file_name()) {
+        Some(name) => format!("{} downloaded successfully.", name.to_string_lossy()),
+        None => format!("{} file downloaded successfully.", format.to_uppercase()),
+    };
+
+    #[cfg(windows)]
+    if let Some(path) = file_path {
+        match windows_toast::show(title, &body, path) {
+            Ok(()) => return,
+            Err(e) => debug!("Toast notification failed, using a plain one: {}", e),
+        }
+    }
+
+    if let Err(e) = Notification::new().summary(title).body(&body).show() {
+        debug!("Could not show notification: {}", e);
+    }
+}
+
+/// Notify that the queue finished its batch (`--when-done notify`). Shown
+/// even when per-download notifications are turned off, since it was asked for.
+pub fn notify_queue_finished(completed: usize, failed: usize) {
+    if !environment::current().notifications_available() {
+        debug!("No desktop session, skipping the notification");
+        return;
+    }
+
+    let body = match failed {
+        0 => format!("{} downloads completed.", completed),
+        _ => format!("{} downloads completed, {} failed.", completed, failed),
+    };
+    if let Err(e) = Notification::new().summary("Queue Finished").body(&body).show() {
+        debug!("Could not show notification: {}", e);
+    }
+}
+
+/// Notify that the queue was paused after repeated network failures
+pub fn notify_queue_held() {
+    show_queue_notification("Downloads Paused", "Several downloads failed with network errors. The queue resumes when the connection is back.");
+}
+
+/// Notify that the network is back and the queue resumed
+pub fn notify_queue_restored() {
+    show_queue_notification("Downloads Resumed", "The connection is back; the queue is running again.");
+}
+
+fn show_queue_notification(title: &str, body: &str) {
+    if !load_config().map_or(true, |config| config.notifications_enabled()) {
+        return;
+    }
+    if !environment::current().notifications_available() {
+        debug!("No desktop session, skipping the notification");
+        return;
+    }
+    if let Err(e) = Notification::new().summary(title).body(body).show() {
+        debug!("Could not show notification: {}", e);
+    }
+}
+
+/// Escape text for an XML attribute or element
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// `file:` URI for a local path
+#[cfg_attr(not(windows), allow(dead_code))]
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded = path.replace('%', "%25").replace(' ', "%20").replace('#', "%23");
+    format!("file:///{}", encoded.trim_start_matches('/'))
+}
+
+/// Toast XML with "Open" and "Show in folder" buttons. The buttons use
+/// protocol activation with `file:` URIs, so they keep working after
+/// Rustloader has exited.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn toast_xml(title: &str, body: &str, file: &Path) -> String {
+    let file_link = file_uri(file);
+    let folder_link = file.parent().map(file_uri).unwrap_or_else(|| file_link.clone());
+
+    format!(
+        concat!(
+            "<toast activationType=\"protocol\" launch=\"{file}\">",
+            "<visual><binding template=\"ToastGeneric\"><text>{title}</text><text>{body}</text></binding></visual>",
+            "<actions>",
+            "<action content=\"Open\" activationType=\"protocol\" arguments=\"{file}\"/>",
+            "<action content=\"Show in folder\" activationType=\"protocol\" arguments=\"{folder}\"/>",
+            "</actions>",
+            "</toast>"
+        ),
+        file = xml_escape(&file_link),
+        folder = xml_escape(&folder_link),
+        title = xml_escape(title),
+        body = xml_escape(body),
+    )
+}
+
+#[cfg(windows)]
+mod windows_toast {
+    use super::toast_xml;
+    use crate::error::AppError;
+    use crate::process::CommandSpec;
+    use base64::{engine::general_purpose, Engine as _};
+    use std::path::Path;
+    use std::process::Stdio;
+
+    /// App ID toasts are shown under; PowerShell's is registered on every
+    /// Windows install, so no Start menu shortcut is needed
+    const APP_ID: &str = "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+    /// Show a toast through the WinRT notification API, driven from PowerShell
+    pub fn show(title: &str, body: &str, file: &Path) -> Result<(), AppError> {
+        // The XML goes in a single-quoted PowerShell string, where only ' needs doubling
+        let xml = toast_xml(title, body, file).replace('\'', "''");
+        let script = format!(
+            concat!(
+                "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null;",
+                "[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] > $null;",
+                "$xml = New-Object Windows.Data.Xml.Dom.XmlDocument;",
+                "$xml.LoadXml('{xml}');",
+                "$toast = New-Object Windows.UI.Notifications.ToastNotification $xml;",
+                "[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{app_id}').Show($toast);"
+            ),
+            xml = xml,
+            app_id = APP_ID,
+        );
+
+        // -EncodedCommand takes UTF-16LE, so nothing in the script is reparsed by a shell
+        let encoded: Vec<u8> = script.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        let status = CommandSpec::system("powershell")
+            .flag("-NoProfile")
+            .flag("-NonInteractive")
+            .option("-WindowStyle", "Hidden")
+            .option("-EncodedCommand", general_purpose::STANDARD.encode(encoded))
+            .to_command()?
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(AppError::General(format!("PowerShell exited with {}", status)))
+        }
+    }
+}
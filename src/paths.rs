@@ -0,0 +1,78 @@
+// src/paths.rs
+//! Base directories for everything Rustloader stores. On Linux these follow
+//! the XDG base directory spec ($XDG_CONFIG_HOME, $XDG_DATA_HOME and the
+//! XDG_DOWNLOAD_DIR user directory); RUSTLOADER_CONFIG_DIR and
+//! RUSTLOADER_DATA_DIR override them on every platform, except for records
+//! like the trial that must not be moved.
+
+use crate::error::AppError;
+use dirs_next as dirs;
+use std::fs;
+use std::path::PathBuf;
+
+/// Overrides the directory holding config.json, the license and recent URLs
+pub const CONFIG_DIR_ENV: &str = "RUSTLOADER_CONFIG_DIR";
+/// Overrides the directory holding the queue state, download counter and other data
+pub const DATA_DIR_ENV: &str = "RUSTLOADER_DATA_DIR";
+
+const APP_DIR: &str = "rustloader";
+
+/// A directory from an environment variable; relative paths are ignored, as
+/// the XDG spec requires for its own variables
+pub fn dir_from_env(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// Configuration directory, created if missing
+pub fn config_dir() -> Result<PathBuf, AppError> {
+    match dir_from_env(CONFIG_DIR_ENV) {
+        Some(path) => {
+            fs::create_dir_all(&path)?;
+            Ok(path)
+        }
+        None => platform_config_dir(),
+    }
+}
+
+/// Data directory for per-machine state, created if missing
+pub fn data_dir() -> Result<PathBuf, AppError> {
+    match dir_from_env(DATA_DIR_ENV) {
+        Some(path) => {
+            fs::create_dir_all(&path)?;
+            Ok(path)
+        }
+        None => platform_data_dir(),
+    }
+}
+
+/// The platform configuration directory, ignoring RUSTLOADER_CONFIG_DIR;
+/// created if missing
+pub fn platform_config_dir() -> Result<PathBuf, AppError> {
+    let path = dirs::config_dir()
+        .ok_or_else(|| AppError::PathError("Could not find config directory".to_string()))?
+        .join(APP_DIR);
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+/// The platform data directory, ignoring RUSTLOADER_DATA_DIR; created if missing
+pub fn platform_data_dir() -> Result<PathBuf, AppError> {
+    let path = dirs::data_local_dir()
+        .ok_or_else(|| AppError::PathError("Could not find local data directory".to_string()))?
+        .join(APP_DIR);
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+/// The user's download directory (XDG_DOWNLOAD_DIR on Linux), or
+/// ~/Downloads when the platform doesn't define one
+pub fn user_download_dir() -> Option<PathBuf> {
+    dirs::download_dir().or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+}
+
+/// Folder downloads go to when no output directory is set
+pub fn default_download_dir() -> Option<PathBuf> {
+    user_download_dir().map(|downloads| downloads.join(APP_DIR))
+}
@@ -0,0 +1,75 @@
+// src/player.rs
+//! `rustloader play`: hand a download to an external player while it is
+//! still downloading. The player is pointed at the growing file, so the
+//! download uses a single-file format; a merged one only becomes playable
+//! once the merge is done.
+
+use crate::cleanup::scan_partial_files;
+use crate::download_manager::DownloadItem;
+use crate::error::AppError;
+use std::path::{Path, PathBuf};
+
+/// Players looked for on the PATH, in order, when none is configured
+pub const KNOWN_PLAYERS: [&str; 2] = ["mpv", "vlc"];
+
+/// Format for played downloads: one file with audio and video, so the
+/// player can start on it before the download finishes
+pub const PLAY_FORMAT: &str = "best[ext=mp4]/best";
+
+/// How much has to be downloaded before the player is started, so it
+/// doesn't catch up with the download right away
+pub const MIN_PLAYABLE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Find the player to launch: the configured one (a name on the PATH or a
+/// path to the program), otherwise the first known player installed
+pub fn find_player(configured: Option<&str>) -> Result<PathBuf, AppError> {
+    match configured.map(str::trim).filter(|player| !player.is_empty()) {
+        Some(player) => {
+            let path = Path::new(player);
+            if path.components().count() > 1 {
+                return if path.is_file() {
+                    Ok(path.to_path_buf())
+                } else {
+                    Err(AppError::ValidationError(format!("Player not found: {}", player)))
+                };
+            }
+            find_on_path(player)
+                .ok_or_else(|| AppError::ValidationError(format!("Player '{}' is not on the PATH", player)))
+        }
+        None => KNOWN_PLAYERS.iter().find_map(|player| find_on_path(player)).ok_or_else(|| {
+            AppError::ValidationError(format!(
+                "No player found; install {} or set \"player\" in config.json",
+                KNOWN_PLAYERS.join(" or ")
+            ))
+        }),
+    }
+}
+
+/// Look a program up in the directories on the PATH
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let candidate = dir.join(format!("{}.exe", program));
+        (cfg!(windows) && candidate.is_file()).then_some(candidate)
+    })
+}
+
+/// The file a player can be pointed at for a download: the finished file
+/// once it's done, otherwise its partial file once enough of it is there
+pub fn playable_file(item: &DownloadItem) -> Option<PathBuf> {
+    if item.is_completed() {
+        return item.output_path.as_ref().map(PathBuf::from).filter(|path| path.is_file());
+    }
+    scan_partial_files(std::slice::from_ref(item))
+        .ok()?
+        .into_iter()
+        .filter(|file| file.download.as_ref().is_some_and(|download| download.id == item.id))
+        .filter(|file| file.path.extension().is_some_and(|ext| ext == "part"))
+        .filter(|file| file.size >= MIN_PLAYABLE_BYTES)
+        .max_by_key(|file| file.size)
+        .map(|file| file.path)
+}
@@ -0,0 +1,88 @@
+// src/playlist.rs
+//! Playlist expansion for `--playlist --add-to-queue`. The playlist is
+//! listed with `yt-dlp --flat-playlist -J`, which reads only the listing
+//! pages, and every video in it is queued as its own download.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::process::CommandSpec;
+use serde_json::Value;
+
+/// A video listed in a playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    pub url: String,
+    pub id: Option<String>,
+    pub title: Option<String>,
+}
+
+/// The videos of a playlist, in playlist order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playlist {
+    pub title: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// The playlist in `yt-dlp --flat-playlist -J` output. Entries yt-dlp
+/// couldn't list (deleted or private videos) and nested playlists such as
+/// a channel's tabs are left out.
+pub fn parse_flat_playlist(info: &Value) -> Playlist {
+    let text = |value: &Value| value.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let entries = info["entries"]
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|entry| entry.is_object() && entry["_type"].as_str() != Some("playlist"))
+                .filter_map(|entry| {
+                    let id = text(&entry["id"]);
+                    let url = entry_url(entry, id.as_deref())?;
+                    Some(PlaylistEntry {
+                        url,
+                        id,
+                        title: text(&entry["title"]),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Playlist {
+        title: text(&info["title"]),
+        entries,
+    }
+}
+
+/// The entry's web page. Flat YouTube entries may carry only the video ID.
+fn entry_url(entry: &Value, id: Option<&str>) -> Option<String> {
+    let is_web = |url: &&str| url.starts_with("https://") || url.starts_with("http://");
+    entry["url"]
+        .as_str()
+        .filter(is_web)
+        .or_else(|| entry["webpage_url"].as_str().filter(is_web))
+        .map(str::to_string)
+        .or_else(|| match (entry["ie_key"].as_str(), id) {
+            (Some("Youtube"), Some(id)) => Some(format!("https://www.youtube.com/watch?v={}", id)),
+            _ => None,
+        })
+}
+
+/// List the videos of the playlist at `url` without downloading them
+pub async fn fetch_playlist(url: &str, request_headers: &RequestHeaders) -> Result<Playlist, AppError> {
+    let mut spec = CommandSpec::ytdlp();
+    spec.flag("--flat-playlist")
+        .flag("--dump-single-json")
+        .flag("--yes-playlist")
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options())
+        .operand(url);
+
+    let output = spec.to_async_command()?.output().await.map_err(AppError::IoError)?;
+    if !output.status.success() {
+        return Err(AppError::DownloadError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let info: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(parse_flat_playlist(&info))
+}
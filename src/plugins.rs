@@ -0,0 +1,348 @@
+// src/plugins.rs
+//! External plugins: executables in the plugins directory that extend
+//! Rustloader as URL providers, post-processors or notifiers.
+//!
+//! Each plugin lives in its own folder under `<data dir>/plugins` with a
+//! `plugin.json` manifest naming its executable and capabilities. For every
+//! request Rustloader starts the executable in that folder, writes one JSON
+//! object to its stdin and reads one JSON object back from its stdout:
+//!
+//! ```text
+//! -> {"abi":1,"action":"resolve","url":"https://example.com/v/1"}
+//! <- {"ok":true,"url":"https://cdn.example.com/1.mp4"}
+//! -> {"abi":1,"action":"post_process","file":"/home/me/Downloads/a.mp4","format":"mp4","url":"..."}
+//! <- {"ok":true,"file":"/home/me/Downloads/a.mkv"}
+//! -> {"abi":1,"action":"notify","event":"download_complete","file":null,"format":"mp3","url":"..."}
+//! <- {"ok":false,"error":"webhook unreachable"}
+//! ```
+//!
+//! Plugins get a minimal environment, a time limit and a cap on their output;
+//! see the plugin rules in `security.rs`.
+
+use crate::error::AppError;
+use crate::paths::data_dir;
+use crate::process::CommandSpec;
+use crate::security::{
+    validate_plugin_executable, validate_plugin_output, PLUGIN_ENV_ALLOWLIST, PLUGIN_MAX_OUTPUT_BYTES,
+    PLUGIN_MAX_TIMEOUT,
+};
+use log::{debug, warn};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Version of the stdin/stdout contract plugins are written against
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// Name of the manifest in each plugin folder
+pub const MANIFEST_FILE: &str = "plugin.json";
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// What a plugin can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PluginCapability {
+    /// Turns page URLs on its hosts into downloadable URLs
+    Provider,
+    /// Processes finished downloads
+    PostProcessor,
+    /// Is told about finished downloads
+    Notifier,
+}
+
+impl PluginCapability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginCapability::Provider => "provider",
+            PluginCapability::PostProcessor => "post-processor",
+            PluginCapability::Notifier => "notifier",
+        }
+    }
+}
+
+/// Contents of `plugin.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    /// Contract version the plugin implements
+    pub abi: u32,
+    /// Path of the executable, relative to the plugin folder
+    pub executable: String,
+    pub capabilities: Vec<PluginCapability>,
+    /// Hosts a provider handles, e.g. "example.com" (subdomains included)
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Seconds a single request may take
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl PluginManifest {
+    /// Check the manifest is complete and consistent
+    pub fn validate(&self) -> Result<(), AppError> {
+        let invalid = |reason: String| Err(AppError::ValidationError(format!("Plugin '{}': {}", self.name, reason)));
+
+        if !is_valid_plugin_name(&self.name) {
+            return invalid("name must be lowercase letters, digits and dashes".to_string());
+        }
+        if self.abi != PLUGIN_ABI_VERSION {
+            return invalid(format!("built for plugin ABI {}, this Rustloader supports {}", self.abi, PLUGIN_ABI_VERSION));
+        }
+        if self.capabilities.is_empty() {
+            return invalid("declares no capabilities".to_string());
+        }
+        let is_provider = self.has(PluginCapability::Provider);
+        if is_provider && self.hosts.is_empty() {
+            return invalid("a provider must list the hosts it handles".to_string());
+        }
+        if !is_provider && !self.hosts.is_empty() {
+            return invalid("only providers may list hosts".to_string());
+        }
+        if !self.hosts.iter().all(|host| is_valid_host_pattern(host)) {
+            return invalid("hosts must be plain domain names like example.com".to_string());
+        }
+        match self.timeout_secs {
+            Some(0) => invalid("timeout must be at least one second".to_string()),
+            Some(secs) if secs > PLUGIN_MAX_TIMEOUT.as_secs() => {
+                invalid(format!("timeout may be at most {}s", PLUGIN_MAX_TIMEOUT.as_secs()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn has(&self, capability: PluginCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Whether a provider handles this URL
+    pub fn handles_url(&self, url: &str) -> bool {
+        url_matches_hosts(url, &self.hosts)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+/// A request sent to a plugin
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PluginRequest {
+    Resolve { url: String },
+    PostProcess { file: PathBuf, format: String, url: String },
+    Notify { event: String, file: Option<PathBuf>, format: String, url: String },
+}
+
+/// A plugin's answer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PluginResponse {
+    pub ok: bool,
+    pub error: Option<String>,
+    /// Resolved URL, from providers
+    pub url: Option<String>,
+    /// Processed file, from post-processors that produced a new one
+    pub file: Option<PathBuf>,
+}
+
+/// An installed plugin with a valid manifest
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub manifest: PluginManifest,
+    pub dir: PathBuf,
+    executable: PathBuf,
+}
+
+impl Plugin {
+    /// Load and validate the plugin in `dir`
+    pub fn load(dir: &Path) -> Result<Self, AppError> {
+        let contents = std::fs::read_to_string(dir.join(MANIFEST_FILE))?;
+        let manifest: PluginManifest = serde_json::from_str(&contents)?;
+        manifest.validate()?;
+        let executable = validate_plugin_executable(dir, &manifest.executable)?;
+        Ok(Self {
+            manifest,
+            dir: dir.to_path_buf(),
+            executable,
+        })
+    }
+
+    /// Send a request and wait for the answer
+    pub async fn call(&self, request: &PluginRequest) -> Result<PluginResponse, AppError> {
+        let name = &self.manifest.name;
+        let mut payload = serde_json::to_value(request)?;
+        payload["abi"] = PLUGIN_ABI_VERSION.into();
+
+        let mut command = CommandSpec::plugin(&self.executable).to_async_command()?;
+        command
+            .current_dir(&self.dir)
+            .env_clear()
+            .envs(PLUGIN_ENV_ALLOWLIST.iter().filter_map(|var| std::env::var_os(var).map(|value| (var, value))))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        let mut child = command.spawn()?;
+
+        let exchange = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(format!("{}\n", payload).as_bytes()).await?;
+            }
+            let mut output = Vec::new();
+            if let Some(stdout) = child.stdout.take() {
+                stdout.take(PLUGIN_MAX_OUTPUT_BYTES as u64 + 1).read_to_end(&mut output).await?;
+            }
+            let status = child.wait().await?;
+            Ok::<_, std::io::Error>((status, output))
+        };
+        let (status, output) = tokio::time::timeout(self.manifest.timeout(), exchange)
+            .await
+            .map_err(|_| AppError::General(format!("Plugin '{}' timed out", name)))??;
+
+        if output.len() > PLUGIN_MAX_OUTPUT_BYTES {
+            return Err(AppError::General(format!("Plugin '{}' wrote too much output", name)));
+        }
+        if !status.success() {
+            return Err(AppError::General(format!("Plugin '{}' exited with {}", name, status)));
+        }
+        let response: PluginResponse = serde_json::from_slice(&output)?;
+        if !response.ok {
+            return Err(AppError::General(format!(
+                "Plugin '{}' failed: {}",
+                name,
+                response.error.as_deref().unwrap_or("no reason given")
+            )));
+        }
+        Ok(response)
+    }
+}
+
+/// Plugin names are lowercase letters, digits and dashes
+pub(crate) fn is_valid_plugin_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Hosts are plain domain names like example.com
+pub(crate) fn is_valid_host_pattern(host: &str) -> bool {
+    !host.is_empty() && !host.contains(['/', ':', '*'])
+}
+
+/// Whether the URL's host is one of `hosts` or a subdomain of one
+pub(crate) fn url_matches_hosts(url: &str, hosts: &[String]) -> bool {
+    let Some(host) = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_lowercase)) else {
+        return false;
+    };
+    hosts.iter().any(|pattern| {
+        let pattern = pattern.to_lowercase();
+        host == pattern || host.ends_with(&format!(".{}", pattern))
+    })
+}
+
+/// Folder plugins are installed in
+pub fn plugins_dir() -> Result<PathBuf, AppError> {
+    Ok(data_dir()?.join("plugins"))
+}
+
+/// Every folder in `root` with a manifest, and whether its plugin is usable
+pub fn discover_plugins(root: &Path) -> Vec<(PathBuf, Result<Plugin, AppError>)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(PathBuf, Result<Plugin, AppError>)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .map(|path| {
+            let plugin = Plugin::load(&path);
+            (path, plugin)
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+/// The usable plugins with a capability. Broken plugins are skipped with a warning.
+pub fn plugins_with(capability: PluginCapability) -> Vec<Plugin> {
+    let Ok(root) = plugins_dir() else {
+        return Vec::new();
+    };
+    discover_plugins(&root)
+        .into_iter()
+        .filter_map(|(path, plugin)| match plugin {
+            Ok(plugin) => Some(plugin),
+            Err(e) => {
+                warn!("Skipping plugin in {:?}: {}", path, e);
+                None
+            }
+        })
+        .filter(|plugin| plugin.manifest.has(capability))
+        .collect()
+}
+
+/// Let the first provider that handles the URL resolve it. The URL is
+/// returned unchanged when no provider applies or the provider fails.
+pub async fn resolve_with_plugins(url: &str) -> String {
+    for plugin in plugins_with(PluginCapability::Provider) {
+        if !plugin.manifest.handles_url(url) {
+            continue;
+        }
+        match plugin.call(&PluginRequest::Resolve { url: url.to_string() }).await {
+            Ok(PluginResponse { url: Some(resolved), .. }) if crate::utils::validate_url(&resolved).is_ok() => {
+                debug!("Plugin '{}' resolved {} to {}", plugin.manifest.name, url, resolved);
+                return resolved;
+            }
+            Ok(_) => warn!("Plugin '{}' returned no usable URL for {}", plugin.manifest.name, url),
+            Err(e) => warn!("{}", e),
+        }
+    }
+    #[cfg(feature = "wasm-plugins")]
+    if let Some(resolved) = crate::wasm_plugins::resolve_with_wasm(url).await {
+        return resolved;
+    }
+    url.to_string()
+}
+
+/// Run every post-processor over a finished download in turn. Returns the
+/// final file, which stays inside `download_dir`.
+pub async fn post_process_with_plugins(file: &Path, format: &str, url: &str, download_dir: &Path) -> PathBuf {
+    let mut current = file.to_path_buf();
+    for plugin in plugins_with(PluginCapability::PostProcessor) {
+        let request = PluginRequest::PostProcess {
+            file: current.clone(),
+            format: format.to_string(),
+            url: url.to_string(),
+        };
+        match plugin.call(&request).await {
+            Ok(PluginResponse { file: Some(new_file), .. }) => match validate_plugin_output(&new_file, download_dir) {
+                Ok(new_file) => current = new_file,
+                Err(e) => warn!("Ignoring file from plugin '{}': {}", plugin.manifest.name, e),
+            },
+            Ok(_) => {}
+            Err(e) => warn!("{}", e),
+        }
+    }
+    #[cfg(feature = "wasm-plugins")]
+    {
+        current = crate::wasm_plugins::apply_filename_rules(&current, format, url).await;
+        crate::wasm_plugins::enrich_metadata(&current, format, url).await;
+    }
+    current
+}
+
+/// Tell the notifier plugins a download finished
+pub async fn notify_plugins(file: Option<&Path>, format: &str, url: &str) {
+    for plugin in plugins_with(PluginCapability::Notifier) {
+        let request = PluginRequest::Notify {
+            event: "download_complete".to_string(),
+            file: file.map(Path::to_path_buf),
+            format: format.to_string(),
+            url: url.to_string(),
+        };
+        if let Err(e) = plugin.call(&request).await {
+            warn!("{}", e);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// src/power.rs
+//! System sleep and wake detection, so the queue can stop its yt-dlp
+//! children before the machine suspends and restart them on wake instead of
+//! waiting for the stall detector to notice dead connections.
+//!
+//! Wake-ups are detected everywhere by comparing the wall clock with the
+//! monotonic clock, which stops while the system is suspended. On Linux the
+//! logind `PrepareForSleep` signal also announces an imminent suspend.
+
+use log::{debug, warn};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+
+/// How often the clocks are compared
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Wall-clock time beyond the monotonic clock that counts as a suspend;
+/// well above scheduler delays and NTP adjustments
+pub const SLEEP_GAP_THRESHOLD: Duration = Duration::from_secs(20);
+
+/// A change in the system's power state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// The system is about to suspend
+    Suspending,
+    /// The system woke up
+    Resumed,
+}
+
+/// Time the system spent suspended between two clock readings, if any.
+/// `wall` may be None when the wall clock went backwards.
+pub fn sleep_gap(wall: Option<Duration>, monotonic: Duration) -> Option<Duration> {
+    let gap = wall?.checked_sub(monotonic)?;
+    (gap >= SLEEP_GAP_THRESHOLD).then_some(gap)
+}
+
+/// Reads `dbus-monitor` output for logind's `PrepareForSleep(bool)` signal,
+/// whose argument follows on the line after the signal header
+#[derive(Debug, Default)]
+pub struct LogindParser {
+    in_prepare_for_sleep: bool,
+}
+
+impl LogindParser {
+    pub fn feed(&mut self, line: &str) -> Option<PowerEvent> {
+        let line = line.trim();
+        if line.starts_with("signal ") {
+            self.in_prepare_for_sleep = line.contains("member=PrepareForSleep");
+            return None;
+        }
+        if !self.in_prepare_for_sleep {
+            return None;
+        }
+        self.in_prepare_for_sleep = false;
+        match line {
+            "boolean true" => Some(PowerEvent::Suspending),
+            "boolean false" => Some(PowerEvent::Resumed),
+            _ => None,
+        }
+    }
+}
+
+/// Start watching for sleep and wake. Events arrive on the returned
+/// channel; a wake may be reported by both sources.
+pub fn watch_power_events() -> mpsc::UnboundedReceiver<PowerEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    spawn_clock_watcher(tx.clone());
+    #[cfg(target_os = "linux")]
+    spawn_logind_watcher(tx);
+    rx
+}
+
+fn spawn_clock_watcher(tx: mpsc::UnboundedSender<PowerEvent>) {
+    tokio::spawn(async move {
+        let mut last_wall = SystemTime::now();
+        let mut last_monotonic = Instant::now();
+        loop {
+            tokio::time::sleep(CLOCK_CHECK_INTERVAL).await;
+            let (wall, monotonic) = (SystemTime::now(), Instant::now());
+            if let Some(gap) = sleep_gap(wall.duration_since(last_wall).ok(), monotonic - last_monotonic) {
+                debug!("System was asleep for about {}s", gap.as_secs());
+                if tx.send(PowerEvent::Resumed).is_err() {
+                    return;
+                }
+            }
+            last_wall = wall;
+            last_monotonic = monotonic;
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_logind_watcher(tx: mpsc::UnboundedSender<PowerEvent>) {
+    use crate::error::AppError;
+    use crate::process::CommandSpec;
+    use std::process::Stdio;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    tokio::spawn(async move {
+        let child = CommandSpec::system("dbus-monitor")
+            .flag("--system")
+            .operand("type='signal',interface='org.freedesktop.login1.Manager',member='PrepareForSleep'")
+            .to_async_command()
+            .and_then(|mut command| {
+                command
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map_err(AppError::IoError)
+            });
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                debug!("dbus-monitor unavailable, sleep is only detected after wake: {}", e);
+                return;
+            }
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+
+        let mut parser = LogindParser::default();
+        let mut lines = BufReader::new(stdout).lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    if let Some(event) = parser.feed(&line) {
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Could not read logind sleep signals: {}", e);
+                    break;
+                }
+            }
+        }
+        let _ = child.wait().await;
+    });
+}
@@ -0,0 +1,466 @@
+// src/process.rs
+//! Construction of external commands.
+//!
+//! Every external program Rustloader runs (yt-dlp, ffmpeg, the tools used
+//! to find and install them, players, plugins and the system programs behind
+//! power actions, notifications and file actions) goes through
+//! [`CommandSpec`], which only accepts known flags, refuses option values that could be read as another
+//! option (a crafted title or URL starting with `-`), and places operands
+//! after a `--` separator where the program supports one.
+
+use crate::error::AppError;
+use log::warn;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tokio::process::Command as AsyncCommand;
+
+/// Flags Rustloader passes to yt-dlp
+const YTDLP_FLAGS: &[&str] = &[
+    "--add-header",
+    "--audio-format",
+    "--audio-quality",
+    "--buffer-size",
+    "--concurrent-fragments",
+    "--downloader",
+    "--downloader-args",
+    "--download-sections",
+    "--dump-json",
+    "--dump-single-json",
+    "--embed-metadata",
+    "--extract-audio",
+    "--extractor-args",
+    "--flat-playlist",
+    "--fragment-retries",
+    "--geo-bypass-country",
+    "--get-title",
+    "--limit-rate",
+    "--max-filesize",
+    "--newline",
+    "--no-continue",
+    "--no-part-file",
+    "--no-playlist",
+    "--paths",
+    "--postprocessor-args",
+    "--progress-template",
+    "--proxy",
+    "--recode-video",
+    "--retries",
+    "--socket-timeout",
+    "--sub-langs",
+    "--throttled-rate",
+    "--update",
+    "--user-agent",
+    "--verbose",
+    "--version",
+    "--write-subs",
+    "--yes-playlist",
+    "-f",
+    "-o",
+];
+
+/// Flags Rustloader passes to ffmpeg
+const FFMPEG_FLAGS: &[&str] = &[
+    "-af",
+    "-b:v",
+    "-c",
+    "-c:a",
+    "-c:v",
+    "-crf",
+    "-f",
+    "-frames:v",
+    "-headers",
+    "-hide_banner",
+    "-i",
+    "-loglevel",
+    "-map",
+    "-n",
+    "-nostats",
+    "-preset",
+    "-q:v",
+    "-ss",
+    "-t",
+    "-to",
+    "-version",
+    "-vf",
+];
+
+/// Flags accepted when only checking that a program runs
+const VERSION_FLAGS: &[&str] = &["--version", "-version"];
+
+/// Package managers dependencies are looked up and installed with
+const PACKAGE_MANAGERS: &[&str] = &[
+    "apt", "apt-file", "apt-get", "brew", "choco", "dnf", "dpkg", "flatpak", "pacman", "port", "rpm", "scoop",
+    "snap", "winget", "yum", "zypper",
+];
+
+/// Subcommands and flags passed to package managers
+const PACKAGE_MANAGER_FLAGS: &[&str] = &[
+    "--noconfirm", "-i", "-L", "-l", "-Q", "-Ql", "-Qo", "-q", "-ql", "-S", "-y", "info", "install", "list",
+    "policy", "repoquery", "se",
+];
+
+/// Programs pip is run as, directly or as `python -m pip`
+const PIP_PROGRAMS: &[&str] = &["pip", "pip3", "python", "python3"];
+const PYTHONS: &[&str] = &["python", "python3"];
+
+/// Subcommands and flags passed to pip
+const PIP_FLAGS: &[&str] = &["--upgrade", "--user", "install"];
+
+/// Flags passed to curl when downloading a release
+const CURL_FLAGS: &[&str] = &["-fsSL", "-o"];
+
+/// Subcommands and flags passed to rustup
+const RUSTUP_FLAGS: &[&str] = &["--version", "update"];
+
+/// System programs behind power actions, desktop integration, Windows
+/// toasts and the macOS Gatekeeper checks
+const SYSTEM_PROGRAMS: &[&str] = &[
+    "codesign", "dbus-monitor", "gdbus", "osascript", "pmset", "powershell", "rundll32.exe", "shutdown", "systemctl",
+    "xattr",
+];
+
+/// Subcommands and flags passed to system programs
+const SYSTEM_FLAGS: &[&str] = &[
+    "--object-path", "--session", "--signal", "--strict", "--system", "--verify", "-EncodedCommand", "-NoProfile",
+    "-NonInteractive", "-WindowStyle", "-d", "-e", "-h", "-p", "emit", "sleepnow", "suspend",
+];
+
+/// Flags passed to the platform file opener
+const OPENER_FLAGS: &[&str] = &["-R"];
+
+/// A validated external command
+#[derive(Debug, Clone)]
+pub struct CommandSpec {
+    program: String,
+    allowed_flags: &'static [&'static str],
+    /// Whether the program understands `--` as the end of options
+    separator: bool,
+    args: Vec<String>,
+    operands: Vec<String>,
+    /// Run through `sudo`
+    as_root: bool,
+    error: Option<String>,
+}
+
+impl CommandSpec {
+    fn new(program: &str, allowed_flags: &'static [&'static str], separator: bool) -> Self {
+        let mut spec = Self {
+            program: program.to_string(),
+            allowed_flags,
+            separator,
+            args: Vec::new(),
+            operands: Vec::new(),
+            as_root: false,
+            error: None,
+        };
+        if program.is_empty() || program.starts_with('-') {
+            spec.reject(format!("invalid program name {:?}", program));
+        }
+        spec
+    }
+
+    /// A yt-dlp command
+    pub fn ytdlp() -> Self {
+        Self::new("yt-dlp", YTDLP_FLAGS, true)
+    }
+
+    /// An ffmpeg command. ffmpeg has no `--` separator, so its operands are
+    /// held to the same rules as option values.
+    pub fn ffmpeg() -> Self {
+        Self::new("ffmpeg", FFMPEG_FLAGS, false)
+    }
+
+    /// Run `program --version` (or `-version` for ffmpeg-style tools) to
+    /// check it is usable
+    pub fn version_probe(program: &str, version_flag: &str) -> Self {
+        let mut spec = Self::new(program, VERSION_FLAGS, false);
+        spec.flag(version_flag);
+        spec
+    }
+
+    /// Look `name` up on the PATH with `which`, or `where` on Windows
+    pub fn locate(name: &str) -> Self {
+        let program = if cfg!(target_os = "windows") { "where" } else { "which" };
+        let mut spec = Self::new(program, &[], false);
+        spec.operand(name);
+        spec
+    }
+
+    /// A package manager query or install. Few package managers take `--`,
+    /// so package names are held to the same rules as option values.
+    pub fn package_manager(program: &str) -> Self {
+        let mut spec = Self::new(program, PACKAGE_MANAGER_FLAGS, false);
+        if !PACKAGE_MANAGERS.contains(&program) {
+            spec.reject(format!("{} is not a known package manager", program));
+        }
+        spec
+    }
+
+    /// pip, run directly or as `python -m pip`
+    pub fn pip(program: &str) -> Self {
+        let mut spec = Self::new(program, PIP_FLAGS, false);
+        if !PIP_PROGRAMS.contains(&program) {
+            spec.reject(format!("{} is not a known pip", program));
+        } else if PYTHONS.contains(&program) {
+            spec.args.extend(["-m".to_string(), "pip".to_string()]);
+        }
+        spec
+    }
+
+    /// A Python module run as `python -m <module>`, only to check its version
+    pub fn python_module(python: &str, module: &str) -> Self {
+        let mut spec = Self::new(python, VERSION_FLAGS, false);
+        if !PYTHONS.contains(&python) {
+            spec.reject(format!("{} is not a known Python", python));
+        } else if spec.check_value("-m", module) {
+            spec.args.extend(["-m".to_string(), module.to_string()]);
+        }
+        spec
+    }
+
+    /// A curl download
+    pub fn curl() -> Self {
+        Self::new("curl", CURL_FLAGS, true)
+    }
+
+    /// A rustup command
+    pub fn rustup() -> Self {
+        Self::new("rustup", RUSTUP_FLAGS, false)
+    }
+
+    /// A media player pointed at a file. mpv and VLC both take `--`, so the
+    /// file is never read as an option, whatever it is named.
+    pub fn player(program: &Path) -> Self {
+        Self::from_path(program, &[], true)
+    }
+
+    /// A plugin executable, which gets its request on stdin and no arguments
+    pub fn plugin(executable: &Path) -> Self {
+        Self::from_path(executable, &[], false)
+    }
+
+    /// A system program, e.g. `systemctl suspend` or `gdbus emit`
+    pub fn system(program: &str) -> Self {
+        let mut spec = Self::new(program, SYSTEM_FLAGS, false);
+        if !SYSTEM_PROGRAMS.contains(&program) {
+            spec.reject(format!("{} is not a known system program", program));
+        }
+        spec
+    }
+
+    /// The platform's file opener: `open` on macOS, `explorer` on Windows and
+    /// `xdg-open` elsewhere. None of them take `--`, so paths are held to the
+    /// same rules as option values.
+    pub fn opener() -> Self {
+        let program = if cfg!(target_os = "macos") {
+            "open"
+        } else if cfg!(target_os = "windows") {
+            "explorer"
+        } else {
+            "xdg-open"
+        };
+        Self::new(program, OPENER_FLAGS, false)
+    }
+
+    /// A command the user wrote themselves, e.g. for `--when-done run`. The
+    /// arguments are theirs to choose, so they are passed as written.
+    pub fn user_command(program: &str, args: &[String]) -> Self {
+        let mut spec = Self::new(program, &[], false);
+        spec.args.extend(args.iter().cloned());
+        spec
+    }
+
+    fn from_path(program: &Path, allowed_flags: &'static [&'static str], separator: bool) -> Self {
+        match program.to_str() {
+            Some(program) => Self::new(program, allowed_flags, separator),
+            None => {
+                let mut spec = Self::new(&program.to_string_lossy(), allowed_flags, separator);
+                spec.reject(format!("program path is not valid UTF-8: {}", program.display()));
+                spec
+            }
+        }
+    }
+
+    /// Run the command through `sudo`
+    pub fn as_root(&mut self) -> &mut Self {
+        self.as_root = true;
+        self
+    }
+
+    /// Add a flag without a value
+    pub fn flag(&mut self, flag: &str) -> &mut Self {
+        if self.check_flag(flag) {
+            self.args.push(flag.to_string());
+        }
+        self
+    }
+
+    /// Add a flag followed by its value
+    pub fn option(&mut self, flag: &str, value: impl AsRef<str>) -> &mut Self {
+        let value = value.as_ref();
+        if self.check_flag(flag) && self.check_value(flag, value) {
+            self.args.push(flag.to_string());
+            self.args.push(value.to_string());
+        }
+        self
+    }
+
+    /// Add several flag/value pairs
+    pub fn options<I, V>(&mut self, options: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (&'static str, V)>,
+        V: AsRef<str>,
+    {
+        for (flag, value) in options {
+            self.option(flag, value);
+        }
+        self
+    }
+
+    /// Add arguments in the order a lookup table lists them: allowed flags
+    /// and subcommands as they are, anything else as an operand
+    pub fn args(&mut self, args: &[&str]) -> &mut Self {
+        for arg in args {
+            if arg.starts_with('-') || self.allowed_flags.contains(arg) {
+                self.flag(arg);
+            } else {
+                self.operand(arg);
+            }
+        }
+        self
+    }
+
+    /// Add a positional argument such as a URL or output file
+    pub fn operand(&mut self, value: impl AsRef<str>) -> &mut Self {
+        let value = value.as_ref();
+        // "-" is the conventional name for stdout
+        if self.separator || value == "-" || self.check_value("operand", value) {
+            if value.contains('\0') {
+                self.reject("operand contains a NUL byte".to_string());
+            } else {
+                self.operands.push(value.to_string());
+            }
+        }
+        self
+    }
+
+    /// The program to run
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// The full argument list, with operands after `--` when supported
+    pub fn to_args(&self) -> Result<Vec<String>, AppError> {
+        if let Some(reason) = &self.error {
+            warn!("Refusing to run {}: {}", self.program, reason);
+            return Err(AppError::SecurityViolation);
+        }
+
+        let mut args = self.args.clone();
+        if self.separator && !self.operands.is_empty() {
+            args.push("--".to_string());
+        }
+        args.extend(self.operands.iter().cloned());
+        Ok(args)
+    }
+
+    /// The program to start and its arguments, with `sudo --` in front when
+    /// run as root
+    fn argv(&self) -> Result<(&str, Vec<String>), AppError> {
+        let args = self.to_args()?;
+        if !self.as_root {
+            return Ok((&self.program, args));
+        }
+        let mut sudo_args = vec!["--".to_string(), self.program.clone()];
+        sudo_args.extend(args);
+        Ok(("sudo", sudo_args))
+    }
+
+    /// Build a blocking command
+    pub fn to_command(&self) -> Result<Command, AppError> {
+        let (program, args) = self.argv()?;
+        let mut command = Command::new(program);
+        command.args(args);
+        Ok(command)
+    }
+
+    /// Build a tokio command
+    pub fn to_async_command(&self) -> Result<AsyncCommand, AppError> {
+        let (program, args) = self.argv()?;
+        let mut command = AsyncCommand::new(program);
+        command.args(args);
+        // Pausing or cancelling a queued download aborts its task; the
+        // child must not outlive it
+        command.kill_on_drop(true);
+        Ok(command)
+    }
+
+    fn check_flag(&mut self, flag: &str) -> bool {
+        if self.allowed_flags.contains(&flag) {
+            true
+        } else {
+            self.reject(format!("flag {} is not allowed", flag));
+            false
+        }
+    }
+
+    fn check_value(&mut self, flag: &str, value: &str) -> bool {
+        if value.starts_with('-') {
+            self.reject(format!("value for {} looks like an option: {:?}", flag, value));
+            false
+        } else if value.contains('\0') {
+            self.reject(format!("value for {} contains a NUL byte", flag));
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Remember the first problem; it is reported when the command is built
+    fn reject(&mut self, reason: String) {
+        if self.error.is_none() {
+            self.error = Some(reason);
+        }
+    }
+}
+
+/// Whether `program` can be started, checked by asking for its version
+pub fn can_run(program: &str, version_flag: &str) -> bool {
+    CommandSpec::version_probe(program, version_flag)
+        .to_command()
+        .map(|mut command| command.output().is_ok())
+        .unwrap_or(false)
+}
+
+/// Whether `program` is on the PATH
+pub fn is_on_path(program: &str) -> bool {
+    CommandSpec::locate(program)
+        .to_command()
+        .map(|mut command| {
+            command
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        })
+        .unwrap_or(false)
+}
+
+/// Join an argv into one line that can be pasted into a POSIX shell.
+/// Anything outside a conservative set of characters is single-quoted.
+pub fn shell_join(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let safe = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c));
+            if safe {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r#"'"'"'"#))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,150 @@
+// src/progress_parser.rs
+//! Download progress from yt-dlp's output. Progress is requested as JSON
+//! behind a versioned marker, so a change in yt-dlp's field formatting can't
+//! silently leave a download at 0%. Lines from older templates and yt-dlp's
+//! own "[download]  45.3% of 120.50MiB" lines are understood as a fallback,
+//! e.g. for yt-dlp builds that ignore the template.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+/// `--progress-template` for downloads. yt-dlp strips the `download:` type
+/// prefix and prints the rest.
+pub const PROGRESS_TEMPLATE: &str = "download:[rustloader-progress:1] %(progress)j";
+
+const MARKER_PREFIX: &str = "[rustloader-progress:";
+
+static LEGACY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?:download:)?\s*(\d+)\s*/\s*(\d+|NA)\s*$").unwrap());
+static DEFAULT_LINE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[download\]\s+(\d+(?:\.\d+)?)%\s+of\s+~?\s*(\d+(?:\.\d+)?)\s*([KMGT]?i?B)\b").unwrap()
+});
+
+/// Where a progress update was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// The JSON template, by version
+    Json(u32),
+    /// A `downloaded/total` line from the old template
+    Legacy,
+    /// yt-dlp's default progress line
+    Default,
+}
+
+impl ProgressFormat {
+    /// Description for logs
+    pub fn describe(&self) -> String {
+        match self {
+            ProgressFormat::Json(version) => format!("JSON template version {}", version),
+            ProgressFormat::Legacy => "the old downloaded/total template".to_string(),
+            ProgressFormat::Default => "yt-dlp's default progress lines".to_string(),
+        }
+    }
+}
+
+/// Bytes downloaded so far, and the total when yt-dlp knows or estimates it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub format: ProgressFormat,
+}
+
+/// What a line of yt-dlp output says about progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressLine {
+    Progress(ProgressUpdate),
+    /// Carries the progress marker but couldn't be read
+    Unreadable,
+    /// Not a progress line
+    Other,
+}
+
+/// Read a line of yt-dlp output
+pub fn parse_progress_line(line: &str) -> ProgressLine {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix(MARKER_PREFIX) {
+        let Some((version, payload)) = rest.split_once(']') else {
+            return ProgressLine::Unreadable;
+        };
+        let Ok(version) = version.parse::<u32>() else {
+            return ProgressLine::Unreadable;
+        };
+        // Later versions only add fields, so they are read like version 1
+        return match parse_json(payload.trim(), version) {
+            Some(update) => ProgressLine::Progress(update),
+            None => ProgressLine::Unreadable,
+        };
+    }
+
+    if let Some(update) = parse_legacy(line).or_else(|| parse_default_line(line)) {
+        return ProgressLine::Progress(update);
+    }
+    ProgressLine::Other
+}
+
+/// A byte count that yt-dlp may print as an integer, a float or "NA"
+fn bytes(value: &Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_f64().filter(|v| v.is_finite() && *v >= 0.0).map(|v| v as u64))
+        .or_else(|| value.as_str().and_then(|v| v.trim().parse().ok()))
+}
+
+fn parse_json(payload: &str, version: u32) -> Option<ProgressUpdate> {
+    let progress: Value = serde_json::from_str(payload).ok()?;
+    if !progress.is_object() {
+        return None;
+    }
+    let downloaded = bytes(&progress["downloaded_bytes"])?;
+    let fragment_estimate = || {
+        let index = bytes(&progress["fragment_index"]).filter(|index| *index > 0)?;
+        let count = bytes(&progress["fragment_count"]).filter(|count| *count >= index)?;
+        Some(downloaded.saturating_mul(count) / index)
+    };
+    let total = bytes(&progress["total_bytes"])
+        .or_else(|| bytes(&progress["total_bytes_estimate"]))
+        .or_else(fragment_estimate)
+        .filter(|total| *total > 0);
+
+    Some(ProgressUpdate {
+        downloaded,
+        total,
+        format: ProgressFormat::Json(version),
+    })
+}
+
+fn parse_legacy(line: &str) -> Option<ProgressUpdate> {
+    let captures = LEGACY_RE.captures(line)?;
+    Some(ProgressUpdate {
+        downloaded: captures[1].parse().ok()?,
+        total: captures[2].parse().ok().filter(|total| *total > 0),
+        format: ProgressFormat::Legacy,
+    })
+}
+
+fn unit_size(unit: &str) -> Option<f64> {
+    Some(match unit {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    })
+}
+
+fn parse_default_line(line: &str) -> Option<ProgressUpdate> {
+    let captures = DEFAULT_LINE_RE.captures(line)?;
+    let percent: f64 = captures[1].parse().ok()?;
+    let total = captures[2].parse::<f64>().ok()? * unit_size(&captures[3])?;
+    Some(ProgressUpdate {
+        downloaded: (total * percent.clamp(0.0, 100.0) / 100.0) as u64,
+        total: Some(total as u64).filter(|total| *total > 0),
+        format: ProgressFormat::Default,
+    })
+}
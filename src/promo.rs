@@ -0,0 +1,242 @@
+// src/promo.rs
+//! Upgrade messages for the free version.
+//!
+//! Messages come from a message pack: the built-in English one, or a
+//! localized pack at `<config dir>/messages/<locale>.json`. Pro and trial
+//! users never see them, anyone can turn them off with the `promos` setting,
+//! and the frequency cap keeps them to one every few downloads at most.
+
+use crate::config::load_config;
+use crate::error::AppError;
+use crate::license::is_pro_version;
+use crate::paths::{config_dir, data_dir};
+use chrono::{DateTime, Duration, Utc};
+use log::debug;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where in the program a message is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromoSlot {
+    Startup,
+    /// After a download finishes
+    Completion,
+}
+
+/// The messages for each slot in one language. Slots left empty in a
+/// localized pack fall back to the built-in messages.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessagePack {
+    pub startup: Vec<String>,
+    pub completion: Vec<String>,
+}
+
+impl MessagePack {
+    /// The English messages shipped with Rustloader
+    pub fn builtin() -> Self {
+        let messages = |list: &[&str]| list.iter().map(|message| message.to_string()).collect();
+        MessagePack {
+            startup: messages(&[
+                "🚀 Rustloader Pro offers 4K video downloads and 5X faster speeds! 🚀",
+                "💎 Upgrade to Rustloader Pro for AI-powered video upscaling! 💎",
+                "🎵 Enjoy high-quality 320kbps MP3 and FLAC with Rustloader Pro! 🎵",
+                "🔥 Rustloader Pro removes daily download limits! 🔥",
+            ]),
+            completion: messages(&[
+                "🤖 AI-powered video enhancement available in Rustloader Pro! 🤖",
+                "💎 Get advanced subtitle features with Rustloader Pro! 💎",
+                "✨ Unlock advanced post-processing with Rustloader Pro! ✨",
+                "✨ Enjoy your download! Upgrade to Pro for AI-powered features: rustloader.com/pro ✨",
+                "🚀 Rustloader Pro removes daily limits and adds AI capabilities. Learn more: rustloader.com/pro 🚀",
+                "💎 Thanks for using Rustloader! Upgrade to Pro for advanced features: rustloader.com/pro 💎",
+            ]),
+        }
+    }
+
+    /// Fill empty slots from `fallback`
+    pub fn or(mut self, fallback: MessagePack) -> Self {
+        if self.startup.is_empty() {
+            self.startup = fallback.startup;
+        }
+        if self.completion.is_empty() {
+            self.completion = fallback.completion;
+        }
+        self
+    }
+
+    pub fn messages(&self, slot: PromoSlot) -> &[String] {
+        match slot {
+            PromoSlot::Startup => &self.startup,
+            PromoSlot::Completion => &self.completion,
+        }
+    }
+}
+
+/// Turn a locale such as "de_DE.UTF-8" or "pt-BR" into a pack name ("de",
+/// "pt-br"). Returns None for "C"/"POSIX" and anything that isn't a
+/// plain language tag, so it is always safe to use as a file name.
+pub fn normalize_locale(locale: &str) -> Option<String> {
+    let tag = locale.split(['.', '@']).next()?.replace('_', "-").to_lowercase();
+    let mut parts = tag.split('-');
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    match parts.next() {
+        Some(region) if region.len() == 2 && region.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            Some(format!("{}-{}", language, region))
+        }
+        _ => Some(language.to_string()),
+    }
+}
+
+/// The locale to load messages for: the `promo_locale` setting, else the
+/// usual environment variables
+fn current_locale(configured: Option<&str>) -> Option<String> {
+    configured
+        .map(str::to_string)
+        .or_else(|| ["LC_ALL", "LC_MESSAGES", "LANG"].iter().find_map(|var| std::env::var(var).ok()))
+        .and_then(|locale| normalize_locale(&locale))
+}
+
+/// Directory holding localized message packs
+pub fn messages_dir() -> Result<PathBuf, AppError> {
+    Ok(config_dir()?.join("messages"))
+}
+
+/// Load the pack for `locale` from `dir`, trying the region-specific pack
+/// ("pt-br.json") before the language one ("pt.json")
+pub fn load_message_pack(dir: &Path, locale: &str) -> Option<MessagePack> {
+    let language = locale.split('-').next().unwrap_or(locale);
+    [locale, language].iter().find_map(|name| {
+        let path = dir.join(format!("{}.json", name));
+        let contents = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str::<MessagePack>(&contents) {
+            Ok(pack) => Some(pack),
+            Err(e) => {
+                debug!("Ignoring invalid message pack {:?}: {}", path, e);
+                None
+            }
+        }
+    })
+}
+
+/// How often messages may be shown
+#[derive(Debug, Clone, Copy)]
+pub struct FrequencyCap {
+    /// Minimum time between any two messages
+    pub min_interval: Duration,
+    /// Downloads that must finish between two completion messages
+    pub every_n_downloads: u32,
+}
+
+impl Default for FrequencyCap {
+    fn default() -> Self {
+        FrequencyCap {
+            min_interval: Duration::hours(4),
+            every_n_downloads: 5,
+        }
+    }
+}
+
+/// When a message was last shown, kept across runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromoState {
+    pub last_shown: Option<DateTime<Utc>>,
+    /// Downloads finished since the last completion message
+    pub downloads_since: u32,
+}
+
+impl FrequencyCap {
+    pub fn allows(&self, state: &PromoState, slot: PromoSlot, now: DateTime<Utc>) -> bool {
+        if let Some(last) = state.last_shown {
+            if now - last < self.min_interval {
+                return false;
+            }
+        }
+        match slot {
+            PromoSlot::Startup => true,
+            PromoSlot::Completion => state.downloads_since >= self.every_n_downloads,
+        }
+    }
+}
+
+/// When upgrade messages were last shown
+pub fn promo_state_path() -> Result<PathBuf, AppError> {
+    Ok(data_dir()?.join("promo_state.json"))
+}
+
+/// Picks the message, if any, to show in each slot
+pub struct PromoService {
+    enabled: bool,
+    pack: MessagePack,
+    cap: FrequencyCap,
+    state: PromoState,
+    state_path: Option<PathBuf>,
+}
+
+impl PromoService {
+    /// Service for this installation: license, settings, locale and the saved state
+    pub fn load() -> Self {
+        let config = load_config().unwrap_or_default();
+        let enabled = config.promos_enabled() && !is_pro_version();
+        let pack = current_locale(config.promo_locale.as_deref())
+            .and_then(|locale| load_message_pack(&messages_dir().ok()?, &locale))
+            .map(|pack| pack.or(MessagePack::builtin()))
+            .unwrap_or_else(MessagePack::builtin);
+        let state_path = promo_state_path().ok();
+        let state = state_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        PromoService { enabled, pack, cap: FrequencyCap::default(), state, state_path }
+    }
+
+    /// A service with an explicit pack and state that saves nothing
+    #[allow(dead_code)]
+    pub fn new(enabled: bool, pack: MessagePack, cap: FrequencyCap, state: PromoState) -> Self {
+        PromoService { enabled, pack, cap, state, state_path: None }
+    }
+
+    /// Count a finished download toward the frequency cap
+    pub fn note_download(&mut self) {
+        self.state.downloads_since = self.state.downloads_since.saturating_add(1);
+        self.save();
+    }
+
+    /// The message to show in `slot` now, if the cap allows one
+    pub fn message(&mut self, slot: PromoSlot) -> Option<String> {
+        self.message_at(slot, Utc::now())
+    }
+
+    pub fn message_at(&mut self, slot: PromoSlot, now: DateTime<Utc>) -> Option<String> {
+        let messages = self.pack.messages(slot);
+        if !self.enabled || messages.is_empty() || !self.cap.allows(&self.state, slot, now) {
+            return None;
+        }
+        let message = messages[rand::thread_rng().gen_range(0..messages.len())].clone();
+        self.state.last_shown = Some(now);
+        if slot == PromoSlot::Completion {
+            self.state.downloads_since = 0;
+        }
+        self.save();
+        Some(message)
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.state_path {
+            let saved = serde_json::to_string(&self.state).map_err(AppError::from).and_then(|json| {
+                fs::write(path, json)?;
+                Ok(())
+            });
+            if let Err(e) = saved {
+                debug!("Could not save promo state: {}", e);
+            }
+        }
+    }
+}
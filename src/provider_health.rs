@@ -0,0 +1,162 @@
+// src/provider_health.rs
+//! `rustloader providers status`: checks which site extractors work with
+//! the installed yt-dlp. Each site has a canary, a long-lived public video,
+//! whose metadata is extracted without downloading anything. A site whose
+//! canary fails with an extractor error needs a yt-dlp update.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::process::CommandSpec;
+use crate::site_stats::{classify_failure, FailureKind};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Longest a canary extraction may take before the site counts as unreachable
+const CANARY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A known-good public URL for a site
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canary {
+    pub site: &'static str,
+    pub url: &'static str,
+}
+
+/// The canaries checked, one per supported site
+pub const CANARIES: &[Canary] = &[
+    Canary { site: "youtube.com", url: "https://www.youtube.com/watch?v=jNQXAC9IVRw" },
+    Canary { site: "vimeo.com", url: "https://vimeo.com/76979871" },
+    Canary { site: "dailymotion.com", url: "https://www.dailymotion.com/video/x5kesuj" },
+    Canary { site: "archive.org", url: "https://archive.org/details/BigBuckBunny_124" },
+];
+
+/// How a site's canary went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderStatus {
+    Working,
+    /// The extraction failed; see the failure kind
+    Broken,
+    /// The site couldn't be reached, which says nothing about the extractor
+    Unreachable,
+}
+
+impl ProviderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderStatus::Working => "working",
+            ProviderStatus::Broken => "broken",
+            ProviderStatus::Unreachable => "unreachable",
+        }
+    }
+}
+
+/// Result of one canary extraction
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderCheck {
+    pub site: String,
+    pub url: String,
+    pub status: ProviderStatus,
+    pub failure: Option<FailureKind>,
+    /// Last line yt-dlp printed on failure
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+impl ProviderCheck {
+    /// Whether updating yt-dlp is likely to fix the site
+    pub fn needs_ytdlp_update(&self) -> bool {
+        self.failure == Some(FailureKind::Extractor)
+    }
+}
+
+/// Canaries for `sites`, or all of them when empty. Unknown sites are an error.
+pub fn select_canaries(sites: &[String]) -> Result<Vec<Canary>, AppError> {
+    if sites.is_empty() {
+        return Ok(CANARIES.to_vec());
+    }
+    sites
+        .iter()
+        .map(|site| {
+            let site = site.trim().trim_start_matches("www.").to_lowercase();
+            CANARIES.iter().copied().find(|canary| canary.site == site).ok_or_else(|| {
+                let known: Vec<&str> = CANARIES.iter().map(|canary| canary.site).collect();
+                AppError::ValidationError(format!("No canary for '{}', known sites: {}", site, known.join(", ")))
+            })
+        })
+        .collect()
+}
+
+/// Judge a canary from yt-dlp's exit status and output
+pub fn judge_canary(canary: &Canary, success: bool, stdout: &str, stderr: &str, elapsed: Duration) -> ProviderCheck {
+    let extracted = success
+        && serde_json::from_str::<serde_json::Value>(stdout.trim())
+            .is_ok_and(|info| info["id"].as_str().is_some_and(|id| !id.is_empty()));
+    let (status, failure, error) = if extracted {
+        (ProviderStatus::Working, None, None)
+    } else {
+        let lines: Vec<String> = stderr.lines().map(str::to_string).collect();
+        let error = lines.iter().rev().find(|line| !line.trim().is_empty()).cloned();
+        let failure = classify_failure(error.as_deref().unwrap_or("no metadata returned"), &lines);
+        let status = if failure == FailureKind::Network {
+            ProviderStatus::Unreachable
+        } else {
+            ProviderStatus::Broken
+        };
+        (status, Some(failure), error)
+    };
+    ProviderCheck {
+        site: canary.site.to_string(),
+        url: canary.url.to_string(),
+        status,
+        failure,
+        error,
+        elapsed_ms: elapsed.as_millis() as u64,
+    }
+}
+
+/// Extract the metadata of one canary
+pub async fn check_canary(canary: Canary, request_headers: &RequestHeaders) -> Result<ProviderCheck, AppError> {
+    let mut spec = CommandSpec::ytdlp();
+    spec.flag("--dump-json")
+        .flag("--no-playlist")
+        .option("--socket-timeout", "10")
+        .options(request_headers.ytdlp_options())
+        .operand(canary.url);
+    let mut command = spec.to_async_command()?;
+
+    let started = Instant::now();
+    match tokio::time::timeout(CANARY_TIMEOUT, command.output()).await {
+        Ok(output) => {
+            let output = output.map_err(AppError::IoError)?;
+            Ok(judge_canary(
+                &canary,
+                output.status.success(),
+                &String::from_utf8_lossy(&output.stdout),
+                &String::from_utf8_lossy(&output.stderr),
+                started.elapsed(),
+            ))
+        }
+        Err(_) => Ok(judge_canary(&canary, false, "", "Extraction timed out", started.elapsed())),
+    }
+}
+
+/// Check `canaries` at the same time, in the given order
+pub async fn check_providers(canaries: &[Canary], request_headers: &RequestHeaders) -> Result<Vec<ProviderCheck>, AppError> {
+    let tasks: Vec<_> = canaries
+        .iter()
+        .map(|canary| {
+            let canary = *canary;
+            let request_headers = request_headers.clone();
+            tokio::spawn(async move { check_canary(canary, &request_headers).await })
+        })
+        .collect();
+
+    let mut checks = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let check = task
+            .await
+            .map_err(|e| AppError::General(format!("Provider check failed: {}", e)))??;
+        checks.push(check);
+    }
+    Ok(checks)
+}
@@ -0,0 +1,132 @@
+// src/proxy.rs
+//! Proxy configuration for yt-dlp, with per-download circuit isolation for
+//! Tor. Tor's SOCKS port (with its default IsolateSOCKSAuth) sends streams
+//! that use different credentials over different circuits. So giving every
+//! download its own username and password keeps one throttled or blocked
+//! exit from stalling the rest of the queue. Rustloader's own HTTP requests
+//! go through the same proxy, via [`client_builder`].
+
+use crate::config::load_config;
+use crate::error::AppError;
+use rand::Rng;
+use reqwest::{ClientBuilder, Proxy};
+
+/// Replaced with the download's isolation key in a proxy URL, e.g.
+/// `socks5h://{id}:x@127.0.0.1:9050`
+pub const ID_PLACEHOLDER: &str = "{id}";
+
+/// Proxy schemes yt-dlp understands
+const SCHEMES: &[&str] = &["http", "https", "socks4", "socks4a", "socks5", "socks5h"];
+
+/// Longest isolation key put into credentials; SOCKS5 allows 255 bytes
+const MAX_KEY_LEN: usize = 64;
+
+/// A validated proxy URL that may carry `{id}` placeholders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyTemplate {
+    template: String,
+    isolate: bool,
+}
+
+impl ProxyTemplate {
+    /// Check a configured proxy URL. With `isolate` set and no `{id}` in
+    /// the URL, a SOCKS5 proxy gets generated credentials per download.
+    pub fn parse(template: &str, isolate: bool) -> Result<Self, AppError> {
+        let template = template.trim();
+        let invalid = |reason: &str| AppError::ValidationError(format!("Invalid proxy {:?}: {}", template, reason));
+
+        if template.chars().any(|c| c.is_control() || c.is_whitespace()) {
+            return Err(invalid("it contains whitespace or control characters"));
+        }
+        let (scheme, rest) = template
+            .split_once("://")
+            .ok_or_else(|| invalid("expected e.g. socks5h://127.0.0.1:9050"))?;
+        if !SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+            return Err(invalid(&format!("unsupported scheme '{}'", scheme)));
+        }
+        let authority = rest.split('/').next().unwrap_or_default();
+        let (userinfo, host) = match authority.rsplit_once('@') {
+            Some((userinfo, host)) => (Some(userinfo), host),
+            None => (None, authority),
+        };
+        if host.is_empty() || host.contains(ID_PLACEHOLDER) {
+            return Err(invalid("missing host"));
+        }
+
+        let templated = template.contains(ID_PLACEHOLDER);
+        if templated && !userinfo.is_some_and(|userinfo| userinfo.contains(ID_PLACEHOLDER)) {
+            return Err(invalid("{id} may only appear in the credentials"));
+        }
+        if isolate && !templated {
+            if userinfo.is_some() {
+                return Err(invalid("add {id} to the credentials so each download gets its own"));
+            }
+            if !scheme.eq_ignore_ascii_case("socks5") && !scheme.eq_ignore_ascii_case("socks5h") {
+                return Err(invalid("isolation without {id} needs a socks5 or socks5h proxy"));
+            }
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            isolate: isolate || templated,
+        })
+    }
+
+    /// Whether each download gets its own credentials
+    #[allow(dead_code)]
+    pub fn is_isolated(&self) -> bool {
+        self.isolate
+    }
+
+    /// The proxy URL for the download identified by `key`
+    pub fn render(&self, key: &str) -> String {
+        if !self.isolate {
+            return self.template.clone();
+        }
+        let key = isolation_key(key);
+        if self.template.contains(ID_PLACEHOLDER) {
+            return self.template.replace(ID_PLACEHOLDER, &key);
+        }
+        // Checked in parse: a socks5 URL without credentials
+        let (scheme, rest) = self.template.split_once("://").unwrap_or(("socks5h", &self.template));
+        format!("{}://rustloader-{}:{}@{}", scheme, key, key, rest)
+    }
+}
+
+/// `key` reduced to characters that are safe inside URL credentials
+pub fn isolation_key(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(MAX_KEY_LEN)
+        .collect();
+    if key.is_empty() {
+        new_isolation_key()
+    } else {
+        key
+    }
+}
+
+/// A fresh key for a download that isn't in the queue
+pub fn new_isolation_key() -> String {
+    format!("run_{:016x}", rand::thread_rng().gen::<u64>())
+}
+
+/// A builder for an HTTP client that sends every request through `proxy`, a
+/// proxy URL already rendered for one download, or else through the
+/// configured proxy with credentials of its own. A configured proxy that
+/// can't be used is an error, so no request leaves without it.
+pub fn client_builder(proxy: Option<&str>) -> Result<ClientBuilder, AppError> {
+    let proxy = match proxy {
+        Some(proxy) => Some(proxy.to_string()),
+        None => load_config()
+            .unwrap_or_default()
+            .proxy_template()?
+            .map(|template| template.render(&new_isolation_key())),
+    };
+    let builder = reqwest::Client::builder();
+    Ok(match proxy {
+        Some(proxy) => builder.proxy(Proxy::all(proxy)?),
+        None => builder,
+    })
+}
@@ -0,0 +1,130 @@
+// src/publish.rs
+//! Publishing finished downloads into extra folders (a media server library,
+//! a sync folder, ...) as links, so the canonical file stays in the download
+//! directory and no space is used for copies.
+
+use crate::config::load_config;
+use crate::error::AppError;
+use crate::security::validate_path_safety;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How a published file refers to the download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// Hardlink, falling back to a symlink across filesystems
+    #[default]
+    Hardlink,
+    Symlink,
+}
+
+/// Folders a matching download is linked into
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublishRule {
+    /// Only downloads in this format (e.g. "mp3"); any format when unset
+    pub format: Option<String>,
+    /// Only downloads whose URL contains this text; any URL when unset
+    pub url_contains: Option<String>,
+    /// Folders to link the finished file into
+    pub targets: Vec<String>,
+    pub link: LinkKind,
+}
+
+impl PublishRule {
+    /// Whether a download of `url` in `format` should be published by this rule
+    pub fn matches(&self, url: &str, format: &str) -> bool {
+        let format_matches = self
+            .format
+            .as_deref()
+            .map_or(true, |wanted| wanted.eq_ignore_ascii_case(format));
+        let url_matches = self
+            .url_contains
+            .as_deref()
+            .map_or(true, |needle| url.to_lowercase().contains(&needle.to_lowercase()));
+        format_matches && url_matches
+    }
+
+    /// Short description such as "mp3 from youtube.com -> ~/Music (hardlink)"
+    pub fn describe(&self) -> String {
+        let mut filters = vec![self.format.clone().unwrap_or_else(|| "any format".to_string())];
+        if let Some(needle) = &self.url_contains {
+            filters.push(format!("from {}", needle));
+        }
+        let link = match self.link {
+            LinkKind::Hardlink => "hardlink",
+            LinkKind::Symlink => "symlink",
+        };
+        format!("{} -> {} ({})", filters.join(" "), self.targets.join(", "), link)
+    }
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+/// Link `file` into `target_dir` under the same name. An existing file of
+/// that name is left alone.
+pub fn link_into(file: &Path, target_dir: &Path, kind: LinkKind) -> Result<Option<PathBuf>, AppError> {
+    let name = file
+        .file_name()
+        .ok_or_else(|| AppError::PathError(format!("Not a file: {}", file.display())))?;
+    validate_path_safety(target_dir)?;
+    fs::create_dir_all(target_dir)?;
+
+    let destination = target_dir.join(name);
+    if destination.symlink_metadata().is_ok() {
+        warn!("Not publishing to {:?}: a file with that name exists", destination);
+        return Ok(None);
+    }
+
+    // Symlinks must point at an absolute path to work from another folder
+    let original = file.canonicalize()?;
+    match kind {
+        LinkKind::Hardlink => {
+            if let Err(e) = fs::hard_link(&original, &destination) {
+                warn!("Hardlink to {:?} failed ({}), using a symlink", destination, e);
+                symlink(&original, &destination)?;
+            }
+        }
+        LinkKind::Symlink => symlink(&original, &destination)?,
+    }
+
+    Ok(Some(destination))
+}
+
+/// Link `file` into the targets of every rule matching the download.
+/// Failures for one target don't stop the others.
+pub fn publish_file(file: &Path, url: &str, format: &str, rules: &[PublishRule]) -> Vec<PathBuf> {
+    let mut published = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.matches(url, format)) {
+        for target in &rule.targets {
+            match link_into(file, Path::new(target), rule.link) {
+                Ok(Some(destination)) => {
+                    info!("Published {:?} to {:?}", file, destination);
+                    published.push(destination);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Could not publish {:?} to {}: {}", file, target, e),
+            }
+        }
+    }
+    published
+}
+
+/// Publish a finished download according to the configured rules
+pub fn publish_download(file: &Path, url: &str, format: &str) -> Vec<PathBuf> {
+    match load_config() {
+        Ok(config) if !config.publish_rules.is_empty() => publish_file(file, url, format, &config.publish_rules),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,127 @@
+// src/quality_compare.rs
+//! `rustloader compare <url>`: what each quality tier of a video would cost
+//! to download. One row per height and video codec, with the expected size
+//! (including the audio merged into video-only formats), the bitrate and the
+//! bits per pixel, which shows how much compression a tier trades for size.
+
+use crate::error::AppError;
+use crate::format_picker::{parse_formats, short_codec, FormatInfo};
+use crate::http_headers::RequestHeaders;
+use crate::metadata_cache::fetch_media_info;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The estimate for one quality tier
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityTier {
+    pub height: u64,
+    pub format_id: String,
+    pub ext: String,
+    /// Video codec without profile details, e.g. "avc1" or "vp9"
+    pub vcodec: String,
+    /// Audio codec of the format, or of the best audio merged into it
+    pub acodec: Option<String>,
+    pub fps: Option<f64>,
+    /// Bitrate of the video stream, in kbps
+    pub video_kbps: Option<f64>,
+    /// Expected download size including audio
+    pub estimated_bytes: Option<u64>,
+    /// Video bits per pixel per frame
+    pub bits_per_pixel: Option<f64>,
+}
+
+/// The quality tiers of one video, best first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityComparison {
+    pub url: String,
+    pub title: Option<String>,
+    pub duration_secs: Option<f64>,
+    pub tiers: Vec<QualityTier>,
+}
+
+/// Bytes a stream of `kbps` takes over `duration_secs`
+fn bytes_for(kbps: f64, duration_secs: Option<f64>) -> Option<u64> {
+    duration_secs
+        .filter(|duration| *duration > 0.0)
+        .map(|duration| (kbps * 1000.0 / 8.0 * duration) as u64)
+}
+
+/// Expected size of a format, from yt-dlp's size or else its bitrate
+fn format_bytes(format: &FormatInfo, duration_secs: Option<f64>) -> Option<u64> {
+    format.filesize.or_else(|| format.tbr.and_then(|tbr| bytes_for(tbr, duration_secs)))
+}
+
+/// Video bitrate of a format, from yt-dlp's figures or else its size
+fn video_kbps(format: &FormatInfo, duration_secs: Option<f64>) -> Option<f64> {
+    format.vbr.or(format.tbr).or_else(|| {
+        let duration = duration_secs.filter(|duration| *duration > 0.0)?;
+        format.filesize.map(|size| size as f64 * 8.0 / 1000.0 / duration)
+    })
+}
+
+/// Bits each pixel gets per frame at `kbps`
+pub fn bits_per_pixel(kbps: f64, width: u64, height: u64, fps: f64) -> Option<f64> {
+    let pixels_per_second = width as f64 * height as f64 * fps;
+    (pixels_per_second > 0.0 && kbps > 0.0).then(|| kbps * 1000.0 / pixels_per_second)
+}
+
+/// Compare the quality tiers in yt-dlp metadata for `url`
+pub fn compare_qualities(url: &str, info: &Value) -> QualityComparison {
+    let duration_secs = info["duration"].as_f64();
+    let best_audio = parse_formats(info, true).into_iter().next();
+    let audio_bytes = best_audio.as_ref().and_then(|audio| format_bytes(audio, duration_secs));
+
+    let mut tiers: Vec<QualityTier> = Vec::new();
+    // Formats come best first, so the first of each height and codec is kept
+    for format in parse_formats(info, false) {
+        let (Some(height), Some(vcodec)) = (format.height, format.vcodec.as_deref()) else {
+            continue;
+        };
+        let vcodec = short_codec(vcodec).to_string();
+        if tiers.iter().any(|tier| tier.height == height && tier.vcodec == vcodec) {
+            continue;
+        }
+
+        let kbps = video_kbps(&format, duration_secs);
+        let (acodec, estimated_bytes) = match &format.acodec {
+            Some(acodec) => (Some(short_codec(acodec).to_string()), format_bytes(&format, duration_secs)),
+            None => (
+                best_audio.as_ref().and_then(|audio| audio.acodec.as_deref()).map(|acodec| short_codec(acodec).to_string()),
+                format_bytes(&format, duration_secs).map(|video| video + audio_bytes.unwrap_or(0)),
+            ),
+        };
+        let bits_per_pixel = match (kbps, format.width, format.fps) {
+            (Some(kbps), Some(width), Some(fps)) => bits_per_pixel(kbps, width, height, fps),
+            _ => None,
+        };
+
+        tiers.push(QualityTier {
+            height,
+            format_id: format.format_id,
+            ext: format.ext,
+            vcodec,
+            acodec,
+            fps: format.fps,
+            video_kbps: kbps,
+            estimated_bytes,
+            bits_per_pixel,
+        });
+    }
+
+    QualityComparison {
+        url: url.to_string(),
+        title: info["title"].as_str().map(str::to_string),
+        duration_secs,
+        tiers,
+    }
+}
+
+/// Probe `url` and compare its quality tiers
+pub async fn fetch_quality_comparison(url: &str, request_headers: &RequestHeaders) -> Result<QualityComparison, AppError> {
+    let info = fetch_media_info(url, request_headers).await?;
+    let comparison = compare_qualities(url, &info);
+    if comparison.tiers.is_empty() {
+        return Err(AppError::DownloadError("No video formats found to compare".to_string()));
+    }
+    Ok(comparison)
+}
@@ -0,0 +1,142 @@
+// src/queue_changes.rs
+//! Queue changes by sequence number, so a GUI list can follow a large queue
+//! from the items that changed instead of fetching all of it on every
+//! update. Unchanged downloads share their `Arc` between snapshots, so
+//! finding what changed is a pointer comparison per download.
+
+use crate::download_manager::{downloads_snapshot, DownloadItem};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Updates kept for clients that are behind; older clients get the full list
+const KEPT_UPDATES: usize = 64;
+
+/// One change to the queue
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum QueueChange {
+    Added { item: Box<DownloadItem> },
+    /// Only the fields that changed, by their serialized names
+    Updated { id: String, fields: Map<String, Value> },
+    Removed { id: String },
+}
+
+/// The changes that bring a client from one sequence number to `seq`
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDelta {
+    pub seq: u64,
+    /// The changes are the whole queue as `Added`; drop the current list first
+    pub reset: bool,
+    pub changes: Vec<QueueChange>,
+}
+
+/// Fields of `new` that differ from `old`; a field `new` lacks is `null`
+#[allow(dead_code)]
+pub fn changed_fields(old: &DownloadItem, new: &DownloadItem) -> Map<String, Value> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Map::new();
+    };
+    let mut fields: Map<String, Value> = new
+        .iter()
+        .filter(|(name, value)| old.get(name.as_str()) != Some(value))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    for name in old.keys().filter(|name| !new.contains_key(name.as_str())) {
+        fields.insert(name.clone(), Value::Null);
+    }
+    fields
+}
+
+/// Recent queue changes, numbered
+#[derive(Debug, Default)]
+pub struct QueueChangeLog {
+    seq: u64,
+    last: HashMap<String, Arc<DownloadItem>>,
+    recent: VecDeque<(u64, Vec<QueueChange>)>,
+}
+
+impl QueueChangeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sequence number of the latest recorded change
+    #[allow(dead_code)]
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Record how `snapshot` differs from the last one; the sequence number
+    /// only moves when something changed
+    pub fn record(&mut self, snapshot: Vec<Arc<DownloadItem>>) -> u64 {
+        let mut current = HashMap::with_capacity(snapshot.len());
+        let mut changes = Vec::new();
+        for item in snapshot {
+            match self.last.get(&item.id) {
+                Some(previous) if Arc::ptr_eq(previous, &item) => {}
+                Some(previous) => {
+                    let fields = changed_fields(previous, &item);
+                    if !fields.is_empty() {
+                        changes.push(QueueChange::Updated { id: item.id.clone(), fields });
+                    }
+                }
+                None => changes.push(QueueChange::Added { item: Box::new((*item).clone()) }),
+            }
+            current.insert(item.id.clone(), item);
+        }
+        for id in self.last.keys().filter(|id| !current.contains_key(*id)) {
+            changes.push(QueueChange::Removed { id: id.clone() });
+        }
+        self.last = current;
+
+        if !changes.is_empty() {
+            self.seq += 1;
+            self.recent.push_back((self.seq, changes));
+            if self.recent.len() > KEPT_UPDATES {
+                self.recent.pop_front();
+            }
+        }
+        self.seq
+    }
+
+    /// Changes after `since`, or the whole queue when `since` is missing,
+    /// unknown or older than the kept updates
+    pub fn changes_since(&self, since: Option<u64>) -> QueueDelta {
+        let oldest_kept = self.recent.front().map_or(self.seq, |(seq, _)| seq - 1);
+        match since {
+            Some(since) if since <= self.seq && since >= oldest_kept => QueueDelta {
+                seq: self.seq,
+                reset: false,
+                changes: self
+                    .recent
+                    .iter()
+                    .filter(|(seq, _)| *seq > since)
+                    .flat_map(|(_, changes)| changes.iter().cloned())
+                    .collect(),
+            },
+            _ => QueueDelta {
+                seq: self.seq,
+                reset: true,
+                changes: self
+                    .last
+                    .values()
+                    .map(|item| QueueChange::Added { item: Box::new((**item).clone()) })
+                    .collect(),
+            },
+        }
+    }
+}
+
+static CHANGE_LOG: Lazy<Mutex<QueueChangeLog>> = Lazy::new(|| Mutex::new(QueueChangeLog::new()));
+
+/// Changes to the download queue after `since`, checked against its current state
+#[allow(dead_code)]
+pub fn queue_changes_since(since: Option<u64>) -> QueueDelta {
+    let snapshot = downloads_snapshot();
+    let mut log = CHANGE_LOG.lock().unwrap();
+    log.record(snapshot);
+    log.changes_since(since)
+}
@@ -0,0 +1,84 @@
+// src/queue_eta.rs
+
+use crate::bandwidth::expected_throughput;
+use crate::download_manager::{get_all_downloads, DownloadItem, DownloadStatus};
+use crate::queue_order::dispatch_order;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Time estimates for the downloads still waiting in the queue
+#[derive(Debug, Clone, Default)]
+pub struct QueueEta {
+    /// Expected throughput in bytes per second the estimates are based on
+    pub throughput: Option<f64>,
+    /// Time until each download finishes, by download ID
+    pub items: HashMap<String, Duration>,
+    /// When the last download with a known size should finish
+    pub finishes_at: Option<DateTime<Utc>>,
+    /// Unfinished downloads left out because their size is unknown
+    pub unknown_sizes: usize,
+}
+
+impl QueueEta {
+    /// Time until the given download finishes, if it can be estimated
+    pub fn eta(&self, id: &str) -> Option<Duration> {
+        self.items.get(id).copied()
+    }
+}
+
+/// Order in which the queue works through downloads: running ones first,
+/// then queued ones in dispatch order
+fn queue_order(a: &DownloadItem, b: &DownloadItem) -> Ordering {
+    let rank = |item: &DownloadItem| match item.status {
+        DownloadStatus::Downloading => 0,
+        _ => 1,
+    };
+    rank(a).cmp(&rank(b)).then_with(|| dispatch_order(a, b))
+}
+
+/// Estimate when each pending download finishes. Downloads share the
+/// available throughput, so each one finishes once everything ahead of it
+/// plus its own remaining bytes have been transferred. Paused and finished
+/// downloads get no estimate.
+pub fn estimate_queue(downloads: &[DownloadItem], throughput: Option<f64>, now: DateTime<Utc>) -> QueueEta {
+    let mut eta = QueueEta {
+        throughput,
+        ..QueueEta::default()
+    };
+
+    let throughput = match throughput {
+        Some(rate) if rate > 0.0 => rate,
+        _ => return eta,
+    };
+
+    let mut pending: Vec<&DownloadItem> = downloads
+        .iter()
+        .filter(|item| matches!(item.status, DownloadStatus::Downloading | DownloadStatus::Queued))
+        .collect();
+    pending.sort_by(|a, b| queue_order(a, b));
+
+    let mut cumulative_bytes = 0u64;
+    for item in pending {
+        if item.total_bytes == 0 {
+            eta.unknown_sizes += 1;
+            continue;
+        }
+        cumulative_bytes += item.total_bytes.saturating_sub(item.downloaded_bytes);
+        let remaining = Duration::from_secs_f64(cumulative_bytes as f64 / throughput);
+        eta.items.insert(item.id.clone(), remaining);
+    }
+
+    if let Some(longest) = eta.items.values().max() {
+        eta.finishes_at = chrono::Duration::from_std(*longest).ok().map(|d| now + d);
+    }
+
+    eta
+}
+
+/// Estimates for the current queue
+pub fn get_queue_eta() -> QueueEta {
+    let downloads = get_all_downloads();
+    estimate_queue(&downloads, expected_throughput(&downloads), Utc::now())
+}
@@ -0,0 +1,135 @@
+// src/queue_events.rs
+
+use crate::download_manager::{get_download_queue, DownloadItem, DownloadStatus};
+use crate::error::AppError;
+use crate::instance_lock::ensure_queue_owner;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// An event emitted while following the queue, serialized as one NDJSON line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum QueueEvent {
+    /// A download was added or changed status
+    StateChanged {
+        timestamp: DateTime<Utc>,
+        id: String,
+        title: Option<String>,
+        from: Option<DownloadStatus>,
+        to: DownloadStatus,
+    },
+    /// A download left the queue
+    Removed {
+        timestamp: DateTime<Utc>,
+        id: String,
+    },
+    /// Periodic snapshot of an active download
+    Progress {
+        timestamp: DateTime<Utc>,
+        id: String,
+        progress: f64,
+        downloaded_bytes: u64,
+        total_bytes: u64,
+        speed: f64,
+    },
+}
+
+/// Compare the previous status of each download with the current queue and
+/// return the resulting events, updating `known` in place
+pub fn diff_queue_events(
+    known: &mut HashMap<String, DownloadStatus>,
+    current: &[DownloadItem],
+    timestamp: DateTime<Utc>,
+) -> Vec<QueueEvent> {
+    let mut events = Vec::new();
+
+    for item in current {
+        let previous = known.insert(item.id.clone(), item.status);
+        if previous != Some(item.status) {
+            events.push(QueueEvent::StateChanged {
+                timestamp,
+                id: item.id.clone(),
+                title: item.title.clone(),
+                from: previous,
+                to: item.status,
+            });
+        }
+    }
+
+    let mut removed: Vec<String> = known
+        .keys()
+        .filter(|id| !current.iter().any(|item| &item.id == *id))
+        .cloned()
+        .collect();
+    removed.sort();
+    for id in removed {
+        known.remove(&id);
+        events.push(QueueEvent::Removed { timestamp, id });
+    }
+
+    events
+}
+
+/// Progress snapshots for every download currently transferring
+pub fn progress_events(current: &[DownloadItem], timestamp: DateTime<Utc>) -> Vec<QueueEvent> {
+    current
+        .iter()
+        .filter(|item| item.status == DownloadStatus::Downloading)
+        .map(|item| QueueEvent::Progress {
+            timestamp,
+            id: item.id.clone(),
+            progress: item.progress,
+            downloaded_bytes: item.downloaded_bytes,
+            total_bytes: item.total_bytes,
+            speed: item.speed,
+        })
+        .collect()
+}
+
+/// Follow the queue until interrupted, passing each event to `emit`.
+/// State changes are reported as the queue notifies listeners; progress
+/// snapshots are taken every `interval`.
+pub async fn follow_queue<F>(interval: Duration, mut emit: F) -> Result<(), AppError>
+where
+    F: FnMut(&QueueEvent) -> Result<(), AppError>,
+{
+    // Events come from the running queue, which lives in the owning process
+    ensure_queue_owner()?;
+    
+    let queue = get_download_queue().await;
+    let mut notifications = queue.get_notification_receiver();
+    let mut known = HashMap::new();
+    let mut ticker = tokio::time::interval(interval);
+
+    // Report the current state first so consumers start from a full picture
+    for event in diff_queue_events(&mut known, &queue.get_all_downloads(), Utc::now()) {
+        emit(&event)?;
+    }
+
+    loop {
+        tokio::select! {
+            notification = notifications.recv() => {
+                match notification {
+                    // Lagging only means we missed intermediate notifications; a fresh diff catches up
+                    Ok(()) | Err(RecvError::Lagged(_)) => {
+                        for event in diff_queue_events(&mut known, &queue.get_all_downloads(), Utc::now()) {
+                            emit(&event)?;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            _ = ticker.tick() => {
+                for event in progress_events(&queue.get_all_downloads(), Utc::now()) {
+                    emit(&event)?;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+// src/queue_order.rs
+//! The order the queue processor starts downloads in. It depends only on
+//! the downloads themselves, never on how they happen to be stored, so
+//! `queue list`, the ETA and the processor all agree on what runs next.
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::download_map::DownloadMap;
+use std::cmp::Ordering;
+
+/// Compare queued downloads by when they start: higher priority first,
+/// then downloads placed by a manual reorder in that order ahead of the
+/// rest, then the earliest added, and finally by ID so no two downloads
+/// ever tie.
+pub fn dispatch_order(a: &DownloadItem, b: &DownloadItem) -> Ordering {
+    b.priority
+        .cmp(&a.priority)
+        .then_with(|| match (a.queue_position, b.queue_position) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+        .then_with(|| a.added_at.cmp(&b.added_at))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Sort queued download IDs into dispatch order. IDs of downloads that no
+/// longer exist go last.
+pub fn sort_queue_ids(queue: &mut [String], downloads: &DownloadMap) {
+    queue.sort_by(|a, b| match (downloads.get(a), downloads.get(b)) {
+        (Some(a), Some(b)) => dispatch_order(a, b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    });
+}
+
+/// Order for listing the whole queue: running downloads by start time,
+/// then queued ones in the order they will start, then paused ones the
+/// same way, then finished ones oldest first
+pub fn list_order(a: &DownloadItem, b: &DownloadItem) -> Ordering {
+    let rank = |item: &DownloadItem| match item.status {
+        DownloadStatus::Downloading => 0,
+        DownloadStatus::Queued => 1,
+        DownloadStatus::Paused => 2,
+        _ => 3,
+    };
+    rank(a).cmp(&rank(b)).then_with(|| match a.status {
+        DownloadStatus::Downloading => a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)),
+        DownloadStatus::Queued | DownloadStatus::Paused => dispatch_order(a, b),
+        _ => a.added_at.cmp(&b.added_at).then_with(|| a.id.cmp(&b.id)),
+    })
+}
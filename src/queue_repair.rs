@@ -0,0 +1,83 @@
+// src/queue_repair.rs
+//! Recovery for downloads left in Downloading with nothing running them,
+//! e.g. after a crash or a download task that died. Each is queued again
+//! when yt-dlp can continue from its partial files, or failed when the
+//! progress it made is gone.
+
+use crate::cleanup::scan_partial_files;
+use crate::download_manager::DownloadItem;
+use crate::error::AppError;
+use humansize::{format_size, BINARY};
+
+/// What a repair does with a stuck download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairAction {
+    /// Put it back in the queue
+    Requeue,
+    /// Mark it failed so it can be retried deliberately
+    Fail,
+}
+
+/// A repair planned for one stuck download
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    pub id: String,
+    pub title: Option<String>,
+    pub action: RepairAction,
+    /// Size of the partial files found for the download
+    pub partial_bytes: u64,
+}
+
+/// Error recorded on a download failed by a repair
+pub const LOST_PROGRESS_MESSAGE: &str = "Interrupted, and its partial files are gone; retry to start over";
+
+impl Repair {
+    /// One line for `queue repair`, e.g. "a1b2c3d4 My Video: queued again (12 MiB on disk)"
+    pub fn describe(&self) -> String {
+        let short_id = self.id.get(..8).unwrap_or(&self.id);
+        let outcome = match self.action {
+            RepairAction::Requeue if self.partial_bytes > 0 => {
+                format!("queued again ({} on disk)", format_size(self.partial_bytes, BINARY))
+            }
+            RepairAction::Requeue => "queued again".to_string(),
+            RepairAction::Fail => "failed (no partial files left)".to_string(),
+        };
+        format!("{} {}: {}", short_id, self.title.as_deref().unwrap_or("(untitled)"), outcome)
+    }
+}
+
+/// Decide what to do with a stuck download, given the size of the partial
+/// files it left. One that never got going just goes back in the queue.
+pub fn plan_repair(item: &DownloadItem, partial_bytes: u64) -> Repair {
+    let action = if partial_bytes > 0 || item.progress <= 0.0 {
+        RepairAction::Requeue
+    } else {
+        RepairAction::Fail
+    };
+    Repair {
+        id: item.id.clone(),
+        title: item.title.clone(),
+        action,
+        partial_bytes,
+    }
+}
+
+/// Plan repairs for stuck downloads, looking for their partial files in the
+/// download directories
+pub fn plan_repairs(stuck: &[DownloadItem]) -> Result<Vec<Repair>, AppError> {
+    if stuck.is_empty() {
+        return Ok(Vec::new());
+    }
+    let partial_files = scan_partial_files(stuck)?;
+    Ok(stuck
+        .iter()
+        .map(|item| {
+            let partial_bytes = partial_files
+                .iter()
+                .filter(|file| file.download.as_ref().is_some_and(|download| download.id == item.id))
+                .map(|file| file.size)
+                .sum();
+            plan_repair(item, partial_bytes)
+        })
+        .collect())
+}
@@ -0,0 +1,176 @@
+// src/queue_schedule.rs
+//! Start times and recurring time windows for queued downloads. A queued
+//! download with a start time, a window or both stays in the queue until
+//! it may start; the queue processor wakes up when the next one is due.
+//! Windows are in local time and only hold back the start: a download
+//! still running when its window closes keeps going.
+
+use crate::error::AppError;
+use crate::utils::parse_duration;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const TIME_FORMAT: &str = "%H:%M";
+
+/// A daily window in local time, e.g. 01:00-06:00. A window whose end is
+/// before its start runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Parse a window such as "01:00-06:00" or "22:30–05:00"
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let invalid = || {
+            AppError::ValidationError(format!(
+                "Invalid time window '{}', expected e.g. 01:00-06:00",
+                value
+            ))
+        };
+        let (start, end) = value.split_once(['-', '–']).ok_or_else(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), TIME_FORMAT).map_err(|_| invalid())?;
+        let end = NaiveTime::parse_from_str(end.trim(), TIME_FORMAT).map_err(|_| invalid())?;
+        if start == end {
+            return Err(AppError::ValidationError(format!(
+                "Time window '{}' is empty; its start and end must differ",
+                value
+            )));
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Whether `time` is inside the window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// How long from `time` until the window opens; zero inside it
+    pub fn opens_in(&self, time: NaiveTime) -> Duration {
+        if self.contains(time) {
+            return Duration::zero();
+        }
+        let wait = self.start.signed_duration_since(time);
+        if wait < Duration::zero() {
+            wait + Duration::days(1)
+        } else {
+            wait
+        }
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format(TIME_FORMAT), self.end.format(TIME_FORMAT))
+    }
+}
+
+impl TryFrom<String> for TimeWindow {
+    type Error = AppError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<TimeWindow> for String {
+    fn from(window: TimeWindow) -> Self {
+        window.to_string()
+    }
+}
+
+/// Parse a start time relative to `now`: "+2h" (from now), "23:30" (the
+/// next time the clock shows it), "2026-03-01 23:30" or an RFC 3339 time.
+/// Times without an offset are in `now`'s time zone.
+pub fn parse_start_time<Tz: TimeZone>(value: &str, now: &DateTime<Tz>) -> Result<DateTime<Utc>, AppError> {
+    let value = value.trim();
+    let now_utc = now.with_timezone(&Utc);
+    if let Some(delay) = value.strip_prefix('+') {
+        return Ok(now_utc + Duration::seconds(parse_duration(delay)? as i64));
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return in_future(at.with_timezone(&Utc), now_utc, value);
+    }
+
+    let local = |naive: NaiveDateTime| {
+        now.timezone()
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|at| at.with_timezone(&Utc))
+            .ok_or_else(|| AppError::ValidationError(format!("'{}' doesn't exist in the local time zone", value)))
+    };
+    if let Ok(time) = NaiveTime::parse_from_str(value, TIME_FORMAT) {
+        let today = now.date_naive();
+        let at = local(today.and_time(time))?;
+        if at > now_utc {
+            return Ok(at);
+        }
+        let tomorrow = today.succ_opt().unwrap_or(NaiveDate::MAX);
+        return local(tomorrow.and_time(time));
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M") {
+        return in_future(local(naive)?, now_utc, value);
+    }
+    Err(AppError::ValidationError(format!(
+        "Invalid start time '{}', expected e.g. +2h, 23:30, \"2026-03-01 23:30\" or an RFC 3339 time",
+        value
+    )))
+}
+
+fn in_future(at: DateTime<Utc>, now: DateTime<Utc>, value: &str) -> Result<DateTime<Utc>, AppError> {
+    if at <= now {
+        return Err(AppError::ValidationError(format!("Start time '{}' is in the past", value)));
+    }
+    Ok(at)
+}
+
+/// How long a queued download with this start time and window waits from
+/// `now`, or None when it may start now. The window is checked at the
+/// start time, in `now`'s time zone.
+pub fn start_delay<Tz: TimeZone>(
+    scheduled_at: Option<DateTime<Utc>>,
+    window: Option<TimeWindow>,
+    now: &DateTime<Tz>,
+) -> Option<Duration> {
+    let wait = scheduled_at
+        .map(|at| at - now.with_timezone(&Utc))
+        .filter(|wait| *wait > Duration::zero())
+        .unwrap_or_else(Duration::zero);
+    let start = now.clone() + wait;
+    let wait = wait + window.map_or_else(Duration::zero, |window| window.opens_in(start.time()));
+    (wait > Duration::zero()).then_some(wait)
+}
+
+/// A change to when a queued download may start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScheduleChange {
+    /// Start no earlier than this
+    pub at: Option<DateTime<Utc>>,
+    /// Only start inside this window
+    pub window: Option<TimeWindow>,
+    /// Remove the start time and window before applying the others
+    pub clear: bool,
+}
+
+impl ScheduleChange {
+    /// Apply the change to a download's start time and window
+    pub fn apply(&self, scheduled_at: &mut Option<DateTime<Utc>>, window: &mut Option<TimeWindow>) {
+        if self.clear {
+            *scheduled_at = None;
+            *window = None;
+        }
+        if self.at.is_some() {
+            *scheduled_at = self.at;
+        }
+        if self.window.is_some() {
+            *window = self.window;
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// src/queue_undo.rs
+//! Snapshots taken before destructive queue operations (`clear-completed`,
+//! `clear-failed`, bulk cancel) so `queue undo` can bring the downloads back.
+//!
+//! Snapshots are kept next to the queue state, newest last, and go through
+//! the same optional encryption at rest.
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::download_map::DownloadMap;
+use crate::error::AppError;
+use crate::state_crypto::{read_state_file, write_state_file};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Number of operations that can be undone
+pub const MAX_QUEUE_SNAPSHOTS: usize = 10;
+
+/// The downloads an operation changed, as they were before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueSnapshot {
+    /// Command that changed the queue, e.g. "clear-failed"
+    pub operation: String,
+    pub taken_at: DateTime<Utc>,
+    pub items: Vec<DownloadItem>,
+    /// Pending queue order before the operation
+    pub queue: Vec<String>,
+}
+
+impl QueueSnapshot {
+    pub fn new(operation: &str, items: Vec<DownloadItem>, queue: Vec<String>) -> Self {
+        Self {
+            operation: operation.to_string(),
+            taken_at: Utc::now(),
+            items,
+            queue,
+        }
+    }
+
+    /// One line for listings, e.g. "clear-failed (3 downloads) at 14:02:11"
+    pub fn describe(&self) -> String {
+        let local = self.taken_at.with_timezone(&chrono::Local);
+        format!(
+            "{} ({} download{}) at {}",
+            self.operation,
+            self.items.len(),
+            if self.items.len() == 1 { "" } else { "s" },
+            local.format("%Y-%m-%d %H:%M:%S")
+        )
+    }
+}
+
+/// Where snapshots are kept for the queue state at `state_path`
+pub fn get_queue_snapshots_path(state_path: &Path) -> PathBuf {
+    state_path.with_file_name("queue_snapshots.json")
+}
+
+/// Read the snapshot history, oldest first
+pub fn load_snapshots(path: &Path) -> Result<Vec<QueueSnapshot>, AppError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&read_state_file(path)?)?)
+}
+
+pub fn save_snapshots(path: &Path, snapshots: &[QueueSnapshot]) -> Result<(), AppError> {
+    write_state_file(path, &serde_json::to_string_pretty(snapshots)?)
+}
+
+/// Add a snapshot, dropping the oldest beyond `MAX_QUEUE_SNAPSHOTS`
+pub fn push_snapshot(snapshots: &mut Vec<QueueSnapshot>, snapshot: QueueSnapshot) {
+    snapshots.push(snapshot);
+    if snapshots.len() > MAX_QUEUE_SNAPSHOTS {
+        let excess = snapshots.len() - MAX_QUEUE_SNAPSHOTS;
+        snapshots.drain(..excess);
+    }
+}
+
+/// Append a snapshot to the history on disk
+pub fn record_snapshot(path: &Path, snapshot: QueueSnapshot) -> Result<(), AppError> {
+    // A damaged history shouldn't block the operation being recorded
+    let mut snapshots = load_snapshots(path).unwrap_or_default();
+    push_snapshot(&mut snapshots, snapshot);
+    save_snapshots(path, &snapshots)
+}
+
+/// Put the downloads from a snapshot back. A download is skipped if it has
+/// changed since, e.g. it was re-added or a canceled one was retried.
+/// Downloads that were running are queued again at the front. Returns the
+/// IDs restored.
+pub fn restore_snapshot(
+    queue: &mut Vec<String>,
+    downloads: &mut DownloadMap,
+    snapshot: &QueueSnapshot,
+) -> Vec<String> {
+    let mut restored = Vec::new();
+    let mut were_running = Vec::new();
+    for saved in &snapshot.items {
+        let unchanged = match downloads.get(&saved.id) {
+            None => true,
+            Some(current) => current.status == DownloadStatus::Canceled && saved.status != DownloadStatus::Canceled,
+        };
+        if !unchanged {
+            continue;
+        }
+        let mut item = saved.clone();
+        item.cancel_token = None;
+        if item.status == DownloadStatus::Downloading {
+            item.status = DownloadStatus::Queued;
+            were_running.push(item.id.clone());
+        }
+        restored.push(item.id.clone());
+        downloads.insert(item.id.clone(), item);
+    }
+
+    // Pending downloads go back in their old order, ahead of anything queued since
+    let is_queued = |id: &String| downloads.get(id).is_some_and(|item| item.status == DownloadStatus::Queued);
+    let mut order: Vec<String> = were_running;
+    let candidates = snapshot
+        .queue
+        .iter()
+        .filter(|id| is_queued(id))
+        .chain(queue.iter())
+        .chain(restored.iter().filter(|id| is_queued(id)));
+    for id in candidates {
+        if !order.contains(id) {
+            order.push(id.clone());
+        }
+    }
+    *queue = order;
+    restored
+}
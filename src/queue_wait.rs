@@ -0,0 +1,112 @@
+// src/queue_wait.rs
+//! `rustloader queue wait`: block until a download (or the whole queue)
+//! finishes, then exit with a code scripts can branch on:
+//!
+//! ```sh
+//! rustloader queue wait dl_1700000000000_42 --timeout 1h && ./process.sh
+//! ```
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use crate::error::AppError;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Exit code when a download failed
+pub const EXIT_FAILED: i32 = 3;
+/// Exit code when a download was canceled
+pub const EXIT_CANCELED: i32 = 4;
+/// Exit code when the timeout ran out first, as with coreutils `timeout`
+pub const EXIT_TIMED_OUT: i32 = 124;
+
+/// What to wait for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitTarget {
+    /// One download, by full ID
+    Download(String),
+    /// Every download in the queue that isn't paused
+    All,
+}
+
+/// How the wait ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    Completed,
+    Failed,
+    Canceled,
+    TimedOut,
+}
+
+impl WaitOutcome {
+    /// The process exit code for this outcome
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            WaitOutcome::Completed => 0,
+            WaitOutcome::Failed => EXIT_FAILED,
+            WaitOutcome::Canceled => EXIT_CANCELED,
+            WaitOutcome::TimedOut => EXIT_TIMED_OUT,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WaitOutcome::Completed => "completed",
+            WaitOutcome::Failed => "failed",
+            WaitOutcome::Canceled => "canceled",
+            WaitOutcome::TimedOut => "timed out",
+        }
+    }
+}
+
+/// The outcome once everything `target` covers has finished, or None while
+/// something is still queued or running. With several downloads, a failure
+/// outranks a cancel, which outranks success.
+pub fn settled_outcome(target: &WaitTarget, items: &[DownloadItem]) -> Result<Option<WaitOutcome>, AppError> {
+    let waited: Vec<&DownloadItem> = match target {
+        WaitTarget::Download(id) => {
+            let item = items
+                .iter()
+                .find(|item| &item.id == id)
+                .ok_or_else(|| AppError::ValidationError(format!("Download {} is no longer in the queue", id)))?;
+            vec![item]
+        }
+        // Paused downloads won't finish on their own, as with `queue run --until-empty`
+        WaitTarget::All => items.iter().filter(|item| !item.is_paused()).collect(),
+    };
+
+    if waited.iter().any(|item| !item.is_finished()) {
+        return Ok(None);
+    }
+    let any = |status: DownloadStatus| waited.iter().any(|item| item.status == status);
+    Ok(Some(if any(DownloadStatus::Failed) {
+        WaitOutcome::Failed
+    } else if any(DownloadStatus::Canceled) {
+        WaitOutcome::Canceled
+    } else {
+        WaitOutcome::Completed
+    }))
+}
+
+/// Check the downloads returned by `snapshot` every `poll` until `target`
+/// settles or `timeout` runs out
+pub async fn wait_for_downloads<F>(
+    target: &WaitTarget,
+    timeout: Option<Duration>,
+    poll: Duration,
+    mut snapshot: F,
+) -> Result<WaitOutcome, AppError>
+where
+    F: FnMut() -> Result<Vec<DownloadItem>, AppError>,
+{
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        if let Some(outcome) = settled_outcome(target, &snapshot()?)? {
+            return Ok(outcome);
+        }
+        let sleep = match deadline {
+            Some(deadline) if Instant::now() >= deadline => return Ok(WaitOutcome::TimedOut),
+            Some(deadline) => poll.min(deadline - Instant::now()),
+            None => poll,
+        };
+        tokio::time::sleep(sleep).await;
+    }
+}
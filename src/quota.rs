@@ -0,0 +1,56 @@
+// src/quota.rs
+//! How much of the free version's daily download limit is left and when it
+//! starts over, so `rustloader limits` and the GUI can show it before a
+//! download fails on it.
+
+use crate::utils::format_duration;
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use serde::Serialize;
+
+/// Today's use of the daily download limit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaStatus {
+    /// Downloads started today
+    pub used: u32,
+    /// Downloads allowed per day
+    pub limit: u32,
+    /// Downloads left today
+    pub remaining: u32,
+    /// When the count starts over (local midnight)
+    pub resets_at: DateTime<Local>,
+}
+
+impl QuotaStatus {
+    /// Status for `used` of `limit` downloads as of `now`
+    pub fn new(used: u32, limit: u32, now: DateTime<Local>) -> Self {
+        Self {
+            used,
+            limit,
+            remaining: limit.saturating_sub(used),
+            resets_at: next_reset(now),
+        }
+    }
+
+    /// Time until the count starts over
+    pub fn resets_in(&self, now: DateTime<Local>) -> Duration {
+        (self.resets_at - now).max(Duration::zero())
+    }
+
+    /// e.g. "3 of 5 downloads left today, resets in 6h 0m"
+    pub fn describe(&self, now: DateTime<Local>) -> String {
+        format!(
+            "{} of {} downloads left today, resets in {}",
+            self.remaining,
+            self.limit,
+            format_duration(self.resets_in(now).num_seconds() as u64)
+        )
+    }
+}
+
+/// The next local midnight after `now`, when the daily count starts over
+pub fn next_reset(now: DateTime<Local>) -> DateTime<Local> {
+    now.date_naive()
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_time(NaiveTime::MIN).and_local_timezone(Local).earliest())
+        .unwrap_or_else(|| now + Duration::days(1))
+}
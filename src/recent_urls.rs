@@ -0,0 +1,154 @@
+// src/recent_urls.rs
+// Recently used download sources, kept for address bar and shell autocompletion
+
+use crate::config::get_config_path;
+use crate::error::AppError;
+use crate::state_crypto::{read_state_file, write_state_file};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Maximum number of sources remembered; the least recently used are dropped
+pub const MAX_RECENT_SOURCES: usize = 200;
+/// Maximum number of suggestions returned for a prefix
+pub const MAX_SUGGESTIONS: usize = 10;
+
+/// A URL that was downloaded or attempted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentSource {
+    pub url: String,
+    /// Channel or uploader name, when known
+    pub channel: Option<String>,
+    pub last_used: DateTime<Utc>,
+    pub use_count: u32,
+}
+
+/// The recent sources store, most recently used first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentSources {
+    pub sources: Vec<RecentSource>,
+}
+
+impl RecentSources {
+    /// Record a use of `url`, moving it to the front. A known channel name is
+    /// kept when a later use doesn't supply one.
+    pub fn record(&mut self, url: &str, channel: Option<&str>, now: DateTime<Utc>) {
+        let mut source = match self.sources.iter().position(|s| s.url == url) {
+            Some(index) => self.sources.remove(index),
+            None => RecentSource {
+                url: url.to_string(),
+                channel: None,
+                last_used: now,
+                use_count: 0,
+            },
+        };
+
+        if let Some(channel) = channel.filter(|c| !c.trim().is_empty()) {
+            source.channel = Some(channel.trim().to_string());
+        }
+        source.last_used = now;
+        source.use_count = source.use_count.saturating_add(1);
+
+        self.sources.insert(0, source);
+        self.sources.truncate(MAX_RECENT_SOURCES);
+    }
+
+    /// Fill in the channel of a known `url` without counting it as a use
+    pub fn set_channel(&mut self, url: &str, channel: &str) {
+        let channel = channel.trim();
+        if channel.is_empty() {
+            return;
+        }
+        if let Some(source) = self.sources.iter_mut().find(|source| source.url == url) {
+            source.channel = Some(channel.to_string());
+        }
+    }
+
+    /// URLs matching `prefix`, most recent first. The prefix matches the start
+    /// of a URL (ignoring scheme and `www.`) or of its channel name.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let prefix = prefix.trim().to_lowercase();
+        if prefix.is_empty() {
+            return self.sources.iter().take(limit).map(|s| s.url.clone()).collect();
+        }
+        let bare_prefix = strip_scheme(&prefix);
+
+        self.sources
+            .iter()
+            .filter(|source| {
+                let url = source.url.to_lowercase();
+                let channel_matches = source
+                    .channel
+                    .as_deref()
+                    .is_some_and(|channel| channel.to_lowercase().starts_with(&prefix));
+                url.starts_with(&prefix) || strip_scheme(&url).starts_with(bare_prefix) || channel_matches
+            })
+            .take(limit)
+            .map(|source| source.url.clone())
+            .collect()
+    }
+}
+
+/// `https://www.example.com/x` -> `example.com/x`
+fn strip_scheme(url: &str) -> &str {
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    url.strip_prefix("www.").unwrap_or(url)
+}
+
+/// Path of the recent sources file, next to `config.json`
+pub fn get_recent_sources_path() -> Result<PathBuf, AppError> {
+    Ok(get_config_path()?.with_file_name("recent_urls.json"))
+}
+
+/// Load the recent sources, starting empty if none were saved
+pub fn load_recent_sources() -> Result<RecentSources, AppError> {
+    let path = get_recent_sources_path()?;
+    if !path.exists() {
+        return Ok(RecentSources::default());
+    }
+
+    let contents = read_state_file(&path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+fn save_recent_sources(recent: &RecentSources) -> Result<(), AppError> {
+    let path = get_recent_sources_path()?;
+    write_state_file(&path, &serde_json::to_string_pretty(recent)?)?;
+    debug!("Recent sources saved to {:?}", path);
+    Ok(())
+}
+
+/// Remember a downloaded or attempted URL. Failures are logged rather than
+/// returned so they never interrupt a download.
+pub fn remember_url(url: &str, channel: Option<&str>) {
+    let result = load_recent_sources().and_then(|mut recent| {
+        recent.record(url, channel, Utc::now());
+        save_recent_sources(&recent)
+    });
+
+    if let Err(e) = result {
+        warn!("Could not update recent URLs: {}", e);
+    }
+}
+
+/// Remember the channel of a URL already recorded with [`remember_url`]
+pub fn remember_channel(url: &str, channel: &str) {
+    let result = load_recent_sources().and_then(|mut recent| {
+        recent.set_channel(url, channel);
+        save_recent_sources(&recent)
+    });
+
+    if let Err(e) = result {
+        warn!("Could not update recent URLs: {}", e);
+    }
+}
+
+/// Previously used URLs that start with `prefix`, for autocompletion
+pub fn suggest_urls(prefix: &str) -> Result<Vec<String>, AppError> {
+    Ok(load_recent_sources()?.suggest(prefix, MAX_SUGGESTIONS))
+}
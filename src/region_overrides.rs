@@ -0,0 +1,95 @@
+// src/region_overrides.rs
+//! Per-download overrides for region-specific availability: the YouTube
+//! player clients yt-dlp asks for formats with, and the country yt-dlp
+//! pretends to be in for geo-restricted sites. They are typed and checked
+//! here rather than passed through raw, and apply only to the one download.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+/// YouTube player clients yt-dlp knows
+pub const PLAYER_CLIENTS: &[&str] = &[
+    "web",
+    "web_safari",
+    "web_embedded",
+    "web_music",
+    "web_creator",
+    "mweb",
+    "android",
+    "android_vr",
+    "ios",
+    "tv",
+    "tv_embedded",
+];
+
+/// Extractor settings for one download
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegionOverrides {
+    /// YouTube player clients to request formats with, in order
+    pub player_clients: Vec<String>,
+    /// Two-letter country code passed to yt-dlp's geo bypass
+    pub geo_bypass_country: Option<String>,
+}
+
+impl RegionOverrides {
+    /// Build from command-line values, e.g. "android,ios" and "DE"
+    pub fn from_args(player_clients: Option<&str>, geo_bypass_country: Option<&str>) -> Result<Self, AppError> {
+        Ok(Self {
+            player_clients: player_clients.map(parse_player_clients).transpose()?.unwrap_or_default(),
+            geo_bypass_country: geo_bypass_country.map(parse_country_code).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.player_clients.is_empty() && self.geo_bypass_country.is_none()
+    }
+
+    /// Value for yt-dlp's `--extractor-args`, if a player client is set
+    pub fn extractor_args(&self) -> Option<String> {
+        (!self.player_clients.is_empty()).then(|| format!("youtube:player_client={}", self.player_clients.join(",")))
+    }
+
+    /// Short description, e.g. "player client android, country DE"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.player_clients.is_empty() {
+            parts.push(format!("player client {}", self.player_clients.join(", ")));
+        }
+        if let Some(country) = &self.geo_bypass_country {
+            parts.push(format!("country {}", country));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Parse a comma-separated list of YouTube player clients
+pub fn parse_player_clients(value: &str) -> Result<Vec<String>, AppError> {
+    let mut clients = Vec::new();
+    for client in value.split(',').map(|client| client.trim().to_lowercase()) {
+        if !PLAYER_CLIENTS.contains(&client.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Unknown player client '{}'; use one of {}",
+                client,
+                PLAYER_CLIENTS.join(", ")
+            )));
+        }
+        if !clients.contains(&client) {
+            clients.push(client);
+        }
+    }
+    Ok(clients)
+}
+
+/// Parse an ISO 3166-1 alpha-2 country code such as "DE" or "us"
+pub fn parse_country_code(value: &str) -> Result<String, AppError> {
+    let code = value.trim().to_uppercase();
+    if code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(code)
+    } else {
+        Err(AppError::ValidationError(format!(
+            "'{}' is not a two-letter country code such as US or DE",
+            value
+        )))
+    }
+}
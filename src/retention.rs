@@ -0,0 +1,178 @@
+// src/retention.rs
+//! Retention policies: how long finished downloads keep their files and
+//! their queue entries, per tag. Policies live in config.json, e.g.
+//! `{"tag": "temp", "delete_files_after_days": 7}` or
+//! `{"remove_entries_after_days": 30}` for every download. The queue's
+//! maintenance task applies them; `rustloader gc --dry-run` previews it.
+
+use crate::download_manager::DownloadItem;
+use crate::error::AppError;
+use crate::security::validate_path_safety;
+use chrono::{DateTime, Duration, Utc};
+use humansize::{format_size, BINARY};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// How long downloads with a tag (or all downloads) are kept
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Downloads tagged with this; without a tag the policy covers every download
+    pub tag: Option<String>,
+    /// Delete the downloaded file this many days after the download finished
+    pub delete_files_after_days: Option<u32>,
+    /// Remove completed downloads from the queue this many days after they finished
+    pub remove_entries_after_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Whether the policy covers a download
+    pub fn applies_to(&self, item: &DownloadItem) -> bool {
+        match &self.tag {
+            Some(tag) => item.tags.iter().any(|item_tag| item_tag.eq_ignore_ascii_case(tag.trim())),
+            None => true,
+        }
+    }
+}
+
+/// Normalize a download tag: lowercase letters, digits, `-` and `_`
+pub fn parse_tag(tag: &str) -> Result<String, AppError> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() || !tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(AppError::ValidationError(format!(
+            "Invalid tag '{}': use letters, digits, '-' and '_'",
+            tag
+        )));
+    }
+    Ok(tag)
+}
+
+/// Check the configured policies before any of them deletes anything
+pub fn validate_policies(policies: &[RetentionPolicy]) -> Result<(), AppError> {
+    for policy in policies {
+        if let Some(tag) = &policy.tag {
+            parse_tag(tag)?;
+        }
+        if policy.delete_files_after_days.is_none() && policy.remove_entries_after_days.is_none() {
+            return Err(AppError::ValidationError(format!(
+                "Retention policy for {} sets neither delete_files_after_days nor remove_entries_after_days",
+                policy.tag.as_deref().map_or("all downloads".to_string(), |tag| format!("tag '{}'", tag))
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// What a retention policy does to an expired download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcAction {
+    /// Delete its downloaded file
+    DeleteFile,
+    /// Remove it from the queue
+    RemoveEntry,
+}
+
+/// One expired file or queue entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcItem {
+    pub id: String,
+    pub title: Option<String>,
+    pub action: GcAction,
+    /// The file to delete
+    pub path: Option<PathBuf>,
+    /// Size of the file to delete
+    pub bytes: u64,
+}
+
+impl GcItem {
+    /// One line for `rustloader gc`, e.g. "a1b2c3d4 My Video: delete /videos/My Video.mp4 (12 MiB)"
+    pub fn describe(&self) -> String {
+        let short_id = self.id.get(..8).unwrap_or(&self.id);
+        let what = match (self.action, &self.path) {
+            (GcAction::DeleteFile, Some(path)) => {
+                format!("delete {} ({})", path.display(), format_size(self.bytes, BINARY))
+            }
+            (GcAction::DeleteFile, None) => "delete its file".to_string(),
+            (GcAction::RemoveEntry, _) => "remove from the queue".to_string(),
+        };
+        format!("{} {}: {}", short_id, self.title.as_deref().unwrap_or("(untitled)"), what)
+    }
+}
+
+/// The shortest limit any policy covering the download sets
+fn limit(policies: &[RetentionPolicy], item: &DownloadItem, days: fn(&RetentionPolicy) -> Option<u32>) -> Option<Duration> {
+    policies
+        .iter()
+        .filter(|policy| policy.applies_to(item))
+        .filter_map(days)
+        .min()
+        .map(|days| Duration::days(days as i64))
+}
+
+/// Plan what the policies expire at `now`: files of completed downloads in
+/// the queue or the history archive, and completed entries of the queue.
+/// Files already gone are skipped, so a plan can be made again at any time.
+pub fn plan_gc(
+    policies: &[RetentionPolicy],
+    queued: &[DownloadItem],
+    archived: &[DownloadItem],
+    now: DateTime<Utc>,
+) -> Vec<GcItem> {
+    let mut plan = Vec::new();
+    let in_queue = queued.iter().map(|item| (item, true));
+    let in_archive = archived.iter().map(|item| (item, false));
+    for (item, is_queued) in in_queue.chain(in_archive).filter(|(item, _)| item.is_completed()) {
+        let age = now - item.finished_at.unwrap_or(item.added_at);
+        let expired = |days: fn(&RetentionPolicy) -> Option<u32>| limit(policies, item, days).is_some_and(|max| age >= max);
+
+        if expired(|policy| policy.delete_files_after_days) {
+            let file = item
+                .output_path
+                .as_ref()
+                .map(PathBuf::from)
+                .and_then(|path| fs::metadata(&path).ok().filter(|meta| meta.is_file()).map(|meta| (path, meta.len())));
+            if let Some((path, bytes)) = file {
+                plan.push(GcItem {
+                    id: item.id.clone(),
+                    title: item.title.clone(),
+                    action: GcAction::DeleteFile,
+                    path: Some(path),
+                    bytes,
+                });
+            }
+        }
+        if is_queued && expired(|policy| policy.remove_entries_after_days) {
+            plan.push(GcItem {
+                id: item.id.clone(),
+                title: item.title.clone(),
+                action: GcAction::RemoveEntry,
+                path: None,
+                bytes: 0,
+            });
+        }
+    }
+    plan
+}
+
+/// Delete the files a plan expires, skipping any outside the allowed
+/// directories. Returns the items whose files were deleted.
+pub fn delete_expired_files(plan: &[GcItem]) -> Vec<GcItem> {
+    plan.iter()
+        .filter(|item| item.action == GcAction::DeleteFile)
+        .filter(|item| {
+            let Some(path) = &item.path else {
+                return false;
+            };
+            match validate_path_safety(path).and_then(|()| fs::remove_file(path).map_err(AppError::IoError)) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Could not delete expired file {}: {}", path.display(), e);
+                    false
+                }
+            }
+        })
+        .cloned()
+        .collect()
+}
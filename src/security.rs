@@ -4,12 +4,13 @@
 //! and utilities to enhance the overall security posture of the application.
 
 use crate::error::AppError;
+use crate::paths::default_download_dir;
 use base64::{engine::general_purpose, Engine as _};
 use once_cell::sync::Lazy;
 use ring::hmac;
 use ring::rand::{SecureRandom, SystemRandom};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, Once};
 use std::time::{Duration, Instant};
 
@@ -100,7 +101,6 @@ pub fn apply_rate_limit(operation: &str, max_attempts: usize, window: Duration)
 }
 
 /// Generate an HMAC signature for the provided data
-#[allow(dead_code)]
 pub fn generate_hmac_signature(data: &[u8], key: &[u8]) -> Result<Vec<u8>, AppError> {
     let hmac_key = hmac::Key::new(hmac::HMAC_SHA256, key);
     let signature = hmac::sign(&hmac_key, data);
@@ -108,7 +108,6 @@ pub fn generate_hmac_signature(data: &[u8], key: &[u8]) -> Result<Vec<u8>, AppEr
 }
 
 /// Verify an HMAC signature for the provided data
-#[allow(dead_code)]
 pub fn verify_hmac_signature(data: &[u8], signature: &[u8], key: &[u8]) -> Result<bool, AppError> {
     let hmac_key = hmac::Key::new(hmac::HMAC_SHA256, key);
 
@@ -118,6 +117,61 @@ pub fn verify_hmac_signature(data: &[u8], signature: &[u8], key: &[u8]) -> Resul
     }
 }
 
+/// Derive the machine-bound key used to sign local state files
+/// (download counter, persisted queue)
+pub fn get_local_state_key() -> Vec<u8> {
+    let machine_id = match get_machine_id() {
+        Ok(id) => id,
+        Err(_) => "DefaultCounterKey".to_string(),
+    };
+
+    let digest = ring::digest::digest(&ring::digest::SHA256, machine_id.as_bytes());
+    digest.as_ref()[..16].to_vec()
+}
+
+fn get_machine_id() -> Result<String, AppError> {
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(id) = std::fs::read_to_string("/etc/machine-id") {
+            return Ok(id.trim().to_string());
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use std::process::Command;
+        if let Ok(output) = Command::new("ioreg")
+            .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+            .output()
+        {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if let Some(line) = stdout.lines().find(|line| line.contains("IOPlatformUUID")) {
+                if let Some(uuid_start) = line.find("\"") {
+                    if let Some(uuid_end) = line[uuid_start + 1..].find("\"") {
+                        return Ok(line[uuid_start + 1..uuid_start + 1 + uuid_end].to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use winreg::enums::*;
+        use winreg::RegKey;
+        if let Ok(key) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey("SOFTWARE\\Microsoft\\Cryptography") {
+            if let Ok(guid) = key.get_value::<String, _>("MachineGuid") {
+                return Ok(guid);
+            }
+        }
+    }
+
+    match hostname::get() {
+        Ok(name) => Ok(name.to_string_lossy().to_string()),
+        Err(_) => Err(AppError::General("Could not determine machine ID".to_string())),
+    }
+}
+
 /// Enhanced path safety validation with centralized security settings
 pub fn validate_path_safety(path: &Path) -> Result<(), AppError> {
     // Canonicalize the path to resolve any .. or symlinks
@@ -181,23 +235,47 @@ pub fn validate_path_safety(path: &Path) -> Result<(), AppError> {
         .any(|allowed| path_str.starts_with(allowed));
 
     if !in_allowed_path {
-        return Err(AppError::SecurityViolation);
+        return Err(AppError::InvalidPath {
+            path: path.display().to_string(),
+            rule: "must be inside your home or temp directory".to_string(),
+            suggestion: default_download_dir().map(|dir| dir.display().to_string()),
+        });
     }
 
     // Check if path contains any sensitive directories
     for dir in SENSITIVE_DIRECTORIES.iter() {
         if path_str.starts_with(dir) {
-            return Err(AppError::SecurityViolation);
+            return Err(AppError::InvalidPath {
+                path: path.display().to_string(),
+                rule: format!("must not be inside the system directory {}", dir),
+                suggestion: default_download_dir().map(|dir| dir.display().to_string()),
+            });
         }
     }
 
     Ok(())
 }
 
+/// Resolve an existing file or directory to its canonical form and check it
+/// lies in an allowed location, e.g. before handing it to an external program
+pub fn validate_existing_path(path: &Path) -> Result<PathBuf, AppError> {
+    let canonical_path = path
+        .canonicalize()
+        .map_err(|_| AppError::PathError(format!("Path does not exist: {}", path.display())))?;
+    validate_path_safety(&canonical_path)?;
+    Ok(canonical_path)
+}
+
 /// Check path components for relative traversal attempts
 fn check_path_components(path: &Path) -> Result<(), AppError> {
     let path_str = path.to_string_lossy();
 
+    let traversal = || AppError::InvalidPath {
+        path: path_str.to_string(),
+        rule: "must not contain '..' or '~'".to_string(),
+        suggestion: suggest_safe_path(&path_str),
+    };
+
     // Check for potential path traversal sequences
     if path_str.contains("../")
         || path_str.contains("..\\")
@@ -205,7 +283,7 @@ fn check_path_components(path: &Path) -> Result<(), AppError> {
         || path_str.contains("\\..")
         || path_str.contains("~")
     {
-        return Err(AppError::SecurityViolation);
+        return Err(traversal());
     }
 
     // Check each component
@@ -213,7 +291,7 @@ fn check_path_components(path: &Path) -> Result<(), AppError> {
         match component {
             std::path::Component::ParentDir => {
                 // Attempting to navigate up - potential path traversal
-                return Err(AppError::SecurityViolation);
+                return Err(traversal());
             }
             _ => continue,
         }
@@ -222,6 +300,39 @@ fn check_path_components(path: &Path) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Wildcard and shell characters rejected in download directories
+pub const FORBIDDEN_PATH_CHARS: [char; 7] = ['*', '?', '|', ';', '&', '<', '>'];
+
+/// A corrected version of a rejected path, if one can be worked out: a
+/// leading `~` becomes the home directory, `.` and `..` are resolved
+/// without climbing above the path's start, and wildcard and shell
+/// characters are dropped
+pub fn suggest_safe_path(path: &str) -> Option<String> {
+    let expanded = match (path.strip_prefix('~'), dirs_next::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => path.to_string(),
+    };
+    let cleaned: String = expanded
+        .chars()
+        .filter(|c| *c != '~' && !FORBIDDEN_PATH_CHARS.contains(c))
+        .collect();
+
+    let mut resolved = PathBuf::new();
+    for component in Path::new(&cleaned).components() {
+        match component {
+            std::path::Component::ParentDir => {
+                resolved.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    let suggestion = resolved.to_string_lossy().into_owned();
+    (!suggestion.is_empty() && suggestion != path).then_some(suggestion)
+}
+
 /// Verify the integrity of security-critical files
 fn verify_application_integrity() -> Result<(), AppError> {
     // In a real implementation, this would verify hashes of critical files
@@ -464,3 +575,109 @@ pub fn secure_delete_file(file_path: &Path) -> Result<(), AppError> {
 
     Ok(())
 }
+
+// Sandboxing rules for external plugins
+
+/// Environment variables passed through to plugins; everything else,
+/// including credentials in the user's environment, is withheld
+pub const PLUGIN_ENV_ALLOWLIST: [&str; 6] = ["PATH", "HOME", "LANG", "TMPDIR", "SYSTEMROOT", "TEMP"];
+/// Largest response a plugin may write to stdout
+pub const PLUGIN_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+/// Longest a plugin may run for a single request
+pub const PLUGIN_MAX_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Resolve a file shipped with a plugin, which must be a relative path to
+/// a file inside the plugin's own directory that only its owner can modify
+pub fn validate_plugin_file(plugin_dir: &Path, file: &str) -> Result<PathBuf, AppError> {
+    let relative = Path::new(file);
+    if file.is_empty()
+        || relative.is_absolute()
+        || relative
+            .components()
+            .any(|component| !matches!(component, std::path::Component::Normal(_)))
+    {
+        return Err(AppError::ValidationError(format!(
+            "Plugin file '{}' must be a plain path inside the plugin directory",
+            file
+        )));
+    }
+
+    let dir = plugin_dir.canonicalize().map_err(AppError::IoError)?;
+    let path = dir
+        .join(relative)
+        .canonicalize()
+        .map_err(|_| AppError::ValidationError(format!("Plugin file '{}' not found", file)))?;
+    // A symlink may point anywhere; only its target counts
+    if !path.starts_with(&dir) || !path.is_file() {
+        return Err(AppError::SecurityViolation);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = path.metadata().map_err(AppError::IoError)?.permissions().mode();
+        if mode & 0o022 != 0 {
+            return Err(AppError::ValidationError(format!(
+                "Plugin file '{}' is writable by other users",
+                file
+            )));
+        }
+    }
+
+    Ok(path)
+}
+
+/// Resolve a plugin's executable: a plugin file the owner may execute
+pub fn validate_plugin_executable(plugin_dir: &Path, executable: &str) -> Result<PathBuf, AppError> {
+    let path = validate_plugin_file(plugin_dir, executable)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = path.metadata().map_err(AppError::IoError)?.permissions().mode();
+        if mode & 0o100 == 0 {
+            return Err(AppError::ValidationError(format!(
+                "Plugin executable '{}' is not executable",
+                executable
+            )));
+        }
+    }
+
+    Ok(path)
+}
+
+/// Check a file a plugin returned lies inside `allowed_dir`, so a
+/// post-processor can't point Rustloader at arbitrary files
+pub fn validate_plugin_output(path: &Path, allowed_dir: &Path) -> Result<PathBuf, AppError> {
+    let path = path.canonicalize().map_err(|_| {
+        AppError::ValidationError(format!("Plugin returned a missing file: {}", path.display()))
+    })?;
+    let allowed_dir = allowed_dir.canonicalize().map_err(AppError::IoError)?;
+    if !path.starts_with(&allowed_dir) {
+        return Err(AppError::SecurityViolation);
+    }
+    Ok(path)
+}
+
+/// Most linear memory a WASM plugin may grow to
+#[allow(dead_code)]
+pub const WASM_PLUGIN_MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Instructions budget for a single call into a WASM plugin, so a runaway
+/// loop traps instead of hanging the download
+#[allow(dead_code)]
+pub const WASM_PLUGIN_FUEL_PER_CALL: u64 = 2_000_000_000;
+
+/// Check a file name a plugin proposed is a bare name that stays in the
+/// folder of the file it renames
+#[allow(dead_code)]
+pub fn validate_plugin_file_name(name: &str) -> Result<(), AppError> {
+    let path = Path::new(name);
+    let is_bare = matches!(
+        path.components().collect::<Vec<_>>().as_slice(),
+        [std::path::Component::Normal(_)]
+    );
+    if !is_bare || name.contains(['/', '\\', '\0']) || name.starts_with('.') {
+        return Err(AppError::ValidationError(format!("Plugin proposed an unsafe file name '{}'", name)));
+    }
+    Ok(())
+}
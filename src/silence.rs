@@ -0,0 +1,204 @@
+// src/silence.rs
+//! `--trim-silence`: remove leading and trailing silence from extracted
+//! audio. ffmpeg's silencedetect finds the quiet edges, then the audio
+//! between them is copied without re-encoding.
+
+use crate::error::AppError;
+use crate::process::CommandSpec;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+
+/// Silence counts as leading or trailing when it starts or ends within this
+/// many seconds of the edge of the file
+const EDGE_TOLERANCE_SECS: f64 = 0.05;
+
+/// What counts as silence. Set under `silence_trim` in config.json.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SilenceSettings {
+    /// Audio quieter than this, in dBFS, is silence
+    pub threshold_db: f64,
+    /// Shorter quiet stretches are kept
+    pub min_duration_secs: f64,
+}
+
+impl Default for SilenceSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -50.0,
+            min_duration_secs: 0.5,
+        }
+    }
+}
+
+impl SilenceSettings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !(-100.0..0.0).contains(&self.threshold_db) {
+            return Err(AppError::ValidationError("The silence threshold must be between -100 and 0 dB".to_string()));
+        }
+        if !self.min_duration_secs.is_finite() || self.min_duration_secs <= 0.0 {
+            return Err(AppError::ValidationError("The minimum silence duration must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The silencedetect filter for these settings
+    pub fn detect_filter(&self) -> String {
+        format!("silencedetect=noise={}dB:d={}", self.threshold_db, self.min_duration_secs)
+    }
+}
+
+/// A quiet stretch reported by silencedetect; `end` is None when the
+/// silence runs to the end of the file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceInterval {
+    pub start: f64,
+    pub end: Option<f64>,
+}
+
+/// silencedetect's findings and the input's duration, read from ffmpeg's log
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SilenceReport {
+    pub duration: Option<f64>,
+    pub intervals: Vec<SilenceInterval>,
+}
+
+/// Parse ffmpeg's stderr from a silencedetect run
+pub fn parse_silencedetect(stderr: &str) -> SilenceReport {
+    let mut report = SilenceReport::default();
+    for line in stderr.lines() {
+        if let Some(duration) = line.trim().strip_prefix("Duration:") {
+            if report.duration.is_none() {
+                report.duration = duration.split(',').next().and_then(parse_clock);
+            }
+        } else if let Some(start) = field(line, "silence_start:") {
+            report.intervals.push(SilenceInterval { start: start.max(0.0), end: None });
+        } else if let Some(end) = field(line, "silence_end:") {
+            if let Some(interval) = report.intervals.last_mut().filter(|interval| interval.end.is_none()) {
+                interval.end = Some(end);
+            }
+        }
+    }
+    report
+}
+
+fn field(line: &str, name: &str) -> Option<f64> {
+    let rest = &line[line.find(name)? + name.len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Parse ffmpeg's "HH:MM:SS.cc"
+fn parse_clock(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+impl SilenceReport {
+    /// The part of the file to keep, in seconds, or None when there is no
+    /// silence at either edge (or nothing but silence)
+    pub fn audible_range(&self) -> Option<(f64, f64)> {
+        let duration = self.duration?;
+        let start = self
+            .intervals
+            .first()
+            .filter(|interval| interval.start <= EDGE_TOLERANCE_SECS)
+            .map_or(0.0, |interval| interval.end.unwrap_or(duration));
+        let end = self
+            .intervals
+            .last()
+            .filter(|interval| interval.end.map_or(true, |end| end >= duration - EDGE_TOLERANCE_SECS))
+            .map_or(duration, |interval| interval.start);
+
+        if end <= start || (start == 0.0 && end == duration) {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+}
+
+/// The ffmpeg invocation running silencedetect over `input`
+pub fn detect_command(input: &Path, settings: &SilenceSettings) -> CommandSpec {
+    let mut command = CommandSpec::ffmpeg();
+    command
+        .flag("-hide_banner")
+        .flag("-nostats")
+        .option("-i", input.to_string_lossy())
+        .option("-af", settings.detect_filter())
+        .option("-f", "null")
+        .operand("-");
+    command
+}
+
+/// The ffmpeg invocation copying `start`..`end` seconds of `input` to `output`
+pub fn trim_command(input: &Path, output: &Path, start: f64, end: f64) -> CommandSpec {
+    let mut command = CommandSpec::ffmpeg();
+    command
+        .flag("-hide_banner")
+        .option("-loglevel", "error")
+        .option("-i", input.to_string_lossy())
+        .option("-ss", format!("{:.3}", start))
+        .option("-to", format!("{:.3}", end))
+        .option("-map", "0")
+        .option("-c", "copy")
+        .operand(output.to_string_lossy());
+    command
+}
+
+/// Trim leading and trailing silence from the audio file at `path` in
+/// place. Returns the seconds removed.
+pub async fn trim_silence(path: &Path, settings: &SilenceSettings) -> Result<f64, AppError> {
+    settings.validate()?;
+    let path = path.canonicalize()?;
+
+    let detected = detect_command(&path, settings)
+        .to_async_command()?
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|_| AppError::MissingDependency("ffmpeg".to_string()))?;
+    if !detected.status.success() {
+        return Err(AppError::General("ffmpeg could not analyze the audio for silence".to_string()));
+    }
+
+    let report = parse_silencedetect(&String::from_utf8_lossy(&detected.stderr));
+    debug!("Silence in {:?}: {:?}", path, report);
+    let (start, end) = match report.audible_range() {
+        Some(range) => range,
+        None => return Ok(0.0),
+    };
+
+    // Write next to the original, keeping its extension so ffmpeg picks the
+    // same container, then replace it
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    let trimmed = path.with_file_name(format!(".{}.trimmed.{}", stem, extension));
+    // Left over from an interrupted run; ffmpeg would stop to ask about it
+    let _ = fs::remove_file(&trimmed);
+    let result = trim_command(&path, &trimmed, start, end)
+        .to_async_command()?
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|_| AppError::MissingDependency("ffmpeg".to_string()))?;
+    if !result.status.success() {
+        let _ = fs::remove_file(&trimmed);
+        return Err(AppError::General(format!(
+            "ffmpeg could not trim the silence: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    fs::rename(&trimmed, &path)?;
+
+    let removed = report.duration.unwrap_or(end) - (end - start);
+    info!("Trimmed {:.1}s of silence from {:?}", removed, path);
+    Ok(removed)
+}
@@ -0,0 +1,170 @@
+// src/site_stats.rs
+//! Download history aggregated by source site, to spot sites whose yt-dlp
+//! extractor has broken.
+
+use crate::download_manager::{DownloadItem, DownloadStatus};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Minimum finished downloads before a site's failure rate is judged
+const MIN_DOWNLOADS_FOR_HINT: usize = 3;
+/// Extractor failures above this share suggest updating yt-dlp
+const EXTRACTOR_FAILURE_THRESHOLD: f64 = 0.5;
+
+/// Broad reason a download failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// yt-dlp couldn't parse the site, usually fixed by a yt-dlp update
+    Extractor,
+    /// HTTP 403 or a bot check
+    Forbidden,
+    RateLimited,
+    /// Removed, private or region-blocked content
+    Unavailable,
+    Network,
+    PostProcessing,
+    Other,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Extractor => "extractor",
+            FailureKind::Forbidden => "forbidden",
+            FailureKind::RateLimited => "rate limited",
+            FailureKind::Unavailable => "unavailable",
+            FailureKind::Network => "network",
+            FailureKind::PostProcessing => "post-processing",
+            FailureKind::Other => "other",
+        }
+    }
+}
+
+/// Classify a failed download from its error message and yt-dlp output
+pub fn failure_kind(item: &DownloadItem) -> FailureKind {
+    classify_failure(item.error_message.as_deref().unwrap_or_default(), &item.error_context)
+}
+
+/// Classify a download error from its message and the last lines yt-dlp printed
+pub fn classify_failure(message: &str, context: &[String]) -> FailureKind {
+    let mut text = message.to_string();
+    for line in context {
+        text.push('\n');
+        text.push_str(line);
+    }
+    let text = text.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|needle| text.contains(needle));
+
+    if has(&["unable to extract", "unsupported url", "extractor", "please report this issue", "nsig", "signature extraction"]) {
+        FailureKind::Extractor
+    } else if has(&["429", "too many requests", "rate limit"]) {
+        FailureKind::RateLimited
+    } else if has(&["403", "forbidden", "not a bot"]) {
+        FailureKind::Forbidden
+    } else if has(&["404", "unavailable", "private video", "has been removed", "available in your country"]) {
+        FailureKind::Unavailable
+    } else if has(&["ffmpeg", "postprocess", "post-process"]) {
+        FailureKind::PostProcessing
+    } else if has(&["network error", "timed out", "timeout", "connection", "dns"]) {
+        FailureKind::Network
+    } else {
+        FailureKind::Other
+    }
+}
+
+/// Site a URL belongs to, e.g. "youtube.com" for "https://www.youtube.com/watch?v=x"
+pub fn site_of(url: &str) -> String {
+    Url::parse(url.trim())
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+        .map(|host| {
+            ["www.", "m.", "music."]
+                .iter()
+                .find_map(|prefix| host.strip_prefix(prefix))
+                .map(str::to_string)
+                .unwrap_or(host)
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Average transfer speed of a completed download in bytes per second
+fn average_speed(item: &DownloadItem) -> Option<f64> {
+    let (started, finished) = (item.started_at?, item.finished_at?);
+    let secs = finished.signed_duration_since(started).num_milliseconds() as f64 / 1000.0;
+    let bytes = item.total_bytes.max(item.downloaded_bytes);
+    (secs > 0.0 && bytes > 0).then(|| bytes as f64 / secs)
+}
+
+/// History totals for one site
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SiteStats {
+    pub site: String,
+    pub completed: usize,
+    pub failed: usize,
+    /// Share of finished downloads that completed, from 0.0 to 1.0
+    pub success_rate: f64,
+    /// Mean transfer speed of completed downloads in bytes per second
+    pub average_speed: Option<f64>,
+    /// Failure counts, most common first
+    pub failure_kinds: Vec<(FailureKind, usize)>,
+}
+
+impl SiteStats {
+    /// Whether failures look like a broken extractor that a yt-dlp update may fix
+    pub fn needs_ytdlp_update(&self) -> bool {
+        let finished = self.completed + self.failed;
+        let extractor_failures = self
+            .failure_kinds
+            .iter()
+            .find(|(kind, _)| *kind == FailureKind::Extractor)
+            .map_or(0, |(_, count)| *count);
+        finished >= MIN_DOWNLOADS_FOR_HINT
+            && extractor_failures as f64 / finished as f64 > EXTRACTOR_FAILURE_THRESHOLD
+    }
+}
+
+/// A site's stats with the speeds and failures they are computed from
+type SiteTally = (SiteStats, Vec<f64>, HashMap<FailureKind, usize>);
+
+/// Aggregate finished downloads by site, busiest sites first. Canceled and
+/// unfinished downloads don't count.
+pub fn aggregate_site_stats(downloads: &[DownloadItem]) -> Vec<SiteStats> {
+    let mut sites: BTreeMap<String, SiteTally> = BTreeMap::new();
+
+    for item in downloads {
+        if !matches!(item.status, DownloadStatus::Completed | DownloadStatus::Failed) {
+            continue;
+        }
+        let site = site_of(&item.url);
+        let (stats, speeds, failures) = sites.entry(site.clone()).or_insert_with(|| {
+            (SiteStats { site, ..SiteStats::default() }, Vec::new(), HashMap::new())
+        });
+
+        if item.status == DownloadStatus::Completed {
+            stats.completed += 1;
+            speeds.extend(average_speed(item));
+        } else {
+            stats.failed += 1;
+            *failures.entry(failure_kind(item)).or_insert(0) += 1;
+        }
+    }
+
+    let mut result: Vec<SiteStats> = sites
+        .into_values()
+        .map(|(mut stats, speeds, failures)| {
+            stats.success_rate = stats.completed as f64 / (stats.completed + stats.failed) as f64;
+            if !speeds.is_empty() {
+                stats.average_speed = Some(speeds.iter().sum::<f64>() / speeds.len() as f64);
+            }
+            let mut failure_kinds: Vec<(FailureKind, usize)> = failures.into_iter().collect();
+            failure_kinds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            stats.failure_kinds = failure_kinds;
+            stats
+        })
+        .collect();
+
+    result.sort_by(|a, b| (b.completed + b.failed).cmp(&(a.completed + a.failed)).then_with(|| a.site.cmp(&b.site)));
+    result
+}
@@ -0,0 +1,161 @@
+// src/smart_defaults.rs
+//! Options suggested by download history. When the last few downloads from
+//! a channel all used the same format, quality and bitrate, a new URL from
+//! that channel starts with those options. The CLI prints them as a hint
+//! (or applies them, with `"smart_defaults": "apply"` in config.json) and
+//! the GUI prefills them through its `suggest_options` command.
+
+use crate::download_manager::{get_known_downloads, DownloadItem};
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::metadata_cache::fetch_media_info;
+use crate::recent_urls::{load_recent_sources, remember_channel, RecentSources};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Completed downloads from a channel that must agree before their options
+/// are suggested
+pub const MIN_MATCHING_DOWNLOADS: usize = 3;
+
+/// How many of a channel's most recent downloads are compared
+const RECENT_DOWNLOADS_CHECKED: usize = 5;
+
+/// What happens with options suggested by history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmartDefaults {
+    /// Don't look at history
+    Off,
+    /// Print the suggested options
+    #[default]
+    Hint,
+    /// Use the suggested options unless others are given
+    Apply,
+}
+
+impl SmartDefaults {
+    #[allow(dead_code)]
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "off" => Ok(SmartDefaults::Off),
+            "hint" => Ok(SmartDefaults::Hint),
+            "apply" => Ok(SmartDefaults::Apply),
+            _ => Err(AppError::ValidationError(format!(
+                "Unknown smart defaults mode '{}', expected off, hint or apply",
+                value
+            ))),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmartDefaults::Off => "off",
+            SmartDefaults::Hint => "hint",
+            SmartDefaults::Apply => "apply",
+        }
+    }
+}
+
+/// Options a channel's recent downloads agree on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SuggestedOptions {
+    pub channel: String,
+    pub format: String,
+    pub quality: Option<String>,
+    pub bitrate: Option<String>,
+    /// Number of past downloads the suggestion is based on
+    pub based_on: usize,
+}
+
+impl SuggestedOptions {
+    /// The options as command-line flags, e.g. "--format mp3 --video-bitrate 128K"
+    pub fn as_flags(&self) -> String {
+        let mut flags = vec![format!("--format {}", self.format)];
+        if let Some(quality) = &self.quality {
+            flags.push(format!("--quality {}", quality));
+        }
+        if let Some(bitrate) = &self.bitrate {
+            flags.push(format!("--video-bitrate {}", bitrate));
+        }
+        flags.join(" ")
+    }
+}
+
+/// Channel of each recently used URL
+pub fn known_channels(recent: &RecentSources) -> HashMap<String, String> {
+    recent
+        .sources
+        .iter()
+        .filter_map(|source| Some((source.url.clone(), source.channel.clone()?)))
+        .collect()
+}
+
+/// Channel name from `yt-dlp --dump-json` metadata
+pub fn channel_of(info: &serde_json::Value) -> Option<String> {
+    info["channel"]
+        .as_str()
+        .or_else(|| info["uploader"].as_str())
+        .map(str::trim)
+        .filter(|channel| !channel.is_empty())
+        .map(str::to_string)
+}
+
+/// Options the most recent completed downloads from `channel` agree on, or
+/// None when there are too few, they differ, or they used the defaults.
+/// `channels` maps download URLs to their channel.
+pub fn suggest_from_history(
+    channel: &str,
+    history: &[DownloadItem],
+    channels: &HashMap<String, String>,
+) -> Option<SuggestedOptions> {
+    let mut from_channel: Vec<&DownloadItem> = history
+        .iter()
+        .filter(|item| item.is_completed())
+        .filter(|item| {
+            channels
+                .get(&item.url)
+                .is_some_and(|item_channel| item_channel.eq_ignore_ascii_case(channel))
+        })
+        .collect();
+    from_channel.sort_by_key(|item| Reverse(item.finished_at.unwrap_or(item.added_at)));
+    from_channel.truncate(RECENT_DOWNLOADS_CHECKED);
+    if from_channel.len() < MIN_MATCHING_DOWNLOADS {
+        return None;
+    }
+
+    let latest = from_channel[0];
+    let agree = from_channel.iter().all(|item| {
+        item.format == latest.format && item.quality == latest.quality && item.bitrate == latest.bitrate
+    });
+    let defaults = latest.format == "mp4" && latest.quality.is_none() && latest.bitrate.is_none();
+    if !agree || defaults {
+        return None;
+    }
+
+    Some(SuggestedOptions {
+        channel: channel.to_string(),
+        format: latest.format.clone(),
+        quality: latest.quality.clone(),
+        bitrate: latest.bitrate.clone(),
+        based_on: from_channel.len(),
+    })
+}
+
+/// Options suggested for `url` by the history of its channel. The channel
+/// comes from the recent URLs store, or from the video's metadata.
+pub async fn suggest_options(url: &str, request_headers: &RequestHeaders) -> Result<Option<SuggestedOptions>, AppError> {
+    let channels = known_channels(&load_recent_sources()?);
+    let channel = match channels.get(url) {
+        Some(channel) => channel.clone(),
+        None => match channel_of(&fetch_media_info(url, request_headers).await?) {
+            Some(channel) => {
+                remember_channel(url, &channel);
+                channel
+            }
+            None => return Ok(None),
+        },
+    };
+    Ok(suggest_from_history(&channel, &get_known_downloads()?, &channels))
+}
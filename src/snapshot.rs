@@ -0,0 +1,357 @@
+// src/snapshot.rs
+//! Frame-accurate stills from a video URL or a local file.
+//!
+//! ffmpeg seeks before decoding, so only the frames around each timestamp
+//! are read. Progressive HTTP(S) streams are opened directly and fetched
+//! with range requests; segmented streams (HLS, DASH) can't be seeked that
+//! way, so a short section around the timestamps is downloaded first.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::metadata_cache::fetch_media_info;
+use crate::process::CommandSpec;
+use crate::security::validate_path_safety;
+use crate::utils::validate_url;
+use log::{debug, info};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Most stills taken by one command
+pub const MAX_SNAPSHOTS: u32 = 100;
+/// Seconds downloaded before the first and after the last timestamp when a
+/// stream has to be fetched in sections
+const SECTION_MARGIN_SECS: f64 = 5.0;
+
+/// Image format for stills
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpg,
+}
+
+impl ImageFormat {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "png" => Ok(ImageFormat::Png),
+            "jpg" | "jpeg" => Ok(ImageFormat::Jpg),
+            _ => Err(AppError::ValidationError(format!("Unknown image format: {}", value))),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpg => "jpg",
+        }
+    }
+}
+
+/// Which stills to take
+#[derive(Debug, Clone)]
+pub struct SnapshotRequest {
+    /// Seconds into the video of the first still
+    pub at: f64,
+    pub count: u32,
+    /// Seconds between stills
+    pub interval: f64,
+    pub image_format: ImageFormat,
+    pub output_dir: PathBuf,
+}
+
+impl SnapshotRequest {
+    /// Timestamps of the stills, in seconds
+    pub fn times(&self) -> Vec<f64> {
+        (0..self.count).map(|i| self.at + self.interval * i as f64).collect()
+    }
+
+    fn validate(&self) -> Result<(), AppError> {
+        if self.count == 0 || self.count > MAX_SNAPSHOTS {
+            return Err(AppError::ValidationError(format!("--count must be between 1 and {}", MAX_SNAPSHOTS)));
+        }
+        if !self.interval.is_finite() || self.interval <= 0.0 {
+            return Err(AppError::ValidationError("--interval must be a positive number of seconds".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Parse a timestamp such as "00:12:34", "12:34.5" or "754" into seconds
+pub fn parse_timestamp(value: &str) -> Result<f64, AppError> {
+    let invalid = || AppError::TimeFormatError(format!("Invalid timestamp '{}', expected e.g. 00:12:34 or 754.5", value));
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty()) {
+        return Err(invalid());
+    }
+
+    let (seconds, whole) = parts.split_last().ok_or_else(invalid)?;
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    if !seconds.is_finite() || seconds < 0.0 || (!whole.is_empty() && seconds >= 60.0) {
+        return Err(invalid());
+    }
+
+    let mut total = 0.0;
+    for (i, part) in whole.iter().enumerate() {
+        let amount: u32 = part.parse().map_err(|_| invalid())?;
+        // Minutes are bounded when hours are given
+        if i > 0 && amount >= 60 {
+            return Err(invalid());
+        }
+        total = total * 60.0 + amount as f64;
+    }
+    Ok(total * 60.0 + seconds)
+}
+
+/// A timestamp as used in file names, e.g. "00-12-34" or "00-12-34.500"
+pub fn timestamp_label(secs: f64) -> String {
+    let millis = (secs * 1000.0).round() as u64;
+    let (whole, fraction) = (millis / 1000, millis % 1000);
+    let label = format!("{:02}-{:02}-{:02}", whole / 3600, (whole % 3600) / 60, whole % 60);
+    if fraction == 0 {
+        label
+    } else {
+        format!("{}.{:03}", label, fraction)
+    }
+}
+
+/// The video stream yt-dlp would download for a URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInfo {
+    pub url: String,
+    pub format_id: Option<String>,
+    pub protocol: String,
+    /// Headers the site requires when fetching the stream
+    pub http_headers: Vec<(String, String)>,
+}
+
+impl StreamInfo {
+    /// Whether ffmpeg can seek into the stream with HTTP range requests
+    pub fn supports_ranges(&self) -> bool {
+        matches!(self.protocol.as_str(), "http" | "https")
+            && (self.url.starts_with("https://") || self.url.starts_with("http://"))
+    }
+}
+
+/// Pick the video stream from `yt-dlp --dump-json` output: the video half of
+/// a merged selection, or the single format
+pub fn video_stream(info: &Value) -> Option<StreamInfo> {
+    let format = match info.get("requested_formats").and_then(Value::as_array) {
+        Some(formats) => formats
+            .iter()
+            .find(|format| format.get("vcodec").and_then(Value::as_str).is_some_and(|codec| codec != "none"))?,
+        None => info,
+    };
+
+    let url = format.get("url").and_then(Value::as_str)?.to_string();
+    let http_headers = format
+        .get("http_headers")
+        .and_then(Value::as_object)
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(StreamInfo {
+        url,
+        format_id: format.get("format_id").and_then(Value::as_str).map(str::to_string),
+        protocol: format.get("protocol").and_then(Value::as_str).unwrap_or("https").to_string(),
+        http_headers,
+    })
+}
+
+/// The ffmpeg invocation writing the frame at `time` seconds of `input` to
+/// `output`. Seeking before `-i` makes ffmpeg skip to the nearest keyframe
+/// and decode forward to the exact frame.
+pub fn frame_command(input: &str, http_headers: &[(String, String)], time: f64, output: &Path, image_format: ImageFormat) -> CommandSpec {
+    let mut command = CommandSpec::ffmpeg();
+    command.flag("-hide_banner").option("-loglevel", "error").flag("-n");
+    if !http_headers.is_empty() {
+        let headers: String = http_headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+            .collect();
+        command.option("-headers", headers);
+    }
+    command
+        .option("-ss", format!("{:.3}", time))
+        .option("-i", input)
+        .option("-frames:v", "1");
+    if image_format == ImageFormat::Jpg {
+        command.option("-q:v", "2");
+    }
+    command.operand(output.to_string_lossy());
+    command
+}
+
+/// Keep a title usable as a file name on every platform
+fn file_stem_for(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || " -_".contains(c) { c } else { '_' })
+        .collect();
+    let stem = stem.trim().chars().take(80).collect::<String>();
+    if stem.is_empty() { "snapshot".to_string() } else { stem }
+}
+
+/// Where to read frames from and how far its timestamps are shifted
+struct FrameSource {
+    input: String,
+    http_headers: Vec<(String, String)>,
+    /// Seconds of the original video before the start of `input`
+    offset: f64,
+    title: String,
+    /// Downloaded section to remove afterwards
+    temp_dir: Option<PathBuf>,
+}
+
+/// Take the requested stills from a URL or local file and return their paths
+pub async fn take_snapshots(
+    source: &str,
+    request: &SnapshotRequest,
+    request_headers: &RequestHeaders,
+) -> Result<Vec<PathBuf>, AppError> {
+    request.validate()?;
+    validate_path_safety(&request.output_dir)?;
+    fs::create_dir_all(&request.output_dir)?;
+    // Absolute, so a file name can never be read as an ffmpeg option
+    let output_dir = request.output_dir.canonicalize()?;
+
+    let times = request.times();
+    let frame_source = if source.starts_with("http://") || source.starts_with("https://") {
+        remote_source(source, &times, request_headers).await?
+    } else {
+        local_source(Path::new(source))?
+    };
+
+    let result = extract_frames(&frame_source, &times, &output_dir, request.image_format).await;
+    if let Some(dir) = &frame_source.temp_dir {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            debug!("Could not remove {:?}: {}", dir, e);
+        }
+    }
+    result
+}
+
+async fn extract_frames(
+    source: &FrameSource,
+    times: &[f64],
+    output_dir: &Path,
+    image_format: ImageFormat,
+) -> Result<Vec<PathBuf>, AppError> {
+    let stem = file_stem_for(&source.title);
+    let mut saved = Vec::new();
+    for &time in times {
+        let output = output_dir.join(format!("{}_{}.{}", stem, timestamp_label(time), image_format.extension()));
+        if output.exists() {
+            return Err(AppError::ValidationError(format!("{} already exists", output.display())));
+        }
+
+        info!("Extracting frame at {:.3}s to {:?}", time, output);
+        let result = frame_command(&source.input, &source.http_headers, time - source.offset, &output, image_format)
+            .to_async_command()?
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .map_err(|_| AppError::MissingDependency("ffmpeg".to_string()))?;
+
+        // Past the end of the video ffmpeg succeeds without writing a frame
+        if !result.status.success() || !output.exists() {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            return Err(AppError::General(format!(
+                "Could not extract a frame at {}: {}",
+                timestamp_label(time),
+                if stderr.trim().is_empty() { "the timestamp may be past the end of the video" } else { stderr.trim() }
+            )));
+        }
+        saved.push(output);
+    }
+    Ok(saved)
+}
+
+fn local_source(path: &Path) -> Result<FrameSource, AppError> {
+    if !path.is_file() {
+        return Err(AppError::ValidationError(format!("File not found: {}", path.display())));
+    }
+    let path = path.canonicalize()?;
+    validate_path_safety(&path)?;
+    Ok(FrameSource {
+        input: path.to_string_lossy().into_owned(),
+        http_headers: Vec::new(),
+        offset: 0.0,
+        title: path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+        temp_dir: None,
+    })
+}
+
+async fn remote_source(url: &str, times: &[f64], request_headers: &RequestHeaders) -> Result<FrameSource, AppError> {
+    validate_url(url)?;
+    let info = fetch_media_info(url, request_headers).await?;
+    let title = info.get("title").and_then(Value::as_str).unwrap_or_default().to_string();
+
+    let last = times.iter().copied().fold(0.0, f64::max);
+    if let Some(duration) = info.get("duration").and_then(Value::as_f64) {
+        if last >= duration {
+            return Err(AppError::ValidationError(format!(
+                "{} is past the end of the video ({})",
+                timestamp_label(last),
+                timestamp_label(duration)
+            )));
+        }
+    }
+
+    let stream = video_stream(&info)
+        .ok_or_else(|| AppError::DownloadError("No video stream found for this URL".to_string()))?;
+    if stream.supports_ranges() {
+        debug!("Seeking into {} stream directly", stream.protocol);
+        return Ok(FrameSource {
+            input: stream.url,
+            http_headers: stream.http_headers,
+            offset: 0.0,
+            title,
+            temp_dir: None,
+        });
+    }
+
+    // Segmented streams: fetch just the part around the timestamps
+    let first = times.iter().copied().fold(f64::INFINITY, f64::min);
+    let start = (first - SECTION_MARGIN_SECS).max(0.0);
+    let end = last + SECTION_MARGIN_SECS;
+    let temp_dir = std::env::temp_dir().join(format!("rustloader_snapshot_{}", std::process::id()));
+    fs::create_dir_all(&temp_dir)?;
+    println!("Downloading {} to {} of the {} stream...", timestamp_label(start), timestamp_label(end), stream.protocol);
+
+    let mut spec = CommandSpec::ytdlp();
+    spec.option("-f", stream.format_id.as_deref().unwrap_or("bestvideo/best"))
+        .option("--download-sections", format!("*{:.3}-{:.3}", start, end))
+        .flag("--no-playlist")
+        .option("--socket-timeout", "30")
+        .option("-o", temp_dir.join("section.%(ext)s").to_string_lossy())
+        .options(request_headers.ytdlp_options())
+        .operand(url);
+    let output = spec.to_async_command()?.output().await.map_err(AppError::IoError)?;
+    let section = fs::read_dir(&temp_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| path.file_stem().is_some_and(|stem| stem == "section") && path.is_file());
+
+    match section {
+        Some(section) if output.status.success() => Ok(FrameSource {
+            input: section.to_string_lossy().into_owned(),
+            http_headers: Vec::new(),
+            offset: start,
+            title,
+            temp_dir: Some(temp_dir),
+        }),
+        _ => {
+            let _ = fs::remove_dir_all(&temp_dir);
+            Err(AppError::DownloadError(format!(
+                "Could not download the section for the snapshot: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+}
@@ -0,0 +1,229 @@
+// src/split.rs
+//! `--split-size`: splits a finished download into numbered parts no larger
+//! than the given size, for FAT32 drives (4 GiB per file) and upload limits.
+//! "Video.mp4" becomes "Video.mp4.001", "Video.mp4.002", ... plus
+//! "Video.mp4.manifest.json" with each part's size and SHA-256, which
+//! `rustloader join` uses to check the parts and put the file back together.
+
+use crate::error::AppError;
+use crate::guardrails::parse_size_limit;
+use crate::security::{validate_existing_path, validate_path_safety};
+use log::info;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Smallest part size accepted, so a typo can't produce thousands of parts
+pub const MIN_PART_SIZE: u64 = 1024 * 1024;
+/// Parts are numbered with three digits
+pub const MAX_PARTS: u64 = 999;
+
+const MANIFEST_SUFFIX: &str = ".manifest.json";
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// One part of a split file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitPart {
+    pub file_name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Describes a split file, saved next to its parts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitManifest {
+    /// Name of the original file
+    pub file_name: String,
+    pub size: u64,
+    pub sha256: String,
+    pub parts: Vec<SplitPart>,
+}
+
+/// Parse a `--split-size` value such as "2G" or "500M"
+pub fn parse_split_size(value: &str) -> Result<u64, AppError> {
+    match parse_size_limit(value)? {
+        Some(size) if size >= MIN_PART_SIZE => Ok(size),
+        Some(_) => Err(AppError::ValidationError("--split-size must be at least 1M".to_string())),
+        None => Err(AppError::ValidationError(format!("--split-size needs a size such as 2G, not '{}'", value))),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Where the manifest of `file` goes once it's split
+pub fn manifest_path(file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(MANIFEST_SUFFIX);
+    file.with_file_name(name)
+}
+
+/// Split `file` into parts of at most `part_size` bytes and remove it.
+/// Returns the manifest path, or None when the file is small enough to keep.
+pub fn split_file(file: &Path, part_size: u64) -> Result<Option<PathBuf>, AppError> {
+    let size = fs::metadata(file)?.len();
+    if size <= part_size {
+        return Ok(None);
+    }
+    let part_size = part_size.max(MIN_PART_SIZE);
+    let count = size.div_ceil(part_size);
+    if count > MAX_PARTS {
+        return Err(AppError::ValidationError(format!(
+            "Splitting into {} parts is more than the {} allowed; use a larger --split-size",
+            count, MAX_PARTS
+        )));
+    }
+
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| AppError::PathError(format!("{} has no file name", file.display())))?
+        .to_string_lossy()
+        .into_owned();
+    let mut input = File::open(file)?;
+    let mut whole = digest::Context::new(&digest::SHA256);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut parts = Vec::with_capacity(count as usize);
+
+    for index in 1..=count {
+        let part_name = format!("{}.{:03}", file_name, index);
+        let part_path = file.with_file_name(&part_name);
+        if part_path.exists() {
+            return Err(AppError::ValidationError(format!("{} already exists", part_path.display())));
+        }
+        let mut output = BufWriter::new(File::create(&part_path)?);
+        let mut context = digest::Context::new(&digest::SHA256);
+        let mut written = 0u64;
+        while written < part_size {
+            let wanted = (part_size - written).min(BUFFER_SIZE as u64) as usize;
+            let read = input.read(&mut buffer[..wanted])?;
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read])?;
+            context.update(&buffer[..read]);
+            whole.update(&buffer[..read]);
+            written += read as u64;
+        }
+        output.flush()?;
+        parts.push(SplitPart {
+            file_name: part_name,
+            size: written,
+            sha256: hex(context.finish().as_ref()),
+        });
+    }
+
+    let manifest = SplitManifest {
+        file_name,
+        size,
+        sha256: hex(whole.finish().as_ref()),
+        parts,
+    };
+    let path = manifest_path(file);
+    fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+    fs::remove_file(file)?;
+    info!("Split {:?} into {} parts", file, manifest.parts.len());
+    Ok(Some(path))
+}
+
+/// The manifest for `path`, which may be the manifest itself or any part
+pub fn find_manifest(path: &Path) -> Result<PathBuf, AppError> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    if name.ends_with(MANIFEST_SUFFIX) {
+        return Ok(path.to_path_buf());
+    }
+    match name.rsplit_once('.') {
+        Some((original, number)) if number.len() == 3 && number.chars().all(|c| c.is_ascii_digit()) => {
+            Ok(manifest_path(&path.with_file_name(original)))
+        }
+        _ => Err(AppError::ValidationError(format!(
+            "{} is neither a split manifest nor a numbered part",
+            path.display()
+        ))),
+    }
+}
+
+/// A name from a manifest, which must not lead out of the manifest's folder
+fn plain_name(name: &str) -> Result<&str, AppError> {
+    let is_plain = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && Path::new(name).file_name().is_some_and(|file_name| file_name == name);
+    if is_plain {
+        Ok(name)
+    } else {
+        Err(AppError::ValidationError(format!("Invalid file name '{}' in split manifest", name)))
+    }
+}
+
+/// Put a split file back together from the manifest at `path` (or any of
+/// its parts), checking each part. Writes next to the parts unless `output`
+/// is given, and never overwrites an existing file.
+pub fn join_parts(path: &Path, output: Option<&Path>) -> Result<PathBuf, AppError> {
+    let manifest_file = find_manifest(path)?;
+    if !manifest_file.is_file() {
+        return Err(AppError::ValidationError(format!("Split manifest not found: {}", manifest_file.display())));
+    }
+    let manifest_file = validate_existing_path(&manifest_file)?;
+    let manifest: SplitManifest = serde_json::from_str(&fs::read_to_string(&manifest_file)?)?;
+    let dir = manifest_file.parent().unwrap_or(Path::new("."));
+    let output = match output {
+        Some(path) if path.is_absolute() => path.to_path_buf(),
+        Some(path) => std::env::current_dir()?.join(path),
+        None => dir.join(plain_name(&manifest.file_name)?),
+    };
+    if let Some(dir) = output.parent() {
+        validate_path_safety(dir)?;
+    }
+    if output.exists() {
+        return Err(AppError::ValidationError(format!("{} already exists", output.display())));
+    }
+
+    let mut partial_name = output.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".joining");
+    let partial = output.with_file_name(partial_name);
+    let result = write_joined(&manifest, dir, &partial);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &output)?;
+    info!("Joined {} parts into {:?}", manifest.parts.len(), output);
+    Ok(output)
+}
+
+fn write_joined(manifest: &SplitManifest, dir: &Path, partial: &Path) -> Result<(), AppError> {
+    let mut output = BufWriter::new(File::create(partial)?);
+    let mut whole = digest::Context::new(&digest::SHA256);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    for part in &manifest.parts {
+        let part_path = dir.join(plain_name(&part.file_name)?);
+        let mut input = File::open(&part_path)
+            .map_err(|e| AppError::ValidationError(format!("Missing part {}: {}", part_path.display(), e)))?;
+        let mut context = digest::Context::new(&digest::SHA256);
+        let mut size = 0u64;
+        loop {
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read])?;
+            context.update(&buffer[..read]);
+            whole.update(&buffer[..read]);
+            size += read as u64;
+        }
+        if size != part.size || hex(context.finish().as_ref()) != part.sha256 {
+            return Err(AppError::ValidationError(format!("Part {} is damaged or incomplete", part.file_name)));
+        }
+    }
+    output.flush()?;
+
+    if hex(whole.finish().as_ref()) != manifest.sha256 {
+        return Err(AppError::ValidationError("The joined file doesn't match the manifest".to_string()));
+    }
+    Ok(())
+}
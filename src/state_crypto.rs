@@ -0,0 +1,192 @@
+// src/state_crypto.rs
+//! Optional encryption at rest for the download queue, the download history
+//! database and the recent URL list.
+//!
+//! When `encrypt_state` is set in the config, files are sealed with
+//! AES-256-GCM under a key derived from a secret in the OS keychain.
+//! Reading always accepts both forms; [`set_encryption`] rewrites the
+//! existing files so switching the option takes effect immediately.
+
+use crate::config::{load_config, save_config};
+use crate::download_manager::get_queue_state_files;
+use crate::error::AppError;
+use crate::history::{history_path, History};
+use crate::instance_lock::ensure_queue_owner;
+use crate::keychain::{get_or_create_secret, get_secret};
+use crate::recent_urls::get_recent_sources_path;
+use base64::{engine::general_purpose, Engine as _};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Prefix marking an encrypted state file
+const ENCRYPTED_PREFIX: &str = "rustloader-encrypted:v1:";
+/// Keychain account holding the state encryption secret
+const KEYCHAIN_ACCOUNT: &str = "state-encryption";
+const KDF_SALT: &[u8] = b"rustloader state encryption";
+
+/// Whether the contents of a state file are encrypted
+pub fn is_encrypted(contents: &str) -> bool {
+    contents.starts_with(ENCRYPTED_PREFIX)
+}
+
+fn derive_key(secret: &[u8]) -> Result<LessSafeKey, AppError> {
+    let prk = Salt::new(HKDF_SHA256, KDF_SALT).extract(secret);
+    let okm = prk
+        .expand(&[b"state"], &AES_256_GCM)
+        .map_err(|_| AppError::General("Failed to derive the state encryption key".to_string()))?;
+    Ok(LessSafeKey::new(UnboundKey::from(okm)))
+}
+
+/// Encrypt `plaintext` with a key derived from `secret`
+pub fn encrypt(plaintext: &str, secret: &[u8]) -> Result<String, AppError> {
+    let key = derive_key(secret)?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| AppError::General("Failed to generate a nonce".to_string()))?;
+
+    let mut sealed = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+        .map_err(|_| AppError::General("Failed to encrypt state".to_string()))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&sealed);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, general_purpose::STANDARD.encode(payload)))
+}
+
+/// Decrypt contents produced by [`encrypt`]. Tampered data or the wrong
+/// secret is reported as a security violation.
+pub fn decrypt(contents: &str, secret: &[u8]) -> Result<String, AppError> {
+    let encoded = contents
+        .trim_end()
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or_else(|| AppError::ValidationError("State file is not encrypted".to_string()))?;
+    let payload = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| AppError::SecurityViolation)?;
+    if payload.len() < NONCE_LEN {
+        return Err(AppError::SecurityViolation);
+    }
+
+    let (nonce, sealed) = payload.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| AppError::SecurityViolation)?;
+    let mut sealed = sealed.to_vec();
+    let plaintext = derive_key(secret)?
+        .open_in_place(nonce, Aad::empty(), &mut sealed)
+        .map_err(|_| AppError::SecurityViolation)?;
+
+    String::from_utf8(plaintext.to_vec()).map_err(|_| AppError::SecurityViolation)
+}
+
+/// Whether new state files should be written encrypted
+pub fn encryption_enabled() -> bool {
+    load_config().map(|config| config.encrypt_state).unwrap_or(false)
+}
+
+/// Read a state file, decrypting it if needed
+pub fn read_state_file(path: &Path) -> Result<String, AppError> {
+    let contents = fs::read_to_string(path)?;
+    if !is_encrypted(&contents) {
+        return Ok(contents);
+    }
+
+    let secret = get_secret(KEYCHAIN_ACCOUNT)?.ok_or_else(|| {
+        AppError::General(format!(
+            "{:?} is encrypted but the key is missing from the OS keychain",
+            path
+        ))
+    })?;
+    decrypt(&contents, &secret)
+}
+
+/// Replace `path` with `contents` so that a crash leaves either the old or
+/// the new file, never a truncated one: write a temporary file next to it,
+/// flush it to disk, then rename it over the original
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), AppError> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| AppError::PathError(format!("{:?} is not a file path", path)))?;
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+
+    let written = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// Write a state file atomically, encrypting it when encryption is enabled
+pub fn write_state_file(path: &Path, contents: &str) -> Result<(), AppError> {
+    if encryption_enabled() {
+        let secret = get_or_create_secret(KEYCHAIN_ACCOUNT)?;
+        write_atomic(path, encrypt(contents, &secret)?.as_bytes())
+    } else {
+        write_atomic(path, contents.as_bytes())
+    }
+}
+
+/// The secret state is sealed with: created if `create` is set, otherwise
+/// None when there isn't one yet
+pub fn state_secret(create: bool) -> Result<Option<Vec<u8>>, AppError> {
+    if create {
+        get_or_create_secret(KEYCHAIN_ACCOUNT).map(Some)
+    } else {
+        get_secret(KEYCHAIN_ACCOUNT)
+    }
+}
+
+/// Make sure the keychain can provide the encryption key, creating it if needed
+pub fn ensure_encryption_key() -> Result<(), AppError> {
+    get_or_create_secret(KEYCHAIN_ACCOUNT).map(|_| ())
+}
+
+/// Turn encryption at rest on or off and rewrite the existing state files
+/// in the new form. Returns the number of files rewritten.
+pub fn set_encryption(enabled: bool) -> Result<usize, AppError> {
+    // The queue owner could overwrite the state file mid-rewrite
+    ensure_queue_owner()?;
+    if enabled {
+        ensure_encryption_key()?;
+    }
+
+    let mut config = load_config()?;
+    config.encrypt_state = enabled;
+    save_config(&config)?;
+
+    let mut paths = get_queue_state_files();
+    paths.push(get_recent_sources_path()?);
+
+    let mut rewritten = 0;
+    for path in paths.iter().filter(|path| path.exists()) {
+        let contents = read_state_file(path)?;
+        if is_encrypted(&fs::read_to_string(path)?) != enabled {
+            write_state_file(path, &contents)?;
+            rewritten += 1;
+        }
+    }
+
+    let history_path = history_path()?;
+    if history_path.exists() && History::open(&history_path)?.reseal()? > 0 {
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
@@ -0,0 +1,140 @@
+// src/stdout_stream.rs
+//! `--output -`: stream the finished media to stdout or a named pipe so it
+//! can be piped into a player or transcoder
+//! (`rustloader download URL -o - | mpv -`). The download itself still goes
+//! to a private directory first; only the finished file is written out, and
+//! everything meant for the user goes to stderr so it can't corrupt the
+//! stream.
+
+use crate::error::AppError;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Output directory value that means "write the media to stdout"
+pub const STDOUT_TARGET: &str = "-";
+
+/// Where a streamed download is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamTarget {
+    /// The process's standard output
+    Stdout,
+    /// An existing named pipe (FIFO)
+    Pipe(PathBuf),
+}
+
+impl StreamTarget {
+    /// Short name for messages, e.g. "stdout" or the pipe's path
+    pub fn describe(&self) -> String {
+        match self {
+            StreamTarget::Stdout => "stdout".to_string(),
+            StreamTarget::Pipe(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// The stream target an output directory asks for, if any: `-` for stdout,
+/// or the path of an existing named pipe
+pub fn stream_target(output_dir: Option<&str>) -> Option<StreamTarget> {
+    let output_dir = output_dir?.trim();
+    if output_dir == STDOUT_TARGET {
+        return Some(StreamTarget::Stdout);
+    }
+    is_named_pipe(Path::new(output_dir)).then(|| StreamTarget::Pipe(PathBuf::from(output_dir)))
+}
+
+#[cfg(unix)]
+fn is_named_pipe(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_named_pipe(_path: &Path) -> bool {
+    false
+}
+
+/// Reject options a single stream of bytes can't carry
+pub fn validate_stream_options(use_playlist: bool, use_queue: bool, split_size: Option<u64>) -> Result<(), AppError> {
+    let conflict = if use_playlist {
+        Some("--playlist")
+    } else if use_queue {
+        Some("--queue")
+    } else if split_size.is_some() {
+        Some("--split-size")
+    } else {
+        None
+    };
+    match conflict {
+        Some(flag) => Err(AppError::ValidationError(format!(
+            "{} can't be combined with streaming to stdout or a pipe; stream one video at a time",
+            flag
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Private directory a streamed download is saved in before it's written out
+pub fn stream_staging_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("rustloader_stream_{}", std::process::id()))
+}
+
+/// Take over stdout for the media. The returned file writes to the original
+/// stdout, and from here on the process's own stdout goes to stderr, so
+/// every message, prompt and child process output stays off the stream.
+#[cfg(unix)]
+pub fn claim_stdout() -> Result<File, AppError> {
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    io::stdout().flush().map_err(AppError::IoError)?;
+    let stdout = io::stdout().as_raw_fd();
+    // SAFETY: dup and dup2 only operate on the standard descriptors, which
+    // stay open for the life of the process; the duplicate is owned by the
+    // returned File alone
+    unsafe {
+        let media = libc::dup(stdout);
+        if media < 0 {
+            return Err(AppError::IoError(io::Error::last_os_error()));
+        }
+        if libc::dup2(io::stderr().as_raw_fd(), stdout) < 0 {
+            let e = io::Error::last_os_error();
+            libc::close(media);
+            return Err(AppError::IoError(e));
+        }
+        Ok(File::from_raw_fd(media))
+    }
+}
+
+/// Take over stdout for the media
+#[cfg(not(unix))]
+pub fn claim_stdout() -> Result<File, AppError> {
+    Err(AppError::ValidationError(
+        "Streaming to stdout is only supported on Unix-like systems; pass a directory to --output".to_string(),
+    ))
+}
+
+/// Copy a finished download into the stream. A reader that stops early,
+/// like a player that was closed, ends the stream without an error.
+/// Returns the number of bytes written.
+pub fn stream_file(path: &Path, target: &StreamTarget, stdout: Option<&mut File>) -> Result<u64, AppError> {
+    let mut media = File::open(path).map_err(AppError::IoError)?;
+    let result = match (target, stdout) {
+        (StreamTarget::Stdout, Some(stdout)) => copy_all(&mut media, stdout),
+        (StreamTarget::Stdout, None) => copy_all(&mut media, &mut io::stdout().lock()),
+        (StreamTarget::Pipe(pipe), _) => {
+            let mut pipe = fs::OpenOptions::new().write(true).open(pipe).map_err(AppError::IoError)?;
+            copy_all(&mut media, &mut pipe)
+        }
+    };
+    match result {
+        Ok(bytes) => Ok(bytes),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(0),
+        Err(e) => Err(AppError::IoError(e)),
+    }
+}
+
+fn copy_all(media: &mut File, out: &mut impl Write) -> io::Result<u64> {
+    let bytes = io::copy(media, out)?;
+    out.flush()?;
+    Ok(bytes)
+}
@@ -0,0 +1,180 @@
+// src/templates.rs
+// Reusable job templates (named option sets) stored in the config file
+
+use crate::config::{load_config, save_config};
+use crate::download_manager::{DeadlineAction, DownloadItem, DownloadPriority};
+use crate::error::AppError;
+use crate::ffmpeg_args::join_args;
+use crate::format_selector::{AudioQuality, VideoCodec};
+use crate::transcode::TranscodeKind;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Extension of job files opened with `rustloader <file>`
+pub const JOB_FILE_EXTENSION: &str = "rustloader";
+/// Job files are small JSON documents; anything larger is refused
+const MAX_JOB_FILE_BYTES: u64 = 64 * 1024;
+
+/// A named set of download options that can be applied with `--template`.
+///
+/// Options given on the command line win over the template. The proxy isn't
+/// part of it, as it lives in the config together with its credentials, and
+/// neither are the output switches (`--no-merge`, `--trim-silence`,
+/// `--transliterate`, media-center naming, splitting, chapters and vertical
+/// mode), which are chosen per download.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobTemplate {
+    pub quality: Option<String>,
+    pub audio_quality: Option<AudioQuality>,
+    pub video_codec: Option<VideoCodec>,
+    pub format: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub use_playlist: Option<bool>,
+    pub download_subtitles: Option<bool>,
+    pub output_dir: Option<String>,
+    pub bitrate: Option<String>,
+    pub priority: Option<DownloadPriority>,
+    pub transcode: Option<TranscodeKind>,
+    /// As given to `--ffmpeg-args`, e.g. "-vf hflip"
+    pub ffmpeg_args: Option<String>,
+    pub user_agent: Option<String>,
+    /// As given to `--header`, e.g. "Referer: https://example.com/"
+    pub headers: Vec<String>,
+    /// As given to `--player-client`, e.g. "android,ios"
+    pub player_client: Option<String>,
+    pub geo_bypass_country: Option<String>,
+    pub mirrors: Vec<String>,
+    pub tags: Vec<String>,
+    /// As given to `--deadline`, e.g. "30m"
+    pub deadline: Option<String>,
+    pub on_deadline: Option<DeadlineAction>,
+    /// As given to `--resume-after`, e.g. "2h"
+    pub resume_after: Option<String>,
+}
+
+impl JobTemplate {
+    /// Capture the options of a previous download as a template
+    pub fn from_download(item: &DownloadItem) -> Self {
+        Self {
+            quality: item.quality.clone(),
+            audio_quality: item.audio_quality,
+            video_codec: item.video_codec,
+            format: Some(item.format.clone()),
+            start_time: item.start_time.clone(),
+            end_time: item.end_time.clone(),
+            use_playlist: Some(item.use_playlist),
+            download_subtitles: Some(item.download_subtitles),
+            output_dir: item.output_dir.clone(),
+            bitrate: item.bitrate.clone(),
+            priority: Some(item.priority),
+            transcode: item.transcode,
+            ffmpeg_args: (!item.ffmpeg_args.is_empty()).then(|| join_args(&item.ffmpeg_args)),
+            user_agent: item.user_agent.clone(),
+            headers: item
+                .headers
+                .iter()
+                .map(|header| format!("{}: {}", header.name, header.value))
+                .collect(),
+            player_client: (!item.region.player_clients.is_empty()).then(|| item.region.player_clients.join(",")),
+            geo_bypass_country: item.region.geo_bypass_country.clone(),
+            mirrors: item.mirrors.clone(),
+            tags: item.tags.clone(),
+            deadline: item.deadline_secs.map(|secs| format!("{}s", secs)),
+            on_deadline: item.deadline_secs.map(|_| item.deadline_action),
+            resume_after: item.resume_after_secs.map(|secs| format!("{}s", secs)),
+        }
+    }
+}
+
+/// Validate a template name (letters, digits, '-' and '_')
+pub fn validate_template_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty() || name.len() > 64 {
+        return Err(AppError::ValidationError(
+            "Template name must be between 1 and 64 characters".to_string(),
+        ));
+    }
+
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(AppError::ValidationError(format!(
+            "Invalid template name: {}. Use letters, digits, '-' or '_'",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+/// List all saved templates, sorted by name
+pub fn list_templates() -> Result<Vec<(String, JobTemplate)>, AppError> {
+    let config = load_config()?;
+    Ok(config.templates.into_iter().collect())
+}
+
+/// Look up a template by name
+pub fn get_template(name: &str) -> Result<JobTemplate, AppError> {
+    let config = load_config()?;
+    config
+        .templates
+        .get(name)
+        .cloned()
+        .ok_or_else(|| AppError::ValidationError(format!("Template not found: {}", name)))
+}
+
+/// Save (or replace) a template
+pub fn save_template(name: &str, template: JobTemplate) -> Result<(), AppError> {
+    validate_template_name(name)?;
+
+    let mut config = load_config()?;
+    config.templates.insert(name.to_string(), template);
+    save_config(&config)
+}
+
+/// Save a template from the options of a previous download
+pub fn save_template_from_download(download_id: &str, name: &str) -> Result<JobTemplate, AppError> {
+    let item = crate::download_manager::find_saved_download(download_id)?
+        .ok_or_else(|| AppError::ValidationError(format!("Download not found: {}", download_id)))?;
+
+    let template = JobTemplate::from_download(&item);
+    save_template(name, template.clone())?;
+    Ok(template)
+}
+
+/// Delete a template, returning an error if it doesn't exist
+pub fn delete_template(name: &str) -> Result<(), AppError> {
+    let mut config = load_config()?;
+    if config.templates.remove(name).is_none() {
+        return Err(AppError::ValidationError(format!("Template not found: {}", name)));
+    }
+    save_config(&config)
+}
+
+/// A download saved as a `.rustloader` job file: the URL plus the options
+/// of a template, e.g. `{"url": "https://...", "format": "mp3"}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobFile {
+    pub url: String,
+    #[serde(flatten)]
+    pub options: JobTemplate,
+}
+
+/// Whether a command-line argument names a job file
+pub fn is_job_file(arg: &str) -> bool {
+    Path::new(arg)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(JOB_FILE_EXTENSION))
+}
+
+/// Read a `.rustloader` job file
+pub fn load_job_file(path: &Path) -> Result<JobFile, AppError> {
+    if fs::metadata(path)?.len() > MAX_JOB_FILE_BYTES {
+        return Err(AppError::ValidationError(format!("Job file is too large: {}", path.display())));
+    }
+    let job: JobFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if job.url.trim().is_empty() {
+        return Err(AppError::ValidationError(format!("Job file has no url: {}", path.display())));
+    }
+    Ok(job)
+}
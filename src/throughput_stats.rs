@@ -0,0 +1,124 @@
+// src/throughput_stats.rs
+// Recent download speed per host, so a download that resumes after a restart
+// starts with a speed and ETA instead of "Calculating..."
+
+use crate::config::get_config_path;
+use crate::error::AppError;
+use crate::site_stats::site_of;
+use crate::state_crypto::{read_state_file, write_state_file};
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Weight of a new measurement in a host's average speed
+const NEW_SPEED_WEIGHT: f64 = 0.3;
+/// Speeds older than this no longer describe the connection to a host
+const STALE_AFTER_DAYS: i64 = 7;
+/// Maximum number of hosts remembered; the least recently measured are dropped
+pub const MAX_HOSTS: usize = 100;
+
+/// Average download speed measured from one host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostThroughput {
+    /// Bytes per second, weighted towards recent downloads
+    pub speed: f64,
+    pub downloads: u32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The throughput store, by host as returned by [`site_of`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThroughputStats {
+    pub hosts: HashMap<String, HostThroughput>,
+}
+
+impl ThroughputStats {
+    /// Add a measured speed for `host` to its average
+    pub fn record(&mut self, host: &str, speed: f64, now: DateTime<Utc>) {
+        if !speed.is_finite() || speed <= 0.0 {
+            return;
+        }
+        let entry = self.hosts.entry(host.to_string()).or_insert(HostThroughput {
+            speed,
+            downloads: 0,
+            updated_at: now,
+        });
+        // A stale average says little about today's connection
+        if entry.downloads > 0 && !is_stale(entry, now) {
+            entry.speed = entry.speed * (1.0 - NEW_SPEED_WEIGHT) + speed * NEW_SPEED_WEIGHT;
+        } else {
+            entry.speed = speed;
+        }
+        entry.downloads = entry.downloads.saturating_add(1);
+        entry.updated_at = now;
+
+        if self.hosts.len() > MAX_HOSTS {
+            if let Some(oldest) = self
+                .hosts
+                .iter()
+                .min_by_key(|(_, entry)| entry.updated_at)
+                .map(|(host, _)| host.clone())
+            {
+                self.hosts.remove(&oldest);
+            }
+        }
+    }
+
+    /// Speed to start a download from `host` with, unless it's stale
+    pub fn expected_speed(&self, host: &str, now: DateTime<Utc>) -> Option<f64> {
+        self.hosts
+            .get(host)
+            .filter(|entry| !is_stale(entry, now))
+            .map(|entry| entry.speed)
+    }
+}
+
+fn is_stale(entry: &HostThroughput, now: DateTime<Utc>) -> bool {
+    now.signed_duration_since(entry.updated_at) > Duration::days(STALE_AFTER_DAYS)
+}
+
+/// Path of the throughput store, next to `config.json`
+pub fn get_throughput_stats_path() -> Result<PathBuf, AppError> {
+    Ok(get_config_path()?.with_file_name("throughput.json"))
+}
+
+/// Load the throughput store, starting empty if none was saved
+pub fn load_throughput_stats() -> Result<ThroughputStats, AppError> {
+    let path = get_throughput_stats_path()?;
+    if !path.exists() {
+        return Ok(ThroughputStats::default());
+    }
+
+    let contents = read_state_file(&path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+fn save_throughput_stats(stats: &ThroughputStats) -> Result<(), AppError> {
+    let path = get_throughput_stats_path()?;
+    write_state_file(&path, &serde_json::to_string_pretty(stats)?)?;
+    debug!("Throughput stats saved to {:?}", path);
+    Ok(())
+}
+
+/// Remember the speed a download from `url` reached. Failures are logged
+/// rather than returned so they never interrupt a download.
+pub fn remember_throughput(url: &str, speed: f64) {
+    let result = load_throughput_stats().and_then(|mut stats| {
+        stats.record(&site_of(url), speed, Utc::now());
+        save_throughput_stats(&stats)
+    });
+
+    if let Err(e) = result {
+        warn!("Could not update throughput stats: {}", e);
+    }
+}
+
+/// Speed recently measured from the host of `url`, in bytes per second
+pub fn expected_speed(url: &str) -> Option<f64> {
+    load_throughput_stats()
+        .ok()?
+        .expected_speed(&site_of(url), Utc::now())
+}
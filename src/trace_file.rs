@@ -0,0 +1,74 @@
+// src/trace_file.rs
+//! `--trace-file`: records the tracing spans around queue ticks, queue
+//! commands and each download, to find what slows the queue processor down
+//! or holds its locks.
+//!
+//! A `.folded` file gets folded stacks for `inferno-flamegraph` or
+//! `flamegraph.pl`; anything else gets a Chrome trace that opens in
+//! Perfetto or `chrome://tracing`.
+
+use crate::error::AppError;
+use crate::security::validate_path_safety;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tracing_chrome::ChromeLayerBuilder;
+use tracing_flame::FlameLayer;
+use tracing_subscriber::prelude::*;
+
+/// How the trace is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Chrome trace event JSON
+    Chrome,
+    /// Folded stacks, one line per span stack with its time
+    Flamegraph,
+}
+
+impl TraceFormat {
+    /// The format for a trace file, chosen by its extension
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("folded") => TraceFormat::Flamegraph,
+            _ => TraceFormat::Chrome,
+        }
+    }
+}
+
+/// Writes out the rest of the trace when dropped; keep it until exit
+pub enum TraceGuard {
+    #[allow(dead_code)]
+    Chrome(tracing_chrome::FlushGuard),
+    #[allow(dead_code)]
+    Flamegraph(tracing_flame::FlushGuard<BufWriter<File>>),
+}
+
+/// Record spans to `path` for the rest of the run
+pub fn start_trace_file(path: &Path) -> Result<TraceGuard, AppError> {
+    validate_path_safety(path)?;
+    let guard = match TraceFormat::for_path(path) {
+        TraceFormat::Chrome => {
+            // Checked here, the layer only panics on a file it can't create
+            File::create(path)?;
+            let (layer, guard) = ChromeLayerBuilder::new().file(path).include_args(true).build();
+            install(tracing_subscriber::registry().with(layer))?;
+            TraceGuard::Chrome(guard)
+        }
+        TraceFormat::Flamegraph => {
+            let (layer, guard) = FlameLayer::with_file(path)
+                .map_err(|e| AppError::General(format!("Could not create trace file {}: {}", path.display(), e)))?;
+            install(tracing_subscriber::registry().with(layer))?;
+            TraceGuard::Flamegraph(guard)
+        }
+    };
+    log::info!("Recording a trace to {}", path.display());
+    Ok(guard)
+}
+
+fn install<S>(subscriber: S) -> Result<(), AppError>
+where
+    S: tracing::Subscriber + Send + Sync + 'static,
+{
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| AppError::General(format!("Could not start tracing: {}", e)))
+}
@@ -0,0 +1,159 @@
+// src/transcode.rs
+
+use crate::config::{load_config, save_config};
+use crate::error::AppError;
+use crate::process::CommandSpec;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Instant;
+
+/// Estimated processing time above which a transcode needs confirmation
+pub const CONFIRM_THRESHOLD_SECS: f64 = 600.0;
+
+const BENCHMARK_HEIGHT: u64 = 720;
+const BENCHMARK_SECONDS: u64 = 3;
+const BENCHMARK_MAX_AGE_DAYS: i64 = 30;
+
+/// Heavy post-processing applied to a video after download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TranscodeKind {
+    /// Re-encode to HEVC (H.265) at the source resolution
+    Hevc,
+    /// Upscale to 1080p
+    Upscale1080,
+    /// Upscale to 2160p
+    Upscale2160,
+}
+
+impl TranscodeKind {
+    /// Parse a `--transcode` value
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "hevc" => Ok(TranscodeKind::Hevc),
+            "upscale-1080" => Ok(TranscodeKind::Upscale1080),
+            "upscale-2160" => Ok(TranscodeKind::Upscale2160),
+            _ => Err(AppError::ValidationError(format!("Unknown transcode option: {}", value))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TranscodeKind::Hevc => "hevc",
+            TranscodeKind::Upscale1080 => "upscale-1080",
+            TranscodeKind::Upscale2160 => "upscale-2160",
+        }
+    }
+
+    /// ffmpeg video encoder used for this transcode
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            TranscodeKind::Hevc => "libx265",
+            TranscodeKind::Upscale1080 | TranscodeKind::Upscale2160 => "libx264",
+        }
+    }
+
+    /// Height of the encoded output
+    fn output_height(&self, source_height: Option<u64>) -> u64 {
+        match self {
+            TranscodeKind::Hevc => source_height.unwrap_or(1080),
+            TranscodeKind::Upscale1080 => 1080,
+            TranscodeKind::Upscale2160 => 2160,
+        }
+    }
+
+    /// ffmpeg arguments for yt-dlp's video convertor
+    #[allow(dead_code)]
+    pub fn ffmpeg_args(&self) -> String {
+        self.ffmpeg_args_for(false)
+    }
+
+    /// ffmpeg arguments for a source of the given orientation; upscaling a
+    /// vertical video scales its width, keeping the aspect ratio
+    pub fn ffmpeg_args_for(&self, vertical: bool) -> String {
+        match self {
+            TranscodeKind::Hevc => "-c:v libx265 -preset medium -crf 26 -tag:v hvc1 -c:a copy".to_string(),
+            TranscodeKind::Upscale1080 | TranscodeKind::Upscale2160 => {
+                let size = self.output_height(None);
+                let scale = if vertical { format!("{}:-2", size) } else { format!("-2:{}", size) };
+                format!("-vf scale={}:flags=lanczos -c:v libx264 -preset medium -crf 20 -c:a copy", scale)
+            }
+        }
+    }
+}
+
+/// Measured encoder throughput on this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodeBenchmark {
+    /// Seconds of 720p video encoded per wall-clock second
+    pub speed: f64,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// Encode a short synthetic 720p clip and measure how fast the encoder runs
+pub fn run_benchmark(encoder: &str) -> Result<EncodeBenchmark, AppError> {
+    info!("Benchmarking {} encoder", encoder);
+    let started = Instant::now();
+
+    let status = CommandSpec::ffmpeg()
+        .flag("-hide_banner")
+        .option("-loglevel", "error")
+        .option("-f", "lavfi")
+        .option("-i", format!("testsrc2=size=1280x{}:rate=30", BENCHMARK_HEIGHT))
+        .option("-t", BENCHMARK_SECONDS.to_string())
+        .option("-c:v", encoder)
+        .option("-preset", "medium")
+        .option("-f", "null")
+        .operand("-")
+        .to_command()?
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|_| AppError::MissingDependency("ffmpeg".to_string()))?;
+
+    if !status.success() {
+        return Err(AppError::General(format!("Encoder benchmark for {} failed", encoder)));
+    }
+
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+    Ok(EncodeBenchmark {
+        speed: BENCHMARK_SECONDS as f64 / elapsed,
+        measured_at: Utc::now(),
+    })
+}
+
+/// Encoder speed for this machine, benchmarking once and caching the result
+/// in the config file
+pub fn encode_speed(encoder: &str) -> Result<f64, AppError> {
+    let mut config = load_config().unwrap_or_default();
+
+    if let Some(benchmark) = config.encode_benchmarks.get(encoder) {
+        let age = Utc::now().signed_duration_since(benchmark.measured_at);
+        if age.num_days() < BENCHMARK_MAX_AGE_DAYS && benchmark.speed > 0.0 {
+            debug!("Using cached {} benchmark: {:.2}x", encoder, benchmark.speed);
+            return Ok(benchmark.speed);
+        }
+    }
+
+    let benchmark = run_benchmark(encoder)?;
+    let speed = benchmark.speed;
+    config.encode_benchmarks.insert(encoder.to_string(), benchmark);
+    if let Err(e) = save_config(&config) {
+        warn!("Could not cache encoder benchmark: {}", e);
+    }
+    Ok(speed)
+}
+
+/// Estimated wall-clock seconds to transcode a video, scaling the 720p
+/// benchmark speed by the output pixel count
+pub fn estimate_transcode_secs(
+    speed: f64,
+    kind: TranscodeKind,
+    duration_secs: f64,
+    source_height: Option<u64>,
+) -> f64 {
+    let scale = kind.output_height(source_height) as f64 / BENCHMARK_HEIGHT as f64;
+    duration_secs * scale * scale / speed.max(f64::EPSILON)
+}
@@ -0,0 +1,43 @@
+// src/transliterate.rs
+//! `--transliterate`: ASCII file names for filesystems, car stereos and
+//! other players that choke on Unicode. A title such as "Привет мир" is
+//! saved as "Privet mir". The original title stays in the file's metadata
+//! tags.
+
+use crate::error::AppError;
+use crate::utils::safe_file_name;
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `name` transliterated to ASCII and made safe as a file name on any
+/// filesystem. Characters with no transliteration become '_'.
+pub fn transliterate_name(name: &str) -> String {
+    safe_file_name(&deunicode::deunicode_with_tofu(name, "_"), None)
+}
+
+/// Where `path` moves when its file name is transliterated, or None when
+/// the name is already plain ASCII
+pub fn transliterated_path(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}", transliterate_name(&stem), transliterate_name(&ext.to_string_lossy())),
+        None => transliterate_name(&stem),
+    };
+    let current = path.file_name()?.to_string_lossy();
+    (name != current).then(|| path.with_file_name(name))
+}
+
+/// Rename the file at `path` to its transliterated name and return where it
+/// is now. An existing file with that name is never overwritten.
+pub fn transliterate_file(path: &Path) -> Result<PathBuf, AppError> {
+    let Some(target) = transliterated_path(path) else {
+        return Ok(path.to_path_buf());
+    };
+    if target.exists() {
+        return Err(AppError::ValidationError(format!("{} already exists", target.display())));
+    }
+    fs::rename(path, &target)?;
+    info!("Renamed {:?} to {:?}", path, target);
+    Ok(target)
+}
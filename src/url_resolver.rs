@@ -0,0 +1,155 @@
+// src/url_resolver.rs
+
+use crate::error::AppError;
+use crate::proxy::client_builder;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{redirect, Url};
+use std::time::Duration;
+
+/// Link shorteners that must be followed over the network to learn the real URL
+const SHORTENER_HOSTS: &[&str] = &[
+    "t.co",
+    "bit.ly",
+    "tinyurl.com",
+    "goo.gl",
+    "ow.ly",
+    "buff.ly",
+    "lnkd.in",
+    "is.gd",
+    "fb.watch",
+    "vm.tiktok.com",
+    "vt.tiktok.com",
+    "redd.it",
+];
+
+const MAX_REDIRECTS: usize = 10;
+const RESOLVE_TIMEOUT_SECS: u64 = 10;
+
+/// Query parameters worth keeping on a YouTube watch URL; everything else
+/// (share tracking such as `si`, `feature`, `pp`) is dropped
+const YOUTUBE_KEPT_PARAMS: &[&str] = &["v", "list", "index", "t"];
+
+static IFRAME_SRC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)<iframe[^>]*\ssrc\s*=\s*["']([^"']+)["']"#).unwrap());
+
+/// Resolve a user-supplied URL to the canonical page URL before validation:
+/// pulls the `src` out of pasted `<iframe>` embed code, rewrites embed and
+/// short YouTube/Vimeo URLs to their watch form, and follows link shorteners.
+/// Falls back to the input if a shortener can't be followed.
+pub async fn resolve_url(input: &str) -> String {
+    let candidate = extract_iframe_src(input).unwrap_or_else(|| input.trim().to_string());
+    let mut url = normalize_url(&candidate);
+
+    if is_shortener(&url) {
+        match follow_redirects(&url).await {
+            Ok(target) => {
+                debug!("Resolved {} to {}", url, target);
+                url = normalize_url(&target);
+            }
+            Err(e) => warn!("Could not resolve shortened URL {}: {}", url, e),
+        }
+    }
+
+    if url != input {
+        debug!("Using resolved URL {} for {}", url, input);
+    }
+    url
+}
+
+/// Extract the `src` URL from pasted iframe embed code
+pub fn extract_iframe_src(input: &str) -> Option<String> {
+    let src = IFRAME_SRC_RE.captures(input)?.get(1)?.as_str().trim();
+    if src.starts_with("//") {
+        Some(format!("https:{}", src))
+    } else {
+        Some(src.to_string())
+    }
+}
+
+/// Whether the URL points at a known link shortener
+pub fn is_shortener(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_lowercase()))
+        .is_some_and(|host| SHORTENER_HOSTS.contains(&host.as_str()))
+}
+
+/// Rewrite embed, short and share URLs to their canonical watch URL.
+/// URLs that aren't recognized are returned unchanged.
+pub fn normalize_url(url: &str) -> String {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url.to_string(),
+    };
+    let host = match parsed.host_str() {
+        Some(host) => host.to_lowercase(),
+        None => return url.to_string(),
+    };
+    let host = host
+        .trim_start_matches("www.")
+        .trim_start_matches("m.");
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|s| s.filter(|seg| !seg.is_empty()).collect())
+        .unwrap_or_default();
+
+    match host {
+        "youtu.be" => match segments.first() {
+            Some(id) => youtube_watch_url(id, &parsed),
+            None => url.to_string(),
+        },
+        "youtube.com" | "youtube-nocookie.com" => match segments.as_slice() {
+            ["embed" | "shorts" | "live" | "v", id, ..] => youtube_watch_url(id, &parsed),
+            ["watch"] => match query_value(&parsed, "v") {
+                Some(id) => youtube_watch_url(&id, &parsed),
+                None => url.to_string(),
+            },
+            _ => url.to_string(),
+        },
+        "player.vimeo.com" => match segments.as_slice() {
+            ["video", id, ..] => format!("https://vimeo.com/{}", id),
+            _ => url.to_string(),
+        },
+        _ => url.to_string(),
+    }
+}
+
+fn query_value(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
+
+fn youtube_watch_url(id: &str, original: &Url) -> String {
+    let mut watch = Url::parse("https://www.youtube.com/watch").expect("static URL is valid");
+    {
+        let mut query = watch.query_pairs_mut();
+        query.append_pair("v", id);
+        for (key, value) in original.query_pairs() {
+            if key != "v" && YOUTUBE_KEPT_PARAMS.contains(&key.as_ref()) {
+                query.append_pair(&key, &value);
+            }
+        }
+    }
+    watch.to_string()
+}
+
+/// Follow redirects and return the final URL
+async fn follow_redirects(url: &str) -> Result<String, AppError> {
+    let client = client_builder(None)?
+        .timeout(Duration::from_secs(RESOLVE_TIMEOUT_SECS))
+        .redirect(redirect::Policy::limited(MAX_REDIRECTS))
+        .build()?;
+
+    let user_agent = format!("rustloader/{}", crate::version::VERSION);
+
+    // Some shorteners reject HEAD, so retry with GET
+    let response = match client.head(url).header("User-Agent", &user_agent).send().await {
+        Ok(resp) if resp.status().is_success() => resp,
+        _ => client.get(url).header("User-Agent", &user_agent).send().await?,
+    };
+
+    Ok(response.url().to_string())
+}
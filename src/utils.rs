@@ -1,17 +1,18 @@
 // src/utils.rs
 
 use crate::error::AppError;
+use crate::paths::user_download_dir;
+use crate::process::CommandSpec;
+use crate::proxy::client_builder;
 use base64::{engine::general_purpose, Engine as _};
 use colored::*;
-use home::home_dir;
 use regex::Regex;
-use reqwest::Client;
 use ring::signature;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command as ShellCommand, Stdio};
+use std::process::Stdio;
 
 /// Validate path to prevent path traversal attacks
 pub fn validate_path_safety(path: &Path) -> Result<(), AppError> {
@@ -44,14 +45,8 @@ fn check_path_components(path: &Path) -> Result<(), AppError> {
 /// Check if a dependency is installed by searching for it in PATH
 #[allow(dead_code)]
 pub fn is_dependency_installed(name: &str) -> Result<bool, AppError> {
-    let command = if cfg!(target_os = "windows") {
-        "where"
-    } else {
-        "which"
-    };
-
-    let output = ShellCommand::new(command)
-        .arg(name)
+    let output = CommandSpec::locate(name)
+        .to_command()?
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
@@ -63,8 +58,8 @@ pub fn is_dependency_installed(name: &str) -> Result<bool, AppError> {
 /// Get the version of a dependency
 #[allow(dead_code)]
 pub fn get_dependency_version(name: &str) -> Result<String, AppError> {
-    let output = ShellCommand::new(name)
-        .arg("--version")
+    let output = CommandSpec::version_probe(name, "--version")
+        .to_command()?
         .output()
         .map_err(AppError::IoError)?;
 
@@ -86,8 +81,9 @@ pub fn get_dependency_version(name: &str) -> Result<String, AppError> {
 /// Check if yt-dlp is up to date
 #[allow(dead_code)]
 pub fn is_ytdlp_updated() -> Result<bool, AppError> {
-    let output = ShellCommand::new("yt-dlp")
-        .arg("--update")
+    let output = CommandSpec::ytdlp()
+        .flag("--update")
+        .to_command()?
         .output()
         .map_err(AppError::IoError)?;
 
@@ -99,8 +95,9 @@ pub fn is_ytdlp_updated() -> Result<bool, AppError> {
 #[allow(dead_code)]
 pub fn update_ytdlp() -> Result<(), AppError> {
     println!("{}", "Updating yt-dlp...".blue());
-    let output = ShellCommand::new("yt-dlp")
-        .arg("--update")
+    let output = CommandSpec::ytdlp()
+        .flag("--update")
+        .to_command()?
         .status()
         .map_err(AppError::IoError)?;
 
@@ -162,9 +159,9 @@ pub fn install_ffmpeg() -> Result<(), AppError> {
 
     #[cfg(target_os = "macos")]
     {
-        let status = ShellCommand::new("brew")
-            .arg("install")
-            .arg("ffmpeg")
+        let status = CommandSpec::package_manager("brew")
+            .args(&["install", "ffmpeg"])
+            .to_command()?
             .status()
             .map_err(AppError::IoError)?;
 
@@ -181,11 +178,10 @@ pub fn install_ffmpeg() -> Result<(), AppError> {
 
     #[cfg(target_os = "linux")]
     {
-        let status = ShellCommand::new("sudo")
-            .arg("apt")
-            .arg("install")
-            .arg("-y")
-            .arg("ffmpeg")
+        let status = CommandSpec::package_manager("apt")
+            .args(&["install", "-y", "ffmpeg"])
+            .as_root()
+            .to_command()?
             .status()
             .map_err(AppError::IoError)?;
 
@@ -297,8 +293,8 @@ pub fn validate_url(url: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-/// Validate time format (HH:MM:SS)
-pub fn validate_time_format(time: &str) -> Result<(), AppError> {
+/// Validate time format (HH:MM:SS), returning the time in seconds
+pub fn validate_time_format(time: &str) -> Result<u64, AppError> {
     let re = Regex::new(r"^\d{2}:\d{2}:\d{2}$").unwrap();
     if !re.is_match(time) {
         return Err(AppError::TimeFormatError(
@@ -339,65 +335,217 @@ pub fn validate_time_format(time: &str) -> Result<(), AppError> {
         ));
     }
 
-    Ok(())
+    Ok(hours as u64 * 3600 + minutes as u64 * 60 + seconds as u64)
 }
 
-/// Validate the provided bitrate format (e.g., 1000K)
-pub fn validate_bitrate(bitrate: &str) -> Result<(), AppError> {
-    let re = Regex::new(r"^(\d+)(K|M)$").unwrap();
-    if !re.is_match(bitrate) {
-        return Err(AppError::ValidationError(format!(
-            "Invalid bitrate format: {}. Use format like '1000K' or '5M'",
-            bitrate
-        )));
+/// Validate a `--start-time`/`--end-time` pair: each in HH:MM:SS, the start
+/// before the end, and both within the media duration when it's known
+pub fn validate_time_range(start: Option<&str>, end: Option<&str>, duration_secs: Option<f64>) -> Result<(), AppError> {
+    let start_secs = start.map(validate_time_format).transpose()?;
+    let end_secs = end.map(validate_time_format).transpose()?;
+
+    if let (Some(start), Some(end), Some(start_secs), Some(end_secs)) = (start, end, start_secs, end_secs) {
+        if start_secs >= end_secs {
+            return Err(AppError::TimeFormatError(format!("start {} is not before end {}", start, end)));
+        }
     }
 
-    if let Some(captures) = re.captures(bitrate) {
-        let value = captures.get(1).unwrap().as_str();
-        let value_num: u32 = match value.parse() {
-            Ok(num) => num,
-            Err(_) => {
-                return Err(AppError::ValidationError(format!(
-                    "Invalid bitrate value: {}. Must be a valid number.",
-                    value
+    let Some(duration) = duration_secs.filter(|duration| *duration > 0.0) else {
+        return Ok(());
+    };
+    for (name, time, secs) in [("start", start, start_secs), ("end", end, end_secs)] {
+        if let (Some(time), Some(secs)) = (time, secs) {
+            if secs as f64 > duration {
+                return Err(AppError::TimeFormatError(format!(
+                    "{} {} exceeds duration {}",
+                    name,
+                    time,
+                    format_clock(duration as u64)
                 )));
             }
-        };
-        if value_num == 0 {
-            return Err(AppError::ValidationError(
-                "Bitrate cannot be zero.".to_string(),
-            ));
         }
-
-        let unit = captures.get(2).unwrap().as_str();
-        if unit == "K" && value_num > 10000 {
-            return Err(AppError::ValidationError(
-                "Bitrate too high (max 10000K)".to_string(),
-            ));
-        } else if unit == "M" && value_num > 100 {
-            return Err(AppError::ValidationError(
-                "Bitrate too high (max 100M)".to_string(),
-            ));
+    }
+    // A clip starting at the very end would be empty
+    if let (Some(start), Some(secs)) = (start, start_secs) {
+        if secs as f64 >= duration {
+            return Err(AppError::TimeFormatError(format!(
+                "start {} is at the end of the {} video",
+                start,
+                format_clock(duration as u64)
+            )));
         }
     }
 
     Ok(())
 }
 
+/// Format seconds like a media player clock, e.g. "58:12" or "1:02:03"
+pub fn format_clock(total_secs: u64) -> String {
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// A requested bitrate: a target in kbit/s, or the best the source offers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bitrate {
+    Best,
+    Kbps(f64),
+}
+
+impl std::fmt::Display for Bitrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bitrate::Best => write!(f, "best"),
+            Bitrate::Kbps(kbps) if *kbps >= 1000.0 && kbps % 100.0 == 0.0 => write!(f, "{}M", kbps / 1000.0),
+            Bitrate::Kbps(kbps) => write!(f, "{}K", kbps),
+        }
+    }
+}
+
+/// Bitrates accepted for an output format, in kbit/s. MP3 tops out at 320K;
+/// video targets cover everything from low-bandwidth streams to 8K masters.
+pub fn bitrate_range(format: &str) -> (f64, f64) {
+    match format {
+        "mp3" => (32.0, 320.0),
+        _ => (100.0, 100_000.0),
+    }
+}
+
+/// Parse a bitrate such as "128K", "1.5M", "2500kbps" or "best" for the given
+/// output format, checking it against the format's range
+pub fn parse_bitrate(bitrate: &str, format: &str) -> Result<Bitrate, AppError> {
+    let value = bitrate.trim();
+    if value.eq_ignore_ascii_case("best") {
+        return Ok(Bitrate::Best);
+    }
+
+    let re = Regex::new(r"(?i)^(\d+(?:\.\d+)?)\s*(k|m)(?:bps)?$").unwrap();
+    let Some(captures) = re.captures(value) else {
+        let hint = if value.parse::<f64>().is_ok() {
+            format!("Bitrate '{}' needs a unit, e.g. '{}K' or '{}M'", value, value, value)
+        } else {
+            format!("Invalid bitrate '{}'. Use a value like '128K', '1.5M' or 'best'", value)
+        };
+        return Err(AppError::ValidationError(hint));
+    };
+
+    let number: f64 = captures[1].parse().map_err(|_| {
+        AppError::ValidationError(format!("Invalid bitrate value '{}'", &captures[1]))
+    })?;
+    let kbps = if captures[2].eq_ignore_ascii_case("m") { number * 1000.0 } else { number };
+    if kbps == 0.0 {
+        return Err(AppError::ValidationError("Bitrate cannot be zero".to_string()));
+    }
+
+    let (min, max) = bitrate_range(format);
+    if kbps < min || kbps > max {
+        return Err(AppError::ValidationError(format!(
+            "Bitrate {} is outside the {} range for {} ({} to {})",
+            Bitrate::Kbps(kbps),
+            if format == "mp3" { "audio" } else { "video" },
+            format.to_uppercase(),
+            Bitrate::Kbps(min),
+            Bitrate::Kbps(max)
+        )));
+    }
+    Ok(Bitrate::Kbps(kbps))
+}
+
+/// Validate a video bitrate such as "1000K", "5M" or "best"
+#[allow(dead_code)]
+pub fn validate_bitrate(bitrate: &str) -> Result<(), AppError> {
+    parse_bitrate(bitrate, "mp4").map(|_| ())
+}
+
+/// Format a number of seconds as a short human-readable duration (e.g. "1h 5m")
+pub fn format_duration(total_secs: u64) -> String {
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Parse a duration such as "90s", "30m", "2h" or "1h30m" into seconds.
+/// A bare number is taken as minutes.
+pub fn parse_duration(value: &str) -> Result<u64, AppError> {
+    let invalid = || AppError::ValidationError(format!("Invalid duration '{}', expected e.g. 30m, 2h or 1h30m", value));
+    let value = value.trim().to_lowercase();
+    if let Ok(minutes) = value.parse::<u64>() {
+        return minutes.checked_mul(60).filter(|secs| *secs > 0).ok_or_else(invalid);
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let amount: u64 = number.parse().map_err(|_| invalid())?;
+        total = amount
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    if !number.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
 /// Enhanced initialize_download_dir with security checks
 pub fn initialize_download_dir(
     custom_dir: Option<&str>,
     program_name: &str,
     file_type: &str,
+) -> Result<PathBuf, AppError> {
+    let download_dir = download_dir_path(custom_dir, program_name, file_type)?;
+
+    if !download_dir.exists() {
+        fs::create_dir_all(&download_dir).map_err(|e| {
+            eprintln!("{}: {:?}", "Failed to create download directory".red(), e);
+            AppError::IoError(e)
+        })?;
+        println!("{} {:?}", "Created directory:".green(), download_dir);
+    }
+
+    Ok(download_dir)
+}
+
+/// The directory downloads would be saved to, without creating it
+pub fn download_dir_path(
+    custom_dir: Option<&str>,
+    program_name: &str,
+    file_type: &str,
 ) -> Result<PathBuf, AppError> {
     let download_dir = if let Some(dir) = custom_dir {
         let path = PathBuf::from(dir);
         validate_path_safety(&path)?;
         path
     } else {
-        match home_dir() {
+        match user_download_dir() {
             Some(mut path) => {
-                path.push("Downloads");
                 path.push(program_name);
                 path.push(file_type);
                 validate_path_safety(&path)?;
@@ -411,17 +559,46 @@ pub fn initialize_download_dir(
         }
     };
 
-    if !download_dir.exists() {
-        fs::create_dir_all(&download_dir).map_err(|e| {
-            eprintln!("{}: {:?}", "Failed to create download directory".red(), e);
-            AppError::IoError(e)
-        })?;
-        println!("{} {:?}", "Created directory:".green(), download_dir);
-    }
-
     Ok(download_dir)
 }
 
+/// Characters FAT/exFAT and Windows don't allow in file names
+const RESERVED_NAME_CHARS: &str = "<>:\"/\\|?*";
+
+/// Names Windows keeps for devices, with or without an extension
+const WINDOWS_DEVICE_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Used when nothing printable is left of a name
+const FALLBACK_FILE_NAME: &str = "download";
+
+/// `name` made safe as a file name on any filesystem: reserved and control
+/// characters become '_', whitespace is collapsed, the name is cut to
+/// `max_chars` characters if given, and a Windows device name such as "CON"
+/// or "nul.txt" gets a '_' so it names a file
+pub fn safe_file_name(name: &str, max_chars: Option<usize>) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_control() || RESERVED_NAME_CHARS.contains(c) { '_' } else { c })
+        .collect();
+    let collapsed = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let shortened: String = collapsed.chars().take(max_chars.unwrap_or(usize::MAX)).collect();
+    // Windows drops trailing dots and spaces; a leading dot would hide the file
+    let trimmed = shortened.trim_matches(|c| c == '.' || c == ' ');
+    if trimmed.is_empty() {
+        return FALLBACK_FILE_NAME.to_string();
+    }
+
+    let (device, rest) = trimmed.split_at(trimmed.find('.').unwrap_or(trimmed.len()));
+    if WINDOWS_DEVICE_NAMES.iter().any(|reserved| device.trim_end().eq_ignore_ascii_case(reserved)) {
+        format!("{}_{}", device.trim_end(), rest)
+    } else {
+        trimmed.to_string()
+    }
+}
+
 /// Sanitize a path string using a strict whitelist approach
 fn sanitize_path(path: &str) -> Result<String, AppError> {
     let path_obj = std::path::Path::new(path);
@@ -429,17 +606,13 @@ fn sanitize_path(path: &str) -> Result<String, AppError> {
         let dir_str = parent.to_string_lossy();
         if dir_str.contains("..")
             || dir_str.contains('~')
-            || dir_str.contains('*')
-            || dir_str.contains('?')
-            || dir_str.contains('|')
-            || dir_str.contains(';')
-            || dir_str.contains('&')
-            || dir_str.contains('<')
-            || dir_str.contains('>')
+            || dir_str.contains(crate::security::FORBIDDEN_PATH_CHARS)
         {
-            return Err(AppError::ValidationError(
-                "Directory path contains invalid characters".to_string(),
-            ));
+            return Err(AppError::InvalidPath {
+                path: dir_str.to_string(),
+                rule: "must not contain '..', '~' or any of * ? | ; & < >".to_string(),
+                suggestion: crate::security::suggest_safe_path(&dir_str),
+            });
         }
         dir_str.to_string()
     } else {
@@ -463,9 +636,12 @@ fn sanitize_path(path: &str) -> Result<String, AppError> {
             .collect();
 
         if sanitized_file.len() < file_str.len() * 3 / 4 {
-            return Err(AppError::ValidationError(
-                "Filename contains too many invalid characters".to_string(),
-            ));
+            return Err(AppError::InvalidInput {
+                what: "file name".to_string(),
+                value: file_str.to_string(),
+                rule: "too many characters other than letters, digits, spaces and . - _ ( ) %".to_string(),
+                suggestion: Some(sanitized_file.trim().to_string()).filter(|name| !name.is_empty()),
+            });
         }
         sanitized_file
     } else {
@@ -485,7 +661,17 @@ pub fn format_output_path<P: AsRef<Path>>(
     download_dir: P,
     format: &str,
 ) -> Result<String, AppError> {
-    validate_path_safety(download_dir.as_ref())?;
+    output_path_named(download_dir.as_ref(), format, "%(title)s")
+}
+
+/// Output template that names files by video ID, for downloads renamed
+/// once their metadata is known
+pub fn format_id_output_path<P: AsRef<Path>>(download_dir: P, format: &str) -> Result<String, AppError> {
+    output_path_named(download_dir.as_ref(), format, "%(id)s")
+}
+
+fn output_path_named(download_dir: &Path, format: &str, name: &str) -> Result<String, AppError> {
+    validate_path_safety(download_dir)?;
     match format {
         "mp3" | "mp4" | "webm" | "m4a" | "flac" | "wav" | "ogg" => {}
         _ => {
@@ -496,7 +682,7 @@ pub fn format_output_path<P: AsRef<Path>>(
         }
     }
 
-    let path_buf = download_dir.as_ref().join(format!("%(title)s.{}", format));
+    let path_buf = download_dir.join(format!("{}.{}", name, format));
     let path_str = path_buf
         .to_str()
         .ok_or_else(|| AppError::PathError("Invalid path encoding".to_string()))?
@@ -620,7 +806,7 @@ pub async fn check_for_updates() -> Result<bool, AppError> {
         }
     };
 
-    let client = Client::builder()
+    let client = client_builder(None)?
         .timeout(std::time::Duration::from_secs(5))
         .https_only(true)
         .build()?;
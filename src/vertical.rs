@@ -0,0 +1,226 @@
+// src/vertical.rs
+//! Handling for vertical (short-form) video.
+//!
+//! A source taller than it is wide is recognized from its metadata. Quality
+//! limits then apply to its width, so "1080" means 1080x1920 rather than a
+//! 608x1080 downscale, and `--vertical-mode pad|crop` can reframe the result
+//! to a device's aspect ratio after download.
+
+use crate::error::AppError;
+use crate::http_headers::RequestHeaders;
+use crate::metadata_cache::fetch_media_info;
+use crate::process::CommandSpec;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+
+/// What to do when the video's shape doesn't match the target aspect ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalMode {
+    /// Leave the frame as it is
+    #[default]
+    Keep,
+    /// Add black bars to reach the target aspect ratio
+    Pad,
+    /// Cut the edges to fill the target aspect ratio
+    Crop,
+}
+
+impl VerticalMode {
+    /// Parse a `--vertical-mode` value
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "keep" => Ok(VerticalMode::Keep),
+            "pad" => Ok(VerticalMode::Pad),
+            "crop" => Ok(VerticalMode::Crop),
+            _ => Err(AppError::ValidationError(format!("Unknown vertical mode: {}", value))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerticalMode::Keep => "keep",
+            VerticalMode::Pad => "pad",
+            VerticalMode::Crop => "crop",
+        }
+    }
+}
+
+/// Aspect ratio of the target device's screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AspectRatio {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for AspectRatio {
+    /// 9:16, the portrait screen short-form video is made for
+    fn default() -> Self {
+        AspectRatio { width: 9, height: 16 }
+    }
+}
+
+impl AspectRatio {
+    /// Parse a ratio such as "9:16" or "4:5"
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::ValidationError(format!("Invalid aspect ratio '{}', expected e.g. 9:16", value));
+        let (width, height) = value.trim().split_once(':').ok_or_else(invalid)?;
+        let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+        let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+        if width == 0 || height == 0 || width > 100 || height > 100 {
+            return Err(invalid());
+        }
+        Ok(AspectRatio { width, height })
+    }
+}
+
+impl std::fmt::Display for AspectRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)
+    }
+}
+
+/// How a download handles vertical video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerticalOptions {
+    pub mode: VerticalMode,
+    pub target: AspectRatio,
+}
+
+/// Width and height of a video frame in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FrameSize {
+    pub fn is_vertical(&self) -> bool {
+        self.height > self.width
+    }
+
+    /// Read the frame size from `yt-dlp --dump-json` metadata
+    pub fn from_info(info: &serde_json::Value) -> Option<Self> {
+        let dimension = |name: &str| info.get(name).and_then(serde_json::Value::as_u64).filter(|value| *value > 0);
+        Some(FrameSize {
+            width: u32::try_from(dimension("width")?).ok()?,
+            height: u32::try_from(dimension("height")?).ok()?,
+        })
+    }
+}
+
+/// Round down to an even number; most encoders need even dimensions
+fn even(value: u64) -> u32 {
+    (value - value % 2).min(u32::MAX as u64) as u32
+}
+
+/// Frame size after padding or cropping `source` to `target`, or None when
+/// it already has that shape or the mode is `keep`
+pub fn reframe_size(source: FrameSize, mode: VerticalMode, target: AspectRatio) -> Option<FrameSize> {
+    let (width, height) = (source.width as u64, source.height as u64);
+    let (target_width, target_height) = (target.width as u64, target.height as u64);
+    // Cross-multiplied so the comparison is exact
+    let source_is_narrower = width * target_height < height * target_width;
+    // Padding rounds up so the frame still fits; cropping rounds down
+    let size = match (mode, source_is_narrower) {
+        (VerticalMode::Keep, _) => return None,
+        (VerticalMode::Pad, true) => FrameSize {
+            width: even((height * target_width).div_ceil(target_height) + 1),
+            height: even(height + 1),
+        },
+        (VerticalMode::Pad, false) => FrameSize {
+            width: even(width + 1),
+            height: even((width * target_height).div_ceil(target_width) + 1),
+        },
+        (VerticalMode::Crop, true) => FrameSize {
+            width: even(width),
+            height: even(width * target_height / target_width),
+        },
+        (VerticalMode::Crop, false) => FrameSize {
+            width: even(height * target_width / target_height),
+            height: even(height),
+        },
+    };
+    // Within rounding of the source itself: nothing to do
+    if size.width.abs_diff(source.width) <= 1 && size.height.abs_diff(source.height) <= 1 {
+        None
+    } else {
+        Some(size)
+    }
+}
+
+/// The ffmpeg video filter reframing `source` to `target`, centered
+pub fn reframe_filter(source: FrameSize, options: VerticalOptions) -> Option<String> {
+    let size = reframe_size(source, options.mode, options.target)?;
+    Some(match options.mode {
+        VerticalMode::Pad => format!(
+            "pad={}:{}:{}:{}:black,setsar=1",
+            size.width,
+            size.height,
+            (size.width.saturating_sub(source.width)) / 2,
+            (size.height.saturating_sub(source.height)) / 2
+        ),
+        _ => format!("crop={}:{},setsar=1", size.width, size.height),
+    })
+}
+
+/// The ffmpeg invocation re-encoding `input` through `filter` into `output`
+pub fn reframe_command(input: &Path, output: &Path, filter: &str) -> CommandSpec {
+    let webm = output.extension().is_some_and(|ext| ext == "webm");
+    let mut command = CommandSpec::ffmpeg();
+    command
+        .flag("-hide_banner")
+        .option("-loglevel", "error")
+        .option("-i", input.to_string_lossy())
+        .option("-vf", filter);
+    if webm {
+        command.option("-c:v", "libvpx-vp9").option("-crf", "32").option("-b:v", "0");
+    } else {
+        command.option("-c:v", "libx264").option("-preset", "medium").option("-crf", "20");
+    }
+    command.option("-c:a", "copy").operand(output.to_string_lossy());
+    command
+}
+
+/// The source's frame size from its metadata, if yt-dlp reports one
+pub async fn probe_frame_size(url: &str, request_headers: &RequestHeaders) -> Option<FrameSize> {
+    match fetch_media_info(url, request_headers).await {
+        Ok(info) => FrameSize::from_info(&info),
+        Err(e) => {
+            debug!("Could not read the frame size of {}: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Re-encode the video at `path` in place through `filter`
+pub async fn reframe(path: &Path, filter: &str) -> Result<(), AppError> {
+    let path = path.canonicalize()?;
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    let reframed = path.with_file_name(format!(".{}.reframed.{}", stem, extension));
+    // Left over from an interrupted run; ffmpeg would stop to ask about it
+    let _ = fs::remove_file(&reframed);
+
+    info!("Reframing {:?} with {}", path, filter);
+    let result = reframe_command(&path, &reframed, filter)
+        .to_async_command()?
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|_| AppError::MissingDependency("ffmpeg".to_string()))?;
+    if !result.status.success() {
+        let _ = fs::remove_file(&reframed);
+        return Err(AppError::General(format!(
+            "ffmpeg could not reframe the video: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    fs::rename(&reframed, &path)?;
+    Ok(())
+}
@@ -0,0 +1,178 @@
+// src/video_id.rs
+
+use once_cell::sync::Lazy;
+use reqwest::Url;
+
+/// A site-qualified video identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VideoId {
+    /// Extractor name, matching yt-dlp's lowercase extractor key where possible
+    pub site: &'static str,
+    pub id: String,
+}
+
+impl VideoId {
+    /// Key in yt-dlp's download archive format (`<extractor> <id>`)
+    pub fn archive_key(&self) -> String {
+        format!("{} {}", self.site, self.id)
+    }
+
+    /// Whether the ID is the generic URL hash rather than a real site ID,
+    /// in which case it never appears in file names
+    #[allow(dead_code)]
+    pub fn is_generic(&self) -> bool {
+        self.site == GENERIC_SITE
+    }
+}
+
+const GENERIC_SITE: &str = "generic";
+const GENERIC_ID_LEN: usize = 16;
+
+/// Extracts video IDs for one site
+trait SiteExtractor: Send + Sync {
+    fn site(&self) -> &'static str;
+    fn matches_host(&self, host: &str) -> bool;
+    fn extract(&self, url: &Url, segments: &[&str]) -> Option<String>;
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+struct YouTubeExtractor;
+
+impl SiteExtractor for YouTubeExtractor {
+    fn site(&self) -> &'static str {
+        "youtube"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        matches!(host, "youtube.com" | "music.youtube.com" | "youtube-nocookie.com" | "youtu.be")
+    }
+
+    fn extract(&self, url: &Url, segments: &[&str]) -> Option<String> {
+        let candidate = if url.host_str()?.ends_with("youtu.be") {
+            segments.first().map(|s| s.to_string())
+        } else {
+            match segments {
+                ["embed" | "shorts" | "live" | "v", id, ..] => Some(id.to_string()),
+                _ => url.query_pairs().find(|(k, _)| k == "v").map(|(_, v)| v.into_owned()),
+            }
+        }?;
+
+        (candidate.len() >= 8 && candidate.len() <= 12 && candidate.chars().all(is_id_char))
+            .then_some(candidate)
+    }
+}
+
+struct VimeoExtractor;
+
+impl SiteExtractor for VimeoExtractor {
+    fn site(&self) -> &'static str {
+        "vimeo"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        matches!(host, "vimeo.com" | "player.vimeo.com")
+    }
+
+    fn extract(&self, _url: &Url, segments: &[&str]) -> Option<String> {
+        // vimeo.com/<id>, vimeo.com/channels/<name>/<id>, player.vimeo.com/video/<id>
+        segments
+            .iter()
+            .rev()
+            .find(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()))
+            .map(|s| s.to_string())
+    }
+}
+
+struct TwitchVodExtractor;
+
+impl SiteExtractor for TwitchVodExtractor {
+    fn site(&self) -> &'static str {
+        "twitchvod"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        host == "twitch.tv"
+    }
+
+    fn extract(&self, _url: &Url, segments: &[&str]) -> Option<String> {
+        match segments {
+            ["videos", id, ..] if id.chars().all(|c| c.is_ascii_digit()) => Some(format!("v{}", id)),
+            _ => None,
+        }
+    }
+}
+
+struct TwitchClipsExtractor;
+
+impl SiteExtractor for TwitchClipsExtractor {
+    fn site(&self) -> &'static str {
+        "twitchclips"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        matches!(host, "twitch.tv" | "clips.twitch.tv")
+    }
+
+    fn extract(&self, url: &Url, segments: &[&str]) -> Option<String> {
+        let slug = if url.host_str()?.starts_with("clips.") {
+            segments.first()
+        } else {
+            match segments {
+                [_, "clip", slug, ..] => Some(slug),
+                _ => None,
+            }
+        }?;
+
+        slug.chars().all(is_id_char).then(|| slug.to_string())
+    }
+}
+
+static EXTRACTORS: Lazy<Vec<Box<dyn SiteExtractor>>> = Lazy::new(|| {
+    vec![
+        Box::new(YouTubeExtractor),
+        Box::new(VimeoExtractor),
+        Box::new(TwitchVodExtractor),
+        Box::new(TwitchClipsExtractor),
+    ]
+});
+
+/// Extract a site-specific video ID, if the URL belongs to a known site
+pub fn extract_video_id(url: &str) -> Option<VideoId> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    let host = host.trim_start_matches("www.").trim_start_matches("m.");
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|s| s.filter(|seg| !seg.is_empty()).collect())
+        .unwrap_or_default();
+
+    EXTRACTORS
+        .iter()
+        .filter(|extractor| extractor.matches_host(host))
+        .find_map(|extractor| {
+            extractor.extract(&parsed, &segments).map(|id| VideoId {
+                site: extractor.site(),
+                id,
+            })
+        })
+}
+
+/// Identify a video by its site ID, falling back to a stable hash of the URL
+/// for sites without a dedicated extractor
+pub fn video_key(url: &str) -> VideoId {
+    extract_video_id(url).unwrap_or_else(|| {
+        let url = url.trim();
+        // Ignore fragments, which never change the video
+        let url = url.split('#').next().unwrap_or(url);
+        let digest = ring::digest::digest(&ring::digest::SHA256, url.as_bytes());
+        let hex: String = digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+
+        VideoId {
+            site: GENERIC_SITE,
+            id: hex[..GENERIC_ID_LEN].to_string(),
+        }
+    })
+}
@@ -0,0 +1,432 @@
+// src/wasm_plugins.rs
+//! WebAssembly plugins: community extensions that run inside Rustloader
+//! with no access to the file system, network or environment beyond the
+//! host functions their manifest asks for.
+//!
+//! A WASM plugin lives in a folder under the plugins directory with a
+//! `wasm-plugin.json` manifest and a module. Plugin ABI 1:
+//!
+//! ```text
+//! exports  memory
+//!          rl_abi_version() -> i32            must return 1
+//!          rl_alloc(len: i32) -> i32          buffer for the host to write input to
+//!          rl_filename(ptr, len) -> i64       filename-rule hook
+//!          rl_enrich(ptr, len) -> i64         metadata-enricher hook
+//!          rl_resolve(ptr, len) -> i64        site-handler hook
+//! imports  rustloader.log(ptr, len)           with the "log" host capability
+//!          rustloader.now_unix() -> i64       with the "clock" host capability
+//! ```
+//!
+//! Hooks take UTF-8 JSON at `ptr`/`len` and return `(ptr << 32) | len` of a
+//! JSON answer in their memory, or 0 to leave things as they are:
+//!
+//! ```text
+//! rl_filename {"file_name":"a.mp4","format":"mp4","url":"..."} -> {"file_name":"b.mp4"}
+//! rl_enrich   {"file":"/dl/a.mp4","format":"mp4","url":"..."}  -> {"metadata":{"album":"x"}}
+//! rl_resolve  {"url":"https://example.com/v/1"}               -> {"url":"https://cdn..."}
+//! ```
+//!
+//! Each call runs in a fresh instance with a memory cap and a fuel budget;
+//! see the plugin rules in `security.rs`.
+
+use crate::error::AppError;
+use crate::plugins::{is_valid_host_pattern, is_valid_plugin_name, plugins_dir, url_matches_hosts};
+use crate::security::{
+    validate_plugin_file, validate_plugin_file_name, WASM_PLUGIN_FUEL_PER_CALL, WASM_PLUGIN_MAX_MEMORY_BYTES,
+};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Version of the WASM plugin ABI this host implements
+pub const WASM_PLUGIN_ABI_VERSION: u32 = 1;
+/// Name of the manifest in each WASM plugin folder
+pub const WASM_MANIFEST_FILE: &str = "wasm-plugin.json";
+const HOST_MODULE: &str = "rustloader";
+/// Largest JSON answer a hook may return
+const MAX_ANSWER_BYTES: usize = 1024 * 1024;
+
+/// Extension points a WASM plugin can implement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WasmHook {
+    /// Renames finished downloads
+    FilenameRule,
+    /// Adds fields to a download's `.info.json`
+    MetadataEnricher,
+    /// Turns page URLs on its hosts into downloadable URLs
+    SiteHandler,
+}
+
+impl WasmHook {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WasmHook::FilenameRule => "filename-rule",
+            WasmHook::MetadataEnricher => "metadata-enricher",
+            WasmHook::SiteHandler => "site-handler",
+        }
+    }
+
+    fn export(&self) -> &'static str {
+        match self {
+            WasmHook::FilenameRule => "rl_filename",
+            WasmHook::MetadataEnricher => "rl_enrich",
+            WasmHook::SiteHandler => "rl_resolve",
+        }
+    }
+}
+
+/// Host functions a WASM plugin may import. Nothing else is linked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostCapability {
+    /// `rustloader.log`, written to Rustloader's log
+    Log,
+    /// `rustloader.now_unix`, the current time
+    Clock,
+}
+
+/// Contents of `wasm-plugin.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WasmManifest {
+    pub name: String,
+    pub version: String,
+    /// Plugin ABI the module is built against
+    pub abi: u32,
+    /// Path of the `.wasm` module, relative to the plugin folder
+    pub module: String,
+    pub hooks: Vec<WasmHook>,
+    /// Hosts a site handler handles, e.g. "example.com" (subdomains included)
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub host_capabilities: Vec<HostCapability>,
+}
+
+impl WasmManifest {
+    /// Check the manifest is complete and consistent
+    pub fn validate(&self) -> Result<(), AppError> {
+        let invalid = |reason: String| Err(AppError::ValidationError(format!("Plugin '{}': {}", self.name, reason)));
+
+        if !is_valid_plugin_name(&self.name) {
+            return invalid("name must be lowercase letters, digits and dashes".to_string());
+        }
+        if self.abi != WASM_PLUGIN_ABI_VERSION {
+            return invalid(format!(
+                "built for WASM plugin ABI {}, this Rustloader supports {}",
+                self.abi, WASM_PLUGIN_ABI_VERSION
+            ));
+        }
+        if self.hooks.is_empty() {
+            return invalid("declares no hooks".to_string());
+        }
+        let is_site_handler = self.has(WasmHook::SiteHandler);
+        if is_site_handler && self.hosts.is_empty() {
+            return invalid("a site handler must list the hosts it handles".to_string());
+        }
+        if !is_site_handler && !self.hosts.is_empty() {
+            return invalid("only site handlers may list hosts".to_string());
+        }
+        if !self.hosts.iter().all(|host| is_valid_host_pattern(host)) {
+            return invalid("hosts must be plain domain names like example.com".to_string());
+        }
+        if !self.module.ends_with(".wasm") {
+            return invalid("module must be a .wasm file".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn has(&self, hook: WasmHook) -> bool {
+        self.hooks.contains(&hook)
+    }
+
+    /// Whether a site handler handles this URL
+    pub fn handles_url(&self, url: &str) -> bool {
+        url_matches_hosts(url, &self.hosts)
+    }
+}
+
+struct HostState {
+    plugin: String,
+    limits: StoreLimits,
+}
+
+fn wasm_error(plugin: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::General(format!("Plugin '{}': {}", plugin, e))
+}
+
+/// An installed WASM plugin with a valid manifest and a compiled module
+#[derive(Clone)]
+pub struct WasmPlugin {
+    pub manifest: WasmManifest,
+    pub dir: PathBuf,
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmPlugin {
+    /// Load, validate and compile the plugin in `dir`
+    pub fn load(dir: &Path) -> Result<Self, AppError> {
+        let contents = std::fs::read_to_string(dir.join(WASM_MANIFEST_FILE))?;
+        let manifest: WasmManifest = serde_json::from_str(&contents)?;
+        manifest.validate()?;
+        let module_path = validate_plugin_file(dir, &manifest.module)?;
+
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| wasm_error(&manifest.name, e))?;
+        let module = Module::from_file(&engine, &module_path).map_err(|e| wasm_error(&manifest.name, e))?;
+
+        let plugin = Self {
+            manifest,
+            dir: dir.to_path_buf(),
+            engine,
+            module,
+        };
+        // Instantiating checks the imports against the granted capabilities
+        // and the ABI version up front, rather than on the first download
+        let (mut store, instance) = plugin.instantiate()?;
+        let abi = instance
+            .get_typed_func::<(), i32>(&mut store, "rl_abi_version")
+            .and_then(|f| f.call(&mut store, ()))
+            .map_err(|e| wasm_error(&plugin.manifest.name, e))?;
+        if abi != WASM_PLUGIN_ABI_VERSION as i32 {
+            return Err(AppError::ValidationError(format!(
+                "Plugin '{}': module reports ABI {}, manifest says {}",
+                plugin.manifest.name, abi, WASM_PLUGIN_ABI_VERSION
+            )));
+        }
+        for hook in &plugin.manifest.hooks {
+            if instance.get_func(&mut store, hook.export()).is_none() {
+                return Err(AppError::ValidationError(format!(
+                    "Plugin '{}': declares {} but doesn't export {}",
+                    plugin.manifest.name,
+                    hook.as_str(),
+                    hook.export()
+                )));
+            }
+        }
+        Ok(plugin)
+    }
+
+    fn instantiate(&self) -> Result<(Store<HostState>, wasmtime::Instance), AppError> {
+        let name = &self.manifest.name;
+        let state = HostState {
+            plugin: name.clone(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(WASM_PLUGIN_MAX_MEMORY_BYTES)
+                .instances(1)
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(WASM_PLUGIN_FUEL_PER_CALL).map_err(|e| wasm_error(name, e))?;
+
+        let mut linker: Linker<HostState> = Linker::new(&self.engine);
+        for capability in &self.manifest.host_capabilities {
+            match capability {
+                HostCapability::Log => linker.func_wrap(
+                    HOST_MODULE,
+                    "log",
+                    |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+                        let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+                            return;
+                        };
+                        let mut message = vec![0u8; (len.max(0) as usize).min(4096)];
+                        if memory.read(&caller, ptr as usize, &mut message).is_ok() {
+                            info!("[{}] {}", caller.data().plugin, String::from_utf8_lossy(&message));
+                        }
+                    },
+                ),
+                HostCapability::Clock => {
+                    linker.func_wrap(HOST_MODULE, "now_unix", || chrono::Utc::now().timestamp())
+                }
+            }
+            .map_err(|e| wasm_error(name, e))?;
+        }
+
+        let instance = linker.instantiate(&mut store, &self.module).map_err(|e| wasm_error(name, e))?;
+        Ok((store, instance))
+    }
+
+    /// Run one hook with a JSON input. None when the plugin has nothing to say.
+    pub fn call(&self, hook: WasmHook, input: &Value) -> Result<Option<Value>, AppError> {
+        let name = &self.manifest.name;
+        let fail = |e: wasmtime::Error| wasm_error(name, e);
+        let (mut store, instance) = self.instantiate()?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasm_error(name, "module exports no memory"))?;
+
+        let input = serde_json::to_vec(input)?;
+        let len = i32::try_from(input.len()).map_err(|_| wasm_error(name, "input too large"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "rl_alloc").map_err(fail)?;
+        let ptr = alloc.call(&mut store, len).map_err(fail)?;
+        memory.write(&mut store, ptr as u32 as usize, &input).map_err(|e| wasm_error(name, e))?;
+
+        let entry = instance.get_typed_func::<(i32, i32), i64>(&mut store, hook.export()).map_err(fail)?;
+        let packed = entry.call(&mut store, (ptr, len)).map_err(fail)? as u64;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if out_len > MAX_ANSWER_BYTES {
+            return Err(wasm_error(name, "answer too large"));
+        }
+        let mut answer = vec![0u8; out_len];
+        memory.read(&store, out_ptr, &mut answer).map_err(|e| wasm_error(name, e))?;
+        Ok(Some(serde_json::from_slice(&answer)?))
+    }
+
+    /// Ask a filename rule for a new name and rename the file. The file
+    /// keeps its folder and extension, and nothing is overwritten.
+    pub fn rename(&self, file: &Path, format: &str, url: &str) -> Result<PathBuf, AppError> {
+        let file_name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let input = json!({ "file_name": file_name, "format": format, "url": url });
+        let Some(new_name) = self
+            .call(WasmHook::FilenameRule, &input)?
+            .and_then(|answer| answer["file_name"].as_str().map(str::to_string))
+        else {
+            return Ok(file.to_path_buf());
+        };
+        if new_name == file_name {
+            return Ok(file.to_path_buf());
+        }
+        validate_plugin_file_name(&new_name)?;
+        let target = file.with_file_name(&new_name);
+        if target.extension() != file.extension() {
+            return Err(AppError::ValidationError(format!(
+                "Plugin '{}' may not change the extension of {}",
+                self.manifest.name, file_name
+            )));
+        }
+        if target.exists() {
+            return Err(AppError::ValidationError(format!(
+                "Plugin '{}' renamed {} to {}, which already exists",
+                self.manifest.name, file_name, new_name
+            )));
+        }
+        std::fs::rename(file, &target)?;
+        Ok(target)
+    }
+
+    /// Ask a metadata enricher for fields and merge them into the file's
+    /// `.info.json`, creating it if yt-dlp didn't write one
+    pub fn enrich(&self, file: &Path, format: &str, url: &str) -> Result<(), AppError> {
+        let input = json!({ "file": file, "format": format, "url": url });
+        let Some(Value::Object(fields)) = self
+            .call(WasmHook::MetadataEnricher, &input)?
+            .map(|mut answer| answer["metadata"].take())
+        else {
+            return Ok(());
+        };
+        let info_path = file.with_extension("info.json");
+        let mut info = std::fs::read_to_string(&info_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+            .filter(Value::is_object)
+            .unwrap_or_else(|| json!({}));
+        for (key, value) in fields {
+            info[key] = value;
+        }
+        std::fs::write(&info_path, serde_json::to_string_pretty(&info)?)?;
+        Ok(())
+    }
+}
+
+/// Every folder in `root` with a WASM manifest, and whether its plugin is usable
+pub fn discover_wasm_plugins(root: &Path) -> Vec<(PathBuf, Result<WasmPlugin, AppError>)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(PathBuf, Result<WasmPlugin, AppError>)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join(WASM_MANIFEST_FILE).is_file())
+        .map(|path| {
+            let plugin = WasmPlugin::load(&path);
+            (path, plugin)
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+/// The usable WASM plugins with a hook. Broken plugins are skipped with a warning.
+pub fn wasm_plugins_with(hook: WasmHook) -> Vec<WasmPlugin> {
+    let Ok(root) = plugins_dir() else {
+        return Vec::new();
+    };
+    discover_wasm_plugins(&root)
+        .into_iter()
+        .filter_map(|(path, plugin)| match plugin {
+            Ok(plugin) => Some(plugin),
+            Err(e) => {
+                warn!("Skipping WASM plugin in {:?}: {}", path, e);
+                None
+            }
+        })
+        .filter(|plugin| plugin.manifest.has(hook))
+        .collect()
+}
+
+/// Let the first site handler for the URL's host resolve it
+pub async fn resolve_with_wasm(url: &str) -> Option<String> {
+    let url = url.to_string();
+    tokio::task::spawn_blocking(move || {
+        for plugin in wasm_plugins_with(WasmHook::SiteHandler) {
+            if !plugin.manifest.handles_url(&url) {
+                continue;
+            }
+            match plugin.call(WasmHook::SiteHandler, &json!({ "url": url })) {
+                Ok(Some(answer)) => match answer["url"].as_str() {
+                    Some(resolved) if crate::utils::validate_url(resolved).is_ok() => {
+                        debug!("Plugin '{}' resolved {} to {}", plugin.manifest.name, url, resolved);
+                        return Some(resolved.to_string());
+                    }
+                    _ => warn!("Plugin '{}' returned no usable URL for {}", plugin.manifest.name, url),
+                },
+                Ok(None) => {}
+                Err(e) => warn!("{}", e),
+            }
+        }
+        None
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Run every filename rule over a finished download in turn
+pub async fn apply_filename_rules(file: &Path, format: &str, url: &str) -> PathBuf {
+    let (file, format, url) = (file.to_path_buf(), format.to_string(), url.to_string());
+    let fallback = file.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut current = file;
+        for plugin in wasm_plugins_with(WasmHook::FilenameRule) {
+            match plugin.rename(&current, &format, &url) {
+                Ok(renamed) => current = renamed,
+                Err(e) => warn!("{}", e),
+            }
+        }
+        current
+    })
+    .await
+    .unwrap_or(fallback)
+}
+
+/// Let every metadata enricher add to a finished download's `.info.json`
+pub async fn enrich_metadata(file: &Path, format: &str, url: &str) {
+    let (file, format, url) = (file.to_path_buf(), format.to_string(), url.to_string());
+    let _ = tokio::task::spawn_blocking(move || {
+        for plugin in wasm_plugins_with(WasmHook::MetadataEnricher) {
+            if let Err(e) = plugin.enrich(&file, &format, &url) {
+                warn!("{}", e);
+            }
+        }
+    })
+    .await;
+}
@@ -0,0 +1,209 @@
+// src/webhooks.rs
+//! HTTP webhooks for queue events, set under `webhooks` in config.json:
+//!
+//! ```json
+//! "webhooks": [
+//!   { "url": "https://example.com/hook", "progress": { "every_percent": 10, "every_secs": 60 } }
+//! ]
+//! ```
+//!
+//! Each webhook gets a POST with a [`QueueEvent`] JSON body when a download
+//! completes, fails or is canceled. With `progress` set, it also gets
+//! `progress` events while a download runs, at most one each time either
+//! limit is reached.
+
+use crate::download_manager::DownloadStatus;
+use crate::download_map::{snapshot_items, DownloadMap};
+use crate::error::AppError;
+use crate::proxy::client_builder;
+use crate::queue_events::{diff_queue_events, progress_events, QueueEvent};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// How often running downloads are checked against the progress throttles
+const PROGRESS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When a webhook gets progress updates; one goes out as soon as either
+/// limit is reached
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressThrottle {
+    /// Percentage points of progress between updates
+    pub every_percent: Option<f64>,
+    /// Seconds between updates
+    pub every_secs: Option<u64>,
+}
+
+impl ProgressThrottle {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.every_percent.is_none() && self.every_secs.is_none() {
+            return Err(AppError::ValidationError(
+                "Webhook progress needs every_percent or every_secs".to_string(),
+            ));
+        }
+        if self.every_percent.is_some_and(|step| !(step > 0.0 && step <= 100.0)) {
+            return Err(AppError::ValidationError("every_percent must be between 0 and 100".to_string()));
+        }
+        if self.every_secs == Some(0) {
+            return Err(AppError::ValidationError("every_secs must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// A URL that receives queue events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Progress updates; only finished downloads are reported when absent
+    #[serde(default)]
+    pub progress: Option<ProgressThrottle>,
+}
+
+impl Webhook {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let url = Url::parse(&self.url)
+            .map_err(|_| AppError::ValidationError(format!("Invalid webhook URL: {}", self.url)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::ValidationError(format!("Webhook URL must be http or https: {}", self.url)));
+        }
+        if let Some(progress) = &self.progress {
+            progress.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a webhook hears about a change to `status`
+pub fn is_reported(status: DownloadStatus) -> bool {
+    matches!(status, DownloadStatus::Completed | DownloadStatus::Failed | DownloadStatus::Canceled)
+}
+
+/// When each download last had a progress update sent to one webhook
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    last_sent: HashMap<String, (f64, DateTime<Utc>)>,
+}
+
+impl ProgressTracker {
+    /// Whether an update for download `id` at `progress` percent is due at
+    /// `now`, recording it as sent if so. The first one always is.
+    pub fn due(&mut self, throttle: &ProgressThrottle, id: &str, progress: f64, now: DateTime<Utc>) -> bool {
+        let due = match self.last_sent.get(id) {
+            None => true,
+            Some((last_progress, last_time)) => {
+                throttle.every_percent.is_some_and(|step| progress - last_progress >= step)
+                    || throttle
+                        .every_secs
+                        .is_some_and(|secs| now - *last_time >= chrono::Duration::seconds(secs as i64))
+            }
+        };
+        if due {
+            self.last_sent.insert(id.to_string(), (progress, now));
+        }
+        due
+    }
+
+    /// Drop the record for a download that is no longer running
+    pub fn forget(&mut self, id: &str) {
+        self.last_sent.remove(id);
+    }
+}
+
+/// Post the queue's events to `webhooks` for as long as `notifications`
+/// is open. Invalid webhooks are skipped with a warning.
+pub fn spawn_webhooks(
+    webhooks: Vec<Webhook>,
+    downloads: Arc<RwLock<DownloadMap>>,
+    mut notifications: broadcast::Receiver<()>,
+) {
+    let webhooks: Vec<Webhook> = webhooks
+        .into_iter()
+        .filter(|webhook| match webhook.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Ignoring webhook: {}", e);
+                false
+            }
+        })
+        .collect();
+    if webhooks.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let client = match client_builder(None).and_then(|builder| {
+            Ok(builder.timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS)).build()?)
+        }) {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Webhooks are disabled, the HTTP client failed to start: {}", e);
+                return;
+            }
+        };
+        let snapshot = || snapshot_items(&downloads);
+        let mut trackers: Vec<ProgressTracker> = webhooks.iter().map(|_| ProgressTracker::default()).collect();
+        let mut ticker = tokio::time::interval(PROGRESS_CHECK_INTERVAL);
+
+        // Downloads that had already finished before the queue started aren't reported
+        let mut known = HashMap::new();
+        diff_queue_events(&mut known, &snapshot(), Utc::now());
+
+        loop {
+            tokio::select! {
+                notification = notifications.recv() => {
+                    match notification {
+                        // Lagging only means we missed intermediate notifications; a fresh diff catches up
+                        Ok(()) | Err(RecvError::Lagged(_)) => {
+                            for event in diff_queue_events(&mut known, &snapshot(), Utc::now()) {
+                                let QueueEvent::StateChanged { id, to, .. } = &event else { continue };
+                                if !is_reported(*to) {
+                                    continue;
+                                }
+                                for (webhook, tracker) in webhooks.iter().zip(trackers.iter_mut()) {
+                                    tracker.forget(id);
+                                    post(&client, &webhook.url, &event);
+                                }
+                            }
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                _ = ticker.tick() => {
+                    let now = Utc::now();
+                    let events = progress_events(&snapshot(), now);
+                    for (webhook, tracker) in webhooks.iter().zip(trackers.iter_mut()) {
+                        let Some(throttle) = &webhook.progress else { continue };
+                        for event in &events {
+                            if let QueueEvent::Progress { id, progress, .. } = event {
+                                if tracker.due(throttle, id, *progress, now) {
+                                    post(&client, &webhook.url, event);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Send `event` in the background so a slow endpoint never holds up the others
+fn post(client: &Client, url: &str, event: &QueueEvent) {
+    let request = client.post(url).json(event);
+    let url = url.to_string();
+    tokio::spawn(async move {
+        match request.send().await.and_then(|response| response.error_for_status()) {
+            Ok(_) => debug!("Webhook {} notified", url),
+            Err(e) => warn!("Webhook {} failed: {}", url, e),
+        }
+    });
+}
@@ -0,0 +1,114 @@
+// src/windows_integration.rs
+//! Windows shell integration registered by `rustloader service install`:
+//! `rustloader://download?url=...` links and `.rustloader` job files open in
+//! Rustloader. Registration is per user, under HKCU\Software\Classes.
+
+use crate::error::AppError;
+use reqwest::Url;
+
+/// URL scheme handled by Rustloader
+pub const PROTOCOL_SCHEME: &str = "rustloader";
+
+/// Whether a command-line argument is a `rustloader://` link
+pub fn is_protocol_link(arg: &str) -> bool {
+    arg.get(..PROTOCOL_SCHEME.len() + 3)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("rustloader://"))
+}
+
+/// The URL to download from a `rustloader://download?url=<encoded URL>` link
+pub fn parse_protocol_link(link: &str) -> Result<String, AppError> {
+    let parsed = Url::parse(link).map_err(|e| AppError::ValidationError(format!("Invalid link {}: {}", link, e)))?;
+    if parsed.scheme() != PROTOCOL_SCHEME {
+        return Err(AppError::ValidationError(format!("Not a rustloader:// link: {}", link)));
+    }
+    if parsed.host_str() != Some("download") {
+        return Err(AppError::ValidationError(format!(
+            "Unsupported rustloader:// action: {}",
+            parsed.host_str().unwrap_or("")
+        )));
+    }
+
+    let url = parsed
+        .query_pairs()
+        .find(|(key, _)| key == "url")
+        .map(|(_, value)| value.into_owned())
+        .ok_or_else(|| AppError::ValidationError("The link has no url parameter".to_string()))?;
+    // The download itself validates the URL further
+    if !matches!(Url::parse(&url).map(|url| url.scheme().to_string()).as_deref(), Ok("http" | "https")) {
+        return Err(AppError::ValidationError(format!("Links can only download http(s) URLs, not {}", url)));
+    }
+    Ok(url)
+}
+
+#[cfg(windows)]
+mod registry {
+    use super::PROTOCOL_SCHEME;
+    use crate::error::AppError;
+    use crate::templates::JOB_FILE_EXTENSION;
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    const CLASSES_KEY: &str = "Software\\Classes";
+    const JOB_FILE_CLASS: &str = "Rustloader.Job";
+
+    fn classes() -> Result<RegKey, AppError> {
+        Ok(RegKey::predef(HKEY_CURRENT_USER).create_subkey(CLASSES_KEY)?.0)
+    }
+
+    /// Point `class\shell\open\command` at this executable
+    fn set_open_command(class: &RegKey) -> Result<(), AppError> {
+        let exe = std::env::current_exe()?;
+        let (command, _) = class.create_subkey("shell\\open\\command")?;
+        command.set_value("", &format!("\"{}\" \"%1\"", exe.display()))?;
+        Ok(())
+    }
+
+    pub fn register_handlers() -> Result<(), AppError> {
+        let classes = classes()?;
+
+        let (protocol, _) = classes.create_subkey(PROTOCOL_SCHEME)?;
+        protocol.set_value("", &"URL:Rustloader Protocol")?;
+        protocol.set_value("URL Protocol", &"")?;
+        set_open_command(&protocol)?;
+
+        let (extension, _) = classes.create_subkey(format!(".{}", JOB_FILE_EXTENSION))?;
+        extension.set_value("", &JOB_FILE_CLASS)?;
+        let (job_file, _) = classes.create_subkey(JOB_FILE_CLASS)?;
+        job_file.set_value("", &"Rustloader Job")?;
+        set_open_command(&job_file)?;
+        Ok(())
+    }
+
+    pub fn unregister_handlers() -> Result<(), AppError> {
+        let classes = classes()?;
+        for key in [PROTOCOL_SCHEME.to_string(), format!(".{}", JOB_FILE_EXTENSION), JOB_FILE_CLASS.to_string()] {
+            match classes.delete_subkey_all(&key) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Register the `rustloader://` protocol and `.rustloader` job files for the current user
+#[cfg(windows)]
+pub fn register_handlers() -> Result<(), AppError> {
+    registry::register_handlers()
+}
+
+/// Remove the registrations made by [`register_handlers`]
+#[cfg(windows)]
+pub fn unregister_handlers() -> Result<(), AppError> {
+    registry::unregister_handlers()
+}
+
+#[cfg(not(windows))]
+pub fn register_handlers() -> Result<(), AppError> {
+    Err(AppError::General("File and link registration is only available on Windows".to_string()))
+}
+
+#[cfg(not(windows))]
+pub fn unregister_handlers() -> Result<(), AppError> {
+    Err(AppError::General("File and link registration is only available on Windows".to_string()))
+}
@@ -0,0 +1,59 @@
+// tests/age_gate_test.rs
+
+use rustloader::age_gate::{fallback_strategies, is_age_gated, AgeGateStrategy, PLAYER_CLIENTS};
+use rustloader::download_manager::DownloadItem;
+use rustloader::http_headers::RequestHeaders;
+
+const WATCH_URL: &str = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+
+#[test]
+fn test_detects_age_gate() {
+    let context = vec![
+        "[youtube] dQw4w9WgXcQ: Downloading webpage".to_string(),
+        "ERROR: [youtube] dQw4w9WgXcQ: Sign in to confirm your age. This video may be inappropriate for some users."
+            .to_string(),
+    ];
+    assert!(is_age_gated("yt-dlp exited with status 1", &context));
+    assert!(is_age_gated("Video is Age-Restricted", &[]));
+    assert!(!is_age_gated("HTTP Error 403: Forbidden", &["ERROR: unable to download video data".to_string()]));
+}
+
+#[test]
+fn test_fallback_strategies() {
+    let strategies = fallback_strategies(WATCH_URL);
+    assert_eq!(strategies.len(), 1 + PLAYER_CLIENTS.len());
+    assert_eq!(strategies[0], AgeGateStrategy::EmbedUrl);
+    assert_eq!(strategies[1], AgeGateStrategy::PlayerClient("tv_embedded".to_string()));
+
+    assert!(fallback_strategies("https://vimeo.com/76979871").is_empty());
+    assert!(fallback_strategies("https://www.youtube.com/playlist?list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs").is_empty());
+}
+
+#[test]
+fn test_strategy_urls_and_args() {
+    let embed = AgeGateStrategy::EmbedUrl;
+    assert_eq!(embed.url(WATCH_URL), "https://www.youtube.com/embed/dQw4w9WgXcQ");
+    assert_eq!(embed.url("https://youtu.be/dQw4w9WgXcQ"), "https://www.youtube.com/embed/dQw4w9WgXcQ");
+    assert_eq!(embed.extractor_args(), None);
+    assert_eq!(embed.describe(), "embed URL");
+
+    let client = AgeGateStrategy::PlayerClient("web_creator".to_string());
+    assert_eq!(client.url(WATCH_URL), WATCH_URL);
+    assert_eq!(client.extractor_args().as_deref(), Some("youtube:player_client=web_creator"));
+    assert_eq!(client.describe(), "web_creator player client");
+}
+
+#[test]
+fn test_strategy_reaches_ytdlp() {
+    let headers = RequestHeaders::default().with_extractor_args(Some("youtube:player_client=tv_embedded".to_string()));
+    let args = headers.ytdlp_args();
+    assert_eq!(&args[args.len() - 2..], ["--extractor-args", "youtube:player_client=tv_embedded"]);
+
+    // Recorded on the item, so a retried download starts with what worked
+    let mut item = DownloadItem::new(WATCH_URL, "mp4");
+    assert_eq!(item.request_headers().extractor_args, None);
+    item.age_gate_strategy = Some(AgeGateStrategy::PlayerClient("tv_embedded".to_string()));
+    let json = serde_json::to_string(&item).unwrap();
+    let restored: DownloadItem = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.request_headers().extractor_args.as_deref(), Some("youtube:player_client=tv_embedded"));
+}
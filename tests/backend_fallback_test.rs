@@ -0,0 +1,26 @@
+// tests/backend_fallback_test.rs
+use rustloader::download_manager::DownloadItem;
+use rustloader::downloader::{is_aria2c_failure, DownloadBackend};
+
+#[test]
+fn test_detects_aria2c_failures() {
+    assert!(is_aria2c_failure("ERROR: aria2c exited with code 1"));
+    assert!(is_aria2c_failure("[download] Got error\nERROR: [aria2c] error: HLS fragment missing"));
+    assert!(!is_aria2c_failure("ERROR: HTTP Error 404: Not Found"));
+    assert!(!is_aria2c_failure("[download] Destination via aria2c"));
+}
+
+#[test]
+fn test_backend_recorded_in_history() {
+    assert_eq!(DownloadBackend::Native.as_str(), "native");
+
+    let mut item = DownloadItem::new("https://example.com/v", "mp4");
+    item.backend = Some(DownloadBackend::Aria2c);
+    let json = serde_json::to_string(&item).unwrap();
+    assert!(json.contains("\"backend\":\"aria2c\""));
+
+    // History written before backends were recorded still loads
+    let legacy = json.replace(",\"backend\":\"aria2c\"", "");
+    let restored: DownloadItem = serde_json::from_str(&legacy).unwrap();
+    assert_eq!(restored.backend, None);
+}
@@ -0,0 +1,139 @@
+// tests/bandwidth_test.rs
+mod common;
+
+use chrono::NaiveTime;
+use common::{temp_dir, EnvGuard};
+use rustloader::bandwidth::{
+    current_limit, format_rate_limit, parse_rate_limit, set_bandwidth_override, BandwidthManager, BandwidthProfile,
+    RateLimitWatch, MIN_RESTART_CHANGE,
+};
+use rustloader::config::AppConfig;
+use rustloader::paths::CONFIG_DIR_ENV;
+
+fn time(h: u32, m: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(h, m, 0).unwrap()
+}
+
+fn profiles() -> Vec<BandwidthProfile> {
+    vec![
+        BandwidthProfile {
+            name: "work-hours".to_string(),
+            start: "09:00".to_string(),
+            end: "17:00".to_string(),
+            limit: Some("1M".to_string()),
+        },
+        BandwidthProfile {
+            name: "night".to_string(),
+            start: "22:00".to_string(),
+            end: "06:00".to_string(),
+            limit: None,
+        },
+    ]
+}
+
+#[test]
+fn test_parse_rate_limit() {
+    assert_eq!(parse_rate_limit("500K").unwrap(), Some(500 * 1024));
+    assert_eq!(parse_rate_limit("2M").unwrap(), Some(2 * 1024 * 1024));
+    assert_eq!(parse_rate_limit("unlimited").unwrap(), None);
+    assert!(parse_rate_limit("fast").is_err());
+    assert!(parse_rate_limit("1.5M").is_err());
+
+    assert_eq!(format_rate_limit(2 * 1024 * 1024), "2M");
+    assert_eq!(format_rate_limit(500 * 1024), "500K");
+}
+
+#[test]
+fn test_profile_selection_without_transition() {
+    let manager = BandwidthManager::new(&profiles(), 0).unwrap();
+
+    assert_eq!(manager.active_profile(time(12, 0)), Some("work-hours"));
+    assert_eq!(manager.limit_at(time(12, 0)), Some(1024 * 1024));
+
+    // Night profile wraps past midnight
+    assert_eq!(manager.active_profile(time(2, 0)), Some("night"));
+    assert_eq!(manager.limit_at(time(2, 0)), None);
+
+    // Gaps between profiles are unlimited
+    assert_eq!(manager.active_profile(time(19, 0)), None);
+    assert_eq!(manager.limit_at(time(19, 0)), None);
+}
+
+#[test]
+fn test_smooth_transition_between_profiles() {
+    let manager = BandwidthManager::new(&profiles(), 10).unwrap();
+
+    // At the boundary the previous (unlimited) rate still applies via the ramp ceiling
+    let at_start = manager.limit_at(time(9, 0)).unwrap();
+    let halfway = manager.limit_at(time(9, 5)).unwrap();
+    let settled = manager.limit_at(time(9, 10)).unwrap();
+
+    assert!(at_start > halfway);
+    assert!(halfway > settled);
+    assert_eq!(settled, 1024 * 1024);
+}
+
+#[test]
+fn test_override_replaces_schedule() {
+    let manager = BandwidthManager::new(&profiles(), 10)
+        .unwrap()
+        .with_override(Some(Some(256 * 1024)));
+
+    assert_eq!(manager.limit_at(time(12, 0)), Some(256 * 1024));
+    assert_eq!(manager.limit_at(time(2, 0)), Some(256 * 1024));
+}
+
+#[test]
+fn test_invalid_profile_rejected() {
+    let mut bad = profiles();
+    bad[0].start = "9am".to_string();
+    assert!(BandwidthManager::new(&bad, 0).is_err());
+}
+
+#[test]
+fn test_zero_transition_switches_at_boundary() {
+    let mut config = AppConfig {
+        bandwidth_profiles: profiles(),
+        ..AppConfig::default()
+    };
+
+    // Unset uses the default ramp
+    let manager = BandwidthManager::from_app_config(&config).unwrap();
+    assert!(manager.limit_at(time(9, 0)).unwrap() > 1024 * 1024);
+
+    // 0 means no ramp rather than the default
+    config.bandwidth_transition_minutes = Some(0);
+    let manager = BandwidthManager::from_app_config(&config).unwrap();
+    assert_eq!(manager.limit_at(time(9, 0)), Some(1024 * 1024));
+}
+
+#[test]
+fn test_running_download_picks_up_a_new_override() {
+    let tmp = temp_dir("bandwidth");
+    let _env = EnvGuard::lock().set(CONFIG_DIR_ENV, tmp.path());
+
+    set_bandwidth_override(Some("1M")).unwrap();
+    let mut watch = RateLimitWatch::new(current_limit());
+    assert_eq!(watch.applied(), Some(1024 * 1024));
+    assert_eq!(watch.changed(), None);
+
+    // Set while the download runs
+    set_bandwidth_override(Some("256K")).unwrap();
+    assert_eq!(watch.changed(), Some(Some(256 * 1024)));
+    assert_eq!(watch.applied(), Some(256 * 1024));
+
+    // Back to the (empty) schedule lifts the limit
+    set_bandwidth_override(None).unwrap();
+    assert_eq!(watch.changed(), Some(None));
+    assert_eq!(watch.applied(), None);
+}
+
+#[test]
+fn test_small_ramp_steps_do_not_restart_ytdlp() {
+    let mut watch = RateLimitWatch::new(Some(4 * 1024 * 1024)).with_min_change(MIN_RESTART_CHANGE);
+
+    assert_eq!(watch.check(Some(3900 * 1024)), None);
+    assert_eq!(watch.applied(), Some(4 * 1024 * 1024));
+    assert_eq!(watch.check(Some(1024 * 1024)), Some(Some(1024 * 1024)));
+    assert_eq!(watch.check(None), Some(None));
+}
@@ -0,0 +1,58 @@
+// tests/chapters_test.rs
+use rustloader::chapters::{chapter_template, chapters_from_info, parse_chapter_selection, select_chapters, ChapterRef};
+use serde_json::json;
+
+fn info() -> serde_json::Value {
+    json!({
+        "title": "Course",
+        "chapters": [
+            {"start_time": 0.0, "end_time": 60.0, "title": "Intro"},
+            {"start_time": 60.0, "end_time": 600.0, "title": "Lecture 1"},
+            {"start_time": 600.0, "end_time": 1200.0, "title": "Lecture 2"},
+        ]
+    })
+}
+
+#[test]
+fn test_parse_titles_and_numbers() {
+    assert_eq!(
+        parse_chapter_selection(" Intro , 3,,Lecture 2").unwrap(),
+        vec![
+            ChapterRef::Title("Intro".to_string()),
+            ChapterRef::Number(3),
+            ChapterRef::Title("Lecture 2".to_string()),
+        ]
+    );
+    assert!(parse_chapter_selection(" , ").is_err());
+    assert!(parse_chapter_selection("0").is_err());
+}
+
+#[test]
+fn test_chapters_from_metadata() {
+    let chapters = chapters_from_info(&info());
+    assert_eq!(chapters.len(), 3);
+    assert_eq!(chapters[2].number, 3);
+    assert_eq!(chapters[2].title, "Lecture 2");
+    assert_eq!(chapters[2].start_secs, 600.0);
+    assert!(chapters_from_info(&json!({"title": "No chapters"})).is_empty());
+}
+
+#[test]
+fn test_selection_keeps_video_order_once() {
+    let chapters = chapters_from_info(&info());
+    let picked = select_chapters(&chapters, &parse_chapter_selection("lecture 2,1,Intro").unwrap()).unwrap();
+    assert_eq!(picked.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), vec!["Intro", "Lecture 2"]);
+
+    let error = select_chapters(&chapters, &parse_chapter_selection("Outro,7").unwrap()).unwrap_err().to_string();
+    assert!(error.contains("\"Outro\", 7"), "{}", error);
+    assert!(error.contains("2. Lecture 1"), "{}", error);
+    assert!(select_chapters(&[], &[ChapterRef::Number(1)]).is_err());
+}
+
+#[test]
+fn test_chapter_template() {
+    assert_eq!(
+        chapter_template("/dl/%(title)s.mp3"),
+        "/dl/%(title)s - %(section_number)02d %(section_title)s.mp3"
+    );
+}
@@ -0,0 +1,76 @@
+// tests/circuit_breaker_test.rs
+use chrono::{Duration, Utc};
+use rustloader::circuit_breaker::{CircuitBreaker, CircuitBreakerSettings};
+use rustloader::site_stats::FailureKind;
+
+fn settings() -> CircuitBreakerSettings {
+    CircuitBreakerSettings {
+        failures: 3,
+        window_minutes: 10,
+        ..CircuitBreakerSettings::default()
+    }
+}
+
+#[test]
+fn test_opens_after_consecutive_network_failures() {
+    let mut breaker = CircuitBreaker::new();
+    let now = Utc::now();
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now));
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now + Duration::minutes(1)));
+    assert!(breaker.record_failure(FailureKind::Network, &settings(), now + Duration::minutes(2)));
+    assert!(breaker.is_open());
+
+    // Already open: further failures don't open it again
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now + Duration::minutes(3)));
+
+    breaker.close();
+    assert!(!breaker.is_open());
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now + Duration::minutes(4)));
+}
+
+#[test]
+fn test_success_or_other_failure_ends_the_run() {
+    let mut breaker = CircuitBreaker::new();
+    let now = Utc::now();
+    breaker.record_failure(FailureKind::Network, &settings(), now);
+    breaker.record_failure(FailureKind::Network, &settings(), now);
+    breaker.record_success();
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now));
+
+    breaker.record_failure(FailureKind::Network, &settings(), now);
+    assert!(!breaker.record_failure(FailureKind::Unavailable, &settings(), now));
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now));
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn test_old_failures_fall_out_of_the_window() {
+    let mut breaker = CircuitBreaker::new();
+    let now = Utc::now();
+    breaker.record_failure(FailureKind::Network, &settings(), now);
+    breaker.record_failure(FailureKind::Network, &settings(), now + Duration::minutes(1));
+    assert!(!breaker.record_failure(FailureKind::Network, &settings(), now + Duration::minutes(30)));
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn test_disabled_never_opens() {
+    let disabled = CircuitBreakerSettings {
+        enabled: false,
+        failures: 1,
+        ..CircuitBreakerSettings::default()
+    };
+    let mut breaker = CircuitBreaker::new();
+    assert!(!breaker.record_failure(FailureKind::Network, &disabled, Utc::now()));
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn test_probe_interval_has_a_floor() {
+    let settings = CircuitBreakerSettings {
+        probe_interval_secs: 0,
+        ..CircuitBreakerSettings::default()
+    };
+    assert_eq!(settings.probe_interval(), std::time::Duration::from_secs(5));
+    assert_eq!(CircuitBreakerSettings::default().probe_interval(), std::time::Duration::from_secs(60));
+}
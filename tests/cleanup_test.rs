@@ -0,0 +1,53 @@
+// tests/cleanup_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::cleanup::{discard_file, is_partial_file_name, match_partial_file, partial_base_name};
+use rustloader::download_manager::DownloadItem;
+
+#[test]
+fn test_partial_file_detection() {
+    assert!(is_partial_file_name("My Video.mp4.part"));
+    assert!(is_partial_file_name("My Video.f137.mp4.ytdl"));
+    assert!(is_partial_file_name("My Video.f137.mp4.part-Frag12"));
+    assert!(!is_partial_file_name("My Video.mp4"));
+}
+
+#[test]
+fn test_partial_base_name() {
+    assert_eq!(partial_base_name("My Video.mp4.part"), "My Video");
+    assert_eq!(partial_base_name("My Video.f137.mp4.part"), "My Video");
+    assert_eq!(partial_base_name("My Video.f251.webm.part-Frag3"), "My Video");
+    assert_eq!(partial_base_name("Song.mp3.ytdl"), "Song");
+}
+
+#[test]
+fn test_match_partial_file() {
+    let downloads = vec![
+        DownloadItem::builder("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4")
+            .title(Some("Never Gonna Give You Up"))
+            .build(),
+        DownloadItem::builder("https://youtu.be/abcdefghijk", "mp3").build(),
+    ];
+
+    let by_title = match_partial_file("Never Gonna Give You Up.f137.mp4.part", &downloads);
+    assert_eq!(by_title.map(|d| d.id.as_str()), Some(downloads[0].id.as_str()));
+
+    let by_id = match_partial_file("clip-abcdefghijk.mp3.part", &downloads);
+    assert_eq!(by_id.map(|d| d.id.as_str()), Some(downloads[1].id.as_str()));
+
+    assert!(match_partial_file("Something Else.mp4.part", &downloads).is_none());
+}
+
+#[test]
+fn test_discard_file_permanently() {
+    let tmp = temp_dir("cleanup");
+    let dir = tmp.path().to_path_buf();
+    let file = dir.join("Leftover.mp4.part");
+    std::fs::write(&file, b"partial").unwrap();
+
+    discard_file(&file, true).unwrap();
+    assert!(!file.exists());
+    // Already gone
+    assert!(discard_file(&file, true).is_err());
+}
@@ -155,4 +155,63 @@ fn test_cli_invalid_arguments() {
         "invalid"
     ]);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cli_independent_quality_options() {
+    let app = build_cli();
+
+    let matches = app
+        .try_get_matches_from(vec![
+            "rustloader",
+            "https://example.com",
+            "--video-quality",
+            "1080",
+            "--audio-quality",
+            "high",
+            "--codec",
+            "vp9",
+        ])
+        .unwrap();
+
+    assert_eq!(matches.get_one::<String>("quality").unwrap(), "1080");
+    assert_eq!(matches.get_one::<String>("audio-quality").unwrap(), "high");
+    assert_eq!(matches.get_one::<String>("codec").unwrap(), "vp9");
+}
+
+#[test]
+fn test_cli_queue_show_json() {
+    let app = build_cli();
+
+    let matches = app
+        .try_get_matches_from(vec!["rustloader", "queue", "show", "1a2b3c4d", "--json"])
+        .unwrap();
+
+    let show = matches
+        .subcommand_matches("queue")
+        .and_then(|queue| queue.subcommand_matches("show"))
+        .unwrap();
+    assert_eq!(show.get_one::<String>("id").unwrap(), "1a2b3c4d");
+    assert!(show.get_flag("json"));
+}
+
+#[test]
+fn test_cli_turning_off_template_switches() {
+    let app = build_cli();
+
+    let matches = app
+        .clone()
+        .try_get_matches_from(vec!["rustloader", "https://example.com", "--no-playlist", "--no-subs"])
+        .unwrap();
+    assert!(matches.get_flag("no-playlist"));
+    assert!(matches.get_flag("no-subtitles"));
+
+    let result = app.clone().try_get_matches_from(vec![
+        "rustloader",
+        "download",
+        "https://example.com",
+        "--playlist",
+        "--no-playlist",
+    ]);
+    assert!(result.is_err());
+}
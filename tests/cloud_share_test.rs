@@ -0,0 +1,77 @@
+// tests/cloud_share_test.rs
+
+use rustloader::cloud_share::{direct_download_url, drive_confirm_url, CloudProvider};
+
+#[test]
+fn test_google_drive_links() {
+    let expected = "https://drive.usercontent.google.com/download?id=1AbC-d_E&export=download";
+    for url in [
+        "https://drive.google.com/file/d/1AbC-d_E/view?usp=sharing",
+        "https://drive.google.com/open?id=1AbC-d_E",
+        "https://drive.google.com/uc?id=1AbC-d_E&export=download",
+        "https://docs.google.com/uc?export=download&id=1AbC-d_E",
+    ] {
+        let link = direct_download_url(url).unwrap();
+        assert_eq!(link.provider, CloudProvider::GoogleDrive);
+        assert_eq!(link.url, expected, "{}", url);
+    }
+    assert!(direct_download_url("https://drive.google.com/drive/folders").is_none());
+    assert!(direct_download_url("https://drive.google.com/open?id=../etc").is_none());
+}
+
+#[test]
+fn test_dropbox_links() {
+    let link = direct_download_url("https://www.dropbox.com/scl/fi/abc/video.mp4?rlkey=xyz&dl=0").unwrap();
+    assert_eq!(link.provider, CloudProvider::Dropbox);
+    assert_eq!(link.url, "https://www.dropbox.com/scl/fi/abc/video.mp4?rlkey=xyz&dl=1");
+
+    let link = direct_download_url("https://www.dropbox.com/s/abc/video.mp4").unwrap();
+    assert_eq!(link.url, "https://www.dropbox.com/s/abc/video.mp4?dl=1");
+
+    assert!(direct_download_url("https://www.dropbox.com/home").is_none());
+}
+
+#[test]
+fn test_onedrive_links() {
+    let link = direct_download_url("https://1drv.ms/v/s!AbCdEf").unwrap();
+    assert_eq!(link.provider, CloudProvider::OneDrive);
+    assert_eq!(
+        link.url,
+        "https://api.onedrive.com/v1.0/shares/u!aHR0cHM6Ly8xZHJ2Lm1zL3YvcyFBYkNkRWY/root/content"
+    );
+
+    let link = direct_download_url("https://contoso.sharepoint.com/:v:/g/personal/me/EAbc?e=x1").unwrap();
+    assert_eq!(link.url, "https://contoso.sharepoint.com/:v:/g/personal/me/EAbc?e=x1&download=1");
+    assert!(direct_download_url("https://contoso.sharepoint.com/sites/team").is_none());
+}
+
+#[test]
+fn test_other_urls_are_not_share_links() {
+    assert!(direct_download_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ").is_none());
+    assert!(direct_download_url("not a url").is_none());
+}
+
+#[test]
+fn test_drive_confirm_form() {
+    let page = r#"<html><body><form id="download-form" action="https://drive.usercontent.google.com/download" method="get">
+        <input type="submit" id="uc-download-link" value="Download anyway"/>
+        <input type="hidden" name="id" value="1AbC">
+        <input type="hidden" name="export" value="download">
+        <input type="hidden" name="confirm" value="t">
+        <input type="hidden" name="uuid" value="0f1e-2d3c">
+        </form></body></html>"#;
+    assert_eq!(
+        drive_confirm_url(page).as_deref(),
+        Some("https://drive.usercontent.google.com/download?id=1AbC&export=download&confirm=t&uuid=0f1e-2d3c")
+    );
+}
+
+#[test]
+fn test_drive_legacy_confirm_link() {
+    let page = r#"<a id="uc-download-link" href="/uc?export=download&amp;confirm=Xy_1&amp;id=1AbC">Download anyway</a>"#;
+    assert_eq!(
+        drive_confirm_url(page).as_deref(),
+        Some("https://drive.google.com/uc?export=download&confirm=Xy_1&id=1AbC")
+    );
+    assert_eq!(drive_confirm_url("<html>Sign in</html>"), None);
+}
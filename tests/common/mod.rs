@@ -0,0 +1,62 @@
+// tests/common/mod.rs
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use rustloader::download_manager::DownloadItem;
+use rustloader::download_map::DownloadMap;
+use std::ffi::OsStr;
+use std::sync::{Mutex, MutexGuard};
+use tempfile::TempDir;
+
+/// A new empty directory, removed with its contents when dropped, also when
+/// the test fails
+pub fn temp_dir(name: &str) -> TempDir {
+    tempfile::Builder::new()
+        .prefix(&format!("rustloader_{}_", name))
+        .tempdir()
+        .unwrap()
+}
+
+/// A queued download with the ID `id`
+pub fn item(id: &str) -> DownloadItem {
+    let mut item = DownloadItem::new(&format!("https://www.youtube.com/watch?v={}", id), "mp4");
+    item.id = id.to_string();
+    item
+}
+
+/// A download map holding copies of `items` under their IDs
+pub fn download_map(items: &[DownloadItem]) -> DownloadMap {
+    items.iter().map(|item| (item.id.clone(), item.clone())).collect()
+}
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Environment variables set for as long as the guard lives. The tests of a
+/// file run on parallel threads sharing one environment, so only one guard
+/// exists at a time and its variables are removed again when it is dropped.
+pub struct EnvGuard {
+    vars: Vec<String>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl EnvGuard {
+    /// Wait until no other test holds the environment
+    pub fn lock() -> Self {
+        let lock = ENV_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        EnvGuard { vars: Vec::new(), _lock: lock }
+    }
+
+    pub fn set(mut self, key: &str, value: impl AsRef<OsStr>) -> Self {
+        std::env::set_var(key, value);
+        self.vars.push(key.to_string());
+        self
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for key in &self.vars {
+            std::env::remove_var(key);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// tests/completion_test.rs
+use rustloader::completion::{is_batch_finished, is_queue_busy, CompletionAction, QueueSummary};
+use std::collections::HashSet;
+use rustloader::download_manager::DownloadItem;
+
+#[test]
+fn test_parse_completion_action() {
+    assert_eq!(CompletionAction::parse(&["shutdown"]).unwrap(), CompletionAction::Shutdown);
+    assert_eq!(CompletionAction::parse(&["Sleep"]).unwrap(), CompletionAction::Sleep);
+    assert_eq!(CompletionAction::parse(&["notify"]).unwrap(), CompletionAction::Notify);
+    assert_eq!(
+        CompletionAction::parse(&["run", "rsync -a ~/Downloads nas:/media"]).unwrap(),
+        CompletionAction::Run("rsync -a ~/Downloads nas:/media".to_string())
+    );
+
+    assert!(CompletionAction::parse(&[]).is_err());
+    assert!(CompletionAction::parse(&["run"]).is_err());
+    assert!(CompletionAction::parse(&["run", "  "]).is_err());
+    assert!(CompletionAction::parse(&["run", "echo \"unterminated"]).is_err());
+    assert!(CompletionAction::parse(&["notify", "extra"]).is_err());
+    assert!(CompletionAction::parse(&["reboot"]).is_err());
+}
+
+#[test]
+fn test_completion_action_serde() {
+    let action: CompletionAction = serde_json::from_str(r#"{"action":"run","command":"./sync.sh"}"#).unwrap();
+    assert_eq!(action, CompletionAction::Run("./sync.sh".to_string()));
+    let action: CompletionAction = serde_json::from_str(r#"{"action":"sleep"}"#).unwrap();
+    assert_eq!(action, CompletionAction::Sleep);
+}
+
+#[test]
+fn test_queue_summary_and_busy() {
+    let mut done = DownloadItem::new("https://www.youtube.com/watch?v=a", "mp4");
+    done.mark_completed(None);
+    let mut failed = DownloadItem::new("https://www.youtube.com/watch?v=b", "mp4");
+    failed.mark_failed(Some("HTTP 403".to_string()));
+    let mut running = DownloadItem::new("https://www.youtube.com/watch?v=c", "mp4");
+    running.mark_started();
+
+    let summary = QueueSummary::from_downloads([&done, &failed, &running]);
+    assert_eq!(summary, QueueSummary { completed: 1, failed: 1, canceled: 0 });
+
+    assert!(is_queue_busy(&[done.clone(), running.clone()]));
+    running.mark_completed(None);
+    assert!(!is_queue_busy(&[done, failed, running]));
+    assert!(!is_queue_busy(&[]));
+}
+
+#[test]
+fn test_paused_downloads_hold_the_completion_action() {
+    let mut deadline = DownloadItem::new("https://www.youtube.com/watch?v=a", "mp4");
+    deadline.mark_paused();
+    deadline.resume_at = Some(chrono::Utc::now());
+    let mut sleeping = DownloadItem::new("https://www.youtube.com/watch?v=b", "mp4");
+    sleeping.mark_paused();
+    sleeping.paused_for_sleep = true;
+    assert!(is_queue_busy(&[deadline.clone()]));
+    assert!(is_queue_busy(&[sleeping]));
+
+    // Paused by the user: not busy, but the batch it belongs to isn't done
+    let mut by_user = deadline.clone();
+    by_user.resume_at = None;
+    assert!(!is_queue_busy(&[by_user.clone()]));
+    let batch: HashSet<String> = [by_user.id.clone()].into();
+    assert!(!is_batch_finished(&[by_user.clone()], &batch));
+    by_user.mark_completed(None);
+    assert!(is_batch_finished(&[by_user], &batch));
+    assert!(!is_batch_finished(&[], &HashSet::new()));
+}
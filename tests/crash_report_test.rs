@@ -0,0 +1,52 @@
+// tests/crash_report_test.rs
+mod common;
+
+use common::{temp_dir, EnvGuard};
+use rustloader::crash_report::{recent_operations, record_operation, CrashReport, MAX_RECENT_OPERATIONS};
+use rustloader::paths::DATA_DIR_ENV;
+use std::sync::Mutex;
+
+static CAPTURED: Mutex<Option<CrashReport>> = Mutex::new(None);
+
+/// Panic on purpose and capture the report the hook would write
+fn capture_panic(message: &'static str) -> CrashReport {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|info| {
+        *CAPTURED.lock().unwrap() = Some(CrashReport::capture(info));
+    }));
+    let result = std::panic::catch_unwind(|| panic!("{}", message));
+    std::panic::set_hook(previous);
+    assert!(result.is_err());
+    CAPTURED.lock().unwrap().take().unwrap()
+}
+
+#[test]
+fn test_crash_report_records_panic_operations_and_versions() {
+    for n in 0..MAX_RECENT_OPERATIONS + 5 {
+        record_operation(&format!("op{}", n));
+    }
+    let operations = recent_operations();
+    assert_eq!(operations.len(), MAX_RECENT_OPERATIONS);
+    assert!(operations[0].ends_with(" op5"));
+    assert!(operations.last().unwrap().ends_with(&format!(" op{}", MAX_RECENT_OPERATIONS + 4)));
+
+    let report = capture_panic("queue exploded");
+    assert_eq!(report.message, "queue exploded");
+    assert!(report.location.as_deref().unwrap().contains("crash_report_test.rs"));
+    assert_eq!(report.operations, recent_operations());
+
+    let text = report.render();
+    assert!(text.contains("Panic:    queue exploded"));
+    assert!(text.contains("rustloader: "));
+    assert!(text.contains(&format!("op{}", MAX_RECENT_OPERATIONS + 4)));
+    assert!(text.contains("Backtrace:"));
+
+    // Saved in the crash directory under the data directory
+    let tmp = temp_dir("crash");
+    let data = tmp.path().to_path_buf();
+    let env = EnvGuard::lock().set(DATA_DIR_ENV, &data);
+    let path = report.write().unwrap();
+    drop(env);
+    assert!(path.starts_with(data.join("crashes")));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+}
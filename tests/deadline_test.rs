@@ -0,0 +1,60 @@
+// tests/deadline_test.rs
+use chrono::{Duration, Utc};
+use rustloader::download_manager::{DeadlineAction, DownloadItem, DownloadStatus};
+use rustloader::utils::parse_duration;
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("90s").unwrap(), 90);
+    assert_eq!(parse_duration("30m").unwrap(), 1800);
+    assert_eq!(parse_duration("2h").unwrap(), 7200);
+    assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+    assert_eq!(parse_duration(" 45 ").unwrap(), 2700);
+    assert_eq!(parse_duration("1H").unwrap(), 3600);
+
+    assert!(parse_duration("").is_err());
+    assert!(parse_duration("0m").is_err());
+    assert!(parse_duration("30x").is_err());
+    assert!(parse_duration("h").is_err());
+    assert!(parse_duration("1h30").is_err());
+}
+
+#[test]
+fn test_deadline_action_parse() {
+    assert_eq!(DeadlineAction::parse("fail").unwrap(), DeadlineAction::Fail);
+    assert_eq!(DeadlineAction::parse("Pause").unwrap(), DeadlineAction::Pause);
+    assert!(DeadlineAction::parse("stop").is_err());
+    assert_eq!(DeadlineAction::default(), DeadlineAction::Fail);
+}
+
+#[test]
+fn test_deadline_pause_schedules_resume() {
+    let now = Utc::now();
+    let mut item = DownloadItem::builder("https://example.com/video", "mp4")
+        .deadline(Some(1800), DeadlineAction::Pause, Some(3600))
+        .build();
+    item.mark_started();
+    item.mark_deadline_paused(now);
+
+    assert_eq!(item.status, DownloadStatus::Paused);
+    assert_eq!(item.resume_at, Some(now + Duration::seconds(3600)));
+    assert!(!item.is_resume_due(now));
+    assert!(item.is_resume_due(now + Duration::seconds(3600)));
+
+    item.mark_resumed();
+    assert_eq!(item.resume_at, None);
+    assert!(!item.is_paused());
+}
+
+#[test]
+fn test_deadline_pause_without_resume_after_stays_paused() {
+    let now = Utc::now();
+    let mut item = DownloadItem::builder("https://example.com/video", "mp4")
+        .deadline(Some(1800), DeadlineAction::Pause, None)
+        .build();
+    item.mark_deadline_paused(now);
+
+    assert!(item.is_paused());
+    assert_eq!(item.resume_at, None);
+    assert!(!item.is_resume_due(now + Duration::days(365)));
+}
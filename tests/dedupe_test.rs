@@ -0,0 +1,44 @@
+// tests/dedupe_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::dedupe::{file_hash, find_duplicate, replace_with_hardlink};
+use std::fs;
+
+#[test]
+fn test_find_duplicate_matches_identical_contents_only() {
+    let library_dir = temp_dir("dedupe_library");
+    let downloads_dir = temp_dir("dedupe_downloads");
+    let library = library_dir.path().to_path_buf();
+    let downloads = downloads_dir.path().to_path_buf();
+
+    fs::write(library.join("same size.mp4"), b"abcdefgh").unwrap();
+    fs::write(library.join("original.mp4"), b"12345678").unwrap();
+    let new_file = downloads.join("new.mp4");
+    fs::write(&new_file, b"12345678").unwrap();
+
+    let found = find_duplicate(&new_file, &[library.clone(), downloads.clone()]).unwrap();
+    assert_eq!(found, Some(library.join("original.mp4")));
+
+    // The download itself is never reported as its own duplicate
+    fs::write(&new_file, b"unique contents").unwrap();
+    assert_eq!(find_duplicate(&new_file, &[library, downloads]).unwrap(), None);
+}
+
+#[test]
+fn test_replace_with_hardlink_keeps_contents() {
+    let tmp = temp_dir("dedupe_link");
+    let dir = tmp.path().to_path_buf();
+    let original = dir.join("original.mp4");
+    let duplicate = dir.join("copy.mp4");
+    fs::write(&original, b"video bytes").unwrap();
+    fs::write(&duplicate, b"video bytes").unwrap();
+
+    let saved = replace_with_hardlink(&duplicate, &original).unwrap();
+    assert_eq!(saved, 11);
+    assert_eq!(file_hash(&duplicate).unwrap(), file_hash(&original).unwrap());
+
+    // Once linked, the two paths no longer count as duplicates
+    #[cfg(unix)]
+    assert_eq!(find_duplicate(&duplicate, std::slice::from_ref(&dir)).unwrap(), None);
+}
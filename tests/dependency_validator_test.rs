@@ -178,4 +178,37 @@ fn test_sanitize_version_string() {
     // Test with extra parts
     assert!(is_minimum_version("4.0.0.1", "4.0.0")); // Extra part
     assert!(!is_minimum_version("3.9.9.9", "4.0.0")); // Extra part, older major
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_dependency_info_serializes_for_the_gui() {
+    use rustloader::dependency_validator::DependencyInfo;
+
+    let info = DependencyInfo {
+        name: "yt-dlp".to_string(),
+        version: "2024.03.10".to_string(),
+        path: "/usr/bin/yt-dlp".to_string(),
+        hash: None,
+        installed: true,
+        is_min_version: true,
+        is_vulnerable: false,
+    };
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["version"], "2024.03.10");
+    assert_eq!(json["path"], "/usr/bin/yt-dlp");
+    assert_eq!(json["installed"], true);
+    assert_eq!(json["is_min_version"], true);
+    assert_eq!(json["is_vulnerable"], false);
+}
+
+// Re-checks run the real binaries
+#[test]
+#[ignore]
+fn test_recheck_updates_the_status() {
+    use rustloader::dependency_validator::{dependency_status, recheck_dependencies, TRACKED_DEPENDENCIES};
+
+    let status = recheck_dependencies();
+    assert_eq!(status.len(), TRACKED_DEPENDENCIES.len());
+    let names: Vec<String> = dependency_status().into_iter().map(|info| info.name).collect();
+    assert_eq!(names, vec!["yt-dlp", "ffmpeg"]);
+}
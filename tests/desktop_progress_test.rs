@@ -0,0 +1,59 @@
+// tests/desktop_progress_test.rs
+#![cfg(feature = "platform-integration")]
+
+mod common;
+
+use rustloader::desktop_progress::{launcher_progress, launcher_properties, update_command, LauncherProgress, APP_URI};
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+
+fn item(status: DownloadStatus, downloaded_bytes: u64, total_bytes: u64, progress: f64) -> DownloadItem {
+    let mut item = common::item("dQw4w9WgXcQ");
+    item.status = status;
+    item.downloaded_bytes = downloaded_bytes;
+    item.total_bytes = total_bytes;
+    item.progress = progress;
+    item
+}
+
+#[test]
+fn test_launcher_progress() {
+    assert_eq!(launcher_progress(&[]), None);
+    assert_eq!(launcher_progress(&[item(DownloadStatus::Completed, 10, 10, 100.0)]), None);
+
+    // Weighted by size when all sizes are known
+    let progress = launcher_progress(&[
+        item(DownloadStatus::Downloading, 100, 400, 25.0),
+        item(DownloadStatus::Downloading, 500, 600, 83.3),
+        item(DownloadStatus::Queued, 0, 1_000, 0.0),
+    ])
+    .unwrap();
+    assert_eq!(progress.active, 2);
+    assert!((progress.fraction - 0.6).abs() < 1e-9);
+
+    // Otherwise the average percentage
+    let progress = launcher_progress(&[
+        item(DownloadStatus::Downloading, 100, 400, 20.0),
+        item(DownloadStatus::Downloading, 0, 0, 60.0),
+    ])
+    .unwrap();
+    assert!((progress.fraction - 0.4).abs() < 1e-9);
+}
+
+#[test]
+fn test_launcher_signal() {
+    let properties = launcher_properties(Some(LauncherProgress {
+        active: 2,
+        fraction: 0.25,
+    }));
+    assert_eq!(
+        properties,
+        "{'progress': <0.250>, 'progress-visible': <true>, 'count': <int64 2>, 'count-visible': <true>}"
+    );
+    assert_eq!(launcher_properties(None), "{'progress-visible': <false>, 'count-visible': <false>}");
+
+    let args = update_command(&properties).to_args().unwrap();
+    assert_eq!(args[0], "emit");
+    assert!(args.contains(&"com.canonical.Unity.LauncherEntry.Update".to_string()));
+    assert_eq!(args[args.len() - 2], APP_URI);
+    assert_eq!(args[args.len() - 1], properties);
+}
@@ -0,0 +1,272 @@
+// tests/direct_download_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::downloader::direct::{
+    content_disposition_file_name, content_range_total, direct_media_extension, download_direct, file_name,
+    fragment_path, matches_format, part_path, plan_segments, probe, validator_path, DirectOptions, MIN_SEGMENT_BYTES,
+};
+use rustloader::http_headers::RequestHeaders;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Serve `body` at any path, answering Range requests when `ranges` is set.
+/// Returns the base URL and the number of requests served.
+fn serve(body: Vec<u8>, ranges: bool) -> (String, Arc<AtomicUsize>) {
+    serve_versions(vec![(body, None)], ranges)
+}
+
+/// Serve version `i` of the file, with its ETag if any, for request `i`,
+/// and the last version from then on. A Range request with an If-Range
+/// that doesn't match gets the whole file.
+fn serve_versions(versions: Vec<(Vec<u8>, Option<&'static str>)>, ranges: bool) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&requests);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let served = counter.fetch_add(1, Ordering::SeqCst);
+            let (body, etag) = versions[served.min(versions.len() - 1)].clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+                let mut if_range = None;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        let (start, end) = value.trim().split_once('-').unwrap();
+                        let start: usize = start.parse().unwrap();
+                        let end = end.parse::<usize>().map(|end| end + 1).unwrap_or(body.len());
+                        range = Some((start, end.min(body.len())));
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("if-range:") {
+                        if_range = Some(value.trim().to_string());
+                    }
+                }
+                let current = if_range.map_or(true, |validator| Some(validator.as_str()) == etag);
+                let (status, slice, mut extra) = match range.filter(|_| ranges && current) {
+                    Some((start, end)) => (
+                        "206 Partial Content",
+                        &body[start..end],
+                        format!("Content-Range: bytes {}-{}/{}\r\n", start, end - 1, body.len()),
+                    ),
+                    None => ("200 OK", &body[..], String::new()),
+                };
+                if let Some(etag) = etag {
+                    extra.push_str(&format!("ETag: {}\r\n", etag));
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: video/mp4\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                    status,
+                    slice.len(),
+                    extra
+                );
+                let _ = stream.write_all(slice);
+            });
+        }
+    });
+    (url, requests)
+}
+
+fn options(dir: PathBuf, connections: usize) -> DirectOptions {
+    DirectOptions {
+        dir,
+        extension: "mp4".to_string(),
+        connections,
+        rate_limit: None,
+        follow_bandwidth: false,
+        max_filesize: None,
+        force: false,
+    }
+}
+
+fn body(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn test_direct_media_links() {
+    assert_eq!(direct_media_extension("https://cdn.example.com/a/clip.MP4?token=1").as_deref(), Some("mp4"));
+    assert_eq!(direct_media_extension("http://example.com/song.mp3").as_deref(), Some("mp3"));
+    assert_eq!(direct_media_extension("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), None);
+    assert_eq!(direct_media_extension("https://example.com/page.html"), None);
+    assert_eq!(direct_media_extension("ftp://example.com/clip.mp4"), None);
+    assert!(matches_format("mp4", "mp4"));
+    assert!(matches_format("m4v", "mp4"));
+    // An mp3 is only saved as is when an mp3 was asked for
+    assert!(!matches_format("mp4", "mp3"));
+}
+
+#[test]
+fn test_segments_cover_the_file_once() {
+    assert!(plan_segments(0, 4).is_empty());
+    assert_eq!(plan_segments(1000, 4), vec![0..1000]);
+    let size = MIN_SEGMENT_BYTES * 3 + 5;
+    let segments = plan_segments(size, 8);
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments[0].start, 0);
+    assert_eq!(segments.last().unwrap().end, size);
+    assert!(segments.windows(2).all(|pair| pair[0].end == pair[1].start));
+    assert_eq!(plan_segments(MIN_SEGMENT_BYTES * 100, 4).len(), 4);
+    assert_eq!(plan_segments(MIN_SEGMENT_BYTES * 100, 0).len(), 1);
+}
+
+#[test]
+fn test_header_parsing_and_file_names() {
+    assert_eq!(content_range_total("bytes 0-0/1234"), Some(1234));
+    assert_eq!(content_range_total("bytes 0-0/*"), None);
+    assert_eq!(
+        content_disposition_file_name("attachment; filename=\"My Clip.mp4\"").as_deref(),
+        Some("My Clip.mp4")
+    );
+    assert_eq!(content_disposition_file_name("inline"), None);
+
+    assert_eq!(file_name("https://example.com/v/My%20Clip.mp4?x=1", None, "mp4"), "My Clip.mp4");
+    assert_eq!(file_name("https://example.com/v/a.mp4", Some("../../etc/passwd.mp4"), "mp4"), "passwd.mp4");
+    assert_eq!(file_name("https://example.com/v/a.m4v", None, "m4v"), "a.m4v");
+    assert!(!file_name("https://example.com/v/bad%3Cname%3E.mp4", None, "mp4").contains('<'));
+
+    let dest = PathBuf::from("/downloads/clip.mp4");
+    assert_eq!(part_path(&dest), PathBuf::from("/downloads/clip.mp4.part"));
+    assert_eq!(fragment_path(&dest, 1, 4), PathBuf::from("/downloads/clip.mp4.part-Frag1of4"));
+}
+
+#[tokio::test]
+async fn test_downloads_over_several_connections() {
+    let data = body(MIN_SEGMENT_BYTES as usize * 2 + 123);
+    let (base, requests) = serve(data.clone(), true);
+    let url = format!("{}/media/clip.mp4", base);
+    let headers = RequestHeaders::default();
+
+    let remote = probe(&url, &headers).await.unwrap();
+    assert_eq!(remote.size, Some(data.len() as u64));
+    assert!(remote.accepts_ranges);
+
+    let tmp = temp_dir("direct_segments");
+    let dir = tmp.path().to_path_buf();
+    let reported = Arc::new(AtomicU64::new(0));
+    let seen = Arc::clone(&reported);
+    let path = download_direct(
+        &url,
+        &remote,
+        &options(dir.clone(), 4),
+        &headers,
+        Arc::new(move |downloaded, _| {
+            seen.fetch_max(downloaded, Ordering::SeqCst);
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(path, dir.join("clip.mp4"));
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert_eq!(reported.load(Ordering::SeqCst), data.len() as u64);
+    // The probe, then one request per segment
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+    assert!(!fragment_path(&path, 0, 2).exists());
+}
+
+#[tokio::test]
+async fn test_resumes_from_a_part_file() {
+    let data = body(100_000);
+    let (base, _) = serve(data.clone(), true);
+    let url = format!("{}/clip.mp4", base);
+    let headers = RequestHeaders::default();
+    let tmp = temp_dir("direct_resume");
+    let dir = tmp.path().to_path_buf();
+    std::fs::write(part_path(&dir.join("clip.mp4")), &data[..40_000]).unwrap();
+
+    let remote = probe(&url, &headers).await.unwrap();
+    let first = Arc::new(AtomicU64::new(u64::MAX));
+    let seen = Arc::clone(&first);
+    let path = download_direct(
+        &url,
+        &remote,
+        &options(dir.clone(), 4),
+        &headers,
+        Arc::new(move |downloaded, _| {
+            let _ = seen.compare_exchange(u64::MAX, downloaded, Ordering::SeqCst, Ordering::SeqCst);
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(first.load(Ordering::SeqCst), 40_000);
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert!(!part_path(&path).exists());
+}
+
+#[tokio::test]
+async fn test_server_without_ranges_starts_over_and_keeps_existing_files() {
+    let data = body(50_000);
+    let (base, _) = serve(data.clone(), false);
+    let url = format!("{}/clip.mp4", base);
+    let headers = RequestHeaders::default();
+    let tmp = temp_dir("direct_no_ranges");
+    let dir = tmp.path().to_path_buf();
+    std::fs::write(dir.join("clip.mp4"), b"keep me").unwrap();
+    std::fs::write(part_path(&dir.join("clip (2).mp4")), b"stale").unwrap();
+
+    let remote = probe(&url, &headers).await.unwrap();
+    assert!(!remote.accepts_ranges);
+    let path = download_direct(&url, &remote, &options(dir.clone(), 4), &headers, Arc::new(|_, _| {}))
+        .await
+        .unwrap();
+    assert_eq!(path, dir.join("clip (2).mp4"));
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert_eq!(std::fs::read(dir.join("clip.mp4")).unwrap(), b"keep me");
+
+    // Larger than the size limit: nothing is downloaded
+    let mut limited = options(dir.clone(), 1);
+    limited.max_filesize = Some(1000);
+    assert!(download_direct(&url, &remote, &limited, &headers, Arc::new(|_, _| {})).await.is_err());
+}
+
+#[tokio::test]
+async fn test_file_replaced_during_the_download_starts_over() {
+    let old = body(MIN_SEGMENT_BYTES as usize * 2 + 123);
+    let new: Vec<u8> = old.iter().map(|byte| byte.wrapping_add(1)).collect();
+    // Replaced on the server right after the probe
+    let (base, requests) = serve_versions(vec![(old, Some("\"v1\"")), (new.clone(), Some("\"v2\""))], true);
+    let url = format!("{}/clip.mp4", base);
+    let headers = RequestHeaders::default();
+    let tmp = temp_dir("direct_replaced");
+    let dir = tmp.path().to_path_buf();
+
+    let remote = probe(&url, &headers).await.unwrap();
+    assert_eq!(remote.validator.as_deref(), Some("\"v1\""));
+    let path = download_direct(&url, &remote, &options(dir.clone(), 4), &headers, Arc::new(|_, _| {}))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), new);
+    // A segment of the old version at least, then a new probe and both segments again
+    assert!(requests.load(Ordering::SeqCst) >= 5);
+    assert!(!validator_path(&path).exists());
+}
+
+#[tokio::test]
+async fn test_part_file_from_another_version_is_not_resumed() {
+    let data = body(100_000);
+    let (base, _) = serve_versions(vec![(data.clone(), Some("\"v2\""))], true);
+    let url = format!("{}/clip.mp4", base);
+    let headers = RequestHeaders::default();
+    let tmp = temp_dir("direct_other_version");
+    let dir = tmp.path().to_path_buf();
+    let dest = dir.join("clip.mp4");
+    std::fs::write(part_path(&dest), vec![0xAA; 40_000]).unwrap();
+    std::fs::write(validator_path(&dest), "\"v1\"").unwrap();
+
+    let remote = probe(&url, &headers).await.unwrap();
+    let path = download_direct(&url, &remote, &options(dir.clone(), 4), &headers, Arc::new(|_, _| {}))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert!(!validator_path(&path).exists());
+}
@@ -0,0 +1,55 @@
+// tests/download_history_test.rs
+mod common;
+
+use common::{download_map, item, temp_dir};
+use chrono::{Duration, Utc};
+use rustloader::download_history::{append_history, load_history, take_archivable};
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+
+fn finished(name: &str, days_ago: i64) -> DownloadItem {
+    let mut item = item(name);
+    item.mark_completed(None);
+    item.finished_at = Some(Utc::now() - Duration::days(days_ago));
+    item
+}
+
+#[test]
+fn test_only_old_finished_downloads_are_taken() {
+    let old = finished("old", 40);
+    let older = finished("older", 90);
+    let recent = finished("recent", 2);
+    let mut queued = item("queued");
+    queued.added_at = Utc::now() - Duration::days(100);
+
+    let mut downloads = download_map(&[old.clone(), older.clone(), recent.clone(), queued.clone()]);
+    let taken = take_archivable(&mut downloads, Duration::days(30), Utc::now());
+
+    // Oldest first, and the rest stay in the queue
+    let ids: Vec<&str> = taken.iter().map(|item| item.id.as_str()).collect();
+    assert_eq!(ids, vec![older.id.as_str(), old.id.as_str()]);
+    assert_eq!(downloads.len(), 2);
+    assert!(downloads.contains_key(&recent.id));
+    assert_eq!(downloads.get(&queued.id).unwrap().status, DownloadStatus::Queued);
+}
+
+#[test]
+fn test_append_history_replaces_rearchived_downloads() {
+    let tmp = temp_dir("history");
+    let dir = tmp.path().to_path_buf();
+    let path = dir.join("history.sqlite3");
+
+    assert!(load_history(&path).unwrap().is_empty());
+    let first = finished("a", 60);
+    let second = finished("b", 45);
+    append_history(&path, &[first.clone(), second.clone()]).unwrap();
+
+    // Retried after archiving, then archived again
+    let mut again = finished("a", 35);
+    again.id = first.id.clone();
+    append_history(&path, &[again.clone()]).unwrap();
+
+    let history = load_history(&path).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].id, second.id);
+    assert_eq!(history[1].finished_at, again.finished_at);
+}
@@ -0,0 +1,83 @@
+// tests/download_map_test.rs
+mod common;
+
+use common::{download_map, item};
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+use rustloader::download_map::{snapshot_items, DownloadMap};
+use std::sync::{Arc, RwLock};
+
+#[test]
+fn test_snapshot_is_unaffected_by_later_changes() {
+    let mut downloads = download_map(&[item("a"), item("b")]);
+    let snapshot = downloads.snapshot();
+
+    downloads.get_mut("a").unwrap().status = DownloadStatus::Paused;
+    downloads.remove("b");
+
+    assert_eq!(snapshot.len(), 2);
+    assert!(snapshot.iter().all(|item| item.status == DownloadStatus::Queued));
+    assert_eq!(downloads.get("a").unwrap().status, DownloadStatus::Paused);
+    assert!(!downloads.contains_key("b"));
+}
+
+#[test]
+fn test_snapshot_shares_unchanged_downloads() {
+    let mut downloads = download_map(&[item("a"), item("b")]);
+    let first = downloads.snapshot();
+    downloads.get_mut("a").unwrap().progress = 50.0;
+    let second = downloads.snapshot();
+
+    let find = |snapshot: &[Arc<DownloadItem>], id: &str| snapshot.iter().find(|item| item.id == id).cloned().unwrap();
+    assert!(Arc::ptr_eq(&find(&first, "b"), &find(&second, "b")));
+    assert!(!Arc::ptr_eq(&find(&first, "a"), &find(&second, "a")));
+}
+
+#[test]
+fn test_matching_mut_copies_only_matches() {
+    let mut downloads = download_map(&[item("a"), item("b")]);
+    downloads.get_mut("a").unwrap().status = DownloadStatus::Paused;
+    let before = downloads.snapshot();
+
+    let changed: Vec<String> = downloads
+        .matching_mut(|item| item.status == DownloadStatus::Paused)
+        .map(|(id, item)| {
+            item.status = DownloadStatus::Queued;
+            id.clone()
+        })
+        .collect();
+    assert_eq!(changed, ["a"]);
+
+    let after = downloads.snapshot();
+    let find = |snapshot: &[Arc<DownloadItem>], id: &str| snapshot.iter().find(|item| item.id == id).cloned().unwrap();
+    // The download the filter skipped is still shared with the snapshot
+    assert!(Arc::ptr_eq(&find(&before, "b"), &find(&after, "b")));
+    assert!(!Arc::ptr_eq(&find(&before, "a"), &find(&after, "a")));
+    assert_eq!(find(&before, "a").status, DownloadStatus::Paused);
+}
+
+#[test]
+fn test_insert_and_remove_return_items() {
+    let mut downloads = DownloadMap::new();
+    assert!(downloads.is_empty());
+    assert!(downloads.insert("a".to_string(), item("a")).is_none());
+
+    let mut replacement = item("a");
+    replacement.format = "mp3".to_string();
+    let replaced = downloads.insert("a".to_string(), replacement).unwrap();
+    assert_eq!(replaced.format, "mp4");
+    assert_eq!(downloads.len(), 1);
+
+    let removed = downloads.remove("a").unwrap();
+    assert_eq!(removed.format, "mp3");
+    assert!(downloads.get("a").is_none());
+}
+
+#[test]
+fn test_snapshot_items_copies_out_of_lock() {
+    let downloads = RwLock::new(download_map(&[item("a"), item("b"), item("c")]));
+    let mut ids: Vec<String> = snapshot_items(&downloads).into_iter().map(|item| item.id).collect();
+    ids.sort();
+    assert_eq!(ids, ["a", "b", "c"]);
+    // The lock is free again once the snapshot is taken
+    assert!(downloads.try_write().is_ok());
+}
@@ -0,0 +1,53 @@
+// tests/duplicate_naming_test.rs
+mod common;
+
+use chrono::{Local, TimeZone};
+use common::temp_dir;
+use rustloader::dedupe::DuplicateNaming;
+use rustloader::downloader::duplicate_output_path;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn file_name(path: &str) -> String {
+    PathBuf::from(path).file_name().unwrap().to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_counter_naming_picks_next_free_number() {
+    let tmp = temp_dir("naming_counter");
+    let dir = tmp.path();
+    let now = Local::now();
+    let path = |dir: &Path| duplicate_output_path(dir, "mp4", "My Video", DuplicateNaming::Counter, now).unwrap();
+
+    fs::write(dir.join("My Video.mp4"), b"").unwrap();
+    assert_eq!(file_name(&path(dir)), "%(title)s (1).mp4");
+
+    fs::write(dir.join("My Video (1).mp4"), b"").unwrap();
+    fs::write(dir.join("My Video (3).mp4"), b"").unwrap();
+    // Other titles and formats don't count
+    fs::write(dir.join("Other (7).mp4"), b"").unwrap();
+    fs::write(dir.join("My Video (9).mp3"), b"").unwrap();
+    assert_eq!(file_name(&path(dir)), "%(title)s (4).mp4");
+}
+
+#[test]
+fn test_timestamp_and_uploader_naming() {
+    let tmp = temp_dir("naming_other");
+    let dir = tmp.path();
+    let now = Local.with_ymd_and_hms(2024, 1, 31, 18, 45, 0).unwrap();
+
+    let path = duplicate_output_path(dir, "mp3", "Song", DuplicateNaming::Timestamp, now).unwrap();
+    assert_eq!(file_name(&path), "%(title)s (20240131-184500).mp3");
+
+    let path = duplicate_output_path(dir, "mp4", "Song", DuplicateNaming::Uploader, now).unwrap();
+    assert_eq!(file_name(&path), "%(uploader)s - %(title)s.mp4");
+
+    assert!(duplicate_output_path(dir, "exe", "Song", DuplicateNaming::Counter, now).is_err());
+}
+
+#[test]
+fn test_duplicate_naming_parse() {
+    assert_eq!(DuplicateNaming::parse("timestamp").unwrap(), DuplicateNaming::Timestamp);
+    assert_eq!(DuplicateNaming::default(), DuplicateNaming::Counter);
+    assert!(DuplicateNaming::parse("suffix").is_err());
+}
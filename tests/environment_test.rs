@@ -0,0 +1,62 @@
+// tests/environment_test.rs
+use rustloader::environment::{is_ci, is_container_cgroup, RunEnvironment};
+use std::collections::HashMap;
+
+fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let map: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    move |name| map.get(name).cloned()
+}
+
+#[test]
+fn test_is_ci() {
+    assert!(is_ci(vars(&[("CI", "true")])));
+    assert!(is_ci(vars(&[("GITHUB_ACTIONS", "true")])));
+    assert!(is_ci(vars(&[("JENKINS_URL", "https://jenkins.example.com/")])));
+    assert!(!is_ci(vars(&[("CI", "false")])));
+    assert!(!is_ci(vars(&[("CI", "0")])));
+    assert!(!is_ci(vars(&[])));
+}
+
+#[test]
+fn test_is_container_cgroup() {
+    assert!(is_container_cgroup("12:pids:/docker/3f1c2a9e8b7d\n"));
+    assert!(is_container_cgroup("0::/kubepods/besteffort/pod1234/abcd\n"));
+    assert!(!is_container_cgroup("0::/init.scope\n"));
+    assert!(!is_container_cgroup(""));
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn test_has_display() {
+    use rustloader::environment::has_display;
+
+    assert!(has_display(vars(&[("DISPLAY", ":0")])));
+    assert!(has_display(vars(&[("WAYLAND_DISPLAY", "wayland-0")])));
+    assert!(!has_display(vars(&[("DISPLAY", "")])));
+    assert!(!has_display(vars(&[])));
+}
+
+#[test]
+fn test_headless_environments_turn_off_prompts_and_notifications() {
+    let desktop = RunEnvironment { container: false, ci: false, display: true, terminal: true };
+    assert!(desktop.interactive());
+    assert!(desktop.notifications_available());
+    assert!(desktop.color_enabled());
+
+    let container = RunEnvironment { container: true, ..desktop };
+    assert!(!container.interactive());
+    assert!(!container.notifications_available());
+    assert!(!container.color_enabled());
+
+    let ci = RunEnvironment { ci: true, ..desktop };
+    assert!(!ci.interactive());
+    assert!(!ci.color_enabled());
+
+    let piped = RunEnvironment { terminal: false, ..desktop };
+    assert!(!piped.interactive());
+    assert!(piped.notifications_available());
+
+    let ssh = RunEnvironment { display: false, ..desktop };
+    assert!(ssh.interactive());
+    assert!(!ssh.notifications_available());
+}
@@ -0,0 +1,72 @@
+// tests/error_report_test.rs
+use rustloader::download_manager::DownloadItem;
+use rustloader::error::{AppError, ErrorCategory, ErrorReport, NetworkErrorKind};
+use serde_json::json;
+
+#[test]
+fn test_errors_map_to_categories() {
+    let cases = [
+        (AppError::MissingDependency("ffmpeg".to_string()), "missing_dependency", ErrorCategory::Dependency, false),
+        (AppError::ValidationError("bad URL".to_string()), "validation_failed", ErrorCategory::Input, false),
+        (AppError::PathError("not writable".to_string()), "path_error", ErrorCategory::Settings, false),
+        (AppError::DailyLimitExceeded, "daily_limit_exceeded", ErrorCategory::License, false),
+        (AppError::DownloadError("yt-dlp exited".to_string()), "download_failed", ErrorCategory::Download, true),
+        (
+            AppError::NetworkError {
+                kind: NetworkErrorKind::Timeout,
+                message: "timed out".to_string(),
+                retriable: true,
+            },
+            "network_timeout",
+            ErrorCategory::Network,
+            true,
+        ),
+        (
+            AppError::NetworkError {
+                kind: NetworkErrorKind::ContentUnavailable,
+                message: "removed".to_string(),
+                retriable: false,
+            },
+            "content_unavailable",
+            ErrorCategory::Network,
+            false,
+        ),
+    ];
+    for (error, code, category, retriable) in cases {
+        assert_eq!(error.code(), code);
+        assert_eq!(error.category(), category, "{}", code);
+        assert_eq!(error.is_retriable(), retriable, "{}", code);
+    }
+}
+
+#[test]
+fn test_report_serializes_for_the_gui() {
+    let error = AppError::MissingDependency("yt-dlp".to_string());
+    let report = ErrorReport::new(&error).for_item("abc");
+    assert_eq!(
+        serde_json::to_value(&report).unwrap(),
+        json!({
+            "code": "missing_dependency",
+            "category": "dependency",
+            "message": "Missing dependency: yt-dlp",
+            "item_id": "abc",
+            "retriable": false,
+        })
+    );
+}
+
+#[test]
+fn test_failed_download_report_is_cleared_when_requeued() {
+    let mut item = DownloadItem::new("https://example.com/v", "mp4");
+    item.mark_failed(Some("Download error: yt-dlp exited".to_string()));
+    item.error_report = Some(ErrorReport::new(&AppError::DownloadError("yt-dlp exited".to_string())).for_item(&item.id));
+
+    // Older queue files have no report
+    let mut saved = serde_json::to_value(&item).unwrap();
+    saved.as_object_mut().unwrap().remove("error_report");
+    let restored: DownloadItem = serde_json::from_value(saved).unwrap();
+    assert_eq!(restored.error_report, None);
+
+    item.mark_requeued();
+    assert_eq!(item.error_report, None);
+}
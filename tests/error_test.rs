@@ -81,4 +81,42 @@ fn test_from_str_for_app_error() {
         AppError::General(message) => assert_eq!(message, "Test error"),
         _ => panic!("Expected AppError::General"),
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_invalid_input_carries_value_rule_and_suggestion() {
+    let error = AppError::InvalidInput {
+        what: "file name".to_string(),
+        value: "my|video".to_string(),
+        rule: "must not contain '|'".to_string(),
+        suggestion: Some("myvideo".to_string()),
+    };
+    assert_eq!(
+        error.to_string(),
+        "Invalid file name \"my|video\": must not contain '|' (try \"myvideo\")"
+    );
+    assert_eq!(error.suggestion(), Some("myvideo"));
+
+    let error = AppError::InvalidPath {
+        path: "/etc/videos".to_string(),
+        rule: "must not be inside the system directory /etc".to_string(),
+        suggestion: None,
+    };
+    assert_eq!(error.to_string(), "Invalid path \"/etc/videos\": must not be inside the system directory /etc");
+    assert_eq!(error.suggestion(), None);
+    assert_eq!(AppError::ValidationError("Invalid URL".to_string()).suggestion(), None);
+}
+
+#[test]
+fn test_invalid_values_stay_on_one_line() {
+    let error = AppError::InvalidInput {
+        what: "file name".to_string(),
+        value: format!("line\nbreak{}", "x".repeat(200)),
+        rule: "too long".to_string(),
+        suggestion: None,
+    };
+    let message = error.to_string();
+    assert!(!message.contains('\n'));
+    assert!(message.starts_with("Invalid file name \"line\\nbreak"));
+    assert!(message.contains("…\": too long"));
+}
@@ -0,0 +1,86 @@
+// tests/feature_gate_test.rs
+use rustloader::cli::build_cli;
+use rustloader::error::AppError;
+use rustloader::feature_gate::{check_free_features, gated_options, RequestedFeatures};
+
+fn requested(args: &[&str]) -> RequestedFeatures {
+    let matches = build_cli().try_get_matches_from(args).unwrap();
+    RequestedFeatures::from_matches(&matches)
+}
+
+#[test]
+fn test_free_options_pass() {
+    for args in [
+        &["rustloader", "https://example.com/v"][..],
+        &["rustloader", "download", "https://example.com/v", "-q", "1080", "--transcode", "hevc"],
+        &["rustloader", "https://example.com/v", "-f", "mp3", "--bitrate", "128K"],
+        // Video bitrates aren't limited
+        &["rustloader", "https://example.com/v", "--bitrate", "8M"],
+    ] {
+        assert!(check_free_features(&requested(args), false).is_ok(), "{:?}", args);
+    }
+}
+
+#[test]
+fn test_all_gated_options_are_reported_together() {
+    let features = requested(&[
+        "rustloader",
+        "download",
+        "https://example.com/v",
+        "-q",
+        "2160",
+        "--transcode",
+        "upscale-2160",
+    ]);
+    let gated = gated_options(&features);
+    assert_eq!(
+        gated.iter().map(|g| g.option.as_str()).collect::<Vec<_>>(),
+        vec!["--quality 2160", "--transcode upscale-2160"]
+    );
+    match check_free_features(&features, false) {
+        Err(AppError::PremiumFeature(message)) => {
+            assert!(message.contains("4K video"));
+            assert!(message.contains("4K upscaling"));
+        }
+        other => panic!("expected a premium feature error, got {:?}", other),
+    }
+    assert!(check_free_features(&features, true).is_ok());
+}
+
+#[test]
+fn test_mp3_bitrate_above_free_limit() {
+    let gated = gated_options(&requested(&["rustloader", "https://example.com/v", "-f", "mp3", "--bitrate", "320K"]));
+    assert_eq!(gated.len(), 1);
+    assert_eq!(gated[0].to_string(), "--bitrate 320K (MP3 above 128K)");
+    assert_eq!(
+        gated_options(&requested(&["rustloader", "https://example.com/v", "-f", "mp3", "--bitrate", "best"])).len(),
+        1
+    );
+    // Invalid values are left to the usual validation
+    assert!(gated_options(&requested(&["rustloader", "https://example.com/v", "-f", "mp3", "--bitrate", "fast"])).is_empty());
+}
+
+#[test]
+fn test_qualities_above_1080_need_pro() {
+    let gated = gated_options(&requested(&["rustloader", "https://example.com/v", "-q", "1440"]));
+    assert_eq!(gated.len(), 1);
+    assert_eq!(gated[0].to_string(), "--quality 1440 (1440p video)");
+    assert!(gated_options(&requested(&["rustloader", "https://example.com/v", "-q", "1080"])).is_empty());
+}
+
+#[test]
+fn test_resolved_options_are_gated() {
+    // A saved default quality and a picked 4K format, with nothing on the command line
+    let resolved = RequestedFeatures {
+        format: Some("mp4".to_string()),
+        quality: Some("2160".to_string()),
+        format_height: Some(2160),
+        ..RequestedFeatures::default()
+    };
+    assert_eq!(
+        gated_options(&resolved).iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec!["--quality 2160 (4K video)", "--interactive 2160p format (4K video)"]
+    );
+    let picked_1080 = RequestedFeatures { format_height: Some(1080), ..RequestedFeatures::default() };
+    assert!(check_free_features(&picked_1080, false).is_ok());
+}
@@ -0,0 +1,59 @@
+// tests/ffmpeg_args_test.rs
+use rustloader::ffmpeg_args::{check_conflicts, join_args, parse_ffmpeg_args, split_args};
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_parse_accepts_filters_with_quotes() {
+    let args = parse_ffmpeg_args(r#"-vf "scale=1280:-2, hflip" -crf 20"#).unwrap();
+    assert_eq!(args, strings(&["-vf", "scale=1280:-2, hflip", "-crf", "20"]));
+
+    // Joining quotes values so yt-dlp splits them back the same way
+    assert_eq!(split_args(&join_args(&args)).unwrap(), args);
+}
+
+#[test]
+fn test_parse_rejects_unsafe_arguments() {
+    // Unknown options, extra inputs/outputs and dangling values
+    assert!(parse_ffmpeg_args("-i /etc/passwd").is_err());
+    assert!(parse_ffmpeg_args("-vf hflip out.mp4").is_err());
+    assert!(parse_ffmpeg_args("-vf -y").is_err());
+    assert!(parse_ffmpeg_args("-vf 'hflip").is_err());
+
+    // Filters that read files or load libraries
+    assert!(parse_ffmpeg_args("-vf movie=/etc/passwd").is_err());
+    assert!(parse_ffmpeg_args("-vf drawtext=textfile=/etc/passwd").is_err());
+    assert!(parse_ffmpeg_args("-vf drawtext=fontfile=/tmp/x.ttf:text=hi").is_err());
+    assert!(parse_ffmpeg_args("-vf subtitles=/etc/passwd").is_err());
+    assert!(parse_ffmpeg_args("-vf hflip,ass=/tmp/x.ass").is_err());
+    assert!(parse_ffmpeg_args("-vf lut3d=/tmp/x.cube").is_err());
+    assert!(parse_ffmpeg_args("-vf frei0r=/tmp/evil.so").is_err());
+    assert!(parse_ffmpeg_args("-af ladspa=file=/tmp/evil.so").is_err());
+    assert!(parse_ffmpeg_args("-filter:a lv2=p=urn:evil").is_err());
+
+    // A quoted separator can't hide a filter behind an allowed one
+    assert!(parse_ffmpeg_args(r#"-vf "scale='1;movie'=/etc/passwd""#).is_err());
+}
+
+#[test]
+fn test_parse_accepts_allowed_filtergraphs() {
+    assert!(parse_ffmpeg_args("-vf [in]scale=640:-2[out]").is_ok());
+    assert!(parse_ffmpeg_args("-af volume=0.5,atempo=1.25").is_ok());
+    assert!(parse_ffmpeg_args("-filter:v transpose=1;hflip").is_ok());
+    // Values of other options aren't filtergraphs
+    assert!(parse_ffmpeg_args("-metadata title=movie").is_ok());
+}
+
+#[test]
+fn test_conflicts_with_generated_arguments() {
+    let trim = strings(&["-ss", "30"]);
+    assert!(check_conflicts(&trim, &[], false).is_ok());
+    assert!(check_conflicts(&trim, &[], true).is_err());
+
+    let bitrate = strings(&["-b:a", "320k"]);
+    let generated = strings(&["-b:a", "128k"]);
+    assert!(check_conflicts(&bitrate, &generated, false).is_err());
+    assert!(check_conflicts(&strings(&["-af", "loudnorm"]), &generated, true).is_ok());
+}
@@ -0,0 +1,56 @@
+// tests/ffmpeg_features_test.rs
+use rustloader::error::AppError;
+use rustloader::ffmpeg_features::{
+    check_feature_support, parse_ffmpeg_version, required_features, unsupported_features, FfmpegFeature, ALL_FEATURES,
+};
+use rustloader::format_selector::VideoCodec;
+use rustloader::transcode::TranscodeKind;
+
+#[test]
+fn test_parse_ffmpeg_version() {
+    assert_eq!(parse_ffmpeg_version("ffmpeg version 4.2.7-0ubuntu0.1 Copyright (c) 2000-2022"), Some("4.2.7".to_string()));
+    assert_eq!(parse_ffmpeg_version("ffmpeg version n6.1.1 Copyright"), Some("6.1.1".to_string()));
+    assert_eq!(parse_ffmpeg_version("ffmpeg version 7.0 Copyright"), Some("7.0".to_string()));
+    // Git snapshots don't carry a release number
+    assert_eq!(parse_ffmpeg_version("ffmpeg version N-109421-g6c3a4e0 Copyright"), None);
+    assert_eq!(parse_ffmpeg_version("not ffmpeg"), None);
+}
+
+#[test]
+fn test_required_features() {
+    assert!(required_features("mp4", false, None, None, &[]).is_empty());
+    assert_eq!(
+        required_features("mp3", true, None, None, &[]),
+        vec![FfmpegFeature::AudioExtraction, FfmpegFeature::TimeRange]
+    );
+    // AV1 is only decoded when the video is re-encoded
+    assert!(required_features("mp4", false, None, Some(VideoCodec::Av1), &[]).is_empty());
+    assert_eq!(
+        required_features("mp4", false, Some(TranscodeKind::Hevc), Some(VideoCodec::Av1), &[]),
+        vec![FfmpegFeature::HevcTranscode, FfmpegFeature::Av1Decode]
+    );
+    let args = vec!["-af".to_string(), "loudnorm=I=-16".to_string()];
+    assert_eq!(required_features("mp3", false, None, None, &args), vec![FfmpegFeature::AudioExtraction, FfmpegFeature::Loudnorm]);
+}
+
+#[test]
+fn test_feature_support_by_version() {
+    assert_eq!(
+        unsupported_features("4.2.7", ALL_FEATURES),
+        vec![FfmpegFeature::Av1Decode, FfmpegFeature::Loudnorm]
+    );
+    assert!(unsupported_features("6.1", ALL_FEATURES).is_empty());
+
+    // Old versions still handle the features they support
+    assert!(check_feature_support(Some("4.2.7"), &[FfmpegFeature::AudioExtraction]).is_ok());
+    match check_feature_support(Some("4.2.7"), &[FfmpegFeature::AudioExtraction, FfmpegFeature::Loudnorm]) {
+        Err(AppError::MissingDependency(message)) => {
+            assert!(message.contains("4.3.0"));
+            assert!(message.contains("loudnorm"));
+            assert!(message.contains("4.2.7"));
+        }
+        other => panic!("expected a missing dependency error, got {:?}", other),
+    }
+    // An unknown version isn't blocked
+    assert!(check_feature_support(None, ALL_FEATURES).is_ok());
+}
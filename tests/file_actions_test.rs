@@ -0,0 +1,34 @@
+// tests/file_actions_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::download_manager::DownloadItem;
+use rustloader::file_actions::locate_output_file;
+use std::fs;
+
+#[test]
+fn test_locate_output_file_from_template() {
+    let tmp = temp_dir("file_actions");
+    let dir = tmp.path().to_path_buf();
+    fs::write(dir.join("Never Gonna Give You Up.mp4"), b"video").unwrap();
+    fs::write(dir.join("Never Gonna Give You Up.mp3"), b"audio").unwrap();
+
+    let mut item = DownloadItem::builder("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4")
+        .title(Some("Never Gonna Give You Up"))
+        .build();
+
+    // Not finished yet
+    assert!(locate_output_file(&item).is_err());
+
+    item.output_path = Some(dir.join("%(title)s.mp4").to_string_lossy().to_string());
+    let found = locate_output_file(&item).unwrap();
+    assert_eq!(found, dir.join("Never Gonna Give You Up.mp4"));
+
+    // A real file path is returned as-is
+    item.output_path = Some(found.to_string_lossy().to_string());
+    assert_eq!(locate_output_file(&item).unwrap(), found);
+
+    item.title = Some("Another Video".to_string());
+    item.output_path = Some(dir.join("%(title)s.mp4").to_string_lossy().to_string());
+    assert!(locate_output_file(&item).is_err());
+}
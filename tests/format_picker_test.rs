@@ -0,0 +1,76 @@
+// tests/format_picker_test.rs
+use rustloader::format_picker::{check_bitrate_available, parse_formats, FormatInfo};
+use rustloader::utils::Bitrate;
+use serde_json::json;
+
+fn sample_info() -> serde_json::Value {
+    json!({
+        "formats": [
+            {"format_id": "sb0", "ext": "mhtml", "vcodec": "none", "acodec": "none"},
+            {"format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2", "filesize": 3_000_000, "tbr": 129.5},
+            {"format_id": "251", "ext": "webm", "vcodec": "none", "acodec": "opus", "filesize": 3_200_000, "tbr": 135.0},
+            {"format_id": "18", "ext": "mp4", "vcodec": "avc1.42001E", "acodec": "mp4a.40.2", "height": 360, "filesize_approx": 9_000_000},
+            {"format_id": "137", "ext": "mp4", "vcodec": "avc1.640028", "acodec": "none", "height": 1080, "filesize": 50_000_000}
+        ]
+    })
+}
+
+#[test]
+fn test_video_formats_best_first() {
+    let formats = parse_formats(&sample_info(), false);
+    let ids: Vec<&str> = formats.iter().map(|f| f.format_id.as_str()).collect();
+    assert_eq!(ids, vec!["137", "18"]);
+
+    assert_eq!(formats[0].resolution, "1080p");
+    assert_eq!(formats[0].filesize, Some(50_000_000));
+    assert_eq!(formats[1].filesize, Some(9_000_000));
+}
+
+#[test]
+fn test_audio_formats_only() {
+    let formats = parse_formats(&sample_info(), true);
+    let ids: Vec<&str> = formats.iter().map(|f| f.format_id.as_str()).collect();
+    assert_eq!(ids, vec!["251", "140"]);
+    assert_eq!(formats[0].resolution, "audio only");
+}
+
+#[test]
+fn test_selector_merges_video_only_formats() {
+    let formats = parse_formats(&sample_info(), false);
+    assert_eq!(formats[0].selector(), "137+bestaudio/137");
+    assert_eq!(formats[1].selector(), "18");
+}
+
+fn format_with_tbr(format_id: &str, resolution: &str, tbr: Option<f64>) -> FormatInfo {
+    FormatInfo {
+        format_id: format_id.to_string(),
+        ext: "mp4".to_string(),
+        resolution: resolution.to_string(),
+        height: None,
+        width: None,
+        fps: None,
+        vcodec: Some("avc1".to_string()),
+        acodec: None,
+        filesize: None,
+        tbr,
+        vbr: None,
+    }
+}
+
+#[test]
+fn test_check_bitrate_available() {
+    let formats = vec![
+        format_with_tbr("137", "1080p", Some(2600.4)),
+        format_with_tbr("136", "720p", Some(1200.0)),
+    ];
+    assert!(check_bitrate_available(Bitrate::Kbps(2000.0), &formats, false).is_ok());
+    assert!(check_bitrate_available(Bitrate::Best, &formats, false).is_ok());
+
+    let err = check_bitrate_available(Bitrate::Kbps(8000.0), &formats, false).unwrap_err().to_string();
+    assert!(err.contains("2.6M") && err.contains("1080p") && err.contains("format 137"), "{}", err);
+
+    // Without reported bitrates there is nothing to check against
+    let unknown = vec![format_with_tbr("18", "360p", None)];
+    assert!(check_bitrate_available(Bitrate::Kbps(8000.0), &unknown, false).is_ok());
+    assert!(check_bitrate_available(Bitrate::Kbps(8000.0), &[], true).is_ok());
+}
@@ -0,0 +1,54 @@
+// tests/format_selector_test.rs
+use rustloader::format_selector::{AudioQuality, FormatPreferences, FormatSelector, VideoCodec};
+
+#[test]
+fn test_quality_only_matches_legacy_expression() {
+    let selector = FormatSelector::new().with_quality(Some("720"));
+    assert_eq!(
+        selector.expression(),
+        "bestvideo[height<=720]+bestaudio/best[height<=720]/best"
+    );
+    assert!(FormatSelector::new().is_default());
+}
+
+#[test]
+fn test_audio_quality_and_codec_fallbacks() {
+    let selector = FormatSelector::new()
+        .with_quality(Some("1080"))
+        .with_preferences(FormatPreferences {
+            audio_quality: Some(AudioQuality::Medium),
+            video_codec: Some(VideoCodec::Av1),
+        });
+
+    assert_eq!(
+        selector.expression(),
+        "bestvideo[height<=1080][vcodec^=av01]+bestaudio[abr<=128]\
+         /bestvideo[height<=1080][vcodec^=av01]+bestaudio\
+         /bestvideo[height<=1080]+bestaudio[abr<=128]\
+         /bestvideo[height<=1080]+bestaudio\
+         /best[height<=1080]/best"
+    );
+}
+
+#[test]
+fn test_preference_parsing() {
+    assert_eq!(AudioQuality::parse("low").unwrap(), AudioQuality::Low);
+    assert!(AudioQuality::parse("lossless").is_err());
+    assert_eq!(VideoCodec::parse("h264").unwrap(), VideoCodec::Avc1);
+    assert!(VideoCodec::parse("theora").is_err());
+}
+
+#[test]
+fn test_separate_expression_keeps_streams_apart() {
+    let selector = FormatSelector::new()
+        .with_quality(Some("720"))
+        .with_preferences(FormatPreferences {
+            audio_quality: Some(AudioQuality::Low),
+            video_codec: None,
+        });
+    assert_eq!(
+        selector.separate_expression(),
+        "bestvideo[height<=720],bestaudio[abr<=64]/bestaudio"
+    );
+    assert_eq!(FormatSelector::new().separate_expression(), "bestvideo,bestaudio");
+}
@@ -0,0 +1,46 @@
+// tests/gatekeeper_test.rs
+use rustloader::dependency_validator::parse_release_checksum;
+use rustloader::gatekeeper::{blocked_launch_message, parse_codesign_output, SignatureStatus};
+use std::path::Path;
+
+#[test]
+fn test_parse_codesign_output() {
+    assert_eq!(parse_codesign_output(true, ""), SignatureStatus::Valid);
+    assert_eq!(
+        parse_codesign_output(false, "/usr/local/bin/ffmpeg: code object is not signed at all\n"),
+        SignatureStatus::Unsigned
+    );
+    assert_eq!(
+        parse_codesign_output(false, "yt-dlp: invalid signature (code or signature have been modified)\n"),
+        SignatureStatus::Invalid("yt-dlp: invalid signature (code or signature have been modified)".to_string())
+    );
+}
+
+#[test]
+fn test_blocked_launch_message_explains_the_fix() {
+    let path = Path::new("/Users/me/bin/yt-dlp");
+    let message = blocked_launch_message("yt-dlp", path, true, &SignatureStatus::Unsigned);
+    assert!(message.contains("xattr -d com.apple.quarantine \"/Users/me/bin/yt-dlp\""));
+    assert!(message.contains("rustloader install yt-dlp"));
+    // Quarantine is the problem to fix first, so unsigned isn't mentioned
+    assert!(!message.contains("Privacy & Security"));
+
+    let message = blocked_launch_message("ffmpeg", path, false, &SignatureStatus::Unsigned);
+    assert!(message.contains("Privacy & Security"));
+    assert!(!message.contains("xattr"));
+
+    let message = blocked_launch_message("ffmpeg", path, false, &SignatureStatus::Invalid("modified".to_string()));
+    assert!(message.contains("code signature is invalid"));
+}
+
+#[test]
+fn test_parse_release_checksum() {
+    let hash = "a".repeat(64);
+    let other = "b".repeat(64);
+    let sums = format!("{}  yt-dlp\n{}  yt-dlp_macos\n{} *yt-dlp.exe\n", hash, other, hash.to_uppercase());
+    assert_eq!(parse_release_checksum(&sums, "yt-dlp_macos"), Some(other));
+    assert_eq!(parse_release_checksum(&sums, "yt-dlp.exe"), Some(hash.clone()));
+    assert_eq!(parse_release_checksum(&sums, "yt-dlp"), Some(hash));
+    assert_eq!(parse_release_checksum(&sums, "yt-dlp_linux"), None);
+    assert_eq!(parse_release_checksum("short  yt-dlp", "yt-dlp"), None);
+}
@@ -0,0 +1,83 @@
+// tests/guardrails_test.rs
+use rustloader::downloader::{CleanupIntensity, MemoryCleanupPolicy};
+use rustloader::guardrails::{parse_size_limit, preflight_warnings, required_disk_bytes, staged_preflight_warnings, Resources};
+use rustloader::transcode::TranscodeKind;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[test]
+fn test_parse_size_limit() {
+    assert_eq!(parse_size_limit("10G").unwrap(), Some(10 * GIB));
+    assert_eq!(parse_size_limit("500m").unwrap(), Some(500 * 1024 * 1024));
+    assert_eq!(parse_size_limit("1.5G").unwrap(), Some(GIB * 3 / 2));
+    assert_eq!(parse_size_limit("2048").unwrap(), Some(2048));
+    assert_eq!(parse_size_limit("none").unwrap(), None);
+    assert_eq!(parse_size_limit("0").unwrap(), None);
+
+    // Only what yt-dlp itself accepts
+    assert!(parse_size_limit("10GB").is_err());
+    assert!(parse_size_limit("-5G").is_err());
+    assert!(parse_size_limit("big").is_err());
+    assert!(parse_size_limit("").is_err());
+}
+
+#[test]
+fn test_required_disk_includes_transcode_copy() {
+    assert_eq!(required_disk_bytes(GIB, false), 2 * GIB);
+    assert_eq!(required_disk_bytes(GIB, true), 3 * GIB);
+}
+
+#[test]
+fn test_preflight_warnings() {
+    let plenty = Resources { free_disk_bytes: Some(500 * GIB), available_memory_bytes: Some(16 * GIB) };
+    assert!(preflight_warnings(4 * GIB, Some(10 * GIB), Some(TranscodeKind::Hevc), &plenty).is_empty());
+
+    // Over the yt-dlp size limit
+    let warnings = preflight_warnings(12 * GIB, Some(10 * GIB), None, &plenty);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("max_filesize"));
+
+    // Enough disk for the download, not for the transcode copy
+    let tight = Resources { free_disk_bytes: Some(10 * GIB), available_memory_bytes: Some(GIB) };
+    assert_eq!(preflight_warnings(4 * GIB, None, None, &tight).len(), 0);
+    let warnings = preflight_warnings(4 * GIB, None, Some(TranscodeKind::Hevc), &tight);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("transcoding"));
+    assert!(warnings[1].contains("memory"));
+
+    // Unknown resources never warn
+    assert!(preflight_warnings(100 * GIB, None, Some(TranscodeKind::Hevc), &Resources::default()).is_empty());
+}
+
+#[test]
+fn test_staged_download_checks_both_directories() {
+    let roomy = Resources { free_disk_bytes: Some(100 * GIB), available_memory_bytes: None };
+    let tight = Resources { free_disk_bytes: Some(3 * GIB), available_memory_bytes: None };
+
+    // The merge copies only need room in the staging directory
+    assert!(staged_preflight_warnings(2 * GIB, None, None, &roomy, &tight).is_empty());
+
+    let warnings = staged_preflight_warnings(2 * GIB, None, None, &tight, &roomy);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("staging directory"));
+
+    let full = Resources { free_disk_bytes: Some(GIB), available_memory_bytes: None };
+    let warnings = staged_preflight_warnings(2 * GIB, None, None, &roomy, &full);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("download directory"));
+}
+
+#[test]
+fn test_memory_cleanup_policy() {
+    let policy = MemoryCleanupPolicy::default();
+    assert_eq!(policy.intensity(30), CleanupIntensity::Low);
+    assert_eq!(policy.intensity(1200), CleanupIntensity::Medium);
+    assert_eq!(policy.intensity(7200), CleanupIntensity::High);
+    assert_eq!(policy.capacity_threshold(CleanupIntensity::High), 10);
+    assert_eq!(policy.capacity_threshold(CleanupIntensity::Low), 30);
+
+    let parsed: MemoryCleanupPolicy = serde_json::from_str(r#"{"interval_secs": 0, "speed_sample_limit": 0}"#).unwrap();
+    assert_eq!(parsed.interval_secs, 0);
+    assert_eq!(parsed.sample_limit(), 1);
+    assert_eq!(parsed.high_after_secs, 3600);
+}
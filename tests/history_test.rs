@@ -0,0 +1,114 @@
+// tests/history_test.rs
+mod common;
+
+use common::temp_dir;
+use chrono::{Duration, Utc};
+use rustloader::download_manager::DownloadItem;
+use rustloader::history::{History, HistoryEntry};
+
+fn completed(title: &str, days_ago: i64) -> DownloadItem {
+    let mut item = DownloadItem::new(&format!("https://example.com/{}", title), "mp4");
+    item.title = Some(title.to_string());
+    item.total_bytes = 1024;
+    item.mark_completed(Some(format!("/videos/{}.mp4", title)));
+    item.finished_at = Some(Utc::now() - Duration::days(days_ago));
+    item
+}
+
+#[test]
+fn test_only_completed_and_failed_downloads_are_recorded() {
+    let queued = DownloadItem::new("https://example.com/queued", "mp4");
+    assert!(HistoryEntry::from_item(&queued).is_none());
+    let mut canceled = queued.clone();
+    canceled.cancel();
+    assert!(HistoryEntry::from_item(&canceled).is_none());
+
+    let mut failed = DownloadItem::new("https://example.com/failed", "mp3");
+    failed.mark_failed(Some("HTTP Error 403".to_string()));
+    let entry = HistoryEntry::from_item(&failed).unwrap();
+    assert_eq!(entry.status, "failed");
+    assert_eq!(entry.error.as_deref(), Some("HTTP Error 403"));
+    assert_eq!(entry.file_size, None);
+    assert_eq!(entry.display_name(), "https://example.com/failed");
+}
+
+#[test]
+fn test_record_and_list_newest_first() {
+    let tmp = temp_dir("history_list");
+
+    let path = tmp.path().join("history.sqlite3");
+    let history = History::open(&path).unwrap();
+    let old = HistoryEntry::from_item(&completed("old", 3)).unwrap();
+    let new = HistoryEntry::from_item(&completed("new", 1)).unwrap();
+    history.record(&old).unwrap();
+    history.record(&new).unwrap();
+
+    // Kept across reopening
+    let history = History::open(&path).unwrap();
+    let entries = history.list(10).unwrap();
+    assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec![new.id.as_str(), old.id.as_str()]);
+    assert_eq!(entries[0].file_size, Some(1024));
+    assert_eq!(entries[0].output_path.as_deref(), Some("/videos/new.mp4"));
+    assert_eq!(entries[0].finished_at.timestamp_millis(), new.finished_at.timestamp_millis());
+    assert_eq!(history.list(1).unwrap().len(), 1);
+
+    // Recording a retried download replaces its entry
+    let mut retried = old.clone();
+    retried.status = "failed".to_string();
+    history.record(&retried).unwrap();
+    assert_eq!(history.count().unwrap(), 2);
+}
+
+#[test]
+fn test_search_matches_literally_and_ignores_case() {
+    let tmp = temp_dir("history_search");
+
+    let path = tmp.path().join("history.sqlite3");
+    let history = History::open(&path).unwrap();
+    for title in ["Rust Tutorial", "Cooking 100%", "cooking_show"] {
+        history.record(&HistoryEntry::from_item(&completed(title, 1)).unwrap()).unwrap();
+    }
+    assert_eq!(history.search("rust", 10).unwrap().len(), 1);
+    assert_eq!(history.search("COOKING", 10).unwrap().len(), 2);
+    // Wildcards are plain text
+    assert_eq!(history.search("100%", 10).unwrap().len(), 1);
+    assert_eq!(history.search("g_s", 10).unwrap().len(), 1);
+    assert_eq!(history.search("%", 10).unwrap().len(), 1);
+    assert!(history.search("  ", 10).is_err());
+}
+
+#[test]
+fn test_clear_older_than_or_everything() {
+    let tmp = temp_dir("history_clear");
+
+    let path = tmp.path().join("history.sqlite3");
+    let history = History::open(&path).unwrap();
+    for (title, days_ago) in [("a", 40), ("b", 10), ("c", 1)] {
+        history.record(&HistoryEntry::from_item(&completed(title, days_ago)).unwrap()).unwrap();
+    }
+    assert_eq!(history.clear(Some(Utc::now() - Duration::days(30))).unwrap(), 1);
+    assert_eq!(history.count().unwrap(), 2);
+    assert_eq!(history.clear(None).unwrap(), 2);
+    assert!(history.list(10).unwrap().is_empty());
+}
+
+#[test]
+fn test_archived_downloads_share_the_database() {
+    let tmp = temp_dir("history_archive");
+
+    let path = tmp.path().join("history.sqlite3");
+    let history = History::open(&path).unwrap();
+    let done = completed("done", 40);
+    history.record(&HistoryEntry::from_item(&done).unwrap()).unwrap();
+    let mut canceled = DownloadItem::new("https://example.com/canceled", "mp4");
+    canceled.cancel();
+    history.archive(&[done.clone(), canceled.clone()]).unwrap();
+
+    // Archiving a recorded download keeps one entry for it
+    let archived = history.archived().unwrap();
+    assert_eq!(archived.len(), 2);
+    assert_eq!(history.count().unwrap(), 1);
+    // Canceled downloads are only kept for the archive
+    assert_eq!(history.list(10).unwrap()[0].id, done.id);
+    assert!(history.search("canceled", 10).unwrap().is_empty());
+}
@@ -0,0 +1,37 @@
+// tests/http_headers_test.rs
+use rustloader::http_headers::{HttpHeader, RequestHeaders, DEFAULT_USER_AGENT};
+
+#[test]
+fn test_header_parsing() {
+    let header = HttpHeader::parse("Referer: https://example.com/embed").unwrap();
+    assert_eq!(header.name, "Referer");
+    assert_eq!(header.value, "https://example.com/embed");
+
+    assert!(HttpHeader::parse("no separator").is_err());
+    assert!(HttpHeader::parse("Bad Name: value").is_err());
+    assert!(HttpHeader::parse("X-Test: one\r\nX-Injected: two").is_err());
+}
+
+#[test]
+fn test_request_headers_from_args() {
+    let defaults = RequestHeaders::from_args(None, Vec::new()).unwrap();
+    assert_eq!(defaults.user_agent(), DEFAULT_USER_AGENT);
+    assert_eq!(defaults.ytdlp_args(), vec!["--user-agent", DEFAULT_USER_AGENT]);
+
+    let custom = RequestHeaders::from_args(
+        None,
+        vec!["Referer: https://example.com/", "User-Agent: TestAgent/1.0"],
+    )
+    .unwrap();
+    assert_eq!(custom.user_agent(), "TestAgent/1.0");
+    assert_eq!(
+        custom.ytdlp_args(),
+        vec!["--user-agent", "TestAgent/1.0", "--add-header", "Referer:https://example.com/"]
+    );
+
+    // --user-agent wins over a User-Agent header
+    let explicit = RequestHeaders::from_args(Some("Explicit/2.0"), vec!["User-Agent: TestAgent/1.0"]).unwrap();
+    assert_eq!(explicit.user_agent(), "Explicit/2.0");
+
+    assert!(RequestHeaders::from_args(Some("--exec rm"), Vec::new()).is_err());
+}
@@ -0,0 +1,62 @@
+// tests/init_wizard_test.rs
+use rustloader::config::AppConfig;
+use rustloader::init_wizard::configure;
+use std::io::Cursor;
+
+#[test]
+fn test_enter_keeps_defaults() {
+    let mut input = Cursor::new("\n\n\n\n\n");
+    let mut output = Vec::new();
+
+    let config = configure(&mut input, &mut output, AppConfig::default(), false).unwrap();
+
+    assert_eq!(config.download_dir, None);
+    assert_eq!(config.default_format.as_deref(), Some("mp4"));
+    assert_eq!(config.default_quality.as_deref(), Some("1080"));
+    assert_eq!(config.max_concurrent_downloads, Some(3));
+    assert!(config.notifications_enabled());
+}
+
+#[test]
+fn test_invalid_answers_are_asked_again() {
+    // "ogg" and "0" are rejected before valid answers are given
+    let mut input = Cursor::new("/tmp\nogg\nmp3\n720\n0\n2\nn\n");
+    let mut output = Vec::new();
+
+    let config = configure(&mut input, &mut output, AppConfig::default(), false).unwrap();
+
+    assert_eq!(config.download_dir.as_deref(), Some("/tmp"));
+    assert_eq!(config.default_format.as_deref(), Some("mp3"));
+    assert_eq!(config.default_quality.as_deref(), Some("720"));
+    assert_eq!(config.max_concurrent_downloads, Some(2));
+    assert!(!config.notifications_enabled());
+
+    let transcript = String::from_utf8(output).unwrap();
+    assert!(transcript.contains("Please enter one of: mp4, mp3"));
+    assert!(transcript.contains("Please enter a number from 1 to 10"));
+}
+
+#[test]
+fn test_end_of_input_cancels_setup() {
+    let mut input = Cursor::new("\n");
+    let mut output = Vec::new();
+    assert!(configure(&mut input, &mut output, AppConfig::default(), false).is_err());
+}
+
+#[test]
+fn test_pro_qualities_are_only_offered_with_pro() {
+    let mut input = Cursor::new("\nmp4\n2160\n1080\n\n\n");
+    let mut output = Vec::new();
+    let config = configure(&mut input, &mut output, AppConfig::default(), false).unwrap();
+    assert_eq!(config.default_quality.as_deref(), Some("1080"));
+    assert!(!String::from_utf8(output).unwrap().contains("2160"));
+
+    let mut input = Cursor::new("\nmp4\n2160\n\n\n");
+    let config = configure(&mut input, &mut Vec::new(), AppConfig::default(), true).unwrap();
+    assert_eq!(config.default_quality.as_deref(), Some("2160"));
+
+    // A Pro default saved earlier isn't kept once the license is gone
+    let saved = AppConfig { default_quality: Some("1440".to_string()), ..AppConfig::default() };
+    let config = configure(&mut Cursor::new("\n\n\n\n\n"), &mut Vec::new(), saved, false).unwrap();
+    assert_eq!(config.default_quality.as_deref(), Some("1080"));
+}
@@ -0,0 +1,45 @@
+// tests/instance_lock_test.rs
+mod common;
+
+use common::{temp_dir, EnvGuard};
+use rustloader::instance_lock::{acquire_instance_lock, other_instance, try_lock_in, InstanceKind};
+use rustloader::paths::DATA_DIR_ENV;
+
+#[test]
+fn test_second_lock_reports_holder() {
+    let tmp = temp_dir("lock_test");
+    let dir = tmp.path().to_path_buf();
+
+    let first = try_lock_in(&dir, InstanceKind::Gui).unwrap().expect("first lock should succeed");
+
+    let holder = try_lock_in(&dir, InstanceKind::Cli)
+        .unwrap()
+        .expect_err("second lock should fail")
+        .expect("holder details should be recorded");
+    assert_eq!(holder.kind, InstanceKind::Gui);
+    assert_eq!(holder.pid, std::process::id());
+    assert!(holder.describe().contains("GUI"));
+
+    // Releasing the lock lets the next process take over
+    drop(first);
+    assert!(try_lock_in(&dir, InstanceKind::Cli).unwrap().is_ok());
+}
+
+#[test]
+fn test_retrying_takes_the_queue_over() {
+    let tmp = temp_dir("lock_retry");
+    let env = EnvGuard::lock().set(DATA_DIR_ENV, tmp.path());
+
+    let cli = try_lock_in(tmp.path(), InstanceKind::Cli).unwrap().unwrap();
+    assert!(acquire_instance_lock(InstanceKind::Gui).unwrap().is_none());
+    assert_eq!(other_instance().unwrap().unwrap().kind, InstanceKind::Cli);
+
+    // Once the CLI run ends, the next attempt owns the queue
+    drop(cli);
+    let gui = acquire_instance_lock(InstanceKind::Gui).unwrap();
+    assert!(gui.is_some());
+    assert!(other_instance().is_none());
+
+    drop(gui);
+    drop(env);
+}
@@ -0,0 +1,71 @@
+// tests/ipc_test.rs
+mod common;
+
+use chrono::Utc;
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+use rustloader::download_map::DownloadMap;
+use rustloader::ipc::{encode_event, event_stream_path, spawn_event_stream};
+use rustloader::paths::DATA_DIR_ENV;
+use rustloader::queue_events::QueueEvent;
+use std::sync::{Arc, RwLock};
+
+#[test]
+fn test_encode_event() {
+    let line = encode_event(&QueueEvent::Removed {
+        timestamp: Utc::now(),
+        id: "dl_1".to_string(),
+    })
+    .unwrap();
+    assert!(line.ends_with('\n'));
+    assert_eq!(line.matches('\n').count(), 1);
+    let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(value["event"], "removed");
+    assert_eq!(value["id"], "dl_1");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stream_over_socket() {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let tmp = common::temp_dir("ipc");
+    let data_dir = tmp.path().to_path_buf();
+    // The event stream reads the data directory from its own task
+    let _env = common::EnvGuard::lock().set(DATA_DIR_ENV, &data_dir);
+    let path = event_stream_path().unwrap();
+    assert_eq!(path, data_dir.join("events.sock"));
+
+    let mut item = DownloadItem::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4");
+    item.status = DownloadStatus::Downloading;
+    let id = item.id.clone();
+    let downloads = Arc::new(RwLock::new(DownloadMap::from_iter([(id.clone(), item)])));
+    let (notify_tx, notify_rx) = tokio::sync::broadcast::channel(16);
+    spawn_event_stream(Arc::clone(&downloads), notify_rx);
+
+    let mut socket = None;
+    for _ in 0..50 {
+        if let Ok(stream) = UnixStream::connect(&path).await {
+            socket = Some(stream);
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let mut lines = BufReader::new(socket.expect("event socket")).lines();
+
+    // The current state comes first
+    let first: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(first["event"], "state_changed");
+    assert_eq!(first["id"], id.as_str());
+    assert_eq!(first["to"], "Downloading");
+
+    downloads.write().unwrap().get_mut(&id).unwrap().status = DownloadStatus::Completed;
+    notify_tx.send(()).unwrap();
+    loop {
+        let event: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        if event["event"] == "state_changed" {
+            assert_eq!(event["to"], "Completed");
+            break;
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// tests/license_test.rs
+use chrono::{Duration, TimeZone, Utc};
+use rustloader::license::{mask_license_key, LicenseState, LicenseStatus, LicenseTier, TrialInfo, TRIAL_DAYS};
+
+#[test]
+fn test_mask_license_key() {
+    assert_eq!(mask_license_key("PRO-ABCD-EFGH-1234"), "PRO-****-****-1234");
+    assert_eq!(mask_license_key("PRO-ABCDEFGH-XY"), "PRO-****-XY");
+    assert_eq!(mask_license_key("NODASHES"), "********");
+}
+
+#[test]
+fn test_state_from_free_and_invalid() {
+    let free = LicenseState::from_status(&LicenseStatus::Free);
+    assert_eq!(free.tier, LicenseTier::Free);
+    assert!(free.features.is_empty());
+    assert!(free.email.is_none());
+
+    let invalid = LicenseState::from_status(&LicenseStatus::Invalid("expired".to_string()));
+    assert_eq!(invalid.tier, LicenseTier::Invalid);
+    assert_eq!(invalid.reason.as_deref(), Some("expired"));
+
+    let json = serde_json::to_value(&invalid).unwrap();
+    assert_eq!(json["tier"], "invalid");
+}
+
+#[test]
+fn test_trial_countdown() {
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+    let trial = TrialInfo::new(start, "machine");
+    assert_eq!(trial.expires_at, start + Duration::days(TRIAL_DAYS));
+    assert_eq!(trial.days_left_at(start), TRIAL_DAYS);
+    // A partial day still counts
+    assert_eq!(trial.days_left_at(trial.expires_at - Duration::hours(1)), 1);
+    assert_eq!(trial.days_left_at(trial.expires_at), 0);
+    assert_eq!(trial.days_left_at(trial.expires_at + Duration::days(3)), 0);
+}
+
+#[test]
+fn test_trial_detects_clock_rollback() {
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+    let mut trial = TrialInfo::new(start, "machine");
+    assert!(trial.check_clock_at(start + Duration::days(3)).is_ok());
+    assert!(trial.check_clock_at(start - Duration::hours(1)).is_err());
+
+    // Going back from the last use, but not before the start, is caught too
+    trial.last_seen = Some(start + Duration::days(10));
+    assert!(trial.check_clock_at(start + Duration::days(2)).is_err());
+    // Small corrections like a time sync are tolerated
+    assert!(trial.check_clock_at(start + Duration::days(10) - Duration::minutes(1)).is_ok());
+
+    // Records from before last-seen tracking still load
+    let json = serde_json::to_value(&trial).unwrap();
+    let mut old = json.as_object().unwrap().clone();
+    old.remove("last_seen");
+    let old: TrialInfo = serde_json::from_value(old.into()).unwrap();
+    assert_eq!(old.last_seen, None);
+}
+
+#[test]
+fn test_state_from_trial() {
+    let trial = TrialInfo::new(Utc::now(), "machine");
+    let state = LicenseState::from_status(&LicenseStatus::Trial(trial.clone()));
+    assert_eq!(state.tier, LicenseTier::Trial);
+    assert_eq!(state.expires_at, Some(trial.expires_at));
+    assert_eq!(state.trial_days_left, Some(TRIAL_DAYS));
+    assert!(!state.features.is_empty());
+}
@@ -0,0 +1,138 @@
+// tests/media_naming_test.rs
+mod common;
+
+use common::temp_dir;
+
+use rustloader::media_naming::{
+    detect_episode, episode_file_name, episode_nfo, rename_download, EpisodeInfo, MediaMetadata, MediaNaming,
+};
+use std::fs;
+
+fn titled(title: &str) -> MediaMetadata {
+    MediaMetadata {
+        id: "abc123".to_string(),
+        title: title.to_string(),
+        ..MediaMetadata::default()
+    }
+}
+
+fn episode(show: &str, season: u32, episode: u32, title: &str) -> EpisodeInfo {
+    EpisodeInfo {
+        show: show.to_string(),
+        season,
+        episode,
+        title: title.to_string(),
+    }
+}
+
+#[test]
+fn test_detects_series_metadata() {
+    let metadata = MediaMetadata {
+        series: Some("The Show".to_string()),
+        season_number: Some(2),
+        episode_number: Some(7),
+        episode: Some("The Return".to_string()),
+        ..titled("The Show 2x07 full episode")
+    };
+    assert_eq!(detect_episode(&metadata), Some(episode("The Show", 2, 7, "The Return")));
+}
+
+#[test]
+fn test_detects_patterns_in_title() {
+    assert_eq!(detect_episode(&titled("My Show - S01E03 - Pilot")), Some(episode("My Show", 1, 3, "Pilot")));
+    assert_eq!(detect_episode(&titled("My Show s1e3")), Some(episode("My Show", 1, 3, "Episode 3")));
+    assert_eq!(detect_episode(&titled("Cooking 4x12: Bread")), Some(episode("Cooking", 4, 12, "Bread")));
+    assert_eq!(
+        detect_episode(&titled("Garden Time Season 3 Episode 10 | Roses")),
+        Some(episode("Garden Time", 3, 10, "Roses"))
+    );
+
+    // Without a show name in the title, the channel stands in
+    let metadata = MediaMetadata {
+        channel: Some("Garden Channel".to_string()),
+        ..titled("S02E05 Tulips")
+    };
+    assert_eq!(detect_episode(&metadata), Some(episode("Garden Channel", 2, 5, "Tulips")));
+}
+
+#[test]
+fn test_detects_playlist_position() {
+    let metadata = MediaMetadata {
+        playlist_title: Some("Rust Course".to_string()),
+        playlist_index: Some(4),
+        ..titled("Ownership explained")
+    };
+    assert_eq!(detect_episode(&metadata), Some(episode("Rust Course", 1, 4, "Ownership explained")));
+}
+
+#[test]
+fn test_no_episode_without_pattern_or_playlist() {
+    assert_eq!(detect_episode(&titled("Cat plays piano")), None);
+    assert_eq!(detect_episode(&titled("1920x1080 test pattern")), None);
+    // A pattern with no show name anywhere isn't enough
+    assert_eq!(detect_episode(&titled("S01E01")), None);
+}
+
+#[test]
+fn test_episode_file_name() {
+    assert_eq!(episode_file_name(&episode("My Show", 1, 3, "Pilot"), "mp4"), "My Show - S01E03 - Pilot.mp4");
+    assert_eq!(
+        episode_file_name(&episode("What/If?", 10, 120, "A: \"B\"  ."), "mkv"),
+        "What_If_ - S10E120 - A_ _B_.mkv"
+    );
+}
+
+#[test]
+fn test_episode_nfo() {
+    let metadata = MediaMetadata {
+        upload_date: Some("20240102".to_string()),
+        description: Some("Tom & Jerry <3".to_string()),
+        ..titled("x")
+    };
+    let nfo = episode_nfo(&episode("Show", 1, 2, "Title"), &metadata);
+    assert!(nfo.starts_with("<?xml"));
+    assert!(nfo.contains("<showtitle>Show</showtitle>"));
+    assert!(nfo.contains("<season>1</season>"));
+    assert!(nfo.contains("<episode>2</episode>"));
+    assert!(nfo.contains("<aired>2024-01-02</aired>"));
+    assert!(nfo.contains("<plot>Tom &amp; Jerry &lt;3</plot>"));
+
+    let nfo = episode_nfo(&episode("Show", 1, 2, "Title"), &titled("x"));
+    assert!(!nfo.contains("<aired>"));
+    assert!(!nfo.contains("<plot>"));
+}
+
+#[test]
+fn test_metadata_from_ytdlp_json() {
+    let json = r#"{"id": "abc123", "title": "Show S01E02", "series": null, "playlist_index": 3, "duration": 60}"#;
+    let metadata: MediaMetadata = serde_json::from_str(json).unwrap();
+    assert_eq!(metadata.id, "abc123");
+    assert_eq!(metadata.series, None);
+    assert_eq!(metadata.playlist_index, Some(3));
+}
+
+#[test]
+fn test_rename_download() {
+    let tmp = temp_dir("media_naming");
+    let dir = tmp.path().to_path_buf();
+
+    let file = dir.join("abc123.mp4");
+    fs::write(&file, b"video").unwrap();
+    let renamed = rename_download(&file, &titled("My Show S01E03 Pilot"), MediaNaming { nfo: true }).unwrap();
+    assert_eq!(renamed, dir.join("My Show - S01E03 - Pilot.mp4"));
+    assert!(renamed.exists() && !file.exists());
+    assert!(dir.join("My Show - S01E03 - Pilot.nfo").exists());
+
+    // Not an episode: named after the title, without an NFO
+    let other = dir.join("def456.mp4");
+    fs::write(&other, b"video").unwrap();
+    let renamed = rename_download(&other, &titled("Cat plays piano"), MediaNaming { nfo: true }).unwrap();
+    assert_eq!(renamed, dir.join("Cat plays piano.mp4"));
+    assert!(!dir.join("Cat plays piano.nfo").exists());
+
+    // Never overwrites
+    let again = dir.join("ghi789.mp4");
+    fs::write(&again, b"video").unwrap();
+    assert!(rename_download(&again, &titled("Cat plays piano"), MediaNaming::default()).is_err());
+    assert!(again.exists());
+}
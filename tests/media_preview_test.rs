@@ -0,0 +1,73 @@
+// tests/media_preview_test.rs
+use rustloader::media_preview::{preview_from_info, warning_from_error, PreviewWarning, QualityEstimate};
+use rustloader::metadata_cache::MetadataCache;
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn test_preview_summarizes_formats_and_sizes() {
+    let info = json!({
+        "title": "Example",
+        "uploader": "Channel",
+        "duration": 120.0,
+        "upload_date": "20240131",
+        "thumbnail": "https://example.com/thumb.jpg",
+        "age_limit": 18,
+        "chapters": [{"title": "Intro"}, {"title": "Main"}],
+        "formats": [
+            {"format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2", "filesize": 1000},
+            {"format_id": "18", "ext": "mp4", "vcodec": "avc1", "acodec": "mp4a", "height": 360, "filesize": 5000},
+            {"format_id": "136", "ext": "mp4", "vcodec": "avc1", "acodec": "none", "height": 720, "filesize": 20000},
+            {"format_id": "248", "ext": "webm", "vcodec": "vp9", "acodec": "none", "height": 1080, "filesize_approx": 40000}
+        ]
+    });
+
+    let preview = preview_from_info("https://example.com/v", &info);
+    assert!(preview.available);
+    assert_eq!(preview.upload_date.as_deref(), Some("2024-01-31"));
+    assert_eq!(preview.chapter_count, 2);
+    assert_eq!(preview.warnings, vec![PreviewWarning::AgeRestricted]);
+    assert_eq!(preview.formats.video_formats, 3);
+    assert_eq!(preview.formats.audio_formats, 1);
+    assert_eq!(preview.formats.max_height, Some(1080));
+    assert_eq!(preview.formats.containers, vec!["mp4", "webm"]);
+
+    // Video-only formats include the best audio; nothing above 1080p is offered
+    assert_eq!(
+        preview.size_estimates,
+        vec![
+            QualityEstimate { quality: 360, bytes: Some(5000) },
+            QualityEstimate { quality: 480, bytes: Some(5000) },
+            QualityEstimate { quality: 720, bytes: Some(21000) },
+            QualityEstimate { quality: 1080, bytes: Some(41000) },
+        ]
+    );
+}
+
+#[test]
+fn test_warning_from_error() {
+    assert_eq!(
+        warning_from_error("ERROR: [youtube] abc: The uploader has not made this video available in your country"),
+        Some(PreviewWarning::RegionBlocked)
+    );
+    assert_eq!(
+        warning_from_error("ERROR: Sign in to confirm your age. This video may be inappropriate for some users."),
+        Some(PreviewWarning::AgeRestricted)
+    );
+    assert_eq!(warning_from_error("ERROR: Unable to download webpage: HTTP Error 500"), None);
+}
+
+#[test]
+fn test_metadata_cache_expiry_and_capacity() {
+    let cache = MetadataCache::new(Duration::from_secs(60), 2);
+    cache.insert("a", 1);
+    std::thread::sleep(Duration::from_millis(5));
+    cache.insert("b", 2);
+    cache.insert("c", 3);
+    assert_eq!(cache.get("a"), None);
+    assert_eq!(cache.get("c"), Some(3));
+
+    let expired = MetadataCache::new(Duration::ZERO, 2);
+    expired.insert("a", 1);
+    assert_eq!(expired.get("a"), None);
+}
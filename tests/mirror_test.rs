@@ -0,0 +1,35 @@
+// tests/mirror_test.rs
+use rustloader::download_manager::{should_try_mirror, DownloadItem};
+use rustloader::error::AppError;
+
+#[test]
+fn test_builder_sets_mirrors() {
+    let mirrors = vec![
+        "https://mirror1.example.com/video".to_string(),
+        "https://mirror2.example.com/video".to_string(),
+    ];
+    let item = DownloadItem::builder("https://example.com/video", "mp4").mirrors(&mirrors).build();
+    assert_eq!(item.mirrors, mirrors);
+    assert_eq!(item.downloaded_from, None);
+}
+
+#[test]
+fn test_items_saved_without_mirrors_still_load() {
+    let mut value = serde_json::to_value(DownloadItem::new("https://example.com/video", "mp4")).unwrap();
+    let object = value.as_object_mut().unwrap();
+    object.remove("mirrors");
+    object.remove("downloaded_from");
+
+    let item: DownloadItem = serde_json::from_value(value).unwrap();
+    assert!(item.mirrors.is_empty());
+    assert_eq!(item.downloaded_from, None);
+}
+
+#[test]
+fn test_should_try_mirror() {
+    assert!(should_try_mirror(&AppError::DownloadError("HTTP Error 404".to_string())));
+    assert!(should_try_mirror(&AppError::General("yt-dlp exited with status 1".to_string())));
+    assert!(!should_try_mirror(&AppError::ValidationError("Invalid URL".to_string())));
+    assert!(!should_try_mirror(&AppError::DailyLimitExceeded));
+    assert!(!should_try_mirror(&AppError::MissingDependency("ffmpeg".to_string())));
+}
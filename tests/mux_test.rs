@@ -0,0 +1,53 @@
+// tests/mux_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::mux::{container_for, default_output, mux, mux_command};
+use std::path::Path;
+
+#[test]
+fn test_container_keeps_codecs_compatible() {
+    assert_eq!(container_for(Path::new("a.f137.mp4"), Path::new("a.f140.m4a")), "mp4");
+    assert_eq!(container_for(Path::new("a.f248.webm"), Path::new("a.f251.webm")), "webm");
+    assert_eq!(container_for(Path::new("a.f248.webm"), Path::new("a.f140.m4a")), "mkv");
+}
+
+#[test]
+fn test_default_output_drops_format_suffix() {
+    assert_eq!(
+        default_output(Path::new("/dl/Some Title.f137.mp4"), Path::new("/dl/Some Title.f140.m4a")),
+        Path::new("/dl/Some Title.mp4")
+    );
+    // Titles that merely contain ".f" keep their name
+    assert_eq!(
+        default_output(Path::new("/dl/Trip.final.webm"), Path::new("/dl/audio.m4a")),
+        Path::new("/dl/Trip.final.mkv")
+    );
+}
+
+#[test]
+fn test_mux_command_copies_streams() {
+    let args = mux_command(Path::new("/dl/v.mp4"), Path::new("/dl/a.m4a"), Path::new("/dl/out.mp4"))
+        .to_args()
+        .unwrap();
+    assert_eq!(
+        args,
+        [
+            "-hide_banner", "-loglevel", "error", "-n",
+            "-i", "/dl/v.mp4", "-i", "/dl/a.m4a",
+            "-map", "0:v:0", "-map", "1:a:0", "-c", "copy",
+            "/dl/out.mp4",
+        ]
+    );
+}
+
+#[test]
+fn test_mux_rejects_missing_inputs() {
+    let tmp = temp_dir("mux");
+    let dir = tmp.path().to_path_buf();
+    let video = dir.join("video.f137.mp4");
+    std::fs::write(&video, b"video").unwrap();
+
+    assert!(mux(&video, &dir.join("missing.m4a"), None).is_err());
+    assert!(mux(&video, &video, None).is_err());
+}
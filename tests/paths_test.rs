@@ -0,0 +1,47 @@
+// tests/paths_test.rs
+mod common;
+
+use common::{temp_dir, EnvGuard};
+use rustloader::downloader::get_counter_path;
+use rustloader::paths::{config_dir, data_dir, dir_from_env, CONFIG_DIR_ENV, DATA_DIR_ENV};
+
+#[test]
+fn test_dir_from_env_ignores_relative_paths() {
+    let var = "RUSTLOADER_PATHS_TEST_DIR";
+    let env = EnvGuard::lock().set(var, "relative/dir");
+    assert_eq!(dir_from_env(var), None);
+
+    let absolute = std::env::temp_dir().join("rustloader-paths-test");
+    let env = env.set(var, &absolute);
+    assert_eq!(dir_from_env(var), Some(absolute));
+
+    drop(env);
+    assert_eq!(dir_from_env(var), None);
+}
+
+#[test]
+fn test_env_overrides_config_and_data_dirs() {
+    let tmp = temp_dir("paths");
+    let root = tmp.path().to_path_buf();
+    let config = root.join("config");
+    let data = root.join("data");
+    let env = EnvGuard::lock().set(CONFIG_DIR_ENV, &config).set(DATA_DIR_ENV, &data);
+
+    assert_eq!(config_dir().unwrap(), config);
+    assert_eq!(data_dir().unwrap(), data);
+    assert!(config.is_dir());
+    assert!(data.is_dir());
+
+    drop(env);
+}
+
+#[test]
+fn test_download_counter_ignores_data_dir_override() {
+    let tmp = temp_dir("paths-counter");
+    let env = EnvGuard::lock().set(DATA_DIR_ENV, tmp.path());
+
+    // Pointing the data dir at an empty folder mustn't reset the daily limit
+    assert!(!get_counter_path().unwrap().starts_with(tmp.path()));
+
+    drop(env);
+}
@@ -0,0 +1,57 @@
+// tests/player_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::download_manager::DownloadItem;
+use rustloader::player::{find_player, playable_file, MIN_PLAYABLE_BYTES};
+use std::fs;
+
+fn downloading(dir: &std::path::Path) -> DownloadItem {
+    let mut item = DownloadItem::new("https://example.com/watch/abc", "mp4");
+    item.title = Some("Growing Video".to_string());
+    item.output_dir = Some(dir.to_string_lossy().into_owned());
+    item.streaming = true;
+    item.mark_started();
+    item
+}
+
+#[test]
+fn test_rejects_a_missing_player() {
+    assert!(find_player(Some("/nonexistent/bin/mpv")).is_err());
+    assert!(find_player(Some("rustloader-no-such-player")).is_err());
+}
+
+#[test]
+fn test_accepts_a_player_given_by_path() {
+    let tmp = temp_dir("player_path");
+    let dir = tmp.path().to_path_buf();
+    let player = dir.join("myplayer");
+    fs::write(&player, b"").unwrap();
+    assert_eq!(find_player(player.to_str()).unwrap(), player);
+}
+
+#[test]
+fn test_waits_for_enough_of_the_partial_file() {
+    let tmp = temp_dir("player_partial");
+    let dir = tmp.path().to_path_buf();
+    let item = downloading(&dir);
+    assert_eq!(playable_file(&item), None);
+
+    let partial = dir.join("Growing Video.mp4.part");
+    fs::write(&partial, vec![0u8; 1024]).unwrap();
+    assert_eq!(playable_file(&item), None);
+
+    fs::write(&partial, vec![0u8; MIN_PLAYABLE_BYTES as usize]).unwrap();
+    assert_eq!(playable_file(&item), Some(partial));
+}
+
+#[test]
+fn test_plays_the_finished_file_once_completed() {
+    let tmp = temp_dir("player_done");
+    let dir = tmp.path().to_path_buf();
+    let mut item = downloading(&dir);
+    let finished = dir.join("Growing Video.mp4");
+    fs::write(&finished, b"video").unwrap();
+    item.mark_completed(Some(finished.to_string_lossy().into_owned()));
+    assert_eq!(playable_file(&item), Some(finished));
+}
@@ -0,0 +1,62 @@
+// tests/playlist_test.rs
+use rustloader::playlist::{parse_flat_playlist, PlaylistEntry};
+use rustloader::process::CommandSpec;
+use serde_json::json;
+
+#[test]
+fn test_flat_playlist_entries() {
+    let info = json!({
+        "_type": "playlist",
+        "title": "Course",
+        "entries": [
+            {"_type": "url", "ie_key": "Youtube", "id": "aaaaaaaaaaa", "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa", "title": "Intro"},
+            {"_type": "url", "ie_key": "Youtube", "id": "bbbbbbbbbbb", "url": "bbbbbbbbbbb", "title": " "},
+            null,
+            {"_type": "url", "ie_key": "Vimeo", "id": "123", "url": "123", "webpage_url": "https://vimeo.com/123", "title": "Lecture"},
+            {"_type": "url", "ie_key": "Generic", "id": "x", "url": "x"},
+            {"_type": "playlist", "title": "Shorts", "url": "https://www.youtube.com/@channel/shorts"},
+        ]
+    });
+    let playlist = parse_flat_playlist(&info);
+    assert_eq!(playlist.title.as_deref(), Some("Course"));
+    assert_eq!(
+        playlist.entries,
+        vec![
+            PlaylistEntry {
+                url: "https://www.youtube.com/watch?v=aaaaaaaaaaa".to_string(),
+                id: Some("aaaaaaaaaaa".to_string()),
+                title: Some("Intro".to_string()),
+            },
+            PlaylistEntry {
+                url: "https://www.youtube.com/watch?v=bbbbbbbbbbb".to_string(),
+                id: Some("bbbbbbbbbbb".to_string()),
+                title: None,
+            },
+            PlaylistEntry {
+                url: "https://vimeo.com/123".to_string(),
+                id: Some("123".to_string()),
+                title: Some("Lecture".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_not_a_playlist() {
+    let playlist = parse_flat_playlist(&json!({"id": "aaaaaaaaaaa", "title": "A video"}));
+    assert!(playlist.entries.is_empty());
+}
+
+#[test]
+fn test_flat_playlist_flags_are_allowed() {
+    let args = CommandSpec::ytdlp()
+        .flag("--flat-playlist")
+        .flag("--dump-single-json")
+        .operand("https://www.youtube.com/playlist?list=PL1")
+        .to_args()
+        .unwrap();
+    assert_eq!(
+        args,
+        vec!["--flat-playlist", "--dump-single-json", "--", "https://www.youtube.com/playlist?list=PL1"]
+    );
+}
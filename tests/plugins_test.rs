@@ -0,0 +1,175 @@
+// tests/plugins_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::plugins::{discover_plugins, Plugin, PluginCapability, PluginManifest, PluginRequest};
+use rustloader::security::{validate_plugin_executable, validate_plugin_output};
+use std::fs;
+use std::path::Path;
+
+fn manifest(capabilities: Vec<PluginCapability>, hosts: &[&str]) -> PluginManifest {
+    PluginManifest {
+        name: "example-provider".to_string(),
+        version: "1.0.0".to_string(),
+        abi: 1,
+        executable: "run.sh".to_string(),
+        capabilities,
+        hosts: hosts.iter().map(|host| host.to_string()).collect(),
+        timeout_secs: None,
+    }
+}
+
+#[cfg(unix)]
+fn install(dir: &Path, manifest_json: &str, script: &str, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::write(dir.join("plugin.json"), manifest_json).unwrap();
+    let script_path = dir.join("run.sh");
+    fs::write(&script_path, script).unwrap();
+    fs::set_permissions(&script_path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn test_manifest_validation() {
+    assert!(manifest(vec![PluginCapability::Provider], &["example.com"]).validate().is_ok());
+    assert!(manifest(vec![PluginCapability::Notifier], &[]).validate().is_ok());
+
+    assert!(manifest(vec![], &[]).validate().is_err());
+    assert!(manifest(vec![PluginCapability::Provider], &[]).validate().is_err());
+    assert!(manifest(vec![PluginCapability::Notifier], &["example.com"]).validate().is_err());
+    assert!(manifest(vec![PluginCapability::Provider], &["*.example.com"]).validate().is_err());
+
+    let mut bad = manifest(vec![PluginCapability::Notifier], &[]);
+    bad.name = "../escape".to_string();
+    assert!(bad.validate().is_err());
+
+    let mut bad = manifest(vec![PluginCapability::Notifier], &[]);
+    bad.abi = 2;
+    assert!(bad.validate().is_err());
+
+    let mut bad = manifest(vec![PluginCapability::Notifier], &[]);
+    bad.timeout_secs = Some(3600);
+    assert!(bad.validate().is_err());
+}
+
+#[test]
+fn test_manifest_rejects_unknown_fields() {
+    let json = r#"{"name":"x","version":"1","abi":1,"executable":"run.sh","capabilities":["notifier"],"network":true}"#;
+    assert!(serde_json::from_str::<PluginManifest>(json).is_err());
+    let json = r#"{"name":"x","version":"1","abi":1,"executable":"run.sh","capabilities":["post-processor"]}"#;
+    let parsed: PluginManifest = serde_json::from_str(json).unwrap();
+    assert!(parsed.has(PluginCapability::PostProcessor));
+}
+
+#[test]
+fn test_handles_url() {
+    let provider = manifest(vec![PluginCapability::Provider], &["Example.com"]);
+    assert!(provider.handles_url("https://example.com/v/1"));
+    assert!(provider.handles_url("https://media.example.com/v/1"));
+    assert!(!provider.handles_url("https://notexample.com/v/1"));
+    assert!(!provider.handles_url("https://example.com.evil.net/v/1"));
+    assert!(!provider.handles_url("not a url"));
+}
+
+#[test]
+fn test_request_serialization() {
+    let request = PluginRequest::Resolve { url: "https://example.com/v/1".to_string() };
+    assert_eq!(
+        serde_json::to_string(&request).unwrap(),
+        r#"{"action":"resolve","url":"https://example.com/v/1"}"#
+    );
+}
+
+#[test]
+fn test_executable_must_stay_in_plugin_dir() {
+    let tmp = temp_dir("plugins_escape");
+    let dir = tmp.path().to_path_buf();
+    assert!(validate_plugin_executable(&dir, "../run.sh").is_err());
+    assert!(validate_plugin_executable(&dir, "/bin/sh").is_err());
+    assert!(validate_plugin_executable(&dir, "").is_err());
+    assert!(validate_plugin_executable(&dir, "missing.sh").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_executable_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmp = temp_dir("plugins_permissions");
+    let dir = tmp.path().to_path_buf();
+    let script = dir.join("run.sh");
+    fs::write(&script, "#!/bin/sh\n").unwrap();
+
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(validate_plugin_executable(&dir, "run.sh").is_ok());
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o777)).unwrap();
+    assert!(validate_plugin_executable(&dir, "run.sh").is_err());
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+    assert!(validate_plugin_executable(&dir, "run.sh").is_err());
+
+    // A symlink out of the plugin folder is judged by its target
+    std::os::unix::fs::symlink("/bin/sh", dir.join("shell")).unwrap();
+    assert!(validate_plugin_executable(&dir, "shell").is_err());
+}
+
+#[test]
+fn test_plugin_output_must_stay_in_download_dir() {
+    let downloads_dir = temp_dir("plugins_output");
+    let downloads = downloads_dir.path().to_path_buf();
+    let file = downloads.join("video.mkv");
+    fs::write(&file, b"").unwrap();
+    assert!(validate_plugin_output(&file, &downloads).is_ok());
+    assert!(validate_plugin_output(&downloads.join("missing.mkv"), &downloads).is_err());
+
+    let outside_dir = temp_dir("plugins_output_outside");
+    let outside = outside_dir.path().join("other.mkv");
+    fs::write(&outside, b"").unwrap();
+    assert!(validate_plugin_output(&outside, &downloads).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_discover_plugins_reports_broken_ones() {
+    let root_dir = temp_dir("plugins_discover");
+    let root = root_dir.path().to_path_buf();
+    let good = root.join("good");
+    let broken = root.join("broken");
+    fs::create_dir_all(&good).unwrap();
+    fs::create_dir_all(&broken).unwrap();
+    fs::create_dir_all(root.join("no-manifest")).unwrap();
+
+    let json = r#"{"name":"good","version":"1","abi":1,"executable":"run.sh","capabilities":["notifier"]}"#;
+    install(&good, json, "#!/bin/sh\n", 0o755);
+    install(&broken, json, "#!/bin/sh\n", 0o666);
+
+    let found = discover_plugins(&root);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].0, broken);
+    assert!(found[0].1.is_err());
+    assert_eq!(found[1].1.as_ref().unwrap().manifest.name, "good");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_call_plugin() {
+    let tmp = temp_dir("plugins_call");
+    let dir = tmp.path().to_path_buf();
+    let json = r#"{"name":"echo","version":"1","abi":1,"executable":"run.sh","capabilities":["provider"],"hosts":["example.com"]}"#;
+    // Only allowlisted variables reach the plugin
+    let script = "#!/bin/sh\nread request\n[ -z \"$RUSTLOADER_PLUGIN_SECRET\" ] || exit 1\necho '{\"ok\":true,\"url\":\"https://cdn.example.com/1.mp4\"}'\n";
+    install(&dir, json, script, 0o755);
+    let _env = common::EnvGuard::lock().set("RUSTLOADER_PLUGIN_SECRET", "hunter2");
+
+    let plugin = Plugin::load(&dir).unwrap();
+    let response = plugin
+        .call(&PluginRequest::Resolve { url: "https://example.com/v/1".to_string() })
+        .await
+        .unwrap();
+    assert_eq!(response.url.as_deref(), Some("https://cdn.example.com/1.mp4"));
+
+    install(&dir, json, "#!/bin/sh\nread request\necho '{\"ok\":false,\"error\":\"nope\"}'\n", 0o755);
+    let plugin = Plugin::load(&dir).unwrap();
+    let error = plugin
+        .call(&PluginRequest::Resolve { url: "https://example.com/v/1".to_string() })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("nope"));
+}
@@ -0,0 +1,38 @@
+// tests/power_test.rs
+use rustloader::power::{sleep_gap, LogindParser, PowerEvent, SLEEP_GAP_THRESHOLD};
+use std::time::Duration;
+
+#[test]
+fn test_sleep_gap() {
+    let monotonic = Duration::from_secs(5);
+    assert_eq!(sleep_gap(Some(Duration::from_secs(5)), monotonic), None);
+    assert_eq!(sleep_gap(Some(Duration::from_secs(7)), monotonic), None);
+    assert_eq!(sleep_gap(Some(monotonic + SLEEP_GAP_THRESHOLD), monotonic), Some(SLEEP_GAP_THRESHOLD));
+    assert_eq!(sleep_gap(Some(Duration::from_secs(3605)), monotonic), Some(Duration::from_secs(3600)));
+    // Wall clock set backwards
+    assert_eq!(sleep_gap(None, monotonic), None);
+    assert_eq!(sleep_gap(Some(Duration::from_secs(1)), monotonic), None);
+}
+
+#[test]
+fn test_logind_parser() {
+    let output = "\
+signal time=1700000000.1 sender=org.freedesktop.DBus -> destination=:1.80 serial=2 path=/org/freedesktop/DBus; interface=org.freedesktop.DBus; member=NameAcquired
+   string \":1.80\"
+signal time=1700000100.2 sender=:1.3 -> destination=(null destination) serial=900 path=/org/freedesktop/login1; interface=org.freedesktop.login1.Manager; member=PrepareForSleep
+   boolean true
+signal time=1700003700.9 sender=:1.3 -> destination=(null destination) serial=901 path=/org/freedesktop/login1; interface=org.freedesktop.login1.Manager; member=PrepareForSleep
+   boolean false
+";
+    let mut parser = LogindParser::default();
+    let events: Vec<PowerEvent> = output.lines().filter_map(|line| parser.feed(line)).collect();
+    assert_eq!(events, vec![PowerEvent::Suspending, PowerEvent::Resumed]);
+}
+
+#[test]
+fn test_logind_parser_ignores_other_booleans() {
+    let mut parser = LogindParser::default();
+    assert_eq!(parser.feed("signal time=1.0 sender=:1.3 path=/org/freedesktop/login1; member=PrepareForShutdown"), None);
+    assert_eq!(parser.feed("   boolean true"), None);
+    assert_eq!(parser.feed("   boolean true"), None);
+}
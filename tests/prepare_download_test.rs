@@ -0,0 +1,38 @@
+// tests/prepare_download_test.rs
+use rustloader::download_manager::DownloadOptions;
+use rustloader::prepare_download;
+
+// Invalid options are rejected before the queue is loaded or the URL probed
+
+#[tokio::test]
+async fn test_rejects_empty_url() {
+    let options = DownloadOptions {
+        url: "  ",
+        ..DownloadOptions::default()
+    };
+    assert!(prepare_download(options).await.is_err());
+}
+
+#[tokio::test]
+async fn test_rejects_inverted_time_range() {
+    let start = "00:05:00".to_string();
+    let end = "00:01:00".to_string();
+    let options = DownloadOptions {
+        url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        start_time: Some(&start),
+        end_time: Some(&end),
+        ..DownloadOptions::default()
+    };
+    assert!(prepare_download(options).await.is_err());
+}
+
+#[tokio::test]
+async fn test_rejects_invalid_bitrate() {
+    let bitrate = "fast".to_string();
+    let options = DownloadOptions {
+        url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        bitrate: Some(&bitrate),
+        ..DownloadOptions::default()
+    };
+    assert!(prepare_download(options).await.is_err());
+}
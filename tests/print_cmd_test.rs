@@ -0,0 +1,19 @@
+// tests/print_cmd_test.rs
+use rustloader::downloader::{DownloadBackend, YtdlpCommandBuilder};
+
+#[test]
+fn test_build_preview_is_full_argv() {
+    let start = "00:01:00".to_string();
+    let argv = YtdlpCommandBuilder::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "/tmp/out/%(title)s.mp3")
+        .with_format("mp3")
+        .with_time_range(Some(&start), None)
+        .with_backend(DownloadBackend::Native)
+        .build_preview()
+        .unwrap();
+
+    assert_eq!(argv[0], "yt-dlp");
+    assert!(argv.windows(2).any(|pair| pair == ["--audio-format", "mp3"]));
+    assert!(argv.windows(2).any(|pair| pair == ["-o", "/tmp/out/%(title)s.mp3"]));
+    assert!(argv.iter().any(|arg| arg.starts_with("ffmpeg:") && arg.contains("-ss 00:01:00")));
+    assert_eq!(argv[argv.len() - 2..], ["--", "https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+}
@@ -0,0 +1,117 @@
+// tests/process_test.rs
+use rustloader::process::{shell_join, CommandSpec};
+use std::path::Path;
+
+#[test]
+fn test_ytdlp_operands_follow_separator() {
+    let args = CommandSpec::ytdlp()
+        .flag("--dump-json")
+        .option("-f", "bestvideo+bestaudio")
+        .operand("-https://example.com/watch?v=x")
+        .to_args()
+        .unwrap();
+
+    assert_eq!(
+        args,
+        vec!["--dump-json", "-f", "bestvideo+bestaudio", "--", "-https://example.com/watch?v=x"]
+    );
+}
+
+#[test]
+fn test_rejects_unknown_flags_and_option_like_values() {
+    assert!(CommandSpec::ytdlp().flag("--exec").to_args().is_err());
+    assert!(CommandSpec::ytdlp().option("-o", "--exec=rm -rf ~").to_args().is_err());
+    assert!(CommandSpec::ytdlp().option("--user-agent", "a\0b").to_args().is_err());
+    assert!(CommandSpec::version_probe("-rf", "--version").to_args().is_err());
+}
+
+#[test]
+fn test_ffmpeg_has_no_separator() {
+    let args = CommandSpec::ffmpeg()
+        .option("-f", "null")
+        .operand("-")
+        .to_args()
+        .unwrap();
+    assert_eq!(args, vec!["-f", "null", "-"]);
+
+    // Without a separator, an operand that looks like an option is refused
+    assert!(CommandSpec::ffmpeg().operand("-y").to_args().is_err());
+}
+
+#[test]
+fn test_shell_join_quotes_unsafe_arguments() {
+    let argv: Vec<String> = [
+        "yt-dlp",
+        "-o",
+        "/home/me/Downloads/%(title)s.mp4",
+        "--",
+        "https://www.youtube.com/watch?v=abc&list=xyz",
+        "it's",
+        "",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    assert_eq!(
+        shell_join(&argv),
+        r#"yt-dlp -o '/home/me/Downloads/%(title)s.mp4' -- 'https://www.youtube.com/watch?v=abc&list=xyz' 'it'"'"'s' ''"#
+    );
+}
+
+#[test]
+fn test_package_manager_commands() {
+    let command = CommandSpec::package_manager("apt")
+        .args(&["install", "-y", "ffmpeg"])
+        .as_root()
+        .to_command()
+        .unwrap();
+    let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    assert_eq!(command.get_program(), "sudo");
+    assert_eq!(args, vec!["--", "apt", "install", "-y", "ffmpeg"]);
+
+    // Unknown programs and flags, and package names that look like options
+    assert!(CommandSpec::package_manager("rm").args(&["install"]).to_args().is_err());
+    assert!(CommandSpec::package_manager("apt").args(&["install", "-o", "x"]).to_args().is_err());
+    assert!(CommandSpec::package_manager("pacman").operand("--overwrite=*").to_args().is_err());
+}
+
+#[test]
+fn test_pip_and_path_lookups() {
+    let args = CommandSpec::pip("python3").args(&["install", "--user", "yt-dlp"]).to_args().unwrap();
+    assert_eq!(args, vec!["-m", "pip", "install", "--user", "yt-dlp"]);
+    assert!(CommandSpec::python_module("python3", "-c").to_args().is_err());
+
+    assert_eq!(CommandSpec::locate("ffmpeg").to_args().unwrap(), vec!["ffmpeg"]);
+    assert!(CommandSpec::locate("-a").to_args().is_err());
+}
+
+#[test]
+fn test_player_file_follows_separator() {
+    let args = CommandSpec::player(Path::new("/usr/bin/mpv"))
+        .operand("-Rick Astley.mp4.part")
+        .to_args()
+        .unwrap();
+    assert_eq!(args, vec!["--", "-Rick Astley.mp4.part"]);
+
+    // Players and plugins take no flags of their own
+    assert!(CommandSpec::player(Path::new("/usr/bin/mpv")).flag("--fs").to_args().is_err());
+    assert!(CommandSpec::plugin(Path::new("/plugins/vimeo/run")).operand("x").to_args().is_ok());
+    assert!(CommandSpec::plugin(Path::new("/plugins/vimeo/run")).flag("-x").to_args().is_err());
+}
+
+#[test]
+fn test_system_programs_and_opener() {
+    let args = CommandSpec::system("shutdown").args(&["-h", "+1"]).to_args().unwrap();
+    assert_eq!(args, vec!["-h", "+1"]);
+    assert!(CommandSpec::system("sh").args(&["-c", "true"]).to_args().is_err());
+    assert!(CommandSpec::system("systemctl").args(&["--force", "poweroff"]).to_args().is_err());
+
+    // Openers don't take `--`, so a path that looks like an option is refused
+    assert!(CommandSpec::opener().operand("/home/me/video.mp4").to_args().is_ok());
+    assert!(CommandSpec::opener().operand("--help").to_args().is_err());
+
+    let args = CommandSpec::user_command("notify-send", &["--urgency=low".to_string(), "done".to_string()])
+        .to_args()
+        .unwrap();
+    assert_eq!(args, vec!["--urgency=low", "done"]);
+}
@@ -0,0 +1,80 @@
+// tests/progress_parser_test.rs
+use rustloader::progress_parser::{parse_progress_line, ProgressFormat, ProgressLine, ProgressUpdate, PROGRESS_TEMPLATE};
+
+fn update(line: &str) -> ProgressUpdate {
+    match parse_progress_line(line) {
+        ProgressLine::Progress(update) => update,
+        other => panic!("{:?} for {}", other, line),
+    }
+}
+
+#[test]
+fn test_template_prints_the_marker() {
+    // yt-dlp strips the "download:" type, leaving the marker at the start
+    let printed = PROGRESS_TEMPLATE.strip_prefix("download:").unwrap();
+    assert!(printed.starts_with("[rustloader-progress:1] "));
+}
+
+#[test]
+fn test_json_progress() {
+    let progress = update(r#"[rustloader-progress:1] {"status": "downloading", "downloaded_bytes": 1024, "total_bytes": 4096, "speed": 512.5}"#);
+    assert_eq!(progress.downloaded, 1024);
+    assert_eq!(progress.total, Some(4096));
+    assert_eq!(progress.format, ProgressFormat::Json(1));
+}
+
+#[test]
+fn test_json_progress_without_exact_total() {
+    // Fragmented downloads only have an estimate, sometimes as a float
+    let estimated = update(r#"[rustloader-progress:1] {"downloaded_bytes": 2048, "total_bytes": null, "total_bytes_estimate": 10240.7}"#);
+    assert_eq!(estimated.total, Some(10240));
+
+    let fragments = update(r#"[rustloader-progress:1] {"downloaded_bytes": 3000, "fragment_index": 3, "fragment_count": 10}"#);
+    assert_eq!(fragments.total, Some(10000));
+
+    let unknown = update(r#"[rustloader-progress:1] {"downloaded_bytes": 3000, "total_bytes": "NA"}"#);
+    assert_eq!(unknown.downloaded, 3000);
+    assert_eq!(unknown.total, None);
+}
+
+#[test]
+fn test_newer_versions_are_read() {
+    let progress = update(r#"[rustloader-progress:2] {"downloaded_bytes": 5, "total_bytes": 10, "new_field": true}"#);
+    assert_eq!(progress.format, ProgressFormat::Json(2));
+    assert_eq!(progress.total, Some(10));
+}
+
+#[test]
+fn test_unreadable_marker_lines() {
+    assert_eq!(parse_progress_line("[rustloader-progress:1] NA"), ProgressLine::Unreadable);
+    assert_eq!(parse_progress_line("[rustloader-progress:x] {}"), ProgressLine::Unreadable);
+    assert_eq!(parse_progress_line(r#"[rustloader-progress:1] {"status": "finished"}"#), ProgressLine::Unreadable);
+}
+
+#[test]
+fn test_legacy_template() {
+    let progress = update("download:100/400");
+    assert_eq!((progress.downloaded, progress.total), (100, Some(400)));
+    assert_eq!(progress.format, ProgressFormat::Legacy);
+
+    let without_total = update("100/NA");
+    assert_eq!(without_total.total, None);
+}
+
+#[test]
+fn test_default_progress_line() {
+    let progress = update("[download]  50.0% of   10.00MiB at    1.00MiB/s ETA 00:05");
+    assert_eq!(progress.total, Some(10 * 1024 * 1024));
+    assert_eq!(progress.downloaded, 5 * 1024 * 1024);
+    assert_eq!(progress.format, ProgressFormat::Default);
+
+    let estimated = update("[download]  25.0% of ~  2.00GiB at  5.00MiB/s ETA 05:00 (frag 10/40)");
+    assert_eq!(estimated.total, Some(2 * 1024 * 1024 * 1024));
+}
+
+#[test]
+fn test_other_output() {
+    assert_eq!(parse_progress_line("[youtube] abc: Downloading webpage"), ProgressLine::Other);
+    assert_eq!(parse_progress_line("[download] Destination: video.mp4"), ProgressLine::Other);
+    assert_eq!(parse_progress_line(""), ProgressLine::Other);
+}
@@ -0,0 +1,76 @@
+// tests/promo_test.rs
+mod common;
+
+use common::temp_dir;
+use chrono::{Duration, TimeZone, Utc};
+use rustloader::promo::{
+    load_message_pack, normalize_locale, FrequencyCap, MessagePack, PromoService, PromoSlot, PromoState,
+};
+use std::fs;
+
+#[test]
+fn test_normalize_locale() {
+    assert_eq!(normalize_locale("de_DE.UTF-8").as_deref(), Some("de-de"));
+    assert_eq!(normalize_locale("pt-BR").as_deref(), Some("pt-br"));
+    assert_eq!(normalize_locale("fr").as_deref(), Some("fr"));
+    assert_eq!(normalize_locale("sr_RS@latin").as_deref(), Some("sr-rs"));
+    assert_eq!(normalize_locale("C"), None);
+    assert_eq!(normalize_locale("POSIX"), None);
+    assert_eq!(normalize_locale("../../etc/passwd"), None);
+}
+
+#[test]
+fn test_frequency_cap() {
+    let cap = FrequencyCap::default();
+    let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+
+    let fresh = PromoState::default();
+    assert!(cap.allows(&fresh, PromoSlot::Startup, now));
+    assert!(!cap.allows(&fresh, PromoSlot::Completion, now));
+
+    let after_downloads = PromoState { last_shown: None, downloads_since: cap.every_n_downloads };
+    assert!(cap.allows(&after_downloads, PromoSlot::Completion, now));
+
+    let recent = PromoState { last_shown: Some(now - Duration::minutes(5)), downloads_since: 100 };
+    assert!(!cap.allows(&recent, PromoSlot::Startup, now));
+    assert!(!cap.allows(&recent, PromoSlot::Completion, now));
+}
+
+#[test]
+fn test_service_shows_one_message_per_cap() {
+    let cap = FrequencyCap { min_interval: Duration::hours(1), every_n_downloads: 2 };
+    let mut service = PromoService::new(true, MessagePack::builtin(), cap, PromoState::default());
+    let now = Utc::now();
+
+    service.note_download();
+    assert!(service.message_at(PromoSlot::Completion, now).is_none());
+    service.note_download();
+    assert!(service.message_at(PromoSlot::Completion, now).is_some());
+
+    // Counter reset and interval not yet passed
+    service.note_download();
+    service.note_download();
+    assert!(service.message_at(PromoSlot::Completion, now + Duration::minutes(30)).is_none());
+    assert!(service.message_at(PromoSlot::Completion, now + Duration::hours(2)).is_some());
+}
+
+#[test]
+fn test_disabled_service_is_silent() {
+    let mut service = PromoService::new(false, MessagePack::builtin(), FrequencyCap::default(), PromoState::default());
+    assert!(service.message(PromoSlot::Startup).is_none());
+}
+
+#[test]
+fn test_localized_pack_falls_back_per_slot() {
+    let tmp = temp_dir("promo");
+    let dir = tmp.path().to_path_buf();
+    fs::write(dir.join("de.json"), r#"{"startup":["Rustloader Pro ausprobieren!"]}"#).unwrap();
+    fs::write(dir.join("fr.json"), "not json").unwrap();
+
+    let pack = load_message_pack(&dir, "de-at").unwrap().or(MessagePack::builtin());
+    assert_eq!(pack.messages(PromoSlot::Startup), ["Rustloader Pro ausprobieren!".to_string()]);
+    assert_eq!(pack.messages(PromoSlot::Completion), MessagePack::builtin().completion.as_slice());
+
+    assert!(load_message_pack(&dir, "fr").is_none());
+    assert!(load_message_pack(&dir, "es").is_none());
+}
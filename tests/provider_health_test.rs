@@ -0,0 +1,54 @@
+// tests/provider_health_test.rs
+
+use rustloader::provider_health::{judge_canary, select_canaries, ProviderStatus, CANARIES};
+use rustloader::site_stats::FailureKind;
+use std::time::Duration;
+
+#[test]
+fn test_select_canaries() {
+    assert_eq!(select_canaries(&[]).unwrap(), CANARIES.to_vec());
+
+    let selected = select_canaries(&["www.YouTube.com".to_string(), "vimeo.com".to_string()]).unwrap();
+    let sites: Vec<&str> = selected.iter().map(|canary| canary.site).collect();
+    assert_eq!(sites, ["youtube.com", "vimeo.com"]);
+
+    assert!(select_canaries(&["example.com".to_string()]).is_err());
+}
+
+#[test]
+fn test_judge_working_canary() {
+    let check = judge_canary(&CANARIES[0], true, r#"{"id": "jNQXAC9IVRw", "title": "Me at the zoo"}"#, "", Duration::from_millis(2_500));
+    assert_eq!(check.status, ProviderStatus::Working);
+    assert_eq!(check.failure, None);
+    assert_eq!(check.error, None);
+    assert_eq!(check.elapsed_ms, 2_500);
+    assert!(!check.needs_ytdlp_update());
+
+    // A zero exit without metadata isn't a working extractor
+    let check = judge_canary(&CANARIES[0], true, "", "", Duration::ZERO);
+    assert_eq!(check.status, ProviderStatus::Broken);
+}
+
+#[test]
+fn test_judge_failed_canary() {
+    let stderr = "[youtube] jNQXAC9IVRw: Downloading webpage\nERROR: [youtube] jNQXAC9IVRw: Unable to extract nsig function code\n";
+    let check = judge_canary(&CANARIES[0], false, "", stderr, Duration::from_secs(3));
+    assert_eq!(check.status, ProviderStatus::Broken);
+    assert_eq!(check.failure, Some(FailureKind::Extractor));
+    assert_eq!(check.error.as_deref(), Some("ERROR: [youtube] jNQXAC9IVRw: Unable to extract nsig function code"));
+    assert!(check.needs_ytdlp_update());
+
+    // Network trouble says nothing about the extractor
+    let check = judge_canary(&CANARIES[1], false, "", "Extraction timed out", Duration::from_secs(60));
+    assert_eq!(check.status, ProviderStatus::Unreachable);
+    assert!(!check.needs_ytdlp_update());
+}
+
+#[test]
+fn test_check_serializes() {
+    let check = judge_canary(&CANARIES[2], false, "", "ERROR: HTTP Error 403: Forbidden", Duration::ZERO);
+    let json = serde_json::to_value(&check).unwrap();
+    assert_eq!(json["site"], "dailymotion.com");
+    assert_eq!(json["status"], "broken");
+    assert_eq!(json["failure"], "forbidden");
+}
@@ -0,0 +1,70 @@
+// tests/publish_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::publish::{link_into, publish_file, LinkKind, PublishRule};
+use std::fs;
+
+#[test]
+fn test_rule_matching() {
+    let rule = PublishRule {
+        format: Some("mp3".to_string()),
+        url_contains: Some("YouTube.com".to_string()),
+        targets: vec!["/music".to_string()],
+        link: LinkKind::Hardlink,
+    };
+    assert!(rule.matches("https://www.youtube.com/watch?v=abc", "mp3"));
+    assert!(!rule.matches("https://www.youtube.com/watch?v=abc", "mp4"));
+    assert!(!rule.matches("https://vimeo.com/123", "mp3"));
+
+    // A rule without filters publishes everything
+    assert!(PublishRule::default().matches("https://vimeo.com/123", "mp4"));
+}
+
+#[test]
+fn test_publish_links_into_every_target() {
+    let tmp = temp_dir("publish_targets");
+    let dir = tmp.path().to_path_buf();
+    let file = dir.join("song.mp3");
+    fs::write(&file, b"audio").unwrap();
+
+    let library = dir.join("library");
+    let sync = dir.join("sync");
+    let rules = vec![
+        PublishRule {
+            targets: vec![library.to_string_lossy().into_owned()],
+            ..PublishRule::default()
+        },
+        PublishRule {
+            format: Some("mp3".to_string()),
+            targets: vec![sync.to_string_lossy().into_owned()],
+            link: LinkKind::Symlink,
+            ..PublishRule::default()
+        },
+        PublishRule {
+            format: Some("mp4".to_string()),
+            targets: vec![dir.join("videos").to_string_lossy().into_owned()],
+            ..PublishRule::default()
+        },
+    ];
+
+    let published = publish_file(&file, "https://example.com/song", "mp3", &rules);
+    assert_eq!(published, vec![library.join("song.mp3"), sync.join("song.mp3")]);
+    assert_eq!(fs::read(library.join("song.mp3")).unwrap(), b"audio");
+    assert_eq!(fs::read(sync.join("song.mp3")).unwrap(), b"audio");
+    assert!(!dir.join("videos").exists());
+}
+
+#[test]
+fn test_existing_files_are_not_replaced() {
+    let tmp = temp_dir("publish_existing");
+    let dir = tmp.path().to_path_buf();
+    let file = dir.join("video.mp4");
+    fs::write(&file, b"new").unwrap();
+    let target = dir.join("library");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("video.mp4"), b"old").unwrap();
+
+    assert_eq!(link_into(&file, &target, LinkKind::Hardlink).unwrap(), None);
+    assert_eq!(fs::read(target.join("video.mp4")).unwrap(), b"old");
+}
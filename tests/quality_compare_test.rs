@@ -0,0 +1,60 @@
+// tests/quality_compare_test.rs
+use rustloader::quality_compare::{bits_per_pixel, compare_qualities};
+use serde_json::json;
+
+fn sample_info() -> serde_json::Value {
+    json!({
+        "title": "Sample",
+        "duration": 100.0,
+        "formats": [
+            {"format_id": "sb0", "ext": "mhtml", "vcodec": "none", "acodec": "none"},
+            {"format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2", "filesize": 1_600_000, "tbr": 128.0},
+            {"format_id": "18", "ext": "mp4", "vcodec": "avc1.42001E", "acodec": "mp4a.40.2", "height": 360, "width": 640, "fps": 30.0, "tbr": 600.0},
+            {"format_id": "137", "ext": "mp4", "vcodec": "avc1.640028", "acodec": "none", "height": 1080, "width": 1920, "fps": 30.0, "vbr": 4000.0, "tbr": 4000.0, "filesize": 50_000_000},
+            {"format_id": "399", "ext": "mp4", "vcodec": "av01.0.08M.08", "acodec": "none", "height": 1080, "width": 1920, "fps": 30.0, "tbr": 2000.0, "filesize": 25_000_000},
+            {"format_id": "136", "ext": "mp4", "vcodec": "avc1.4d401f", "acodec": "none", "height": 1080, "width": 1920, "fps": 30.0, "tbr": 3000.0}
+        ]
+    })
+}
+
+#[test]
+fn test_one_tier_per_height_and_codec() {
+    let comparison = compare_qualities("https://example.com/v", &sample_info());
+    assert_eq!(comparison.title.as_deref(), Some("Sample"));
+    let tiers: Vec<(u64, &str, &str)> = comparison
+        .tiers
+        .iter()
+        .map(|tier| (tier.height, tier.vcodec.as_str(), tier.format_id.as_str()))
+        .collect();
+    // The highest bitrate avc1 1080p format represents its tier
+    assert_eq!(tiers, vec![(1080, "avc1", "137"), (1080, "av01", "399"), (360, "avc1", "18")]);
+}
+
+#[test]
+fn test_sizes_include_audio_for_video_only_formats() {
+    let comparison = compare_qualities("https://example.com/v", &sample_info());
+    let avc = &comparison.tiers[0];
+    assert_eq!(avc.acodec.as_deref(), Some("mp4a"));
+    assert_eq!(avc.estimated_bytes, Some(51_600_000));
+
+    // Without a reported size the bitrate and duration give the estimate
+    let combined = &comparison.tiers[2];
+    assert_eq!(combined.estimated_bytes, Some(7_500_000));
+}
+
+#[test]
+fn test_bits_per_pixel() {
+    let comparison = compare_qualities("https://example.com/v", &sample_info());
+    let bpp = comparison.tiers[0].bits_per_pixel.unwrap();
+    assert!((bpp - 4_000_000.0 / (1920.0 * 1080.0 * 30.0)).abs() < 1e-9);
+    assert!(comparison.tiers[1].bits_per_pixel.unwrap() < bpp);
+
+    assert_eq!(bits_per_pixel(1000.0, 0, 1080, 30.0), None);
+    assert_eq!(bits_per_pixel(0.0, 1920, 1080, 30.0), None);
+}
+
+#[test]
+fn test_no_video_formats() {
+    let info = json!({"formats": [{"format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2"}]});
+    assert!(compare_qualities("https://example.com/v", &info).tiers.is_empty());
+}
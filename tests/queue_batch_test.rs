@@ -0,0 +1,72 @@
+// tests/queue_batch_test.rs
+use rustloader::download_manager::{
+    apply_batch_action, BatchAction, DownloadItem, DownloadPriority, DownloadStatus,
+};
+use rustloader::download_map::DownloadMap;
+
+fn setup() -> (Vec<String>, DownloadMap, Vec<String>) {
+    let items: Vec<DownloadItem> = (1..=4)
+        .map(|n| DownloadItem::builder(&format!("https://example.com/{}", n), "mp4").build())
+        .collect();
+    let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+    let downloads = items.into_iter().map(|item| (item.id.clone(), item)).collect();
+    (ids.clone(), downloads, ids)
+}
+
+#[test]
+fn test_batch_pause_stops_running_and_dequeues() {
+    let (mut queue, mut downloads, ids) = setup();
+    downloads.get_mut(&ids[0]).unwrap().mark_started();
+    queue.retain(|id| id != &ids[0]);
+    downloads.get_mut(&ids[3]).unwrap().mark_completed(None);
+
+    let outcome = apply_batch_action(&mut queue, &mut downloads, BatchAction::Pause, &ids);
+    assert_eq!(outcome.changed, ids[..3].to_vec());
+    assert_eq!(outcome.stopped, vec![ids[0].clone()]);
+    assert_eq!(queue, vec![ids[3].clone()]);
+    assert!(ids[..3].iter().all(|id| downloads.get(id).unwrap().status == DownloadStatus::Paused));
+    assert_eq!(downloads.get(&ids[3]).unwrap().status, DownloadStatus::Completed);
+}
+
+#[test]
+fn test_batch_cancel() {
+    let (mut queue, mut downloads, ids) = setup();
+    let selected = [ids[1].clone(), ids[2].clone()];
+
+    let outcome = apply_batch_action(&mut queue, &mut downloads, BatchAction::Cancel, &selected);
+    assert_eq!(outcome.changed, selected.to_vec());
+    assert!(outcome.stopped.is_empty());
+    assert_eq!(queue, vec![ids[0].clone(), ids[3].clone()]);
+    assert_eq!(downloads.get(&ids[1]).unwrap().status, DownloadStatus::Canceled);
+}
+
+#[test]
+fn test_batch_priority_moves_queued_downloads() {
+    let (mut queue, mut downloads, ids) = setup();
+    let selected = [ids[2].clone(), ids[3].clone()];
+
+    let outcome = apply_batch_action(&mut queue, &mut downloads, BatchAction::SetPriority(DownloadPriority::High), &selected);
+    assert_eq!(outcome.changed.len(), 2);
+    assert_eq!(queue, vec![ids[3].clone(), ids[2].clone(), ids[0].clone(), ids[1].clone()]);
+    assert_eq!(downloads.get(&ids[2]).unwrap().priority, DownloadPriority::High);
+
+    // Already at that priority: nothing to do
+    let outcome = apply_batch_action(&mut queue, &mut downloads, BatchAction::SetPriority(DownloadPriority::High), &selected);
+    assert!(outcome.changed.is_empty());
+}
+
+#[test]
+fn test_batch_retry_requeues_failed_only() {
+    let (mut queue, mut downloads, ids) = setup();
+    queue.clear();
+    downloads.get_mut(&ids[0]).unwrap().mark_failed(Some("HTTP 403".to_string()));
+    downloads.get_mut(&ids[1]).unwrap().cancel();
+    downloads.get_mut(&ids[2]).unwrap().mark_completed(None);
+
+    let outcome = apply_batch_action(&mut queue, &mut downloads, BatchAction::Retry, &ids[..3]);
+    assert_eq!(outcome.changed, ids[..2].to_vec());
+    assert_eq!(queue, ids[..2].to_vec());
+    assert_eq!(downloads.get(&ids[0]).unwrap().status, DownloadStatus::Queued);
+    assert!(downloads.get(&ids[0]).unwrap().error_message.is_none());
+    assert_eq!(downloads.get(&ids[2]).unwrap().status, DownloadStatus::Completed);
+}
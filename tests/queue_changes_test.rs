@@ -0,0 +1,87 @@
+// tests/queue_changes_test.rs
+mod common;
+
+use common::{download_map, item};
+use rustloader::download_manager::DownloadStatus;
+use rustloader::queue_changes::{changed_fields, QueueChange, QueueChangeLog};
+use serde_json::json;
+
+#[test]
+fn test_changed_fields_lists_only_changes() {
+    let old = item("a");
+    let mut new = old.clone();
+    new.progress = 42.5;
+    new.status = DownloadStatus::Downloading;
+
+    let fields = changed_fields(&old, &new);
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields["progress"], json!(42.5));
+    assert_eq!(fields["status"], serde_json::to_value(DownloadStatus::Downloading).unwrap());
+    assert!(changed_fields(&old, &old.clone()).is_empty());
+}
+
+#[test]
+fn test_records_added_updated_and_removed() {
+    let mut downloads = download_map(&[item("a"), item("b")]);
+    let mut log = QueueChangeLog::new();
+    assert_eq!(log.record(downloads.snapshot()), 1);
+
+    // Nothing changed, so the sequence number stays
+    assert_eq!(log.record(downloads.snapshot()), 1);
+    assert!(log.changes_since(Some(1)).changes.is_empty());
+
+    downloads.get_mut("a").unwrap().progress = 10.0;
+    downloads.remove("b");
+    downloads.insert("c".to_string(), item("c"));
+    assert_eq!(log.record(downloads.snapshot()), 2);
+
+    let delta = log.changes_since(Some(1));
+    assert!(!delta.reset);
+    assert_eq!(delta.seq, 2);
+    assert_eq!(delta.changes.len(), 3);
+    assert!(delta.changes.iter().any(|change| matches!(change,
+        QueueChange::Updated { id, fields } if id == "a" && fields.len() == 1 && fields["progress"] == json!(10.0))));
+    assert!(delta.changes.iter().any(|change| matches!(change, QueueChange::Removed { id } if id == "b")));
+    assert!(delta.changes.iter().any(|change| matches!(change, QueueChange::Added { item } if item.id == "c")));
+}
+
+#[test]
+fn test_unknown_sequence_gets_full_list() {
+    let mut downloads = download_map(&[item("a"), item("b")]);
+    let mut log = QueueChangeLog::new();
+    log.record(downloads.snapshot());
+    downloads.get_mut("a").unwrap().progress = 10.0;
+    log.record(downloads.snapshot());
+
+    for since in [None, Some(99)] {
+        let delta = log.changes_since(since);
+        assert!(delta.reset);
+        assert_eq!(delta.seq, 2);
+        assert_eq!(delta.changes.len(), 2);
+        assert!(delta.changes.iter().all(|change| matches!(change, QueueChange::Added { .. })));
+    }
+
+    // Catching up from the start replays every kept update
+    let delta = log.changes_since(Some(0));
+    assert!(!delta.reset);
+    assert_eq!(delta.changes.len(), 3);
+}
+
+#[test]
+fn test_old_sequence_falls_back_to_full_list() {
+    let mut downloads = download_map(&[item("a")]);
+    let mut log = QueueChangeLog::new();
+    for progress in 0..100 {
+        downloads.get_mut("a").unwrap().progress = progress as f64 + 1.0;
+        log.record(downloads.snapshot());
+    }
+    assert_eq!(log.seq(), 100);
+    assert!(log.changes_since(Some(1)).reset);
+    assert!(!log.changes_since(Some(99)).reset);
+}
+
+#[test]
+fn test_changes_serialize_with_kind() {
+    let change = QueueChange::Removed { id: "a".to_string() };
+    assert_eq!(serde_json::to_value(&change).unwrap(), json!({"change": "removed", "id": "a"}));
+}
@@ -0,0 +1,60 @@
+// tests/queue_eta_test.rs
+mod common;
+
+use chrono::{Duration, Utc};
+use rustloader::bandwidth::{average_throughput, BandwidthManager};
+use rustloader::download_manager::{DownloadItem, DownloadPriority, DownloadStatus};
+use rustloader::queue_eta::estimate_queue;
+
+fn item(id: &str, status: DownloadStatus, total: u64, downloaded: u64) -> DownloadItem {
+    let mut item = common::item(id);
+    item.status = status;
+    item.total_bytes = total;
+    item.downloaded_bytes = downloaded;
+    item
+}
+
+#[test]
+fn test_etas_accumulate_in_queue_order() {
+    let now = Utc::now();
+    let active = item("active", DownloadStatus::Downloading, 1000, 600);
+    let mut urgent = item("urgent", DownloadStatus::Queued, 2000, 0);
+    urgent.priority = DownloadPriority::High;
+    let normal = item("normal", DownloadStatus::Queued, 1000, 0);
+    let unknown = item("unknown", DownloadStatus::Queued, 0, 0);
+    let paused = item("paused", DownloadStatus::Paused, 5000, 0);
+
+    let downloads = vec![normal.clone(), paused.clone(), unknown, urgent.clone(), active.clone()];
+    let eta = estimate_queue(&downloads, Some(100.0), now);
+
+    assert_eq!(eta.eta(&active.id).unwrap().as_secs(), 4);
+    assert_eq!(eta.eta(&urgent.id).unwrap().as_secs(), 24);
+    assert_eq!(eta.eta(&normal.id).unwrap().as_secs(), 34);
+    assert!(eta.eta(&paused.id).is_none());
+    assert_eq!(eta.unknown_sizes, 1);
+    assert_eq!(eta.finishes_at, Some(now + Duration::seconds(34)));
+}
+
+#[test]
+fn test_no_estimate_without_throughput() {
+    let downloads = vec![item("queued", DownloadStatus::Queued, 1000, 0)];
+    let eta = estimate_queue(&downloads, None, Utc::now());
+    assert!(eta.items.is_empty());
+    assert!(eta.finishes_at.is_none());
+}
+
+#[test]
+fn test_throughput_from_history_capped_by_limit() {
+    let finished = Utc::now();
+    let mut done = item("done", DownloadStatus::Completed, 10 * 1024 * 1024, 10 * 1024 * 1024);
+    done.started_at = Some(finished - Duration::seconds(5));
+    done.finished_at = Some(finished);
+
+    let measured = average_throughput(&[done]).unwrap();
+    assert_eq!(measured, 2.0 * 1024.0 * 1024.0);
+
+    let manager = BandwidthManager::new(&[], 0).unwrap().with_override(Some(Some(1024 * 1024)));
+    let time = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+    assert_eq!(manager.expected_throughput(Some(measured), time), Some(1024.0 * 1024.0));
+    assert_eq!(manager.expected_throughput(None, time), Some(1024.0 * 1024.0));
+}
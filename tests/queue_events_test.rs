@@ -0,0 +1,48 @@
+// tests/queue_events_test.rs
+use chrono::Utc;
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+use rustloader::queue_events::{diff_queue_events, progress_events, QueueEvent};
+use std::collections::HashMap;
+
+#[test]
+fn test_diff_reports_new_changed_and_removed() {
+    let now = Utc::now();
+    let mut known = HashMap::new();
+
+    let mut first = DownloadItem::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4");
+    let second = DownloadItem::new("https://vimeo.com/76979871", "mp4");
+
+    // Every download is new the first time it is seen
+    let events = diff_queue_events(&mut known, &[first.clone(), second.clone()], now);
+    assert_eq!(events.len(), 2);
+
+    // Nothing changed, nothing to report
+    assert!(diff_queue_events(&mut known, &[first.clone(), second.clone()], now).is_empty());
+
+    // A status change and a removal
+    first.status = DownloadStatus::Downloading;
+    let events = diff_queue_events(&mut known, &[first.clone()], now);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        &events[0],
+        QueueEvent::StateChanged { id, from: Some(DownloadStatus::Queued), to: DownloadStatus::Downloading, .. } if id == &first.id
+    ));
+    assert!(matches!(&events[1], QueueEvent::Removed { id, .. } if id == &second.id));
+}
+
+#[test]
+fn test_events_serialize_as_tagged_lines() {
+    let now = Utc::now();
+    let mut item = DownloadItem::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4");
+    item.status = DownloadStatus::Downloading;
+    item.progress = 42.0;
+
+    let events = progress_events(&[item], now);
+    assert_eq!(events.len(), 1);
+
+    let line = serde_json::to_string(&events[0]).unwrap();
+    assert!(!line.contains('\n'));
+    let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(value["event"], "progress");
+    assert_eq!(value["progress"], 42.0);
+}
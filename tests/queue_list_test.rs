@@ -0,0 +1,78 @@
+// tests/queue_list_test.rs
+mod common;
+
+use chrono::{Duration, Utc};
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+use rustloader::queue_list::{elapsed, filter_by_status, parse_status, sort_downloads, QueueSort};
+
+fn item(name: &str, minutes_ago: i64, speed: f64, progress: f64, total_bytes: u64) -> DownloadItem {
+    let mut item = common::item(name);
+    item.title = Some(name.to_string());
+    item.added_at = Utc::now() - Duration::minutes(minutes_ago);
+    item.speed = speed;
+    item.progress = progress;
+    item.total_bytes = total_bytes;
+    item
+}
+
+fn titles(downloads: &[DownloadItem]) -> Vec<&str> {
+    downloads.iter().map(|item| item.title.as_deref().unwrap()).collect()
+}
+
+fn sample() -> Vec<DownloadItem> {
+    vec![
+        item("a", 30, 100.0, 10.0, 0),
+        item("b", 20, 500.0, 80.0, 2_000),
+        item("c", 10, 500.0, 50.0, 9_000),
+    ]
+}
+
+#[test]
+fn test_sort_downloads() {
+    let mut downloads = sample();
+
+    sort_downloads(&mut downloads, QueueSort::Speed);
+    assert_eq!(titles(&downloads), ["b", "c", "a"]); // tie broken by age
+    sort_downloads(&mut downloads, QueueSort::Progress);
+    assert_eq!(titles(&downloads), ["b", "c", "a"]);
+    sort_downloads(&mut downloads, QueueSort::Size);
+    assert_eq!(titles(&downloads), ["c", "b", "a"]); // unknown size last
+    sort_downloads(&mut downloads, QueueSort::Added);
+    assert_eq!(titles(&downloads), ["a", "b", "c"]);
+}
+
+#[test]
+fn test_filter_by_status() {
+    let mut downloads = sample();
+    downloads[0].mark_failed(Some("HTTP 403".to_string()));
+    downloads[1].mark_started();
+
+    assert_eq!(filter_by_status(downloads.clone(), &[]).len(), 3);
+    assert_eq!(titles(&filter_by_status(downloads.clone(), &[DownloadStatus::Failed])), ["a"]);
+    assert_eq!(
+        titles(&filter_by_status(downloads, &[DownloadStatus::Downloading, DownloadStatus::Failed])),
+        ["a", "b"]
+    );
+}
+
+#[test]
+fn test_parse_sort_and_status() {
+    assert_eq!(QueueSort::parse("Speed").unwrap(), QueueSort::Speed);
+    assert!(QueueSort::parse("eta").is_err());
+    assert_eq!(parse_status("downloading").unwrap(), DownloadStatus::Downloading);
+    assert_eq!(parse_status("cancelled").unwrap(), DownloadStatus::Canceled);
+    assert!(parse_status("done").is_err());
+}
+
+#[test]
+fn test_elapsed() {
+    let now = Utc::now();
+    let mut download = item("a", 30, 0.0, 0.0, 0);
+    assert_eq!(elapsed(&download, now), None);
+
+    download.started_at = Some(now - Duration::seconds(90));
+    assert_eq!(elapsed(&download, now), Some(std::time::Duration::from_secs(90)));
+
+    download.finished_at = Some(now - Duration::seconds(30));
+    assert_eq!(elapsed(&download, now), Some(std::time::Duration::from_secs(60)));
+}
@@ -0,0 +1,137 @@
+// tests/queue_order_test.rs
+use chrono::{Duration, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rustloader::download_manager::{DownloadItem, DownloadPriority, DownloadStatus};
+use rustloader::download_map::DownloadMap;
+use rustloader::queue_order::{dispatch_order, list_order, sort_queue_ids};
+use std::cmp::Ordering;
+
+const PRIORITIES: [DownloadPriority; 4] = [
+    DownloadPriority::Low,
+    DownloadPriority::Normal,
+    DownloadPriority::High,
+    DownloadPriority::Critical,
+];
+
+/// Random queued downloads; added times and positions collide on purpose
+fn random_queue(rng: &mut StdRng) -> Vec<DownloadItem> {
+    let base = Utc::now();
+    (0..rng.gen_range(1..30))
+        .map(|n| {
+            let mut item = DownloadItem::new(&format!("https://example.com/{}", n), "mp4");
+            item.priority = PRIORITIES[rng.gen_range(0..PRIORITIES.len())];
+            item.queue_position = rng.gen_bool(0.3).then(|| rng.gen_range(0..5));
+            item.added_at = base + Duration::seconds(rng.gen_range(0..10));
+            item
+        })
+        .collect()
+}
+
+fn sorted_ids(items: &[DownloadItem]) -> Vec<String> {
+    let mut items = items.to_vec();
+    items.sort_by(dispatch_order);
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_order_is_total_and_consistent() {
+    for seed in 0..100 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let items = random_queue(&mut rng);
+        for a in &items {
+            for b in &items {
+                let order = dispatch_order(a, b);
+                assert_eq!(order, dispatch_order(b, a).reverse(), "seed {}", seed);
+                assert_eq!(order == Ordering::Equal, a.id == b.id, "seed {}", seed);
+                for c in &items {
+                    if order == Ordering::Less && dispatch_order(b, c) == Ordering::Less {
+                        assert_eq!(dispatch_order(a, c), Ordering::Less, "seed {}", seed);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_order_does_not_depend_on_storage_order() {
+    for seed in 0..100 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut items = random_queue(&mut rng);
+        let expected = sorted_ids(&items);
+        for _ in 0..5 {
+            items.shuffle(&mut rng);
+            assert_eq!(sorted_ids(&items), expected, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_priority_then_manual_position_then_arrival() {
+    for seed in 0..100 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut items = random_queue(&mut rng);
+        items.sort_by(dispatch_order);
+        for pair in items.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            assert!(a.priority >= b.priority, "seed {}", seed);
+            if a.priority != b.priority {
+                continue;
+            }
+            match (a.queue_position, b.queue_position) {
+                (Some(a), Some(b)) => assert!(a <= b, "seed {}", seed),
+                (None, Some(_)) => panic!("unplaced download ahead of a placed one (seed {})", seed),
+                // Equal priority and no manual placement: first come, first served
+                (None, None) => assert!(a.added_at <= b.added_at, "seed {}", seed),
+                (Some(_), None) => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sort_queue_ids_matches_dispatch_order() {
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let items = random_queue(&mut rng);
+        let expected = sorted_ids(&items);
+        let mut queue: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+        queue.shuffle(&mut rng);
+        queue.push("gone".to_string());
+        let downloads: DownloadMap = items.into_iter().map(|item| (item.id.clone(), item)).collect();
+
+        sort_queue_ids(&mut queue, &downloads);
+        assert_eq!(queue.pop().as_deref(), Some("gone"));
+        assert_eq!(queue, expected, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_list_order_shows_running_then_dispatch_order() {
+    let mut running = DownloadItem::new("https://example.com/running", "mp4");
+    running.mark_started();
+    let mut done = DownloadItem::new("https://example.com/done", "mp4");
+    done.mark_completed(None);
+    let mut paused = DownloadItem::new("https://example.com/paused", "mp4");
+    paused.mark_paused();
+    let normal = DownloadItem::new("https://example.com/normal", "mp4");
+    let mut urgent = DownloadItem::new("https://example.com/urgent", "mp4");
+    urgent.priority = DownloadPriority::High;
+
+    let mut items = [done, paused, normal, running, urgent];
+    items.sort_by(list_order);
+    let urls: Vec<&str> = items.iter().map(|item| item.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/running",
+            "https://example.com/urgent",
+            "https://example.com/normal",
+            "https://example.com/paused",
+            "https://example.com/done",
+        ]
+    );
+    assert_eq!(items[0].status, DownloadStatus::Downloading);
+}
@@ -0,0 +1,52 @@
+// tests/queue_reorder_test.rs
+use rustloader::download_manager::{apply_queue_order, DownloadItem, DownloadPriority, DownloadStatus};
+use rustloader::error::AppError;
+use rustloader::download_map::DownloadMap;
+
+fn queued(url: &str, priority: DownloadPriority) -> DownloadItem {
+    DownloadItem::builder(url, "mp4").priority(priority).build()
+}
+
+fn setup() -> (Vec<String>, DownloadMap, Vec<String>) {
+    let items = vec![
+        queued("https://example.com/1", DownloadPriority::High),
+        queued("https://example.com/2", DownloadPriority::Normal),
+        queued("https://example.com/3", DownloadPriority::Normal),
+        queued("https://example.com/4", DownloadPriority::Normal),
+    ];
+    let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+    let downloads = items.into_iter().map(|item| (item.id.clone(), item)).collect();
+    (ids.clone(), downloads, ids)
+}
+
+#[test]
+fn test_reorder_moves_only_listed_downloads() {
+    let (mut queue, downloads, ids) = setup();
+
+    apply_queue_order(&mut queue, &downloads, &[ids[3].clone(), ids[1].clone()]).unwrap();
+    assert_eq!(queue, vec![ids[0].clone(), ids[3].clone(), ids[2].clone(), ids[1].clone()]);
+}
+
+#[test]
+fn test_reorder_validates_ids() {
+    let (mut queue, mut downloads, ids) = setup();
+    let original = queue.clone();
+
+    let invalid = [
+        vec![],
+        vec![ids[1].clone(), ids[1].clone()],
+        vec!["unknown".to_string()],
+        // Normal downloads can't jump ahead of a High one
+        vec![ids[2].clone(), ids[0].clone()],
+    ];
+    for order in invalid {
+        let result = apply_queue_order(&mut queue, &downloads, &order);
+        assert!(matches!(result, Err(AppError::ValidationError(_))), "{:?} should be rejected", order);
+    }
+
+    // Downloads that already started can't be reordered
+    downloads.get_mut(&ids[3]).unwrap().status = DownloadStatus::Downloading;
+    assert!(apply_queue_order(&mut queue, &downloads, &[ids[3].clone(), ids[2].clone()]).is_err());
+
+    assert_eq!(queue, original);
+}
@@ -0,0 +1,40 @@
+// tests/queue_repair_test.rs
+use rustloader::download_manager::DownloadItem;
+use rustloader::queue_repair::{plan_repair, RepairAction};
+
+fn stuck(progress: f64) -> DownloadItem {
+    let mut item = DownloadItem::new("https://example.com/stuck", "mp4");
+    item.mark_started();
+    item.progress = progress;
+    item.title = Some("Stuck Video".to_string());
+    item
+}
+
+#[test]
+fn test_requeues_when_partial_files_remain() {
+    let repair = plan_repair(&stuck(40.0), 12 * 1024 * 1024);
+    assert_eq!(repair.action, RepairAction::Requeue);
+    assert_eq!(repair.partial_bytes, 12 * 1024 * 1024);
+    assert!(repair.describe().ends_with("Stuck Video: queued again (12 MiB on disk)"));
+}
+
+#[test]
+fn test_fails_when_progress_was_lost() {
+    let repair = plan_repair(&stuck(40.0), 0);
+    assert_eq!(repair.action, RepairAction::Fail);
+    assert!(repair.describe().contains("failed"));
+}
+
+#[test]
+fn test_requeues_a_download_that_never_started() {
+    let repair = plan_repair(&stuck(0.0), 0);
+    assert_eq!(repair.action, RepairAction::Requeue);
+    assert!(repair.describe().ends_with("queued again"));
+}
+
+#[test]
+fn test_describe_uses_a_short_id() {
+    let item = stuck(0.0);
+    let repair = plan_repair(&item, 0);
+    assert!(repair.describe().starts_with(&item.id[..8]));
+}
@@ -0,0 +1,114 @@
+// tests/queue_save_test.rs
+mod common;
+
+use common::{temp_dir, EnvGuard};
+use rustloader::download_manager::{get_queue_state_path, load_saved_downloads_from, DownloadQueue, SaveDebounce};
+use rustloader::error::AppError;
+use rustloader::instance_lock::{acquire_instance_lock, try_lock_in, InstanceKind};
+use rustloader::paths::DATA_DIR_ENV;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_first_save_runs_immediately() {
+    let mut debounce = SaveDebounce::new(Duration::from_secs(2));
+    assert!(debounce.request(Instant::now()));
+    assert!(debounce.due_in(Instant::now()).is_none());
+}
+
+#[test]
+fn test_burst_is_coalesced() {
+    let start = Instant::now();
+    let mut debounce = SaveDebounce::new(Duration::from_secs(2));
+    debounce.saved(start);
+
+    // Requests right after a save are deferred, not dropped
+    assert!(!debounce.request(start + Duration::from_millis(100)));
+    assert!(!debounce.request(start + Duration::from_millis(500)));
+    assert!(debounce.due_in(start + Duration::from_millis(500)).is_some());
+    assert!(!debounce.is_due(start + Duration::from_secs(1)));
+
+    // One save covers the whole burst
+    assert!(debounce.is_due(start + Duration::from_secs(2)));
+    debounce.saved(start + Duration::from_secs(2));
+    assert!(debounce.due_in(start + Duration::from_secs(2)).is_none());
+    assert!(!debounce.is_due(start + Duration::from_secs(10)));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_blocking_stop_saves_from_inside_the_runtime() {
+    let tmp = temp_dir("queue_save");
+    let data = tmp.path().to_path_buf();
+    let env = EnvGuard::lock().set(DATA_DIR_ENV, &data);
+    let queue = DownloadQueue::new(2);
+    let state_path = get_queue_state_path();
+    assert!(state_path.starts_with(&data));
+
+    // Blocking the only runtime thread must not wait on the runtime
+    queue.stop_blocking(Duration::from_secs(5)).unwrap();
+    assert!(state_path.is_file());
+    drop(env);
+}
+
+#[test]
+fn test_read_only_queue_leaves_the_owners_state_alone() {
+    let tmp = temp_dir("read_only_flush");
+    let data = tmp.path().to_path_buf();
+    let env = EnvGuard::lock().set(DATA_DIR_ENV, &data);
+    let owner = try_lock_in(&data, InstanceKind::Cli).unwrap().unwrap();
+    assert!(acquire_instance_lock(InstanceKind::Gui).unwrap().is_none());
+
+    let state_path = get_queue_state_path();
+    let saved = r#"{"downloads": [], "signature": "from the owner"}"#;
+    std::fs::write(&state_path, saved).unwrap();
+
+    let queue = DownloadQueue::new(2);
+    queue.flush_blocking(Duration::from_secs(5)).unwrap();
+    queue.stop_blocking(Duration::from_secs(5)).unwrap();
+    assert_eq!(std::fs::read_to_string(&state_path).unwrap(), saved);
+    assert!(!data.join("download_queue.json.bak").exists());
+
+    // Owning the queue again for the other tests
+    drop(owner);
+    assert!(acquire_instance_lock(InstanceKind::Gui).unwrap().is_some());
+    drop(env);
+}
+
+#[test]
+fn test_unsigned_state_is_only_migrated_once() {
+    let tmp = temp_dir("unsigned_state");
+    let dir = tmp.path().to_path_buf();
+    let state_path = dir.join("download_queue.json");
+    let unsigned = r#"{"downloads": []}"#;
+
+    // State from before signing loads while nothing signed was ever written
+    std::fs::write(&state_path, unsigned).unwrap();
+    assert!(load_saved_downloads_from(&state_path).unwrap().is_empty());
+
+    // Once there is a backup, unsigned state and backups are rejected
+    std::fs::write(dir.join("download_queue.json.bak"), unsigned).unwrap();
+    assert!(matches!(load_saved_downloads_from(&state_path), Err(AppError::SecurityViolation)));
+}
+
+#[test]
+fn test_reading_invalid_state_leaves_it_in_place() {
+    let tmp = temp_dir("invalid_state");
+    let state_path = tmp.path().join("download_queue.json");
+    std::fs::write(&state_path, "{ not json").unwrap();
+
+    // Only the queue's owner moves a bad file aside
+    assert!(load_saved_downloads_from(&state_path).is_err());
+    assert!(state_path.is_file());
+    assert!(!tmp.path().join("download_queue.json.corrupt").exists());
+}
+
+#[test]
+fn test_deferred_save_reports_when_it_is_due() {
+    let start = Instant::now();
+    let mut debounce = SaveDebounce::new(Duration::from_secs(2));
+    assert_eq!(debounce.due_in(start), None);
+
+    debounce.saved(start);
+    assert!(!debounce.request(start + Duration::from_millis(500)));
+    assert_eq!(debounce.due_in(start + Duration::from_millis(500)), Some(Duration::from_millis(1500)));
+    assert_eq!(debounce.due_in(start + Duration::from_secs(3)), Some(Duration::ZERO));
+}
@@ -0,0 +1,71 @@
+// tests/queue_schedule_test.rs
+use chrono::{Duration, FixedOffset, NaiveTime, TimeZone, Utc};
+use rustloader::download_manager::DownloadItem;
+use rustloader::queue_schedule::{parse_start_time, start_delay, ScheduleChange, TimeWindow};
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[test]
+fn test_window_parsing_and_midnight() {
+    let night = TimeWindow::parse("22:30–05:00").unwrap();
+    assert_eq!(night.to_string(), "22:30-05:00");
+    assert!(night.contains(time(23, 0)));
+    assert!(night.contains(time(4, 59)));
+    assert!(!night.contains(time(5, 0)));
+    assert_eq!(night.opens_in(time(21, 30)), Duration::hours(1));
+    assert_eq!(night.opens_in(time(1, 0)), Duration::zero());
+
+    let early = TimeWindow::parse("01:00-06:00").unwrap();
+    assert_eq!(early.opens_in(time(7, 0)), Duration::hours(18));
+    assert!(TimeWindow::parse("01:00-01:00").is_err());
+    assert!(TimeWindow::parse("1am-6am").is_err());
+
+    // Stored as its text form
+    assert_eq!(serde_json::to_string(&early).unwrap(), "\"01:00-06:00\"");
+    assert_eq!(serde_json::from_str::<TimeWindow>("\"01:00-06:00\"").unwrap(), early);
+    assert!(serde_json::from_str::<TimeWindow>("\"late\"").is_err());
+}
+
+#[test]
+fn test_start_times_are_in_the_future() {
+    let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+    let now = zone.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+
+    assert_eq!(parse_start_time("+90m", &now).unwrap(), now + Duration::minutes(90));
+    // A clock time already past today means tomorrow
+    assert_eq!(parse_start_time("13:00", &now).unwrap(), zone.with_ymd_and_hms(2026, 3, 1, 13, 0, 0).unwrap());
+    assert_eq!(parse_start_time("11:00", &now).unwrap(), zone.with_ymd_and_hms(2026, 3, 2, 11, 0, 0).unwrap());
+    assert_eq!(
+        parse_start_time("2026-03-05 08:15", &now).unwrap(),
+        zone.with_ymd_and_hms(2026, 3, 5, 8, 15, 0).unwrap()
+    );
+    assert_eq!(
+        parse_start_time("2026-03-01T11:00:00Z", &now).unwrap(),
+        Utc.with_ymd_and_hms(2026, 3, 1, 11, 0, 0).unwrap()
+    );
+    assert!(parse_start_time("2026-02-01 08:15", &now).is_err());
+    assert!(parse_start_time("tomorrow", &now).is_err());
+}
+
+#[test]
+fn test_start_delay_combines_time_and_window() {
+    let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+    let window = TimeWindow::parse("01:00-06:00").ok();
+
+    assert_eq!(start_delay(None, None, &now), None);
+    assert_eq!(start_delay(Some(now - Duration::hours(1)), None, &now), None);
+    assert_eq!(start_delay(Some(now + Duration::hours(2)), None, &now), Some(Duration::hours(2)));
+    assert_eq!(start_delay(None, window, &now), Some(Duration::hours(13)));
+    // The window is checked at the start time
+    assert_eq!(start_delay(Some(now + Duration::hours(14)), window, &now), Some(Duration::hours(14)));
+    assert_eq!(start_delay(Some(now + Duration::hours(19)), window, &now), Some(Duration::hours(37)));
+
+    let mut item = DownloadItem::new("https://example.com/v", "mp4");
+    let change = ScheduleChange { at: Some(now + Duration::hours(2)), window, clear: false };
+    change.apply(&mut item.scheduled_at, &mut item.start_window);
+    assert_eq!(item.start_delay(&now), Some(Duration::hours(13)));
+    ScheduleChange { clear: true, ..Default::default() }.apply(&mut item.scheduled_at, &mut item.start_window);
+    assert_eq!(item.start_delay(&now), None);
+}
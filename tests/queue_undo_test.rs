@@ -0,0 +1,92 @@
+// tests/queue_undo_test.rs
+mod common;
+
+use common::{download_map, item, temp_dir};
+use rustloader::download_manager::DownloadStatus;
+use rustloader::queue_undo::{
+    load_snapshots, push_snapshot, restore_snapshot, save_snapshots, QueueSnapshot, MAX_QUEUE_SNAPSHOTS,
+};
+
+#[test]
+fn test_restore_removed_downloads() {
+    let mut failed = item("a");
+    failed.mark_failed(Some("HTTP 403".to_string()));
+    let queued = item("b");
+    let snapshot = QueueSnapshot::new("clear-failed", vec![failed.clone()], vec![queued.id.clone()]);
+
+    let mut downloads = download_map(std::slice::from_ref(&queued));
+    let mut queue = vec![queued.id.clone()];
+    let restored = restore_snapshot(&mut queue, &mut downloads, &snapshot);
+
+    assert_eq!(restored, vec![failed.id.clone()]);
+    assert_eq!(downloads.get(&failed.id).unwrap().status, DownloadStatus::Failed);
+    assert_eq!(queue, vec![queued.id]);
+}
+
+#[test]
+fn test_restore_canceled_downloads_in_order() {
+    let first = item("a");
+    let second = item("b");
+    let mut running = item("c");
+    running.mark_started();
+    let later = item("d");
+    let snapshot = QueueSnapshot::new(
+        "cancel",
+        vec![first.clone(), second.clone(), running.clone()],
+        vec![first.id.clone(), second.id.clone()],
+    );
+
+    // After the cancel: everything canceled, and one download queued since
+    let mut downloads = download_map(&[first.clone(), second.clone(), running.clone(), later.clone()]);
+    for id in [&first.id, &second.id, &running.id] {
+        downloads.get_mut(id).unwrap().cancel();
+    }
+    let mut queue = vec![later.id.clone()];
+
+    let restored = restore_snapshot(&mut queue, &mut downloads, &snapshot);
+    assert_eq!(restored.len(), 3);
+    // The running download restarts first, then the old order, then the newcomer
+    assert_eq!(queue, vec![running.id.clone(), first.id.clone(), second.id.clone(), later.id]);
+    assert_eq!(downloads.get(&running.id).unwrap().status, DownloadStatus::Queued);
+}
+
+#[test]
+fn test_restore_skips_changed_downloads() {
+    let mut failed = item("a");
+    failed.mark_failed(None);
+    let snapshot = QueueSnapshot::new("clear-failed", vec![failed.clone()], Vec::new());
+
+    // Re-added and finished since it was cleared
+    let mut again = failed.clone();
+    again.mark_completed(None);
+    let mut downloads = download_map(&[again]);
+    let mut queue = Vec::new();
+
+    assert!(restore_snapshot(&mut queue, &mut downloads, &snapshot).is_empty());
+    assert_eq!(downloads.get(&failed.id).unwrap().status, DownloadStatus::Completed);
+}
+
+#[test]
+fn test_history_is_bounded() {
+    let mut history = Vec::new();
+    for n in 0..MAX_QUEUE_SNAPSHOTS + 3 {
+        push_snapshot(&mut history, QueueSnapshot::new(&format!("op{}", n), Vec::new(), Vec::new()));
+    }
+    assert_eq!(history.len(), MAX_QUEUE_SNAPSHOTS);
+    assert_eq!(history[0].operation, "op3");
+    assert_eq!(history.last().unwrap().operation, format!("op{}", MAX_QUEUE_SNAPSHOTS + 2));
+}
+
+#[test]
+fn test_snapshots_round_trip() {
+    let tmp = temp_dir("undo");
+    let dir = tmp.path().to_path_buf();
+    let path = dir.join("queue_snapshots.json");
+
+    assert!(load_snapshots(&path).unwrap().is_empty());
+    let snapshot = QueueSnapshot::new("clear-completed", vec![item("a")], Vec::new());
+    save_snapshots(&path, &[snapshot]).unwrap();
+    let loaded = load_snapshots(&path).unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert!(loaded[0].describe().starts_with("clear-completed (1 download) at "));
+}
@@ -0,0 +1,80 @@
+// tests/queue_wait_test.rs
+mod common;
+
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+use rustloader::queue_wait::{settled_outcome, wait_for_downloads, WaitOutcome, WaitTarget};
+use std::time::Duration;
+
+fn item(id: &str, status: DownloadStatus) -> DownloadItem {
+    let mut item = common::item(id);
+    item.status = status;
+    item
+}
+
+#[test]
+fn test_single_download() {
+    let mut items = vec![item("a", DownloadStatus::Downloading), item("b", DownloadStatus::Failed)];
+    let target = WaitTarget::Download(items[0].id.clone());
+    assert_eq!(settled_outcome(&target, &items).unwrap(), None);
+
+    items[0].status = DownloadStatus::Completed;
+    // The other download's failure doesn't matter
+    assert_eq!(settled_outcome(&target, &items).unwrap(), Some(WaitOutcome::Completed));
+
+    items[0].status = DownloadStatus::Canceled;
+    assert_eq!(settled_outcome(&target, &items).unwrap(), Some(WaitOutcome::Canceled));
+
+    assert!(settled_outcome(&WaitTarget::Download("dl_missing".to_string()), &items).is_err());
+}
+
+#[test]
+fn test_whole_queue() {
+    assert_eq!(settled_outcome(&WaitTarget::All, &[]).unwrap(), Some(WaitOutcome::Completed));
+
+    let mut items = vec![item("a", DownloadStatus::Completed), item("b", DownloadStatus::Queued), item("c", DownloadStatus::Paused)];
+    assert_eq!(settled_outcome(&WaitTarget::All, &items).unwrap(), None);
+
+    items[1].status = DownloadStatus::Canceled;
+    assert_eq!(settled_outcome(&WaitTarget::All, &items).unwrap(), Some(WaitOutcome::Canceled));
+
+    items.push(item("d", DownloadStatus::Failed));
+    assert_eq!(settled_outcome(&WaitTarget::All, &items).unwrap(), Some(WaitOutcome::Failed));
+}
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(WaitOutcome::Completed.exit_code(), 0);
+    assert_eq!(WaitOutcome::Failed.exit_code(), 3);
+    assert_eq!(WaitOutcome::Canceled.exit_code(), 4);
+    assert_eq!(WaitOutcome::TimedOut.exit_code(), 124);
+}
+
+#[tokio::test]
+async fn test_wait_until_finished() {
+    let mut download = item("a", DownloadStatus::Downloading);
+    let target = WaitTarget::Download(download.id.clone());
+    let mut polls = 0;
+    let outcome = wait_for_downloads(&target, None, Duration::from_millis(1), || {
+        polls += 1;
+        if polls == 3 {
+            download.status = DownloadStatus::Completed;
+        }
+        Ok(vec![download.clone()])
+    })
+    .await
+    .unwrap();
+    assert_eq!(outcome, WaitOutcome::Completed);
+    assert_eq!(polls, 3);
+}
+
+#[tokio::test]
+async fn test_wait_times_out() {
+    let download = item("a", DownloadStatus::Queued);
+    let target = WaitTarget::All;
+    let outcome = wait_for_downloads(&target, Some(Duration::from_millis(20)), Duration::from_millis(5), || {
+        Ok(vec![download.clone()])
+    })
+    .await
+    .unwrap();
+    assert_eq!(outcome, WaitOutcome::TimedOut);
+}
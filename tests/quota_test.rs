@@ -0,0 +1,42 @@
+// tests/quota_test.rs
+use chrono::{Duration, Local, TimeZone, Timelike};
+use rustloader::quota::{next_reset, QuotaStatus};
+
+#[test]
+fn test_resets_at_the_next_local_midnight() {
+    let now = Local.with_ymd_and_hms(2026, 3, 10, 18, 0, 0).unwrap();
+    let reset = next_reset(now);
+    assert_eq!(reset, Local.with_ymd_and_hms(2026, 3, 11, 0, 0, 0).unwrap());
+
+    let now = Local::now();
+    let reset = next_reset(now);
+    assert!(reset > now && reset - now <= Duration::hours(25));
+    assert_eq!((reset.hour(), reset.minute()), (0, 0));
+}
+
+#[test]
+fn test_describes_remaining_downloads() {
+    let now = Local.with_ymd_and_hms(2026, 3, 10, 18, 0, 0).unwrap();
+    let quota = QuotaStatus::new(2, 5, now);
+    assert_eq!(quota.remaining, 3);
+    assert_eq!(quota.describe(now), "3 of 5 downloads left today, resets in 6h 0m");
+}
+
+#[test]
+fn test_remaining_never_goes_negative() {
+    let now = Local.with_ymd_and_hms(2026, 3, 10, 23, 30, 0).unwrap();
+    let quota = QuotaStatus::new(7, 5, now);
+    assert_eq!(quota.remaining, 0);
+    assert_eq!(quota.resets_in(now), Duration::minutes(30));
+    assert_eq!(quota.resets_in(now + Duration::hours(2)), Duration::zero());
+}
+
+#[test]
+fn test_serializes_for_the_gui() {
+    let now = Local.with_ymd_and_hms(2026, 3, 10, 18, 0, 0).unwrap();
+    let json = serde_json::to_value(QuotaStatus::new(1, 5, now)).unwrap();
+    assert_eq!(json["used"], 1);
+    assert_eq!(json["limit"], 5);
+    assert_eq!(json["remaining"], 4);
+    assert!(json["resets_at"].is_string());
+}
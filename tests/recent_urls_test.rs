@@ -0,0 +1,45 @@
+// tests/recent_urls_test.rs
+use chrono::{Duration, Utc};
+use rustloader::recent_urls::{RecentSources, MAX_RECENT_SOURCES};
+
+#[test]
+fn test_record_moves_to_front_and_keeps_channel() {
+    let now = Utc::now();
+    let mut recent = RecentSources::default();
+
+    recent.record("https://www.youtube.com/watch?v=dQw4w9WgXcQ", Some("Rick Astley"), now);
+    recent.record("https://vimeo.com/76979871", None, now + Duration::seconds(1));
+    recent.record("https://www.youtube.com/watch?v=dQw4w9WgXcQ", None, now + Duration::seconds(2));
+
+    assert_eq!(recent.sources.len(), 2);
+    assert_eq!(recent.sources[0].url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+    assert_eq!(recent.sources[0].channel.as_deref(), Some("Rick Astley"));
+    assert_eq!(recent.sources[0].use_count, 2);
+}
+
+#[test]
+fn test_record_is_bounded() {
+    let now = Utc::now();
+    let mut recent = RecentSources::default();
+    for i in 0..(MAX_RECENT_SOURCES + 5) {
+        recent.record(&format!("https://example.com/{}", i), None, now);
+    }
+    assert_eq!(recent.sources.len(), MAX_RECENT_SOURCES);
+    assert_eq!(recent.sources[0].url, format!("https://example.com/{}", MAX_RECENT_SOURCES + 4));
+}
+
+#[test]
+fn test_suggest_matches_url_and_channel() {
+    let now = Utc::now();
+    let mut recent = RecentSources::default();
+    recent.record("https://www.youtube.com/watch?v=dQw4w9WgXcQ", Some("Rick Astley"), now);
+    recent.record("https://vimeo.com/76979871", None, now);
+
+    assert_eq!(recent.suggest("youtube.com", 10), vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+    assert_eq!(recent.suggest("https://vim", 10), vec!["https://vimeo.com/76979871"]);
+    assert_eq!(recent.suggest("rick", 10), vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+    assert!(recent.suggest("dailymotion", 10).is_empty());
+
+    // An empty prefix lists the most recent sources
+    assert_eq!(recent.suggest("", 1), vec!["https://vimeo.com/76979871"]);
+}
@@ -0,0 +1,57 @@
+// tests/region_overrides_test.rs
+use rustloader::http_headers::{RequestHeaders, DEFAULT_USER_AGENT};
+use rustloader::region_overrides::{parse_country_code, parse_player_clients, RegionOverrides};
+
+#[test]
+fn test_player_clients() {
+    assert_eq!(parse_player_clients("android").unwrap(), vec!["android"]);
+    assert_eq!(parse_player_clients(" TV , web,tv").unwrap(), vec!["tv", "web"]);
+    assert!(parse_player_clients("android,").is_err());
+    assert!(parse_player_clients("android;rm").is_err());
+    assert!(parse_player_clients("desktop").is_err());
+}
+
+#[test]
+fn test_country_codes() {
+    assert_eq!(parse_country_code("de").unwrap(), "DE");
+    assert_eq!(parse_country_code(" US ").unwrap(), "US");
+    assert!(parse_country_code("USA").is_err());
+    assert!(parse_country_code("1A").is_err());
+    assert!(parse_country_code("").is_err());
+}
+
+#[test]
+fn test_from_args() {
+    let none = RegionOverrides::from_args(None, None).unwrap();
+    assert!(none.is_empty());
+    assert_eq!(none.extractor_args(), None);
+
+    let region = RegionOverrides::from_args(Some("android,ios"), Some("jp")).unwrap();
+    assert_eq!(region.extractor_args().as_deref(), Some("youtube:player_client=android,ios"));
+    assert_eq!(region.geo_bypass_country.as_deref(), Some("JP"));
+    assert_eq!(region.describe(), "player client android, ios, country JP");
+}
+
+#[test]
+fn test_ytdlp_options() {
+    let region = RegionOverrides::from_args(Some("tv"), Some("US")).unwrap();
+    let headers = RequestHeaders::default().with_region(region);
+    assert_eq!(
+        headers.ytdlp_args(),
+        vec![
+            "--user-agent",
+            DEFAULT_USER_AGENT,
+            "--extractor-args",
+            "youtube:player_client=tv",
+            "--geo-bypass-country",
+            "US"
+        ]
+    );
+
+    // An age-gate retry replaces the chosen player client but keeps the country
+    let age_gate = headers.with_extractor_args(Some("youtube:player_client=web_embedded".to_string()));
+    let args = age_gate.ytdlp_args();
+    assert!(args.contains(&"youtube:player_client=web_embedded".to_string()));
+    assert!(!args.contains(&"youtube:player_client=tv".to_string()));
+    assert!(args.contains(&"--geo-bypass-country".to_string()));
+}
@@ -0,0 +1,103 @@
+// tests/retention_test.rs
+mod common;
+
+use common::temp_dir;
+use chrono::{Duration, Utc};
+use rustloader::download_manager::DownloadItem;
+use rustloader::retention::{delete_expired_files, parse_tag, plan_gc, validate_policies, GcAction, RetentionPolicy};
+use std::fs;
+use std::path::Path;
+
+fn finished(dir: &Path, name: &str, tags: &[&str], days_ago: i64) -> DownloadItem {
+    let path = dir.join(format!("{}.mp4", name));
+    fs::write(&path, b"video").unwrap();
+    let mut item = DownloadItem::new(&format!("https://example.com/{}", name), "mp4");
+    item.title = Some(name.to_string());
+    item.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    item.mark_completed(Some(path.to_string_lossy().into_owned()));
+    item.finished_at = Some(Utc::now() - Duration::days(days_ago));
+    item
+}
+
+fn temp_files_policy() -> RetentionPolicy {
+    RetentionPolicy {
+        tag: Some("temp".to_string()),
+        delete_files_after_days: Some(7),
+        remove_entries_after_days: None,
+    }
+}
+
+#[test]
+fn test_parse_tag() {
+    assert_eq!(parse_tag(" Temp ").unwrap(), "temp");
+    assert_eq!(parse_tag("watch_later-2").unwrap(), "watch_later-2");
+    assert!(parse_tag("").is_err());
+    assert!(parse_tag("a b").is_err());
+    assert!(parse_tag("../x").is_err());
+}
+
+#[test]
+fn test_policy_without_limits_is_rejected() {
+    assert!(validate_policies(&[temp_files_policy()]).is_ok());
+    assert!(validate_policies(&[RetentionPolicy::default()]).is_err());
+}
+
+#[test]
+fn test_deletes_files_of_expired_tagged_downloads_only() {
+    let tmp = temp_dir("retention_tagged");
+    let dir = tmp.path().to_path_buf();
+    let old_temp = finished(&dir, "old_temp", &["temp"], 10);
+    let new_temp = finished(&dir, "new_temp", &["temp"], 2);
+    let old_kept = finished(&dir, "old_kept", &["keep"], 10);
+
+    let plan = plan_gc(&[temp_files_policy()], &[old_temp.clone(), new_temp, old_kept], &[], Utc::now());
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].id, old_temp.id);
+    assert_eq!(plan[0].action, GcAction::DeleteFile);
+    assert_eq!(plan[0].bytes, 5);
+
+    // A dry run is just the plan; the file is only gone once deleted
+    let path = plan[0].path.clone().unwrap();
+    assert!(path.is_file());
+    assert_eq!(delete_expired_files(&plan).len(), 1);
+    assert!(!path.exists());
+    // Nothing left to expire
+    assert!(plan_gc(&[temp_files_policy()], &[old_temp], &[], Utc::now()).is_empty());
+}
+
+#[test]
+fn test_removes_entries_from_the_queue_but_not_the_archive() {
+    let tmp = temp_dir("retention_entries");
+    let dir = tmp.path().to_path_buf();
+    let policy = RetentionPolicy {
+        tag: None,
+        delete_files_after_days: None,
+        remove_entries_after_days: Some(30),
+    };
+    let queued = finished(&dir, "queued", &[], 31);
+    let archived = finished(&dir, "archived", &[], 31);
+    let recent = finished(&dir, "recent", &[], 29);
+
+    let plan = plan_gc(&[policy], &[queued.clone(), recent], &[archived], Utc::now());
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].id, queued.id);
+    assert_eq!(plan[0].action, GcAction::RemoveEntry);
+    assert!(plan[0].describe().ends_with("queued: remove from the queue"));
+}
+
+#[test]
+fn test_shortest_matching_limit_wins_and_unfinished_downloads_are_kept() {
+    let tmp = temp_dir("retention_limits");
+    let dir = tmp.path().to_path_buf();
+    let everything = RetentionPolicy {
+        tag: None,
+        delete_files_after_days: Some(90),
+        remove_entries_after_days: None,
+    };
+    let old_temp = finished(&dir, "old_temp", &["temp"], 8);
+    let mut running = finished(&dir, "running", &["temp"], 8);
+    running.mark_started();
+
+    let plan = plan_gc(&[everything, temp_files_policy()], &[old_temp.clone(), running], &[], Utc::now());
+    assert_eq!(plan.iter().map(|item| item.id.as_str()).collect::<Vec<_>>(), vec![old_temp.id.as_str()]);
+}
@@ -1,5 +1,8 @@
 // tests/security_test.rs
-use rustloader::security::{apply_rate_limit, detect_command_injection};
+use rustloader::security::{
+    apply_rate_limit, detect_command_injection, generate_hmac_signature, get_local_state_key,
+    verify_hmac_signature,
+};
 // rustloader::error::AppError not directly used in this test
 use std::path::Path;
 use std::time::Duration;
@@ -71,4 +74,39 @@ fn test_path_safety_validation() {
     // Path traversal attempts should be rejected
     let traversal_path = home_dir.join("..").join("..").join("etc").join("passwd");
     assert!(validate_path_safety(&traversal_path).is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_local_state_signature() {
+    let key = get_local_state_key();
+    assert_eq!(key.len(), 16);
+    assert_eq!(key, get_local_state_key());
+
+    let data = br#"{"downloads": []}"#;
+    let signature = generate_hmac_signature(data, &key).unwrap();
+    assert!(verify_hmac_signature(data, &signature, &key).unwrap());
+
+    // Any modification to the signed content must be detected
+    let tampered = br#"{"downloads": [{}]}"#;
+    assert!(!verify_hmac_signature(tampered, &signature, &key).unwrap());
+}
+
+#[test]
+fn test_rejected_paths_suggest_a_corrected_path() {
+    use rustloader::error::AppError;
+    use rustloader::security::{suggest_safe_path, validate_path_safety};
+    use std::path::Path;
+
+    let home = dirs_next::home_dir().unwrap();
+    assert_eq!(
+        suggest_safe_path("~/Videos"),
+        Some(home.join("Videos").to_string_lossy().into_owned())
+    );
+    assert_eq!(suggest_safe_path("videos/../music/./x"), Some("music/x".to_string()));
+    assert_eq!(suggest_safe_path("videos;rm"), Some("videosrm".to_string()));
+    assert_eq!(suggest_safe_path("videos"), None);
+
+    let error = validate_path_safety(Path::new("videos/../../secret")).unwrap_err();
+    assert!(matches!(error, AppError::InvalidPath { .. }));
+    assert_eq!(error.suggestion(), Some("secret"));
+}
@@ -0,0 +1,76 @@
+// tests/silence_test.rs
+use rustloader::silence::{detect_command, parse_silencedetect, trim_command, SilenceInterval, SilenceSettings};
+use std::path::Path;
+
+const LOG: &str = "\
+Input #0, mp3, from '/music/song.mp3':
+  Duration: 00:03:20.00, start: 0.025057, bitrate: 128 kb/s
+  Stream #0:0: Audio: mp3, 44100 Hz, stereo, fltp, 128 kb/s
+[silencedetect @ 0x5581] silence_start: 0
+[silencedetect @ 0x5581] silence_end: 12.5 | silence_duration: 12.5
+[silencedetect @ 0x5581] silence_start: 95.2
+[silencedetect @ 0x5581] silence_end: 96.1 | silence_duration: 0.9
+[silencedetect @ 0x5581] silence_start: 191.75
+size=N/A time=00:03:20.00 bitrate=N/A speed= 512x
+";
+
+#[test]
+fn test_parse_silencedetect() {
+    let report = parse_silencedetect(LOG);
+    assert_eq!(report.duration, Some(200.0));
+    assert_eq!(
+        report.intervals,
+        vec![
+            SilenceInterval { start: 0.0, end: Some(12.5) },
+            SilenceInterval { start: 95.2, end: Some(96.1) },
+            SilenceInterval { start: 191.75, end: None },
+        ]
+    );
+}
+
+#[test]
+fn test_audible_range_keeps_middle_silence() {
+    assert_eq!(parse_silencedetect(LOG).audible_range(), Some((12.5, 191.75)));
+}
+
+#[test]
+fn test_audible_range_without_edge_silence() {
+    let log = "  Duration: 00:01:00.00, start: 0.000000\n\
+               [silencedetect @ 0x1] silence_start: 20\n\
+               [silencedetect @ 0x1] silence_end: 21 | silence_duration: 1\n";
+    assert_eq!(parse_silencedetect(log).audible_range(), None);
+
+    // Nothing but silence: leave the file alone
+    let log = "  Duration: 00:00:10.00, start: 0.000000\n\
+               [silencedetect @ 0x1] silence_start: 0\n\
+               [silencedetect @ 0x1] silence_end: 10 | silence_duration: 10\n";
+    assert_eq!(parse_silencedetect(log).audible_range(), None);
+}
+
+#[test]
+fn test_settings_validation() {
+    assert!(SilenceSettings::default().validate().is_ok());
+    assert!(SilenceSettings { threshold_db: 10.0, ..Default::default() }.validate().is_err());
+    assert!(SilenceSettings { min_duration_secs: 0.0, ..Default::default() }.validate().is_err());
+}
+
+#[test]
+fn test_commands() {
+    let detect = detect_command(Path::new("/music/song.mp3"), &SilenceSettings::default()).to_args().unwrap();
+    assert_eq!(
+        detect,
+        ["-hide_banner", "-nostats", "-i", "/music/song.mp3", "-af", "silencedetect=noise=-50dB:d=0.5", "-f", "null", "-"]
+    );
+
+    let trim = trim_command(Path::new("/music/song.mp3"), Path::new("/music/.song.trimmed.mp3"), 12.5, 191.75)
+        .to_args()
+        .unwrap();
+    assert_eq!(
+        trim,
+        [
+            "-hide_banner", "-loglevel", "error", "-i", "/music/song.mp3",
+            "-ss", "12.500", "-to", "191.750", "-map", "0", "-c", "copy",
+            "/music/.song.trimmed.mp3",
+        ]
+    );
+}
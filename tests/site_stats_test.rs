@@ -0,0 +1,88 @@
+// tests/site_stats_test.rs
+use chrono::{Duration, Utc};
+use rustloader::download_manager::DownloadItem;
+use rustloader::site_stats::{aggregate_site_stats, classify_failure, failure_kind, site_of, FailureKind};
+
+fn completed(url: &str, bytes: u64, secs: i64) -> DownloadItem {
+    let mut item = DownloadItem::new(url, "mp4");
+    let finished = Utc::now();
+    item.started_at = Some(finished - Duration::seconds(secs));
+    item.total_bytes = bytes;
+    item.mark_completed(None);
+    item.finished_at = Some(finished);
+    item
+}
+
+fn failed(url: &str, error: &str) -> DownloadItem {
+    let mut item = DownloadItem::new(url, "mp4");
+    item.mark_failed(Some(error.to_string()));
+    item
+}
+
+#[test]
+fn test_site_of_strips_common_prefixes() {
+    assert_eq!(site_of("https://www.youtube.com/watch?v=abc"), "youtube.com");
+    assert_eq!(site_of("https://m.youtube.com/watch?v=abc"), "youtube.com");
+    assert_eq!(site_of("https://music.youtube.com/watch?v=abc"), "youtube.com");
+    assert_eq!(site_of("https://VIMEO.com/123"), "vimeo.com");
+    assert_eq!(site_of("not a url"), "unknown");
+}
+
+#[test]
+fn test_failure_kind_classifies_errors() {
+    assert_eq!(
+        failure_kind(&failed("https://x.com/a", "ERROR: [youtube] abc: Unable to extract uploader id")),
+        FailureKind::Extractor
+    );
+    assert_eq!(failure_kind(&failed("https://x.com/a", "HTTP Error 429: Too Many Requests")), FailureKind::RateLimited);
+    assert_eq!(failure_kind(&failed("https://x.com/a", "HTTP Error 403: Forbidden")), FailureKind::Forbidden);
+    assert_eq!(failure_kind(&failed("https://x.com/a", "Private video")), FailureKind::Unavailable);
+    assert_eq!(
+        failure_kind(&failed("https://x.com/a", "The uploader has not made this video available in your country")),
+        FailureKind::Unavailable
+    );
+    assert_eq!(failure_kind(&failed("https://x.com/a", "something odd")), FailureKind::Other);
+
+    // The yt-dlp output is considered along with the message
+    let mut item = failed("https://x.com/a", "yt-dlp exited with status 1");
+    item.error_context = vec!["ERROR: Postprocessing: ffmpeg exited with code 1".to_string()];
+    assert_eq!(failure_kind(&item), FailureKind::PostProcessing);
+}
+
+#[test]
+fn test_classify_failure_recognizes_outdated_extractors() {
+    let context = vec!["ERROR: [youtube] abc: Signature extraction failed: Some formats may be missing".to_string()];
+    assert_eq!(classify_failure("yt-dlp exited with status 1", &context), FailureKind::Extractor);
+    assert_eq!(classify_failure("ERROR: Unable to extract initial player response", &[]), FailureKind::Extractor);
+    assert_ne!(classify_failure("HTTP Error 404: Not Found", &[]), FailureKind::Extractor);
+}
+
+#[test]
+fn test_aggregate_site_stats() {
+    let downloads = vec![
+        completed("https://www.youtube.com/watch?v=1", 1000, 10),
+        completed("https://youtube.com/watch?v=2", 3000, 10),
+        failed("https://youtube.com/watch?v=3", "HTTP Error 403: Forbidden"),
+        failed("https://vimeo.com/1", "Unable to extract video data"),
+        failed("https://vimeo.com/2", "Unable to extract video data"),
+        failed("https://vimeo.com/3", "timed out"),
+        // Unfinished downloads are ignored
+        DownloadItem::new("https://example.com/video", "mp4"),
+    ];
+
+    let stats = aggregate_site_stats(&downloads);
+    assert_eq!(stats.len(), 2);
+
+    let youtube = stats.iter().find(|site| site.site == "youtube.com").unwrap();
+    assert_eq!((youtube.completed, youtube.failed), (2, 1));
+    assert!((youtube.success_rate - 2.0 / 3.0).abs() < 1e-9);
+    assert!((youtube.average_speed.unwrap() - 200.0).abs() < 1e-6);
+    assert_eq!(youtube.failure_kinds, vec![(FailureKind::Forbidden, 1)]);
+    assert!(!youtube.needs_ytdlp_update());
+
+    let vimeo = stats.iter().find(|site| site.site == "vimeo.com").unwrap();
+    assert_eq!(vimeo.success_rate, 0.0);
+    assert_eq!(vimeo.average_speed, None);
+    assert_eq!(vimeo.failure_kinds, vec![(FailureKind::Extractor, 2), (FailureKind::Network, 1)]);
+    assert!(vimeo.needs_ytdlp_update());
+}
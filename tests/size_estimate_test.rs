@@ -0,0 +1,31 @@
+// tests/size_estimate_test.rs
+use rustloader::downloader::estimate_size_from_metadata;
+use serde_json::json;
+
+#[test]
+fn test_single_format_size() {
+    assert_eq!(estimate_size_from_metadata(&json!({"filesize": 1_048_576})), Some(1_048_576));
+    assert_eq!(estimate_size_from_metadata(&json!({"filesize": null, "filesize_approx": 2048.7})), Some(2048));
+    assert_eq!(estimate_size_from_metadata(&json!({"title": "No size"})), None);
+}
+
+#[test]
+fn test_merged_formats_are_summed() {
+    let info = json!({
+        "filesize": null,
+        "requested_formats": [
+            {"format_id": "137", "filesize": 50_000_000},
+            {"format_id": "140", "filesize_approx": 3_000_000}
+        ]
+    });
+    assert_eq!(estimate_size_from_metadata(&info), Some(53_000_000));
+
+    // A partial estimate would understate the total, so report nothing
+    let partial = json!({
+        "requested_formats": [
+            {"format_id": "137", "filesize": 50_000_000},
+            {"format_id": "140"}
+        ]
+    });
+    assert_eq!(estimate_size_from_metadata(&partial), None);
+}
@@ -0,0 +1,118 @@
+// tests/smart_defaults_test.rs
+
+use chrono::{Duration, TimeZone, Utc};
+use rustloader::download_manager::{DownloadItem, DownloadStatus};
+use rustloader::recent_urls::RecentSources;
+use rustloader::smart_defaults::{channel_of, known_channels, suggest_from_history, SmartDefaults};
+use std::collections::HashMap;
+
+fn download(url: &str, format: &str, bitrate: Option<&str>, minutes_ago: i64) -> DownloadItem {
+    let mut item = DownloadItem::new(url, format);
+    item.status = DownloadStatus::Completed;
+    item.bitrate = bitrate.map(str::to_string);
+    item.finished_at = Some(Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap() - Duration::minutes(minutes_ago));
+    item
+}
+
+fn channels(urls: &[(&str, &str)]) -> HashMap<String, String> {
+    urls.iter().map(|(url, channel)| (url.to_string(), channel.to_string())).collect()
+}
+
+#[test]
+fn test_suggests_options_channel_always_uses() {
+    let history = vec![
+        download("https://example.com/a", "mp3", Some("128K"), 30),
+        download("https://example.com/b", "mp3", Some("128K"), 20),
+        download("https://example.com/c", "mp3", Some("128K"), 10),
+        download("https://example.com/other", "mp4", None, 5),
+    ];
+    let channels = channels(&[
+        ("https://example.com/a", "Lofi Radio"),
+        ("https://example.com/b", "Lofi Radio"),
+        ("https://example.com/c", "lofi radio"),
+        ("https://example.com/other", "News"),
+    ]);
+
+    let suggested = suggest_from_history("Lofi Radio", &history, &channels).unwrap();
+    assert_eq!(suggested.format, "mp3");
+    assert_eq!(suggested.bitrate.as_deref(), Some("128K"));
+    assert_eq!(suggested.quality, None);
+    assert_eq!(suggested.based_on, 3);
+    assert_eq!(suggested.as_flags(), "--format mp3 --video-bitrate 128K");
+
+    // Plain defaults aren't worth suggesting
+    assert_eq!(suggest_from_history("News", &history, &channels), None);
+}
+
+#[test]
+fn test_needs_enough_agreeing_downloads() {
+    let channels = channels(&[
+        ("https://example.com/a", "Lofi Radio"),
+        ("https://example.com/b", "Lofi Radio"),
+        ("https://example.com/c", "Lofi Radio"),
+    ]);
+
+    let too_few = vec![
+        download("https://example.com/a", "mp3", Some("128K"), 30),
+        download("https://example.com/b", "mp3", Some("128K"), 20),
+    ];
+    assert_eq!(suggest_from_history("Lofi Radio", &too_few, &channels), None);
+
+    let mixed = vec![
+        download("https://example.com/a", "mp3", Some("128K"), 30),
+        download("https://example.com/b", "mp3", Some("320K"), 20),
+        download("https://example.com/c", "mp3", Some("128K"), 10),
+    ];
+    assert_eq!(suggest_from_history("Lofi Radio", &mixed, &channels), None);
+
+    // Failed downloads don't count
+    let mut failed = mixed.clone();
+    failed[1].status = DownloadStatus::Failed;
+    assert_eq!(suggest_from_history("Lofi Radio", &failed, &channels), None);
+}
+
+#[test]
+fn test_only_recent_downloads_count() {
+    let urls: Vec<String> = (0..6).map(|i| format!("https://example.com/{}", i)).collect();
+    let channels: HashMap<String, String> =
+        urls.iter().map(|url| (url.clone(), "Lofi Radio".to_string())).collect();
+
+    // An old mp4 download falls outside the five most recent
+    let mut history = vec![download(&urls[0], "mp4", None, 600)];
+    history.extend(urls[1..].iter().enumerate().map(|(i, url)| download(url, "mp3", None, i as i64)));
+
+    let suggested = suggest_from_history("Lofi Radio", &history, &channels).unwrap();
+    assert_eq!(suggested.format, "mp3");
+    assert_eq!(suggested.based_on, 5);
+}
+
+#[test]
+fn test_known_channels() {
+    let mut recent = RecentSources::default();
+    recent.record("https://example.com/a", Some("Lofi Radio"), Utc::now());
+    recent.record("https://example.com/b", None, Utc::now());
+    recent.set_channel("https://example.com/b", "News");
+    recent.set_channel("https://example.com/unknown", "Ignored");
+
+    let channels = known_channels(&recent);
+    assert_eq!(channels.len(), 2);
+    assert_eq!(channels["https://example.com/a"], "Lofi Radio");
+    assert_eq!(channels["https://example.com/b"], "News");
+}
+
+#[test]
+fn test_channel_of() {
+    assert_eq!(channel_of(&serde_json::json!({"channel": "Lofi Radio", "uploader": "lofi"})).as_deref(), Some("Lofi Radio"));
+    assert_eq!(channel_of(&serde_json::json!({"uploader": "lofi"})).as_deref(), Some("lofi"));
+    assert_eq!(channel_of(&serde_json::json!({"channel": "  "})), None);
+}
+
+#[test]
+fn test_smart_defaults_modes() {
+    assert_eq!(SmartDefaults::default(), SmartDefaults::Hint);
+    for mode in [SmartDefaults::Off, SmartDefaults::Hint, SmartDefaults::Apply] {
+        assert_eq!(SmartDefaults::parse(mode.as_str()).unwrap(), mode);
+    }
+    assert!(SmartDefaults::parse("always").is_err());
+    assert_eq!(serde_json::to_string(&SmartDefaults::Apply).unwrap(), "\"apply\"");
+}
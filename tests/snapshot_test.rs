@@ -0,0 +1,78 @@
+// tests/snapshot_test.rs
+use rustloader::snapshot::{frame_command, parse_timestamp, timestamp_label, video_stream, ImageFormat, SnapshotRequest};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("00:12:34").unwrap(), 754.0);
+    assert_eq!(parse_timestamp("12:34.5").unwrap(), 754.5);
+    assert_eq!(parse_timestamp("754").unwrap(), 754.0);
+    assert_eq!(parse_timestamp("90:00").unwrap(), 5400.0);
+
+    for invalid in ["", "1:2:3:4", "00:60:00", "00:00:60", "-5", "ab", "1::2"] {
+        assert!(parse_timestamp(invalid).is_err(), "{} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn test_timestamp_label() {
+    assert_eq!(timestamp_label(754.0), "00-12-34");
+    assert_eq!(timestamp_label(3723.25), "01-02-03.250");
+}
+
+#[test]
+fn test_request_times() {
+    let request = SnapshotRequest {
+        at: 10.0,
+        count: 3,
+        interval: 2.5,
+        image_format: ImageFormat::Png,
+        output_dir: PathBuf::from("/tmp"),
+    };
+    assert_eq!(request.times(), vec![10.0, 12.5, 15.0]);
+}
+
+#[test]
+fn test_video_stream_from_merged_selection() {
+    let info = json!({
+        "title": "Example",
+        "requested_formats": [
+            {
+                "format_id": "137",
+                "vcodec": "avc1.640028",
+                "url": "https://cdn.example.com/video.mp4",
+                "protocol": "https",
+                "http_headers": {"User-Agent": "test"}
+            },
+            {"format_id": "140", "vcodec": "none", "url": "https://cdn.example.com/audio.m4a", "protocol": "https"}
+        ]
+    });
+    let stream = video_stream(&info).unwrap();
+    assert_eq!(stream.format_id.as_deref(), Some("137"));
+    assert_eq!(stream.url, "https://cdn.example.com/video.mp4");
+    assert_eq!(stream.http_headers, vec![("User-Agent".to_string(), "test".to_string())]);
+    assert!(stream.supports_ranges());
+}
+
+#[test]
+fn test_segmented_streams_are_not_seekable() {
+    let info = json!({"format_id": "hls-720", "url": "https://cdn.example.com/index.m3u8", "protocol": "m3u8_native"});
+    assert!(!video_stream(&info).unwrap().supports_ranges());
+}
+
+#[test]
+fn test_frame_command_seeks_before_input() {
+    let args = frame_command("/videos/clip.mp4", &[], 754.5, Path::new("/out/clip_00-12-34.500.jpg"), ImageFormat::Jpg)
+        .to_args()
+        .unwrap();
+    assert_eq!(
+        args,
+        [
+            "-hide_banner", "-loglevel", "error", "-n",
+            "-ss", "754.500", "-i", "/videos/clip.mp4",
+            "-frames:v", "1", "-q:v", "2",
+            "/out/clip_00-12-34.500.jpg",
+        ]
+    );
+}
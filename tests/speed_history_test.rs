@@ -0,0 +1,45 @@
+// tests/speed_history_test.rs
+use chrono::{Duration, Utc};
+use rustloader::download_manager::{DownloadItem, SpeedHistory, SpeedSample, SPEED_HISTORY_CAPACITY};
+
+#[test]
+fn test_speed_history_is_bounded_ring_buffer() {
+    let start = Utc::now();
+    let mut history = SpeedHistory::default();
+
+    for i in 0..(SPEED_HISTORY_CAPACITY as i64 + 10) {
+        let recorded = history.record(SpeedSample {
+            timestamp: start + Duration::seconds(i),
+            speed: i as f64,
+        });
+        assert!(recorded);
+    }
+
+    let samples = history.samples();
+    assert_eq!(samples.len(), SPEED_HISTORY_CAPACITY);
+    // The oldest samples were dropped
+    assert_eq!(samples[0].speed, 10.0);
+    assert_eq!(samples.last().unwrap().speed, (SPEED_HISTORY_CAPACITY + 9) as f64);
+}
+
+#[test]
+fn test_speed_history_throttles_samples() {
+    let start = Utc::now();
+    let mut history = SpeedHistory::default();
+
+    assert!(history.record(SpeedSample { timestamp: start, speed: 1.0 }));
+    assert!(!history.record(SpeedSample { timestamp: start + Duration::milliseconds(200), speed: 2.0 }));
+    assert!(history.record(SpeedSample { timestamp: start + Duration::seconds(1), speed: 3.0 }));
+    assert_eq!(history.samples().len(), 2);
+}
+
+#[test]
+fn test_progress_updates_record_speed() {
+    let mut item = DownloadItem::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4");
+    assert!(item.update_progress(500, 1000, 250.0));
+    assert_eq!(item.progress, 50.0);
+    assert_eq!(item.speed_history.samples().len(), 1);
+
+    item.mark_requeued();
+    assert!(item.speed_history.samples().is_empty());
+}
@@ -0,0 +1,110 @@
+// tests/split_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::split::{find_manifest, join_parts, manifest_path, parse_split_size, split_file, SplitManifest, MIN_PART_SIZE};
+use std::fs;
+use std::path::Path;
+
+fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+#[test]
+fn test_parse_split_size() {
+    assert_eq!(parse_split_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+    assert_eq!(parse_split_size("500M").unwrap(), 500 * 1024 * 1024);
+    assert!(parse_split_size("0").is_err());
+    assert!(parse_split_size("100K").is_err());
+    assert!(parse_split_size("lots").is_err());
+}
+
+#[test]
+fn test_split_and_join_round_trip() {
+    let tmp = temp_dir("split_round_trip");
+
+    let dir = tmp.path().to_path_buf();
+    let file = dir.join("Video.mp4");
+    let data = sample(MIN_PART_SIZE as usize * 2 + 12345);
+    fs::write(&file, &data).unwrap();
+
+    let manifest_file = split_file(&file, MIN_PART_SIZE).unwrap().expect("file is larger than a part");
+    assert_eq!(manifest_file, manifest_path(&file));
+    assert!(!file.exists());
+    for part in ["Video.mp4.001", "Video.mp4.002", "Video.mp4.003"] {
+        assert!(dir.join(part).exists(), "{} missing", part);
+    }
+    let manifest: SplitManifest = serde_json::from_str(&fs::read_to_string(&manifest_file).unwrap()).unwrap();
+    assert_eq!(manifest.file_name, "Video.mp4");
+    assert_eq!(manifest.size, data.len() as u64);
+    assert_eq!(manifest.parts.len(), 3);
+    assert_eq!(manifest.parts[2].size, 12345);
+
+    // Joining from a part finds the manifest
+    let joined = join_parts(&dir.join("Video.mp4.002"), None).unwrap();
+    assert_eq!(joined, dir.canonicalize().unwrap().join("Video.mp4"));
+    assert_eq!(fs::read(&joined).unwrap(), data);
+
+    // The joined file is never overwritten
+    assert!(join_parts(&manifest_file, None).is_err());
+}
+
+#[test]
+fn test_small_file_is_left_alone() {
+    let tmp = temp_dir("split_small");
+
+    let dir = tmp.path().to_path_buf();
+    let file = dir.join("Clip.mp4");
+    fs::write(&file, sample(1000)).unwrap();
+    assert_eq!(split_file(&file, MIN_PART_SIZE).unwrap(), None);
+    assert!(file.exists());
+    assert!(!manifest_path(&file).exists());
+}
+
+#[test]
+fn test_damaged_part_is_rejected() {
+    let tmp = temp_dir("split_damaged");
+
+    let dir = tmp.path().to_path_buf();
+    let file = dir.join("Video.mkv");
+    fs::write(&file, sample(MIN_PART_SIZE as usize + 100)).unwrap();
+    let manifest_file = split_file(&file, MIN_PART_SIZE).unwrap().unwrap();
+
+    let mut part = fs::read(dir.join("Video.mkv.001")).unwrap();
+    part[10] ^= 0xff;
+    fs::write(dir.join("Video.mkv.001"), part).unwrap();
+
+    assert!(join_parts(&manifest_file, None).is_err());
+    assert!(!dir.join("Video.mkv").exists());
+    assert!(!dir.join("Video.mkv.joining").exists());
+}
+
+#[test]
+fn test_manifest_names_cannot_leave_the_folder() {
+    let tmp = temp_dir("split_traversal");
+
+    let dir = tmp.path().to_path_buf();
+    let manifest = SplitManifest {
+        file_name: "../escaped.mp4".to_string(),
+        size: 0,
+        sha256: String::new(),
+        parts: Vec::new(),
+    };
+    let manifest_file = dir.join("escaped.mp4.manifest.json");
+    fs::write(&manifest_file, serde_json::to_string(&manifest).unwrap()).unwrap();
+    assert!(join_parts(&manifest_file, None).is_err());
+    assert!(!dir.parent().unwrap().join("escaped.mp4").exists());
+}
+
+#[test]
+fn test_find_manifest() {
+    assert_eq!(
+        find_manifest(Path::new("/dl/Video.mp4.007")).unwrap(),
+        Path::new("/dl/Video.mp4.manifest.json")
+    );
+    assert_eq!(
+        find_manifest(Path::new("/dl/Video.mp4.manifest.json")).unwrap(),
+        Path::new("/dl/Video.mp4.manifest.json")
+    );
+    assert!(find_manifest(Path::new("/dl/Video.mp4")).is_err());
+}
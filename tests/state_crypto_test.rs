@@ -0,0 +1,63 @@
+// tests/state_crypto_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::error::AppError;
+use rustloader::state_crypto::{decrypt, encrypt, is_encrypted, write_atomic};
+
+const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+#[test]
+fn test_encrypt_round_trip() {
+    let state = r#"{"items":[],"signature":"abc"}"#;
+    let sealed = encrypt(state, SECRET).unwrap();
+
+    assert!(is_encrypted(&sealed));
+    assert!(!sealed.contains("items"));
+    assert_eq!(decrypt(&sealed, SECRET).unwrap(), state);
+
+    // A fresh nonce is used for every write
+    assert_ne!(encrypt(state, SECRET).unwrap(), sealed);
+}
+
+#[test]
+fn test_wrong_secret_or_tampering_is_rejected() {
+    let sealed = encrypt("queue state", SECRET).unwrap();
+
+    let wrong = decrypt(&sealed, b"another secret entirely");
+    assert!(matches!(wrong, Err(AppError::SecurityViolation)));
+
+    let mut tampered = sealed.clone();
+    let last = tampered.pop().unwrap();
+    tampered.push(if last == 'A' { 'B' } else { 'A' });
+    assert!(matches!(decrypt(&tampered, SECRET), Err(AppError::SecurityViolation)));
+}
+
+#[test]
+fn test_plain_state_is_not_encrypted() {
+    assert!(!is_encrypted(r#"{"items":[]}"#));
+    assert!(matches!(decrypt("{}", SECRET), Err(AppError::ValidationError(_))));
+}
+
+#[test]
+fn test_write_atomic_replaces_file() {
+    let tmp = temp_dir("atomic");
+    let dir = tmp.path().to_path_buf();
+    let path = dir.join("queue_state.json");
+
+    write_atomic(&path, b"first").unwrap();
+    write_atomic(&path, b"second").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+
+    // No temporary files are left behind
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty());
+
+    // A failed write leaves the original alone
+    assert!(write_atomic(&dir.join("missing").join("state.json"), b"x").is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+}
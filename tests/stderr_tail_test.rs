@@ -0,0 +1,32 @@
+// tests/stderr_tail_test.rs
+use rustloader::download_manager::DownloadItem;
+use rustloader::downloader::{StderrTail, STDERR_TAIL_LINES};
+
+#[test]
+fn test_stderr_tail_keeps_last_lines() {
+    let tail = StderrTail::new();
+    let shared = tail.clone();
+
+    for i in 0..(STDERR_TAIL_LINES + 5) {
+        shared.push(&format!("line {}", i));
+    }
+
+    let lines = tail.lines();
+    assert_eq!(lines.len(), STDERR_TAIL_LINES);
+    assert_eq!(lines[0], "line 5");
+    assert_eq!(lines.last().unwrap(), &format!("line {}", STDERR_TAIL_LINES + 4));
+
+    tail.clear();
+    assert!(shared.lines().is_empty());
+}
+
+#[test]
+fn test_error_context_cleared_on_requeue() {
+    let mut item = DownloadItem::new("https://example.com/v", "mp4");
+    item.mark_failed(Some("yt-dlp exited with code 1".to_string()));
+    item.error_context = vec!["ERROR: Unsupported URL".to_string()];
+
+    item.mark_requeued();
+    assert!(item.error_message.is_none());
+    assert!(item.error_context.is_empty());
+}
@@ -0,0 +1,57 @@
+// tests/stdout_stream_test.rs
+mod common;
+
+use rustloader::stdout_stream::{stream_target, validate_stream_options, StreamTarget};
+
+#[test]
+fn test_dash_streams_to_stdout() {
+    assert_eq!(stream_target(Some("-")), Some(StreamTarget::Stdout));
+    assert_eq!(stream_target(Some(" - ")), Some(StreamTarget::Stdout));
+}
+
+#[test]
+fn test_directories_are_not_streamed() {
+    assert_eq!(stream_target(None), None);
+    assert_eq!(stream_target(Some("downloads")), None);
+    let dir = std::env::temp_dir();
+    assert_eq!(stream_target(dir.to_str()), None);
+}
+
+#[test]
+fn test_rejects_options_a_stream_cannot_carry() {
+    assert!(validate_stream_options(false, false, None).is_ok());
+    assert!(validate_stream_options(true, false, None).is_err());
+    assert!(validate_stream_options(false, true, None).is_err());
+    assert!(validate_stream_options(false, false, Some(1024)).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_streams_a_file_into_a_named_pipe() {
+    use rustloader::stdout_stream::stream_file;
+    use std::ffi::CString;
+    use std::io::Read;
+
+    let tmp = common::temp_dir("stream_test");
+    let dir = tmp.path().to_path_buf();
+    let media = dir.join("video.mp4");
+    std::fs::write(&media, b"not really a video").unwrap();
+    let pipe = dir.join("player.fifo");
+    let pipe_path = CString::new(pipe.to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(pipe_path.as_ptr(), 0o600) }, 0);
+
+    let target = stream_target(pipe.to_str());
+    assert_eq!(target, Some(StreamTarget::Pipe(pipe.clone())));
+
+    let reader = {
+        let pipe = pipe.clone();
+        std::thread::spawn(move || {
+            let mut received = Vec::new();
+            std::fs::File::open(pipe).unwrap().read_to_end(&mut received).unwrap();
+            received
+        })
+    };
+    let bytes = stream_file(&media, &target.unwrap(), None).unwrap();
+    assert_eq!(bytes, 18);
+    assert_eq!(reader.join().unwrap(), b"not really a video");
+}
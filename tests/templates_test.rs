@@ -0,0 +1,70 @@
+// tests/templates_test.rs
+use rustloader::download_manager::{DeadlineAction, DownloadItem, DownloadPriority};
+use rustloader::ffmpeg_args::parse_ffmpeg_args;
+use rustloader::http_headers::RequestHeaders;
+use rustloader::region_overrides::RegionOverrides;
+use rustloader::templates::{validate_template_name, JobTemplate};
+use rustloader::transcode::TranscodeKind;
+use rustloader::utils::parse_duration;
+
+#[test]
+fn test_template_name_validation() {
+    assert!(validate_template_name("podcast-audio").is_ok());
+    assert!(validate_template_name("hd_720").is_ok());
+
+    assert!(validate_template_name("").is_err());
+    assert!(validate_template_name("../escape").is_err());
+    assert!(validate_template_name("has space").is_err());
+    assert!(validate_template_name(&"a".repeat(65)).is_err());
+}
+
+#[test]
+fn test_template_from_download() {
+    let item = DownloadItem::builder("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp3")
+        .quality(Some("720"))
+        .subtitles(true)
+        .bitrate(Some("128K"))
+        .priority(DownloadPriority::High)
+        .build();
+
+    let template = JobTemplate::from_download(&item);
+
+    assert_eq!(template.format.as_deref(), Some("mp3"));
+    assert_eq!(template.quality.as_deref(), Some("720"));
+    assert_eq!(template.download_subtitles, Some(true));
+    assert_eq!(template.use_playlist, Some(false));
+    assert_eq!(template.bitrate.as_deref(), Some("128K"));
+    assert_eq!(template.priority, Some(DownloadPriority::High));
+}
+
+#[test]
+fn test_template_keeps_request_and_post_processing_options() {
+    let ffmpeg_args = vec!["-vf".to_string(), "scale=1280:-2, hflip".to_string()];
+    let request_headers = RequestHeaders::from_args(Some("Mozilla/5.0"), ["Referer: https://example.com/"])
+        .unwrap()
+        .with_region(RegionOverrides::from_args(Some("android,ios"), Some("DE")).unwrap());
+    let item = DownloadItem::builder("https://www.youtube.com/watch?v=dQw4w9WgXcQ", "mp4")
+        .transcode(Some(TranscodeKind::Hevc))
+        .ffmpeg_args(&ffmpeg_args)
+        .request_headers(request_headers.clone())
+        .mirrors(&["https://mirror.example.com/video.mp4".to_string()])
+        .deadline(Some(1800), DeadlineAction::Pause, Some(7200))
+        .build();
+
+    let template = JobTemplate::from_download(&item);
+
+    assert_eq!(template.transcode, Some(TranscodeKind::Hevc));
+    assert_eq!(parse_ffmpeg_args(template.ffmpeg_args.as_deref().unwrap()).unwrap(), ffmpeg_args);
+    // Captured in command-line form, so applying it goes through the same checks
+    let applied = RequestHeaders::from_args(template.user_agent.as_deref(), template.headers.iter().map(String::as_str))
+        .unwrap()
+        .with_region(
+            RegionOverrides::from_args(template.player_client.as_deref(), template.geo_bypass_country.as_deref()).unwrap(),
+        );
+    assert_eq!(applied, request_headers);
+    assert_eq!(template.mirrors, vec!["https://mirror.example.com/video.mp4"]);
+    assert_eq!(parse_duration(template.deadline.as_deref().unwrap()).unwrap(), 1800);
+    assert_eq!(template.on_deadline, Some(DeadlineAction::Pause));
+    assert_eq!(parse_duration(template.resume_after.as_deref().unwrap()).unwrap(), 7200);
+}
+
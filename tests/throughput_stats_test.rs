@@ -0,0 +1,67 @@
+// tests/throughput_stats_test.rs
+
+use chrono::{Duration, TimeZone, Utc};
+use rustloader::throughput_stats::{ThroughputStats, MAX_HOSTS};
+
+#[test]
+fn test_expected_speed_follows_recent_downloads() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+    let mut stats = ThroughputStats::default();
+    assert_eq!(stats.expected_speed("youtube.com", now), None);
+
+    stats.record("youtube.com", 1_000_000.0, now);
+    assert_eq!(stats.expected_speed("youtube.com", now), Some(1_000_000.0));
+
+    // Later downloads move the average without replacing it
+    stats.record("youtube.com", 2_000_000.0, now + Duration::minutes(5));
+    let speed = stats.expected_speed("youtube.com", now + Duration::minutes(5)).unwrap();
+    assert!(speed > 1_000_000.0 && speed < 2_000_000.0);
+    assert_eq!(stats.hosts["youtube.com"].downloads, 2);
+    assert_eq!(stats.expected_speed("vimeo.com", now), None);
+}
+
+#[test]
+fn test_ignores_bad_measurements() {
+    let now = Utc::now();
+    let mut stats = ThroughputStats::default();
+    stats.record("youtube.com", 0.0, now);
+    stats.record("youtube.com", f64::NAN, now);
+    stats.record("youtube.com", -5.0, now);
+    assert!(stats.hosts.is_empty());
+}
+
+#[test]
+fn test_stale_speeds_are_not_used() {
+    let then = Utc.with_ymd_and_hms(2026, 9, 1, 12, 0, 0).unwrap();
+    let mut stats = ThroughputStats::default();
+    stats.record("vimeo.com", 500_000.0, then);
+    let later = then + Duration::days(30);
+    assert_eq!(stats.expected_speed("vimeo.com", later), None);
+
+    // A new measurement replaces a stale average instead of blending with it
+    stats.record("vimeo.com", 3_000_000.0, later);
+    assert_eq!(stats.expected_speed("vimeo.com", later), Some(3_000_000.0));
+}
+
+#[test]
+fn test_drops_least_recent_host() {
+    let start = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+    let mut stats = ThroughputStats::default();
+    for i in 0..=MAX_HOSTS {
+        stats.record(&format!("host{}.example", i), 1000.0, start + Duration::minutes(i as i64));
+    }
+    assert_eq!(stats.hosts.len(), MAX_HOSTS);
+    assert!(!stats.hosts.contains_key("host0.example"));
+    assert!(stats.hosts.contains_key(&format!("host{}.example", MAX_HOSTS)));
+}
+
+#[test]
+fn test_round_trips_through_json() {
+    let now = Utc::now();
+    let mut stats = ThroughputStats::default();
+    stats.record("archive.org", 750_000.0, now);
+    let json = serde_json::to_string(&stats).unwrap();
+    let restored: ThroughputStats = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.hosts, stats.hosts);
+    assert_eq!(serde_json::from_str::<ThroughputStats>("{}").unwrap().hosts.len(), 0);
+}
@@ -0,0 +1,38 @@
+// tests/trace_file_test.rs
+mod common;
+
+use common::temp_dir;
+
+use rustloader::download_manager::QueueCommand;
+use rustloader::trace_file::TraceFormat;
+use std::path::Path;
+
+#[test]
+fn test_trace_format_for_path() {
+    assert_eq!(TraceFormat::for_path(Path::new("queue.json")), TraceFormat::Chrome);
+    assert_eq!(TraceFormat::for_path(Path::new("queue")), TraceFormat::Chrome);
+    assert_eq!(TraceFormat::for_path(Path::new("queue.folded")), TraceFormat::Flamegraph);
+    assert_eq!(TraceFormat::for_path(Path::new("queue.FOLDED")), TraceFormat::Flamegraph);
+}
+
+#[test]
+fn test_queue_command_names() {
+    assert_eq!(QueueCommand::Pause("abc".to_string()).name(), "pause");
+    assert_eq!(QueueCommand::SaveQueue.name(), "save_queue");
+    assert_eq!(QueueCommand::Undo.name(), "undo");
+}
+
+// The only test here that installs the global subscriber
+#[test]
+fn test_writes_chrome_trace() {
+    let tmp = temp_dir("trace");
+    let dir = tmp.path().to_path_buf();
+    let path = dir.join("trace.json");
+
+    let guard = rustloader::trace_file::start_trace_file(&path).unwrap();
+    tracing::info_span!("queue_tick").in_scope(|| {});
+    drop(guard);
+
+    let trace = std::fs::read_to_string(&path).unwrap();
+    assert!(trace.contains("queue_tick"));
+}
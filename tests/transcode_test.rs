@@ -0,0 +1,27 @@
+// tests/transcode_test.rs
+use rustloader::transcode::{estimate_transcode_secs, TranscodeKind};
+
+#[test]
+fn test_transcode_kind_parsing() {
+    assert_eq!(TranscodeKind::parse("hevc").unwrap(), TranscodeKind::Hevc);
+    assert_eq!(TranscodeKind::parse("upscale-2160").unwrap(), TranscodeKind::Upscale2160);
+    assert!(TranscodeKind::parse("av2").is_err());
+
+    assert_eq!(TranscodeKind::Hevc.encoder(), "libx265");
+    assert!(TranscodeKind::Upscale1080.ffmpeg_args().contains("scale=-2:1080"));
+}
+
+#[test]
+fn test_estimate_scales_with_resolution() {
+    // At 1x realtime, a 10 minute 720p video takes 10 minutes
+    let at_720 = estimate_transcode_secs(1.0, TranscodeKind::Hevc, 600.0, Some(720));
+    assert!((at_720 - 600.0).abs() < 0.001);
+
+    // 1440p has four times the pixels of 720p
+    let at_1440 = estimate_transcode_secs(1.0, TranscodeKind::Hevc, 600.0, Some(1440));
+    assert!((at_1440 - 2400.0).abs() < 0.001);
+
+    // Upscaling always encodes at the target height; a faster encoder takes less time
+    let upscale = estimate_transcode_secs(9.0, TranscodeKind::Upscale2160, 600.0, Some(480));
+    assert!((upscale - 600.0).abs() < 0.001);
+}
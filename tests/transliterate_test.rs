@@ -0,0 +1,49 @@
+// tests/transliterate_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::transliterate::{transliterate_file, transliterate_name, transliterated_path};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_transliterate_name() {
+    assert_eq!(transliterate_name("Привет мир"), "Privet mir");
+    assert_eq!(transliterate_name("Café del Mar"), "Cafe del Mar");
+    assert_eq!(transliterate_name("Plain ASCII title"), "Plain ASCII title");
+    // Reserved characters and runs of whitespace
+    assert_eq!(transliterate_name("AC/DC: Live?  at \"Donington\""), "AC_DC_ Live_ at _Donington_");
+    assert_eq!(transliterate_name("..hidden."), "hidden");
+    assert_eq!(transliterate_name("   "), "download");
+}
+
+#[test]
+fn test_transliterated_path() {
+    assert_eq!(
+        transliterated_path(Path::new("/music/Привет мир.mp3")),
+        Some(PathBuf::from("/music/Privet mir.mp3"))
+    );
+    assert_eq!(transliterated_path(Path::new("/music/Already ASCII.mp3")), None);
+}
+
+#[test]
+fn test_transliterate_file() {
+    let tmp = temp_dir("transliterate");
+    let dir = tmp.path().to_path_buf();
+
+    let original = dir.join("Москва.mp4");
+    fs::write(&original, b"video").unwrap();
+    let renamed = transliterate_file(&original).unwrap();
+    assert_eq!(renamed, dir.join("Moskva.mp4"));
+    assert!(renamed.exists());
+    assert!(!original.exists());
+
+    // An ASCII name is left alone
+    assert_eq!(transliterate_file(&renamed).unwrap(), renamed);
+
+    // Never overwrites another file
+    let clash = dir.join("Москва.mp4");
+    fs::write(&clash, b"other").unwrap();
+    assert!(transliterate_file(&clash).is_err());
+    assert_eq!(fs::read(&renamed).unwrap(), b"video");
+}
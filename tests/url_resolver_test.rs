@@ -0,0 +1,55 @@
+// tests/url_resolver_test.rs
+use rustloader::url_resolver::{extract_iframe_src, is_shortener, normalize_url};
+
+#[test]
+fn test_youtube_short_and_embed_urls() {
+    let expected = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+
+    assert_eq!(normalize_url("https://youtu.be/dQw4w9WgXcQ"), expected);
+    assert_eq!(normalize_url("https://youtu.be/dQw4w9WgXcQ?si=AbCdEf123"), expected);
+    assert_eq!(normalize_url("https://www.youtube.com/embed/dQw4w9WgXcQ"), expected);
+    assert_eq!(normalize_url("https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?rel=0"), expected);
+    assert_eq!(normalize_url("https://youtube.com/shorts/dQw4w9WgXcQ?feature=share"), expected);
+    assert_eq!(normalize_url("https://m.youtube.com/watch?v=dQw4w9WgXcQ&pp=ygU"), expected);
+}
+
+#[test]
+fn test_useful_params_are_kept() {
+    assert_eq!(
+        normalize_url("https://youtu.be/dQw4w9WgXcQ?t=42&si=xyz"),
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42"
+    );
+    assert_eq!(
+        normalize_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123&feature=share"),
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123"
+    );
+}
+
+#[test]
+fn test_vimeo_and_unknown_urls() {
+    assert_eq!(normalize_url("https://player.vimeo.com/video/76979871?h=abc"), "https://vimeo.com/76979871");
+    assert_eq!(normalize_url("https://example.com/video/1"), "https://example.com/video/1");
+    assert_eq!(normalize_url("not a url"), "not a url");
+}
+
+#[test]
+fn test_iframe_src_extraction() {
+    let embed = r#"<iframe width="560" height="315" src="https://www.youtube.com/embed/dQw4w9WgXcQ" frameborder="0"></iframe>"#;
+    assert_eq!(
+        extract_iframe_src(embed).as_deref(),
+        Some("https://www.youtube.com/embed/dQw4w9WgXcQ")
+    );
+
+    let relative = r#"<iframe src='//player.vimeo.com/video/76979871'></iframe>"#;
+    assert_eq!(extract_iframe_src(relative).as_deref(), Some("https://player.vimeo.com/video/76979871"));
+
+    assert!(extract_iframe_src("https://youtu.be/dQw4w9WgXcQ").is_none());
+}
+
+#[test]
+fn test_shortener_detection() {
+    assert!(is_shortener("https://t.co/abc123"));
+    assert!(is_shortener("https://bit.ly/xyz"));
+    assert!(!is_shortener("https://youtu.be/dQw4w9WgXcQ"));
+    assert!(!is_shortener("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+}
@@ -1,5 +1,5 @@
 // tests/utils_test.rs
-use rustloader::utils::{validate_url, validate_time_format, validate_bitrate};
+use rustloader::utils::{format_clock, safe_file_name, validate_url, validate_time_format, validate_time_range, validate_bitrate, parse_bitrate, Bitrate};
 
 #[test]
 fn test_validate_url_valid_formats() {
@@ -52,6 +52,10 @@ fn test_validate_bitrate_valid() {
     assert!(validate_bitrate("5M").is_ok());
     assert!(validate_bitrate("128K").is_ok());
     assert!(validate_bitrate("2M").is_ok());
+    assert!(validate_bitrate("1.5M").is_ok());
+    assert!(validate_bitrate("2500kbps").is_ok());
+    assert!(validate_bitrate("12000K").is_ok());
+    assert!(validate_bitrate("best").is_ok());
 }
 
 #[test]
@@ -61,6 +65,78 @@ fn test_validate_bitrate_invalid() {
     assert!(validate_bitrate("5G").is_err());
     assert!(validate_bitrate("not-a-bitrate").is_err());
     assert!(validate_bitrate("0K").is_err());
-    assert!(validate_bitrate("12000K").is_err()); // Too high for K format
-    assert!(validate_bitrate("200M").is_err());   // Too high for M format
-}
\ No newline at end of file
+    assert!(validate_bitrate("50K").is_err());    // Below the video range
+    assert!(validate_bitrate("200M").is_err());   // Above the video range
+}
+
+#[test]
+fn test_parse_bitrate() {
+    assert_eq!(parse_bitrate("128K", "mp3").unwrap(), Bitrate::Kbps(128.0));
+    assert_eq!(parse_bitrate("1.5m", "mp4").unwrap(), Bitrate::Kbps(1500.0));
+    assert_eq!(parse_bitrate(" Best ", "mp3").unwrap(), Bitrate::Best);
+    assert_eq!(Bitrate::Kbps(1500.0).to_string(), "1.5M");
+    assert_eq!(Bitrate::Kbps(128.0).to_string(), "128K");
+
+    // Audio has its own range
+    assert!(parse_bitrate("320K", "mp3").is_ok());
+    let err = parse_bitrate("1M", "mp3").unwrap_err().to_string();
+    assert!(err.contains("32K to 320K"), "{}", err);
+    assert!(parse_bitrate("16K", "mp3").is_err());
+
+    let err = parse_bitrate("1000", "mp4").unwrap_err().to_string();
+    assert!(err.contains("needs a unit"), "{}", err);
+}
+
+#[test]
+fn test_validate_time_format_returns_seconds() {
+    assert_eq!(validate_time_format("00:00:00").unwrap(), 0);
+    assert_eq!(validate_time_format("01:30:45").unwrap(), 5445);
+}
+
+#[test]
+fn test_validate_time_range_order() {
+    assert!(validate_time_range(Some("00:01:00"), Some("00:02:00"), None).is_ok());
+    assert!(validate_time_range(None, None, Some(60.0)).is_ok());
+    let err = validate_time_range(Some("00:10:00"), Some("00:05:00"), None).unwrap_err();
+    assert!(err.to_string().contains("start 00:10:00 is not before end 00:05:00"));
+    assert!(validate_time_range(Some("00:05:00"), Some("00:05:00"), None).is_err());
+    assert!(validate_time_range(Some("0:5:0"), None, None).is_err());
+}
+
+#[test]
+fn test_validate_time_range_against_duration() {
+    // 58:12
+    let duration = Some(3492.0);
+    assert!(validate_time_range(Some("00:10:00"), Some("00:58:12"), duration).is_ok());
+
+    let err = validate_time_range(None, Some("01:30:00"), duration).unwrap_err();
+    assert!(err.to_string().contains("end 01:30:00 exceeds duration 58:12"));
+    let err = validate_time_range(Some("01:00:00"), None, duration).unwrap_err();
+    assert!(err.to_string().contains("start 01:00:00 exceeds duration 58:12"));
+    assert!(validate_time_range(Some("00:58:12"), None, duration).is_err());
+
+    // Unknown durations only get the order checked
+    assert!(validate_time_range(None, Some("01:30:00"), None).is_ok());
+}
+
+#[test]
+fn test_format_clock() {
+    assert_eq!(format_clock(3492), "58:12");
+    assert_eq!(format_clock(3723), "1:02:03");
+    assert_eq!(format_clock(5), "0:05");
+}
+
+#[test]
+fn test_safe_file_name() {
+    assert_eq!(safe_file_name("AC/DC: Live?  at \"Donington\"", None), "AC_DC_ Live_ at _Donington_");
+    assert_eq!(safe_file_name("..hidden. ", None), "hidden");
+    assert_eq!(safe_file_name(" . ", None), "download");
+    assert_eq!(safe_file_name("abcdef", Some(3)), "abc");
+
+    // Windows device names, whatever the case or extension
+    assert_eq!(safe_file_name("CON", None), "CON_");
+    assert_eq!(safe_file_name("nul.txt", None), "nul_.txt");
+    assert_eq!(safe_file_name("Com1 .tar.gz", None), "Com1_.tar.gz");
+    assert_eq!(safe_file_name("CONSOLE", None), "CONSOLE");
+    assert_eq!(safe_file_name("COM10", None), "COM10");
+}
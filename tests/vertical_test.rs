@@ -0,0 +1,85 @@
+// tests/vertical_test.rs
+use rustloader::format_selector::FormatSelector;
+use rustloader::transcode::TranscodeKind;
+use rustloader::vertical::{reframe_command, reframe_filter, reframe_size, AspectRatio, FrameSize, VerticalMode, VerticalOptions};
+use serde_json::json;
+use std::path::Path;
+
+const PORTRAIT: AspectRatio = AspectRatio { width: 9, height: 16 };
+
+fn size(width: u32, height: u32) -> FrameSize {
+    FrameSize { width, height }
+}
+
+#[test]
+fn test_detects_vertical_sources_from_metadata() {
+    let short = FrameSize::from_info(&json!({"width": 1080, "height": 1920})).unwrap();
+    assert!(short.is_vertical());
+    assert!(!FrameSize::from_info(&json!({"width": 1920, "height": 1080})).unwrap().is_vertical());
+    assert!(FrameSize::from_info(&json!({"title": "no size"})).is_none());
+}
+
+#[test]
+fn test_parse_options() {
+    assert_eq!(AspectRatio::parse("4:5").unwrap(), AspectRatio { width: 4, height: 5 });
+    assert_eq!(AspectRatio::default(), PORTRAIT);
+    for invalid in ["", "16", "0:9", "a:b", "9:16:1"] {
+        assert!(AspectRatio::parse(invalid).is_err(), "{} should be rejected", invalid);
+    }
+    assert_eq!(VerticalMode::parse("crop").unwrap(), VerticalMode::Crop);
+    assert!(VerticalMode::parse("stretch").is_err());
+}
+
+#[test]
+fn test_matching_shape_is_left_alone() {
+    assert_eq!(reframe_size(size(1080, 1920), VerticalMode::Pad, PORTRAIT), None);
+    assert_eq!(reframe_size(size(1080, 1920), VerticalMode::Crop, PORTRAIT), None);
+    assert_eq!(reframe_size(size(1920, 1080), VerticalMode::Keep, PORTRAIT), None);
+}
+
+#[test]
+fn test_pad_and_crop_sizes() {
+    // Landscape to portrait
+    assert_eq!(reframe_size(size(1920, 1080), VerticalMode::Pad, PORTRAIT), Some(size(1920, 3414)));
+    assert_eq!(reframe_size(size(1920, 1080), VerticalMode::Crop, PORTRAIT), Some(size(606, 1080)));
+    // A tall source for a 4:5 feed
+    let feed = AspectRatio { width: 4, height: 5 };
+    assert_eq!(reframe_size(size(1080, 1920), VerticalMode::Pad, feed), Some(size(1536, 1920)));
+    assert_eq!(reframe_size(size(1080, 1920), VerticalMode::Crop, feed), Some(size(1080, 1350)));
+}
+
+#[test]
+fn test_reframe_filters() {
+    let pad = VerticalOptions { mode: VerticalMode::Pad, target: PORTRAIT };
+    assert_eq!(reframe_filter(size(1080, 1080), pad).unwrap(), "pad=1080:1920:0:420:black,setsar=1");
+    let crop = VerticalOptions { mode: VerticalMode::Crop, target: PORTRAIT };
+    assert_eq!(reframe_filter(size(1080, 1080), crop).unwrap(), "crop=606:1080,setsar=1");
+}
+
+#[test]
+fn test_reframe_command() {
+    let args = reframe_command(Path::new("/v/in.mp4"), Path::new("/v/.in.reframed.mp4"), "crop=606:1080,setsar=1")
+        .to_args()
+        .unwrap();
+    assert_eq!(
+        args,
+        [
+            "-hide_banner", "-loglevel", "error", "-i", "/v/in.mp4",
+            "-vf", "crop=606:1080,setsar=1",
+            "-c:v", "libx264", "-preset", "medium", "-crf", "20",
+            "-c:a", "copy", "/v/.in.reframed.mp4",
+        ]
+    );
+}
+
+#[test]
+fn test_vertical_quality_limits_width() {
+    let selector = FormatSelector::new().with_quality(Some("1080")).with_vertical(true);
+    assert_eq!(selector.expression(), "bestvideo[width<=1080]+bestaudio/best[width<=1080]/best");
+}
+
+#[test]
+fn test_vertical_upscale_scales_width() {
+    assert!(TranscodeKind::Upscale1080.ffmpeg_args_for(true).starts_with("-vf scale=1080:-2:"));
+    assert_eq!(TranscodeKind::Upscale1080.ffmpeg_args(), TranscodeKind::Upscale1080.ffmpeg_args_for(false));
+}
@@ -0,0 +1,52 @@
+// tests/video_id_test.rs
+use rustloader::video_id::{extract_video_id, video_key};
+
+fn site_and_id(url: &str) -> Option<(&'static str, String)> {
+    extract_video_id(url).map(|v| (v.site, v.id))
+}
+
+#[test]
+fn test_youtube_ids() {
+    let expected = Some(("youtube", "dQw4w9WgXcQ".to_string()));
+    assert_eq!(site_and_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), expected);
+    assert_eq!(site_and_id("https://youtu.be/dQw4w9WgXcQ"), expected);
+    assert_eq!(site_and_id("https://www.youtube.com/embed/dQw4w9WgXcQ"), expected);
+    assert_eq!(site_and_id("https://youtube.com/shorts/dQw4w9WgXcQ"), expected);
+    assert_eq!(site_and_id("https://music.youtube.com/watch?v=dQw4w9WgXcQ&list=RD"), expected);
+    assert_eq!(site_and_id("https://www.youtube.com/watch?v=bad"), None);
+}
+
+#[test]
+fn test_vimeo_and_twitch_ids() {
+    assert_eq!(site_and_id("https://vimeo.com/76979871"), Some(("vimeo", "76979871".to_string())));
+    assert_eq!(
+        site_and_id("https://player.vimeo.com/video/76979871?h=abc"),
+        Some(("vimeo", "76979871".to_string()))
+    );
+    assert_eq!(
+        site_and_id("https://www.twitch.tv/videos/1234567890"),
+        Some(("twitchvod", "v1234567890".to_string()))
+    );
+    assert_eq!(
+        site_and_id("https://clips.twitch.tv/FunnyClipSlug-abc123"),
+        Some(("twitchclips", "FunnyClipSlug-abc123".to_string()))
+    );
+    assert_eq!(
+        site_and_id("https://www.twitch.tv/somechannel/clip/FunnyClipSlug-abc123"),
+        Some(("twitchclips", "FunnyClipSlug-abc123".to_string()))
+    );
+}
+
+#[test]
+fn test_generic_fallback_is_stable() {
+    assert!(extract_video_id("https://example.com/media/42").is_none());
+
+    let key = video_key("https://example.com/media/42");
+    assert!(key.is_generic());
+    assert_eq!(key.id.len(), 16);
+    assert_eq!(key, video_key("https://example.com/media/42#comments"));
+    assert_ne!(key, video_key("https://example.com/media/43"));
+
+    let youtube = video_key("https://youtu.be/dQw4w9WgXcQ");
+    assert_eq!(youtube.archive_key(), "youtube dQw4w9WgXcQ");
+}
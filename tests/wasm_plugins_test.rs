@@ -0,0 +1,138 @@
+// tests/wasm_plugins_test.rs
+#![cfg(feature = "wasm-plugins")]
+
+mod common;
+
+use common::temp_dir;
+use rustloader::security::validate_plugin_file_name;
+use rustloader::wasm_plugins::{discover_wasm_plugins, HostCapability, WasmHook, WasmManifest, WasmPlugin};
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+/// Install a plugin whose module is given as WAT text
+fn install(name: &str, hooks: &str, capabilities: &str, wat: &str) -> TempDir {
+    let dir = temp_dir(&format!("wasm_{}", name));
+    let manifest = format!(
+        r#"{{"name":"{}","version":"1","abi":1,"module":"plugin.wasm","hooks":[{}],"host_capabilities":[{}]}}"#,
+        name, hooks, capabilities
+    );
+    fs::write(dir.path().join("wasm-plugin.json"), manifest).unwrap();
+    fs::write(dir.path().join("plugin.wasm"), wat).unwrap();
+    dir
+}
+
+/// A filename rule that always answers {"file_name":"renamed.mp4"} (27 bytes at 1024)
+const RENAME_WAT: &str = r#"(module
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "{\"file_name\":\"renamed.mp4\"}")
+  (func (export "rl_abi_version") (result i32) i32.const 1)
+  (func (export "rl_alloc") (param i32) (result i32) i32.const 2048)
+  (func (export "rl_filename") (param i32 i32) (result i64)
+    i64.const 1024 i64.const 32 i64.shl i64.const 27 i64.or))"#;
+
+fn manifest(hooks: Vec<WasmHook>, hosts: &[&str]) -> WasmManifest {
+    WasmManifest {
+        name: "example".to_string(),
+        version: "1.0.0".to_string(),
+        abi: 1,
+        module: "plugin.wasm".to_string(),
+        hooks,
+        hosts: hosts.iter().map(|host| host.to_string()).collect(),
+        host_capabilities: vec![HostCapability::Log],
+    }
+}
+
+#[test]
+fn test_manifest_validation() {
+    assert!(manifest(vec![WasmHook::FilenameRule], &[]).validate().is_ok());
+    assert!(manifest(vec![WasmHook::SiteHandler], &["example.com"]).validate().is_ok());
+
+    assert!(manifest(vec![], &[]).validate().is_err());
+    assert!(manifest(vec![WasmHook::SiteHandler], &[]).validate().is_err());
+    assert!(manifest(vec![WasmHook::MetadataEnricher], &["example.com"]).validate().is_err());
+
+    let mut bad = manifest(vec![WasmHook::FilenameRule], &[]);
+    bad.module = "plugin.so".to_string();
+    assert!(bad.validate().is_err());
+
+    let mut bad = manifest(vec![WasmHook::FilenameRule], &[]);
+    bad.abi = 2;
+    assert!(bad.validate().is_err());
+}
+
+#[test]
+fn test_filename_rule_renames_in_place() {
+    let tmp = install("rename", r#""filename-rule""#, "", RENAME_WAT);
+    let dir = tmp.path();
+    let plugin = WasmPlugin::load(dir).unwrap();
+
+    let file = dir.join("original.mp4");
+    fs::write(&file, b"video").unwrap();
+    let renamed = plugin.rename(&file, "mp4", "https://example.com/v/1").unwrap();
+    assert_eq!(renamed, dir.join("renamed.mp4"));
+    assert!(!file.exists());
+
+    // Never overwrites, never changes the extension
+    fs::write(&file, b"video").unwrap();
+    assert!(plugin.rename(&file, "mp4", "https://example.com/v/1").is_err());
+    let audio = dir.join("song.mp3");
+    fs::write(&audio, b"audio").unwrap();
+    assert!(plugin.rename(&audio, "mp3", "https://example.com/v/1").is_err());
+}
+
+#[test]
+fn test_undeclared_imports_are_refused() {
+    let wat = r#"(module
+      (import "rustloader" "log" (func (param i32 i32)))
+      (memory (export "memory") 1)
+      (func (export "rl_abi_version") (result i32) i32.const 1)
+      (func (export "rl_alloc") (param i32) (result i32) i32.const 0)
+      (func (export "rl_filename") (param i32 i32) (result i64) i64.const 0))"#;
+    assert!(WasmPlugin::load(install("no-log", r#""filename-rule""#, "", wat).path()).is_err());
+    assert!(WasmPlugin::load(install("with-log", r#""filename-rule""#, r#""log""#, wat).path()).is_ok());
+
+    let wasi = wat.replace(r#""rustloader" "log""#, r#""wasi_snapshot_preview1" "fd_write""#);
+    assert!(WasmPlugin::load(install("wasi", r#""filename-rule""#, r#""log""#, &wasi).path()).is_err());
+}
+
+#[test]
+fn test_missing_hook_export_and_wrong_abi() {
+    assert!(WasmPlugin::load(install("no-export", r#""site-handler""#, "", RENAME_WAT).path()).is_err());
+    let wrong_abi = RENAME_WAT.replace("(result i32) i32.const 1)", "(result i32) i32.const 7)");
+    assert!(WasmPlugin::load(install("wrong-abi", r#""filename-rule""#, "", &wrong_abi).path()).is_err());
+}
+
+#[test]
+fn test_runaway_plugin_runs_out_of_fuel() {
+    let wat = r#"(module
+      (memory (export "memory") 1)
+      (func (export "rl_abi_version") (result i32) i32.const 1)
+      (func (export "rl_alloc") (param i32) (result i32) i32.const 0)
+      (func (export "rl_filename") (param i32 i32) (result i64) (loop $spin (br $spin)) i64.const 0))"#;
+    let plugin = WasmPlugin::load(install("spin", r#""filename-rule""#, "", wat).path()).unwrap();
+    assert!(plugin.call(WasmHook::FilenameRule, &json!({})).is_err());
+}
+
+#[test]
+fn test_discover_wasm_plugins() {
+    let tmp = temp_dir("wasm_discover");
+    let root = tmp.path().to_path_buf();
+    fs::create_dir_all(root.join("empty")).unwrap();
+    let plugin = install("discovered", r#""filename-rule""#, "", RENAME_WAT);
+    let target = root.join("discovered");
+    fs::rename(plugin.path(), &target).unwrap();
+
+    let found = discover_wasm_plugins(&root);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].1.as_ref().unwrap().manifest.name, "discovered");
+}
+
+#[test]
+fn test_validate_plugin_file_name() {
+    assert!(validate_plugin_file_name("Artist - Title.mp3").is_ok());
+    assert!(validate_plugin_file_name("../escape.mp3").is_err());
+    assert!(validate_plugin_file_name("sub/dir.mp3").is_err());
+    assert!(validate_plugin_file_name(".hidden.mp3").is_err());
+    assert!(validate_plugin_file_name("").is_err());
+}
@@ -0,0 +1,91 @@
+// tests/webhooks_test.rs
+use chrono::{Duration, Utc};
+use rustloader::config::AppConfig;
+use rustloader::download_manager::DownloadStatus;
+use rustloader::webhooks::{is_reported, ProgressThrottle, ProgressTracker, Webhook};
+
+#[test]
+fn test_webhook_validation() {
+    let webhook = |url: &str| Webhook {
+        url: url.to_string(),
+        progress: None,
+    };
+    assert!(webhook("https://example.com/hook").validate().is_ok());
+    assert!(webhook("http://localhost:8080/hook").validate().is_ok());
+    assert!(webhook("file:///etc/passwd").validate().is_err());
+    assert!(webhook("not a url").validate().is_err());
+
+    let mut with_progress = webhook("https://example.com/hook");
+    with_progress.progress = Some(ProgressThrottle::default());
+    assert!(with_progress.validate().is_err());
+    with_progress.progress = Some(ProgressThrottle {
+        every_percent: Some(0.0),
+        every_secs: None,
+    });
+    assert!(with_progress.validate().is_err());
+    with_progress.progress = Some(ProgressThrottle {
+        every_percent: None,
+        every_secs: Some(30),
+    });
+    assert!(with_progress.validate().is_ok());
+}
+
+#[test]
+fn test_reported_statuses() {
+    assert!(is_reported(DownloadStatus::Completed));
+    assert!(is_reported(DownloadStatus::Failed));
+    assert!(is_reported(DownloadStatus::Canceled));
+    assert!(!is_reported(DownloadStatus::Downloading));
+    assert!(!is_reported(DownloadStatus::Queued));
+}
+
+#[test]
+fn test_progress_every_percent() {
+    let throttle = ProgressThrottle {
+        every_percent: Some(10.0),
+        every_secs: None,
+    };
+    let mut tracker = ProgressTracker::default();
+    let now = Utc::now();
+
+    assert!(tracker.due(&throttle, "dl_1", 0.0, now));
+    assert!(!tracker.due(&throttle, "dl_1", 9.9, now + Duration::hours(1)));
+    assert!(tracker.due(&throttle, "dl_1", 10.0, now));
+    assert!(!tracker.due(&throttle, "dl_1", 15.0, now));
+    // Each download is throttled on its own
+    assert!(tracker.due(&throttle, "dl_2", 15.0, now));
+
+    tracker.forget("dl_1");
+    assert!(tracker.due(&throttle, "dl_1", 15.0, now));
+}
+
+#[test]
+fn test_progress_every_secs() {
+    let throttle = ProgressThrottle {
+        every_percent: Some(50.0),
+        every_secs: Some(30),
+    };
+    let mut tracker = ProgressTracker::default();
+    let now = Utc::now();
+
+    assert!(tracker.due(&throttle, "dl_1", 1.0, now));
+    assert!(!tracker.due(&throttle, "dl_1", 2.0, now + Duration::seconds(29)));
+    assert!(tracker.due(&throttle, "dl_1", 2.0, now + Duration::seconds(30)));
+    // Whichever limit comes first
+    assert!(tracker.due(&throttle, "dl_1", 60.0, now + Duration::seconds(31)));
+}
+
+#[test]
+fn test_webhooks_config() {
+    let config: AppConfig = serde_json::from_str(
+        r#"{"webhooks": [
+            {"url": "https://example.com/done"},
+            {"url": "https://example.com/progress", "progress": {"every_percent": 5}}
+        ]}"#,
+    )
+    .unwrap();
+    assert_eq!(config.webhooks.len(), 2);
+    assert_eq!(config.webhooks[0].progress, None);
+    assert_eq!(config.webhooks[1].progress.unwrap().every_percent, Some(5.0));
+    assert_eq!(config.webhooks[1].progress.unwrap().every_secs, None);
+}
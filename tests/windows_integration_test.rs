@@ -0,0 +1,53 @@
+// tests/windows_integration_test.rs
+mod common;
+
+use common::temp_dir;
+use rustloader::notifications::toast_xml;
+use rustloader::templates::{is_job_file, load_job_file};
+use rustloader::windows_integration::{is_protocol_link, parse_protocol_link};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_parse_protocol_link() {
+    assert!(is_protocol_link("rustloader://download?url=x"));
+    assert!(is_protocol_link("RustLoader://download?url=x"));
+    assert!(!is_protocol_link("https://youtube.com/watch?v=abc"));
+
+    assert_eq!(
+        parse_protocol_link("rustloader://download?url=https%3A%2F%2Fwww.youtube.com%2Fwatch%3Fv%3Dabc").unwrap(),
+        "https://www.youtube.com/watch?v=abc"
+    );
+    assert!(parse_protocol_link("rustloader://delete?url=https%3A%2F%2Fexample.com").is_err());
+    assert!(parse_protocol_link("rustloader://download").is_err());
+    assert!(parse_protocol_link("rustloader://download?url=file%3A%2F%2F%2Fetc%2Fpasswd").is_err());
+}
+
+#[test]
+fn test_load_job_file() {
+    let tmp = temp_dir("job");
+    let dir = tmp.path().to_path_buf();
+    let path = dir.join("song.rustloader");
+    fs::write(&path, r#"{"url": "https://vimeo.com/123", "format": "mp3", "bitrate": "320K"}"#).unwrap();
+
+    assert!(is_job_file(path.to_str().unwrap()));
+    assert!(!is_job_file("https://vimeo.com/123"));
+
+    let job = load_job_file(&path).unwrap();
+    assert_eq!(job.url, "https://vimeo.com/123");
+    assert_eq!(job.options.format.as_deref(), Some("mp3"));
+    assert_eq!(job.options.bitrate.as_deref(), Some("320K"));
+    assert_eq!(job.options.quality, None);
+
+    fs::write(&path, r#"{"format": "mp3"}"#).unwrap();
+    assert!(load_job_file(&path).is_err());
+}
+
+#[test]
+fn test_toast_xml_escapes_and_links_file() {
+    let xml = toast_xml("Download Complete", "Tom & Jerry <1>.mp4 downloaded", Path::new("/videos/Tom & Jerry.mp4"));
+    assert!(xml.contains("Tom &amp; Jerry &lt;1&gt;.mp4"));
+    assert!(xml.contains(r#"arguments="file:///videos/Tom%20&amp;%20Jerry.mp4""#));
+    assert!(xml.contains(r#"arguments="file:///videos""#));
+    assert!(!xml.contains("Jerry <1>"));
+}
@@ -0,0 +1,237 @@
+// tests/ytdlp_command_test.rs
+use rustloader::chapters::Chapter;
+use rustloader::downloader::{separate_streams_template, DownloadBackend, YtdlpCommandBuilder};
+use rustloader::http_headers::RequestHeaders;
+
+const URL: &str = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+const OUT: &str = "/tmp/rustloader/%(title)s.mp4";
+
+fn args(builder: YtdlpCommandBuilder) -> Vec<String> {
+    builder
+        .build_args()
+        .unwrap()
+        .into_iter()
+        .map(|arg| arg.into_string().unwrap())
+        .collect()
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// Arguments every download starts with on the native downloader
+fn native_head() -> Vec<String> {
+    strings(&[
+        "--buffer-size", "64K",
+        "--concurrent-fragments", "4",
+        "--max-filesize", "10G",
+        "--downloader", "native",
+        "--limit-rate", "15M",
+    ])
+}
+
+/// Arguments every download ends with
+fn tail() -> Vec<String> {
+    let mut tail = strings(&[
+        "--socket-timeout", "30",
+        "--retries", "10",
+        "--fragment-retries", "10",
+        "--throttled-rate", "100K",
+        "--newline",
+        "--progress-template", "download:[rustloader-progress:1] %(progress)j",
+    ]);
+    tail.extend(RequestHeaders::default().ytdlp_args());
+    tail.extend(strings(&["--", URL]));
+    tail
+}
+
+fn expected(head: Vec<String>, middle: &[&str]) -> Vec<String> {
+    [head, strings(middle), tail()].concat()
+}
+
+#[test]
+fn test_default_video() {
+    let builder = YtdlpCommandBuilder::new(URL, OUT);
+    assert_eq!(args(builder), expected(native_head(), &["-o", OUT, "--no-playlist"]));
+}
+
+#[test]
+fn test_mp3_with_time_range() {
+    let (start, end) = ("00:00:10".to_string(), "00:01:00".to_string());
+    let builder = YtdlpCommandBuilder::new(URL, OUT)
+        .with_format("mp3")
+        .with_time_range(Some(&start), Some(&end));
+    assert_eq!(
+        args(builder),
+        expected(
+            native_head(),
+            &[
+                "-f", "bestaudio[ext=m4a]",
+                "--extract-audio",
+                "--audio-format", "mp3",
+                "--audio-quality", "7",
+                "-o", OUT,
+                "--no-playlist",
+                "--postprocessor-args", "ffmpeg:-b:a 128K -ss 00:00:10 -to 00:01:00",
+            ]
+        )
+    );
+}
+
+#[test]
+fn test_quality_playlist_and_subtitles() {
+    let builder = YtdlpCommandBuilder::new(URL, OUT)
+        .with_quality(Some("720p"))
+        .with_playlist(true)
+        .with_subtitles(true);
+    assert_eq!(
+        args(builder),
+        expected(
+            native_head(),
+            &[
+                "-f", "bestvideo[height<=720]+bestaudio/best[height<=720]/best",
+                "--verbose",
+                "-o", OUT,
+                "--yes-playlist",
+                "--write-subs",
+                "--sub-langs", "all",
+            ]
+        )
+    );
+}
+
+#[test]
+fn test_format_id_overrides_quality() {
+    let builder = YtdlpCommandBuilder::new(URL, OUT)
+        .with_quality(Some("1080"))
+        .with_format_id(Some("137+bestaudio/137"));
+    assert_eq!(
+        args(builder),
+        expected(native_head(), &["-f", "137+bestaudio/137", "-o", OUT, "--no-playlist"])
+    );
+}
+
+#[test]
+fn test_start_time_only() {
+    let start = "00:02:00".to_string();
+    let builder = YtdlpCommandBuilder::new(URL, OUT).with_time_range(Some(&start), None);
+    assert_eq!(
+        args(builder),
+        expected(
+            native_head(),
+            &["-o", OUT, "--no-playlist", "--postprocessor-args", "ffmpeg:-ss 00:02:00"]
+        )
+    );
+}
+
+#[test]
+fn test_aria2c_with_rate_limit_and_force() {
+    let builder = YtdlpCommandBuilder::new(URL, OUT)
+        .with_backend(DownloadBackend::Aria2c)
+        .with_rate_limit(Some("2M".to_string()))
+        .with_force_download(true);
+    let head = strings(&[
+        "--buffer-size", "64K",
+        "--concurrent-fragments", "4",
+        "--max-filesize", "10G",
+        "--downloader", "aria2c",
+        "--downloader-args", "aria2c:-x4",
+        "--downloader-args", "aria2c:-k64",
+        "--downloader-args", "aria2c:--file-allocation=none",
+        "--downloader-args", "aria2c:--disk-cache=64M",
+        "--limit-rate", "2M",
+        "--no-continue",
+        "--no-part-file",
+    ]);
+    assert_eq!(args(builder), expected(head, &["-o", OUT, "--no-playlist"]));
+}
+
+#[test]
+fn test_aria2c_without_rate_limit_is_unthrottled() {
+    let builder = YtdlpCommandBuilder::new(URL, OUT).with_backend(DownloadBackend::Aria2c);
+    assert!(!args(builder).contains(&"--limit-rate".to_string()));
+}
+
+#[test]
+fn test_build_args_is_deterministic() {
+    let build = || {
+        YtdlpCommandBuilder::new(URL, OUT)
+            .with_quality(Some("480"))
+            .with_subtitles(true)
+            .build_args()
+            .unwrap()
+    };
+    assert_eq!(build(), build());
+}
+
+#[test]
+fn test_max_filesize_is_configurable() {
+    let custom = args(YtdlpCommandBuilder::new(URL, OUT).with_max_filesize(Some("500M".to_string())));
+    let position = custom.iter().position(|arg| arg == "--max-filesize").unwrap();
+    assert_eq!(custom[position + 1], "500M");
+
+    let unlimited = args(YtdlpCommandBuilder::new(URL, OUT).with_max_filesize(None));
+    assert!(!unlimited.iter().any(|arg| arg == "--max-filesize"));
+}
+
+#[test]
+fn test_no_merge_downloads_streams_separately() {
+    let separate = args(YtdlpCommandBuilder::new(URL, OUT).with_quality(Some("1080")).with_no_merge(true));
+    let format = separate.iter().position(|arg| arg == "-f").unwrap();
+    assert_eq!(separate[format + 1], "bestvideo[height<=1080],bestaudio");
+    let output = separate.iter().position(|arg| arg == "-o").unwrap();
+    assert_eq!(separate[output + 1], "/tmp/rustloader/%(title)s.f%(format_id)s.%(ext)s");
+
+    let picked = args(YtdlpCommandBuilder::new(URL, OUT).with_format_id(Some("137+140")).with_no_merge(true));
+    let format = picked.iter().position(|arg| arg == "-f").unwrap();
+    assert_eq!(picked[format + 1], "137,140");
+}
+
+#[test]
+fn test_separate_streams_template() {
+    assert_eq!(separate_streams_template("/dl/%(title)s.mp4"), "/dl/%(title)s.f%(format_id)s.%(ext)s");
+    assert_eq!(separate_streams_template("/dl/%(title)s"), "/dl/%(title)s.f%(format_id)s.%(ext)s");
+}
+
+#[test]
+fn test_staging_dir_keeps_output_relative_to_home() {
+    let staged = args(YtdlpCommandBuilder::new(URL, OUT).with_staging_dir(Some("/scratch".to_string())));
+    let position = staged.iter().position(|arg| arg == "--paths").unwrap();
+    assert_eq!(
+        staged[position..position + 6],
+        strings(&["--paths", "home:/tmp/rustloader", "--paths", "temp:/scratch", "-o", "%(title)s.mp4"])
+    );
+
+    let direct = args(YtdlpCommandBuilder::new(URL, OUT));
+    assert!(!direct.iter().any(|arg| arg == "--paths"));
+}
+
+#[test]
+fn test_embedded_metadata() {
+    let builder = YtdlpCommandBuilder::new(URL, OUT).with_embedded_metadata(true);
+    assert_eq!(args(builder), expected(native_head(), &["-o", OUT, "--no-playlist", "--embed-metadata"]));
+}
+
+#[test]
+fn test_chapters_download_as_sections() {
+    let chapters = [
+        Chapter { number: 1, title: "Intro".to_string(), start_secs: 0.0, end_secs: 60.0 },
+        Chapter { number: 3, title: "Q&A (live)".to_string(), start_secs: 600.0, end_secs: 900.0 },
+    ];
+    let builder = YtdlpCommandBuilder::new(URL, OUT).with_chapters(&chapters);
+    assert_eq!(
+        args(builder),
+        expected(
+            native_head(),
+            &[
+                "-o",
+                "/tmp/rustloader/%(title)s - %(section_number)02d %(section_title)s.mp4",
+                "--no-playlist",
+                "--download-sections",
+                "^Intro$",
+                "--download-sections",
+                r"^Q\&A \(live\)$",
+            ]
+        )
+    );
+}